@@ -0,0 +1,79 @@
+//! Throughput under each `--flush` policy: `cargo bench --bench flush`.
+//!
+//! Pipes a few million generated lines through the release binary with a
+//! long head and a match every few lines, so nearly every line is shown
+//! and flushing dominates, and reads stdout through a pipe the way a
+//! consumer would. Prints the best of a few runs per policy.
+
+use std::io::{self, Read, Write};
+use std::process::{Command, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
+
+const LINES: usize = 2_000_000;
+const RUNS: usize = 3;
+
+/// Lines 1 to `n`, every fourth an error.
+fn generate_lines(n: usize) -> Vec<u8> {
+    let mut input = Vec::with_capacity(n * 40);
+    for i in 1..=n {
+        if i % 4 == 0 {
+            writeln!(input, "2024-01-01T00:00:00Z ERROR request {} failed", i).unwrap();
+        } else {
+            writeln!(input, "2024-01-01T00:00:00Z INFO request {} ok", i).unwrap();
+        }
+    }
+    input
+}
+
+/// One run of trunc over `input`: wall time and bytes of output.
+fn run(input: &[u8], args: &[&str]) -> io::Result<(Duration, usize)> {
+    let started = Instant::now();
+    let mut child = Command::new(env!("CARGO_BIN_EXE_trunc"))
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()?;
+    let mut stdin = child.stdin.take().unwrap();
+    let mut stdout = child.stdout.take().unwrap();
+    let reader = thread::spawn(move || {
+        let mut output = Vec::new();
+        stdout.read_to_end(&mut output).map(|_| output.len())
+    });
+    stdin.write_all(input)?;
+    drop(stdin);
+    let written = reader.join().unwrap()?;
+    child.wait()?;
+    Ok((started.elapsed(), written))
+}
+
+fn main() -> io::Result<()> {
+    let input = generate_lines(LINES);
+    let head = (LINES / 2).to_string();
+    let matches = LINES.to_string();
+    println!(
+        "{} lines, {} MB in; best of {} runs",
+        LINES,
+        input.len() / 1_000_000,
+        RUNS
+    );
+    for policy in ["line", "interval=100", "block"] {
+        let args = [
+            "--flush", policy, "-f", &head, "-m", &matches, "-C", "0", "ERROR",
+        ];
+        let mut best = Duration::MAX;
+        let mut written = 0;
+        for _ in 0..RUNS {
+            let (elapsed, bytes) = run(&input, &args)?;
+            best = best.min(elapsed);
+            written = bytes;
+        }
+        println!(
+            "--flush {:<13} {:>8.1} ms  {:>7.1} MB/s out",
+            policy,
+            best.as_secs_f64() * 1000.0,
+            written as f64 / 1e6 / best.as_secs_f64()
+        );
+    }
+    Ok(())
+}
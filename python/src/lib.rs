@@ -0,0 +1,130 @@
+//! trunc-py - Python bindings for trunc
+//!
+//! `trunc.truncate(text, ...)` truncates a string as the `trunc` command
+//! would its input, and says what it left out. Options are the CLI's flags
+//! of the same names, with the same defaults.
+
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use regex::Regex;
+use trunc::Truncator;
+
+/// What `truncate` kept, and what it left out.
+#[pyclass(frozen, get_all, module = "trunc")]
+struct TruncResult {
+    /// The truncated text, each line ending in a newline
+    text: String,
+    /// Whether anything was left out: hidden lines, or long lines cut
+    truncated: bool,
+    /// Lines and bytes of the text given
+    lines_in: usize,
+    bytes_in: usize,
+    /// Lines of the text shown, and hidden under markers
+    lines_shown: usize,
+    lines_hidden: usize,
+    /// With a pattern: the lines it matched, and those shown
+    matches_found: Option<usize>,
+    matches_shown: Option<usize>,
+}
+
+#[pymethods]
+impl TruncResult {
+    fn __str__(&self) -> &str {
+        &self.text
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "TruncResult(lines_in={}, lines_shown={}, lines_hidden={}, truncated={})",
+            self.lines_in,
+            self.lines_shown,
+            self.lines_hidden,
+            if self.truncated { "True" } else { "False" }
+        )
+    }
+}
+
+/// Truncate `text` as `trunc` would: the first and last lines, and with a
+/// pattern, matches from the middle with context around them.
+#[pyfunction]
+#[pyo3(signature = (
+    text,
+    first = 30,
+    last = 30,
+    pattern = None,
+    matches = 5,
+    context = 3,
+    width = 100,
+    max_bytes = None,
+))]
+#[allow(clippy::too_many_arguments)]
+fn truncate(
+    py: Python<'_>,
+    text: &str,
+    first: usize,
+    last: usize,
+    pattern: Option<&str>,
+    matches: usize,
+    context: usize,
+    width: usize,
+    max_bytes: Option<usize>,
+) -> PyResult<TruncResult> {
+    let mut truncator = Truncator::new()
+        .first(first)
+        .last(last)
+        .matches(matches)
+        .context(context)
+        .width(width);
+    if let Some(pattern) = pattern {
+        let re = Regex::new(pattern).map_err(|e| PyValueError::new_err(e.to_string()))?;
+        truncator = truncator.pattern(re);
+    }
+    if let Some(n) = max_bytes {
+        truncator = truncator.max_bytes(n);
+    }
+    Ok(py.detach(|| run(&truncator, text)))
+}
+
+/// Run `truncator` over `text`, a line at a time, each the size it is in
+/// `text`: `\r\n` endings and a last line without a newline are counted
+/// as they are, as the CLI counts them.
+fn run(truncator: &Truncator, text: &str) -> TruncResult {
+    let mut out = truncator.output(Vec::new());
+    let mut stream = truncator.stream(out.share(1, 0));
+    let (mut lines, bytes) = (0, text.len());
+    for line in text.split_inclusive('\n') {
+        lines += 1;
+        let content = match line.strip_suffix('\n') {
+            Some(content) => content.strip_suffix('\r').unwrap_or(content),
+            None => line,
+        };
+        stream.size(line.len());
+        stream.line(content, &mut out);
+    }
+    stream.finish(&mut out);
+    // Written to memory, so it can't fail
+    let _ = out.finish(lines, bytes);
+    let (matches_found, matches_shown) = out.matches().unzip();
+    let truncated = out.truncated(lines, bytes);
+    let lines_shown = out.lines_written();
+    let lines_hidden = out.lines_hidden(lines);
+    TruncResult {
+        // Lines in are UTF-8, and truncation cuts on character boundaries
+        text: String::from_utf8_lossy(&out.into_inner()).into_owned(),
+        truncated,
+        lines_in: lines,
+        bytes_in: bytes,
+        lines_shown,
+        lines_hidden,
+        matches_found,
+        matches_shown,
+    }
+}
+
+/// Smart truncation of command output: head, tail, and matches from the
+/// middle, with markers saying what was left out.
+#[pymodule(name = "trunc")]
+mod module {
+    #[pymodule_export]
+    use super::{truncate, TruncResult};
+}
@@ -0,0 +1,67 @@
+"""Tests for the Python bindings: `trunc.truncate` against the CLI's output.
+
+Run with `maturin develop && pytest` from `python/`.
+"""
+
+import shutil
+import subprocess
+
+import pytest
+
+import trunc
+
+
+def generate_lines(n, errors=()):
+    """N lines, with ERROR on those in `errors`."""
+    return "".join(
+        f"line {i} ERROR\n" if i in errors else f"line {i}\n" for i in range(1, n + 1)
+    )
+
+
+def test_defaults_keep_first_and_last_30():
+    result = trunc.truncate(generate_lines(100))
+    lines = result.text.splitlines()
+    assert lines[:2] == ["line 1", "line 2"]
+    assert lines[30] == "[... 40 lines truncated ...]"
+    assert lines[-1] == "line 100"
+    assert (result.lines_in, result.lines_shown, result.lines_hidden) == (100, 60, 40)
+    assert result.truncated
+
+
+def test_short_text_is_unchanged():
+    result = trunc.truncate("a\nb\nc")
+    assert result.text == "a\nb\nc\n"
+    assert not result.truncated
+    assert str(result) == result.text
+
+
+def test_pattern_shows_matches_from_the_middle():
+    result = trunc.truncate(
+        generate_lines(1000, {500}), first=2, last=2, pattern="ERROR", context=1
+    )
+    assert "line 499\nline 500 ERROR\nline 501\n" in result.text
+    assert (result.matches_found, result.matches_shown) == (1, 1)
+
+
+def test_no_pattern_counts_no_matches():
+    result = trunc.truncate(generate_lines(10))
+    assert result.matches_found is None
+
+
+def test_bytes_in_are_the_text_as_given():
+    assert trunc.truncate("a\nb\nc").bytes_in == 5
+    assert trunc.truncate("a\r\nb\r\n").bytes_in == 6
+
+
+def test_bad_pattern_raises_value_error():
+    with pytest.raises(ValueError):
+        trunc.truncate("text", pattern="(unclosed")
+
+
+@pytest.mark.skipif(shutil.which("trunc") is None, reason="no trunc on PATH")
+def test_matches_the_cli():
+    text = generate_lines(5000, {700, 2100, 4000})
+    args = ["-f", "10", "-l", "10", "-m", "2", "-w", "40", "ERROR"]
+    cli = subprocess.run(["trunc", *args], input=text, capture_output=True, text=True)
+    result = trunc.truncate(text, first=10, last=10, matches=2, width=40, pattern="ERROR")
+    assert result.text == cli.stdout
@@ -0,0 +1,140 @@
+//! Truncation over async I/O, with the `async` feature: [`process`] for
+//! tokio's readers and writers, and [`TruncatedStream`] for a `Stream` of
+//! lines.
+//!
+//! Both push lines into the same synchronous core as
+//! [`Truncator::process`], which never blocks: output is collected in
+//! memory between lines and written out with `.await`, so the truncation
+//! matches the blocking API line for line.
+
+use std::io::{self, Write};
+use std::mem;
+use std::pin::Pin;
+use std::task::{ready, Context, Poll};
+
+use futures_core::Stream;
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, AsyncWrite, AsyncWriteExt};
+
+use crate::lines::itemized;
+use crate::run::Run;
+use crate::{TruncatedItem, Truncator};
+
+/// Output collected past this is written out even without a flush, under
+/// [`FlushPolicy::Block`](crate::FlushPolicy::Block).
+const CHUNK: usize = 8 * 1024;
+
+/// Truncate `reader` into `writer`, as [`Truncator::process`] does, without
+/// blocking.
+///
+/// Head and match lines are written as they're found, flushed as the
+/// [`FlushPolicy`](crate::FlushPolicy) says; the tail once `reader` ends.
+///
+/// ```
+/// # tokio::runtime::Builder::new_current_thread().build().unwrap().block_on(async {
+/// use trunc::Truncator;
+///
+/// let truncator = Truncator::new().first(1).last(1);
+/// let mut out = Vec::new();
+/// trunc::aio::process(&truncator, &b"a\nb\nc\nd\n"[..], &mut out)
+///     .await
+///     .unwrap();
+/// assert_eq!(out, b"a\n[... 2 lines truncated ...]\nd\n");
+/// # });
+/// ```
+pub async fn process<R, W>(truncator: &Truncator, mut reader: R, mut writer: W) -> io::Result<()>
+where
+    R: AsyncBufRead + Unpin,
+    W: AsyncWrite + Unpin,
+{
+    let mut run = Run::new(truncator, truncator.output(Pending::default()));
+    let mut line = String::new();
+    while reader.read_line(&mut line).await? > 0 {
+        run.read(&line);
+        line.clear();
+        drain(&mut run, &mut writer, false).await?;
+    }
+    let result = run.finish();
+    drain(&mut run, &mut writer, true).await?;
+    result
+}
+
+/// Output not yet written out, and whether it's been flushed since.
+#[derive(Default)]
+struct Pending {
+    buf: Vec<u8>,
+    flush: bool,
+}
+
+impl Write for Pending {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.buf.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.flush = true;
+        Ok(())
+    }
+}
+
+/// Write out what `run` has written to `writer`, once it's flushed, there's
+/// a chunk of it, or `all`.
+async fn drain<W: AsyncWrite + Unpin>(
+    run: &mut Run<'_, Pending>,
+    writer: &mut W,
+    all: bool,
+) -> io::Result<()> {
+    let pending = run.out().inner_mut();
+    if !(all || pending.flush || pending.buf.len() >= CHUNK) {
+        return Ok(());
+    }
+    writer.write_all(&pending.buf).await?;
+    pending.buf.clear();
+    if mem::take(&mut pending.flush) || all {
+        writer.flush().await?;
+    }
+    Ok(())
+}
+
+/// A [`Stream`] of the truncated view of a stream's lines: the async
+/// [`TruncatedLines`](crate::TruncatedLines).
+///
+/// Lines are polled from the stream as items are; head and match lines
+/// come as they're found, the tail once the stream ends.
+pub struct TruncatedStream<'a, S> {
+    source: S,
+    run: Run<'a, io::Sink>,
+}
+
+impl<'a, S: Stream<Item = String> + Unpin> TruncatedStream<'a, S> {
+    /// Truncate `lines`, each without its newline, as `options` says.
+    pub fn new(lines: S, options: &'a Truncator) -> Self {
+        TruncatedStream {
+            source: lines,
+            run: itemized(options),
+        }
+    }
+}
+
+impl<S: Stream<Item = String> + Unpin> Stream for TruncatedStream<'_, S> {
+    type Item = TruncatedItem;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<TruncatedItem>> {
+        let this = self.get_mut();
+        loop {
+            if let Some(item) = this.run.out().next_item() {
+                return Poll::Ready(Some(item));
+            }
+            if this.run.finished() {
+                return Poll::Ready(None);
+            }
+            match ready!(Pin::new(&mut this.source).poll_next(cx)) {
+                Some(line) => this.run.line(&line),
+                None => {
+                    // Written to a sink, so it can't fail
+                    let _ = this.run.finish();
+                }
+            }
+        }
+    }
+}
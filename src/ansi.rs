@@ -0,0 +1,87 @@
+//! ANSI escape sequences in input lines: telling them apart from visible
+//! text, and which of them set the style.
+
+use std::ops::Range;
+
+pub(crate) const ESC: char = '\x1b';
+
+/// SGR reset: back to the default style.
+pub(crate) const RESET: &str = "\x1b[0m";
+
+/// A piece of a line: an escape sequence, or a run of visible text.
+pub(crate) enum Piece {
+    Escape(Range<usize>),
+    Text(Range<usize>),
+}
+
+/// Split `line` into escape sequences and the visible text between them.
+///
+/// Recognises CSI (`ESC [ ... final`), OSC (`ESC ] ... BEL` or `ESC ] ... ESC \`)
+/// and two-char `ESC x` sequences. An unterminated sequence runs to the end
+/// of the line.
+pub(crate) fn pieces(line: &str) -> Vec<Piece> {
+    let bytes = line.as_bytes();
+    let mut pieces = Vec::new();
+    let mut i = 0;
+    while i < line.len() {
+        if bytes[i] != ESC as u8 {
+            let end = line[i..].find(ESC).map_or(line.len(), |n| i + n);
+            pieces.push(Piece::Text(i..end));
+            i = end;
+            continue;
+        }
+        let start = i;
+        i += 1;
+        match bytes.get(i) {
+            Some(b'[') => {
+                i += 1;
+                while i < bytes.len() && !(0x40..=0x7e).contains(&bytes[i]) {
+                    i += 1;
+                }
+                i = (i + 1).min(bytes.len());
+            }
+            Some(b']') => {
+                i += 1;
+                loop {
+                    match bytes.get(i) {
+                        None => break,
+                        Some(0x07) => {
+                            i += 1;
+                            break;
+                        }
+                        Some(&b) if b == ESC as u8 && bytes.get(i + 1) == Some(&b'\\') => {
+                            i += 2;
+                            break;
+                        }
+                        Some(_) => i += 1,
+                    }
+                }
+            }
+            Some(_) => i += line[i..].chars().next().map_or(1, char::len_utf8),
+            None => {}
+        }
+        pieces.push(Piece::Escape(start..i));
+    }
+    pieces
+}
+
+/// `line` without its escape sequences, if it has any.
+pub(crate) fn strip(line: &str) -> Option<String> {
+    if !line.contains(ESC) {
+        return None;
+    }
+    let text = pieces(line)
+        .into_iter()
+        .filter_map(|piece| match piece {
+            Piece::Text(range) => Some(&line[range]),
+            Piece::Escape(_) => None,
+        })
+        .collect();
+    Some(text)
+}
+
+/// Whether an escape sequence sets the style (SGR), and whether it resets it.
+pub(crate) fn sgr(seq: &str) -> Option<bool> {
+    let params = seq.strip_prefix("\x1b[")?.strip_suffix('m')?;
+    Some(params.is_empty() || params == "0")
+}
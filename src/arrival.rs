@@ -0,0 +1,62 @@
+//! When and where input lines arrived, for `--elapsed`, `--timestamps`
+//! and the byte ranges of regions: kept for the lines that can still be
+//! shown and the first line of the open gap, whose marker starts at it,
+//! rather than for every line read.
+
+use std::collections::VecDeque;
+
+use crate::clock::Instant;
+
+/// When a line was read, and where it starts in the input.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Arrival {
+    pub(crate) at: Instant,
+    pub(crate) offset: usize,
+}
+
+#[derive(Debug, Default)]
+pub(crate) struct Arrivals {
+    /// Input line number and arrival of the lines kept, oldest first
+    lines: VecDeque<(usize, Arrival)>,
+    /// The first line of the open gap, once it's older than `lines`
+    gap: Option<(usize, Arrival)>,
+}
+
+impl Arrivals {
+    /// Input line `original`, the line after the last one pushed, arrived.
+    pub(crate) fn push(&mut self, original: usize, arrival: Arrival) {
+        self.lines.push_back((original, arrival));
+    }
+
+    /// When and where input line `original` arrived, unless it's been
+    /// forgotten.
+    pub(crate) fn get(&self, original: usize) -> Option<Arrival> {
+        if let Some((_, arrival)) = self.gap.filter(|(line, _)| *line == original) {
+            return Some(arrival);
+        }
+        let i = self.lines.partition_point(|(line, _)| *line < original);
+        self.lines
+            .get(i)
+            .filter(|(line, _)| *line == original)
+            .map(|(_, arrival)| *arrival)
+    }
+
+    /// When and where the last line pushed arrived.
+    pub(crate) fn last(&self) -> Option<Arrival> {
+        self.lines.back().map(|(_, arrival)| *arrival)
+    }
+
+    /// Forget the lines before `oldest`, bar `gap`, the first line of the
+    /// open gap.
+    pub(crate) fn forget_before(&mut self, oldest: usize, gap: usize) {
+        if self.gap.is_some_and(|(line, _)| line != gap) {
+            self.gap = None;
+        }
+        while let Some(&(line, arrival)) = self.lines.front().filter(|(line, _)| *line < oldest) {
+            if line == gap {
+                self.gap = Some((line, arrival));
+            }
+            self.lines.pop_front();
+        }
+    }
+}
@@ -0,0 +1,63 @@
+//! The heuristic behind [`Truncator::block_aware`]: which lines carry on a
+//! multi-line block (a traceback, a compiler diagnostic) rather than start
+//! a new one, so a cut point can move to the block's edge.
+//!
+//! [`Truncator::block_aware`]: crate::Truncator::block_aware
+
+/// Most lines a cut point moves to keep a block whole; a longer block is
+/// cut anyway.
+pub(crate) const MAX_EXTENSION: usize = 50;
+
+/// Whose output it is, for telling where blocks start and end.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Lang {
+    /// Indentation and the shapes common to most tools' output
+    #[default]
+    Generic,
+    /// rustc and cargo: an `error[E0308]: ...` diagnostic is a block with
+    /// its `-->` line, code frame and `help:` / `note:` lines
+    Rust,
+}
+
+/// Whether `line` continues the block above it: it's indented (traceback
+/// frames, rustc's `-->` and `= note:` lines), a rustc source line
+/// (`12 |     let x`), a Java `Caused by:`, or the exception a Python
+/// traceback ends with (`ValueError: ...`). For Rust, so is a `help:` or
+/// `note:` line.
+pub(crate) fn continues(line: &str, lang: Lang) -> bool {
+    if line.trim().is_empty() {
+        return false;
+    }
+    if line.starts_with([' ', '\t']) || line.starts_with("Caused by: ") {
+        return true;
+    }
+    if lang == Lang::Rust && (line.starts_with("help: ") || line.starts_with("note: ")) {
+        return true;
+    }
+    let gutter = line.trim_start_matches(|c: char| c.is_ascii_digit());
+    if gutter.len() < line.len() && gutter.trim_start_matches(' ').starts_with('|') {
+        return true;
+    }
+    let name = line
+        .split(':')
+        .next()
+        .filter(|name| !name.contains(|c: char| !c.is_alphanumeric() && c != '_' && c != '.'))
+        .unwrap_or("");
+    name.ends_with("Error") || name.ends_with("Exception")
+}
+
+/// Whether `line` is the first line of a block in `lang`, such that the
+/// block is all the context a match on it needs: for Rust, a diagnostic
+/// with a code (`error[E0308]: `, `warning[E0170]: `).
+pub(crate) fn starts(line: &str, lang: Lang) -> bool {
+    match lang {
+        Lang::Generic => false,
+        Lang::Rust => ["error[", "warning["].iter().any(|kind| {
+            line.strip_prefix(kind)
+                .and_then(|rest| rest.split_once("]: "))
+                .is_some_and(|(code, _)| {
+                    !code.is_empty() && code.chars().all(|c| c.is_ascii_alphanumeric())
+                })
+        }),
+    }
+}
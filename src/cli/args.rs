@@ -0,0 +1,1592 @@
+//! The command line: clap's view of trunc's options, and how they map
+//! onto the library's [`Truncator`].
+
+use crate::cli::detectors;
+use crate::cli::glob;
+use crate::cli::input::{Input, Reading, Separator};
+use crate::cli::presets;
+use crate::cli::terminal;
+use crate::cut::Cut;
+use crate::{
+    parse_timestamp, FlushPolicy, Lang, LineCut, MarkerStyle, Normalizer, Predicate, Redactor,
+    Selection, TimestampRewrite, Timestamps, Trailer, Truncator, WidthMode,
+};
+use clap::builder::FalseyValueParser;
+use clap::{Parser, Subcommand, ValueEnum};
+use regex::{Regex, RegexBuilder, RegexSet, RegexSetBuilder};
+use std::ffi::OsString;
+use std::io::{self, IsTerminal};
+use std::ops::{Range, RangeInclusive};
+use std::path::PathBuf;
+use std::process;
+use std::time::{Duration, SystemTime};
+
+/// Smart truncation for pipe output - like head+tail combined.
+///
+/// Shows the first N and last M lines, with optional grep-style pattern matching
+/// to extract relevant lines from the middle.
+#[derive(Parser, Debug)]
+#[command(name = "trunc", version, about, args_conflicts_with_subcommands = true)]
+pub(crate) struct Cli {
+    #[command(flatten)]
+    pub(crate) args: Args,
+
+    /// Print help, then the format of the markers and other lines trunc
+    /// adds to its output
+    #[arg(long = "help-full")]
+    pub(crate) help_full: bool,
+
+    #[command(subcommand)]
+    pub(crate) command: Option<Command>,
+}
+
+#[derive(Subcommand, Debug)]
+pub(crate) enum Command {
+    /// Re-truncate a recording made with --record, without re-running the command
+    Replay {
+        /// Recording file written by --record
+        file: PathBuf,
+
+        #[command(flatten)]
+        args: Box<Args>,
+    },
+    /// Print lines a marker hid, from a file saved with --save
+    Show {
+        /// File written by --save or --save-auto
+        file: PathBuf,
+
+        /// Which marker's lines, numbered from 1 as in the markers
+        #[arg(long = "region", value_name = "N", required_unless_present = "bytes")]
+        region: Option<usize>,
+
+        /// Bytes START up to END of the file, as given in a marker
+        #[arg(long = "bytes", value_name = "START-END", value_parser = parse_byte_range, conflicts_with = "region")]
+        bytes: Option<Range<usize>>,
+    },
+    /// Print the man page, in roff, to stdout
+    #[command(hide = true)]
+    Manpage,
+}
+
+/// Truncation options, shared by the default mode and `replay`.
+#[derive(clap::Args, Clone, Debug)]
+pub(crate) struct Args {
+    /// Number of lines to show from start, or a percentage of the input's
+    /// lines (e.g. 10%)
+    #[arg(
+        short = 'f',
+        long = "first",
+        default_value = "30",
+        visible_alias = "head",
+        short_alias = 'H',
+        value_parser = parse_size
+    )]
+    pub(crate) first: Size,
+
+    /// Number of lines to show from end, or a percentage of the input's
+    /// lines (e.g. 10%)
+    #[arg(
+        short = 'l',
+        long = "last",
+        default_value = "30",
+        visible_alias = "tail",
+        short_alias = 'T',
+        value_parser = parse_size
+    )]
+    pub(crate) last: Size,
+
+    /// Instead of -f, show as many whole lines from the start as fit in
+    /// SIZE bytes (e.g. 4096, 4K, 4KB)
+    #[arg(long = "first-bytes", value_name = "SIZE", value_parser = parse_bytes, conflicts_with = "first")]
+    pub(crate) first_bytes: Option<usize>,
+
+    /// Instead of -l, show as many whole lines from the end as fit in SIZE
+    /// bytes (e.g. 8192, 8K, 8KB)
+    #[arg(long = "last-bytes", value_name = "SIZE", value_parser = parse_bytes, conflicts_with = "last")]
+    pub(crate) last_bytes: Option<usize>,
+
+    /// Show no lines from the start (as -f 0, but its lines can go to the
+    /// tail under --rebalance)
+    #[arg(long = "no-head", conflicts_with = "first_bytes")]
+    pub(crate) no_head: bool,
+
+    /// Show no lines from the end (as -l 0, but its lines can go to the
+    /// head under --rebalance)
+    #[arg(long = "no-tail", conflicts_with = "last_bytes")]
+    pub(crate) no_tail: bool,
+
+    /// Give lines a section doesn't use to the others: those of --no-head
+    /// or --no-tail to the other, and those set aside for matches not found
+    /// to the tail
+    #[arg(long = "rebalance")]
+    pub(crate) rebalance: bool,
+
+    /// Always show the first K input lines (default 1), such as a column
+    /// header or version banner, on top of -f. A pattern that's a number
+    /// goes after -e
+    #[arg(
+        long = "keep-header",
+        value_name = "K",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "1"
+    )]
+    pub(crate) keep_header: Option<usize>,
+
+    /// Pass input through whole, like cat and without markers, when it's no
+    /// longer than head and tail together, or than the screen on a
+    /// terminal. TRUNC_ONLY_IF_NEEDED=1 makes it the default
+    #[arg(long = "only-if-needed", env = "TRUNC_ONLY_IF_NEEDED", value_parser = FalseyValueParser::new())]
+    pub(crate) only_if_needed: bool,
+
+    /// Truncate even input that would pass through under --only-if-needed
+    #[arg(long = "always-truncate")]
+    pub(crate) always_truncate: bool,
+
+    /// Max matches to show in pattern mode
+    #[arg(short = 'm', long = "matches", default_value = "5")]
+    pub(crate) matches: usize,
+
+    /// In pattern mode, make the tail the last K matches, with context,
+    /// rather than the last lines
+    #[arg(
+        long = "tail-matches",
+        value_name = "K",
+        conflicts_with_all = ["last", "last_bytes", "no_tail", "count"]
+    )]
+    pub(crate) tail_matches: Option<usize>,
+
+    /// Show no matches, just a summary of how many the middle hides
+    #[arg(short = 'c', long = "count", conflicts_with_all = ["rank_by", "spread"])]
+    pub(crate) count: bool,
+
+    /// Lines of context around each match (-B and -A override either side)
+    #[arg(short = 'C', long = "context", default_value = "3")]
+    pub(crate) context: usize,
+
+    /// Lines of context before each match (default: -C)
+    #[arg(short = 'B', long = "before-context", value_name = "N")]
+    pub(crate) before_context: Option<usize>,
+
+    /// Lines of context after each match (default: -C)
+    #[arg(short = 'A', long = "after-context", value_name = "N")]
+    pub(crate) after_context: Option<usize>,
+
+    /// End each match's after-context at the next line matching REGEX (a
+    /// blank line, `^----`), not after -A lines
+    #[arg(
+        long = "context-until",
+        value_name = "REGEX",
+        conflicts_with = "after_context"
+    )]
+    pub(crate) context_until: Option<String>,
+
+    /// Don't cut in the middle of an indented block (a traceback, a compiler
+    /// diagnostic): head, tail and context extend to its edge
+    #[arg(long = "block-aware")]
+    pub(crate) block_aware: bool,
+
+    /// Whose output this is, for --block-aware (which it implies): with
+    /// rust, a match on `error[E0308]: ...` shows the whole diagnostic
+    #[arg(long = "lang", value_name = "LANG")]
+    pub(crate) lang: Option<Language>,
+
+    /// Chars to show at start/end of long lines (0 = no limit)
+    #[arg(short = 'w', long = "width", default_value = "100")]
+    pub(crate) width: usize,
+
+    /// --width for head lines
+    #[arg(long = "width-head", value_name = "N")]
+    pub(crate) width_head: Option<usize>,
+
+    /// --width for match lines and their context, which usually deserve
+    /// more
+    #[arg(long = "width-match", value_name = "N")]
+    pub(crate) width_match: Option<usize>,
+
+    /// --width for tail lines
+    #[arg(long = "width-tail", value_name = "N")]
+    pub(crate) width_tail: Option<usize>,
+
+    /// What --width counts: chars (grapheme clusters) or terminal columns,
+    /// where wide CJK chars and emoji take two
+    #[arg(long = "width-mode", value_name = "MODE", default_value = "chars")]
+    pub(crate) width_mode: WidthUnit,
+
+    /// Which part of a long line to cut out: start (keeping the last 2 x
+    /// --width, such as a path's file name), middle or end
+    #[arg(long = "line-cut", value_name = "PART", default_value = "middle")]
+    pub(crate) line_cut: CutPart,
+
+    /// On lines that are JSON, cut long string values to --width chars
+    /// each instead of cutting the line, so it stays valid JSON
+    #[arg(long = "json-values")]
+    pub(crate) json_values: bool,
+
+    /// CSV/TSV input: always show the header (line 1), and cut overlong
+    /// rows by dropping middle columns rather than chars
+    #[arg(long = "table")]
+    pub(crate) table: bool,
+
+    /// How markers are written: verbose (`[... 980 lines truncated ...]`) or
+    /// compact (`…980L…`)
+    #[arg(long = "markers", value_name = "STYLE", default_value = "verbose")]
+    pub(crate) markers: Markers,
+
+    /// Write markers, headers, the --region-ids footer and the trailer to
+    /// stderr, so stdout holds only input lines
+    #[arg(long = "markers-to-stderr")]
+    pub(crate) markers_to_stderr: bool,
+
+    /// Never print more than N bytes in total: head, matches and tail shrink
+    /// proportionally to fit, and markers note where lines were cut for space
+    #[arg(long = "max-bytes", value_name = "N", conflicts_with = "follow")]
+    pub(crate) max_bytes: Option<usize>,
+
+    /// Treat each run of lines up to and including a line that's exactly
+    /// STRING as one record: sizes, matches and context count records
+    #[arg(long = "separator", value_name = "STRING", allow_hyphen_values = true)]
+    pub(crate) separator: Option<String>,
+
+    /// Treat blank-line separated paragraphs as records, as --separator does
+    #[arg(long = "paragraph", conflicts_with = "separator")]
+    pub(crate) paragraph: bool,
+
+    /// Read invalid UTF-8 (binary or garbled output) as U+FFFD replacement
+    /// characters instead of stopping with an error
+    #[arg(long = "binary-safe")]
+    pub(crate) binary_safe: bool,
+
+    /// Decompress any file or stdin whose first bytes say it's gzip, zstd
+    /// or xz, whatever its name (files ending .gz, .zst or .xz always are)
+    #[arg(long = "decompress")]
+    pub(crate) decompress: bool,
+
+    /// Cut lines longer than SIZE bytes (e.g. 1M) as they're read, keeping
+    /// the ends around a `[... N bytes ...]` note, so a giant line is never
+    /// held whole
+    #[arg(long = "max-line-bytes", value_name = "SIZE", value_parser = parse_bytes)]
+    pub(crate) max_line_bytes: Option<usize>,
+
+    /// Hold at most SIZE bytes of lines in the tail and context buffers
+    /// (e.g. 64M): past it, buffered lines are dropped or cut short, noted
+    /// in the next marker
+    #[arg(long = "max-memory", value_name = "SIZE", value_parser = parse_bytes)]
+    pub(crate) max_memory: Option<usize>,
+
+    /// Find a file's matches on every core before truncating it, for
+    /// patterns over multi-GB files (output is the same either way)
+    #[arg(long = "parallel")]
+    pub(crate) parallel: bool,
+
+    /// Append a final trailer line (checksum: digest of the output plus hidden line/byte counts)
+    #[arg(long = "trailer", value_name = "KIND")]
+    pub(crate) trailer: Option<TrailerKind>,
+
+    /// Exit non-zero if REGEX never appears anywhere in the input (repeatable)
+    #[arg(long = "require", value_name = "REGEX")]
+    pub(crate) require: Vec<String>,
+
+    /// Exit non-zero and report offending lines if REGEX appears anywhere in the input (repeatable)
+    #[arg(long = "forbid", value_name = "REGEX")]
+    pub(crate) forbid: Vec<String>,
+
+    /// Save the raw input to FILE, for later `trunc replay`
+    #[arg(long = "record", value_name = "FILE")]
+    pub(crate) record: Option<PathBuf>,
+
+    /// Save the complete input to FILE, and give the byte range of it each
+    /// truncation marker hides
+    #[arg(long = "save", value_name = "FILE", conflicts_with = "save_auto")]
+    pub(crate) save: Option<PathBuf>,
+
+    /// As --save, to a new file in the temp directory
+    #[arg(long = "save-auto")]
+    pub(crate) save_auto: bool,
+
+    /// As --save, keeping FILE flushed as lines arrive, so it can be read
+    /// or followed while trunc runs
+    #[arg(
+        long = "tee",
+        value_name = "FILE",
+        conflicts_with_all = ["save", "save_auto"]
+    )]
+    pub(crate) tee: Option<PathBuf>,
+
+    /// Also write the head lines to FILE
+    #[arg(long = "out-head", value_name = "FILE")]
+    pub(crate) out_head: Option<PathBuf>,
+
+    /// Also write the match lines, with their context, to FILE
+    #[arg(long = "out-matches", value_name = "FILE")]
+    pub(crate) out_matches: Option<PathBuf>,
+
+    /// Also write the tail lines to FILE
+    #[arg(long = "out-tail", value_name = "FILE")]
+    pub(crate) out_tail: Option<PathBuf>,
+
+    /// Print nothing to stdout, e.g. when --out-head and the like are all
+    /// that's wanted
+    #[arg(short = 'q', long = "quiet")]
+    pub(crate) quiet: bool,
+
+    /// Number every truncation marker, and end with a footer giving the
+    /// input lines and bytes each one hid
+    #[arg(long = "region-ids")]
+    pub(crate) region_ids: bool,
+
+    /// Give every truncation marker the byte range of the input it hides,
+    /// for dd or tail -c
+    #[arg(long = "byte-ranges")]
+    pub(crate) byte_ranges: bool,
+
+    /// Count trunc markers in the input, from truncating it before, as a
+    /// line each rather than as the lines they hid
+    #[arg(long = "no-idempotent")]
+    pub(crate) no_idempotent: bool,
+
+    /// Note in every truncation marker how long the lines it hides took
+    /// to arrive, for slow pipes like a build
+    #[arg(long)]
+    pub(crate) elapsed: bool,
+
+    /// Print a summary to stderr at the end: lines and bytes read and
+    /// emitted, matches found and shown, the longest line, time taken
+    #[arg(long = "stats")]
+    pub(crate) stats: bool,
+
+    /// Print the --stats summary as one JSON object, with what each marker
+    /// hid, to stderr or the given file descriptor (e.g. 3). A pattern
+    /// that's a number goes after -e
+    #[arg(
+        long = "stats-json",
+        value_name = "FD",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "2"
+    )]
+    pub(crate) stats_json: Option<u32>,
+
+    /// Print to stderr at the end how many input lines were at each log
+    /// level: ERROR, WARN, INFO, DEBUG and TRACE
+    #[arg(long = "level-histogram")]
+    pub(crate) level_histogram: bool,
+
+    /// Trace to stderr why each line was shown, held for the tail or
+    /// hidden, when budgets ran out and where markers went
+    #[arg(long = "explain")]
+    pub(crate) explain: bool,
+
+    /// Print nothing of the input: instead, count what would be printed
+    /// (lines per section, markers, bytes, tokens) for these flags and a
+    /// few alternatives
+    #[arg(
+        long = "plan",
+        conflicts_with_all = [
+            "follow", "watch", "quiet", "markers_to_stderr", "out_head", "out_matches",
+            "out_tail", "save", "save_auto", "tee",
+        ]
+    )]
+    pub(crate) plan: bool,
+
+    /// A level for --level-histogram, as NAME=REGEX (repeatable, most
+    /// severe first; replaces the default levels)
+    #[arg(
+        long = "level",
+        value_name = "NAME=REGEX",
+        value_parser = parse_level,
+        requires = "level_histogram"
+    )]
+    pub(crate) level: Vec<(String, String)>,
+
+    /// Keep reading after EOF (files) and periodically show the rolling tail,
+    /// instead of waiting for EOF before any tail output
+    #[arg(short = 'F', long = "follow")]
+    pub(crate) follow: bool,
+
+    /// Truncate a file again each time it changes, clearing the screen
+    /// first on a terminal (like `watch`), until interrupted
+    #[arg(long = "watch", conflicts_with_all = ["follow", "command"])]
+    pub(crate) watch: bool,
+
+    /// In follow mode, show the whole truncated view of everything read so
+    /// far at each refresh, clearing the screen first on a terminal
+    #[arg(
+        long = "snapshot",
+        requires = "follow",
+        conflicts_with_all = ["command", "save", "save_auto", "tee", "record"]
+    )]
+    pub(crate) snapshot: bool,
+
+    /// Time between rolling-tail refreshes in follow mode (e.g. 2s, 500ms)
+    #[arg(long = "refresh", value_name = "DURATION", default_value = "2s", value_parser = parse_duration)]
+    pub(crate) refresh: Duration,
+
+    /// If no input arrives for DURATION (e.g. 30s), show the tail so far
+    /// under an `input stalled` marker, without waiting for EOF
+    #[arg(long = "idle-timeout", value_name = "DURATION", value_parser = parse_duration, conflicts_with_all = ["follow", "max_bytes"])]
+    pub(crate) idle_timeout: Option<Duration>,
+
+    /// With --idle-timeout, stop at the first stall (killing a wrapped
+    /// command) and exit 124
+    #[arg(long = "idle-exit", requires = "idle_timeout")]
+    pub(crate) idle_exit: bool,
+
+    /// Every DURATION (e.g. 60s), update a `still reading…` status line on
+    /// stderr with the lines and matches so far
+    #[arg(long = "heartbeat", value_name = "DURATION", value_parser = parse_duration, conflicts_with = "follow")]
+    pub(crate) heartbeat: Option<Duration>,
+
+    /// When to flush shown lines: `line` (after each head and match line),
+    /// `block` (when the buffer fills) or `interval=MS` (at most every MS
+    /// milliseconds) [default: line on a terminal, else interval=100]
+    #[arg(long = "flush", value_name = "POLICY", value_parser = parse_flush)]
+    pub(crate) flush: Option<FlushPolicy>,
+
+    /// Regex pattern to search for in the middle section; repeat to match
+    /// any of several (every operand is then a file). Given more than one,
+    /// `REGEX:N` shows at most N of its matches, apart from -m
+    #[arg(short = 'e', long = "pattern", value_name = "REGEX")]
+    pub(crate) patterns: Vec<String>,
+
+    /// Show at most N matches of PATTERN, one of the patterns given, apart
+    /// from -m (repeatable)
+    #[arg(long = "max-per", value_name = "PATTERN=N", value_parser = parse_max_per)]
+    pub(crate) max_per: Vec<(String, usize)>,
+
+    /// Match budgets of the patterns, one per pattern
+    #[arg(skip)]
+    pub(crate) match_limits: Vec<Option<usize>>,
+
+    /// Only count a line as a match if it matches all of these too (with
+    /// no other pattern, lines matching all of them are the matches)
+    #[arg(long = "all-of", value_name = "REGEX", num_args = 1.., conflicts_with = "fixed_string")]
+    pub(crate) all_of: Vec<String>,
+
+    /// Only count a line as a match if it matches any of these too (with
+    /// no other pattern, lines matching any of them are the matches)
+    #[arg(long = "any-of", value_name = "REGEX", num_args = 1.., conflicts_with = "fixed_string")]
+    pub(crate) any_of: Vec<String>,
+
+    /// Don't count a line as a match if it matches this regex (repeatable);
+    /// unlike --exclude, it may still show as context
+    #[arg(long = "and-not", value_name = "REGEX")]
+    pub(crate) and_not: Vec<String>,
+
+    /// Show a match only if its captures give a TEMPLATE (`$1`, `${code}`)
+    /// not seen in a match shown before; repeats are counted in markers
+    #[arg(long = "unique-by", value_name = "TEMPLATE")]
+    pub(crate) unique_by: Option<String>,
+
+    /// Drop lines matching this regex before anything else sees them
+    /// (repeatable); how many is noted before the tail
+    #[arg(long = "exclude", value_name = "REGEX")]
+    pub(crate) exclude: Vec<String>,
+
+    /// Drop lines timestamped before TIME: a duration ago (5m, 2h, 1d) or
+    /// a timestamp (2024-05-01T12:00:00Z); lines without one go with the
+    /// line above
+    #[arg(long = "since", value_name = "TIME", value_parser = parse_time)]
+    pub(crate) since: Option<SystemTime>,
+
+    /// Drop lines timestamped after TIME, as for --since
+    #[arg(long = "until", value_name = "TIME", value_parser = parse_time)]
+    pub(crate) until: Option<SystemTime>,
+
+    /// Without a pattern, show the first timestamped line of each DURATION
+    /// (e.g. 1s, 5m) from the middle, for an overview over time
+    #[arg(
+        long = "sample-per",
+        value_name = "DURATION",
+        value_parser = parse_duration,
+        conflicts_with_all = ["region_ids", "save", "tee"]
+    )]
+    pub(crate) sample_per: Option<Duration>,
+
+    /// Without a pattern, also show K lines picked at random from the
+    /// middle, with their line numbers
+    #[arg(
+        long = "sample",
+        value_name = "K",
+        value_parser = parse_positive,
+        conflicts_with_all = ["sample_per", "summarize"]
+    )]
+    pub(crate) sample: Option<usize>,
+
+    /// Seed for --sample, to pick the same lines on every run
+    #[arg(long = "seed", value_name = "N", requires = "sample")]
+    pub(crate) seed: Option<u64>,
+
+    /// Without a pattern, say in the marker what the middle hides: lines
+    /// per log level, distinct lines and the most frequent ones
+    #[arg(long = "summarize")]
+    pub(crate) summarize: bool,
+
+    /// Always show lines matching this regex, even from the middle and
+    /// without a pattern (repeatable; they don't count towards -m)
+    #[arg(long = "always", value_name = "REGEX")]
+    pub(crate) always: Vec<String>,
+
+    /// Always show input lines N to M, as for --always (repeatable; `N` for
+    /// a single line). Use -f 0 -l 0 to see only these
+    #[arg(long = "lines", value_name = "N-M", value_parser = parse_range)]
+    pub(crate) lines: Vec<RangeInclusive<usize>>,
+
+    /// Always show input line LINE and CONTEXT lines either side (default:
+    /// -B/-A/-C), as for --lines (repeatable)
+    #[arg(long = "around", value_name = "LINE[:CONTEXT]", value_parser = parse_around)]
+    pub(crate) around: Vec<Around>,
+
+    /// Always show the N lines at the centre of the input, as for --lines.
+    /// Use -f 0 -l 0 to see only these
+    #[arg(long = "middle", value_name = "N", value_parser = parse_positive)]
+    pub(crate) middle: Option<usize>,
+
+    /// Also show every Nth input line from the middle, with its line
+    /// number, for a skeleton of a long output
+    #[arg(long = "every", value_name = "N", value_parser = parse_positive)]
+    pub(crate) every: Option<usize>,
+
+    /// After the tail, list the K longest input lines with their line
+    /// numbers
+    #[arg(long = "longest", value_name = "K", value_parser = parse_positive)]
+    pub(crate) longest: Option<usize>,
+
+    /// Replace secrets (AWS keys, bearer tokens, password=...) in the output
+    /// with [REDACTED]
+    #[arg(long = "redact")]
+    pub(crate) redact: bool,
+
+    /// Also redact matches of this regex, or its `secret` group if it has
+    /// one (repeatable)
+    #[arg(long = "redact-pattern", value_name = "REGEX")]
+    pub(crate) redact_patterns: Vec<String>,
+
+    /// On logfmt (key=value) lines, show only these fields and a count of
+    /// the rest (comma-separated, e.g. ts,level,msg)
+    #[arg(long = "fields", value_name = "NAMES", value_delimiter = ',')]
+    pub(crate) fields: Vec<String>,
+
+    /// Collapse runs of lines that differ only in timestamps, ids and
+    /// numbers into the first, with a count
+    #[arg(long = "dedup-fuzzy")]
+    pub(crate) dedup_fuzzy: bool,
+
+    /// Fold deep Python tracebacks to their first frame, a count of the
+    /// rest, the last 3 frames and the exception, and JVM stack traces to
+    /// the first 3 and last frames of each cause (on with --auto)
+    #[arg(long = "fold-tracebacks")]
+    pub(crate) fold_tracebacks: bool,
+
+    /// Also ignore matches of this regex when comparing lines for
+    /// --dedup-fuzzy (repeatable)
+    #[arg(long = "dedup-strip", value_name = "REGEX", requires = "dedup_fuzzy")]
+    pub(crate) dedup_strip: Vec<String>,
+
+    /// Remove ANSI escape sequences from the input (default when stdout
+    /// isn't a terminal)
+    #[arg(long = "strip-ansi", conflicts_with = "keep_ansi")]
+    pub(crate) strip_ansi: bool,
+
+    /// Keep ANSI escape sequences from the input exactly (default when
+    /// stdout is a terminal)
+    #[arg(long = "keep-ansi")]
+    pub(crate) keep_ansi: bool,
+
+    /// Prefix each line shown with its line number in the input
+    #[arg(short = 'n', long = "line-numbers")]
+    pub(crate) line_numbers: bool,
+
+    /// Give each match's line number in the input in its marker
+    /// (`match 2 @ line 4812 shown`)
+    #[arg(long = "match-line-numbers")]
+    pub(crate) match_line_numbers: bool,
+
+    /// Prefix each line shown with when trunc read it: the time in UTC
+    /// (wall, the default) or seconds since trunc started (relative).
+    /// A pattern that's one of these words goes after -e
+    #[arg(
+        long,
+        value_name = "KIND",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "wall"
+    )]
+    pub(crate) timestamps: Option<StampKind>,
+
+    /// Remove the timestamp each line shown starts with (ISO 8601, syslog,
+    /// Common Log Format or Unix time, as for --since), to save room
+    #[arg(long = "strip-timestamps", conflicts_with = "normalize_timestamps")]
+    pub(crate) strip_timestamps: bool,
+
+    /// Rewrite the timestamp each line shown starts with: relative gives
+    /// the time since the input's first (`+12.345s`)
+    #[arg(long = "normalize-timestamps", value_name = "KIND")]
+    pub(crate) normalize_timestamps: Option<Normalization>,
+
+    /// Show every frame of a line redrawn with \r (a progress bar), not just
+    /// the last
+    #[arg(long = "keep-progress")]
+    pub(crate) keep_progress: bool,
+
+    /// Treat patterns as literal strings, not regexes (`-F` is --follow)
+    #[arg(long = "fixed-string", visible_alias = "fixed-strings")]
+    pub(crate) fixed_string: bool,
+
+    /// Regex engine for the patterns: rust, or pcre2 for look-around and
+    /// backreferences (in builds with the pcre2 feature)
+    #[arg(
+        long = "engine",
+        value_name = "ENGINE",
+        default_value = "rust",
+        conflicts_with_all = ["fixed_string", "auto"]
+    )]
+    pub(crate) engine: Engine,
+
+    /// Treat patterns as shell-style globs matching a whole line (`*` any
+    /// text, `?` one character, `[abc]` a set), not regexes
+    #[arg(long = "glob", conflicts_with_all = ["fixed_string", "engine", "auto"])]
+    pub(crate) glob: bool,
+
+    /// When there are more matches than -m, show the most severe rather than
+    /// the first (the whole input is read before any match is shown)
+    #[arg(long = "rank-by", value_name = "RANK", conflicts_with = "follow")]
+    pub(crate) rank_by: Option<RankBy>,
+
+    /// When there are more matches than -m, show the first, the last and
+    /// evenly spaced ones between (the whole input is read first)
+    #[arg(long = "spread", conflicts_with_all = ["follow", "rank_by"])]
+    pub(crate) spread: bool,
+
+    /// Severity ladder for --rank-by severity, most severe first (repeatable;
+    /// default: fatal|panic|critical, error, warn, info)
+    #[arg(long = "severity", value_name = "REGEX", requires = "rank_by")]
+    pub(crate) severity: Vec<String>,
+
+    /// Also match built-in detectors for common failures (panics, errors,
+    /// tracebacks, failed tests...), named in match markers
+    #[arg(long = "auto", conflicts_with = "fixed_string")]
+    pub(crate) auto: bool,
+
+    /// Patterns and budgets tuned for a tool's output; flags given
+    /// explicitly still win
+    #[arg(long = "preset", value_name = "TOOL", value_parser = clap::builder::PossibleValuesParser::new(presets::names()))]
+    pub(crate) preset: Option<String>,
+
+    /// Say which pattern matched in each match marker
+    #[arg(long = "label")]
+    pub(crate) label: bool,
+
+    /// Highlight matches and dim markers (auto: when stdout is a terminal and NO_COLOR is unset)
+    #[arg(long = "color", value_name = "WHEN", default_value = "auto")]
+    pub(crate) color: ColorChoice,
+
+    /// Regex pattern to search for in the middle section, then files to
+    /// read instead of stdin (`-` for stdin). With -e or --files, every
+    /// operand is a file
+    #[arg(value_name = "PATTERN|FILE")]
+    pub(crate) operands: Vec<String>,
+
+    /// Read every operand as a file, with no pattern unless -e gives one
+    /// (`trunc --files big.log`)
+    #[arg(long = "files")]
+    pub(crate) files: bool,
+
+    /// Command to run instead of reading input: its stdout is truncated and
+    /// trunc exits with its status
+    #[arg(last = true, value_name = "CMD")]
+    pub(crate) command: Vec<String>,
+
+    /// A wrapped command's stderr: pass it through, merge it into the
+    /// output (tagged), or truncate it separately to stderr
+    #[arg(
+        long = "stderr",
+        value_name = "MODE",
+        default_value = "pass",
+        requires = "command"
+    )]
+    pub(crate) stderr: StderrMode,
+
+    /// Run the wrapped command on a pseudo-terminal, so it keeps the colors
+    /// and progress output it would give a terminal (Unix only)
+    #[arg(long = "pty", requires = "command")]
+    pub(crate) pty: bool,
+
+    /// Exit 3 if anything was truncated, 0 if the input passed through
+    /// unchanged (errors, failed gates and a wrapped command's failure
+    /// still take precedence)
+    #[arg(long = "status")]
+    pub(crate) status: bool,
+}
+
+/// A `-f`/`-l` size: a line count, or a percentage of the input's lines.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum Size {
+    Lines(usize),
+    Percent(f64),
+}
+
+impl Size {
+    /// Lines to show out of `total`. A percentage rounds up, so any
+    /// non-zero percentage of a non-empty input shows at least one line.
+    pub(crate) fn of(self, total: usize) -> usize {
+        match self {
+            Size::Lines(n) => n,
+            Size::Percent(p) => (total as f64 * p / 100.0).ceil() as usize,
+        }
+    }
+}
+
+/// Parse a size like `20` or `10%`.
+pub(crate) fn parse_size(s: &str) -> Result<Size, String> {
+    match s.strip_suffix('%') {
+        Some(number) => {
+            let p: f64 = number
+                .trim()
+                .parse()
+                .map_err(|_| format!("invalid percentage: {}", s))?;
+            if !(0.0..=100.0).contains(&p) {
+                return Err(format!("percentage must be between 0 and 100: {}", s));
+            }
+            Ok(Size::Percent(p))
+        }
+        None => s
+            .parse()
+            .map(Size::Lines)
+            .map_err(|_| format!("invalid line count: {}", s)),
+    }
+}
+
+/// Parse a line range like `500-620`, or a single line like `42`.
+pub(crate) fn parse_range(s: &str) -> Result<RangeInclusive<usize>, String> {
+    let line = |n: &str| match n.trim().parse::<usize>() {
+        Ok(n) if n > 0 => Ok(n),
+        _ => Err(format!("invalid line number in range: {}", s)),
+    };
+    let (start, end) = match s.split_once('-') {
+        Some((start, end)) => (line(start)?, line(end)?),
+        None => (line(s)?, line(s)?),
+    };
+    if start > end {
+        return Err(format!("range ends before it starts: {}", s));
+    }
+    Ok(start..=end)
+}
+
+/// Parse a byte range like `4096-182044`: from the first byte up to, not
+/// including, the second.
+pub(crate) fn parse_byte_range(s: &str) -> Result<Range<usize>, String> {
+    let invalid = || format!("invalid byte range: {}", s);
+    let (start, end) = s.split_once('-').ok_or_else(invalid)?;
+    let start: usize = start.trim().parse().map_err(|_| invalid())?;
+    let end: usize = end.trim().parse().map_err(|_| invalid())?;
+    if start > end {
+        return Err(format!("range ends before it starts: {}", s));
+    }
+    Ok(start..end)
+}
+
+/// An `--around` window: a line, and how many lines either side of it.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct Around {
+    pub(crate) line: usize,
+    pub(crate) context: Option<usize>,
+}
+
+/// Parse an `--around` window like `1234:20`, or `1234` for the default
+/// context.
+pub(crate) fn parse_around(s: &str) -> Result<Around, String> {
+    let (line, context) = match s.split_once(':') {
+        Some((line, context)) => (line, Some(context)),
+        None => (s, None),
+    };
+    let line = match line.trim().parse::<usize>() {
+        Ok(n) if n > 0 => n,
+        _ => return Err(format!("invalid line number: {}", s)),
+    };
+    let context = context
+        .map(|c| c.trim().parse::<usize>())
+        .transpose()
+        .map_err(|_| format!("invalid context: {}", s))?;
+    Ok(Around { line, context })
+}
+
+/// Parse a `--level` like `ERROR=\b(ERROR|FATAL)\b`.
+pub(crate) fn parse_level(s: &str) -> Result<(String, String), String> {
+    match s.split_once('=') {
+        Some((name, re)) if !name.trim().is_empty() && !re.is_empty() => {
+            Ok((name.trim().to_string(), re.to_string()))
+        }
+        _ => Err(format!("expected NAME=REGEX: {}", s)),
+    }
+}
+
+/// `--trailer` values; see [`Trailer`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub(crate) enum TrailerKind {
+    /// SHA-256 of everything emitted before the trailer, plus hidden line/byte counts
+    Checksum,
+}
+
+impl From<TrailerKind> for Trailer {
+    fn from(kind: TrailerKind) -> Self {
+        match kind {
+            TrailerKind::Checksum => Trailer::Checksum,
+        }
+    }
+}
+
+/// `--width-mode` values; see [`WidthMode`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub(crate) enum WidthUnit {
+    Chars,
+    Columns,
+}
+
+impl From<WidthUnit> for WidthMode {
+    fn from(unit: WidthUnit) -> Self {
+        match unit {
+            WidthUnit::Chars => WidthMode::Chars,
+            WidthUnit::Columns => WidthMode::Columns,
+        }
+    }
+}
+
+/// `--line-cut` values; see [`LineCut`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub(crate) enum CutPart {
+    Start,
+    Middle,
+    End,
+}
+
+impl From<CutPart> for LineCut {
+    fn from(part: CutPart) -> Self {
+        match part {
+            CutPart::Start => LineCut::Start,
+            CutPart::Middle => LineCut::Middle,
+            CutPart::End => LineCut::End,
+        }
+    }
+}
+
+/// `--markers` values; see [`MarkerStyle`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub(crate) enum Markers {
+    Verbose,
+    Compact,
+}
+
+impl From<Markers> for MarkerStyle {
+    fn from(markers: Markers) -> Self {
+        match markers {
+            Markers::Verbose => MarkerStyle::Verbose,
+            Markers::Compact => MarkerStyle::Compact,
+        }
+    }
+}
+
+/// `--timestamps` values; see [`Timestamps`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub(crate) enum StampKind {
+    Wall,
+    Relative,
+}
+
+impl From<StampKind> for Timestamps {
+    fn from(kind: StampKind) -> Self {
+        match kind {
+            StampKind::Wall => Timestamps::Wall,
+            StampKind::Relative => Timestamps::Relative,
+        }
+    }
+}
+
+/// `--normalize-timestamps` values; see [`TimestampRewrite`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub(crate) enum Normalization {
+    Relative,
+}
+
+impl From<Normalization> for TimestampRewrite {
+    fn from(kind: Normalization) -> Self {
+        match kind {
+            Normalization::Relative => TimestampRewrite::Relative,
+        }
+    }
+}
+
+/// `--lang` values; see [`Lang`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum Language {
+    Rust,
+}
+
+impl From<Language> for Lang {
+    fn from(language: Language) -> Self {
+        match language {
+            Language::Rust => Lang::Rust,
+        }
+    }
+}
+
+/// `--stderr` values.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub(crate) enum StderrMode {
+    /// Interleave with stdout, each line prefixed `[stderr] `
+    Merge,
+    /// Truncate on its own, with its own budgets, to trunc's stderr
+    Separate,
+    /// Leave it to the terminal, untouched
+    Pass,
+}
+
+/// `--engine` values.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub(crate) enum Engine {
+    /// The regex crate: linear time, no look-around or backreferences
+    Rust,
+    /// PCRE2: look-around and backreferences, with backtracking
+    Pcre2,
+}
+
+/// `--rank-by` values.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub(crate) enum RankBy {
+    /// Severity ladder (see --severity)
+    Severity,
+}
+
+/// `--color` values.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub(crate) enum ColorChoice {
+    Auto,
+    Always,
+    Never,
+}
+
+impl ColorChoice {
+    /// Whether to color the output. See <https://no-color.org>.
+    pub(crate) fn enabled(self) -> bool {
+        match self {
+            ColorChoice::Always => true,
+            ColorChoice::Never => false,
+            ColorChoice::Auto => {
+                io::stdout().is_terminal()
+                    && std::env::var_os("NO_COLOR").is_none_or(|v| v.is_empty())
+            }
+        }
+    }
+}
+
+impl Args {
+    /// Whether to strip escape sequences from the input: asked for, or
+    /// output isn't going to a terminal that would render them.
+    pub(crate) fn strip_ansi(&self) -> bool {
+        self.strip_ansi || (!self.keep_ansi && !io::stdout().is_terminal())
+    }
+
+    /// `--flush`, or by default every line on a terminal, where someone
+    /// may be watching, and in periodic blocks anywhere else.
+    pub(crate) fn flush_policy(&self) -> FlushPolicy {
+        self.flush.unwrap_or_else(|| {
+            if io::stdout().is_terminal() {
+                FlushPolicy::Line
+            } else {
+                FlushPolicy::Interval(DEFAULT_FLUSH_INTERVAL)
+            }
+        })
+    }
+
+    /// How inputs' lines are read: `--binary-safe` and `--max-line-bytes`.
+    pub(crate) fn reading(&self) -> Reading {
+        Reading {
+            lossy: self.binary_safe,
+            cut: self.max_line_bytes.map(|max| Cut::new(max, self.width)),
+            raw: self.save.is_some()
+                || self.save_auto
+                || self.tee.is_some()
+                || self.record.is_some(),
+        }
+    }
+
+    /// Whether `-f` or `-l` is a percentage, or `--middle` is given, so
+    /// each input's line count must be known before truncating it.
+    pub(crate) fn relative_sizes(&self) -> bool {
+        matches!(self.first, Size::Percent(_))
+            || matches!(self.last, Size::Percent(_))
+            || self.middle.is_some()
+    }
+
+    /// The library settings these options describe. Percentage sizes are
+    /// left at 0 here; see [`Args::sized`].
+    pub(crate) fn truncator(&self) -> Truncator {
+        let records = self.separator().is_some();
+        let (first, last, matches) = self.sizes(0);
+        let mut truncator = Truncator::new()
+            .first(first)
+            .header(self.keep_header.unwrap_or(0))
+            .last(last)
+            .matches(matches)
+            // Without a tail, nothing can take what matches leave
+            .rebalance(self.rebalance && !self.no_tail)
+            .before_context(self.before_context.unwrap_or(self.context))
+            .after_context(self.after_context.unwrap_or(self.context))
+            .width(self.width)
+            .width_mode(self.width_mode.into())
+            .line_cut(self.line_cut.into())
+            .json_values(self.json_values)
+            .table(self.table)
+            .summarize(self.summarize)
+            .marker_style(self.markers.into())
+            .flush(self.flush_policy());
+        if let Some(n) = self.first_bytes {
+            truncator = truncator.first_bytes(n);
+        }
+        if let Some(n) = self.last_bytes {
+            truncator = truncator.last_bytes(n);
+        }
+        if let Some(k) = self.tail_matches {
+            truncator = truncator.tail_matches(k);
+        }
+        if self.auto {
+            // Your own patterns are labelled by their text, detectors by name
+            let (names, patterns): (Vec<&str>, Vec<&str>) = self
+                .patterns
+                .iter()
+                .map(|p| (p.as_str(), p.as_str()))
+                .chain(detectors::DETECTORS.iter().copied())
+                .unzip();
+            truncator = truncator.named_patterns(compile_regex_set(&patterns, records), names);
+        } else if !self.patterns.is_empty() {
+            truncator = if self.fixed_string {
+                truncator.fixed_strings(&self.patterns)
+            } else if self.glob {
+                // Labelled by the globs given, not the regexes they became
+                let regexes: Vec<String> =
+                    self.patterns.iter().map(|g| glob::to_regex(g)).collect();
+                truncator.named_patterns(compile_regex_set(&regexes, records), &self.patterns)
+            } else if self.engine == Engine::Pcre2 {
+                truncator.engine_patterns(compile_pcre2(&self.patterns, records))
+            } else {
+                truncator.patterns(compile_regex_set(&self.patterns, records))
+            };
+        } else if let Some(group) = [&self.any_of, &self.all_of]
+            .into_iter()
+            .find(|g| !g.is_empty())
+        {
+            // Without other patterns, a group's lines are the matches
+            truncator = truncator.patterns(compile_regex_set(group, records));
+        }
+        if let Some(template) = &self.unique_by {
+            truncator = truncator.unique_by(template);
+        }
+        let mut filter = Vec::new();
+        if !self.all_of.is_empty() {
+            filter.push(Predicate::all_of(compile_regexes(&self.all_of, records)));
+        }
+        if !self.any_of.is_empty() {
+            filter.push(Predicate::any_of(compile_regexes(&self.any_of, records)));
+        }
+        if !self.and_not.is_empty() {
+            filter.push(Predicate::any_of(compile_regexes(&self.and_not, records)).negate());
+        }
+        if !filter.is_empty() {
+            truncator = truncator.match_filter(Predicate::All(filter));
+        }
+        if let Some(pattern) = &self.context_until {
+            truncator = truncator.context_until(compile_regex(pattern));
+        }
+        if self.match_limits.iter().any(Option::is_some) {
+            // --auto's detectors come after, sharing -m
+            truncator = truncator.match_limits(self.match_limits.iter().copied());
+        }
+        if !self.exclude.is_empty() {
+            truncator = truncator.exclude(compile_regex_set(&self.exclude, records));
+        }
+        if let Some(since) = self.since {
+            truncator = truncator.since(since);
+        }
+        if let Some(until) = self.until {
+            truncator = truncator.until(until);
+        }
+        if let Some(every) = self.sample_per {
+            truncator = truncator.sample_per(every);
+        }
+        if !self.always.is_empty() {
+            truncator = truncator.always(compile_regex_set(&self.always, records));
+        }
+        for range in &self.lines {
+            truncator = truncator.lines(range.clone());
+        }
+        if self.table {
+            truncator = truncator.lines(1..=1);
+        }
+        if let Some(k) = self.sample {
+            truncator = truncator.sample(k);
+        }
+        if let Some(seed) = self.seed {
+            truncator = truncator.seed(seed);
+        }
+        if let Some(n) = self.every {
+            truncator = truncator.every(n);
+        }
+        if let Some(k) = self.longest {
+            truncator = truncator.longest(k);
+        }
+        for around in &self.around {
+            let before = around
+                .context
+                .unwrap_or(self.before_context.unwrap_or(self.context));
+            let after = around
+                .context
+                .unwrap_or(self.after_context.unwrap_or(self.context));
+            truncator =
+                truncator.lines(around.line.saturating_sub(before).max(1)..=around.line + after);
+        }
+        if self.redact || !self.redact_patterns.is_empty() {
+            let builtin = if self.redact {
+                Redactor::builtin()
+            } else {
+                Redactor::new()
+            };
+            let redactor = self
+                .redact_patterns
+                .iter()
+                .fold(builtin, |r, p| r.pattern(compile_regex(p)));
+            truncator = truncator.redact(redactor);
+        }
+        truncator = self
+            .fields
+            .iter()
+            .fold(truncator, |t, name| t.field(name.as_str()));
+        if self.dedup_fuzzy {
+            let normalizer = self
+                .dedup_strip
+                .iter()
+                .fold(Normalizer::fuzzy(), |n, p| n.rule(compile_regex(p)));
+            truncator = truncator.dedup(normalizer);
+        }
+        if let Some(RankBy::Severity) = self.rank_by {
+            let selection = if self.severity.is_empty() {
+                Selection::severity()
+            } else {
+                Selection::Severity(self.severity.iter().map(|p| compile_regex(p)).collect())
+            };
+            truncator = truncator.select(selection);
+        } else if self.spread {
+            truncator = truncator.select(Selection::Spread);
+        }
+        truncator = truncator
+            .label_matches(self.label || self.auto)
+            .count_only(self.count)
+            .collapse_progress(!self.keep_progress)
+            .fold_tracebacks(self.fold_tracebacks || self.auto)
+            .strip_ansi(self.strip_ansi())
+            .line_numbers(self.line_numbers)
+            .match_line_numbers(self.match_line_numbers)
+            .region_ids(self.region_ids)
+            .byte_ranges(self.byte_ranges)
+            .idempotent(!self.no_idempotent)
+            .elapsed(self.elapsed)
+            .records(records)
+            .block_aware(self.block_aware || self.lang.is_some())
+            .track_regions(self.stats_json.is_some())
+            .color(self.color.enabled());
+        if let Some(max_bytes) = self.max_bytes {
+            truncator = truncator.max_bytes(max_bytes);
+        }
+        if let Some(max_memory) = self.max_memory {
+            truncator = truncator.max_memory(max_memory);
+        }
+        if let Some(n) = self.width_head {
+            truncator = truncator.head_width(n);
+        }
+        if let Some(n) = self.width_match {
+            truncator = truncator.match_width(n);
+        }
+        if let Some(n) = self.width_tail {
+            truncator = truncator.tail_width(n);
+        }
+        if let Some(kind) = self.timestamps {
+            truncator = truncator.timestamps(kind.into());
+        }
+        if self.strip_timestamps {
+            truncator = truncator.rewrite_timestamps(TimestampRewrite::Strip);
+        } else if let Some(kind) = self.normalize_timestamps {
+            truncator = truncator.rewrite_timestamps(kind.into());
+        }
+        if self.only_if_needed && !self.always_truncate {
+            // A screenful less the line the prompt comes back on
+            let screen = terminal::stdout_rows().map_or(0, |rows| rows - 1);
+            truncator = truncator.pass_through(screen);
+        }
+        if let Some(kind) = self.trailer {
+            truncator = truncator.trailer(kind.into());
+        }
+        if let Some(lang) = self.lang {
+            truncator = truncator.lang(lang.into());
+        }
+        truncator
+    }
+
+    /// What ends a record, with `--separator` or `--paragraph`.
+    pub(crate) fn separator(&self) -> Option<Separator> {
+        match (&self.separator, self.paragraph) {
+            (Some(line), _) => Some(Separator::Line(line.clone())),
+            (None, true) => Some(Separator::Blank),
+            (None, false) => None,
+        }
+    }
+
+    /// Whether lines can be skipped unread as far as options outside the
+    /// library go: gates, recording, saving, stats and records see every
+    /// line.
+    pub(crate) fn can_skip_lines(&self) -> bool {
+        self.separator().is_none()
+            && self.require.is_empty()
+            && self.forbid.is_empty()
+            && self.record.is_none()
+            && self.save.is_none()
+            && !self.save_auto
+            && self.tee.is_none()
+            && !self.stats
+            && self.stats_json.is_none()
+            && !self.level_histogram
+            && !self.plan
+    }
+
+    /// `truncator` with percentage sizes and `--middle` resolved for an
+    /// input of `total` lines.
+    pub(crate) fn sized(&self, truncator: &Truncator, total: usize) -> Truncator {
+        let (first, last, matches) = self.sizes(total);
+        let sized = truncator.clone().first(first).last(last).matches(matches);
+        match self.middle {
+            Some(n) if total > 0 => {
+                // An odd line out goes after, as the centre rounds down
+                let start = total.saturating_sub(n) / 2 + 1;
+                sized.lines(start..=(start + n - 1).min(total))
+            }
+            _ => sized,
+        }
+    }
+
+    /// Head, tail and match counts for an input of `total` lines. `--no-head`
+    /// and `--no-tail` empty a section; under `--rebalance` its lines go to
+    /// the other one, or to more matches if both are empty.
+    pub(crate) fn sizes(&self, total: usize) -> (usize, usize, usize) {
+        let (first, last) = (self.first.of(total), self.last.of(total));
+        let per_match = self.before_context.unwrap_or(self.context)
+            + self.after_context.unwrap_or(self.context)
+            + 1;
+        match (self.no_head, self.no_tail, self.rebalance) {
+            (true, true, true) => (0, 0, self.matches + (first + last) / per_match),
+            (true, false, true) => (0, first + last, self.matches),
+            (false, true, true) => (first + last, 0, self.matches),
+            (no_head, no_tail, _) => (
+                if no_head { 0 } else { first },
+                if no_tail { 0 } else { last },
+                self.matches,
+            ),
+        }
+    }
+}
+
+/// Split a match budget off the end of one of several `-e` patterns:
+/// `ERROR:5` is `ERROR`, showing at most 5 matches. An escaped `\:` isn't
+/// one, so `port\:80` matches `port:80`; under `--fixed-string` (`fixed`)
+/// the backslash is dropped, as it only means something here.
+pub(crate) fn split_limit(pattern: &mut String, fixed: bool) -> Option<usize> {
+    let (rest, n) = pattern.rsplit_once(':')?;
+    if n.is_empty() || !n.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    if rest.ends_with('\\') {
+        if fixed {
+            pattern.remove(rest.len() - 1);
+        }
+        return None;
+    }
+    let n = n.parse().ok()?;
+    pattern.truncate(rest.len());
+    Some(n)
+}
+
+/// Parse a `--max-per` like `WARN=2`: the pattern is everything up to the
+/// last `=`.
+pub(crate) fn parse_max_per(s: &str) -> Result<(String, usize), String> {
+    match s.rsplit_once('=') {
+        Some((pattern, n)) if !pattern.is_empty() => n
+            .trim()
+            .parse()
+            .map(|n| (pattern.to_string(), n))
+            .map_err(|_| format!("invalid match count: {}", s)),
+        _ => Err(format!("expected PATTERN=N: {}", s)),
+    }
+}
+
+/// Parse a count that must be at least 1.
+pub(crate) fn parse_positive(s: &str) -> Result<usize, String> {
+    match s.trim().parse::<usize>() {
+        Ok(n) if n > 0 => Ok(n),
+        _ => Err(format!("expected a number of at least 1: {}", s)),
+    }
+}
+
+/// How often output is flushed by default when stdout isn't a terminal:
+/// rarely enough to batch a fast producer's lines into few writes, often
+/// enough that a reader sees them come in.
+pub(crate) const DEFAULT_FLUSH_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Parse a `--flush` policy: `line`, `block` or `interval=MS`.
+pub(crate) fn parse_flush(s: &str) -> Result<FlushPolicy, String> {
+    match s {
+        "line" => Ok(FlushPolicy::Line),
+        "block" => Ok(FlushPolicy::Block),
+        _ => match s.strip_prefix("interval=").map(str::parse::<u64>) {
+            Some(Ok(ms)) if ms > 0 => Ok(FlushPolicy::Interval(Duration::from_millis(ms))),
+            _ => Err(format!(
+                "expected line, block or interval=MS (MS at least 1): {}",
+                s
+            )),
+        },
+    }
+}
+
+/// Parse a duration like `500ms`, `2s`, `1.5s` or `5m`. A bare number is seconds.
+pub(crate) fn parse_duration(s: &str) -> Result<Duration, String> {
+    let (number, unit) = match s.find(|c: char| c.is_ascii_alphabetic()) {
+        Some(i) => s.split_at(i),
+        None => (s, "s"),
+    };
+    let value: f64 = number
+        .trim()
+        .parse()
+        .map_err(|_| format!("invalid duration: {}", s))?;
+    let seconds = match unit {
+        "ms" => value / 1000.0,
+        "s" => value,
+        "m" => value * 60.0,
+        "h" => value * 3600.0,
+        "d" => value * 86400.0,
+        _ => {
+            return Err(format!(
+                "unknown duration unit '{}' (use ms, s, m, h or d)",
+                unit
+            ))
+        }
+    };
+    if !seconds.is_finite() || seconds <= 0.0 {
+        return Err(format!("duration must be positive: {}", s));
+    }
+    Ok(Duration::from_secs_f64(seconds))
+}
+
+/// Parse a time for --since or --until: a duration ago, with its unit
+/// (`5m`, `2h`), or a timestamp as [`parse_timestamp`] reads them.
+pub(crate) fn parse_time(s: &str) -> Result<SystemTime, String> {
+    if s.ends_with(|c: char| c.is_ascii_alphabetic()) {
+        if let Ok(ago) = parse_duration(s) {
+            return Ok(SystemTime::now() - ago);
+        }
+    }
+    parse_timestamp(s).ok_or_else(|| {
+        format!(
+            "invalid time: {} (use a duration ago like 5m, or a timestamp like 2024-05-01T12:00:00Z)",
+            s
+        )
+    })
+}
+
+/// Parse a byte size like `4096`, `4K` or `4KB`. As with `head -c`, `K`,
+/// `M` and `G` are powers of 1024 and `KB`, `MB` and `GB` powers of 1000.
+pub(crate) fn parse_bytes(s: &str) -> Result<usize, String> {
+    let (number, unit) = match s.find(|c: char| c.is_ascii_alphabetic()) {
+        Some(i) => s.split_at(i),
+        None => (s, ""),
+    };
+    let value: usize = number
+        .trim()
+        .parse()
+        .map_err(|_| format!("invalid byte size: {}", s))?;
+    let multiplier: usize = match unit {
+        "" => 1,
+        "K" => 1 << 10,
+        "M" => 1 << 20,
+        "G" => 1 << 30,
+        "KB" => 1000,
+        "MB" => 1000 * 1000,
+        "GB" => 1000 * 1000 * 1000,
+        _ => {
+            return Err(format!(
+                "unknown size unit '{}' (use K, M, G, KB, MB or GB)",
+                unit
+            ))
+        }
+    };
+    value
+        .checked_mul(multiplier)
+        .ok_or_else(|| format!("byte size too large: {}", s))
+}
+
+/// Compile a user-supplied regex, exiting with an error if it's invalid.
+pub(crate) fn compile_regex(pattern: &str) -> Regex {
+    match Regex::new(pattern) {
+        Ok(re) => re,
+        Err(e) => {
+            eprintln!("Invalid regex pattern: {}", e);
+            process::exit(1);
+        }
+    }
+}
+
+/// Compile patterns one by one, exiting with an error if any is invalid.
+/// For records, `^` and `$` match at each line of the record.
+pub(crate) fn compile_regexes(patterns: &[String], records: bool) -> Vec<Regex> {
+    patterns
+        .iter()
+        .map(|p| {
+            RegexBuilder::new(p)
+                .multi_line(records)
+                .build()
+                .unwrap_or_else(|e| {
+                    eprintln!("Invalid regex pattern: {}", e);
+                    process::exit(1);
+                })
+        })
+        .collect()
+}
+
+/// Compile `-e` patterns into one set, exiting with an error if any is
+/// invalid. For records, `^` and `$` match at each line of the record.
+pub(crate) fn compile_regex_set<S: AsRef<str>>(patterns: &[S], records: bool) -> RegexSet {
+    RegexSetBuilder::new(patterns)
+        .multi_line(records)
+        .build()
+        .unwrap_or_else(|e| {
+            // Only now compiled one by one, to name the pattern at fault
+            for pattern in patterns {
+                compile_regex(pattern.as_ref());
+            }
+            eprintln!("Invalid regex pattern: {}", e);
+            process::exit(1);
+        })
+}
+
+/// Compile patterns with PCRE2 (`--engine pcre2`), exiting with an error
+/// if any is invalid. For records, `^` and `$` match at each line of the
+/// record.
+#[cfg(feature = "pcre2")]
+pub(crate) fn compile_pcre2(patterns: &[String], records: bool) -> Vec<pcre2::bytes::Regex> {
+    patterns
+        .iter()
+        .map(|p| {
+            pcre2::bytes::RegexBuilder::new()
+                .utf(true)
+                .ucp(true)
+                .jit_if_available(true)
+                .multi_line(records)
+                .build(p)
+                .unwrap_or_else(|e| {
+                    eprintln!("Invalid regex pattern: {}", e);
+                    process::exit(1);
+                })
+        })
+        .collect()
+}
+
+/// Without the pcre2 feature there's no PCRE2 to compile with.
+#[cfg(not(feature = "pcre2"))]
+pub(crate) fn compile_pcre2(_: &[String], _: bool) -> Vec<Regex> {
+    eprintln!("trunc: --engine pcre2: this build of trunc has no PCRE2 (cargo feature `pcre2`)");
+    process::exit(1);
+}
+
+/// Split positional operands into the pattern and the inputs to read.
+///
+/// As with grep, the first operand is the pattern and the rest are files
+/// (`-` for stdin), so `trunc ERROR` and `trunc ERROR big.log` work; with
+/// `-e` or `--files`, every operand is a file. Whether a file exists
+/// doesn't matter: one that doesn't is reported when it's opened. When
+/// wrapping a command, the only input is its output, so an operand is
+/// always the pattern. The resolved pattern is added to `args.patterns`.
+/// Match budgets (`-e ERROR:5 -e WARN:2`, or `--max-per`) go into
+/// `args.match_limits`.
+pub(crate) fn resolve_operands(args: &mut Args) -> Vec<Input> {
+    let explicit_pattern = !args.patterns.is_empty() || args.files;
+    // With a single pattern, a `:N` is just part of it
+    if args.patterns.len() > 1 {
+        let fixed = args.fixed_string;
+        args.match_limits = args
+            .patterns
+            .iter_mut()
+            .map(|pattern| split_limit(pattern, fixed))
+            .collect();
+    }
+    let wrapping = !args.command.is_empty();
+    let mut inputs = Vec::new();
+    for operand in std::mem::take(&mut args.operands) {
+        if wrapping {
+            if !args.patterns.is_empty() {
+                eprintln!(
+                    "trunc: {}: a wrapped command's output is the only input; give one pattern",
+                    operand
+                );
+                process::exit(1);
+            }
+            args.patterns.push(operand);
+        } else if explicit_pattern || !args.patterns.is_empty() {
+            inputs.push(Input::from_path(&operand));
+        } else {
+            args.patterns.push(operand);
+        }
+    }
+    for (pattern, n) in &args.max_per {
+        let Some(i) = args.patterns.iter().position(|p| p == pattern) else {
+            eprintln!(
+                "trunc: --max-per: {}: not one of the patterns given",
+                pattern
+            );
+            process::exit(1);
+        };
+        args.match_limits.resize(args.patterns.len(), None);
+        args.match_limits[i] = Some(*n);
+    }
+    inputs
+}
+
+/// Join a flag whose value is optional to the word after it, when that
+/// word is one of its values: clap only takes such a value after `=`, so
+/// `--timestamps relative`, `--stats-json 3` or `--keep-header 3` would
+/// otherwise make the word the pattern.
+/// Nothing after `--` is touched.
+pub(crate) fn attach_optional_values(argv: impl IntoIterator<Item = OsString>) -> Vec<OsString> {
+    let mut argv = argv.into_iter().peekable();
+    let mut joined = Vec::new();
+    while let Some(arg) = argv.next() {
+        if arg == "--" {
+            joined.push(arg);
+            joined.extend(argv);
+            break;
+        }
+        let takes = |word: &str| match arg.to_str() {
+            Some("--timestamps") => StampKind::from_str(word, false).is_ok(),
+            Some("--stats-json") => word.parse::<u32>().is_ok(),
+            Some("--keep-header") => word.parse::<usize>().is_ok(),
+            _ => false,
+        };
+        let value = argv
+            .peek()
+            .and_then(|next| next.to_str())
+            .filter(|word| takes(word))
+            .map(str::to_owned);
+        let mut arg = arg;
+        if let Some(value) = value {
+            argv.next();
+            arg.push("=");
+            arg.push(value);
+        }
+        joined.push(arg);
+    }
+    joined
+}
@@ -0,0 +1,73 @@
+//! `--watch`: waiting for a file to change, by filesystem notifications, so
+//! it can be truncated again. A build that rewrites its log usually does it
+//! in a burst of writes (or by renaming a new file over it), so changes
+//! close together are waited out and count as one.
+
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::time::Duration;
+
+use notify::{EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+
+/// How long a file has to stay unchanged before a change counts as over.
+const SETTLE: Duration = Duration::from_millis(100);
+
+/// Changes to one file.
+pub struct Changes {
+    /// Kept for as long as its events are wanted
+    _watcher: RecommendedWatcher,
+    events: Receiver<notify::Result<notify::Event>>,
+    name: OsString,
+}
+
+impl Changes {
+    /// Start watching the file at `path`. Its directory is what's watched,
+    /// so the file can be deleted, created again or renamed over.
+    pub fn watch(path: &Path) -> notify::Result<Self> {
+        let dir = match path.parent() {
+            Some(dir) if !dir.as_os_str().is_empty() => dir.to_path_buf(),
+            _ => PathBuf::from("."),
+        };
+        let name = path.file_name().map(OsString::from).unwrap_or_default();
+        let (tx, rx) = mpsc::channel();
+        let mut watcher = notify::recommended_watcher(tx)?;
+        watcher.watch(&dir, RecursiveMode::NonRecursive)?;
+        Ok(Changes {
+            _watcher: watcher,
+            events: rx,
+            name,
+        })
+    }
+
+    /// Wait until the file has changed and then settled. False if the
+    /// watcher has stopped, so no change will ever come.
+    pub fn wait(&self) -> bool {
+        loop {
+            match self.events.recv() {
+                Ok(event) if self.concerns(&event) => break,
+                Ok(_) => {}
+                Err(_) => return false,
+            }
+        }
+        loop {
+            match self.events.recv_timeout(SETTLE) {
+                Ok(_) => {}
+                Err(RecvTimeoutError::Timeout) => return true,
+                Err(RecvTimeoutError::Disconnected) => return false,
+            }
+        }
+    }
+
+    /// Whether `event` changed the file: reading it doesn't.
+    fn concerns(&self, event: &notify::Result<notify::Event>) -> bool {
+        let Ok(event) = event else {
+            return false;
+        };
+        !matches!(event.kind, EventKind::Access(_))
+            && event
+                .paths
+                .iter()
+                .any(|p| p.file_name() == Some(self.name.as_os_str()))
+    }
+}
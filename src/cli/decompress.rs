@@ -0,0 +1,99 @@
+//! Reading compressed inputs through their decoder, so `trunc app.log.gz
+//! ERROR` just works: files named with a known suffix always are, and with
+//! `--decompress` anything whose first bytes say it's compressed. Each
+//! decoder is behind a cargo feature of its own (all on by default).
+
+use std::io::{self, BufRead};
+use std::path::Path;
+
+/// A compression format trunc can read.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Compression {
+    Gzip,
+    Zstd,
+    Xz,
+}
+
+impl Compression {
+    /// The format `path`'s suffix names, if any: `.gz`, `.zst` or `.xz`.
+    pub fn from_path(path: &Path) -> Option<Self> {
+        match path.extension()?.to_str()? {
+            "gz" => Some(Compression::Gzip),
+            "zst" => Some(Compression::Zstd),
+            "xz" => Some(Compression::Xz),
+            _ => None,
+        }
+    }
+
+    /// The format data starting with `start` is in, by its magic number.
+    pub fn sniff(start: &[u8]) -> Option<Self> {
+        if start.starts_with(&[0x1f, 0x8b]) {
+            Some(Compression::Gzip)
+        } else if start.starts_with(&[0x28, 0xb5, 0x2f, 0xfd]) {
+            Some(Compression::Zstd)
+        } else if start.starts_with(&[0xfd, b'7', b'z', b'X', b'Z', 0x00]) {
+            Some(Compression::Xz)
+        } else {
+            None
+        }
+    }
+
+    /// The cargo feature with this format's decoder.
+    fn feature(self) -> &'static str {
+        match self {
+            Compression::Gzip => "gzip",
+            Compression::Zstd => "zstd",
+            Compression::Xz => "xz",
+        }
+    }
+
+    /// `reader`'s data decompressed. Concatenated streams (as `cat a.gz
+    /// b.gz` makes) are read one after another. An error if trunc was
+    /// built without this format's feature.
+    pub fn decoder<R: BufRead + Send + 'static>(
+        self,
+        reader: R,
+    ) -> io::Result<Box<dyn BufRead + Send>> {
+        match self {
+            #[cfg(feature = "gzip")]
+            Compression::Gzip => Ok(Box::new(io::BufReader::new(
+                flate2::bufread::MultiGzDecoder::new(reader),
+            ))),
+            #[cfg(feature = "zstd")]
+            Compression::Zstd => Ok(Box::new(io::BufReader::new(
+                zstd::stream::read::Decoder::with_buffer(reader)?,
+            ))),
+            #[cfg(feature = "xz")]
+            Compression::Xz => Ok(Box::new(io::BufReader::new(
+                xz2::bufread::XzDecoder::new_multi_decoder(reader),
+            ))),
+            #[allow(unreachable_patterns)]
+            format => {
+                drop(reader);
+                Err(io::Error::new(
+                    io::ErrorKind::Unsupported,
+                    format!(
+                        "compressed input, but trunc was built without the `{}` feature",
+                        format.feature()
+                    ),
+                ))
+            }
+        }
+    }
+}
+
+/// `reader` through the decoder for `format`, or if that's None, for the
+/// format its first bytes are in; as it is if they aren't compressed.
+pub fn open<R: BufRead + Send + 'static>(
+    mut reader: R,
+    format: Option<Compression>,
+) -> io::Result<Box<dyn BufRead + Send>> {
+    let format = match format {
+        Some(format) => Some(format),
+        None => Compression::sniff(reader.fill_buf()?),
+    };
+    match format {
+        Some(format) => format.decoder(reader),
+        None => Ok(Box::new(reader)),
+    }
+}
@@ -0,0 +1,24 @@
+//! Built-in detectors for `--auto`: high-signal regexes for the ways runs
+//! usually fail, so the failure is pulled out of the middle even when
+//! nobody knew what to grep for.
+//!
+//! Each has a short name, which match markers show instead of the regex.
+
+/// (name, regex) of every detector.
+pub const DETECTORS: &[(&str, &str)] = &[
+    // Rust `panicked at`, Go `panic:`
+    ("panic", r"panicked at|^panic: "),
+    // `error:`, `error[E0308]:`, `fatal:`, `Error:`
+    ("error", r"(?i)\b(error|fatal)(\[[^\]]*\])?:"),
+    ("traceback", r"^Traceback \(most recent call last\)"),
+    ("exception", r"\b\w+Exception\b"),
+    ("assertion", r"(?i)assertion\b.*\bfailed|AssertionError"),
+    ("failed", r"\bFAIL(ED|URE)?\b"),
+    // `exited with code 1`, `returned non-zero exit status 2`, make's
+    // `*** [all] Error 2`
+    (
+        "exit status",
+        r"(?i)exit(ed)? (with )?(code|status):? [1-9]|non-zero exit|\*\*\* .*Error [1-9]",
+    ),
+    ("segfault", r"Segmentation fault|core dumped"),
+];
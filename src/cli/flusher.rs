@@ -0,0 +1,49 @@
+//! Buffered output for `--flush` and `--tee`. Under `interval=MS` a thread of
+//! its own flushes it every interval, so lines written just before a slow
+//! producer goes quiet still go out while the main loop waits on a read.
+
+use std::io::{self, BufWriter, Stdout, Write};
+use std::sync::{Arc, Mutex, Weak};
+use std::thread;
+use std::time::Duration;
+
+/// A writer (stdout unless said otherwise) behind a buffer shared with the
+/// flushing thread, if any.
+pub struct Flusher<W: Write + Send + 'static = Stdout>(Arc<Mutex<BufWriter<W>>>);
+
+impl Flusher {
+    /// Buffered stdout, flushed every `interval` if given.
+    pub fn start(interval: Option<Duration>) -> Self {
+        Flusher::new(io::stdout(), interval)
+    }
+}
+
+impl<W: Write + Send + 'static> Flusher<W> {
+    /// `writer` buffered, flushed every `interval` if given. The thread stops
+    /// once the `Flusher` is dropped.
+    pub fn new(writer: W, interval: Option<Duration>) -> Self {
+        let shared = Arc::new(Mutex::new(BufWriter::new(writer)));
+        if let Some(interval) = interval {
+            let weak: Weak<Mutex<BufWriter<W>>> = Arc::downgrade(&shared);
+            thread::spawn(move || loop {
+                thread::sleep(interval);
+                let Some(shared) = weak.upgrade() else {
+                    return;
+                };
+                // An error shows up again at the main thread's next flush
+                let _ = shared.lock().unwrap().flush();
+            });
+        }
+        Flusher(shared)
+    }
+}
+
+impl<W: Write + Send + 'static> Write for Flusher<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.lock().unwrap().write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.0.lock().unwrap().flush()
+    }
+}
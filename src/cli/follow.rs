@@ -0,0 +1,174 @@
+//! Follow mode: keep reading a file after EOF (like `tail -f`), and tick
+//! periodically so the rolling tail can be shown without waiting for an EOF
+//! that may never come.
+//!
+//! Lines are read on a background thread and handed over a channel, so the
+//! main loop can wake up on a timer even while the input is idle.
+
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Read};
+use std::process::ChildStderr;
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::cli::decompress;
+use crate::cli::input::{self, Event, Input, Reading};
+use crate::cli::record;
+
+/// How long to wait before checking a file for new data after EOF.
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Input events from a followed input: lines as they arrive, and a
+/// [`Event::Tick`] every `interval`.
+pub struct Follow {
+    lines: Receiver<io::Result<Event>>,
+    interval: Duration,
+    next_tick: Instant,
+}
+
+impl Follow {
+    /// Start following `input`.
+    ///
+    /// Files are polled for appended data forever. Stdin, recordings and
+    /// wrapped commands end at EOF as usual; the ticks are what make
+    /// following them useful. Lines are [`input::decode`]d.
+    pub fn start(input: Input, interval: Duration, reading: Reading) -> io::Result<Self> {
+        let (tx, rx) = mpsc::channel();
+        read(input, true, reading, tx)?;
+        Ok(Follow {
+            lines: rx,
+            interval,
+            next_tick: Instant::now() + interval,
+        })
+    }
+}
+
+impl Iterator for Follow {
+    type Item = io::Result<Event>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let now = Instant::now();
+        if now >= self.next_tick {
+            self.next_tick = now + self.interval;
+            return Some(Ok(Event::Tick));
+        }
+        match self.lines.recv_timeout(self.next_tick - now) {
+            Ok(event) => Some(event),
+            Err(RecvTimeoutError::Timeout) => {
+                self.next_tick = Instant::now() + self.interval;
+                Some(Ok(Event::Tick))
+            }
+            Err(RecvTimeoutError::Disconnected) => None,
+        }
+    }
+}
+
+/// Read `input` on background threads, sending its events to `tx`; with
+/// `follow`, a file is polled for appended data rather than ending at EOF.
+/// Opening it is done here, so a missing file is an error up front.
+pub fn read(
+    input: Input,
+    follow: bool,
+    reading: Reading,
+    tx: Sender<io::Result<Event>>,
+) -> io::Result<()> {
+    match input {
+        Input::Stdin => {
+            thread::spawn(move || send_lines(io::stdin().lock(), Event::Line, reading, tx));
+        }
+        Input::File(path) => {
+            let file = BufReader::new(File::open(path)?);
+            if follow {
+                thread::spawn(move || poll_lines(file, reading, tx));
+            } else {
+                thread::spawn(move || send_lines(file, Event::Line, reading, tx));
+            }
+        }
+        // Decompressed to the end rather than followed; the first bytes,
+        // which may be a while coming, are looked at on the thread
+        Input::Compressed { path, format } => {
+            let raw = Input::raw(path.as_deref())?;
+            thread::spawn(move || match decompress::open(raw, format) {
+                Ok(reader) => send_lines(reader, Event::Line, reading, tx),
+                Err(e) => {
+                    let _ = tx.send(Err(e));
+                }
+            });
+        }
+        Input::Recording(path) => {
+            let reader = record::open(&path)?;
+            thread::spawn(move || send_lines(reader, Event::Line, reading, tx));
+        }
+        Input::Command { stdout, stderr, .. } => read_command(stdout, stderr, reading, tx),
+    }
+    Ok(())
+}
+
+/// Read a wrapped command's stdout, and its stderr if captured, on
+/// background threads. Both pipes are drained at once so the command never
+/// stalls on a full one; lines are sent in the order they arrive.
+pub fn read_command(
+    stdout: Box<dyn Read + Send>,
+    stderr: Option<ChildStderr>,
+    reading: Reading,
+    tx: Sender<io::Result<Event>>,
+) {
+    if let Some(stderr) = stderr {
+        let tx = tx.clone();
+        thread::spawn(move || send_lines(BufReader::new(stderr), Event::Stderr, reading, tx));
+    }
+    thread::spawn(move || send_lines(BufReader::new(stdout), Event::Line, reading, tx));
+}
+
+/// Send every line until EOF.
+fn send_lines(
+    reader: impl BufRead,
+    event: fn(String) -> Event,
+    reading: Reading,
+    tx: Sender<io::Result<Event>>,
+) {
+    // Only the main stream's bytes are counted or copied, so stderr's
+    // aren't
+    let counted = matches!(event(String::new()), Event::Line(_));
+    for line in input::lines(reader, reading) {
+        let failed = line.is_err();
+        let line = match line {
+            Ok(Event::Line(line)) => Ok(event(line)),
+            Ok(Event::Size(_) | Event::Raw(_)) if !counted => continue,
+            other => other,
+        };
+        if tx.send(line).is_err() || failed {
+            return;
+        }
+    }
+}
+
+/// Send lines as they're appended to a file, waiting at EOF for more.
+///
+/// A line is only sent once its newline arrives, so a writer caught halfway
+/// through a line doesn't split it in two.
+fn poll_lines(mut reader: impl BufRead, reading: Reading, tx: Sender<io::Result<Event>>) {
+    let mut buf = Vec::new();
+    let mut ready = VecDeque::new();
+    loop {
+        match reader.read_until(b'\n', &mut buf) {
+            Ok(0) => thread::sleep(POLL_INTERVAL),
+            Ok(_) if buf.ends_with(b"\n") => {
+                input::line_events(std::mem::take(&mut buf), 0, reading, &mut ready);
+                for event in ready.drain(..) {
+                    let failed = event.is_err();
+                    if tx.send(event).is_err() || failed {
+                        return;
+                    }
+                }
+            }
+            Ok(_) => {} // partial line: keep reading into the same buffer
+            Err(e) => {
+                let _ = tx.send(Err(e));
+                return;
+            }
+        }
+    }
+}
@@ -0,0 +1,97 @@
+//! Output gates (`--require`, `--forbid`): patterns checked against every
+//! input line, truncated away or not, that fail the run.
+
+use crate::cli::args::{compile_regex, Args};
+use crate::marker;
+use crate::{truncate_line, WidthMode};
+use regex::Regex;
+
+/// A `--forbid` pattern, with the first few offending lines and a total count.
+pub(crate) struct Forbidden {
+    re: Regex,
+    /// (location, content) of the offending lines shown in the report
+    shown: Vec<(String, String)>,
+    count: usize,
+}
+
+/// Output gates (`--require`, `--forbid`), checked against every input line.
+pub(crate) struct Gates {
+    /// `--require` patterns, and whether each has been seen yet
+    required: Vec<(Regex, bool)>,
+    forbidden: Vec<Forbidden>,
+    max_shown: usize,
+}
+
+impl Gates {
+    pub(crate) fn new(args: &Args) -> Self {
+        Gates {
+            required: args
+                .require
+                .iter()
+                .map(|p| (compile_regex(p), false))
+                .collect(),
+            forbidden: args
+                .forbid
+                .iter()
+                .map(|p| Forbidden {
+                    re: compile_regex(p),
+                    shown: Vec::new(),
+                    count: 0,
+                })
+                .collect(),
+            max_shown: args.matches,
+        }
+    }
+
+    /// Check one line. `location` describes where it came from, for reports.
+    pub(crate) fn check(&mut self, content: &str, location: impl Fn() -> String) {
+        for (re, seen) in &mut self.required {
+            if !*seen && re.is_match(content) {
+                *seen = true;
+            }
+        }
+        for forbidden in &mut self.forbidden {
+            if forbidden.re.is_match(content) {
+                forbidden.count += 1;
+                if forbidden.shown.len() < self.max_shown {
+                    forbidden.shown.push((location(), content.to_string()));
+                }
+            }
+        }
+    }
+
+    /// Report failures on stderr. Returns true if any gate failed.
+    ///
+    /// Offending lines go to stderr so they're visible even if truncated away.
+    pub(crate) fn report(&self, width: usize, mode: WidthMode) -> bool {
+        let mut failed = false;
+        for (re, seen) in &self.required {
+            if !seen {
+                eprintln!("trunc: required pattern not found: {}", re.as_str());
+                failed = true;
+            }
+        }
+        for forbidden in self.forbidden.iter().filter(|f| f.count > 0) {
+            eprintln!(
+                "trunc: forbidden pattern found {}: {}",
+                marker::counted(forbidden.count, "time", "times"),
+                forbidden.re.as_str()
+            );
+            for (location, content) in &forbidden.shown {
+                eprintln!(
+                    "  {}: {}",
+                    location,
+                    truncate_line(content, width, mode).text
+                );
+            }
+            if forbidden.count > forbidden.shown.len() {
+                eprintln!(
+                    "  [... {} more ...]",
+                    forbidden.count - forbidden.shown.len()
+                );
+            }
+            failed = true;
+        }
+        failed
+    }
+}
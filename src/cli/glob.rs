@@ -0,0 +1,76 @@
+//! `--glob`: shell-style wildcard patterns (`*timeout*retry*`), translated
+//! to the regexes the rest of trunc matches with.
+//!
+//! A glob matches a whole line, as in a shell: `*` is any run of
+//! characters, `?` any one, `[abc]`, `[a-z]` and `[!abc]` a set, and `\`
+//! takes the next character as it is. Everything else is literal.
+
+/// The regex for `glob`, anchored to the whole line.
+pub fn to_regex(glob: &str) -> String {
+    let chars: Vec<char> = glob.chars().collect();
+    let mut regex = String::from("^");
+    let mut i = 0;
+    while i < chars.len() {
+        match chars[i] {
+            '*' => {
+                // A run of stars is one
+                while chars.get(i + 1) == Some(&'*') {
+                    i += 1;
+                }
+                regex.push_str(".*");
+            }
+            '?' => regex.push('.'),
+            '[' => match class(&chars[i + 1..]) {
+                Some((set, len)) => {
+                    regex.push_str(&set);
+                    i += len;
+                }
+                // Never closed: a literal `[`
+                None => regex.push_str(r"\["),
+            },
+            '\\' if i + 1 < chars.len() => {
+                i += 1;
+                push_literal(&mut regex, chars[i]);
+            }
+            c => push_literal(&mut regex, c),
+        }
+        i += 1;
+    }
+    regex.push('$');
+    regex
+}
+
+/// The regex class for a glob set, from `rest`, the glob after its `[`,
+/// and how much of `rest` the set takes; `None` if it's never closed.
+fn class(rest: &[char]) -> Option<(String, usize)> {
+    let mut set = String::from("[");
+    let mut i = 0;
+    if matches!(rest.first(), Some('!' | '^')) {
+        set.push('^');
+        i = 1;
+    }
+    // A `]` straight after the `[` is a member, not the end
+    let start = i;
+    loop {
+        let c = *rest.get(i)?;
+        if c == ']' && i > start {
+            break;
+        }
+        match c {
+            // Literal in a glob set, but special in a regex class
+            '\\' | '[' | ']' | '&' | '~' | '^' => {
+                set.push('\\');
+                set.push(c);
+            }
+            c => set.push(c),
+        }
+        i += 1;
+    }
+    set.push(']');
+    Some((set, i + 1))
+}
+
+fn push_literal(regex: &mut String, c: char) {
+    let mut buf = [0; 4];
+    regex.push_str(&regex::escape(c.encode_utf8(&mut buf)));
+}
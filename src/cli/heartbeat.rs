@@ -0,0 +1,73 @@
+//! `--heartbeat`: a status line on stderr while trunc reads a slow
+//! producer, so neither a human nor an agent mistakes the quiet middle
+//! section for a hang.
+
+use std::io::{self, IsTerminal, Write};
+
+use crate::marker;
+
+/// The status line. On a terminal it's rewritten in place; otherwise each
+/// beat is a line of its own.
+pub struct Heartbeat {
+    terminal: bool,
+    /// Stdout is the same kind of terminal, so output lines would land on
+    /// the end of the status line
+    shares_terminal: bool,
+    /// The status line is on screen, unfinished
+    shown: bool,
+}
+
+impl Heartbeat {
+    pub fn new() -> Self {
+        let terminal = io::stderr().is_terminal();
+        Heartbeat {
+            terminal,
+            shares_terminal: terminal && io::stdout().is_terminal(),
+            shown: false,
+        }
+    }
+
+    /// `[trunc] still reading… 1.2M lines, 3 matches so far`, with matches
+    /// only given with a pattern.
+    pub fn beat(&mut self, lines: usize, matches: Option<usize>) {
+        let mut status = format!("[trunc] still reading\u{2026} {} lines", abbreviate(lines));
+        if let Some(matches) = matches {
+            let matches = marker::counted(matches, "match", "matches");
+            status.push_str(&format!(", {} so far", matches));
+        }
+        let mut err = io::stderr().lock();
+        let _ = if self.terminal {
+            self.shown = true;
+            write!(err, "\r\x1b[K{}", status)
+        } else {
+            writeln!(err, "{}", status)
+        };
+        let _ = err.flush();
+    }
+
+    /// Take the status line off the screen before output might go there.
+    pub fn clear(&mut self) {
+        if self.shown && self.shares_terminal {
+            self.finish();
+        }
+    }
+
+    /// Take the status line off the screen for good.
+    pub fn finish(&mut self) {
+        if self.shown {
+            let _ = write!(io::stderr(), "\r\x1b[K");
+            self.shown = false;
+        }
+    }
+}
+
+/// `n` to a few significant digits: `950`, `12.3K`, `1.2M`.
+fn abbreviate(n: usize) -> String {
+    const UNITS: [(f64, &str); 3] = [(1e9, "G"), (1e6, "M"), (1e3, "K")];
+    UNITS
+        .iter()
+        .find(|(size, _)| n as f64 >= *size)
+        .map_or(n.to_string(), |(size, unit)| {
+            format!("{:.1}{}", n as f64 / size, unit)
+        })
+}
@@ -0,0 +1,726 @@
+//! Input sources: stdin, files named on the command line, recordings, and
+//! the output of a wrapped command.
+
+use std::cell::Cell;
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::process::{Child, ChildStderr, Command, Stdio};
+use std::rc::Rc;
+use std::sync::mpsc;
+use std::time::Duration;
+
+use crate::cli::decompress::{self, Compression};
+use crate::cli::follow::{self, Follow};
+#[cfg(unix)]
+use crate::cli::pty;
+use crate::cli::record::{self, Recording};
+use crate::cut::Cut;
+
+/// What the truncation loop sees from an input.
+#[derive(Debug)]
+pub enum Event {
+    /// The next line, without its line ending
+    Line(String),
+    /// A line from a wrapped command's stderr, when it's captured
+    Stderr(String),
+    /// Follow mode's refresh interval elapsed
+    Tick,
+    /// Nothing arrived for `--idle-timeout`
+    Stalled,
+    /// The `--heartbeat` interval elapsed
+    Heartbeat,
+    /// The next line's size as read, ending and all, when that isn't its
+    /// text and a `\n`: it ended in `\r\n`, or had invalid UTF-8 replaced
+    Size(usize),
+    /// The next line's bytes as read, ending and all, when
+    /// [`Reading::raw`] asks for them
+    Raw(Vec<u8>),
+    /// Lines passed over unread, `bytes` of them with their line endings.
+    /// After a line cut short by `--max-line-bytes`, no lines: the bytes
+    /// cut from it
+    Skip { lines: usize, bytes: usize },
+}
+
+/// How an input's lines are read.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Reading {
+    /// Invalid UTF-8 becomes U+FFFD rather than an error (`--binary-safe`)
+    pub lossy: bool,
+    /// Cut overlong lines short as they're read (`--max-line-bytes`)
+    pub cut: Option<Cut>,
+    /// Give each line's bytes as read before it, for `--save`, `--tee` and
+    /// `--record`
+    pub raw: bool,
+}
+
+/// Read a line into `buf` as [`BufRead::read_until`] does, but cut short
+/// if `cut` says it's too long. Returns the bytes read and how many more
+/// the line had than `buf` now holds.
+fn read_line(
+    reader: &mut impl BufRead,
+    buf: &mut Vec<u8>,
+    cut: Option<Cut>,
+) -> io::Result<(usize, usize)> {
+    let Some(cut) = cut else {
+        return reader.read_until(b'\n', buf).map(|n| (n, 0));
+    };
+    let mut read = 0;
+    let mut dropped = 0;
+    loop {
+        let chunk = match reader.fill_buf() {
+            Ok(chunk) => chunk,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        };
+        let (used, done) = match chunk.iter().position(|&b| b == b'\n') {
+            Some(i) => (i + 1, true),
+            None => (chunk.len(), chunk.is_empty()),
+        };
+        buf.extend_from_slice(&chunk[..used]);
+        reader.consume(used);
+        read += used;
+        if done {
+            break;
+        }
+        dropped += cut.trim(buf);
+    }
+    Ok((read, cut.finish(buf, dropped)))
+}
+
+/// A line read up to and including its newline (if it has one), as text:
+/// the line ending is dropped, and invalid UTF-8 is an error unless `lossy`,
+/// when each bad sequence becomes U+FFFD.
+pub fn decode(mut buf: Vec<u8>, lossy: bool) -> io::Result<String> {
+    if buf.last() == Some(&b'\n') {
+        buf.pop();
+        if buf.last() == Some(&b'\r') {
+            buf.pop();
+        }
+    }
+    match String::from_utf8(buf) {
+        Ok(line) => Ok(line),
+        Err(e) if lossy => Ok(String::from_utf8_lossy(e.as_bytes()).into_owned()),
+        Err(_) => Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "stream did not contain valid UTF-8 (see --binary-safe)",
+        )),
+    }
+}
+
+/// `reader`'s lines as [`Event::Line`]s, as [`BufRead::lines`] gives them
+/// but [`decode`]d: each line not read as its text and a `\n` after an
+/// [`Event::Size`], and each cut short line followed by an [`Event::Skip`]
+/// of the bytes cut. With
+/// [`Reading::raw`], each line's [`Event::Raw`] bytes come first.
+pub fn lines(reader: impl BufRead, reading: Reading) -> impl Iterator<Item = io::Result<Event>> {
+    reused_lines(reader, reading, Spare::default())
+}
+
+/// [`lines`], each read into the buffer of the line last given back to
+/// `spare`, so reading allocates nothing once it's under way.
+pub fn reused_lines(
+    mut reader: impl BufRead,
+    reading: Reading,
+    spare: Spare,
+) -> impl Iterator<Item = io::Result<Event>> {
+    let mut ready = VecDeque::new();
+    std::iter::from_fn(move || {
+        if let Some(event) = ready.pop_front() {
+            return Some(event);
+        }
+        let mut buf = spare.take();
+        match read_line(&mut reader, &mut buf, reading.cut) {
+            Ok((0, _)) => None,
+            Ok((_, cut)) => {
+                line_events(buf, cut, reading, &mut ready);
+                ready.pop_front()
+            }
+            Err(e) => Some(Err(e)),
+        }
+    })
+}
+
+/// Queue the events for a line read into `buf` with `cut` bytes cut from
+/// it, as [`lines`] gives them.
+pub(crate) fn line_events(
+    buf: Vec<u8>,
+    cut: usize,
+    reading: Reading,
+    ready: &mut VecDeque<io::Result<Event>>,
+) {
+    if reading.raw {
+        ready.push_back(Ok(Event::Raw(buf.clone())));
+    }
+    // A last line without a newline is a byte short of its text and a `\n`
+    let read = buf.len();
+    let line = decode(buf, reading.lossy);
+    if line.as_ref().is_ok_and(|line| line.len() + 1 != read) {
+        ready.push_back(Ok(Event::Size(read)));
+    }
+    ready.push_back(line.map(Event::Line));
+    if cut > 0 {
+        ready.push_back(Ok(Event::Skip {
+            lines: 0,
+            bytes: cut,
+        }));
+    }
+}
+
+/// A line's buffer, given back once the line's been dealt with for the
+/// next to be read into by [`reused_lines`].
+#[derive(Clone, Default)]
+pub struct Spare(Rc<Cell<Option<String>>>);
+
+impl Spare {
+    pub fn give(&self, line: String) {
+        self.0.set(Some(line));
+    }
+
+    /// An empty buffer: the last one given, or a new one.
+    fn take(&self) -> Vec<u8> {
+        let mut buf = self.0.take().map(String::into_bytes).unwrap_or_default();
+        buf.clear();
+        buf
+    }
+}
+
+/// How much of a file is read at a time when looking for its tail or
+/// counting the lines before it.
+const CHUNK: usize = 64 * 1024;
+
+/// Somewhere to read lines from.
+pub enum Input {
+    Stdin,
+    File(PathBuf),
+    /// A file (or stdin, without a path) read through a decoder: `format`'s,
+    /// or with none, whichever its first bytes call for
+    Compressed {
+        path: Option<PathBuf>,
+        format: Option<Compression>,
+    },
+    /// A file written by `--record`, read back by `trunc replay`
+    Recording(PathBuf),
+    /// Output of a command run by `trunc -- cmd args`: its stdout, and its
+    /// stderr unless that's passed through
+    Command {
+        name: String,
+        stdout: Box<dyn Read + Send>,
+        stderr: Option<ChildStderr>,
+    },
+}
+
+impl Input {
+    /// A file operand, with `-` meaning stdin.
+    pub fn from_path(operand: &str) -> Input {
+        if operand == "-" {
+            Input::Stdin
+        } else {
+            Input::file(PathBuf::from(operand))
+        }
+    }
+
+    /// A file, read through a decoder if its suffix names a compression
+    /// format.
+    pub fn file(path: PathBuf) -> Input {
+        match Compression::from_path(&path) {
+            Some(format) => Input::Compressed {
+                path: Some(path),
+                format: Some(format),
+            },
+            None => Input::File(path),
+        }
+    }
+
+    /// The input read through a decoder if its first bytes say it's
+    /// compressed (`--decompress`). Only files and stdin are.
+    pub fn decompressed(self) -> Input {
+        match self {
+            Input::Stdin => Input::Compressed {
+                path: None,
+                format: None,
+            },
+            Input::File(path) => Input::Compressed {
+                path: Some(path),
+                format: None,
+            },
+            input => input,
+        }
+    }
+
+    /// Run `argv` with its stdout piped to us, and its stderr too if
+    /// `capture_stderr`. Stdin (and otherwise stderr) is inherited. The
+    /// child is returned so its exit status can be collected once its output
+    /// has been read.
+    pub fn spawn(argv: &[String], capture_stderr: bool) -> io::Result<(Input, Child)> {
+        let stderr = if capture_stderr {
+            Stdio::piped()
+        } else {
+            Stdio::inherit()
+        };
+        let mut child = Command::new(&argv[0])
+            .args(&argv[1..])
+            .stdout(Stdio::piped())
+            .stderr(stderr)
+            .spawn()?;
+        let input = Input::Command {
+            name: argv[0].clone(),
+            stdout: Box::new(child.stdout.take().expect("stdout is piped")),
+            stderr: child.stderr.take(),
+        };
+        Ok((input, child))
+    }
+
+    /// [`Input::spawn`], but with the command writing to a pseudo-terminal
+    /// (`--pty`), its stderr there too if `capture_stderr`.
+    #[cfg(unix)]
+    pub fn spawn_pty(argv: &[String], capture_stderr: bool) -> io::Result<(Input, Child)> {
+        let (master, child) = pty::spawn(argv, capture_stderr)?;
+        let input = Input::Command {
+            name: argv[0].clone(),
+            stdout: Box::new(master),
+            stderr: None,
+        };
+        Ok((input, child))
+    }
+
+    #[cfg(not(unix))]
+    pub fn spawn_pty(_argv: &[String], _capture_stderr: bool) -> io::Result<(Input, Child)> {
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "--pty is only supported on Unix",
+        ))
+    }
+
+    /// Count a file's lines with a pass of its own, counting a final line
+    /// without a newline too. None for other inputs, which can only be read
+    /// once.
+    pub fn line_count(&self) -> Option<io::Result<usize>> {
+        let reader = self.reread()?;
+        let count = || -> io::Result<usize> {
+            let mut reader = reader?;
+            let mut lines = 0;
+            let mut last = b'\n';
+            loop {
+                let buf = reader.fill_buf()?;
+                let Some(&end) = buf.last() else {
+                    break;
+                };
+                lines += buf.iter().filter(|&&b| b == b'\n').count();
+                last = end;
+                let len = buf.len();
+                reader.consume(len);
+            }
+            Ok(lines + usize::from(last != b'\n'))
+        };
+        Some(count())
+    }
+
+    /// The file read, if it's one.
+    pub fn path(&self) -> Option<&Path> {
+        match self {
+            Input::File(path)
+            | Input::Compressed {
+                path: Some(path), ..
+            } => Some(path),
+            _ => None,
+        }
+    }
+
+    /// A regular file's path: it can be read again, and in pieces. None
+    /// for other inputs, and for pipes and devices.
+    pub fn regular_file(&self) -> Option<&Path> {
+        let Input::File(path) = self else {
+            return None;
+        };
+        path.metadata()
+            .is_ok_and(|meta| meta.is_file())
+            .then_some(path.as_path())
+    }
+
+    /// A reader of its own over a file, for a pass before the main one.
+    /// None for other inputs, which can only be read once.
+    pub fn reread(&self) -> Option<io::Result<Box<dyn BufRead>>> {
+        match self {
+            Input::File(path) => {
+                Some(File::open(path).map(|f| Box::new(BufReader::new(f)) as Box<dyn BufRead>))
+            }
+            Input::Compressed {
+                path: Some(path),
+                format,
+            } => Some(
+                File::open(path)
+                    .and_then(|f| decompress::open(BufReader::new(f), *format))
+                    .map(|reader| reader as Box<dyn BufRead>),
+            ),
+            _ => None,
+        }
+    }
+
+    /// A regular file's first `head` lines, one [`Event::Skip`] for the lines
+    /// between them and the last `tail` lines, then the rest. The tail is
+    /// found by seeking back from the end (in a recording, from its line
+    /// index), and the lines between are only counted. None for other
+    /// inputs, and for files that aren't regular (pipes, devices), which
+    /// have to be streamed. Lines are [`decode`]d.
+    ///
+    /// With `markers`, a line between that could be a truncation marker
+    /// (one starting `[`, `…` or an escape) is given as a line after a
+    /// [`Event::Skip`] of those before it, so the lines it hid are counted.
+    pub fn skim(
+        &self,
+        head: usize,
+        tail: usize,
+        reading: Reading,
+        markers: bool,
+    ) -> Option<io::Result<Box<dyn Iterator<Item = io::Result<Event>>>>> {
+        let skim = |file: File, recording: Option<Recording>| -> io::Result<_> {
+            let mut reader = BufReader::with_capacity(CHUNK, file);
+            let data = recording.as_ref().map(Recording::data);
+            let pos = data.as_ref().map_or(0, |data| data.start);
+            reader.seek(SeekFrom::Start(pos))?;
+            Ok(Box::new(Skim {
+                reader,
+                recording,
+                end: data.map(|data| data.end),
+                head_left: head,
+                tail,
+                reading,
+                markers,
+                pos,
+                skipped: false,
+                middle_end: None,
+                ready: VecDeque::new(),
+            })
+                as Box<dyn Iterator<Item = io::Result<Event>>>)
+        };
+        let path = match self {
+            Input::File(path) => path,
+            Input::Recording(path) => {
+                let opened = Recording::open(path)
+                    .and_then(|r| r.file().try_clone().map(|file| (file, r)))
+                    .and_then(|(file, r)| skim(file, Some(r)));
+                return Some(opened);
+            }
+            _ => return None,
+        };
+        match path.metadata() {
+            Ok(meta) if meta.is_file() => {}
+            Ok(_) => return None,
+            Err(e) => return Some(Err(e)),
+        }
+        Some(File::open(path).and_then(|file| skim(file, None)))
+    }
+
+    pub fn open(self) -> io::Result<Box<dyn BufRead>> {
+        match self {
+            Input::Stdin => Ok(Box::new(io::stdin().lock())),
+            Input::File(path) => Ok(Box::new(BufReader::new(File::open(path)?))),
+            Input::Compressed { path, format } => {
+                Ok(decompress::open(Input::raw(path.as_deref())?, format)?)
+            }
+            Input::Recording(path) => Ok(Box::new(record::open(&path)?)),
+            Input::Command { stdout, .. } => Ok(Box::new(BufReader::new(stdout))),
+        }
+    }
+
+    /// The undecoded bytes of the file at `path`, or without one, stdin,
+    /// in a reader that can go to another thread.
+    pub fn raw(path: Option<&Path>) -> io::Result<Box<dyn BufRead + Send>> {
+        Ok(match path {
+            Some(path) => Box::new(BufReader::new(File::open(path)?)),
+            None => Box::new(BufReader::new(io::stdin())),
+        })
+    }
+
+    /// Read the input as a stream of events, with lines [`decode`]d.
+    ///
+    /// With `follow`, the input is read on a background thread (files are
+    /// followed past EOF) and a [`Event::Tick`] arrives every interval. A
+    /// wrapped command's captured stderr is always read on its own thread.
+    ///
+    /// Read directly, each line goes into the buffer last given to `spare`.
+    pub fn events(
+        self,
+        follow: Option<Duration>,
+        reading: Reading,
+        spare: &Spare,
+    ) -> io::Result<Box<dyn Iterator<Item = io::Result<Event>>>> {
+        match (follow, self) {
+            (Some(interval), input) => Ok(Box::new(Follow::start(input, interval, reading)?)),
+            (
+                None,
+                Input::Command {
+                    stdout,
+                    stderr: Some(stderr),
+                    ..
+                },
+            ) => {
+                let (tx, rx) = mpsc::channel();
+                follow::read_command(stdout, Some(stderr), reading, tx);
+                Ok(Box::new(rx.into_iter()))
+            }
+            (None, input) => Ok(Box::new(reused_lines(
+                input.open()?,
+                reading,
+                spare.clone(),
+            ))),
+        }
+    }
+
+    /// Name for messages: the path as given, or `-` for stdin.
+    pub fn name(&self) -> String {
+        match self {
+            Input::Stdin => "-".to_string(),
+            Input::File(path) | Input::Recording(path) => path.display().to_string(),
+            Input::Compressed {
+                path: Some(path), ..
+            } => path.display().to_string(),
+            Input::Compressed { path: None, .. } => "-".to_string(),
+            Input::Command { name, .. } => name.clone(),
+        }
+    }
+}
+
+/// A file's lines with the middle skipped, from [`Input::skim`].
+struct Skim {
+    reader: BufReader<File>,
+    /// The recording the file holds, if it's one
+    recording: Option<Recording>,
+    /// Where the input ends in the file, if before the end (a recording's
+    /// index follows it)
+    end: Option<u64>,
+    /// Lines still to read before skipping
+    head_left: usize,
+    tail: usize,
+    reading: Reading,
+    /// Give lines between that could be markers
+    markers: bool,
+    /// Where in the file the next line starts
+    pos: u64,
+    skipped: bool,
+    /// Where the tail starts, while between it and the head
+    middle_end: Option<u64>,
+    /// The rest of the events for the last line read
+    ready: VecDeque<io::Result<Event>>,
+}
+
+impl Skim {
+    /// Find the start of the tail, so the lines up to it can be skipped.
+    fn find_tail(&mut self) -> io::Result<()> {
+        let start = match &self.recording {
+            Some(recording) => recording.tail_start(self.tail)?,
+            None => tail_start(self.reader.get_ref(), self.tail)?,
+        };
+        self.middle_end = Some(start.max(self.pos));
+        // The file has moved under the reader: this also drops its buffer
+        self.reader.seek(SeekFrom::Start(self.pos))?;
+        Ok(())
+    }
+
+    /// The next event up to `end`: an [`Event::Skip`] of the lines before
+    /// the next that could be a marker, or that line's first. None at `end`.
+    ///
+    /// A last line without a newline (the end of the file, when there's no
+    /// tail) is counted too.
+    fn middle(&mut self, end: u64) -> io::Result<Option<Event>> {
+        let (mut lines, mut bytes) = (0, 0);
+        let mut line_start = true;
+        while self.pos < end {
+            let buf = self.reader.fill_buf()?;
+            if buf.is_empty() {
+                break; // the file shrank
+            }
+            let buf = &buf[..buf.len().min((end - self.pos) as usize)];
+            let mut i = 0;
+            while i < buf.len() {
+                if line_start && self.markers && matches!(buf[i], b'[' | 0xE2 | 0x1B) {
+                    break;
+                }
+                match buf[i..].iter().position(|&b| b == b'\n') {
+                    Some(at) => {
+                        lines += 1;
+                        i += at + 1;
+                        line_start = true;
+                    }
+                    None => {
+                        i = buf.len();
+                        line_start = false;
+                    }
+                }
+            }
+            let found = i < buf.len();
+            self.reader.consume(i);
+            self.pos += i as u64;
+            bytes += i;
+            if found {
+                break;
+            }
+        }
+        lines += usize::from(!line_start);
+        if bytes > 0 {
+            Ok(Some(Event::Skip { lines, bytes }))
+        } else if self.pos < end {
+            self.line()?;
+            self.ready.pop_front().transpose()
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Read the line at `pos`, queueing its events as [`lines`] gives
+    /// them.
+    fn line(&mut self) -> io::Result<()> {
+        let mut buf = Vec::new();
+        let left = self
+            .end
+            .map_or(u64::MAX, |end| end.saturating_sub(self.pos));
+        let mut reader = (&mut self.reader).take(left);
+        let (n, cut) = read_line(&mut reader, &mut buf, self.reading.cut)?;
+        if n > 0 {
+            self.pos += n as u64;
+            line_events(buf, cut, self.reading, &mut self.ready);
+        }
+        Ok(())
+    }
+}
+
+impl Iterator for Skim {
+    type Item = io::Result<Event>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(event) = self.ready.pop_front() {
+            return Some(event);
+        }
+        if self.head_left == 0 && !self.skipped {
+            self.skipped = true;
+            if let Err(e) = self.find_tail() {
+                return Some(Err(e));
+            }
+        }
+        if let Some(end) = self.middle_end {
+            match self.middle(end) {
+                Ok(Some(event)) => return Some(Ok(event)),
+                Ok(None) => self.middle_end = None,
+                Err(e) => return Some(Err(e)),
+            }
+        }
+        self.head_left = self.head_left.saturating_sub(1);
+        if let Err(e) = self.line() {
+            return Some(Err(e));
+        }
+        self.ready.pop_front()
+    }
+}
+
+/// Where the last `n` lines of `file` start, reading back from the end. A
+/// final newline ends the last line rather than starting another.
+fn tail_start(mut file: &File, n: usize) -> io::Result<u64> {
+    let len = file.seek(SeekFrom::End(0))?;
+    if n == 0 {
+        return Ok(len);
+    }
+    let mut buf = vec![0; CHUNK];
+    let mut newlines = 0;
+    let mut end = len;
+    while end > 0 {
+        let start = end.saturating_sub(CHUNK as u64);
+        let chunk = &mut buf[..(end - start) as usize];
+        file.seek(SeekFrom::Start(start))?;
+        file.read_exact(chunk)?;
+        for (i, &b) in chunk.iter().enumerate().rev() {
+            let at = start + i as u64;
+            if b != b'\n' || at == len - 1 {
+                continue;
+            }
+            newlines += 1;
+            if newlines == n {
+                return Ok(at + 1);
+            }
+        }
+        end = start;
+    }
+    Ok(0)
+}
+
+/// What ends a record under `--separator` or `--paragraph`.
+#[derive(Clone, Debug)]
+pub enum Separator {
+    /// A line that's exactly this
+    Line(String),
+    /// One or more blank (empty or whitespace-only) lines
+    Blank,
+}
+
+/// Group the lines among `events` into records, each one [`Event::Line`]
+/// of its lines joined with `\n`. A record keeps the separator line(s) that
+/// end it, so the records written out one per line are the input again.
+/// A record's [`Event::Size`] is its lines' sizes added up. Other events
+/// pass straight through; the last record ends at EOF.
+pub fn records(
+    events: impl Iterator<Item = io::Result<Event>>,
+    separator: Separator,
+) -> impl Iterator<Item = io::Result<Event>> {
+    let mut events = events.fuse();
+    let mut record: Option<String> = None;
+    // The size of `record` as read, and of the next line if it's given
+    let mut size = 0;
+    let mut line_size = None;
+    // Under --paragraph, the blank lines ending `record` have started
+    let mut ending = false;
+    let mut ready = VecDeque::new();
+    // The events for `record`, if there is one
+    let mut give = move |record: Option<String>, size: usize| {
+        if let Some(r) = record {
+            if size != r.len() + 1 {
+                ready.push_back(Ok(Event::Size(size)));
+            }
+            ready.push_back(Ok(Event::Line(r)));
+        }
+        ready.pop_front()
+    };
+    std::iter::from_fn(move || loop {
+        if let Some(event) = give(None, 0) {
+            return Some(event);
+        }
+        let line = match events.next() {
+            Some(Ok(Event::Line(line))) => line,
+            Some(Ok(Event::Size(bytes))) => {
+                line_size = Some(bytes);
+                continue;
+            }
+            None => return give(record.take(), std::mem::take(&mut size)),
+            other => return other,
+        };
+        let ends = match &separator {
+            Separator::Line(sep) => line == *sep,
+            Separator::Blank => line.trim().is_empty(),
+        };
+        let read = line_size.take().unwrap_or(line.len() + 1);
+        if ending && !ends {
+            // The first line of the next paragraph
+            ending = false;
+            let done = std::mem::replace(&mut size, read);
+            return give(record.replace(line), done);
+        }
+        size += read;
+        match &mut record {
+            Some(r) => {
+                r.push('\n');
+                r.push_str(&line);
+            }
+            None => record = Some(line),
+        }
+        match separator {
+            Separator::Line(_) if ends => {
+                return give(record.take(), std::mem::take(&mut size));
+            }
+            // Leading blank lines belong to the first paragraph
+            Separator::Blank => {
+                ending |= ends && record.as_deref().is_some_and(|r| !r.trim().is_empty())
+            }
+            _ => {}
+        }
+    })
+}
@@ -0,0 +1,66 @@
+//! `--level-histogram`: how many lines of the whole input were at each log
+//! level, for judging whether the middle hid anything worth a second look.
+
+use regex::Regex;
+use std::io::{self, Write};
+
+/// The levels counted without `--level`, most severe first.
+const DEFAULT_LEVELS: [(&str, &str); 5] = [
+    (
+        "ERROR",
+        r"\b(ERROR|FATAL|CRITICAL)\b|\blevel=(error|fatal|critical)\b",
+    ),
+    ("WARN", r"\bWARN(ING)?\b|\blevel=warn(ing)?\b"),
+    ("INFO", r"\bINFO\b|\blevel=info\b"),
+    ("DEBUG", r"\bDEBUG\b|\blevel=debug\b"),
+    ("TRACE", r"\bTRACE\b|\blevel=trace\b"),
+];
+
+/// Lines counted per level.
+pub struct Levels {
+    /// Name, pattern and count, most severe first
+    levels: Vec<(String, Regex, usize)>,
+}
+
+impl Levels {
+    /// Count `levels`, most severe first, or the default five if none are
+    /// given.
+    pub fn new(levels: &[(String, Regex)]) -> Self {
+        let levels = if levels.is_empty() {
+            DEFAULT_LEVELS
+                .iter()
+                .map(|(name, re)| {
+                    let re = Regex::new(re).expect("built-in levels are valid");
+                    (name.to_string(), re, 0)
+                })
+                .collect()
+        } else {
+            levels
+                .iter()
+                .map(|(name, re)| (name.clone(), re.clone(), 0))
+                .collect()
+        };
+        Levels { levels }
+    }
+
+    /// Count one line, at the most severe level it matches.
+    pub fn line(&mut self, content: &str) {
+        if let Some((_, _, count)) = self
+            .levels
+            .iter_mut()
+            .find(|(_, re, _)| re.is_match(content))
+        {
+            *count += 1;
+        }
+    }
+
+    /// `[trunc levels] ERROR 12 | WARN 240 | INFO 3900 | DEBUG 0 | TRACE 0`
+    pub fn write(&self, out: &mut impl Write) -> io::Result<()> {
+        let counts: Vec<String> = self
+            .levels
+            .iter()
+            .map(|(name, _, count)| format!("{} {}", name, count))
+            .collect();
+        writeln!(out, "[trunc levels] {}", counts.join(" | "))
+    }
+}
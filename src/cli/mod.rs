@@ -0,0 +1,902 @@
+//! The `trunc` command, with the `cli` feature: argument parsing, inputs,
+//! gates and recording around the truncation in the rest of the library.
+//! The binary is just [`main`].
+
+mod args;
+mod changes;
+mod decompress;
+mod detectors;
+mod flusher;
+mod follow;
+mod gates;
+mod glob;
+mod heartbeat;
+mod input;
+mod levels;
+mod plan;
+mod presets;
+#[cfg(unix)]
+mod pty;
+mod record;
+mod snapshot;
+mod spill;
+mod stats;
+mod stderr;
+mod terminal;
+mod watch;
+
+use crate::{output_format, FlushPolicy, Output, Shortlist, Stream, Truncator};
+use args::{
+    attach_optional_values, compile_regex, resolve_operands, Args, Cli, Command, StderrMode,
+    DEFAULT_FLUSH_INTERVAL,
+};
+use clap::{CommandFactory, FromArgMatches};
+use flusher::Flusher;
+use gates::Gates;
+use heartbeat::Heartbeat;
+use input::{Event, Input, Reading, Spare};
+use levels::Levels;
+use plan::{Counts, Trial};
+use record::Recorder;
+use regex::Regex;
+use spill::Spill;
+use stats::Stats;
+use std::io::{self, Write};
+use std::path::Path;
+use std::process;
+use std::time::{Duration, Instant};
+use stderr::{StderrView, STDERR_TAG};
+use watch::Watch;
+
+/// The `trunc` command: parse the arguments and run, exiting with the
+/// run's status.
+pub fn main() {
+    // Parsed via ArgMatches so presets can tell which flags were given
+    let matches = Cli::command().get_matches_from(attach_optional_values(std::env::args_os()));
+    let cli = Cli::from_arg_matches(&matches).unwrap_or_else(|e| e.exit());
+
+    if cli.help_full {
+        let help = Cli::command().render_long_help().to_string();
+        print!("{}\nOutput format:\n{}", help, indent(&output_format()));
+        return;
+    }
+
+    match cli.command {
+        Some(Command::Manpage) => {
+            let mut page = Vec::new();
+            let man = clap_mangen::Man::new(Cli::command());
+            let written = man.render(&mut page).and_then(|()| {
+                page.extend_from_slice(output_format_roff().as_bytes());
+                io::stdout().write_all(&page)
+            });
+            if let Err(e) = written {
+                if e.kind() != io::ErrorKind::BrokenPipe {
+                    eprintln!("trunc: {}", e);
+                    process::exit(1);
+                }
+            }
+        }
+        Some(Command::Replay { file, mut args }) => {
+            if !resolve_operands(&mut args).is_empty() || !args.command.is_empty() {
+                eprintln!("trunc: replay reads only the recording; use -e to give the pattern");
+                process::exit(1);
+            }
+            let (_, replay_matches) = matches.subcommand().expect("replay was given");
+            presets::apply(&mut args, replay_matches);
+            let outcome = run(&args, vec![Input::Recording(file)]);
+            process::exit(outcome.exit_code(&args));
+        }
+        Some(Command::Show {
+            file,
+            region,
+            bytes,
+        }) => {
+            let range = match (bytes, region) {
+                (Some(range), _) => Ok(range),
+                (None, Some(n)) => spill::region(&file, n),
+                (None, None) => unreachable!("clap requires one"),
+            };
+            let result = range.and_then(|range| spill::show(&file, range, &mut io::stdout()));
+            if let Err(e) = result {
+                // A closed pipe (e.g. `| head`) isn't worth reporting
+                if e.kind() != io::ErrorKind::BrokenPipe {
+                    eprintln!("trunc: {}: {}", file.display(), e);
+                    process::exit(1);
+                }
+            }
+        }
+        None => {
+            let mut args = cli.args;
+            let mut inputs = resolve_operands(&mut args);
+            presets::apply(&mut args, &matches);
+            if !args.command.is_empty() {
+                wrap(&args);
+            }
+            if inputs.is_empty() {
+                inputs.push(Input::Stdin);
+            }
+            if args.decompress {
+                inputs = inputs.into_iter().map(Input::decompressed).collect();
+            }
+            if args.watch {
+                snapshot::watch(&args, inputs);
+            }
+            if args.follow && inputs.len() > 1 {
+                eprintln!("trunc: --follow takes a single input");
+                process::exit(1);
+            }
+            if args.snapshot {
+                snapshot::snapshots(&args, inputs);
+            }
+            let outcome = run(&args, inputs);
+            process::exit(outcome.exit_code(&args));
+        }
+    }
+}
+
+/// `text` with each non-empty line indented, to sit under a help heading.
+fn indent(text: &str) -> String {
+    text.lines()
+        .map(|line| match line {
+            "" => "\n".to_string(),
+            _ => format!("  {}\n", line),
+        })
+        .collect()
+}
+
+/// The OUTPUT FORMAT section of the man page, as roff: `output_format()`
+/// unfilled, so its columns line up.
+fn output_format_roff() -> String {
+    use clap_mangen::roff::{roman, Roff};
+    let mut roff = Roff::new();
+    roff.control("SH", ["OUTPUT FORMAT"]).control("nf", []);
+    for line in output_format().lines() {
+        roff.text([roman(line)]);
+    }
+    roff.control("fi", []);
+    roff.to_roff()
+}
+
+/// Exit status under `--status` when something was truncated.
+pub(crate) const TRUNCATED_STATUS: i32 = 3;
+
+/// Exit status when `--idle-exit` gave up on a stalled input, as
+/// `timeout(1)` uses.
+pub(crate) const IDLE_STATUS: i32 = 124;
+
+/// How a run went, for the exit status.
+pub(crate) struct Outcome {
+    /// An input couldn't be read or a gate failed
+    pub(crate) failed: bool,
+    /// Some of the input was left out of the output
+    pub(crate) truncated: bool,
+    /// `--idle-exit` stopped reading at a stall
+    pub(crate) stalled: bool,
+}
+
+impl Outcome {
+    /// 124 if stopped at a stall; 1 on failure; with `--status`, 3 if
+    /// anything was truncated; else 0.
+    pub(crate) fn exit_code(&self, args: &Args) -> i32 {
+        if self.stalled {
+            IDLE_STATUS
+        } else if self.failed {
+            1
+        } else if args.status && self.truncated {
+            TRUNCATED_STATUS
+        } else {
+            0
+        }
+    }
+}
+
+/// `trunc -- cmd args`: run the command, truncate its stdout, and exit with
+/// its status. If the command succeeded, the exit status is trunc's own (1
+/// if a gate failed, and so on). A command that can't be started exits 127,
+/// like a shell.
+pub(crate) fn wrap(args: &Args) -> ! {
+    let capture_stderr = args.stderr != StderrMode::Pass;
+    if args.pty && args.stderr == StderrMode::Separate {
+        eprintln!(
+            "trunc: --pty gives stdout and stderr the same terminal, so can't keep stderr separate"
+        );
+        process::exit(1);
+    }
+    let spawned = if args.pty {
+        Input::spawn_pty(&args.command, capture_stderr)
+    } else {
+        Input::spawn(&args.command, capture_stderr)
+    };
+    let (input, mut child) = spawned.unwrap_or_else(|e| {
+        eprintln!("trunc: {}: {}", args.command[0], e);
+        process::exit(127);
+    });
+    let outcome = run(args, vec![input]);
+    if outcome.stalled {
+        // Hung: don't wait for it
+        let _ = child.kill();
+    }
+    let status = child.wait().unwrap_or_else(|e| {
+        eprintln!("trunc: {}: {}", args.command[0], e);
+        process::exit(1);
+    });
+    match exit_code(status) {
+        _ if outcome.stalled => process::exit(IDLE_STATUS),
+        0 => process::exit(outcome.exit_code(args)),
+        code => process::exit(code),
+    }
+}
+
+/// The exit code to pass on for a child's status: its own code, or
+/// 128 + the signal that killed it, as shells report it.
+pub(crate) fn exit_code(status: process::ExitStatus) -> i32 {
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::ExitStatusExt;
+        if let Some(signal) = status.signal() {
+            return 128 + signal;
+        }
+    }
+    status.code().unwrap_or(1)
+}
+
+/// A new file at `path` for `--out-head` and the like, or exit saying why.
+pub(crate) fn create_section_file(path: &Path) -> io::BufWriter<std::fs::File> {
+    match std::fs::File::create(path) {
+        Ok(file) => io::BufWriter::new(file),
+        Err(e) => {
+            eprintln!("trunc: {}: {}", path.display(), e);
+            process::exit(1);
+        }
+    }
+}
+
+/// Flush a `--save` or `--tee` file, and finish a `--record` file, before
+/// exiting early, so each holds everything read up to then.
+pub(crate) fn close(spill: Option<Spill>, recorder: Option<Recorder>) {
+    if let Some(mut spill) = spill {
+        let _ = spill.flush();
+    }
+    if let Some(recorder) = recorder {
+        let _ = recorder.finish();
+    }
+}
+
+/// Truncate each input to stdout according to `args`.
+///
+/// With several inputs, each gets a `==> name <==` header and its own
+/// budgets; gates, the recording and the trailer cover all of them.
+///
+/// Returns how the run went: failures, truncation and stalls.
+pub(crate) fn run(args: &Args, inputs: Vec<Input>) -> Outcome {
+    let follow = args.follow.then_some(args.refresh);
+    if follow.is_some() && args.relative_sizes() {
+        eprintln!(
+            "trunc: percentage sizes and --middle need the whole input, so can't be used with --follow"
+        );
+        process::exit(1);
+    }
+    let spill = open_spill(args);
+    // Only the main stream is saved, so only its markers point into the file
+    let plain = args.truncator();
+    let truncator = match &spill {
+        Some(spill) => plain.clone().spill(spill.path().display().to_string()),
+        None => plain.clone(),
+    };
+    let mut session = Session::start(args, &truncator, &plain, spill);
+
+    let names: Vec<String> = inputs.iter().map(Input::name).collect();
+    // Lines read directly go into the buffer of the line before
+    let spare = Spare::default();
+    for (i, input) in inputs.into_iter().enumerate() {
+        session.input(input, &names, i, follow, &spare);
+        if session.stalled {
+            break;
+        }
+    }
+    session.finish()
+}
+
+/// The `--save`, `--save-auto` or `--tee` copy of the input, or exit
+/// saying why it can't be made.
+fn open_spill(args: &Args) -> Option<Spill> {
+    let spill = match (&args.save, args.save_auto, &args.tee) {
+        (Some(path), _, _) => Spill::create(path),
+        (None, true, _) => Spill::create_temp(),
+        (None, false, Some(path)) => {
+            Spill::tee(path, flush_interval(args).unwrap_or(DEFAULT_FLUSH_INTERVAL))
+        }
+        (None, false, None) => return None,
+    };
+    Some(spill.unwrap_or_else(|e| {
+        eprintln!("trunc: can't save input: {}", e);
+        process::exit(1);
+    }))
+}
+
+/// How often buffered lines go out even while a read waits, under
+/// `--flush interval=MS`.
+fn flush_interval(args: &Args) -> Option<Duration> {
+    match args.flush_policy() {
+        FlushPolicy::Interval(interval) => Some(interval),
+        _ => None,
+    }
+}
+
+/// An input's lines and what else happened reading it.
+type Events = Box<dyn Iterator<Item = io::Result<Event>>>;
+
+/// One input's events, with what has to be known before its first line is
+/// shown.
+struct Opened {
+    events: Events,
+    /// The input's lines, with percentage sizes or `--middle`
+    line_count: Option<usize>,
+    /// A second pass over a file, for a match selection's survey
+    reread: Option<Box<dyn io::BufRead>>,
+    /// Every event, read up front where there's no second pass to take
+    buffered: Option<Vec<io::Result<Event>>>,
+}
+
+/// A run's state across its inputs: where the output goes, what sees every
+/// line besides the truncation, and the totals reported at the end.
+struct Session<'a> {
+    args: &'a Args,
+    truncator: &'a Truncator,
+    started: Instant,
+    out: Output<Box<dyn Write>>,
+    /// `--plan`: the output is counted rather than printed, alongside
+    /// trials of other flags
+    counts: Option<Counts>,
+    trials: Vec<Trial>,
+    stderr_view: Option<StderrView<'a>>,
+    /// The truncator `stderr_view` is sized from, without the spill
+    plain: &'a Truncator,
+    gates: Gates,
+    levels: Option<Levels>,
+    heartbeat: Option<Heartbeat>,
+    spill: Option<Spill>,
+    recorder: Option<Recorder>,
+    stats_json: Option<Box<dyn Write>>,
+    total_lines: usize,
+    bytes_read: usize,
+    longest_line: usize,
+    failed: bool,
+    stalled: bool,
+}
+
+impl<'a> Session<'a> {
+    /// Open the output and everything alongside it, or exit saying what
+    /// couldn't be.
+    fn start(
+        args: &'a Args,
+        truncator: &'a Truncator,
+        plain: &'a Truncator,
+        spill: Option<Spill>,
+    ) -> Self {
+        let started = Instant::now();
+        let stats_json = args.stats_json.map(|fd| {
+            stats::json_sink(fd).unwrap_or_else(|e| {
+                eprintln!("trunc: --stats-json: fd {}: {}", fd, e);
+                process::exit(1);
+            })
+        });
+        let levels = args.level_histogram.then(|| {
+            let given: Vec<(String, Regex)> = args
+                .level
+                .iter()
+                .map(|(name, re)| (name.clone(), compile_regex(re)))
+                .collect();
+            Levels::new(&given)
+        });
+
+        let counts = args.plan.then(Counts::default);
+        let stdout: Box<dyn Write> = match (&counts, args.quiet) {
+            (Some(counts), _) => Box::new(counts.writer()),
+            (None, true) => Box::new(io::sink()),
+            (None, false) => Box::new(Flusher::start(flush_interval(args))),
+        };
+        let mut out = truncator.output(stdout);
+        if let Some(counts) = &counts {
+            out = counts.attach(out);
+        }
+        let trials = match args.plan {
+            true => plan::trials(args, truncator),
+            false => Vec::new(),
+        };
+        if args.markers_to_stderr {
+            out = out.markers_to(io::stderr());
+        }
+        if args.explain {
+            out = out.explain_to(io::stderr());
+        }
+        if let Some(path) = &args.out_head {
+            out = out.head_to(create_section_file(path));
+        }
+        if let Some(path) = &args.out_matches {
+            out = out.matches_to(create_section_file(path));
+        }
+        if let Some(path) = &args.out_tail {
+            out = out.tail_to(create_section_file(path));
+        }
+        let stderr_view =
+            (args.stderr == StderrMode::Separate).then(|| StderrView::new(args, plain));
+
+        let recorder = args.record.as_deref().map(|path| {
+            Recorder::create(path).unwrap_or_else(|e| {
+                eprintln!("trunc: {}: {}", path.display(), e);
+                process::exit(1);
+            })
+        });
+
+        Session {
+            args,
+            truncator,
+            started,
+            out,
+            counts,
+            trials,
+            stderr_view,
+            plain,
+            gates: Gates::new(args),
+            levels,
+            heartbeat: args.heartbeat.map(|_| Heartbeat::new()),
+            spill,
+            recorder,
+            stats_json,
+            total_lines: 0,
+            bytes_read: 0,
+            longest_line: 0,
+            failed: false,
+            stalled: false,
+        }
+    }
+
+    /// Truncate input `i` of those `names` names. An input that can't be
+    /// read is reported and counts as a failure; the rest still run.
+    fn input(
+        &mut self,
+        input: Input,
+        names: &[String],
+        i: usize,
+        follow: Option<Duration>,
+        spare: &Spare,
+    ) {
+        let args = self.args;
+        let name = &names[i];
+        let input_path = input.regular_file().map(Path::to_path_buf);
+        // A decoder's errors say what was wrong with the data, not where
+        let compressed = matches!(input, Input::Compressed { .. });
+        let opened = match self.open(input, follow, spare) {
+            Ok(opened) => opened,
+            Err(e) => {
+                eprintln!("trunc: {}: {}", name, e);
+                self.failed = true;
+                return;
+            }
+        };
+        let Opened {
+            mut events,
+            line_count,
+            reread,
+            buffered,
+        } = opened;
+
+        let sized = line_count.map(|n| args.sized(self.truncator, n));
+        let truncator = sized.as_ref().unwrap_or(self.truncator);
+        let alternatives: Vec<Truncator> = self
+            .trials
+            .iter()
+            .map(|trial| trial.change.apply(args, truncator, line_count))
+            .collect();
+        let (shortlist, shortlists) = survey(
+            args,
+            truncator,
+            &alternatives,
+            reread,
+            buffered.as_deref(),
+            spare,
+        );
+        if let Some(all) = buffered {
+            events = Box::new(all.into_iter());
+        }
+
+        // --parallel: a file's matches are found up front, a chunk per core,
+        // where the pattern sees lines just as they're read
+        let mut scanned = None;
+        if args.parallel
+            && truncator.can_scan()
+            && follow.is_none()
+            && args.separator().is_none()
+            && args.max_line_bytes.is_none()
+        {
+            if let Some(path) = input_path.as_deref() {
+                match truncator.scan(path, args.binary_safe) {
+                    Ok(found) => scanned = Some(found),
+                    Err(e) => {
+                        eprintln!("trunc: {}: {}", name, e);
+                        self.failed = true;
+                        return;
+                    }
+                }
+            }
+        }
+
+        let show_headers = names.len() > 1;
+        if show_headers {
+            self.out.header(name);
+            for trial in &mut self.trials {
+                trial.out.header(name);
+            }
+        }
+
+        // --max-bytes: split what's left evenly between this and the
+        // remaining inputs; anything an input doesn't use carries over
+        let later_headers = if show_headers {
+            names[i + 1..]
+                .iter()
+                .map(|name| self.out.header_len(name))
+                .sum()
+        } else {
+            0
+        };
+        let inputs_left = names.len() - i;
+        let mut state = truncator.stream(self.out.share(inputs_left, later_headers));
+        if let Some(shortlist) = shortlist {
+            state = state.shortlist(shortlist);
+        }
+        if let Some(scanned) = scanned {
+            state = state.scanned(scanned);
+        }
+        state = state.byte_offset(self.bytes_read);
+        let mut alternative_states: Vec<_> = alternatives
+            .iter()
+            .zip(&self.trials)
+            .zip(shortlists)
+            .map(|((alternative, trial), shortlist)| {
+                let share = trial.out.share(inputs_left, later_headers);
+                let state = alternative.stream(share).byte_offset(self.bytes_read);
+                match shortlist {
+                    Some(shortlist) => state.shortlist(shortlist),
+                    None => state,
+                }
+            })
+            .collect();
+
+        let location = |line: usize| match show_headers {
+            true => format!("{}:{}", name, line),
+            false => format!("line {}", line),
+        };
+        self.read(events, &mut state, &mut alternative_states, location, spare)
+            .unwrap_or_else(|e| {
+                if compressed {
+                    eprintln!("trunc: {}: {}", name, e);
+                } else {
+                    eprintln!("Error reading input: {}", e);
+                }
+                close(self.spill.take(), self.recorder.take());
+                process::exit(1);
+            });
+
+        if let Some(heartbeat) = &mut self.heartbeat {
+            heartbeat.finish();
+        }
+        state.finish(&mut self.out);
+        for (state, trial) in alternative_states.into_iter().zip(&mut self.trials) {
+            state.finish(&mut trial.out);
+        }
+    }
+
+    /// Start reading `input`: counting its lines first if sizes are
+    /// relative to them, skimming it if nothing needs its middle, and
+    /// otherwise reading every line.
+    fn open(
+        &mut self,
+        input: Input,
+        follow: Option<Duration>,
+        spare: &Spare,
+    ) -> io::Result<Opened> {
+        let args = self.args;
+        // Percentage sizes need the input's line count, and a match
+        // selection a survey of its matches, before the first line is
+        // shown. A file gets passes of its own; anything else is read into
+        // memory.
+        let mut line_count = None;
+        // Records are counted as they're grouped, below
+        if args.relative_sizes() && args.separator().is_none() {
+            line_count = input.line_count().transpose()?;
+        }
+        let reread = match self.truncator.needs_survey() {
+            true => input.reread().transpose()?,
+            false => None,
+        };
+
+        // Without anything that needs every line, a file's middle can be
+        // skipped over rather than read line by line
+        let skim = self
+            .truncator
+            .skip_window()
+            .filter(|_| follow.is_none() && !args.relative_sizes() && args.can_skip_lines())
+            .and_then(|(head, tail)| input.skim(head, tail, args.reading(), !args.no_idempotent));
+
+        let watched = args.idle_timeout.is_some() || args.heartbeat.is_some();
+        let events = skim.unwrap_or_else(|| {
+            if watched {
+                Watch::start(input, args.idle_timeout, args.heartbeat, args.reading())
+                    .map(|watch| Box::new(watch) as Events)
+            } else {
+                input.events(follow, args.reading(), spare)
+            }
+        })?;
+        // Under --stderr merge, a wrapped command's stderr lines join the
+        // main stream, tagged, and are copied out as they're shown
+        let merged_stderr = self.stderr_view.is_none();
+        let raw = args.reading().raw;
+        let mut events: Events = Box::new(events.flat_map(move |event| match event {
+            Ok(Event::Stderr(l)) if merged_stderr => {
+                let line = format!("{}{}", STDERR_TAG, l);
+                let copy = raw.then(|| Ok(Event::Raw(format!("{}\n", line).into_bytes())));
+                copy.into_iter().chain([Ok(Event::Line(line))]).collect()
+            }
+            other => vec![other],
+        }));
+        if let Some(separator) = args.separator() {
+            events = Box::new(input::records(events, separator));
+        }
+        let mut buffered = None;
+        if (args.relative_sizes() && line_count.is_none())
+            || (self.truncator.needs_survey() && reread.is_none())
+        {
+            let all: Vec<_> = events.collect();
+            let lines = all.iter().filter(|e| matches!(e, Ok(Event::Line(_))));
+            line_count.get_or_insert(lines.count());
+            buffered = Some(all);
+            events = Box::new(std::iter::empty());
+        }
+        Ok(Opened {
+            events,
+            line_count,
+            reread,
+            buffered,
+        })
+    }
+
+    /// Feed an input's events to its streams and to everything else that
+    /// sees every line. `location` describes a line number, for gate
+    /// reports. Stops early at a stall under `--idle-exit`.
+    fn read(
+        &mut self,
+        events: Events,
+        state: &mut Stream,
+        alternative_states: &mut [Stream],
+        location: impl Fn(usize) -> String,
+        spare: &Spare,
+    ) -> io::Result<()> {
+        let mut read_size = None;
+        for event in events {
+            let content = match event? {
+                Event::Line(l) => l,
+                Event::Stderr(l) => {
+                    if let Some(view) = &mut self.stderr_view {
+                        self.gates
+                            .check(&l, || format!("stderr line {}", view.lines + 1));
+                        if let Some(levels) = &mut self.levels {
+                            levels.line(&l);
+                        }
+                        view.line(l);
+                    }
+                    continue;
+                }
+                Event::Raw(bytes) => {
+                    self.copy(&bytes);
+                    continue;
+                }
+                Event::Size(bytes) => {
+                    read_size = Some(bytes);
+                    state.size(bytes);
+                    for state in alternative_states.iter_mut() {
+                        state.size(bytes);
+                    }
+                    continue;
+                }
+                Event::Skip { lines, bytes } => {
+                    self.total_lines += lines;
+                    self.bytes_read += bytes;
+                    state.skip(lines, bytes);
+                    continue;
+                }
+                Event::Tick => {
+                    state.refresh(&mut self.out);
+                    if let Some(view) = &mut self.stderr_view {
+                        view.refresh();
+                    }
+                    continue;
+                }
+                Event::Heartbeat => {
+                    if let Some(heartbeat) = &mut self.heartbeat {
+                        heartbeat.beat(self.total_lines, state.matches_found());
+                    }
+                    continue;
+                }
+                Event::Stalled => {
+                    if let Some(heartbeat) = &mut self.heartbeat {
+                        heartbeat.clear();
+                    }
+                    state.stalled(&mut self.out);
+                    for (state, trial) in alternative_states.iter_mut().zip(&mut self.trials) {
+                        state.stalled(&mut trial.out);
+                    }
+                    if let Some(view) = &mut self.stderr_view {
+                        view.refresh();
+                    }
+                    if self.args.idle_exit {
+                        self.stalled = true;
+                        break;
+                    }
+                    continue;
+                }
+            };
+
+            self.total_lines += 1;
+            self.bytes_read += read_size.take().unwrap_or(content.len() + 1);
+            self.longest_line = self.longest_line.max(content.chars().count());
+
+            self.gates
+                .check(&content, || location(state.line_number() + 1));
+            if let Some(levels) = &mut self.levels {
+                levels.line(&content);
+            }
+
+            if let Some(heartbeat) = &mut self.heartbeat {
+                heartbeat.clear();
+            }
+            state.line(&content, &mut self.out);
+            for (state, trial) in alternative_states.iter_mut().zip(&mut self.trials) {
+                state.line(&content, &mut trial.out);
+            }
+            spare.give(content);
+        }
+        Ok(())
+    }
+
+    /// Copy a line's bytes as read to the recording and the saved input,
+    /// or exit saying which couldn't be written.
+    fn copy(&mut self, bytes: &[u8]) {
+        if let Some(rec) = &mut self.recorder {
+            if let Err(e) = rec.line(bytes) {
+                eprintln!("Error writing recording: {}", e);
+                close(self.spill.take(), None);
+                process::exit(1);
+            }
+        }
+        if let Some(spill) = &mut self.spill {
+            if let Err(e) = spill.write(bytes) {
+                eprintln!("Error writing {}: {}", spill.path().display(), e);
+                close(None, self.recorder.take());
+                process::exit(1);
+            }
+        }
+    }
+
+    /// Finish the output and report on the run: the plan, stats, level
+    /// histogram and stderr view, then the recording and saved input, then
+    /// the gates.
+    fn finish(mut self) -> Outcome {
+        let args = self.args;
+        let (total_lines, bytes_read) = (self.total_lines, self.bytes_read);
+        // A closed pipe (e.g. `| head`) isn't worth reporting
+        let mut truncated = self.out.truncated(total_lines, bytes_read);
+        let regions = self.out.spill_regions().to_vec();
+        let _ = self.out.finish(total_lines, bytes_read);
+        if let Some(counts) = &self.counts {
+            let mut estimates = vec![counts.estimate("as given", &self.out)];
+            for trial in &mut self.trials {
+                let _ = trial.out.finish(total_lines, bytes_read);
+                estimates.push(trial.estimate());
+            }
+            let _ = plan::write(&mut io::stdout(), total_lines, bytes_read, &estimates);
+        }
+        if args.stats || self.stats_json.is_some() {
+            let stats = Stats {
+                lines_read: total_lines,
+                bytes_read,
+                lines_emitted: self.out.lines_written(),
+                bytes_emitted: self.out.bytes_written(),
+                matches: self.out.matches(),
+                longest_line: self.longest_line,
+                elapsed: self.started.elapsed(),
+                regions: self.out.regions().to_vec(),
+            };
+            if args.stats {
+                let _ = stats.write(&mut io::stderr());
+            }
+            if let Some(sink) = &mut self.stats_json {
+                let _ = writeln!(sink, "{}", stats.json());
+            }
+        }
+        if let Some(levels) = &self.levels {
+            let _ = levels.write(&mut io::stderr());
+        }
+        if let Some(view) = self.stderr_view {
+            truncated |= view.finish(args, self.plain);
+        }
+
+        if let Some(rec) = self.recorder {
+            if let Err(e) = rec.finish() {
+                eprintln!("Error writing recording: {}", e);
+                close(self.spill, None);
+                process::exit(1);
+            }
+        }
+        if let Some(spill) = self.spill {
+            let path = spill.path().display().to_string();
+            if let Err(e) = spill.finish(&regions) {
+                eprintln!("Error writing {}: {}", path, e);
+                process::exit(1);
+            }
+        }
+
+        // Output gates: the truncated view is already printed, now fail if asked.
+        let failed = self.gates.report(args.width, args.width_mode.into()) || self.failed;
+        Outcome {
+            failed,
+            truncated,
+            stalled: self.stalled,
+        }
+    }
+}
+
+/// Survey an input's matches for `truncator` and each of `alternatives`,
+/// if they select matches: from a second pass over a file (`reread`), or
+/// the events read up front (`buffered`). Each gets its own shortlist.
+fn survey(
+    args: &Args,
+    truncator: &Truncator,
+    alternatives: &[Truncator],
+    reread: Option<Box<dyn io::BufRead>>,
+    buffered: Option<&[io::Result<Event>]>,
+    spare: &Spare,
+) -> (Option<Shortlist>, Vec<Option<Shortlist>>) {
+    if !truncator.needs_survey() {
+        return (None, vec![None; alternatives.len()]);
+    }
+    let mut surveys: Vec<_> = std::iter::once(truncator)
+        .chain(alternatives)
+        .map(Truncator::survey)
+        .collect();
+    if let Some(reader) = reread {
+        // Only the main pass copies lines out
+        let reading = Reading {
+            raw: false,
+            ..args.reading()
+        };
+        let mut lines: Events = Box::new(input::reused_lines(reader, reading, spare.clone()));
+        if let Some(separator) = args.separator() {
+            lines = Box::new(input::records(lines, separator));
+        }
+        for line in lines {
+            match line {
+                Ok(Event::Line(line)) => {
+                    for survey in &mut surveys {
+                        survey.line(&line);
+                    }
+                    spare.give(line);
+                }
+                Ok(_) => {}
+                // The main pass will hit and report it
+                Err(_) => break,
+            }
+        }
+    } else if let Some(all) = buffered {
+        for event in all {
+            if let Ok(Event::Line(line)) = event {
+                for survey in &mut surveys {
+                    survey.line(line);
+                }
+            }
+        }
+    }
+    let mut finished = surveys.into_iter().map(|survey| Some(survey.finish()));
+    let shortlist = finished.next().flatten();
+    (shortlist, finished.collect())
+}
@@ -0,0 +1,190 @@
+//! `--plan`: what trunc would print, counted rather than printed, for the
+//! flags given and a few alternatives, so budgets can be picked before the
+//! output is paid for.
+
+use crate::{MarkerStyle, Output, Truncator};
+use std::io::{self, Write};
+use std::sync::{Arc, Mutex};
+
+use crate::cli::args::{Args, Markers};
+
+/// Bytes per token, roughly, for logs and English text.
+const BYTES_PER_TOKEN: usize = 4;
+
+/// A writer that counts the lines and bytes written to it, and keeps
+/// nothing. Clones share the count.
+#[derive(Clone, Default)]
+pub struct Tally(Arc<Mutex<(usize, usize)>>);
+
+impl Tally {
+    fn get(&self) -> (usize, usize) {
+        *self.0.lock().unwrap()
+    }
+}
+
+impl Write for Tally {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let newlines = buf.iter().filter(|&&b| b == b'\n').count();
+        let mut count = self.0.lock().unwrap();
+        *count = (count.0 + newlines, count.1 + buf.len());
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// What an [`Output`] writes, counted: in all, and of its head, match and
+/// tail lines.
+#[derive(Clone, Default)]
+pub struct Counts {
+    all: Tally,
+    sections: [Tally; 3],
+}
+
+impl Counts {
+    /// The writer to give the output.
+    pub fn writer(&self) -> Tally {
+        self.all.clone()
+    }
+
+    /// `out`, with its head, match and tail lines counted too.
+    pub fn attach<W: Write>(&self, out: Output<W>) -> Output<W> {
+        let [head, matches, tail] = self.sections.clone();
+        out.head_to(head).matches_to(matches).tail_to(tail)
+    }
+
+    /// The figures for `flags`, from `out` once it's finished.
+    pub fn estimate<W: Write>(&self, flags: &str, out: &Output<W>) -> Estimate {
+        let (lines, bytes) = self.all.get();
+        let [head, matches, tail] = self.sections.each_ref().map(|s| s.get().0);
+        Estimate {
+            flags: flags.to_string(),
+            head,
+            matches,
+            tail,
+            markers: out.markers_written(),
+            lines,
+            bytes,
+        }
+    }
+}
+
+/// A change to the flags given, estimated alongside them.
+#[derive(Clone, Copy)]
+pub enum Change {
+    /// `-f`, `-l` and `-m` multiplied by the first and divided by the
+    /// second
+    Scale(usize, usize),
+    /// `--markers compact`
+    Compact,
+}
+
+impl Change {
+    fn flags(self) -> &'static str {
+        match self {
+            Change::Scale(1, 2) => "-f/-l/-m halved",
+            Change::Scale(..) => "-f/-l/-m doubled",
+            Change::Compact => "--markers compact",
+        }
+    }
+
+    /// `truncator` with the change made: sized for an input of `total`
+    /// lines, if percentage sizes need it.
+    pub fn apply(self, args: &Args, truncator: &Truncator, total: Option<usize>) -> Truncator {
+        match self {
+            Change::Scale(times, over) => {
+                let (first, last, matches) = args.sizes(total.unwrap_or(0));
+                let scale = |n: usize| n * times / over;
+                truncator
+                    .clone()
+                    .first(scale(first))
+                    .last(scale(last))
+                    .matches(scale(matches))
+            }
+            Change::Compact => truncator.clone().marker_style(MarkerStyle::Compact),
+        }
+    }
+}
+
+/// An alternative being estimated: the change, and its output, counted.
+pub struct Trial {
+    pub change: Change,
+    counts: Counts,
+    pub out: Output<Tally>,
+}
+
+impl Trial {
+    /// The figures for the change, once `out` has finished.
+    pub fn estimate(&self) -> Estimate {
+        self.counts.estimate(self.change.flags(), &self.out)
+    }
+}
+
+/// The alternatives to the flags given: half and twice the sizes, and
+/// compact markers unless they already are.
+pub fn trials(args: &Args, truncator: &Truncator) -> Vec<Trial> {
+    let mut changes = vec![Change::Scale(1, 2), Change::Scale(2, 1)];
+    if args.markers != Markers::Compact {
+        changes.push(Change::Compact);
+    }
+    changes
+        .into_iter()
+        .map(|change| {
+            let counts = Counts::default();
+            let out = change.apply(args, truncator, None).output(counts.writer());
+            Trial {
+                change,
+                out: counts.attach(out),
+                counts,
+            }
+        })
+        .collect()
+}
+
+/// What one set of flags would print.
+pub struct Estimate {
+    flags: String,
+    head: usize,
+    matches: usize,
+    tail: usize,
+    markers: usize,
+    lines: usize,
+    bytes: usize,
+}
+
+/// The plan: what was read, then a row of figures for each estimate, the
+/// flags given first.
+pub fn write(
+    out: &mut impl Write,
+    lines_read: usize,
+    bytes_read: usize,
+    estimates: &[Estimate],
+) -> io::Result<()> {
+    writeln!(
+        out,
+        "[trunc plan] read {} lines, {} bytes",
+        lines_read, bytes_read
+    )?;
+    writeln!(
+        out,
+        "{:<18} {:>6} {:>7} {:>6} {:>7} {:>6} {:>8} {:>7}",
+        "flags", "head", "matches", "tail", "markers", "lines", "bytes", "~tokens"
+    )?;
+    for e in estimates {
+        writeln!(
+            out,
+            "{:<18} {:>6} {:>7} {:>6} {:>7} {:>6} {:>8} {:>7}",
+            e.flags,
+            e.head,
+            e.matches,
+            e.tail,
+            e.markers,
+            e.lines,
+            e.bytes,
+            e.bytes.div_ceil(BYTES_PER_TOKEN)
+        )?;
+    }
+    Ok(())
+}
@@ -0,0 +1,114 @@
+//! Built-in presets (`--preset cargo`): patterns and budgets tuned for the
+//! output of common build and test tools.
+//!
+//! A preset is an overlay on the parsed options. It only fills in what the
+//! command line left alone, so `--preset cargo -C 10` keeps its own context,
+//! and a pattern given on the command line replaces the preset's.
+
+use clap::parser::ValueSource;
+use clap::ArgMatches;
+
+use crate::cli::args::{Args, Language, Size};
+
+/// Settings a preset applies.
+struct Preset {
+    name: &'static str,
+    /// Regexes for the lines worth pulling out of the middle
+    patterns: &'static [&'static str],
+    first: usize,
+    last: usize,
+    matches: usize,
+    context: usize,
+    /// Whose blocks to keep whole (`--lang`)
+    lang: Option<Language>,
+}
+
+/// Every preset, by name.
+const PRESETS: &[Preset] = &[
+    // Compiler errors carry several lines of source snippet below them
+    // (shown whole when they have a code), and the test summary comes last
+    Preset {
+        name: "cargo",
+        patterns: &[r"^error(\[E\d+\])?:", r"panicked at", r"FAILED"],
+        first: 10,
+        last: 30,
+        matches: 5,
+        context: 6,
+        lang: Some(Language::Rust),
+    },
+    // `E` lines hold the assertion details; the short summary comes last
+    Preset {
+        name: "pytest",
+        patterns: &[r"^E ", r"^(FAILED|ERROR) ", r"Traceback"],
+        first: 10,
+        last: 20,
+        matches: 5,
+        context: 5,
+        lang: None,
+    },
+    Preset {
+        name: "npm",
+        patterns: &[r"npm (ERR!|error)", r"\bError:", "✕", r"FAIL "],
+        first: 10,
+        last: 20,
+        matches: 5,
+        context: 4,
+        lang: None,
+    },
+    // "What went wrong" is followed by the cause, and `e:` is a Kotlin error
+    Preset {
+        name: "gradle",
+        patterns: &[
+            r"FAILURE:",
+            r"What went wrong",
+            r"^e: ",
+            r"error:",
+            r"FAILED",
+        ],
+        first: 10,
+        last: 20,
+        matches: 5,
+        context: 5,
+        lang: None,
+    },
+];
+
+/// Preset names, for `--preset`'s possible values.
+pub fn names() -> impl Iterator<Item = &'static str> {
+    PRESETS.iter().map(|p| p.name)
+}
+
+/// Apply the preset named by `--preset`, if any, to whatever `matches`
+/// shows wasn't set on the command line. Call after operands are resolved,
+/// so a pattern operand counts as set.
+pub fn apply(args: &mut Args, matches: &ArgMatches) {
+    let Some(name) = &args.preset else {
+        return;
+    };
+    let preset = PRESETS
+        .iter()
+        .find(|p| p.name == name)
+        .expect("clap only accepts preset names");
+    let unset = |id: &str| matches.value_source(id) != Some(ValueSource::CommandLine);
+
+    if unset("first") {
+        args.first = Size::Lines(preset.first);
+    }
+    if unset("last") {
+        args.last = Size::Lines(preset.last);
+    }
+    if unset("matches") {
+        args.matches = preset.matches;
+    }
+    if unset("context") {
+        args.context = preset.context;
+    }
+    if args.lang.is_none() {
+        args.lang = preset.lang;
+    }
+    if args.patterns.is_empty() {
+        args.patterns = preset.patterns.iter().map(|p| p.to_string()).collect();
+        // --fixed-string is about the user's patterns; these are regexes
+        args.fixed_string = false;
+    }
+}
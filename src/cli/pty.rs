@@ -0,0 +1,144 @@
+//! `--pty`: running a wrapped command on a pseudo-terminal, so tools that
+//! check whether they're writing to a terminal (cargo, npm and most others)
+//! keep their colors and progress output, for trunc to filter. Unix only.
+
+use std::fs::File;
+use std::io::{self, IsTerminal, Read};
+use std::os::fd::{AsRawFd, FromRawFd, OwnedFd};
+use std::process::{Child, Command, Stdio};
+
+use crate::cli::terminal;
+
+/// The pseudo-terminal's side trunc reads what the child writes from.
+pub struct Master(File);
+
+impl Read for Master {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self.0.read(buf) {
+            // What Linux gives once the child's side is closed
+            Err(e) if e.raw_os_error() == Some(libc::EIO) => Ok(0),
+            other => other,
+        }
+    }
+}
+
+/// Run `argv` with its stdout on a new pseudo-terminal, and its stderr too
+/// if `capture_stderr` (the two can't be told apart there). Stdin, and
+/// otherwise stderr, is inherited. The terminal is the size of trunc's own,
+/// if it has one.
+pub fn spawn(argv: &[String], capture_stderr: bool) -> io::Result<(Master, Child)> {
+    let (master, terminal) = open()?;
+    let mut command = Command::new(&argv[0]);
+    command
+        .args(&argv[1..])
+        .stdout(Stdio::from(terminal.try_clone()?));
+    if capture_stderr {
+        command.stderr(Stdio::from(terminal));
+    }
+    let child = command.spawn()?;
+    // The child must hold the only copies of its side, so reading ends
+    // when it exits
+    drop(command);
+    Ok((Master(File::from(master)), child))
+}
+
+/// A new pseudo-terminal's two sides, neither inherited by children except
+/// as they're given.
+///
+/// Both are opened close-on-exec, not marked so after: a child spawned by
+/// another thread in between would keep a copy of the terminal, and
+/// reading would never end.
+#[cfg(any(target_os = "linux", target_os = "android"))]
+fn open() -> io::Result<(OwnedFd, OwnedFd)> {
+    use std::ffi::CStr;
+    use std::fs::OpenOptions;
+    use std::os::unix::ffi::OsStrExt;
+    use std::os::unix::fs::OpenOptionsExt;
+
+    // SAFETY: takes no pointers
+    let master = unsafe { libc::posix_openpt(libc::O_RDWR | libc::O_NOCTTY | libc::O_CLOEXEC) };
+    if master == -1 {
+        return Err(io::Error::last_os_error());
+    }
+    // SAFETY: posix_openpt succeeded, so it's open and now ours alone
+    let master = unsafe { OwnedFd::from_raw_fd(master) };
+    let mut name = [0; 64];
+    // SAFETY: master is open, and name is as long as ptsname_r is told
+    let named = unsafe {
+        libc::grantpt(master.as_raw_fd()) == 0
+            && libc::unlockpt(master.as_raw_fd()) == 0
+            && libc::ptsname_r(master.as_raw_fd(), name.as_mut_ptr(), name.len()) == 0
+    };
+    if !named {
+        return Err(io::Error::last_os_error());
+    }
+    // SAFETY: ptsname_r succeeded, so name holds a nul-terminated path
+    let path = unsafe { CStr::from_ptr(name.as_ptr()) };
+    // std opens files close-on-exec
+    let terminal = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .custom_flags(libc::O_NOCTTY)
+        .open(std::ffi::OsStr::from_bytes(path.to_bytes()))?;
+    if let Some(size) = window_size() {
+        // SAFETY: the terminal is open, and the size is read only
+        if unsafe { libc::ioctl(terminal.as_raw_fd(), libc::TIOCSWINSZ, &size) } == -1 {
+            return Err(io::Error::last_os_error());
+        }
+    }
+    Ok((master, terminal.into()))
+}
+
+/// A new pseudo-terminal's two sides, neither inherited by children except
+/// as they're given.
+///
+/// Without a way to open them close-on-exec here, they're marked so straight
+/// after: `--pty` spawns its command before trunc starts any other thread
+/// or child, so none can inherit them in between.
+#[cfg(not(any(target_os = "linux", target_os = "android")))]
+fn open() -> io::Result<(OwnedFd, OwnedFd)> {
+    use std::ptr;
+
+    let mut master = -1;
+    let mut terminal = -1;
+    let mut size = window_size();
+    let size_ptr = size
+        .as_mut()
+        .map_or(ptr::null_mut(), |size| size as *mut libc::winsize);
+    // SAFETY: the out pointers are valid, and the size (if any) is read only
+    let opened = unsafe {
+        libc::openpty(
+            &mut master,
+            &mut terminal,
+            ptr::null_mut(),
+            ptr::null_mut(),
+            size_ptr,
+        )
+    };
+    if opened != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    // SAFETY: openpty succeeded, so both are open and now ours alone
+    let (master, terminal) =
+        unsafe { (OwnedFd::from_raw_fd(master), OwnedFd::from_raw_fd(terminal)) };
+    for fd in [&master, &terminal] {
+        // SAFETY: fd is open for as long as the call
+        if unsafe { libc::fcntl(fd.as_raw_fd(), libc::F_SETFD, libc::FD_CLOEXEC) } == -1 {
+            return Err(io::Error::last_os_error());
+        }
+    }
+    Ok((master, terminal))
+}
+
+/// The size of whichever of trunc's stdout, stderr and stdin is a terminal.
+fn window_size() -> Option<libc::winsize> {
+    [
+        io::stdout().is_terminal().then_some(libc::STDOUT_FILENO),
+        io::stderr().is_terminal().then_some(libc::STDERR_FILENO),
+        io::stdin().is_terminal().then_some(libc::STDIN_FILENO),
+    ]
+    .into_iter()
+    .flatten()
+    .next()
+    .and_then(terminal::size)
+}
@@ -0,0 +1,147 @@
+//! Recordings: the raw input stream saved alongside a line index, so the
+//! same output can be re-truncated later with different flags.
+//!
+//! File layout:
+//!
+//! ```text
+//! TRUNCREC1\n                 magic
+//! <data>                      the input's bytes as read, line endings and all
+//! <index>                     u64 LE offset of each line within <data>
+//! <line count> <data length>  u64 LE each
+//! TRUNCIDX                    end magic
+//! ```
+//!
+//! The footer sits at a fixed distance from the end of the file, so a reader
+//! can find the data and index without scanning. The index lets a replay
+//! find its tail without reading the lines before it.
+
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Read, Seek, SeekFrom, Write};
+use std::ops::Range;
+use std::path::Path;
+
+const MAGIC: &[u8] = b"TRUNCREC1\n";
+const END_MAGIC: &[u8] = b"TRUNCIDX";
+const FOOTER_LEN: u64 = 8 + 8 + END_MAGIC.len() as u64;
+
+/// Writes a recording as lines arrive.
+pub struct Recorder {
+    file: BufWriter<File>,
+    offsets: Vec<u64>,
+    data_len: u64,
+}
+
+impl Recorder {
+    pub fn create(path: &Path) -> io::Result<Self> {
+        let mut file = BufWriter::new(File::create(path)?);
+        file.write_all(MAGIC)?;
+        Ok(Recorder {
+            file,
+            offsets: Vec::new(),
+            data_len: 0,
+        })
+    }
+
+    /// Record a line's bytes as read, its [`Event::Raw`](crate::cli::input::Event::Raw).
+    pub fn line(&mut self, bytes: &[u8]) -> io::Result<()> {
+        self.file.write_all(bytes)?;
+        self.offsets.push(self.data_len);
+        self.data_len += bytes.len() as u64;
+        Ok(())
+    }
+
+    /// Write the index and footer. Called on the way out after a read
+    /// error too, so what was read up to then can be replayed.
+    pub fn finish(mut self) -> io::Result<()> {
+        for offset in &self.offsets {
+            self.file.write_all(&offset.to_le_bytes())?;
+        }
+        self.file
+            .write_all(&(self.offsets.len() as u64).to_le_bytes())?;
+        self.file.write_all(&self.data_len.to_le_bytes())?;
+        self.file.write_all(END_MAGIC)?;
+        self.file.flush()
+    }
+}
+
+/// A recording opened for replay: where its data and index are.
+pub struct Recording {
+    file: File,
+    lines: u64,
+    data_len: u64,
+}
+
+impl Recording {
+    pub fn open(path: &Path) -> io::Result<Self> {
+        let invalid = || io::Error::new(io::ErrorKind::InvalidData, "not a trunc recording");
+
+        let mut file = File::open(path)?;
+        let mut magic = [0u8; MAGIC.len()];
+        file.read_exact(&mut magic).map_err(|_| invalid())?;
+        if magic != MAGIC {
+            return Err(invalid());
+        }
+
+        let file_len = file.metadata()?.len();
+        if file_len < MAGIC.len() as u64 + FOOTER_LEN {
+            return Err(invalid());
+        }
+        file.seek(SeekFrom::End(-(FOOTER_LEN as i64)))?;
+        let mut footer = [0u8; FOOTER_LEN as usize];
+        file.read_exact(&mut footer)?;
+        if &footer[16..] != END_MAGIC {
+            return Err(invalid());
+        }
+        let lines = u64::from_le_bytes(footer[0..8].try_into().unwrap());
+        let data_len = u64::from_le_bytes(footer[8..16].try_into().unwrap());
+        let expected = lines
+            .checked_mul(8)
+            .and_then(|index| index.checked_add(data_len))
+            .and_then(|len| len.checked_add(MAGIC.len() as u64 + FOOTER_LEN));
+        if expected != Some(file_len) {
+            return Err(invalid());
+        }
+        Ok(Recording {
+            file,
+            lines,
+            data_len,
+        })
+    }
+
+    /// Where the recorded input lies in the file.
+    pub fn data(&self) -> Range<u64> {
+        let start = MAGIC.len() as u64;
+        start..start + self.data_len
+    }
+
+    /// Where in the file the last `n` lines start, from the index.
+    pub fn tail_start(&self, n: usize) -> io::Result<u64> {
+        let Some(line) = self.lines.checked_sub(n as u64) else {
+            return Ok(self.data().start);
+        };
+        if line == self.lines {
+            return Ok(self.data().end);
+        }
+        let mut offset = [0u8; 8];
+        let mut file = &self.file;
+        file.seek(SeekFrom::Start(self.data().end + line * 8))?;
+        file.read_exact(&mut offset)?;
+        Ok(self.data().start + u64::from_le_bytes(offset))
+    }
+
+    /// The file, for reading the data directly.
+    pub fn file(&self) -> &File {
+        &self.file
+    }
+
+    /// A reader over the recorded input.
+    pub fn reader(mut self) -> io::Result<impl BufRead> {
+        self.file.seek(SeekFrom::Start(self.data().start))?;
+        Ok(BufReader::new(self.file.take(self.data_len)))
+    }
+}
+
+/// Open a recording and return a reader over its recorded input.
+pub fn open(path: &Path) -> io::Result<impl BufRead> {
+    Recording::open(path)?.reader()
+}
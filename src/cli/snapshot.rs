@@ -0,0 +1,143 @@
+//! Truncating an input again as it changes: `--watch` re-runs on each
+//! change to a file, `--snapshot` on each `--refresh` that brought lines.
+
+use crate::cli::args::Args;
+use crate::cli::changes::Changes;
+use crate::cli::input::{Event, Input, Reading, Spare};
+use crate::cli::run;
+use crate::cli::spill::Spill;
+use std::io::{self, IsTerminal, Write};
+use std::path::Path;
+use std::process;
+
+/// Truncate the one file in `inputs` now and again each time it changes
+/// (`--watch`). Each time is a run of its own, on a cleared screen if
+/// stdout is a terminal.
+pub(crate) fn watch(args: &Args, inputs: Vec<Input>) -> ! {
+    let path = match inputs.as_slice() {
+        [input] => input.path().map(Path::to_path_buf),
+        _ => None,
+    };
+    let Some(path) = path else {
+        eprintln!("trunc: --watch takes a single file");
+        process::exit(1);
+    };
+    let changes = Changes::watch(&path).unwrap_or_else(|e| {
+        eprintln!("trunc: --watch: {}: {}", path.display(), e);
+        process::exit(1);
+    });
+    let clear = io::stdout().is_terminal();
+    let mut inputs = inputs;
+    loop {
+        if clear {
+            clear_screen();
+        }
+        run(args, inputs);
+        if !changes.wait() {
+            eprintln!("trunc: --watch: {}: stopped watching", path.display());
+            process::exit(1);
+        }
+        let input = Input::file(path.clone());
+        inputs = vec![if args.decompress {
+            input.decompressed()
+        } else {
+            input
+        }];
+    }
+}
+
+/// Follow the one input in `inputs` and, at each `--refresh` that brought
+/// new lines, truncate everything read so far afresh (`--snapshot`): on a
+/// cleared screen if stdout is a terminal, otherwise under a
+/// `==> snapshot N <==` line. A file is truncated again where it is;
+/// anything else is copied to a temporary file as it's read, removed at the
+/// end. Each snapshot is a run of its own, and the last, at EOF, gives the
+/// exit status.
+pub(crate) fn snapshots(args: &Args, inputs: Vec<Input>) -> ! {
+    let Ok([input]) = <[Input; 1]>::try_from(inputs) else {
+        unreachable!("--follow takes a single input");
+    };
+    let name = input.name();
+    let mut once = args.clone();
+    once.follow = false;
+    once.snapshot = false;
+    once.decompress = false;
+    let (path, mut copy) = match &input {
+        Input::File(path) => (path.clone(), None),
+        _ => {
+            let copy = Spill::create_temp().unwrap_or_else(|e| {
+                eprintln!("trunc: --snapshot: {}", e);
+                process::exit(1);
+            });
+            (copy.path().to_path_buf(), Some(copy))
+        }
+    };
+    // Lines only say when there's more; each snapshot reads them properly
+    let reading = Reading {
+        lossy: true,
+        cut: None,
+        raw: copy.is_some(),
+    };
+    let events = input
+        .events(Some(args.refresh), reading, &Spare::default())
+        .unwrap_or_else(|e| {
+            eprintln!("trunc: {}: {}", name, e);
+            process::exit(1);
+        });
+    let clear = io::stdout().is_terminal();
+    let mut taken = 0;
+    let mut take = |copy: &mut Option<Spill>| {
+        if let Some(copy) = copy {
+            if let Err(e) = copy.flush() {
+                eprintln!("Error writing {}: {}", copy.path().display(), e);
+                process::exit(1);
+            }
+        }
+        taken += 1;
+        if clear {
+            clear_screen();
+        } else {
+            let mut stdout = io::stdout();
+            let _ = writeln!(stdout, "==> snapshot {} <==", taken).and_then(|_| stdout.flush());
+        }
+        run(&once, vec![Input::File(path.clone())])
+    };
+    let mut last = None;
+    let mut fresh = false;
+    for event in events {
+        match event {
+            Ok(Event::Raw(bytes)) => {
+                if let Some(copy) = &mut copy {
+                    if let Err(e) = copy.write(&bytes) {
+                        eprintln!("Error writing {}: {}", copy.path().display(), e);
+                        process::exit(1);
+                    }
+                }
+            }
+            Ok(Event::Line(_)) => fresh = true,
+            Ok(Event::Tick) if fresh => {
+                fresh = false;
+                last = Some(take(&mut copy));
+            }
+            Ok(_) => {}
+            Err(e) => {
+                eprintln!("Error reading input: {}", e);
+                process::exit(1);
+            }
+        }
+    }
+    let outcome = match last {
+        Some(outcome) if !fresh => outcome,
+        _ => take(&mut copy),
+    };
+    if let Some(copy) = copy {
+        let _ = std::fs::remove_file(copy.path());
+    }
+    process::exit(outcome.exit_code(args));
+}
+
+/// Clear a terminal and put the cursor top left, before a re-render.
+pub(crate) fn clear_screen() {
+    let mut stdout = io::stdout();
+    let _ = write!(stdout, "\x1b[2J\x1b[H").and_then(|_| stdout.flush());
+}
@@ -0,0 +1,121 @@
+//! Spill files: the complete input, written by `--save` or `--tee` while
+//! it's truncated, so the byte range a marker names can be read back later.
+//!
+//! The file is the input's bytes as read, line endings and all, so marker
+//! offsets are byte offsets into it. Alongside it, `<file>.idx` lists
+//! the byte range each marker hid, one `<start> <end>` line per region, for
+//! `trunc show --region`.
+
+use std::collections::hash_map::RandomState;
+use std::fs::{self, File, OpenOptions};
+use std::hash::{BuildHasher, Hasher};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use crate::cli::flusher::Flusher;
+
+/// Writes a spill file as lines arrive.
+pub struct Spill {
+    file: Flusher<File>,
+    path: PathBuf,
+}
+
+impl Spill {
+    pub fn create(path: &Path) -> io::Result<Self> {
+        Spill::open(path, None)
+    }
+
+    /// As [`Spill::create`], for `--tee`: the file is flushed every
+    /// `interval`, so it can be read or followed while the input runs.
+    pub fn tee(path: &Path, interval: Duration) -> io::Result<Self> {
+        Spill::open(path, Some(interval))
+    }
+
+    fn open(path: &Path, interval: Option<Duration>) -> io::Result<Self> {
+        Ok(Spill {
+            file: Flusher::new(File::create(path)?, interval),
+            path: path.to_path_buf(),
+        })
+    }
+
+    /// A new file in the temp directory, named `trunc-<random>.log`.
+    pub fn create_temp() -> io::Result<Self> {
+        loop {
+            let id = RandomState::new().build_hasher().finish() as u32;
+            let path = std::env::temp_dir().join(format!("trunc-{:08x}.log", id));
+            match OpenOptions::new().write(true).create_new(true).open(&path) {
+                Ok(file) => {
+                    return Ok(Spill {
+                        file: Flusher::new(file, None),
+                        path,
+                    })
+                }
+                Err(e) if e.kind() == io::ErrorKind::AlreadyExists => continue,
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Copy `bytes` of the input, a line's [`Event::Raw`](crate::cli::input::Event::Raw).
+    pub fn write(&mut self, bytes: &[u8]) -> io::Result<()> {
+        self.file.write_all(bytes)
+    }
+
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.file.flush()
+    }
+
+    /// Flush the file and write the index of `regions`.
+    pub fn finish(mut self, regions: &[Range<usize>]) -> io::Result<()> {
+        self.file.flush()?;
+        let index: String = regions
+            .iter()
+            .map(|r| format!("{} {}\n", r.start, r.end))
+            .collect();
+        fs::write(index_path(&self.path), index)
+    }
+}
+
+/// Where the index for the spill file at `path` goes.
+fn index_path(path: &Path) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(".idx");
+    name.into()
+}
+
+/// The byte range region `n` (from 1) of the spill file at `path` hid.
+pub fn region(path: &Path, n: usize) -> io::Result<Range<usize>> {
+    let invalid = |msg: String| io::Error::new(io::ErrorKind::InvalidData, msg);
+    let index = fs::read_to_string(index_path(path)).map_err(|e| match e.kind() {
+        io::ErrorKind::NotFound => {
+            invalid("no index alongside (was it written by --save or --tee?)".into())
+        }
+        _ => e,
+    })?;
+    let count = index.lines().count();
+    let line = n
+        .checked_sub(1)
+        .and_then(|i| index.lines().nth(i))
+        .ok_or_else(|| invalid(format!("no region {} ({} recorded)", n, count)))?;
+    let parse = |s: Option<&str>| s.and_then(|s| s.parse().ok());
+    let mut fields = line.split(' ');
+    match (parse(fields.next()), parse(fields.next())) {
+        (Some(start), Some(end)) => Ok(start..end),
+        _ => Err(invalid(format!("bad index line: {}", line))),
+    }
+}
+
+/// Copy bytes `range` of the spill file at `path` to `out`.
+pub fn show(path: &Path, range: Range<usize>, out: &mut impl Write) -> io::Result<()> {
+    let mut file = File::open(path)?;
+    file.seek(SeekFrom::Start(range.start as u64))?;
+    let len = range.end.saturating_sub(range.start) as u64;
+    io::copy(&mut file.take(len), out)?;
+    out.flush()
+}
@@ -0,0 +1,140 @@
+//! `--stats` / `--stats-json`: a summary of what was read and what made it
+//! into the output, for judging whether a re-run with bigger budgets is
+//! worthwhile.
+
+use crate::Region;
+use std::io::{self, Write};
+use std::time::Duration;
+
+/// Totals for one run.
+pub struct Stats {
+    pub lines_read: usize,
+    pub bytes_read: usize,
+    pub lines_emitted: usize,
+    pub bytes_emitted: usize,
+    /// Matches found and shown, with a pattern
+    pub matches: Option<(usize, usize)>,
+    /// In chars
+    pub longest_line: usize,
+    pub elapsed: Duration,
+    /// What each marker hid (recorded for `--stats-json`)
+    pub regions: Vec<Region>,
+}
+
+impl Stats {
+    /// The stats block, one `name: value` line each.
+    pub fn write(&self, out: &mut impl Write) -> io::Result<()> {
+        writeln!(out, "[trunc stats]")?;
+        writeln!(out, "lines read:    {}", self.lines_read)?;
+        writeln!(out, "bytes read:    {}", self.bytes_read)?;
+        writeln!(out, "lines emitted: {}", self.lines_emitted)?;
+        writeln!(out, "bytes emitted: {}", self.bytes_emitted)?;
+        if let Some((found, shown)) = self.matches {
+            writeln!(out, "matches:       {} found, {} shown", found, shown)?;
+        }
+        writeln!(out, "longest line:  {} chars", self.longest_line)?;
+        writeln!(out, "elapsed:       {:.1?}", self.elapsed)
+    }
+
+    /// The same stats as one line of JSON, with a `regions` array giving
+    /// each marker's id, input, lines and bytes. Matches are `null` without
+    /// a pattern, as are a region's lines if it only hid matches.
+    pub fn json(&self) -> String {
+        let (found, shown) = match self.matches {
+            Some((found, shown)) => (found.to_string(), shown.to_string()),
+            None => ("null".to_string(), "null".to_string()),
+        };
+        let regions: Vec<String> = self
+            .regions
+            .iter()
+            .enumerate()
+            .map(|(i, r)| {
+                let (first, last) = (*r.lines.start(), *r.lines.end());
+                let (first_line, last_line) = if first <= last {
+                    (first.to_string(), last.to_string())
+                } else {
+                    ("null".to_string(), "null".to_string())
+                };
+                format!(
+                    concat!(
+                        "{{\"id\":{},\"input\":{},\"lines\":{},\"first_line\":{},",
+                        "\"last_line\":{},\"byte_start\":{},\"byte_end\":{}}}"
+                    ),
+                    i + 1,
+                    r.input.as_deref().map_or("null".to_string(), json_string),
+                    (last + 1).saturating_sub(first),
+                    first_line,
+                    last_line,
+                    r.bytes.start,
+                    r.bytes.end
+                )
+            })
+            .collect();
+        format!(
+            concat!(
+                "{{\"lines_read\":{},\"bytes_read\":{},\"lines_emitted\":{},",
+                "\"bytes_emitted\":{},\"matches_found\":{},\"matches_shown\":{},",
+                "\"longest_line\":{},\"elapsed_ms\":{:.3},\"regions\":[{}]}}"
+            ),
+            self.lines_read,
+            self.bytes_read,
+            self.lines_emitted,
+            self.bytes_emitted,
+            found,
+            shown,
+            self.longest_line,
+            self.elapsed.as_secs_f64() * 1000.0,
+            regions.join(",")
+        )
+    }
+}
+
+/// `s` as a JSON string literal.
+fn json_string(s: &str) -> String {
+    let mut out = String::from("\"");
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+/// Where `--stats-json` goes: stderr for fd 2, else the already-open file
+/// descriptor `fd` (on Unix, e.g. `3>stats.json`).
+pub fn json_sink(fd: u32) -> io::Result<Box<dyn Write>> {
+    if fd == 2 {
+        return Ok(Box::new(io::stderr()));
+    }
+    inherited(fd)
+}
+
+/// A copy of inherited descriptor `fd`. It's written to rather than the
+/// file reopened, so it keeps its offset and `3>>stats.json` appends.
+#[cfg(unix)]
+fn inherited(fd: u32) -> io::Result<Box<dyn Write>> {
+    use std::fs::File;
+    use std::os::fd::FromRawFd;
+    // SAFETY: dup takes no pointers, and fails if fd isn't open
+    let copy = unsafe { libc::dup(fd as libc::c_int) };
+    if copy == -1 {
+        return Err(io::Error::last_os_error());
+    }
+    // SAFETY: dup succeeded, so the copy is open and ours alone
+    Ok(Box::new(unsafe { File::from_raw_fd(copy) }))
+}
+
+#[cfg(not(unix))]
+fn inherited(_fd: u32) -> io::Result<Box<dyn Write>> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "only fd 2 (stderr) is supported on this platform",
+    ))
+}
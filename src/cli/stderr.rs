@@ -0,0 +1,74 @@
+//! A wrapped command's stderr, under `--stderr merge` and `--stderr separate`.
+
+use crate::cli::args::Args;
+use crate::{Output, Stream, Truncator};
+use std::io;
+
+/// Prefix for a wrapped command's stderr lines under `--stderr merge`.
+pub(crate) const STDERR_TAG: &str = "[stderr] ";
+
+/// `--stderr separate`: a wrapped command's stderr, truncated on its own to
+/// trunc's stderr.
+pub(crate) struct StderrView<'a> {
+    out: Output<io::Stderr>,
+    /// None with percentage sizes or a match selection: lines wait in
+    /// `pending` until they've all arrived
+    stream: Option<Stream<'a>>,
+    pending: Vec<String>,
+    pub(crate) lines: usize,
+    bytes: usize,
+}
+
+impl<'a> StderrView<'a> {
+    pub(crate) fn new(args: &Args, truncator: &'a Truncator) -> Self {
+        let out = truncator.output(io::stderr());
+        StderrView {
+            stream: (!args.relative_sizes() && !truncator.needs_survey())
+                .then(|| truncator.stream(out.share(1, 0))),
+            out,
+            pending: Vec::new(),
+            lines: 0,
+            bytes: 0,
+        }
+    }
+
+    pub(crate) fn line(&mut self, content: String) {
+        self.lines += 1;
+        self.bytes += content.len() + 1;
+        match &mut self.stream {
+            Some(stream) => stream.line(&content, &mut self.out),
+            None => self.pending.push(content),
+        }
+    }
+
+    pub(crate) fn refresh(&mut self) {
+        if let Some(stream) = &mut self.stream {
+            stream.refresh(&mut self.out);
+        }
+    }
+
+    /// Finish the view. Returns true if anything was truncated.
+    pub(crate) fn finish(mut self, args: &Args, truncator: &Truncator) -> bool {
+        match self.stream {
+            Some(stream) => stream.finish(&mut self.out),
+            None => {
+                let sized = args.sized(truncator, self.pending.len());
+                let mut stream = sized.stream(self.out.share(1, 0));
+                if sized.needs_survey() {
+                    let mut survey = sized.survey();
+                    for content in &self.pending {
+                        survey.line(content);
+                    }
+                    stream = stream.shortlist(survey.finish());
+                }
+                for content in self.pending {
+                    stream.line(&content, &mut self.out);
+                }
+                stream.finish(&mut self.out);
+            }
+        }
+        let truncated = self.out.truncated(self.lines, self.bytes);
+        let _ = self.out.finish(self.lines, self.bytes);
+        truncated
+    }
+}
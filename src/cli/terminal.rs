@@ -0,0 +1,35 @@
+//! The size of the terminal trunc runs in, for sizing a `--pty` like it and
+//! for `--only-if-needed` to know what fits on the screen.
+
+use std::io::{self, IsTerminal};
+
+/// The size of the terminal `fd` is on, if it's one.
+#[cfg(unix)]
+pub fn size(fd: libc::c_int) -> Option<libc::winsize> {
+    let mut size = libc::winsize {
+        ws_row: 0,
+        ws_col: 0,
+        ws_xpixel: 0,
+        ws_ypixel: 0,
+    };
+    // SAFETY: TIOCGWINSZ writes a winsize, which `size` is
+    let got = unsafe { libc::ioctl(fd, libc::TIOCGWINSZ, &mut size) };
+    (got == 0).then_some(size)
+}
+
+/// Rows on the screen stdout is on, if it's a terminal.
+pub fn stdout_rows() -> Option<usize> {
+    if !io::stdout().is_terminal() {
+        return None;
+    }
+    #[cfg(unix)]
+    {
+        size(libc::STDOUT_FILENO)
+            .map(|size| usize::from(size.ws_row))
+            .filter(|&rows| rows > 0)
+    }
+    #[cfg(not(unix))]
+    {
+        None
+    }
+}
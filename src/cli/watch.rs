@@ -0,0 +1,99 @@
+//! Reading an input on a thread of its own, so the main loop can wake up on
+//! a timer while a read is blocked: `--idle-timeout` notices when input
+//! goes quiet, such as a hung build step, so the lines before the hang can
+//! be shown without waiting for an EOF that may never come, and
+//! `--heartbeat` says trunc is still reading a slow producer.
+
+use std::io;
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::time::{Duration, Instant};
+
+use crate::cli::follow;
+use crate::cli::input::{Event, Input, Reading};
+
+/// An input's events, with an [`Event::Stalled`] once nothing has arrived
+/// for the idle timeout, and an [`Event::Heartbeat`] every heartbeat
+/// interval. There's one stall per quiet spell: the next comes after input
+/// resumes and stops again.
+pub struct Watch {
+    events: Receiver<io::Result<Event>>,
+    idle_timeout: Option<Duration>,
+    /// When the last event arrived
+    last_event: Instant,
+    stalled: bool,
+    heartbeat: Option<Duration>,
+    next_beat: Option<Instant>,
+}
+
+impl Watch {
+    /// Start reading `input`'s events. Lines are [`crate::cli::input::decode`]d.
+    pub fn start(
+        input: Input,
+        idle_timeout: Option<Duration>,
+        heartbeat: Option<Duration>,
+        reading: Reading,
+    ) -> io::Result<Self> {
+        let (tx, rx) = mpsc::channel();
+        follow::read(input, false, reading, tx)?;
+        let now = Instant::now();
+        Ok(Watch {
+            events: rx,
+            idle_timeout,
+            last_event: now,
+            stalled: false,
+            heartbeat,
+            next_beat: heartbeat.map(|interval| now + interval),
+        })
+    }
+
+    /// When the input counts as stalled, unless it already has.
+    fn stall_at(&self) -> Option<Instant> {
+        let timeout = self.idle_timeout.filter(|_| !self.stalled)?;
+        Some(self.last_event + timeout)
+    }
+}
+
+impl Iterator for Watch {
+    type Item = io::Result<Event>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let now = Instant::now();
+            if let (Some(at), Some(interval)) = (self.next_beat, self.heartbeat) {
+                if now >= at {
+                    self.next_beat = Some(now + interval);
+                    return Some(Ok(Event::Heartbeat));
+                }
+            }
+            let stall_at = self.stall_at();
+            let wake = match (stall_at, self.next_beat) {
+                (Some(a), Some(b)) => Some(a.min(b)),
+                (a, b) => a.or(b),
+            };
+            let received = match wake {
+                Some(wake) => self
+                    .events
+                    .recv_timeout(wake.saturating_duration_since(now)),
+                None => self
+                    .events
+                    .recv()
+                    .map_err(|_| RecvTimeoutError::Disconnected),
+            };
+            match received {
+                Ok(event) => {
+                    self.last_event = Instant::now();
+                    self.stalled = false;
+                    return Some(event);
+                }
+                Err(RecvTimeoutError::Timeout) => {
+                    if stall_at.is_some_and(|at| Instant::now() >= at) {
+                        self.stalled = true;
+                        return Some(Ok(Event::Stalled));
+                    }
+                    // Otherwise it's time for a heartbeat
+                }
+                Err(RecvTimeoutError::Disconnected) => return None,
+            }
+        }
+    }
+}
@@ -0,0 +1,8 @@
+//! The clock the library reads: `std::time`, except in the browser
+//! (wasm32-unknown-unknown), where std's panics and `web-time` asks
+//! JavaScript instead.
+
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+pub(crate) use std::time::{Instant, SystemTime, UNIX_EPOCH};
+#[cfg(all(target_arch = "wasm32", target_os = "unknown"))]
+pub(crate) use web_time::{Instant, SystemTime, UNIX_EPOCH};
@@ -0,0 +1,108 @@
+//! ANSI color for `--color`: matched text in shown match lines is
+//! highlighted, and markers are dimmed so they stand apart from the input.
+
+use std::ops::Range;
+use std::sync::Arc;
+
+use crate::ansi::RESET;
+use crate::matcher::{Matcher, Pattern};
+
+/// Bold red, like grep's default match color.
+const MATCH: &str = "\x1b[1;31m";
+const DIM: &str = "\x1b[2m";
+
+/// Bytes that dimming adds to a marker.
+pub(crate) const DIM_OVERHEAD: usize = DIM.len() + RESET.len();
+
+/// Wrap a whole marker line in the dim style.
+pub(crate) fn dim(text: &str) -> String {
+    format!("{}{}{}", DIM, text, RESET)
+}
+
+/// Finds the spans of a line to highlight.
+///
+/// A `RegexSet` only says which patterns matched, not where, so the
+/// patterns are compiled individually for this.
+#[derive(Debug)]
+pub(crate) enum Highlighter {
+    Patterns(Vec<Arc<dyn Pattern>>),
+    Literals(Vec<String>),
+}
+
+impl Highlighter {
+    pub(crate) fn new(matcher: &Matcher) -> Self {
+        match matcher {
+            Matcher::Literal(needles) => Highlighter::Literals(needles.clone()),
+            _ => Highlighter::Patterns(matcher.patterns()),
+        }
+    }
+
+    /// Non-empty matched byte ranges of `text`, sorted, with overlaps merged.
+    pub(crate) fn spans(&self, text: &str) -> Vec<Range<usize>> {
+        let mut spans: Vec<Range<usize>> = match self {
+            Highlighter::Patterns(patterns) => {
+                patterns.iter().flat_map(|p| p.find_all(text)).collect()
+            }
+            Highlighter::Literals(needles) => needles
+                .iter()
+                .filter(|n| !n.is_empty())
+                .flat_map(|n| text.match_indices(n.as_str()).map(|(i, m)| i..i + m.len()))
+                .collect(),
+        };
+        spans.retain(|s| !s.is_empty());
+        spans.sort_by_key(|s| s.start);
+
+        let mut merged: Vec<Range<usize>> = Vec::with_capacity(spans.len());
+        for span in spans {
+            match merged.last_mut() {
+                Some(last) if span.start <= last.end => last.end = last.end.max(span.end),
+                _ => merged.push(span),
+            }
+        }
+        merged
+    }
+
+    /// The first non-empty match in `text`, on its own: not merged with
+    /// those it touches, as [`Highlighter::spans`] merges them.
+    pub(crate) fn first(&self, text: &str) -> Option<Range<usize>> {
+        let spans: Vec<Range<usize>> = match self {
+            Highlighter::Patterns(patterns) => patterns
+                .iter()
+                .filter_map(|p| p.find_all(text).into_iter().find(|s| !s.is_empty()))
+                .collect(),
+            Highlighter::Literals(needles) => needles
+                .iter()
+                .filter(|n| !n.is_empty())
+                .filter_map(|n| text.find(n.as_str()).map(|i| i..i + n.len()))
+                .collect(),
+        };
+        spans.into_iter().min_by_key(|s| (s.start, s.end))
+    }
+}
+
+/// Color `text`: highlight `spans` and dim the within-line truncation
+/// `markers`. Spans that touch a marker are left alone, since they'd be
+/// matching the marker's own text or a match cut in half by it.
+pub(crate) fn paint(text: &str, spans: &[Range<usize>], markers: &[Range<usize>]) -> String {
+    let overlaps_marker =
+        |s: &Range<usize>| markers.iter().any(|m| s.start < m.end && m.start < s.end);
+    let mut regions: Vec<(Range<usize>, &str)> = spans
+        .iter()
+        .filter(|s| !overlaps_marker(s))
+        .map(|s| (s.clone(), MATCH))
+        .collect();
+    regions.extend(markers.iter().map(|m| (m.clone(), DIM)));
+    regions.sort_by_key(|(r, _)| r.start);
+
+    let mut painted = String::with_capacity(text.len() + regions.len() * 12);
+    let mut pos = 0;
+    for (range, style) in regions {
+        painted.push_str(&text[pos..range.start]);
+        painted.push_str(style);
+        painted.push_str(&text[range.clone()]);
+        painted.push_str(RESET);
+        pos = range.end;
+    }
+    painted.push_str(&text[pos..]);
+    painted
+}
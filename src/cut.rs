@@ -0,0 +1,73 @@
+//! [`Cut`]: cutting a line short as it comes in, so a giant line with no
+//! newline is never held whole (`--max-line-bytes`, and
+//! [`Truncator::max_line_bytes`](crate::Truncator::max_line_bytes)).
+
+/// Longest note [`Cut`] leaves in a line, for a count of `usize::MAX`.
+const NOTE_MAX: usize = "[... 18446744073709551615 bytes ...]".len();
+
+/// How far past its two ends a line being cut grows before its middle is
+/// dropped, so it isn't dropped on every read.
+const SLACK: usize = 64 * 1024;
+
+/// Cutting a line short as it's read, so a giant line with no newline is
+/// never held whole: past `over` bytes, only the first `keep` and a rolling
+/// window of the last `keep` are kept, with a `[... N bytes ...]` note of
+/// what was cut between.
+#[derive(Clone, Copy, Debug)]
+pub struct Cut {
+    over: usize,
+    keep: usize,
+}
+
+impl Cut {
+    /// Cut lines over `max` bytes, for output `width` wide (0 for no
+    /// limit). Within a width, the line is left no wider than `-w` would
+    /// leave it, so it isn't truncated again and the note stays in view.
+    pub fn new(max: usize, width: usize) -> Self {
+        let keep = match width {
+            0 => max / 2,
+            width => (max / 2).min(width.saturating_sub(NOTE_MAX.div_ceil(2))),
+        };
+        Cut { over: max, keep }
+    }
+
+    /// Drop the middle of `buf`, a line still coming in, if it's grown
+    /// well past what's kept of it. Returns the bytes dropped.
+    pub(crate) fn trim(&self, buf: &mut Vec<u8>) -> usize {
+        if buf.len() > self.over && buf.len() > 2 * self.keep + SLACK {
+            buf.drain(self.keep..buf.len() - self.keep).len()
+        } else {
+            0
+        }
+    }
+
+    /// Cut `buf`, a whole line with any newline and `dropped` bytes already
+    /// trimmed, to its ends around a note, if it's too long. Returns how
+    /// many more bytes the line had than `buf` now holds.
+    pub(crate) fn finish(&self, buf: &mut Vec<u8>, mut dropped: usize) -> usize {
+        if buf.len() <= self.over && dropped == 0 {
+            return 0;
+        }
+        let newline = buf.last() == Some(&b'\n');
+        if newline {
+            buf.pop();
+        }
+        let keep = self.keep.min(buf.len() / 2);
+        dropped += buf.drain(keep..buf.len() - keep).len();
+        // Neither end keeps part of a char
+        let head_end = match std::str::from_utf8(&buf[..keep]) {
+            Err(e) if e.error_len().is_none() => e.valid_up_to(),
+            _ => keep,
+        };
+        let tail_start = (keep..buf.len())
+            .find(|&i| buf[i] & 0xC0 != 0x80)
+            .unwrap_or(buf.len());
+        dropped += tail_start - head_end;
+        let note = format!("[... {} bytes ...]", dropped);
+        buf.splice(head_end..tail_start, note.bytes());
+        if newline {
+            buf.push(b'\n');
+        }
+        dropped.saturating_sub(note.len())
+    }
+}
@@ -0,0 +1,221 @@
+//! Truncating the string values of a JSON line (`--json-values`), so a
+//! JSONL log stays parseable where cutting the middle out of the line would
+//! break it.
+
+/// Cut each string value in `line` longer than `width` chars to its first
+/// `width`, followed by `…[+N chars]`. Keys are left alone, as is
+/// everything between strings. None if `line` isn't JSON.
+///
+/// Chars are counted as written, so an escape (`\n`, `\u00e9`, or a
+/// surrogate pair of them) is one char and is never split.
+pub(crate) fn truncate_values(line: &str, width: usize) -> Option<String> {
+    let mut parser = Parser {
+        bytes: line.as_bytes(),
+        pos: 0,
+        values: Vec::new(),
+    };
+    parser.whitespace();
+    parser.value()?;
+    parser.whitespace();
+    if parser.pos != line.len() {
+        return None;
+    }
+
+    let mut out = String::with_capacity(line.len());
+    let mut copied = 0;
+    for (start, end) in parser.values {
+        let chars = Chars {
+            text: &line[start..end],
+        };
+        let lengths: Vec<usize> = chars.collect();
+        if lengths.len() <= width {
+            continue;
+        }
+        let kept: usize = lengths[..width].iter().sum();
+        let marker = format!("…[+{} chars]", lengths.len() - width);
+        // Only cut if the value comes out strictly shorter
+        if start + kept + marker.len() >= end {
+            continue;
+        }
+        out.push_str(&line[copied..start + kept]);
+        out.push_str(&marker);
+        copied = end;
+    }
+    out.push_str(&line[copied..]);
+    Some(out)
+}
+
+/// Byte lengths of the chars of a JSON string's contents, an escape
+/// counting as one.
+struct Chars<'a> {
+    text: &'a str,
+}
+
+impl Iterator for Chars<'_> {
+    type Item = usize;
+
+    fn next(&mut self) -> Option<usize> {
+        let c = self.text.chars().next()?;
+        let len = match self.text.as_bytes() {
+            [b'\\', b'u', ..]
+                if is_high_surrogate(&self.text[2..]) && self.text[6..].starts_with("\\u") =>
+            {
+                12
+            }
+            [b'\\', b'u', ..] => 6,
+            [b'\\', ..] => 2,
+            _ => c.len_utf8(),
+        };
+        self.text = &self.text[len..];
+        Some(len)
+    }
+}
+
+/// Whether `hex` starts with the 4 hex digits of a UTF-16 high surrogate,
+/// which takes the `\u` escape after it to make one char.
+fn is_high_surrogate(hex: &str) -> bool {
+    hex.get(..4)
+        .and_then(|hex| u16::from_str_radix(hex, 16).ok())
+        .is_some_and(|unit| (0xD800..0xDC00).contains(&unit))
+}
+
+/// A JSON validator noting where each string value's contents are.
+struct Parser<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+    /// Byte ranges of string values' contents, between the quotes
+    values: Vec<(usize, usize)>,
+}
+
+impl Parser<'_> {
+    fn peek(&self) -> Option<u8> {
+        self.bytes.get(self.pos).copied()
+    }
+
+    fn whitespace(&mut self) {
+        while matches!(self.peek(), Some(b' ' | b'\t' | b'\n' | b'\r')) {
+            self.pos += 1;
+        }
+    }
+
+    fn eat(&mut self, byte: u8) -> Option<()> {
+        (self.peek() == Some(byte)).then(|| self.pos += 1)
+    }
+
+    fn literal(&mut self, word: &str) -> Option<()> {
+        let end = self.pos + word.len();
+        (self.bytes.get(self.pos..end) == Some(word.as_bytes())).then(|| self.pos = end)
+    }
+
+    fn value(&mut self) -> Option<()> {
+        match self.peek()? {
+            b'{' => self.object(),
+            b'[' => self.array(),
+            b'"' => {
+                let contents = self.string()?;
+                self.values.push(contents);
+                Some(())
+            }
+            b't' => self.literal("true"),
+            b'f' => self.literal("false"),
+            b'n' => self.literal("null"),
+            _ => self.number(),
+        }
+    }
+
+    fn object(&mut self) -> Option<()> {
+        self.eat(b'{')?;
+        self.whitespace();
+        if self.eat(b'}').is_some() {
+            return Some(());
+        }
+        loop {
+            self.whitespace();
+            self.string()?;
+            self.whitespace();
+            self.eat(b':')?;
+            self.whitespace();
+            self.value()?;
+            self.whitespace();
+            if self.eat(b'}').is_some() {
+                return Some(());
+            }
+            self.eat(b',')?;
+        }
+    }
+
+    fn array(&mut self) -> Option<()> {
+        self.eat(b'[')?;
+        self.whitespace();
+        if self.eat(b']').is_some() {
+            return Some(());
+        }
+        loop {
+            self.whitespace();
+            self.value()?;
+            self.whitespace();
+            if self.eat(b']').is_some() {
+                return Some(());
+            }
+            self.eat(b',')?;
+        }
+    }
+
+    /// A string, returning the byte range of its contents.
+    fn string(&mut self) -> Option<(usize, usize)> {
+        self.eat(b'"')?;
+        let start = self.pos;
+        loop {
+            match self.peek()? {
+                b'"' => break,
+                b'\\' => {
+                    self.pos += 1;
+                    match self.peek()? {
+                        b'"' | b'\\' | b'/' | b'b' | b'f' | b'n' | b'r' | b't' => self.pos += 1,
+                        b'u' => {
+                            let hex = self.bytes.get(self.pos + 1..self.pos + 5)?;
+                            if !hex.iter().all(u8::is_ascii_hexdigit) {
+                                return None;
+                            }
+                            self.pos += 5;
+                        }
+                        _ => return None,
+                    }
+                }
+                byte if byte < 0x20 => return None,
+                _ => self.pos += 1,
+            }
+        }
+        let end = self.pos;
+        self.pos += 1;
+        Some((start, end))
+    }
+
+    fn number(&mut self) -> Option<()> {
+        self.eat(b'-');
+        match self.peek()? {
+            b'0' => self.pos += 1,
+            b'1'..=b'9' => self.digits(),
+            _ => return None,
+        }
+        if self.eat(b'.').is_some() {
+            self.peek().filter(u8::is_ascii_digit)?;
+            self.digits();
+        }
+        if matches!(self.peek(), Some(b'e' | b'E')) {
+            self.pos += 1;
+            if matches!(self.peek(), Some(b'+' | b'-')) {
+                self.pos += 1;
+            }
+            self.peek().filter(u8::is_ascii_digit)?;
+            self.digits();
+        }
+        Some(())
+    }
+
+    fn digits(&mut self) {
+        while self.peek().is_some_and(|byte| byte.is_ascii_digit()) {
+            self.pos += 1;
+        }
+    }
+}
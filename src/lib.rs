@@ -0,0 +1,980 @@
+//! trunc - Smart truncation for pipe output
+//!
+//! Shows the first N and last M lines of a stream, with an optional
+//! pattern-matching mode that extracts matches from the middle. This is the
+//! library behind the `trunc` command, for tools that want the same
+//! truncation without shelling out.
+//!
+//! Streams output: first lines appear immediately, matches stream as found,
+//! only the tail waits for EOF.
+//!
+//! ```
+//! use trunc::Truncator;
+//!
+//! let input: String = (1..=100).map(|i| format!("line {}\n", i)).collect();
+//! let mut out = Vec::new();
+//! Truncator::new()
+//!     .first(2)
+//!     .last(2)
+//!     .process(input.as_bytes(), &mut out)
+//!     .unwrap();
+//!
+//! assert_eq!(
+//!     String::from_utf8(out).unwrap(),
+//!     "line 1\nline 2\n[... 96 lines truncated ...]\nline 99\nline 100\n"
+//! );
+//! ```
+//!
+//! To truncate output as it's written instead, use [`Truncator::writer`];
+//! for the lines shown and what was hidden as items to render yourself,
+//! [`TruncatedLines`]. With the `async` feature, `trunc::aio` does both
+//! over tokio's async I/O.
+//! For several inputs sharing one output (as the CLI does with multiple
+//! files), create the [`Output`] and one [`Stream`] per input yourself.
+
+#[cfg(feature = "async")]
+pub mod aio;
+mod ansi;
+mod arrival;
+mod block;
+#[cfg(feature = "cli")]
+#[doc(hidden)]
+pub mod cli;
+mod clock;
+mod color;
+mod cut;
+mod json;
+mod lines;
+mod logfmt;
+mod longest;
+mod marker;
+mod matcher;
+mod normalize;
+mod output;
+mod predicate;
+mod progress;
+mod redact;
+mod reservoir;
+mod run;
+mod sample;
+mod scan;
+mod select;
+mod stream;
+mod summary;
+mod table;
+mod timestamp;
+mod traceback;
+mod unique;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+mod width;
+mod writer;
+
+pub use block::Lang;
+pub use lines::{TruncatedItem, TruncatedLines};
+pub use marker::{output_format, MarkerStyle, Region};
+pub use matcher::Pattern;
+pub use normalize::Normalizer;
+pub use output::{truncate_line, truncate_line_cut, FlushPolicy, Output, TruncatedLine};
+pub use predicate::Predicate;
+pub use redact::Redactor;
+pub use scan::Scanned;
+pub use select::{Selection, Shortlist, Survey};
+pub use stream::Stream;
+pub use timestamp::parse_timestamp;
+pub use width::{LineCut, WidthMode};
+pub use writer::TruncWriter;
+
+use clock::SystemTime;
+use cut::Cut;
+use matcher::Matcher;
+use regex::{Regex, RegexSet};
+use run::Run;
+use std::io::{self, BufRead, Write};
+use std::ops::RangeInclusive;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+use timestamp::Window;
+
+/// Kinds of trailer line that can be appended after the output.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Trailer {
+    /// SHA-256 of everything emitted before the trailer, plus hidden line/byte counts
+    Checksum,
+}
+
+/// What [`Truncator::timestamps`] prefixes each line with.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Timestamps {
+    /// When trunc read it, in UTC: `2024-05-01T12:00:00.123Z`
+    Wall,
+    /// Seconds since trunc started: `+12.345s`
+    Relative,
+}
+
+/// How [`Truncator::rewrite_timestamps`] rewrites the timestamps lines
+/// start with.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TimestampRewrite {
+    /// Drop it, with any brackets or key around it and the space after
+    Strip,
+    /// Replace it with the time since the input's first: `+12.345s`
+    Relative,
+}
+
+/// Truncation settings. Defaults match the `trunc` command.
+#[derive(Clone, Debug)]
+pub struct Truncator {
+    first: usize,
+    header: usize,
+    last: usize,
+    first_bytes: Option<usize>,
+    last_bytes: Option<usize>,
+    matches: usize,
+    before: usize,
+    after: usize,
+    context_until: Option<Regex>,
+    width: usize,
+    /// Head, match and tail widths, if they differ from `width`
+    section_widths: [Option<usize>; 3],
+    width_mode: WidthMode,
+    line_cut: LineCut,
+    json_values: bool,
+    table: bool,
+    patterns: Option<Matcher>,
+    match_limits: Vec<Option<usize>>,
+    match_filter: Option<Predicate>,
+    unique_by: Option<String>,
+    label_matches: bool,
+    exclude: Option<RegexSet>,
+    since: Option<SystemTime>,
+    until: Option<SystemTime>,
+    sample_per: Option<Duration>,
+    sample: Option<usize>,
+    seed: Option<u64>,
+    summarize: bool,
+    always: Option<RegexSet>,
+    ranges: Vec<RangeInclusive<usize>>,
+    every: Option<usize>,
+    longest: Option<usize>,
+    redactor: Option<Redactor>,
+    fields: Vec<String>,
+    normalizer: Option<Normalizer>,
+    collapse_progress: bool,
+    strip_ansi: bool,
+    line_numbers: bool,
+    match_line_numbers: bool,
+    count_only: bool,
+    selection: Option<Selection>,
+    color: bool,
+    max_bytes: Option<usize>,
+    max_memory: Option<usize>,
+    max_line_bytes: Option<usize>,
+    pass_through: Option<usize>,
+    rebalance: bool,
+    tail_matches: Option<usize>,
+    trailer: Option<Trailer>,
+    spill: Option<String>,
+    byte_ranges: bool,
+    elapsed: bool,
+    timestamps: Option<Timestamps>,
+    rewrite_timestamps: Option<TimestampRewrite>,
+    idempotent: bool,
+    region_ids: bool,
+    track_regions: bool,
+    marker_style: MarkerStyle,
+    records: bool,
+    block_aware: bool,
+    lang: Lang,
+    fold_tracebacks: bool,
+    flush_policy: FlushPolicy,
+}
+
+impl Default for Truncator {
+    fn default() -> Self {
+        Truncator {
+            first: 30,
+            header: 0,
+            last: 30,
+            first_bytes: None,
+            last_bytes: None,
+            matches: 5,
+            before: 3,
+            after: 3,
+            context_until: None,
+            width: 100,
+            section_widths: [None; 3],
+            width_mode: WidthMode::Chars,
+            line_cut: LineCut::Middle,
+            json_values: false,
+            table: false,
+            patterns: None,
+            match_limits: Vec::new(),
+            match_filter: None,
+            unique_by: None,
+            label_matches: false,
+            exclude: None,
+            since: None,
+            until: None,
+            sample_per: None,
+            sample: None,
+            seed: None,
+            summarize: false,
+            always: None,
+            ranges: Vec::new(),
+            every: None,
+            longest: None,
+            redactor: None,
+            fields: Vec::new(),
+            normalizer: None,
+            collapse_progress: true,
+            strip_ansi: false,
+            line_numbers: false,
+            match_line_numbers: false,
+            count_only: false,
+            selection: None,
+            color: false,
+            max_bytes: None,
+            max_memory: None,
+            max_line_bytes: None,
+            pass_through: None,
+            rebalance: false,
+            tail_matches: None,
+            trailer: None,
+            spill: None,
+            byte_ranges: false,
+            elapsed: false,
+            timestamps: None,
+            rewrite_timestamps: None,
+            idempotent: true,
+            region_ids: false,
+            track_regions: false,
+            marker_style: MarkerStyle::Verbose,
+            records: false,
+            block_aware: false,
+            lang: Lang::Generic,
+            fold_tracebacks: false,
+            flush_policy: FlushPolicy::Line,
+        }
+    }
+}
+
+impl Truncator {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Number of lines to show from start.
+    pub fn first(mut self, n: usize) -> Self {
+        self.first = n;
+        self
+    }
+
+    /// Also show the first `n` lines, such as a column header, ahead of
+    /// the [`Truncator::first`] lines: with `first(10)`, lines 1 to `n + 10`.
+    /// Part of the head when it's sized in bytes.
+    pub fn header(mut self, n: usize) -> Self {
+        self.header = n;
+        self
+    }
+
+    /// Lines the head shows: the header and the first lines.
+    pub(crate) fn head_lines(&self) -> usize {
+        self.first + self.header
+    }
+
+    /// Number of lines to show from end.
+    pub fn last(mut self, n: usize) -> Self {
+        self.last = n;
+        self
+    }
+
+    /// Instead of a number of lines, show as many whole lines from the start
+    /// as fit in `n` bytes of output.
+    pub fn first_bytes(mut self, n: usize) -> Self {
+        self.first_bytes = Some(n);
+        self
+    }
+
+    /// Instead of a number of lines, show as many whole lines from the end
+    /// as fit in `n` bytes of output.
+    pub fn last_bytes(mut self, n: usize) -> Self {
+        self.last_bytes = Some(n);
+        self
+    }
+
+    /// Max matches to show in pattern mode.
+    pub fn matches(mut self, n: usize) -> Self {
+        self.matches = n;
+        self
+    }
+
+    /// Lines of context around each match: shorthand for both
+    /// [`before_context`](Self::before_context) and
+    /// [`after_context`](Self::after_context).
+    pub fn context(self, n: usize) -> Self {
+        self.before_context(n).after_context(n)
+    }
+
+    /// Lines of context before each match.
+    pub fn before_context(mut self, n: usize) -> Self {
+        self.before = n;
+        self
+    }
+
+    /// Lines of context after each match.
+    pub fn after_context(mut self, n: usize) -> Self {
+        self.after = n;
+        self
+    }
+
+    /// End each match's "after" context at the next line matching `re`
+    /// (a blank line, `^----`, the next timestamp), in place of a fixed
+    /// [`after_context`](Self::after_context) count. That line isn't shown,
+    /// and the context stops after 50 lines if none comes.
+    pub fn context_until(mut self, re: Regex) -> Self {
+        self.context_until = Some(re);
+        self
+    }
+
+    /// Chars to show at start/end of long lines (0 = no limit).
+    pub fn width(mut self, n: usize) -> Self {
+        self.width = n;
+        self
+    }
+
+    /// [`Truncator::width`] for head lines alone.
+    pub fn head_width(mut self, n: usize) -> Self {
+        self.section_widths[0] = Some(n);
+        self
+    }
+
+    /// [`Truncator::width`] for match lines and their context alone, which
+    /// usually deserve more than the head and tail.
+    pub fn match_width(mut self, n: usize) -> Self {
+        self.section_widths[1] = Some(n);
+        self
+    }
+
+    /// [`Truncator::width`] for tail lines alone.
+    pub fn tail_width(mut self, n: usize) -> Self {
+        self.section_widths[2] = Some(n);
+        self
+    }
+
+    /// Whether the width counts chars or terminal columns.
+    pub fn width_mode(mut self, mode: WidthMode) -> Self {
+        self.width_mode = mode;
+        self
+    }
+
+    /// Which part of a long line to cut out: the middle (the default)
+    /// keeps the width at each end; the start or end keeps twice the width
+    /// of the other end, such as a path's file name.
+    pub fn line_cut(mut self, cut: LineCut) -> Self {
+        self.line_cut = cut;
+        self
+    }
+
+    /// Width-truncate a line that's JSON one string value at a time, so it
+    /// stays JSON: a value longer than the width keeps its first `width`
+    /// chars and ends `…[+12000 chars]`. Keys are never cut. Lines that
+    /// aren't JSON are truncated as usual.
+    pub fn json_values(mut self, yes: bool) -> Self {
+        self.json_values = yes;
+        self
+    }
+
+    /// Width-truncate CSV and TSV rows a column at a time: a row that's too
+    /// long loses its middle columns to a `…+8 cols…` cell. Which columns
+    /// go is decided by the header (line 1) if it's too long itself, so the
+    /// rows line up with it. Doesn't show the header; see
+    /// [`Truncator::lines`].
+    pub fn table(mut self, yes: bool) -> Self {
+        self.table = yes;
+        self
+    }
+
+    /// Show matches of `re` from the middle section.
+    pub fn pattern(self, re: Regex) -> Self {
+        let set = RegexSet::new([re.as_str()]).expect("a valid regex is a valid set");
+        self.patterns(set)
+    }
+
+    /// Show lines matching any pattern in `set` from the middle section.
+    pub fn patterns(self, set: RegexSet) -> Self {
+        let labels = set.patterns().to_vec();
+        self.named_patterns(set, labels)
+    }
+
+    /// Like [`Truncator::patterns`], but with [`Truncator::label_matches`]
+    /// markers name each pattern by the corresponding entry of `names`
+    /// rather than by the pattern itself.
+    ///
+    /// # Panics
+    ///
+    /// If `names` doesn't have one entry per pattern in `set`.
+    pub fn named_patterns<I, S>(mut self, set: RegexSet, names: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        let labels: Vec<String> = names.into_iter().map(Into::into).collect();
+        assert_eq!(labels.len(), set.len(), "one name per pattern");
+        self.patterns = Some(Matcher::Regex { set, labels });
+        self
+    }
+
+    /// Show lines matching any of `patterns` from the middle section, each
+    /// compiled by a regex engine of the caller's choosing rather than
+    /// into one [`RegexSet`]: PCRE2's, with the `pcre2` feature, for
+    /// look-around and backreferences. Patterns are tried one by one, so
+    /// this is slower than [`Truncator::patterns`] for many of them.
+    pub fn engine_patterns<I, P>(mut self, patterns: I) -> Self
+    where
+        I: IntoIterator<Item = P>,
+        P: Pattern + 'static,
+    {
+        let patterns = patterns
+            .into_iter()
+            .map(|p| Arc::new(p) as Arc<dyn Pattern>)
+            .collect();
+        self.patterns = Some(Matcher::Engine(patterns));
+        self
+    }
+
+    /// Show lines containing any of `needles` from the middle section,
+    /// matched as plain substrings rather than regexes.
+    pub fn fixed_strings<I, S>(mut self, needles: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        let needles = needles.into_iter().map(Into::into).collect();
+        self.patterns = Some(Matcher::Literal(needles));
+        self
+    }
+
+    /// Give patterns match budgets of their own, in the order the patterns
+    /// were given: a pattern with `Some(n)` shows at most `n` matches, and
+    /// their markers name it (`match 2 of 'WARN' shown`); patterns with
+    /// `None`, or past the end of `limits`, share the
+    /// [`matches`](Self::matches) budget. A line matching several patterns
+    /// is charged to the first with budget left.
+    pub fn match_limits<I>(mut self, limits: I) -> Self
+    where
+        I: IntoIterator<Item = Option<usize>>,
+    {
+        self.match_limits = limits.into_iter().collect();
+        self
+    }
+
+    /// Count a line as a match only if it also satisfies `predicate`, such
+    /// as [`Predicate::all_of`] further regexes, or the
+    /// [`negate`](Predicate::negate)d [`Predicate::any_of`] ones to rule
+    /// out. Lines failing it are still shown as context, head or tail.
+    pub fn match_filter(mut self, predicate: Predicate) -> Self {
+        self.match_filter = Some(predicate);
+        self
+    }
+
+    /// Show a match only if no match shown before gave the same `template`
+    /// (`$1`, `${code}`) expanded with its captures, such as the same error
+    /// code or test name. How many were passed over is noted in the next
+    /// marker. The captures are those of the first pattern matching the
+    /// line.
+    pub fn unique_by(mut self, template: impl Into<String>) -> Self {
+        self.unique_by = Some(template.into());
+        self
+    }
+
+    /// Say which pattern(s) matched in each match marker.
+    pub fn label_matches(mut self, yes: bool) -> Self {
+        self.label_matches = yes;
+        self
+    }
+
+    /// Drop lines matching any pattern in `set` before anything else sees
+    /// them: they're never shown, counted as matches or charged to a budget.
+    /// How many were dropped is noted in a marker before the tail.
+    pub fn exclude(mut self, set: RegexSet) -> Self {
+        self.exclude = Some(set);
+        self
+    }
+
+    /// Drop lines timestamped before `time`, as [`Truncator::exclude`]
+    /// does. A line without a timestamp goes with the last one that had
+    /// one; see [`parse_timestamp`] for the formats read.
+    pub fn since(mut self, time: SystemTime) -> Self {
+        self.since = Some(time);
+        self
+    }
+
+    /// Drop lines timestamped after `time`, as for [`Truncator::since`].
+    pub fn until(mut self, time: SystemTime) -> Self {
+        self.until = Some(time);
+        self
+    }
+
+    /// Without a pattern, show the first timestamped line of each `every`
+    /// of time in the middle, then a marker of how many lines and how long
+    /// a stretch they were sampled from: `[... 9812 lines across 00:03:41
+    /// sampled to 221 ...]`. Lines without a timestamp are never sampled;
+    /// see [`parse_timestamp`] for the formats read.
+    pub fn sample_per(mut self, every: Duration) -> Self {
+        self.sample_per = Some(every);
+        self
+    }
+
+    /// Without a pattern, show `k` lines picked at random from those the
+    /// marker before the tail would hide, numbered, in input order, each
+    /// gap between them with a marker of its own. Every hidden line is
+    /// equally likely to be picked, however long the middle. Ignored with
+    /// [`Truncator::sample_per`].
+    pub fn sample(mut self, k: usize) -> Self {
+        self.sample = Some(k);
+        self
+    }
+
+    /// Pick the same [`Truncator::sample`] lines for the same input on
+    /// every run. Without a seed they differ run to run.
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+
+    /// Without a pattern, say what the marker before the tail hides: lines
+    /// per log level, how many distinct lines, and the most frequent ones,
+    /// with timestamps, ids and numbers stripped as by
+    /// [`Normalizer::fuzzy`]: `[... 4200 lines truncated: 3900 INFO, 240
+    /// WARN, 60 unique; top: "GET /health #" ×3102 ...]`. Counts of the top
+    /// lines are estimates once the middle has many distinct lines.
+    pub fn summarize(mut self, yes: bool) -> Self {
+        self.summarize = yes;
+        self
+    }
+
+    /// The `since`/`until` window, if there is one.
+    pub(crate) fn window(&self) -> Option<Window> {
+        (self.since.is_some() || self.until.is_some()).then(|| Window::new(self.since, self.until))
+    }
+
+    /// Always show lines matching any pattern in `set`, wherever they fall
+    /// in the middle and with or without a pattern. They're streamed like
+    /// matches, without context, and don't count towards
+    /// [`Truncator::matches`].
+    pub fn always(mut self, set: RegexSet) -> Self {
+        self.always = Some(set);
+        self
+    }
+
+    /// Always show input lines `range` (numbered from 1, counting excluded
+    /// lines), shown like [`Truncator::always`] lines. Can be called more
+    /// than once.
+    pub fn lines(mut self, range: RangeInclusive<usize>) -> Self {
+        self.ranges.push(range);
+        self
+    }
+
+    /// Also show every `n`th input line from the middle (lines `n`, `2n`,
+    /// ...), prefixed with its line number, for a skeleton of a long
+    /// output. Shown like [`Truncator::always`] lines, with a marker
+    /// counting the lines skipped between them. `n` must be at least 1.
+    pub fn every(mut self, n: usize) -> Self {
+        self.every = Some(n.max(1));
+        self
+    }
+
+    /// After the tail, list the `k` longest lines of the input by char
+    /// count, longest first, each width-truncated and with its line
+    /// number, under a `[... 3 longest lines ...]` marker. Under
+    /// [`Truncator::max_bytes`] they get what the tail leaves.
+    pub fn longest(mut self, k: usize) -> Self {
+        self.longest = Some(k);
+        self
+    }
+
+    /// Replace secrets in every line written with `[REDACTED]`. Matching
+    /// and selection still see the original lines.
+    pub fn redact(mut self, redactor: Redactor) -> Self {
+        self.redactor = Some(redactor);
+        self
+    }
+
+    /// On logfmt (`key=value`) lines written, keep only field `name` and
+    /// the others given, noting how many were dropped (`+12 fields`). Can
+    /// be called more than once. Matching still sees the whole line.
+    pub fn field(mut self, name: impl Into<String>) -> Self {
+        self.fields.push(name.into());
+        self
+    }
+
+    /// Collapse runs of lines that `normalizer` makes identical into the
+    /// run's first line, followed by a marker with the run's length.
+    pub fn dedup(mut self, normalizer: Normalizer) -> Self {
+        self.normalizer = Some(normalizer);
+        self
+    }
+
+    /// Fold each Python traceback down to its first frame, a
+    /// `[... 41 frames ...]` marker, its last 3 frames and the exception,
+    /// and each run of JVM `at` frames down to its first 3 and last one.
+    /// Traces of 5 frames or fewer are left alone.
+    pub fn fold_tracebacks(mut self, yes: bool) -> Self {
+        self.fold_tracebacks = yes;
+        self
+    }
+
+    /// Reduce a line redrawn with `\r` (a progress bar) to its last frame,
+    /// noting how many were dropped. On by default.
+    pub fn collapse_progress(mut self, yes: bool) -> Self {
+        self.collapse_progress = yes;
+        self
+    }
+
+    /// Remove ANSI escape sequences from input lines, so patterns and
+    /// output see plain text.
+    pub fn strip_ansi(mut self, yes: bool) -> Self {
+        self.strip_ansi = yes;
+        self
+    }
+
+    /// Prefix each line shown with its line number in the input.
+    pub fn line_numbers(mut self, yes: bool) -> Self {
+        self.line_numbers = yes;
+        self
+    }
+
+    /// Give the input line number of each match in its marker:
+    /// `match 2 @ line 4812 shown`.
+    pub fn match_line_numbers(mut self, yes: bool) -> Self {
+        self.match_line_numbers = yes;
+        self
+    }
+
+    /// Show no matches, just how many the middle section hides, in a single
+    /// summary marker between head and tail.
+    pub fn count_only(mut self, yes: bool) -> Self {
+        self.count_only = yes;
+        self
+    }
+
+    /// Choose which matches to show when there are more than
+    /// [`Truncator::matches`], rather than showing the first ones. Inputs
+    /// then take two passes; see [`Truncator::survey`].
+    pub fn select(mut self, selection: Selection) -> Self {
+        self.selection = Some(selection);
+        self
+    }
+
+    /// Highlight matched text in match lines and dim markers with ANSI
+    /// color.
+    pub fn color(mut self, yes: bool) -> Self {
+        self.color = yes;
+        self
+    }
+
+    /// Never write more than `n` bytes in total.
+    pub fn max_bytes(mut self, n: usize) -> Self {
+        self.max_bytes = Some(n);
+        self
+    }
+
+    /// Hold at most `n` bytes of lines in each input's tail and context
+    /// buffers together. Past it, the oldest buffered lines are dropped and
+    /// a line longer than half of it is cut down, noted in the next marker.
+    pub fn max_memory(mut self, n: usize) -> Self {
+        self.max_memory = Some(n);
+        self
+    }
+
+    /// Cut lines longer than `n` bytes as a [`TruncWriter`] gets them,
+    /// keeping the ends around a `[... N bytes ...]` note, as
+    /// `--max-line-bytes` does, so a giant line is never held whole.
+    pub fn max_line_bytes(mut self, n: usize) -> Self {
+        self.max_line_bytes = Some(n);
+        self
+    }
+
+    /// Show an input whole, as `cat` would and without markers, if it's no
+    /// longer than the head and tail together, or than `lines` if that's
+    /// more (the screen, say); a longer one is truncated as usual. Lines
+    /// past the head are held until it's known which. No effect with a
+    /// byte budget or byte-sized head or tail.
+    pub fn pass_through(mut self, lines: usize) -> Self {
+        self.pass_through = Some(lines);
+        self
+    }
+
+    /// Lines an input can have and still be shown whole, if
+    /// [`Truncator::pass_through`] applies.
+    pub(crate) fn pass_through_lines(&self) -> Option<usize> {
+        let byte_sized =
+            self.max_bytes.is_some() || self.first_bytes.is_some() || self.last_bytes.is_some();
+        let lines = self.pass_through.filter(|_| !byte_sized)?;
+        Some(lines.max(self.head_lines() + self.last))
+    }
+
+    /// Give the tail the lines set aside for matches an input doesn't have:
+    /// with [`matches`](Self::matches) at 10 and 3 found, the tail grows by
+    /// 7 matches' worth of lines, context included, so the output stays
+    /// about as long. No effect without a pattern, or when only counting.
+    pub fn rebalance(mut self, yes: bool) -> Self {
+        self.rebalance = yes;
+        self
+    }
+
+    /// Make the tail the last `k` matches, each with its context, rather
+    /// than the last lines: with [`matches`](Self::matches) at 1, the first
+    /// error and the last two. Lines between them are cut as usual. No
+    /// effect without a pattern.
+    pub fn tail_matches(mut self, k: usize) -> Self {
+        self.tail_matches = Some(k);
+        self
+    }
+
+    /// Append a trailer line after everything else.
+    pub fn trailer(mut self, kind: Trailer) -> Self {
+        self.trailer = Some(kind);
+        self
+    }
+
+    /// Name `path` in every truncation marker as holding the full input,
+    /// with the byte range of it the marker hides. Writing the file is up to
+    /// the caller: every input line with a newline, in order, with each
+    /// input's start given to [`Stream::byte_offset`].
+    pub fn spill(mut self, path: impl Into<String>) -> Self {
+        self.spill = Some(path.into());
+        self
+    }
+
+    /// Give every truncation marker the byte range of the input it hides
+    /// (`[... 980 lines truncated (bytes 10240-1204113) ...]`), counted
+    /// from the start of the first input as for [`region_ids`](Self::region_ids).
+    /// A [`spill`](Self::spill) note says as much already, so it's left out
+    /// alongside one.
+    pub fn byte_ranges(mut self, yes: bool) -> Self {
+        self.byte_ranges = yes;
+        self
+    }
+
+    /// Note in every truncation marker how long the lines it hides took to
+    /// arrive (`[... 2400 lines truncated (over 4m12s) ...]`): from the
+    /// first of them to the line after, timed as each is given to
+    /// [`Stream::line`]. For input read as it's written, such as a build.
+    pub fn elapsed(mut self, yes: bool) -> Self {
+        self.elapsed = yes;
+        self
+    }
+
+    /// Prefix every input line shown with when it was given to
+    /// [`Stream::line`], like moreutils' `ts`. Markers and headers aren't
+    /// stamped.
+    pub fn timestamps(mut self, kind: Timestamps) -> Self {
+        self.timestamps = Some(kind);
+        self
+    }
+
+    /// Rewrite the timestamp each line shown starts with, found as for
+    /// [`Truncator::since`]: strip it to save room, or make it relative to
+    /// the input's first. Lines are matched and filtered as they were read.
+    pub fn rewrite_timestamps(mut self, rewrite: TimestampRewrite) -> Self {
+        self.rewrite_timestamps = Some(rewrite);
+        self
+    }
+
+    /// Treat input lines that are trunc's own markers, as when output is
+    /// truncated again, as the lines they hid (on by default): a marker
+    /// hiding them counts those lines rather than the marker line itself.
+    pub fn idempotent(mut self, yes: bool) -> Self {
+        self.idempotent = yes;
+        self
+    }
+
+    /// Number every truncation marker (`[#3: 214 lines truncated ...]`) and
+    /// end the output with a footer giving the input lines and bytes each
+    /// one hid. Byte offsets count from the start of the first input, as
+    /// given to [`Stream::byte_offset`].
+    pub fn region_ids(mut self, yes: bool) -> Self {
+        self.region_ids = yes;
+        self
+    }
+
+    /// Record what every truncation marker hides, for [`Output::regions`],
+    /// without numbering the markers or a footer.
+    pub fn track_regions(mut self, yes: bool) -> Self {
+        self.track_regions = yes;
+        self
+    }
+
+    /// How truncation markers are written: `[... 980 lines truncated ...]`
+    /// by default, or `…980L…`.
+    pub fn marker_style(mut self, style: MarkerStyle) -> Self {
+        self.marker_style = style;
+        self
+    }
+
+    /// When an [`Output`] flushes head and match lines: after each one by
+    /// default, or less often for throughput when the writer is buffered
+    /// (a [`std::io::BufWriter`]). Follow-mode refreshes, stalls and the
+    /// end of the output always flush.
+    pub fn flush(mut self, policy: FlushPolicy) -> Self {
+        self.flush_policy = policy;
+        self
+    }
+
+    /// Each line given to a [`Stream`] is a multi-line record (a paragraph,
+    /// a stack trace) rather than a line, so markers count `records`
+    /// (`[... 12 records truncated ...]`, `…12R…`). Sizes, matches and
+    /// widths apply to records as they would to lines either way.
+    pub fn records(mut self, yes: bool) -> Self {
+        self.records = yes;
+        self
+    }
+
+    /// Don't cut in the middle of an indented block, such as a traceback or
+    /// a compiler diagnostic with notes: the end of the head and of a
+    /// match's context move forward, and the start of the tail and of a
+    /// match's context move back, to the block's edge (at most 50 lines).
+    /// The extra lines are shown like any other, within `--max-bytes`.
+    pub fn block_aware(mut self, yes: bool) -> Self {
+        self.block_aware = yes;
+        self
+    }
+
+    /// Recognize blocks for [`Truncator::block_aware`] the way `lang`'s
+    /// tools write them. With [`Lang::Rust`], a match on a diagnostic's
+    /// first line (`error[E0308]: ...`) shows the whole diagnostic as its
+    /// context, in place of the usual lines before and after.
+    pub fn lang(mut self, lang: Lang) -> Self {
+        self.lang = lang;
+        self
+    }
+
+    /// Truncate everything from `reader` into `writer`.
+    ///
+    /// With a [`Selection`], the input is read into memory for the survey
+    /// before anything is written.
+    pub fn process<R: BufRead, W: Write>(&self, mut reader: R, writer: W) -> io::Result<()> {
+        let mut run = Run::new(self, self.output(writer));
+        // Every line is read into the same buffer; the stream copies only
+        // those it holds on to
+        let mut line = String::new();
+        while reader.read_line(&mut line)? > 0 {
+            run.read(&line);
+            line.clear();
+        }
+        run.finish()
+    }
+
+    /// How [`Truncator::max_line_bytes`] cuts lines, if it does.
+    pub(crate) fn long_line_cut(&self) -> Option<Cut> {
+        self.max_line_bytes.map(|max| Cut::new(max, self.width))
+    }
+
+    /// Whether a line a pattern matches counts as a match, under the
+    /// [`match_filter`](Self::match_filter).
+    pub(crate) fn keeps_match(&self, line: &str) -> bool {
+        self.match_filter.as_ref().is_none_or(|p| p.is_match(line))
+    }
+
+    /// `line` as it's matched and shown, if that differs: escapes stripped
+    /// and progress redraws collapsed, as configured.
+    pub(crate) fn clean(&self, line: &str) -> Option<String> {
+        self.clean_counting(line).0
+    }
+
+    /// [`Truncator::clean`], and how many progress redraws were dropped.
+    pub(crate) fn clean_counting(&self, line: &str) -> (Option<String>, usize) {
+        let stripped = self.strip_ansi.then(|| ansi::strip(line)).flatten();
+        let line = stripped.as_deref().unwrap_or(line);
+        match self.collapse_progress.then(|| progress::collapse(line)) {
+            Some(Some((collapsed, redraws))) => (Some(collapsed), redraws),
+            _ => (stripped, 0),
+        }
+    }
+
+    /// The head and tail line counts, if every line between them can be
+    /// passed over unread with [`Stream::skip`]: there's no pattern, nothing
+    /// else that picks lines from the middle (exclusions, always-shown
+    /// lines and ranges, collapsing runs or tracebacks, moving cuts to
+    /// block edges), no regions to record, and head and tail are counted in
+    /// lines.
+    pub fn skip_window(&self) -> Option<(usize, usize)> {
+        let picks_lines = self.patterns.is_some()
+            || self.exclude.is_some()
+            || self.since.is_some()
+            || self.until.is_some()
+            || self.sample_per.is_some()
+            || self.sample.is_some()
+            || self.summarize
+            || self.always.is_some()
+            || !self.ranges.is_empty()
+            || self.every.is_some()
+            || self.longest.is_some()
+            || self.normalizer.is_some()
+            || self.fold_tracebacks
+            || self.block_aware;
+        let records_regions =
+            self.region_ids || self.track_regions || self.spill.is_some() || self.byte_ranges;
+        // Lines are timed as they're read, so none can be skipped
+        let timed = self.elapsed || self.timestamps.is_some();
+        let byte_sized = self.first_bytes.is_some() || self.last_bytes.is_some();
+        // An input longer than head and tail that's shown whole anyway
+        let shown_whole = self
+            .pass_through_lines()
+            .is_some_and(|lines| lines > self.head_lines() + self.last);
+        (!picks_lines && !records_regions && !timed && !byte_sized && !shown_whole)
+            .then_some((self.head_lines(), self.last))
+    }
+
+    /// Whether inputs need a [`Survey`] before they're truncated: a
+    /// [`Selection`] is set and there's a pattern to select matches of
+    /// (and they're shown, not just counted).
+    pub fn needs_survey(&self) -> bool {
+        self.selection.is_some() && self.patterns.is_some() && !self.count_only
+    }
+
+    /// The first pass over an input, for a [`Selection`]. Give the
+    /// [`Shortlist`] it produces to the input's [`Stream::shortlist`].
+    pub fn survey(&self) -> Survey<'_> {
+        Survey::new(self)
+    }
+
+    /// Whether a pattern's matches can be found with [`Truncator::scan`]:
+    /// there's a pattern, and lines are matched as they're read (cleaned,
+    /// but not excluded, windowed, folded or collapsed first).
+    pub fn can_scan(&self) -> bool {
+        self.patterns.is_some()
+            && self.exclude.is_none()
+            && self.window().is_none()
+            && !self.fold_tracebacks
+            && self.normalizer.is_none()
+    }
+
+    /// Find the matches in the regular file at `path` on every core, ahead
+    /// of the main pass. Give the [`Scanned`] lines to the file's
+    /// [`Stream::scanned`]; only [`Truncator::can_scan`] ones match as the
+    /// stream would. With `lossy`, invalid UTF-8 is replaced first.
+    pub fn scan(&self, path: &Path, lossy: bool) -> io::Result<Scanned> {
+        scan::scan(self, path, lossy)
+    }
+
+    /// An [`io::Write`](Write) that truncates what's written to it into
+    /// `writer`, as [`Truncator::process`] would the same bytes read.
+    pub fn writer<W: Write>(&self, writer: W) -> TruncWriter<'_, W> {
+        TruncWriter::new(self, writer)
+    }
+
+    /// An [`Output`] writing to `writer` with these settings.
+    pub fn output<W: Write>(&self, writer: W) -> Output<W> {
+        Output::new(writer, self)
+    }
+
+    /// Truncation state for one input. `max_bytes` is the input's share of
+    /// the byte limit, from [`Output::share`].
+    pub fn stream(&self, max_bytes: Option<usize>) -> Stream<'_> {
+        Stream::new(self, max_bytes)
+    }
+}
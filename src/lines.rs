@@ -0,0 +1,86 @@
+//! [`TruncatedLines`]: the truncated view of lines as items to render,
+//! rather than text with markers in it.
+
+use std::io;
+
+use crate::run::Run;
+use crate::Truncator;
+
+/// One item of the truncated view, from [`TruncatedLines`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TruncatedItem {
+    /// A line shown, as it would be written: width-truncated, redacted and
+    /// so on, as the [`Truncator`] says
+    Line(String),
+    /// Where a marker would be: `lines` input lines hidden, `bytes` long
+    /// with a newline each
+    Omission { lines: usize, bytes: usize },
+}
+
+/// An iterator over the truncated view of an iterator's lines, from
+/// [`TruncatedLines::new`]: the lines shown, and an omission wherever lines
+/// were hidden, for rendering markers some other way.
+///
+/// Lines are pulled from the iterator as items are; head and match lines
+/// come as they're found, the tail once the lines run out. Lines trunc
+/// adds that don't stand for hidden ones, like the heading over the
+/// [`Truncator::longest`] lines, aren't items.
+///
+/// ```
+/// use trunc::{TruncatedItem, TruncatedLines, Truncator};
+///
+/// let truncator = Truncator::new().first(1).last(1);
+/// let lines = ["a", "bb", "ccc", "d"].map(String::from);
+/// let items: Vec<_> = TruncatedLines::new(lines, &truncator).collect();
+///
+/// assert_eq!(
+///     items,
+///     [
+///         TruncatedItem::Line("a".to_string()),
+///         TruncatedItem::Omission { lines: 2, bytes: 7 },
+///         TruncatedItem::Line("d".to_string()),
+///     ]
+/// );
+/// ```
+pub struct TruncatedLines<'a, I> {
+    source: I,
+    run: Run<'a, io::Sink>,
+}
+
+impl<'a, I: Iterator<Item = String>> TruncatedLines<'a, I> {
+    /// Truncate `lines`, each without its newline, as `options` says.
+    pub fn new(lines: impl IntoIterator<IntoIter = I>, options: &'a Truncator) -> Self {
+        TruncatedLines {
+            source: lines.into_iter(),
+            run: itemized(options),
+        }
+    }
+}
+
+impl<I: Iterator<Item = String>> Iterator for TruncatedLines<'_, I> {
+    type Item = TruncatedItem;
+
+    fn next(&mut self) -> Option<TruncatedItem> {
+        loop {
+            if let Some(item) = self.run.out().next_item() {
+                return Some(item);
+            }
+            if self.run.finished() {
+                return None;
+            }
+            match self.source.next() {
+                Some(line) => self.run.line(&line),
+                None => {
+                    // Written to a sink, so it can't fail
+                    let _ = self.run.finish();
+                }
+            }
+        }
+    }
+}
+
+/// A [`Run`] whose output is items, not text: lines shown and what each
+/// marker hides.
+pub(crate) fn itemized(options: &Truncator) -> Run<'_, io::Sink> {
+    Run::new(options, options.output(io::sink()).itemized()).track_regions()
+}
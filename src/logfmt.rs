@@ -0,0 +1,77 @@
+//! Keeping only some fields of logfmt lines (`--fields`): a structured log
+//! line can run to 2KB where the timestamp, level and message are all
+//! anyone reads.
+
+/// `line` with only the fields named in `names`, in the order they appear,
+/// then a `+12 fields` note of how many others were dropped. A field is a
+/// `key=value` pair (the value quoted if it has spaces) or a bare word.
+/// None if `line` has no `key=value` pairs, or nothing to drop.
+pub(crate) fn select(line: &str, names: &[String]) -> Option<String> {
+    let fields = fields(line);
+    if !fields.iter().any(|field| field.value) {
+        return None;
+    }
+    let (kept, dropped): (Vec<_>, Vec<_>) = fields
+        .iter()
+        .partition(|field| names.iter().any(|name| *name == field.key));
+    if dropped.is_empty() {
+        return None;
+    }
+    let mut out: Vec<&str> = kept.iter().map(|field| field.text).collect();
+    let note = match dropped.len() {
+        1 => "+1 field".to_string(),
+        n => format!("+{} fields", n),
+    };
+    out.push(&note);
+    Some(out.join(" "))
+}
+
+/// One whitespace-separated field of a line.
+struct Field<'a> {
+    key: &'a str,
+    /// The whole field, as written
+    text: &'a str,
+    /// Whether it's `key=value` rather than a bare word
+    value: bool,
+}
+
+/// Split `line` into its fields. A quoted value runs to its closing quote,
+/// past any spaces or escaped quotes in it, or to the end of the line if
+/// there isn't one.
+fn fields(line: &str) -> Vec<Field<'_>> {
+    let bytes = line.as_bytes();
+    let mut fields = Vec::new();
+    let mut pos = 0;
+    loop {
+        while pos < bytes.len() && bytes[pos].is_ascii_whitespace() {
+            pos += 1;
+        }
+        if pos == bytes.len() {
+            return fields;
+        }
+        let start = pos;
+        while pos < bytes.len() && !bytes[pos].is_ascii_whitespace() && bytes[pos] != b'=' {
+            pos += 1;
+        }
+        let key = &line[start..pos];
+        let value = pos < bytes.len() && bytes[pos] == b'=' && pos > start;
+        if value {
+            pos += 1;
+            if bytes.get(pos) == Some(&b'"') {
+                pos += 1;
+                while pos < bytes.len() && bytes[pos] != b'"' {
+                    pos += if bytes[pos] == b'\\' { 2 } else { 1 };
+                }
+                pos = (pos + 1).min(bytes.len());
+            }
+        }
+        while pos < bytes.len() && !bytes[pos].is_ascii_whitespace() {
+            pos += 1;
+        }
+        fields.push(Field {
+            key,
+            text: &line[start..pos],
+            value,
+        });
+    }
+}
@@ -0,0 +1,48 @@
+//! The longest lines of an input (`--longest`), kept as lines go by and
+//! listed after the tail: a giant SQL statement or base64 blob is often
+//! the anomaly worth seeing, wherever it fell.
+
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+
+/// Up to `size` of the longest lines seen, by char count.
+#[derive(Debug)]
+pub(crate) struct Longest {
+    size: usize,
+    /// Shortest on top; of two the same length, the later line
+    lines: BinaryHeap<Reverse<(usize, Reverse<usize>, String)>>,
+}
+
+impl Longest {
+    pub(crate) fn new(size: usize) -> Self {
+        Longest {
+            size,
+            lines: BinaryHeap::with_capacity(size + 1),
+        }
+    }
+
+    /// Offer input line `original`, kept if it's among the longest so
+    /// far. Ties go to the earlier line.
+    pub(crate) fn offer(&mut self, original: usize, content: &str) {
+        let chars = content.chars().count();
+        let shortest = self.lines.peek().map(|Reverse((chars, _, _))| *chars);
+        if self.lines.len() == self.size && shortest.is_some_and(|s| chars <= s) {
+            return;
+        }
+        self.lines
+            .push(Reverse((chars, Reverse(original), content.to_string())));
+        if self.lines.len() > self.size {
+            self.lines.pop();
+        }
+    }
+
+    /// The lines kept, as input line number and content, longest first.
+    pub(crate) fn take(self) -> Vec<(usize, String)> {
+        // Ascending, which reversed is longest (then earliest) first
+        self.lines
+            .into_sorted_vec()
+            .into_iter()
+            .map(|Reverse((_, Reverse(original), content))| (original, content))
+            .collect()
+    }
+}
@@ -0,0 +1,598 @@
+//! Markers for omitted lines, in either [`MarkerStyle`], and the ids behind
+//! `--region-ids` and `--save`: every gap a marker covers is a region,
+//! numbered from 1 in output order across all inputs, whose input lines and
+//! bytes are kept for the footer and the spill file's index.
+
+use std::cmp::Ordering;
+use std::ops::{Range, RangeInclusive};
+use std::time::Duration;
+
+use crate::output::{BYTE_LIMIT_NOTE, MEMORY_LIMIT_NOTE};
+use crate::width::WidthMode;
+
+/// What one marker hid, from [`Output::regions`].
+///
+/// [`Output::regions`]: crate::Output::regions
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Region {
+    /// The input's name, from its header, when there are several
+    pub input: Option<String>,
+    /// Input line numbers, counted per input; empty if only matches were
+    /// hidden
+    pub lines: RangeInclusive<usize>,
+    /// Byte offsets, counted across all inputs as in the spill file
+    pub bytes: Range<usize>,
+}
+
+/// Allocates region ids and remembers each region.
+#[derive(Debug, Default)]
+pub(crate) struct Regions(Vec<Region>);
+
+impl Regions {
+    /// Number the next region.
+    pub(crate) fn add(
+        &mut self,
+        input: Option<&str>,
+        lines: RangeInclusive<usize>,
+        bytes: Range<usize>,
+    ) -> usize {
+        self.0.push(Region {
+            input: input.map(str::to_string),
+            lines,
+            bytes,
+        });
+        self.0.len()
+    }
+
+    /// Each region's bytes, region 1 first.
+    pub(crate) fn bytes(&self) -> Vec<Range<usize>> {
+        self.0.iter().map(|r| r.bytes.clone()).collect()
+    }
+
+    pub(crate) fn all(&self) -> &[Region] {
+        &self.0
+    }
+
+    /// One `[#<id>: lines <a>-<b>, bytes <start>-<end>]` line per region,
+    /// with `of <input>` after the lines when there are several inputs. A
+    /// marker that hid no lines (only matches) has `no lines`.
+    pub(crate) fn footer(&self) -> impl Iterator<Item = String> + '_ {
+        self.0.iter().enumerate().map(|(i, r)| {
+            let (first, last) = (r.lines.start(), r.lines.end());
+            let mut lines = match first.cmp(last) {
+                Ordering::Less => format!("lines {}-{}", first, last),
+                Ordering::Equal => format!("line {}", first),
+                Ordering::Greater => "no lines".to_string(),
+            };
+            if let Some(input) = &r.input {
+                lines = format!("{} of {}", lines, input);
+            }
+            format!(
+                "[#{}: {}, bytes {}-{}]",
+                i + 1,
+                lines,
+                r.bytes.start,
+                r.bytes.end
+            )
+        })
+    }
+}
+
+/// How markers are written.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum MarkerStyle {
+    /// `[... 980 lines truncated ...]`, `[... 500 chars ...]`
+    #[default]
+    Verbose,
+    /// `…980L…`, `…500c…`: a fraction of the tokens
+    Compact,
+}
+
+/// What markers count: input lines, or the multi-line records given to
+/// [`Truncator::records`].
+///
+/// [`Truncator::records`]: crate::Truncator::records
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub(crate) enum Unit {
+    #[default]
+    Lines,
+    Records,
+}
+
+impl Unit {
+    fn word(self) -> &'static str {
+        match self {
+            Unit::Lines => "lines",
+            Unit::Records => "records",
+        }
+    }
+
+    fn noun(self) -> &'static str {
+        match self {
+            Unit::Lines => "line",
+            Unit::Records => "record",
+        }
+    }
+
+    fn letter(self) -> char {
+        match self {
+            Unit::Lines => 'L',
+            Unit::Records => 'R',
+        }
+    }
+}
+
+/// What a marker between lines says.
+#[derive(Clone)]
+pub(crate) enum Body {
+    /// Lines hidden, nothing more to say
+    Lines(usize),
+    /// Lines hidden before a match: `match 3` shown, at input line `at` if
+    /// given, with the patterns it matched if labelled (` ('ERROR')`)
+    Match {
+        lines: usize,
+        shown: String,
+        at: Option<usize>,
+        label: String,
+    },
+    /// The end of pattern mode, with matches left unshown (`duplicates`
+    /// of them repeats, under `--unique-by`)
+    Remaining {
+        lines: usize,
+        matches: usize,
+        duplicates: usize,
+        total: usize,
+    },
+    /// The end of pattern mode when the middle had no matches
+    NoMatches(usize),
+    /// `-c`: matches in the gap, of these patterns
+    Count {
+        lines: usize,
+        matches: usize,
+        labels: String,
+    },
+    /// Lines dropped by `--exclude`
+    Excluded(usize),
+    /// `--sample-per`: a stretch of `lines` covering `span`, of which `kept`
+    /// were shown
+    Sampled {
+        lines: usize,
+        span: String,
+        kept: usize,
+    },
+    /// Lines dropped by `--since` and `--until`
+    Outside(usize),
+    /// `--summarize`: lines hidden, and what they were
+    Summarized { lines: usize, digest: String },
+    /// Heads the `--longest` lines listed after the tail
+    Longest(usize),
+    /// `--idle-timeout`: heads the tail as it stood when input stopped
+    Stalled,
+    /// After a progress line: the redraws dropped before its last frame
+    Progress(usize),
+    /// `--dedup-fuzzy`: after a run's first line, the run's length
+    Similar(usize),
+    /// `--fold-tracebacks`: frames left out of a trace
+    Frames(usize),
+}
+
+impl Body {
+    fn text(&self, style: MarkerStyle, unit: Unit) -> String {
+        let (word, letter) = (unit.word(), unit.letter());
+        match style {
+            MarkerStyle::Verbose => match self {
+                Body::Lines(n) => format!("{} {} truncated", n, word),
+                Body::Match {
+                    lines,
+                    shown,
+                    at,
+                    label,
+                } => {
+                    let at = at.map_or(String::new(), |n| format!(" @ {} {}", unit.noun(), n));
+                    format!(
+                        "{} {} truncated, {}{} shown{}",
+                        lines, word, shown, at, label
+                    )
+                }
+                Body::Remaining {
+                    lines,
+                    matches,
+                    duplicates: 0,
+                    total,
+                } => format!(
+                    "{} {} and {} truncated ({} total)",
+                    lines,
+                    word,
+                    count_matches(*matches),
+                    total
+                ),
+                Body::Remaining {
+                    lines,
+                    matches,
+                    duplicates,
+                    total,
+                } => format!(
+                    "{} {} and {} truncated ({}, {} total)",
+                    lines,
+                    word,
+                    count_matches(*matches),
+                    counted(*duplicates, "duplicate", "duplicates"),
+                    total
+                ),
+                Body::NoMatches(n) => format!("{} {} truncated, 0 matches found", n, word),
+                Body::Count {
+                    lines,
+                    matches,
+                    labels,
+                } => format!(
+                    "{} {}, {} of {}",
+                    lines,
+                    word,
+                    count_matches(*matches),
+                    labels
+                ),
+                Body::Excluded(n) => format!("{} {} excluded", n, word),
+                Body::Sampled { lines, span, kept } => {
+                    format!("{} {} across {} sampled to {}", lines, word, span, kept)
+                }
+                Body::Outside(n) => format!("{} {} outside time window", n, word),
+                Body::Summarized { lines, digest } => {
+                    format!("{} {} truncated: {}", lines, word, digest)
+                }
+                Body::Longest(n) => format!("{} longest {}", n, word),
+                Body::Stalled => "input stalled, partial tail".to_string(),
+                Body::Progress(n) => format!("progress output collapsed ({} updates)", n),
+                Body::Similar(n) => format!("{} similar {}", n, word),
+                Body::Frames(n) => format!("{} frames", n),
+            },
+            // The match itself follows, so its marker is just the gap
+            MarkerStyle::Compact => match self {
+                Body::Lines(n)
+                | Body::Match {
+                    lines: n, at: None, ..
+                } => {
+                    format!("{}{}", n, letter)
+                }
+                Body::Match {
+                    lines,
+                    at: Some(at),
+                    ..
+                } => format!("{}{} @{}", lines, letter, at),
+                Body::Remaining { lines, matches, .. } => {
+                    format!("{}{} +{}m", lines, letter, matches)
+                }
+                Body::NoMatches(n) => format!("{}{} 0m", n, letter),
+                Body::Count { lines, matches, .. } => format!("{}{} {}m", lines, letter, matches),
+                Body::Excluded(n) => format!("{}{} excluded", n, letter),
+                Body::Sampled { lines, kept, .. } => {
+                    format!("{}{} sampled {}", lines, letter, kept)
+                }
+                Body::Outside(n) => format!("{}{} outside window", n, letter),
+                Body::Summarized { lines, digest } => format!("{}{}: {}", lines, letter, digest),
+                Body::Longest(n) => format!("{}{} longest", n, letter),
+                Body::Stalled => "stalled".to_string(),
+                Body::Progress(n) => format!("{} updates", n),
+                Body::Similar(n) => format!("{}{} similar", n, letter),
+                Body::Frames(n) => format!("{} frames", n),
+            },
+        }
+    }
+
+    /// The lines it hides, if it counts hidden lines.
+    fn lines_mut(&mut self) -> Option<&mut usize> {
+        match self {
+            Body::Lines(n)
+            | Body::Match { lines: n, .. }
+            | Body::Remaining { lines: n, .. }
+            | Body::NoMatches(n)
+            | Body::Count { lines: n, .. }
+            | Body::Summarized { lines: n, .. } => Some(n),
+            _ => None,
+        }
+    }
+
+    /// This body with `markers` of the lines it hides replaced by the
+    /// `hidden` lines they stood for, as found by [`hidden_lines`].
+    pub(crate) fn unnest(mut self, markers: usize, hidden: usize) -> Body {
+        if let Some(n) = self.lines_mut() {
+            *n = n.saturating_sub(markers) + hidden;
+        }
+        self
+    }
+
+    /// The text in the longer style and unit, for byte budgets.
+    pub(crate) fn longest(&self) -> String {
+        self.text(MarkerStyle::Verbose, Unit::Records)
+    }
+}
+
+/// `[... <body><notes> ...]`, or `[#<id>: <body><notes> ...]` for a
+/// numbered region; compact, `…<body><notes>…` or `…#<id>:<body><notes>…`.
+pub(crate) fn format(
+    style: MarkerStyle,
+    unit: Unit,
+    id: Option<usize>,
+    body: &Body,
+    notes: &str,
+) -> String {
+    let text = body.text(style, unit);
+    match (style, id) {
+        (MarkerStyle::Verbose, None) => format!("[... {}{} ...]", text, notes),
+        (MarkerStyle::Verbose, Some(id)) => format!("[#{}: {}{} ...]", id, text, notes),
+        (MarkerStyle::Compact, None) => format!("\u{2026}{}{}\u{2026}", text, notes),
+        (MarkerStyle::Compact, Some(id)) => {
+            format!("\u{2026}#{}:{}{}\u{2026}", id, text, notes)
+        }
+    }
+}
+
+/// The lines `line` says were hidden, if it's a marker trunc wrote for
+/// hidden lines: `[... 980 lines truncated ...]`, `…980L…`, or either with a
+/// region id, match details or notes. Markers for lines dropped some other
+/// way (excluded, sampled, outside the time window) aren't counted.
+pub(crate) fn hidden_lines(line: &str) -> Option<usize> {
+    let line = line.trim_end();
+    if let Some(inner) = line.strip_prefix('[').and_then(|l| l.strip_suffix(" ...]")) {
+        let body = inner
+            .strip_prefix("... ")
+            .or_else(|| after_id(inner, ": "))?;
+        let (n, rest) = leading_number(body)?;
+        let hid = [" lines truncated", " lines and ", " lines, "];
+        return hid.iter().any(|p| rest.starts_with(p)).then_some(n);
+    }
+    let inner = line.strip_prefix('\u{2026}')?.strip_suffix('\u{2026}')?;
+    let body = after_id(inner, ":").unwrap_or(inner);
+    let (n, rest) = leading_number(body)?;
+    let rest = rest.strip_prefix('L')?;
+    // `…980L…`, `…980L @4812…`, `…980L +3m…`, `…980L 2m…`, `…980L: digest…`,
+    // then any notes
+    let matches = rest
+        .strip_prefix(' ')
+        .and_then(leading_number)
+        .is_some_and(|(_, m)| m.starts_with('m'));
+    let hid = [" @", " +", ": ", ",", " ("];
+    (rest.is_empty() || matches || hid.iter().any(|p| rest.starts_with(p))).then_some(n)
+}
+
+/// `text` after a leading region id, `#<id>` then `separator`.
+fn after_id<'a>(text: &'a str, separator: &str) -> Option<&'a str> {
+    let (_, rest) = leading_number(text.strip_prefix('#')?)?;
+    rest.strip_prefix(separator)
+}
+
+/// The number `text` starts with, and the rest.
+fn leading_number(text: &str) -> Option<(usize, &str)> {
+    let digits = text.len() - text.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+    Some((text[..digits].parse().ok()?, &text[digits..]))
+}
+
+/// The `==> name <==` line before each input's output, when there are
+/// several.
+pub(crate) fn header(name: &str) -> String {
+    format!("==> {} <==", name)
+}
+
+/// The `--trailer checksum` line: the SHA-256 of everything before it, in
+/// hex, and what was hidden.
+pub(crate) fn trailer(hex: &str, lines: usize, bytes: usize) -> String {
+    format!(
+        "[trunc sha256:{} hidden-lines:{} hidden-bytes:{}]",
+        hex, lines, bytes
+    )
+}
+
+/// The output format, for `--help-full` and the man page: what each kind
+/// of line trunc adds to the input's looks like, written by the same code
+/// that writes them.
+pub fn output_format() -> String {
+    let line = |style, body: Body, notes: &str| format(style, Unit::Lines, None, &body, notes);
+    let matched = |lines, shown: &str, at| Body::Match {
+        lines,
+        shown: shown.to_string(),
+        at,
+        label: String::new(),
+    };
+    let remaining = Body::Remaining {
+        lines: 48,
+        matches: 208,
+        duplicates: 0,
+        total: 213,
+    };
+    let count = Body::Count {
+        lines: 4812,
+        matches: 37,
+        labels: "'ERROR'".to_string(),
+    };
+    let sampled = Body::Sampled {
+        lines: 9812,
+        span: "00:03:41".to_string(),
+        kept: 221,
+    };
+    let summarized = Body::Summarized {
+        lines: 980,
+        digest: "412 INFO, 3 WARN".to_string(),
+    };
+    let mut rows = vec![
+        "Input lines are written as read, in input order, apart from cuts".to_string(),
+        "within long lines. Every other line is one of these.".to_string(),
+        String::new(),
+        "Markers (--markers verbose, the default):".to_string(),
+    ];
+    let (verbose, compact) = (MarkerStyle::Verbose, MarkerStyle::Compact);
+    let examples = [
+        (Body::Lines(980), "", "lines hidden"),
+        (
+            matched(36, "match 1", None),
+            "",
+            "lines hidden before a match",
+        ),
+        (
+            matched(31, "match 5/5", None),
+            "",
+            "... the last match -m allows",
+        ),
+        (
+            matched(4811, "match 2", Some(4812)),
+            "",
+            "--match-line-numbers",
+        ),
+        (remaining, "", "the end, with matches left unshown"),
+        (Body::NoMatches(980), "", "the end, when nothing matched"),
+        (count, "", "-c: matches in the gap"),
+        (Body::Excluded(12), "", "--exclude"),
+        (Body::Outside(950), "", "--since and --until"),
+        (sampled, "", "--sample-per"),
+        (summarized, "", "--summarize"),
+        (
+            Body::Progress(1423),
+            "",
+            "after a progress line, its redraws",
+        ),
+        (
+            Body::Similar(200),
+            "",
+            "--dedup-fuzzy: after a run's first line",
+        ),
+        (Body::Frames(41), "", "--fold-tracebacks: frames left out"),
+        (
+            Body::Lines(941),
+            BYTE_LIMIT_NOTE,
+            "cut short by --max-bytes",
+        ),
+        (
+            Body::Lines(980),
+            MEMORY_LIMIT_NOTE,
+            "cut short by --max-memory",
+        ),
+    ];
+    let width = 58;
+    let mut compact_rows = Vec::new();
+    for (body, notes, about) in examples {
+        compact_rows.push(format!(
+            "  {:width$} {}",
+            line(compact, body.clone(), notes),
+            about
+        ));
+        rows.push(format!("  {:width$} {}", line(verbose, body, notes), about));
+    }
+    rows.push(String::new());
+    rows.push("Compact markers (--markers compact):".to_string());
+    rows.extend(compact_rows);
+    let mut regions = Regions::default();
+    regions.add(None, 11..=224, 120..5023);
+    let footer = regions.footer().next().expect("one region");
+    let cut_example = |style| format!("<first 100>{}<last 100>", cut(style, 500, WidthMode::Chars));
+    let notes = [
+        (
+            format(verbose, Unit::Lines, Some(1), &Body::Lines(214), ""),
+            "--region-ids: numbered from 1",
+        ),
+        (footer, "--region-ids: the footer, one per marker"),
+        (
+            line(verbose, Body::Lines(980), &bytes_note(&(10240..1204113))),
+            "--byte-ranges",
+        ),
+        (
+            line(
+                verbose,
+                Body::Lines(980),
+                &spill_note("build.log", &(10240..1204113), 2),
+            ),
+            "--save",
+        ),
+        (
+            line(
+                verbose,
+                Body::Lines(2400),
+                &elapsed_note(Duration::from_secs(252)),
+            ),
+            "--elapsed",
+        ),
+        (cut_example(verbose), "a long line's middle, cut by -w"),
+        (cut_example(compact), "... with compact markers"),
+        (
+            header("app.log"),
+            "before each input, when there are several",
+        ),
+        (
+            trailer(&"0".repeat(64), 980, 1204113),
+            "--trailer checksum, last",
+        ),
+    ];
+    rows.push(String::new());
+    rows.push("Also:".to_string());
+    for (text, about) in notes {
+        rows.push(format!("  {}", text));
+        rows.push(format!("      {}", about));
+    }
+    rows.push(String::new());
+    rows.push("Markers count input lines (records, with --separator or --paragraph).".to_string());
+    rows.push("Markers, headers, the footer and the trailer go to stderr instead".to_string());
+    rows.push("with --markers-to-stderr, leaving stdout only input lines.".to_string());
+    rows.join("\n") + "\n"
+}
+
+/// The marker for `n` chars (or columns) cut from the middle of a line.
+pub(crate) fn cut(style: MarkerStyle, n: usize, mode: WidthMode) -> String {
+    match style {
+        MarkerStyle::Verbose => format!("[... {} {} ...]", n, mode.unit()),
+        MarkerStyle::Compact => format!("\u{2026}{}{}\u{2026}", n, mode.short_unit()),
+    }
+}
+
+/// Most bytes a region id adds to a marker.
+pub(crate) const ID_OVERHEAD: usize = "#18446744073709551615: ".len() - "... ".len();
+
+/// Most bytes [`elapsed_note`] adds to a marker.
+pub(crate) const ELAPSED_OVERHEAD: usize = " (over 18446744073709551615h59m)".len();
+
+/// Appended to a marker's body with `--elapsed`: how long the lines it
+/// hides took to arrive, as `850ms`, `12.4s`, `4m12s` or `2h05m`.
+pub(crate) fn elapsed_note(span: Duration) -> String {
+    let secs = span.as_secs();
+    let text = match secs {
+        0 => format!("{}ms", span.as_millis()),
+        1..60 => format!("{:.1}s", span.as_secs_f64()),
+        60..3600 => format!("{}m{:02}s", secs / 60, secs % 60),
+        _ => format!("{}h{:02}m", secs / 3600, secs / 60 % 60),
+    };
+    format!(" (over {})", text)
+}
+
+/// Appended to the end marker with a pattern: the matches shown in the head
+/// and the tail, which no match marker names.
+pub(crate) fn shown_note(head: usize, tail: usize) -> String {
+    [(head, "head"), (tail, "tail")]
+        .into_iter()
+        .filter(|&(n, _)| n > 0)
+        .map(|(n, at)| format!(", {} in {}", count_matches(n), at))
+        .collect()
+}
+
+/// `1 match`, `3 matches`.
+fn count_matches(n: usize) -> String {
+    counted(n, "match", "matches")
+}
+
+/// `n` and the noun for it: `one` for 1, else `many`.
+pub(crate) fn counted(n: usize, one: &str, many: &str) -> String {
+    match n {
+        1 => format!("1 {}", one),
+        n => format!("{} {}", n, many),
+    }
+}
+
+/// Appended to a marker's body with `--byte-ranges`: the bytes of the
+/// input it hides.
+pub(crate) fn bytes_note(hidden: &Range<usize>) -> String {
+    format!(" (bytes {}-{})", hidden.start, hidden.end)
+}
+
+/// Appended to a marker's body with `--save`: where the lines it hides can
+/// be found, and the region to give `trunc show --region`.
+pub(crate) fn spill_note(path: &str, hidden: &Range<usize>, region: usize) -> String {
+    format!(
+        " (full output: {} bytes {}-{}, region {})",
+        path, hidden.start, hidden.end, region
+    )
+}
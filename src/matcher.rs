@@ -0,0 +1,184 @@
+//! What counts as a match in the middle section: any of a set of regexes,
+//! any of a set of [`Pattern`]s from another engine (`--engine pcre2`), or
+//! (with `--fixed-string`) any of a set of literal substrings.
+
+use regex::{Regex, RegexSet};
+use std::fmt::Debug;
+use std::ops::Range;
+use std::sync::Arc;
+
+/// One compiled pattern, from whichever regex engine: what matching,
+/// highlighting and [`unique_by`](crate::Truncator::unique_by) need of it.
+///
+/// Implemented for [`regex::Regex`], and with the `pcre2` feature for
+/// `pcre2::bytes::Regex` (compiled with UTF-8 on), for look-around and
+/// backreferences. Give them to
+/// [`Truncator::engine_patterns`](crate::Truncator::engine_patterns).
+pub trait Pattern: Debug + Send + Sync {
+    /// The pattern as written.
+    fn as_str(&self) -> &str;
+
+    /// Whether it matches anywhere in `line`.
+    fn is_match(&self, line: &str) -> bool;
+
+    /// The byte ranges of its matches in `line`, in order.
+    fn find_all(&self, line: &str) -> Vec<Range<usize>>;
+
+    /// `template` with `$1`, `${name}` and so on replaced by the groups of
+    /// its first match in `line`, if it matches.
+    fn expand(&self, line: &str, template: &str) -> Option<String>;
+}
+
+impl Pattern for Regex {
+    fn as_str(&self) -> &str {
+        Regex::as_str(self)
+    }
+
+    fn is_match(&self, line: &str) -> bool {
+        Regex::is_match(self, line)
+    }
+
+    fn find_all(&self, line: &str) -> Vec<Range<usize>> {
+        self.find_iter(line).map(|m| m.range()).collect()
+    }
+
+    fn expand(&self, line: &str, template: &str) -> Option<String> {
+        let caps = self.captures(line)?;
+        let mut expanded = String::new();
+        caps.expand(template, &mut expanded);
+        Some(expanded)
+    }
+}
+
+/// A match error (PCRE2's match or backtracking limits) counts as no match.
+#[cfg(feature = "pcre2")]
+impl Pattern for pcre2::bytes::Regex {
+    fn as_str(&self) -> &str {
+        pcre2::bytes::Regex::as_str(self)
+    }
+
+    fn is_match(&self, line: &str) -> bool {
+        pcre2::bytes::Regex::is_match(self, line.as_bytes()).unwrap_or(false)
+    }
+
+    fn find_all(&self, line: &str) -> Vec<Range<usize>> {
+        self.find_iter(line.as_bytes())
+            .map_while(Result::ok)
+            .map(|m| m.start()..m.end())
+            .collect()
+    }
+
+    fn expand(&self, line: &str, template: &str) -> Option<String> {
+        let caps = self.captures(line.as_bytes()).ok()??;
+        Some(expand(template, |group| {
+            let m = match group.parse() {
+                Ok(i) => caps.get(i),
+                Err(_) => caps.name(group),
+            };
+            m.map_or("", |m| &line[m.start()..m.end()])
+        }))
+    }
+}
+
+/// `template` with each `$name`, `${name}` or `$1` replaced by `group` of
+/// the name or number, and `$$` by `$`: the syntax of
+/// [`regex::Captures::expand`], for engines without it.
+#[cfg(feature = "pcre2")]
+fn expand<'a>(template: &str, group: impl Fn(&str) -> &'a str) -> String {
+    let mut expanded = String::new();
+    let mut rest = template;
+    while let Some(at) = rest.find('$') {
+        expanded.push_str(&rest[..at]);
+        rest = &rest[at + 1..];
+        if let Some(after) = rest.strip_prefix('$') {
+            expanded.push('$');
+            rest = after;
+        } else if let Some((name, after)) = rest
+            .strip_prefix('{')
+            .and_then(|braced| braced.split_once('}'))
+        {
+            expanded.push_str(group(name));
+            rest = after;
+        } else {
+            let end = rest
+                .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+                .unwrap_or(rest.len());
+            match end {
+                0 => expanded.push('$'),
+                _ => expanded.push_str(group(&rest[..end])),
+            }
+            rest = &rest[end..];
+        }
+    }
+    expanded.push_str(rest);
+    expanded
+}
+
+#[derive(Clone, Debug)]
+pub(crate) enum Matcher {
+    /// Regexes, each with the label `--label` shows for it (by default the
+    /// pattern itself)
+    Regex { set: RegexSet, labels: Vec<String> },
+    /// Patterns from another engine, tried one by one
+    Engine(Vec<Arc<dyn Pattern>>),
+    /// Plain substring search; no regex is compiled
+    Literal(Vec<String>),
+}
+
+impl Matcher {
+    pub(crate) fn is_match(&self, line: &str) -> bool {
+        match self {
+            Matcher::Regex { set, .. } => set.is_match(line),
+            Matcher::Engine(patterns) => patterns.iter().any(|p| p.is_match(line)),
+            Matcher::Literal(needles) => needles.iter().any(|n| line.contains(n.as_str())),
+        }
+    }
+
+    /// Each pattern on its own (literals escaped as regexes), for what a
+    /// set can't do, like captures.
+    pub(crate) fn patterns(&self) -> Vec<Arc<dyn Pattern>> {
+        let compile = |p: &str| -> Arc<dyn Pattern> {
+            Arc::new(Regex::new(p).expect("pattern already compiled"))
+        };
+        match self {
+            Matcher::Regex { set, .. } => set.patterns().iter().map(|p| compile(p)).collect(),
+            Matcher::Engine(patterns) => patterns.clone(),
+            Matcher::Literal(needles) => {
+                needles.iter().map(|n| compile(&regex::escape(n))).collect()
+            }
+        }
+    }
+
+    /// Labels of every pattern, in the order they were given.
+    pub(crate) fn labels(&self) -> Vec<&str> {
+        match self {
+            Matcher::Regex { labels, .. } => labels.iter().map(String::as_str).collect(),
+            Matcher::Engine(patterns) => patterns.iter().map(|p| p.as_str()).collect(),
+            Matcher::Literal(needles) => needles.iter().map(String::as_str).collect(),
+        }
+    }
+
+    /// Labels of the patterns that match `line`, in the order they were
+    /// given.
+    pub(crate) fn matching<'m>(&'m self, line: &str) -> Vec<&'m str> {
+        let labels = self.labels();
+        self.matching_indices(line)
+            .into_iter()
+            .map(|i| labels[i])
+            .collect()
+    }
+
+    /// Indices of the patterns that match `line`, in the order they were
+    /// given.
+    pub(crate) fn matching_indices(&self, line: &str) -> Vec<usize> {
+        match self {
+            Matcher::Regex { set, .. } => set.matches(line).into_iter().collect(),
+            Matcher::Engine(patterns) => (0..patterns.len())
+                .filter(|&i| patterns[i].is_match(line))
+                .collect(),
+            Matcher::Literal(needles) => (0..needles.len())
+                .filter(|&i| line.contains(needles[i].as_str()))
+                .collect(),
+        }
+    }
+}
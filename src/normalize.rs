@@ -0,0 +1,108 @@
+//! Collapsing runs of near-identical lines (`--dedup-fuzzy`), such as
+//! `GET /health 200 3ms` logged every second.
+//!
+//! Lines are compared after a [`Normalizer`] strips what varies between
+//! them: timestamps, ids, numbers. A run of lines that normalize the same
+//! becomes its first line with a count appended, so both the [`Stream`] and
+//! a [`Survey`] see (and number) the collapsed lines.
+//!
+//! [`Stream`]: crate::Stream
+//! [`Survey`]: crate::Survey
+
+use regex::Regex;
+use std::borrow::Cow;
+
+/// Built-in strip rules for [`Normalizer::fuzzy`], applied in order.
+const FUZZY: &[&str] = &[
+    // ISO 8601 and similar: `2024-05-01T12:00:00.123Z`, `2024-05-01 12:00:00`
+    r"\d{4}-\d{2}-\d{2}[T ]\d{2}:\d{2}:\d{2}(\.\d+)?(Z|[+-]\d{2}:?\d{2})?",
+    // syslog: `May  1 12:00:00`
+    r"\b[A-Z][a-z]{2} [ \d]\d \d{2}:\d{2}:\d{2}\b",
+    r"\b\d{2}:\d{2}:\d{2}(\.\d+)?\b",
+    // UUIDs, `0x` numbers and long hex ids (hashes, trace ids)
+    r"\b[0-9a-fA-F]{8}-[0-9a-fA-F]{4}-[0-9a-fA-F]{4}-[0-9a-fA-F]{4}-[0-9a-fA-F]{12}\b",
+    r"\b0x[0-9a-fA-F]+\b",
+    r"\b[0-9a-f]{8,}\b",
+    r"\d+(\.\d+)?",
+];
+
+/// What to strip from lines before comparing them. Each rule's matches are
+/// replaced with `#`.
+#[derive(Clone, Debug, Default)]
+pub struct Normalizer {
+    rules: Vec<Regex>,
+}
+
+impl Normalizer {
+    /// No rules: only identical lines collapse.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Strip timestamps, UUIDs, hex ids and numbers.
+    pub fn fuzzy() -> Self {
+        Normalizer {
+            rules: FUZZY
+                .iter()
+                .map(|re| Regex::new(re).expect("built-in rules are valid"))
+                .collect(),
+        }
+    }
+
+    /// Also strip matches of `re`.
+    pub fn rule(mut self, re: Regex) -> Self {
+        self.rules.push(re);
+        self
+    }
+
+    /// `line` as it's compared.
+    pub(crate) fn normalize<'a>(&self, line: &'a str) -> Cow<'a, str> {
+        let mut line = Cow::Borrowed(line);
+        for re in &self.rules {
+            if let Cow::Owned(stripped) = re.replace_all(&line, "#") {
+                line = Cow::Owned(stripped);
+            }
+        }
+        line
+    }
+}
+
+/// Holds back the latest line until the next one shows whether it starts a
+/// run.
+#[derive(Debug)]
+pub(crate) struct Dedup<'a> {
+    normalizer: &'a Normalizer,
+    /// The run so far: its first line, that line's number, its normalized
+    /// form and the run's length
+    held: Option<(String, usize, String, usize)>,
+}
+
+impl<'a> Dedup<'a> {
+    pub(crate) fn new(normalizer: &'a Normalizer) -> Self {
+        Dedup {
+            normalizer,
+            held: None,
+        }
+    }
+
+    /// Take the next line, line `number` of the input. Returns the previous
+    /// run's line, number and length once `line` ends it.
+    pub(crate) fn push(&mut self, line: String, number: usize) -> Option<(String, usize, usize)> {
+        let key = self.normalizer.normalize(&line).into_owned();
+        if let Some((_, _, held_key, count)) = &mut self.held {
+            if *held_key == key {
+                *count += 1;
+                return None;
+            }
+        }
+        let released = self.flush();
+        self.held = Some((line, number, key, 1));
+        released
+    }
+
+    /// The held run's line, number and length, if any.
+    pub(crate) fn flush(&mut self) -> Option<(String, usize, usize)> {
+        let (line, number, _, count) = self.held.take()?;
+        Some((line, number, count))
+    }
+}
@@ -0,0 +1,917 @@
+//! Writing truncated output: within-line truncation, markers, headers, and
+//! the running totals behind the trailer and the byte limit.
+
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, VecDeque};
+use std::io::{self, Write};
+use std::ops::{Range, RangeInclusive};
+use std::time::Duration;
+
+use crate::ansi;
+use crate::clock::{Instant, SystemTime};
+use crate::color::{self, Highlighter};
+use crate::json;
+use crate::lines::TruncatedItem;
+use crate::logfmt;
+use crate::marker::{self, MarkerStyle, Region, Regions};
+use crate::redact::Redactor;
+use crate::table::{self, Columns};
+use crate::timestamp::{self, Millis, Restamp};
+use crate::width::{self, LineCut, WidthMode};
+use crate::{Timestamps, Truncator};
+
+/// Appended to a marker when lines in its gap were hidden to fit `--max-bytes`.
+pub(crate) const BYTE_LIMIT_NOTE: &str = ", byte limit";
+
+/// Appended to a marker when lines were dropped or cut short to fit
+/// `--max-memory`.
+pub(crate) const MEMORY_LIMIT_NOTE: &str = ", memory limit";
+
+/// Bytes a marker adds around its body, including the limit notes.
+const MARKER_OVERHEAD: usize =
+    "[...  ...]\n".len() + BYTE_LIMIT_NOTE.len() + MEMORY_LIMIT_NOTE.len();
+
+/// Bytes set aside under `--max-bytes` for each input's end marker. Fits the
+/// longest marker with 12-digit counts.
+const MARKER_RESERVE: usize = 100;
+
+/// Bytes set aside under `--max-bytes` for the `--trailer` line (64 hex
+/// digits plus two 20-digit counts).
+const TRAILER_RESERVE: usize = 150;
+
+/// When shown lines are flushed through to the writer.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum FlushPolicy {
+    /// After every head and match line, so each appears as soon as it's
+    /// found
+    #[default]
+    Line,
+    /// Only when the writer's buffer fills, and at the end
+    Block,
+    /// After a head or match line, unless the last flush was more recent
+    /// than this
+    Interval(Duration),
+}
+
+/// Which part of the output a line belongs to.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Section {
+    Head,
+    Matches,
+    Tail,
+    /// An `--every`, `--sample` or `--longest` line, numbered even without
+    /// `--line-numbers`
+    Checkpoint,
+}
+
+/// A line after width truncation.
+pub struct TruncatedLine {
+    pub text: String,
+    /// Input bytes removed from the middle of the line (0 if left intact).
+    pub bytes_removed: usize,
+    /// Where the `[... N chars ...]` markers are in `text`: none if left
+    /// intact, two if a match was kept from the middle.
+    pub markers: Vec<Range<usize>>,
+}
+
+/// Truncate a line if it's too long.
+///
+/// Produces: `<first W chars>[... N chars ...]<last W chars>`
+/// where N is the number of characters removed. See
+/// [`truncate_line_cut`] to keep just one end.
+/// Only truncates when the result is strictly shorter than the original.
+///
+/// Characters are grapheme clusters, so an emoji or an accented letter is
+/// never split. With [`WidthMode::Columns`], W and N count terminal columns
+/// instead and the marker says `cols`. ANSI escape sequences take no width
+/// and are never split; see [`width::truncate`] for how styles carry across
+/// the cut.
+pub fn truncate_line(line: &str, width: usize, mode: WidthMode) -> TruncatedLine {
+    truncate_line_cut(line, width, mode, LineCut::Middle)
+}
+
+/// [`truncate_line`], cutting out the part `cut` says: with
+/// [`LineCut::Start`], `[... N chars ...]<last 2W chars>`.
+pub fn truncate_line_cut(line: &str, width: usize, mode: WidthMode, cut: LineCut) -> TruncatedLine {
+    truncate_line_styled(line, width, mode, cut, MarkerStyle::Verbose)
+}
+
+/// [`truncate_line_cut`] with the marker in `style`.
+pub(crate) fn truncate_line_styled(
+    line: &str,
+    width: usize,
+    mode: WidthMode,
+    cut: LineCut,
+    style: MarkerStyle,
+) -> TruncatedLine {
+    let intact = || TruncatedLine {
+        text: line.to_string(),
+        bytes_removed: 0,
+        markers: Vec::new(),
+    };
+
+    if width == 0 {
+        return intact();
+    }
+
+    // Plain ASCII is one char and one column per byte
+    if !line.is_ascii() || line.contains(ansi::ESC) {
+        return match width::truncate(line, width, mode, cut, style) {
+            Some((text, bytes_removed, marker)) => TruncatedLine {
+                text,
+                bytes_removed,
+                markers: vec![marker],
+            },
+            None => intact(),
+        };
+    }
+
+    let max_len = width * 2;
+    if line.len() <= max_len {
+        return intact();
+    }
+
+    let removed = line.len() - max_len;
+    let marker = marker::cut(style, removed, mode);
+
+    // Only truncate if the result is strictly shorter than the original
+    let result_len = max_len + marker.len();
+    if result_len >= line.len() {
+        return intact();
+    }
+
+    let (head, tail) = cut.keep(width);
+    let first = &line[..head];
+    let last = &line[line.len() - tail..];
+    let at = first.len()..first.len() + marker.len();
+    TruncatedLine {
+        bytes_removed: removed,
+        markers: vec![at],
+        text: format!("{}{}{}", first, marker, last),
+    }
+}
+
+/// Destination for everything trunc prints, from [`Truncator::output`].
+///
+/// Keeps the running totals the `--trailer` line reports: a digest of every
+/// byte written, and how much of the input actually made it into the output.
+/// With `--max-bytes` it's also the last line of defence: once a write would
+/// go over the limit, nothing more is written.
+///
+/// Write errors stop further output and are returned by [`Output::finish`].
+///
+/// Markers, headers, the region footer and the trailer can be sent to a
+/// separate sink with [`Output::markers_to`], leaving `writer` only input
+/// lines. The byte limit and the digest then cover `writer` alone.
+///
+/// The head, match and tail lines can each be copied to a sink of their
+/// own as well, with [`Output::head_to`], [`Output::matches_to`] and
+/// [`Output::tail_to`].
+///
+/// With [`Output::explain_to`], the stream says why it shows, holds or
+/// hides each line, and where it puts each marker, on a sink of its own.
+///
+/// [`Truncator::output`]: crate::Truncator::output
+pub struct Output<W: Write> {
+    inner: W,
+    /// Where everything but input lines goes, if not `inner`
+    marker_sink: Option<Box<dyn Write + Send>>,
+    /// Where head, match and tail lines are copied to, if anywhere
+    section_sinks: [Option<Box<dyn Write + Send>>; 3],
+    /// Where `--explain` traces go, if anywhere
+    explain_sink: Option<Box<dyn Write + Send>>,
+    width: usize,
+    /// Head, match and tail widths, if they differ from `width`
+    section_widths: [Option<usize>; 3],
+    width_mode: WidthMode,
+    line_cut: LineCut,
+    /// Logfmt fields to keep, if not all
+    fields: Vec<String>,
+    /// Truncate JSON lines' string values rather than the line
+    json_values: bool,
+    /// Truncate CSV and TSV rows by column
+    table: bool,
+    /// The columns the current input's header keeps, if it's too long
+    columns: Option<Columns>,
+    color: bool,
+    /// Prefix input lines with their line numbers
+    line_numbers: bool,
+    timestamps: Option<Timestamps>,
+    /// When trunc started, for [`Timestamps`]
+    started: (Instant, SystemTime),
+    /// When the line about to be written was read
+    stamp: Option<Instant>,
+    /// With a pattern: finds matched text in match lines, to highlight or
+    /// keep when cutting them
+    highlighter: Option<Highlighter>,
+    redactor: Option<Redactor>,
+    restamp: Option<Restamp>,
+    /// Most bytes `--save` or `--byte-ranges`, and `--elapsed`, add to a
+    /// marker
+    spill_note_len: usize,
+    /// Number markers, and list their regions before the trailer
+    region_ids: bool,
+    style: MarkerStyle,
+    /// What each marker so far hid
+    regions: Regions,
+    /// The input being written, from its header
+    input: Option<String>,
+    digest: Option<Sha256>,
+    limit: Option<usize>,
+    bytes_written: usize,
+    clipped: bool,
+    error: Option<io::Error>,
+    flush_policy: FlushPolicy,
+    last_flush: Instant,
+    input_lines_emitted: usize,
+    input_bytes_emitted: usize,
+    markers_written: usize,
+    /// For [`TruncatedLines`]: what's shown and hidden, item by item, in
+    /// place of writing it
+    ///
+    /// [`TruncatedLines`]: crate::TruncatedLines
+    items: Option<VecDeque<TruncatedItem>>,
+    /// The input lines and bytes the next marker hides
+    hiding: Option<(usize, usize)>,
+    /// Matches found and shown, summed over finished inputs (pattern mode)
+    matches: Option<(usize, usize)>,
+    /// Input lines passed on rewritten and not shown yet, by line number:
+    /// their bytes, and what they became
+    rewrites: BTreeMap<usize, (usize, usize)>,
+    /// Markers to write after input lines, if they're shown, by line number
+    notes: BTreeMap<usize, Vec<String>>,
+    /// Bytes of input lines shown rewritten, and what they became
+    rewritten_from: usize,
+    rewritten_to: usize,
+    wrote_header: bool,
+}
+
+impl<W: Write> Output<W> {
+    pub(crate) fn new(inner: W, config: &Truncator) -> Self {
+        Output {
+            inner,
+            marker_sink: None,
+            section_sinks: [None, None, None],
+            explain_sink: None,
+            width: config.width,
+            section_widths: config.section_widths,
+            width_mode: config.width_mode,
+            line_cut: config.line_cut,
+            fields: config.fields.clone(),
+            json_values: config.json_values,
+            table: config.table,
+            columns: None,
+            color: config.color,
+            line_numbers: config.line_numbers,
+            timestamps: config.timestamps,
+            started: (Instant::now(), SystemTime::now()),
+            stamp: None,
+            highlighter: config.patterns.as_ref().map(Highlighter::new),
+            redactor: config.redactor.clone(),
+            restamp: config.rewrite_timestamps.map(Restamp::new),
+            spill_note_len: match config.spill.as_deref() {
+                Some(path) => marker::spill_note(path, &(usize::MAX..usize::MAX), usize::MAX).len(),
+                None if config.byte_ranges => marker::bytes_note(&(usize::MAX..usize::MAX)).len(),
+                None => 0,
+            } + if config.elapsed {
+                marker::ELAPSED_OVERHEAD
+            } else {
+                0
+            },
+            region_ids: config.region_ids,
+            style: config.marker_style,
+            regions: Regions::default(),
+            input: None,
+            digest: config.trailer.map(|_| Sha256::new()),
+            limit: config.max_bytes,
+            bytes_written: 0,
+            clipped: false,
+            error: None,
+            flush_policy: config.flush_policy,
+            last_flush: Instant::now(),
+            input_lines_emitted: 0,
+            input_bytes_emitted: 0,
+            markers_written: 0,
+            items: None,
+            hiding: None,
+            matches: None,
+            rewrites: BTreeMap::new(),
+            notes: BTreeMap::new(),
+            rewritten_from: 0,
+            rewritten_to: 0,
+            wrote_header: false,
+        }
+    }
+
+    /// Send markers, headers, the region footer and the trailer to `sink`
+    /// rather than the output, e.g. stderr, so the output holds nothing but
+    /// input lines.
+    pub fn markers_to(mut self, sink: impl Write + Send + 'static) -> Self {
+        self.marker_sink = Some(Box::new(sink));
+        self
+    }
+
+    /// Also write head lines to `sink`, as shown but uncolored, without
+    /// markers or headers.
+    pub fn head_to(self, sink: impl Write + Send + 'static) -> Self {
+        self.section_to(Section::Head, sink)
+    }
+
+    /// Also write match lines, and the context around them, to `sink`, as
+    /// [`Output::head_to`] does head lines.
+    pub fn matches_to(self, sink: impl Write + Send + 'static) -> Self {
+        self.section_to(Section::Matches, sink)
+    }
+
+    /// Also write tail lines to `sink`, as [`Output::head_to`] does head
+    /// lines.
+    pub fn tail_to(self, sink: impl Write + Send + 'static) -> Self {
+        self.section_to(Section::Tail, sink)
+    }
+
+    /// Write to `sink`, a line at a time, why each line was shown, held for
+    /// the tail or hidden, when budgets ran out, and where markers went.
+    pub fn explain_to(mut self, sink: impl Write + Send + 'static) -> Self {
+        self.explain_sink = Some(Box::new(sink));
+        self
+    }
+
+    /// With [`Output::explain_to`], write `why()`, prefixed with the input's
+    /// name when there are several. Write errors are ignored: the trace is
+    /// an aside, and never stops the output.
+    pub(crate) fn explain(&mut self, why: impl FnOnce() -> String) {
+        let Some(sink) = &mut self.explain_sink else {
+            return;
+        };
+        let _ = match &self.input {
+            Some(input) => writeln!(sink, "explain: {}: {}", input, why()),
+            None => writeln!(sink, "explain: {}", why()),
+        };
+    }
+
+    fn section_to(mut self, section: Section, sink: impl Write + Send + 'static) -> Self {
+        if let Some(slot) = self.section_sink(section) {
+            *slot = Some(Box::new(sink));
+        }
+        self
+    }
+
+    /// The slot for `section`'s sink. Checkpoints don't have one.
+    fn section_sink(&mut self, section: Section) -> Option<&mut Option<Box<dyn Write + Send>>> {
+        let i = match section {
+            Section::Head => 0,
+            Section::Matches => 1,
+            Section::Tail => 2,
+            Section::Checkpoint => return None,
+        };
+        Some(&mut self.section_sinks[i])
+    }
+
+    /// Count `len` bytes against the limit. False (and nothing more is ever
+    /// written) if they don't fit.
+    fn reserve(&mut self, len: usize) -> bool {
+        if self.clipped
+            || self
+                .limit
+                .is_some_and(|limit| self.bytes_written + len > limit)
+        {
+            self.clipped = true;
+            return false;
+        }
+        self.bytes_written += len;
+        true
+    }
+
+    /// Remember the first write error and stop writing.
+    fn check(&mut self, result: io::Result<()>) {
+        if let Err(e) = result {
+            self.error.get_or_insert(e);
+            self.clipped = true;
+        }
+    }
+
+    /// Queue lines as items rather than write them, for
+    /// [`TruncatedLines`](crate::TruncatedLines).
+    pub(crate) fn itemized(mut self) -> Self {
+        self.items = Some(VecDeque::new());
+        self
+    }
+
+    /// The oldest item queued and not yet taken.
+    pub(crate) fn next_item(&mut self) -> Option<TruncatedItem> {
+        self.items.as_mut()?.pop_front()
+    }
+
+    /// The next marker hides `lines` input lines, `bytes` long.
+    pub(crate) fn hiding(&mut self, lines: usize, bytes: usize) {
+        self.hiding = Some((lines, bytes));
+    }
+
+    /// Write raw text followed by a newline. Returns false if it didn't fit
+    /// under the byte limit.
+    fn write_line(&mut self, text: &str) -> bool {
+        if !self.reserve(text.len() + 1) {
+            return false;
+        }
+        if let Some(items) = &mut self.items {
+            items.push_back(TruncatedItem::Line(text.to_string()));
+            return true;
+        }
+        if let Some(digest) = &mut self.digest {
+            digest.update(text.as_bytes());
+            digest.update(b"\n");
+        }
+        let result = writeln!(self.inner, "{}", text);
+        self.check(result);
+        true
+    }
+
+    /// Write a line that isn't input: to the marker sink if there is one
+    /// (outside the byte limit and digest), else like [`Output::write_line`].
+    fn write_note(&mut self, text: &str) -> bool {
+        if let Some(items) = &mut self.items {
+            // A marker is an item if it hid lines; other notes aren't
+            if let Some((lines @ 1.., bytes)) = self.hiding.take() {
+                items.push_back(TruncatedItem::Omission { lines, bytes });
+            }
+            return true;
+        }
+        let Some(sink) = &mut self.marker_sink else {
+            return self.write_line(text);
+        };
+        if self.error.is_some() {
+            return false;
+        }
+        let result = writeln!(sink, "{}", text);
+        self.check(result);
+        true
+    }
+
+    /// How line `number`, `content`, appears in `section`: redacted, its
+    /// timestamp rewritten, cut down to the `--fields` asked for, width-truncated (JSON value by
+    /// value, with `--json-values`; column by column, with `--table`) and,
+    /// with color, painted, with any matches highlighted whatever the
+    /// section. With `--line-numbers` the number goes in a
+    /// gutter, which doesn't count towards the width; with `--timestamps`
+    /// the time goes before that.
+    fn render(&self, number: usize, content: &str, section: Section) -> TruncatedLine {
+        self.render_as(number, content, section, self.color)
+    }
+
+    /// [`Output::render`], painted only if `color`.
+    fn render_as(
+        &self,
+        number: usize,
+        content: &str,
+        section: Section,
+        color: bool,
+    ) -> TruncatedLine {
+        let content = match &self.redactor {
+            Some(redactor) => redactor.redact(content),
+            None => content.into(),
+        };
+        let restamped = self.restamp.as_ref().and_then(|r| r.rewrite(&content));
+        let dropped = restamped
+            .as_ref()
+            .map_or(0, |text| content.len().saturating_sub(text.len()));
+        let content = restamped.map_or(content, Into::into);
+        let selected = match self.fields.is_empty() {
+            true => None,
+            false => logfmt::select(&content, &self.fields),
+        };
+        let dropped = dropped
+            + selected
+                .as_ref()
+                .map_or(0, |text| content.len().saturating_sub(text.len()));
+        let content = selected.map_or(content, Into::into);
+        let columns = match (self.table, number) {
+            (false, _) => None,
+            (true, 1) => table::plan(&content, self.width, self.width_mode),
+            (true, _) => self
+                .columns
+                .or_else(|| table::plan(&content, self.width, self.width_mode)),
+        };
+        let cut = columns.and_then(|columns| table::cut(&content, columns));
+        let dropped = dropped
+            + cut
+                .as_ref()
+                .map_or(0, |text| content.len().saturating_sub(text.len()));
+        let content = cut.map_or(content, Into::into);
+        let width = self.width_in(section);
+        let json = match self.json_values && width > 0 {
+            true => json::truncate_values(&content, width),
+            false => None,
+        };
+        let mut line = match json {
+            Some(text) => TruncatedLine {
+                bytes_removed: content.len().saturating_sub(text.len()),
+                markers: Vec::new(),
+                text,
+            },
+            None => self.truncate_match(&content, width).unwrap_or_else(|| {
+                truncate_line_styled(&content, width, self.width_mode, self.line_cut, self.style)
+            }),
+        };
+        line.bytes_removed += dropped;
+        if color {
+            let spans = match &self.highlighter {
+                Some(h) => h.spans(&line.text),
+                None => Vec::new(),
+            };
+            if !spans.is_empty() || !line.markers.is_empty() {
+                line.text = color::paint(&line.text, &spans, &line.markers);
+            }
+        }
+        if self.line_numbers || section == Section::Checkpoint {
+            let gutter = format!("{:>6}\u{2502} ", number);
+            line.text = match color {
+                true => format!("{}{}", color::dim(&gutter), line.text),
+                false => format!("{}{}", gutter, line.text),
+            };
+        }
+        if let Some(stamp) = self.timestamps.map(|kind| self.stamp_text(kind)) {
+            line.text = match color {
+                true => format!("{} {}", color::dim(&stamp), line.text),
+                false => format!("{} {}", stamp, line.text),
+            };
+        }
+        line
+    }
+
+    /// The `-w` for lines in `section`: `--width-head`, `--width-match` or
+    /// `--width-tail` if given.
+    fn width_in(&self, section: Section) -> usize {
+        let own = match section {
+            Section::Head => self.section_widths[0],
+            Section::Matches => self.section_widths[1],
+            Section::Tail => self.section_widths[2],
+            Section::Checkpoint => None,
+        };
+        own.unwrap_or(self.width)
+    }
+
+    /// `content` width-truncated around its first match, if the usual cut
+    /// would hide it.
+    fn truncate_match(&self, content: &str, width: usize) -> Option<TruncatedLine> {
+        // A line that fits in bytes fits in chars and columns too
+        if width == 0 || content.len() <= width * 2 {
+            return None;
+        }
+        let span = self.highlighter.as_ref()?.first(content)?;
+        let (text, bytes_removed, markers) = width::truncate_around(
+            content,
+            width,
+            self.width_mode,
+            self.line_cut,
+            span,
+            self.style,
+        )?;
+        Some(TruncatedLine {
+            text,
+            bytes_removed,
+            markers,
+        })
+    }
+
+    /// With `--normalize-timestamps`: count from the timestamp `line`
+    /// starts with, if it has one. True if it did.
+    pub(crate) fn timestamp_origin(&mut self, line: &str) -> bool {
+        self.restamp.as_mut().is_some_and(|r| r.set_origin(line))
+    }
+
+    /// With `--timestamps`: when the line about to be written was read.
+    pub(crate) fn stamp(&mut self, at: Instant) {
+        self.stamp = Some(at);
+    }
+
+    /// The [`Output::stamp`] as `kind` shows it.
+    fn stamp_text(&self, kind: Timestamps) -> String {
+        let (started, started_at) = self.started;
+        let since = self
+            .stamp
+            .unwrap_or(started)
+            .saturating_duration_since(started);
+        match kind {
+            Timestamps::Wall => timestamp::format(started_at + since),
+            Timestamps::Relative => timestamp::offset_text(since.as_millis() as Millis),
+        }
+    }
+
+    /// Bytes that `input_line` would write for line `number`, `content`.
+    /// Any notes it's followed by count too.
+    pub(crate) fn cost(&self, number: usize, content: &str, section: Section) -> usize {
+        let color = if self.color { color::DIM_OVERHEAD } else { 0 };
+        let notes = match (&self.marker_sink, self.notes.get(&number)) {
+            (None, Some(notes)) => notes.iter().map(|note| note.len() + 1 + color).sum(),
+            _ => 0,
+        };
+        self.render(number, content, section).text.len() + 1 + notes
+    }
+
+    /// Most bytes a marker with this body can take, including the byte-limit
+    /// note, region id, spill note and color.
+    pub(crate) fn marker_cost(&self, body: &str) -> usize {
+        if self.marker_sink.is_some() {
+            return 0;
+        }
+        let color = if self.color { color::DIM_OVERHEAD } else { 0 };
+        let id = if self.region_ids {
+            marker::ID_OVERHEAD
+        } else {
+            0
+        };
+        body.len() + MARKER_OVERHEAD + id + self.spill_note_len + color
+    }
+
+    /// Emit line `number` of the input, `content`, in `section`.
+    pub(crate) fn input_line(&mut self, number: usize, content: &str, section: Section) {
+        if self.table && number == 1 {
+            self.columns = table::plan(content, self.width, self.width_mode);
+        }
+        let truncated = self.render(number, content, section);
+        let rewrite = self.rewrites.remove(&number);
+        if self.write_line(&truncated.text) {
+            if let Some((from, to)) = rewrite {
+                self.rewritten_from += from;
+                self.rewritten_to += to;
+            }
+            self.input_lines_emitted += 1;
+            // Redaction can make a line longer than its input
+            self.input_bytes_emitted += (content.len() + 1).saturating_sub(truncated.bytes_removed);
+            self.copy_to_section(number, content, section, truncated.text);
+            for note in self.notes.remove(&number).unwrap_or_default() {
+                self.marker(&note);
+            }
+        }
+    }
+
+    /// Copy a line just shown to its section's sink, if it has one.
+    fn copy_to_section(&mut self, number: usize, content: &str, section: Section, shown: String) {
+        if !matches!(self.section_sink(section), Some(Some(_))) {
+            return;
+        }
+        let text = match self.color {
+            true => self.render_as(number, content, section, false).text,
+            false => shown,
+        };
+        if let Some(Some(sink)) = self.section_sink(section) {
+            let result = writeln!(sink, "{}", text);
+            self.check(result);
+        }
+    }
+
+    /// Emit line `number` of the input again, as a `--longest` line: it
+    /// isn't counted as shown a second time.
+    pub(crate) fn repeated_line(&mut self, number: usize, content: &str) {
+        let truncated = self.render(number, content, Section::Checkpoint);
+        self.write_line(&truncated.text);
+    }
+
+    /// Emit a `==> name <==` header before an input's output, separated
+    /// from any previous input by a blank line.
+    pub fn header(&mut self, name: &str) {
+        if self.wrote_header {
+            self.write_note("");
+        }
+        self.wrote_header = true;
+        self.input = Some(name.to_string());
+        self.write_note(&marker::header(name));
+    }
+
+    /// Most bytes [`Output::header`] writes for `name` to the output.
+    pub fn header_len(&self, name: &str) -> usize {
+        if self.marker_sink.is_some() {
+            return 0;
+        }
+        marker::header(name).len() + 2
+    }
+
+    /// Emit a truncation marker.
+    pub(crate) fn marker(&mut self, text: &str) {
+        let written = if self.color {
+            self.write_note(&color::dim(text))
+        } else {
+            self.write_note(text)
+        };
+        if written {
+            self.markers_written += 1;
+        }
+    }
+
+    /// This input's share of what's left under the byte limit (None if
+    /// there is no limit), for [`Truncator::stream`].
+    ///
+    /// `inputs_left` counts this input and those after it; `headers` is the
+    /// bytes of headers still to come after this one. Room for each input's
+    /// end marker and the trailer is held back.
+    ///
+    /// [`Truncator::stream`]: crate::Truncator::stream
+    pub fn share(&self, inputs_left: usize, headers: usize) -> Option<usize> {
+        let sunk = self.marker_sink.is_some();
+        let trailer = if self.digest.is_some() && !sunk {
+            TRAILER_RESERVE
+        } else {
+            0
+        };
+        let inputs_left = inputs_left.max(1);
+        self.limit.map(|limit| {
+            let marker = if sunk {
+                0
+            } else {
+                MARKER_RESERVE + self.spill_note_len
+            };
+            let reserved = self.bytes_written + trailer + headers + marker * inputs_left;
+            limit.saturating_sub(reserved) / inputs_left
+        })
+    }
+
+    /// Input bytes left out of the output so far, out of `input_bytes` read
+    /// (lines counted with their newline).
+    pub fn bytes_hidden(&self, input_bytes: usize) -> usize {
+        // Redaction and collapsed runs' counts add bytes that aren't input
+        (input_bytes + self.rewritten_to)
+            .saturating_sub(self.input_bytes_emitted + self.rewritten_from)
+    }
+
+    /// Line `number`, of `from` input bytes, is passed on as `to` bytes
+    /// (escapes stripped, progress collapsed, a `\r\n` ending made `\n`,
+    /// invalid UTF-8 replaced):
+    /// if it's shown, none of it counts as hidden, and if not, all of it
+    /// does.
+    pub(crate) fn rewritten(&mut self, number: usize, from: usize, to: usize) {
+        let rewrite = self.rewrites.entry(number).or_default();
+        rewrite.0 += from;
+        rewrite.1 += to;
+    }
+
+    /// Follow line `number`, if it's shown, with the marker `text`: what
+    /// became of it, or of the lines after it, before it was numbered
+    /// (progress redraws or a run of similar lines collapsed, trace frames
+    /// folded).
+    pub(crate) fn note(&mut self, number: usize, text: String) {
+        self.notes.entry(number).or_default().push(text);
+    }
+
+    /// No line before `number` will be shown (with `None`, no line of this
+    /// input), so their rewrites and notes can be forgotten.
+    pub(crate) fn forget_rewrites(&mut self, before: Option<usize>) {
+        if self.rewrites.is_empty() && self.notes.is_empty() {
+            return;
+        }
+        match before {
+            Some(number) => {
+                self.rewrites = self.rewrites.split_off(&number);
+                self.notes = self.notes.split_off(&number);
+            }
+            None => {
+                self.rewrites.clear();
+                self.notes.clear();
+            }
+        }
+    }
+
+    /// Record that a marker hides input `lines`, `bytes` into the input.
+    /// Returns the region's id.
+    pub(crate) fn region(&mut self, lines: RangeInclusive<usize>, bytes: Range<usize>) -> usize {
+        self.hiding(lines.clone().count(), bytes.len());
+        self.regions.add(self.input.as_deref(), lines, bytes)
+    }
+
+    /// The input byte ranges hidden by markers so far, region 1 first: for
+    /// the spill file's index.
+    pub fn spill_regions(&self) -> Vec<Range<usize>> {
+        self.regions.bytes()
+    }
+
+    /// What each marker so far hid, region 1 first. Only recorded with
+    /// [`Truncator::region_ids`], [`Truncator::spill`],
+    /// [`Truncator::byte_ranges`] or [`Truncator::track_regions`].
+    ///
+    /// [`Truncator::region_ids`]: crate::Truncator::region_ids
+    /// [`Truncator::byte_ranges`]: crate::Truncator::byte_ranges
+    /// [`Truncator::spill`]: crate::Truncator::spill
+    /// [`Truncator::track_regions`]: crate::Truncator::track_regions
+    pub fn regions(&self) -> &[Region] {
+        self.regions.all()
+    }
+
+    /// An input with a pattern finished having found `found` matches and
+    /// shown `shown` of them.
+    pub(crate) fn count_matches(&mut self, found: usize, shown: usize) {
+        let (total_found, total_shown) = self.matches.get_or_insert((0, 0));
+        *total_found += found;
+        *total_shown += shown;
+    }
+
+    /// Matches found and shown over all finished inputs, if there was a
+    /// pattern.
+    pub fn matches(&self) -> Option<(usize, usize)> {
+        self.matches
+    }
+
+    /// Input lines written so far.
+    pub fn lines_written(&self) -> usize {
+        self.input_lines_emitted
+    }
+
+    /// Markers written so far, to the output or the marker sink.
+    pub fn markers_written(&self) -> usize {
+        self.markers_written
+    }
+
+    /// Bytes written so far (not counting any marker sink).
+    pub fn bytes_written(&self) -> usize {
+        self.bytes_written
+    }
+
+    /// Input lines left out of the output so far, out of `input_lines` read.
+    pub fn lines_hidden(&self, input_lines: usize) -> usize {
+        input_lines - self.input_lines_emitted
+    }
+
+    /// Whether anything was left out of the output: hidden lines or bytes
+    /// cut from long lines.
+    pub fn truncated(&self, input_lines: usize, input_bytes: usize) -> bool {
+        self.lines_hidden(input_lines) > 0 || self.bytes_hidden(input_bytes) > 0
+    }
+
+    /// Flush lines just shown, as far as the [`FlushPolicy`] allows.
+    pub(crate) fn flush_shown(&mut self) {
+        let due = match self.flush_policy {
+            FlushPolicy::Line => true,
+            FlushPolicy::Block => false,
+            FlushPolicy::Interval(interval) => self.last_flush.elapsed() >= interval,
+        };
+        if due {
+            self.flush();
+        }
+    }
+
+    /// Flush whatever's pending, whatever the [`FlushPolicy`].
+    pub fn flush(&mut self) {
+        self.last_flush = Instant::now();
+        let result = self.inner.flush();
+        self.check(result);
+        if let Some(sink) = &mut self.marker_sink {
+            let result = sink.flush();
+            self.check(result);
+        }
+        let results: Vec<_> = self
+            .section_sinks
+            .iter_mut()
+            .flatten()
+            .map(|sink| sink.flush())
+            .collect();
+        for result in results {
+            self.check(result);
+        }
+    }
+
+    /// The writer, back from the output: e.g. a `Vec<u8>` written to.
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+
+    #[cfg(feature = "async")]
+    pub(crate) fn inner_mut(&mut self) -> &mut W {
+        &mut self.inner
+    }
+
+    /// Append the trailer line, if one was requested, and flush.
+    ///
+    /// `input_lines` and `input_bytes` are the totals read; a line given
+    /// without a [`Stream::size`](crate::Stream::size) counts as one byte
+    /// longer than its content for the newline. Call it once, after the
+    /// last input; the totals stay readable afterwards.
+    pub fn finish(&mut self, input_lines: usize, input_bytes: usize) -> io::Result<()> {
+        if self.region_ids {
+            let footer: Vec<String> = self.regions.footer().collect();
+            for line in footer {
+                self.marker(&line);
+            }
+        }
+        if let Some(digest) = self.digest.take() {
+            let hex: String = digest
+                .finalize()
+                .iter()
+                .map(|b| format!("{:02x}", b))
+                .collect();
+            let trailer = marker::trailer(
+                &hex,
+                self.lines_hidden(input_lines),
+                self.bytes_hidden(input_bytes),
+            );
+            if self.marker_sink.is_some() {
+                self.write_note(&trailer);
+            } else if self.reserve(trailer.len() + 1) {
+                let result = writeln!(self.inner, "{}", trailer);
+                self.check(result);
+            }
+        }
+        self.flush();
+        match self.error.take() {
+            Some(e) => Err(e),
+            None => Ok(()),
+        }
+    }
+}
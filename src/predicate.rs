@@ -0,0 +1,43 @@
+//! Boolean combinations of regexes, for narrowing what counts as a match:
+//! `--all-of`, `--any-of` and `--and-not` on the command line.
+
+use regex::Regex;
+
+/// A condition on a line, built up from regexes.
+#[derive(Clone, Debug)]
+pub enum Predicate {
+    /// The line matches this regex
+    Regex(Regex),
+    /// Every one of these holds (so an empty list always does)
+    All(Vec<Predicate>),
+    /// At least one of these holds (so an empty list never does)
+    Any(Vec<Predicate>),
+    /// This doesn't hold
+    Not(Box<Predicate>),
+}
+
+impl Predicate {
+    /// Holds for lines matching every regex in `res`.
+    pub fn all_of<I: IntoIterator<Item = Regex>>(res: I) -> Self {
+        Predicate::All(res.into_iter().map(Predicate::Regex).collect())
+    }
+
+    /// Holds for lines matching any regex in `res`.
+    pub fn any_of<I: IntoIterator<Item = Regex>>(res: I) -> Self {
+        Predicate::Any(res.into_iter().map(Predicate::Regex).collect())
+    }
+
+    /// Holds where `self` doesn't.
+    pub fn negate(self) -> Self {
+        Predicate::Not(Box::new(self))
+    }
+
+    pub fn is_match(&self, line: &str) -> bool {
+        match self {
+            Predicate::Regex(re) => re.is_match(line),
+            Predicate::All(all) => all.iter().all(|p| p.is_match(line)),
+            Predicate::Any(any) => any.iter().any(|p| p.is_match(line)),
+            Predicate::Not(p) => !p.is_match(line),
+        }
+    }
+}
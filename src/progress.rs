@@ -0,0 +1,16 @@
+//! Progress bars and spinners redraw themselves with `\r`, so one line of
+//! input can hold hundreds of frames. Only the last frame is what a terminal
+//! would have shown.
+
+/// `line` as a terminal would leave it, if it was redrawn: the last frame
+/// with any text, and how many earlier frames were dropped.
+pub(crate) fn collapse(line: &str) -> Option<(String, usize)> {
+    if !line.contains('\r') {
+        return None;
+    }
+    let frames: Vec<&str> = line.split('\r').filter(|f| !f.trim().is_empty()).collect();
+    Some(match frames.split_last() {
+        Some((last, earlier)) => (last.to_string(), earlier.len()),
+        None => (String::new(), 0),
+    })
+}
@@ -0,0 +1,80 @@
+//! Scrubbing secrets from lines before they're written, so truncated logs
+//! can be pasted into issues and prompts.
+//!
+//! A pattern with a group named `secret` redacts only that group, so the
+//! key in `password=hunter2` stays readable; otherwise the whole match goes.
+
+use regex::Regex;
+use std::borrow::Cow;
+
+/// Replaces each redacted span.
+const REDACTED: &str = "[REDACTED]";
+
+/// Built-in patterns for `--redact`.
+const BUILTIN: &[&str] = &[
+    // AWS access key IDs
+    r"\b(AKIA|ASIA)[0-9A-Z]{16}\b",
+    // `aws_secret_access_key = ...` and the like
+    r"(?i)\baws_?secret_?(access_?)?key\b\W{0,3}(?P<secret>[A-Za-z0-9/+=]{40})",
+    // `Authorization: Bearer ...`
+    r"(?i)\bbearer\s+(?P<secret>[A-Za-z0-9._~+/-]+=*)",
+    // `password=...`, `api_key: ...`, `token=...` (quoted or not)
+    r#"(?i)\b(password|passwd|pwd|secret|token|api_?key)\b["']?\s*[=:]\s*["']?(?P<secret>[^\s"'&,;]+)"#,
+    // GitHub tokens
+    r"\bgh[pousr]_[A-Za-z0-9]{36,}\b",
+];
+
+/// Which spans to redact, from [`Redactor::builtin`] and/or
+/// [`Redactor::pattern`].
+#[derive(Clone, Debug, Default)]
+pub struct Redactor {
+    patterns: Vec<Regex>,
+}
+
+impl Redactor {
+    /// No patterns: redacts nothing until some are added.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// AWS keys, bearer tokens, `password=`-style assignments and GitHub
+    /// tokens.
+    pub fn builtin() -> Self {
+        Redactor {
+            patterns: BUILTIN
+                .iter()
+                .map(|re| Regex::new(re).expect("built-in patterns are valid"))
+                .collect(),
+        }
+    }
+
+    /// Also redact matches of `re` (or its `secret` group, if it has one).
+    pub fn pattern(mut self, re: Regex) -> Self {
+        self.patterns.push(re);
+        self
+    }
+
+    /// `line` with every secret replaced by [`REDACTED`].
+    pub(crate) fn redact<'a>(&self, line: &'a str) -> Cow<'a, str> {
+        let mut line = Cow::Borrowed(line);
+        for re in &self.patterns {
+            if !re.is_match(&line) {
+                continue;
+            }
+            let redacted = re.replace_all(&line, |caps: &regex::Captures| {
+                let whole = caps.get(0).expect("group 0 always matches");
+                match caps.name("secret") {
+                    Some(secret) => format!(
+                        "{}{}{}",
+                        &whole.as_str()[..secret.start() - whole.start()],
+                        REDACTED,
+                        &whole.as_str()[secret.end() - whole.start()..]
+                    ),
+                    None => REDACTED.to_string(),
+                }
+            });
+            line = Cow::Owned(redacted.into_owned());
+        }
+        line
+    }
+}
@@ -0,0 +1,91 @@
+//! A uniform random sample of the lines the middle hides (`--sample`), kept
+//! in fixed memory as they go by: reservoir sampling (Algorithm R).
+
+use crate::clock::{SystemTime, UNIX_EPOCH};
+
+/// Up to `size` lines chosen uniformly from all those offered since the
+/// last [`Reservoir::take`].
+#[derive(Debug)]
+pub(crate) struct Reservoir {
+    size: usize,
+    /// Lines offered since the last take
+    seen: usize,
+    /// Line number, input line number and content of each line chosen
+    lines: Vec<(usize, usize, String)>,
+    rng: SplitMix64,
+}
+
+impl Reservoir {
+    /// Sample `size` lines, the same ones on every run for the same `seed`
+    /// and input; without a seed they differ run to run.
+    pub(crate) fn new(size: usize, seed: Option<u64>) -> Self {
+        let seed = seed.unwrap_or_else(|| {
+            let now = SystemTime::now().duration_since(UNIX_EPOCH);
+            now.map_or(0, |d| d.as_nanos() as u64) ^ u64::from(process_id())
+        });
+        Reservoir {
+            size,
+            seen: 0,
+            lines: Vec::with_capacity(size),
+            rng: SplitMix64(seed),
+        }
+    }
+
+    /// Offer a hidden line: kept with probability `size / seen`, in place
+    /// of one chosen before.
+    pub(crate) fn offer(&mut self, line_number: usize, original: usize, content: &str) {
+        self.seen += 1;
+        if self.lines.len() < self.size {
+            self.lines
+                .push((line_number, original, content.to_string()));
+            return;
+        }
+        let slot = self.rng.below(self.seen as u64) as usize;
+        if let Some(kept) = self.lines.get_mut(slot) {
+            *kept = (line_number, original, content.to_string());
+        }
+    }
+
+    /// Forget everything offered so far: those lines are behind a marker
+    /// already.
+    pub(crate) fn clear(&mut self) {
+        self.seen = 0;
+        self.lines.clear();
+    }
+
+    /// The lines chosen, in input order. Starts afresh for the next marker.
+    pub(crate) fn take(&mut self) -> Vec<(usize, usize, String)> {
+        self.seen = 0;
+        let mut lines = std::mem::take(&mut self.lines);
+        lines.sort_unstable_by_key(|(line_number, _, _)| *line_number);
+        lines
+    }
+}
+
+/// SplitMix64: small, fast and plenty random for picking lines.
+#[derive(Debug)]
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// A number in `0..n`, near enough uniform for `n` far below 2^64.
+    fn below(&mut self, n: u64) -> u64 {
+        ((u128::from(self.next()) * u128::from(n)) >> 64) as u64
+    }
+}
+
+/// This process's ID, so runs started in the same instant differ. The
+/// browser has no processes, and std panics there for it.
+fn process_id() -> u32 {
+    match cfg!(all(target_arch = "wasm32", target_os = "unknown")) {
+        true => 0,
+        false => std::process::id(),
+    }
+}
@@ -0,0 +1,116 @@
+//! [`Run`]: one input truncated a line at a time, with no reading of its
+//! own. [`Truncator::process`], [`TruncWriter`](crate::TruncWriter),
+//! [`TruncatedLines`](crate::TruncatedLines) and the async adapters all
+//! push lines into one, however they get them.
+
+use std::io::{self, Write};
+
+use crate::{Output, Stream, Truncator};
+
+/// A [`Stream`] and its [`Output`], with the totals read so far, and with a
+/// [`Selection`](crate::Selection) the lines held for the survey.
+pub(crate) struct Run<'a, W: Write> {
+    config: &'a Truncator,
+    /// Taken when the input ends
+    stream: Option<Stream<'a>>,
+    out: Output<W>,
+    /// With a selection: every line, its size and the bytes cut from it,
+    /// surveyed once the input ends
+    held: Option<Vec<(String, usize, usize)>>,
+    lines: usize,
+    bytes: usize,
+}
+
+impl<'a, W: Write> Run<'a, W> {
+    pub(crate) fn new(config: &'a Truncator, out: Output<W>) -> Self {
+        let stream = config.stream(out.share(1, 0));
+        Run {
+            config,
+            stream: Some(stream),
+            out,
+            held: config.needs_survey().then(Vec::new),
+            lines: 0,
+            bytes: 0,
+        }
+    }
+
+    /// Have the stream report what each marker hides, for
+    /// [`Output::next_item`].
+    pub(crate) fn track_regions(mut self) -> Self {
+        self.stream = self.stream.map(Stream::track_regions);
+        self
+    }
+
+    /// Truncate one line, without its newline.
+    pub(crate) fn line(&mut self, line: &str) {
+        self.sized(line, line.len() + 1, 0);
+    }
+
+    /// Truncate one line as read, with its `\n` or `\r\n` if it has one.
+    pub(crate) fn read(&mut self, line: &str) {
+        self.sized(chomp(line), line.len(), 0);
+    }
+
+    /// Truncate one line, without its newline, that was `size` bytes of
+    /// input once `cut` more were cut from its middle as it came in.
+    pub(crate) fn sized(&mut self, line: &str, size: usize, cut: usize) {
+        self.lines += 1;
+        self.bytes += size + cut;
+        match (&mut self.held, &mut self.stream) {
+            (Some(held), _) => held.push((line.to_string(), size, cut)),
+            (None, Some(stream)) => push(stream, line, size, cut, &mut self.out),
+            (None, None) => {}
+        }
+    }
+
+    /// End the input: survey and truncate held lines, then write the tail
+    /// and the trailer. Does nothing the second time.
+    pub(crate) fn finish(&mut self) -> io::Result<()> {
+        let Some(mut stream) = self.stream.take() else {
+            return Ok(());
+        };
+        if let Some(held) = self.held.take() {
+            let mut survey = self.config.survey();
+            for (line, _, _) in &held {
+                survey.line(line);
+            }
+            stream = stream.shortlist(survey.finish());
+            for (line, size, cut) in &held {
+                push(&mut stream, line, *size, *cut, &mut self.out);
+            }
+        }
+        stream.finish(&mut self.out);
+        self.out.finish(self.lines, self.bytes)
+    }
+
+    /// Whether [`Run::finish`] has been called.
+    pub(crate) fn finished(&self) -> bool {
+        self.stream.is_none()
+    }
+
+    pub(crate) fn out(&mut self) -> &mut Output<W> {
+        &mut self.out
+    }
+
+    pub(crate) fn into_output(self) -> Output<W> {
+        self.out
+    }
+}
+
+/// Have `stream` truncate `line`, `size` bytes of input with `cut` more
+/// cut from it.
+fn push<W: Write>(stream: &mut Stream, line: &str, size: usize, cut: usize, out: &mut Output<W>) {
+    stream.size(size);
+    stream.line(line, out);
+    if cut > 0 {
+        stream.skip(0, cut);
+    }
+}
+
+/// `line` as read, without its `\n` or `\r\n`.
+fn chomp(line: &str) -> &str {
+    match line.strip_suffix('\n') {
+        Some(content) => content.strip_suffix('\r').unwrap_or(content),
+        None => line,
+    }
+}
@@ -0,0 +1,82 @@
+//! Sampling the middle of a timestamped log by time (`--sample-per`): at
+//! most one line per bucket of time is shown, for an overview of what the
+//! head and tail leave out.
+
+use std::time::Duration;
+
+use crate::timestamp::{self, Millis};
+
+/// Which middle lines to show, and those shown since the last marker.
+#[derive(Debug)]
+pub(crate) struct Sample {
+    /// Bucket length in milliseconds
+    every: Millis,
+    /// For timestamps without a year
+    year: i64,
+    last_bucket: Option<Millis>,
+    /// The first line of the stretch sampled since the last marker
+    start: usize,
+    /// Timestamps of the lines shown since the last marker
+    shown: Vec<Millis>,
+}
+
+impl Sample {
+    pub(crate) fn new(every: Duration) -> Self {
+        Sample {
+            every: (every.as_millis() as Millis).max(1),
+            year: timestamp::this_year(),
+            last_bucket: None,
+            start: 0,
+            shown: Vec::new(),
+        }
+    }
+
+    /// Whether to show middle line `line`: it's the first line timestamped
+    /// in its bucket. Lines without a timestamp never are. `gap_start` is
+    /// the first line not yet shown.
+    pub(crate) fn wants(&mut self, gap_start: usize, line: &str) -> bool {
+        let Some(at) = timestamp::leading(line, self.year) else {
+            return false;
+        };
+        let bucket = at.div_euclid(self.every);
+        if self.last_bucket == Some(bucket) {
+            return false;
+        }
+        self.last_bucket = Some(bucket);
+        if self.shown.is_empty() {
+            self.start = gap_start;
+        }
+        self.shown.push(at);
+        true
+    }
+
+    /// Forget a line [`Sample::wants`] picked, when it couldn't be shown.
+    pub(crate) fn unshow(&mut self) {
+        self.shown.pop();
+    }
+
+    /// Where the stretch sampled since the last marker starts, how many
+    /// lines were shown from it, and the time between the first and last of
+    /// them. None if none were. Starts afresh for the next marker.
+    pub(crate) fn take(&mut self) -> Option<(usize, usize, Duration)> {
+        let shown = std::mem::take(&mut self.shown);
+        let (first, last) = (shown.first()?, shown.last()?);
+        let span = Duration::from_millis((last - first).max(0) as u64);
+        Some((self.start, shown.len(), span))
+    }
+}
+
+/// A time span as `00:03:41`, with days if it has any (`2d 01:00:00`).
+pub(crate) fn span(span: Duration) -> String {
+    let seconds = span.as_secs();
+    let clock = format!(
+        "{:02}:{:02}:{:02}",
+        seconds / 3600 % 24,
+        seconds / 60 % 60,
+        seconds % 60
+    );
+    match seconds / 86400 {
+        0 => clock,
+        days => format!("{}d {}", days, clock),
+    }
+}
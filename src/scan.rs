@@ -0,0 +1,125 @@
+//! Finding a file's matches on every core before the main pass
+//! (`--parallel`), for multi-GB archived logs where running the pattern
+//! on each line is most of the work. The file is split into chunks on line
+//! boundaries, the chunks are scanned at once, and their matches are
+//! numbered on from the lines of the chunks before, so the [`Scanned`]
+//! lines are what a scan in order would find. The main pass still reads
+//! every line, looking matches up instead of matching them, so markers,
+//! context and budgets come out as they would without it. Without the
+//! `parallel` feature the chunks are scanned one after another.
+
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Read, Seek, SeekFrom};
+use std::path::Path;
+
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+
+use crate::matcher::Matcher;
+use crate::Truncator;
+
+/// Bytes a chunk spans at least: enough that scanning it outweighs
+/// finding its ends and opening the file again.
+const CHUNK: u64 = 8 << 20;
+
+/// Line numbers of the lines a pattern matches, from [`Truncator::scan`].
+#[derive(Clone, Debug, Default)]
+pub struct Scanned(Vec<usize>);
+
+impl Scanned {
+    pub(crate) fn contains(&self, line_number: usize) -> bool {
+        self.0.binary_search(&line_number).is_ok()
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.0.len()
+    }
+}
+
+/// Scan the regular file at `path` for lines `config`'s pattern matches.
+/// Lines end as [`BufRead::lines`] ends them, and are cleaned as the main
+/// pass cleans them; with `lossy`, invalid UTF-8 is replaced before
+/// matching, and otherwise such a line isn't a match (the main pass
+/// reports it).
+pub(crate) fn scan(config: &Truncator, path: &Path, lossy: bool) -> io::Result<Scanned> {
+    let Some(pattern) = &config.patterns else {
+        return Ok(Scanned::default());
+    };
+    let bounds = bounds(path)?;
+    #[cfg(feature = "parallel")]
+    let spans = bounds.par_windows(2);
+    #[cfg(not(feature = "parallel"))]
+    let spans = bounds.windows(2);
+    let chunks = spans
+        .map(|span| scan_chunk(config, pattern, path, span[0], span[1], lossy))
+        .collect::<io::Result<Vec<_>>>()?;
+    let mut matches = Vec::new();
+    let mut lines_before = 0;
+    for (lines, found) in chunks {
+        matches.extend(found.into_iter().map(|n| lines_before + n));
+        lines_before += lines;
+    }
+    Ok(Scanned(matches))
+}
+
+/// Where each chunk starts, and the file's length: every one a line start
+/// at least [`CHUNK`] past the last.
+fn bounds(path: &Path) -> io::Result<Vec<u64>> {
+    let mut reader = BufReader::new(File::open(path)?);
+    let len = reader.get_ref().metadata()?.len();
+    let mut bounds = vec![0];
+    let mut start = CHUNK;
+    while start < len {
+        // The next line start after the byte before `start`
+        reader.seek(SeekFrom::Start(start - 1))?;
+        let to_newline = reader.skip_until(b'\n')? as u64;
+        let next = start - 1 + to_newline;
+        if next >= len {
+            break;
+        }
+        bounds.push(next);
+        start = next + CHUNK;
+    }
+    bounds.push(len);
+    Ok(bounds)
+}
+
+/// The lines from byte `start` to `end`: how many there are, and which of
+/// them (numbered from 1) match.
+fn scan_chunk(
+    config: &Truncator,
+    pattern: &Matcher,
+    path: &Path,
+    start: u64,
+    end: u64,
+    lossy: bool,
+) -> io::Result<(usize, Vec<usize>)> {
+    let mut file = File::open(path)?;
+    file.seek(SeekFrom::Start(start))?;
+    let mut reader = BufReader::new(file.take(end - start));
+    let mut buf = Vec::new();
+    let mut lines = 0;
+    let mut found = Vec::new();
+    while reader.read_until(b'\n', &mut buf)? > 0 {
+        lines += 1;
+        let mut line = buf.as_slice();
+        if let Some(rest) = line.strip_suffix(b"\n") {
+            line = rest.strip_suffix(b"\r").unwrap_or(rest);
+        }
+        let line = match std::str::from_utf8(line) {
+            Ok(line) => Some(line.into()),
+            Err(_) if lossy => Some(String::from_utf8_lossy(line)),
+            Err(_) => None,
+        };
+        let matched = line.is_some_and(|l| {
+            let cleaned = config.clean(&l);
+            let l = cleaned.as_deref().unwrap_or(&l);
+            pattern.is_match(l) && config.keeps_match(l)
+        });
+        if matched {
+            found.push(lines);
+        }
+        buf.clear();
+    }
+    Ok((lines, found))
+}
@@ -0,0 +1,195 @@
+//! Choosing which matches to show when there are more than `-m`, instead of
+//! just the first ones. This needs the whole input, so it takes a first
+//! pass: a [`Survey`] finds the matches, and the [`Shortlist`] it produces
+//! tells the [`Stream`] on the second pass which to show.
+//!
+//! [`Stream`]: crate::Stream
+
+use regex::{Regex, RegexSet};
+use std::collections::BTreeSet;
+
+use crate::matcher::Matcher;
+use crate::normalize::Dedup;
+use crate::timestamp::Window;
+use crate::traceback::Fold;
+use crate::Truncator;
+
+/// How to choose the matches to show.
+#[derive(Clone, Debug)]
+pub enum Selection {
+    /// Prefer more severe matches: a match's severity is the first regex in
+    /// the ladder (most severe first) that it matches. Ties go to the
+    /// earlier match; matches of no rung come last.
+    Severity(Vec<Regex>),
+    /// A sample across the whole input: the first and last matches, and
+    /// the rest evenly spaced between them.
+    Spread,
+}
+
+impl Selection {
+    /// The default severity ladder: fatal/panic/critical (and `panicked`,
+    /// `crit`), then error, then warning, then info.
+    pub fn severity() -> Self {
+        let ladder = [
+            r"(?i)\b(fatal\w*|panic\w*|crit\w*)\b",
+            r"(?i)\berror\b",
+            r"(?i)\bwarn(ing)?\b",
+            r"(?i)\binfo\b",
+        ];
+        Selection::Severity(
+            ladder
+                .iter()
+                .map(|re| Regex::new(re).expect("built-in ladder is valid"))
+                .collect(),
+        )
+    }
+
+    /// Lower ranks are shown first.
+    fn rank(&self, line: &str) -> usize {
+        match self {
+            Selection::Severity(ladder) => ladder
+                .iter()
+                .position(|re| re.is_match(line))
+                .unwrap_or(ladder.len()),
+            Selection::Spread => 0,
+        }
+    }
+}
+
+/// Line numbers of the matches to show, from [`Survey::finish`].
+#[derive(Clone, Debug, Default)]
+pub struct Shortlist(BTreeSet<usize>);
+
+impl Shortlist {
+    pub(crate) fn contains(&self, line_number: usize) -> bool {
+        self.0.contains(&line_number)
+    }
+}
+
+/// The first pass over an input for a [`Selection`], from
+/// [`Truncator::survey`]. Feed it every line the [`Stream`] will see.
+///
+/// [`Stream`]: crate::Stream
+pub struct Survey<'a> {
+    pattern: Option<&'a Matcher>,
+    exclude: Option<&'a RegexSet>,
+    window: Option<Window>,
+    config: &'a Truncator,
+    fold: Option<Fold>,
+    dedup: Option<Dedup<'a>>,
+    selection: Option<&'a Selection>,
+    first: usize,
+    last: usize,
+    max_matches: usize,
+    line_number: usize,
+    /// (rank, line number) of every match outside the head
+    candidates: Vec<(usize, usize)>,
+}
+
+impl<'a> Survey<'a> {
+    pub(crate) fn new(config: &'a Truncator) -> Self {
+        Survey {
+            pattern: config.patterns.as_ref(),
+            exclude: config.exclude.as_ref(),
+            window: config.window(),
+            config,
+            fold: config.fold_tracebacks.then(Fold::default),
+            dedup: config.normalizer.as_ref().map(Dedup::new),
+            selection: config.selection.as_ref(),
+            // Byte-sized heads and tails can't be told apart without
+            // rendering; matches in them are shown there regardless
+            first: if config.first_bytes.is_some() {
+                0
+            } else {
+                config.head_lines()
+            },
+            last: if config.last_bytes.is_some() {
+                0
+            } else {
+                config.last
+            },
+            max_matches: config.matches,
+            line_number: 0,
+            candidates: Vec::new(),
+        }
+    }
+
+    pub fn line(&mut self, content: &str) {
+        let cleaned = self.config.clean(content);
+        let content = cleaned.as_deref().unwrap_or(content);
+        // Numbered as the stream numbers them, without excluded lines
+        if self.exclude.is_some_and(|set| set.is_match(content)) {
+            return;
+        }
+        if self.window.as_mut().is_some_and(|w| !w.admits(content)) {
+            return;
+        }
+        // Only the stream needs the input's line numbers
+        match &mut self.fold {
+            Some(fold) => {
+                for (line, _) in fold.push(content.to_string(), 0) {
+                    self.folded_line(&line);
+                }
+            }
+            None => self.folded_line(content),
+        }
+    }
+
+    fn folded_line(&mut self, content: &str) {
+        match &mut self.dedup {
+            Some(dedup) => {
+                if let Some((line, ..)) = dedup.push(content.to_string(), 0) {
+                    self.numbered_line(&line);
+                }
+            }
+            None => self.numbered_line(content),
+        }
+    }
+
+    fn numbered_line(&mut self, content: &str) {
+        self.line_number += 1;
+        // Matches in the head are shown as head lines
+        if self.line_number <= self.first {
+            return;
+        }
+        let (Some(pattern), Some(selection)) = (self.pattern, self.selection) else {
+            return;
+        };
+        if pattern.is_match(content) && self.config.keeps_match(content) {
+            self.candidates
+                .push((selection.rank(content), self.line_number));
+        }
+    }
+
+    pub fn finish(mut self) -> Shortlist {
+        for (line, _) in self.fold.as_mut().map(Fold::flush).unwrap_or_default() {
+            self.folded_line(&line);
+        }
+        if let Some((line, ..)) = self.dedup.as_mut().and_then(Dedup::flush) {
+            self.numbered_line(&line);
+        }
+        // Matches in the tail are shown there anyway
+        let tail_start = self.line_number.saturating_sub(self.last);
+        self.candidates
+            .retain(|&(_, line_number)| line_number <= tail_start);
+        let (count, wanted) = (self.candidates.len(), self.max_matches);
+        if let Some(Selection::Spread) = self.selection {
+            if count > wanted {
+                // Index i of `wanted` picks, spaced over 0..=count-1
+                let picks = (0..wanted).map(|i| match wanted {
+                    1 => 0,
+                    _ => i * (count - 1) / (wanted - 1),
+                });
+                return Shortlist(picks.map(|i| self.candidates[i].1).collect());
+            }
+        }
+        self.candidates.sort();
+        Shortlist(
+            self.candidates
+                .into_iter()
+                .take(wanted)
+                .map(|(_, line_number)| line_number)
+                .collect(),
+        )
+    }
+}
@@ -0,0 +1,2058 @@
+//! Truncation state for one input: streams the head and matches as lines
+//! arrive, and holds the rolling tail until EOF (or a follow-mode refresh).
+
+use regex::{Regex, RegexSet};
+use std::collections::VecDeque;
+use std::io::Write;
+use std::ops::RangeInclusive;
+
+use crate::arrival::{Arrival, Arrivals};
+use crate::block::{self, Lang, MAX_EXTENSION};
+use crate::clock::Instant;
+use crate::longest::Longest;
+use crate::marker::{self, Body, MarkerStyle, Unit};
+use crate::matcher::Matcher;
+use crate::normalize::Dedup;
+use crate::output::{truncate_line_styled, Output, Section, BYTE_LIMIT_NOTE, MEMORY_LIMIT_NOTE};
+use crate::reservoir::Reservoir;
+use crate::sample::{self, Sample};
+use crate::scan::Scanned;
+use crate::select::Shortlist;
+use crate::summary::Summary;
+use crate::timestamp::Window;
+use crate::traceback::Fold;
+use crate::unique::Unique;
+use crate::{TimestampRewrite, Truncator};
+
+/// Most buffers of lines dropped from the tail and context buffers kept
+/// for the next lines buffered to be copied into.
+const SPARE_BUFFERS: usize = 4;
+
+/// Largest buffer kept for reuse: past this, a line long enough to grow
+/// it would leave every later line buffered holding that much.
+const SPARE_CAPACITY: usize = 4096;
+
+/// Byte allowances for one input under `--max-bytes`.
+///
+/// The input's share is split between head, matches and tail in proportion
+/// to the lines each would show. Whatever the head and matches don't use
+/// goes to the tail.
+struct Budget {
+    head: usize,
+    matches: usize,
+    tail: usize,
+}
+
+impl Budget {
+    fn split(bytes: usize, head_lines: usize, match_lines: usize, tail_lines: usize) -> Self {
+        let total = (head_lines + match_lines + tail_lines).max(1) as u128;
+        let share = |lines: usize| (bytes as u128 * lines as u128 / total) as usize;
+        let head = share(head_lines);
+        let matches = share(match_lines);
+        Budget {
+            head,
+            matches,
+            tail: bytes - head - matches,
+        }
+    }
+}
+
+/// The match budget a match is charged to.
+#[derive(Clone, Copy)]
+enum Quota {
+    /// `-m`, shared by patterns without a budget of their own
+    Shared,
+    /// The budget of the pattern at this index
+    Pattern(usize),
+}
+
+/// Truncation state for one input, from [`Truncator::stream`].
+///
+/// Each input gets its own head, match and tail budgets. Feed it lines with
+/// [`Stream::line`] and end it with [`Stream::finish`].
+pub struct Stream<'a> {
+    pattern: Option<&'a Matcher>,
+    exclude: Option<&'a RegexSet>,
+    /// Lines dropped by `exclude` since the last excluded-lines marker,
+    /// and their bytes
+    excluded: (usize, usize),
+    /// `--since`/`--until`, if given
+    window: Option<Window>,
+    /// Lines dropped by `window` since the last marker noting them, and
+    /// their bytes
+    outside: (usize, usize),
+    /// A line has been inside `window`. The lines before it were skipped
+    /// by `--since`, and are marked before it rather than at the end
+    window_opened: bool,
+    /// `--sample-per`, without a pattern
+    sample: Option<Sample>,
+    /// `--summarize`, without a pattern: counts the lines dropped from the
+    /// tail buffer since the last marker
+    summary: Option<Summary>,
+    /// `--sample`, without a pattern: random lines dropped from the tail
+    /// buffer since the last line shown, to show before the tail
+    reservoir: Option<Reservoir>,
+    always: Option<&'a RegexSet>,
+    /// Input line ranges to show, from `--lines`
+    ranges: &'a [RangeInclusive<usize>],
+    /// `--every`: show input lines that are multiples of this
+    every: Option<usize>,
+    /// `--longest`: the longest lines so far, to list after the tail
+    longest: Option<Longest>,
+    /// For [`Truncator::clean`]
+    config: &'a Truncator,
+    /// With `--fold-tracebacks`: holds back tracebacks to fold them
+    fold: Option<Fold>,
+    /// With a normalizer: collapses runs of similar lines
+    dedup: Option<Dedup<'a>>,
+    label_matches: bool,
+    first_count: usize,
+    last_count: usize,
+    /// With a byte-sized head: output bytes it has left
+    head_bytes: Option<usize>,
+    /// With a byte-sized tail: its size, and the output bytes the tail
+    /// buffer would take
+    tail_bytes: Option<usize>,
+    tail_buffered: usize,
+    before_size: usize,
+    after_size: usize,
+    /// `--context-until`: ends "after" context at a line matching it
+    context_until: Option<&'a Regex>,
+    max_matches: usize,
+    /// Per-pattern match budgets (empty without any), the matches shown
+    /// against each, and against the shared `max_matches`
+    limits: Vec<Option<usize>>,
+    shown_of: Vec<usize>,
+    shared_shown: usize,
+    /// `--unique-by`: the keys of the matches shown, and how many matches
+    /// were passed over as repeats since the last marker
+    unique: Option<Unique>,
+    duplicates: usize,
+    /// `-c`: only count matches, in the marker before the tail
+    count_only: bool,
+    /// With a selection: the only matches to show
+    shortlist: Option<Shortlist>,
+    /// With a scan: the lines that match, in place of the pattern
+    scanned: Option<Scanned>,
+    /// `--rebalance`: the matches there's room for, and the lines each
+    /// takes with its context
+    rebalance: Option<(usize, usize)>,
+    /// `--tail-matches`: how many matches the tail holds, and the line
+    /// numbers of the last ones so far. The tail buffer holds their lines
+    /// and context rather than the last lines.
+    tail_matches: Option<usize>,
+    last_matches: VecDeque<usize>,
+
+    lines_seen: usize,
+    /// Lines numbered for truncation: `lines_seen` less excluded ones
+    numbered: usize,
+    head_output_count: usize,
+    matches_shown: usize,
+    total_matches: usize, // counts ALL matches including past cutoff
+    /// Matches in the head, shown there rather than as matches
+    head_matches: usize,
+    /// Matches past the head that the tail shows, not shown as matches
+    tail_shown: usize,
+    /// Matches already accounted for by a count-only summary
+    matches_counted: usize,
+    last_output_line: usize, // Track the last line number we output
+
+    /// Contiguous ranges of lines output during match streaming, so the tail
+    /// can skip only lines that were actually output.
+    match_output_ranges: Vec<(usize, usize)>,
+
+    /// Ring buffer for tail
+    tail_buffer: VecDeque<(usize, usize, String)>,
+
+    /// Context buffer for pattern mode - holds recent lines for "before" context
+    context_buffer: VecDeque<(usize, usize, String)>,
+
+    /// Buffers of lines dropped from the tail and context buffers, to reuse
+    spare: Vec<String>,
+
+    /// Pending "after" context
+    after_context_remaining: usize,
+
+    /// `--block-aware`: lines the head, and the last match's "after"
+    /// context, may still grow by to finish a block
+    head_block: usize,
+    after_block: usize,
+    /// Lines the tail and context buffers hold beyond their size, for
+    /// moving their start back to a block's
+    slack: usize,
+    lang: Lang,
+
+    /// Remaining bytes under `--max-bytes`
+    budget: Option<Budget>,
+    /// What the tail left of the budget, for the `--longest` lines
+    budget_left: Option<usize>,
+
+    /// Lines were hidden for lack of bytes since the last marker
+    budget_cut: bool,
+    /// Matches the head and the tail show, for the end marker to name
+    shown_around: Option<(usize, usize)>,
+
+    /// `--max-memory`: most bytes of lines the tail and context buffers
+    /// hold together, and what they hold now
+    max_memory: Option<usize>,
+    buffered: usize,
+    /// Buffered lines were dropped or cut short for memory since the last
+    /// marker
+    memory_cut: bool,
+
+    /// With `--save`: the spill file markers point into
+    spill: Option<&'a str>,
+    /// `--byte-ranges`: give markers the bytes they hide
+    byte_ranges: bool,
+    region_ids: bool,
+    /// Record regions for [`Output::regions`] even without ids or a spill
+    track_regions: bool,
+    style: MarkerStyle,
+    unit: Unit,
+    /// With regions to record: where this input starts among all inputs'
+    /// bytes
+    byte_offset: usize,
+    /// Input lines that were trunc markers, and the lines each hid, unless
+    /// `--no-idempotent`
+    nested: Option<Vec<(usize, usize)>>,
+    /// With `--normalize-timestamps`: the input's first timestamp is still
+    /// to be found
+    timestamp_origin: bool,
+    /// With `--elapsed`, `--timestamps` or regions to record: when and
+    /// where the lines that can still be shown arrived
+    arrivals: Arrivals,
+    bytes_seen: usize,
+    /// Input line number of the last line shown
+    shown_through: usize,
+    /// Input line number of the last line through folding and collapsing
+    numbered_through: usize,
+    /// [`Truncator::pass_through`]: the lines past the head, held until the
+    /// input turns out to fit in `pass_through` lines (and is shown whole)
+    /// or not
+    pending: Option<Vec<(String, Instant, Option<usize>)>>,
+    pass_through: usize,
+    /// The next line's size as read, from [`Stream::size`]
+    read_size: Option<usize>,
+    /// Input lines passed over by [`Stream::skip`] and not yet explained
+    skipped: Option<(usize, usize)>,
+}
+
+impl<'a> Stream<'a> {
+    /// `max_bytes` is this input's share of `--max-bytes`, excluding markers.
+    pub(crate) fn new(config: &'a Truncator, max_bytes: Option<usize>) -> Self {
+        let pattern = config.patterns.as_ref();
+        let per_match = config.before + config.after + 1;
+        let match_room = if pattern.is_some() && !config.count_only {
+            config.matches + config.match_limits.iter().flatten().sum::<usize>()
+        } else {
+            0
+        };
+        let match_lines = match_room * per_match;
+        Stream {
+            pattern,
+            exclude: config.exclude.as_ref(),
+            excluded: (0, 0),
+            window: config.window(),
+            outside: (0, 0),
+            window_opened: false,
+            sample: config
+                .sample_per
+                .filter(|_| config.patterns.is_none())
+                .map(Sample::new),
+            summary: (config.summarize && config.patterns.is_none()).then(Summary::new),
+            reservoir: config
+                .sample
+                .filter(|_| config.patterns.is_none() && config.sample_per.is_none())
+                .map(|size| Reservoir::new(size, config.seed)),
+            always: config.always.as_ref(),
+            ranges: &config.ranges,
+            every: config.every,
+            longest: config.longest.map(Longest::new),
+            config,
+            fold: config.fold_tracebacks.then(Fold::default),
+            dedup: config.normalizer.as_ref().map(Dedup::new),
+            label_matches: config.label_matches,
+            first_count: match config.first_bytes {
+                Some(_) => usize::MAX,
+                None => config.head_lines(),
+            },
+            last_count: config.last,
+            head_bytes: config.first_bytes,
+            tail_bytes: config.last_bytes,
+            tail_buffered: 0,
+            before_size: config.before,
+            after_size: if config.context_until.is_some() {
+                MAX_EXTENSION
+            } else {
+                config.after
+            },
+            context_until: config.context_until.as_ref(),
+            max_matches: if config.count_only { 0 } else { config.matches },
+            limits: if config.count_only {
+                Vec::new()
+            } else {
+                config.match_limits.clone()
+            },
+            shown_of: vec![0; config.match_limits.len()],
+            shared_shown: 0,
+            unique: pattern
+                .zip(config.unique_by.as_deref())
+                .filter(|_| !config.count_only)
+                .map(|(matcher, template)| Unique::new(matcher, template)),
+            duplicates: 0,
+            count_only: config.count_only,
+            shortlist: None,
+            scanned: None,
+            rebalance: config.rebalance.then_some((match_room, per_match)),
+            tail_matches: config.tail_matches.filter(|_| pattern.is_some()),
+            last_matches: VecDeque::new(),
+            lines_seen: 0,
+            numbered: 0,
+            head_output_count: 0,
+            matches_shown: 0,
+            total_matches: 0,
+            head_matches: 0,
+            tail_shown: 0,
+            matches_counted: 0,
+            last_output_line: 0,
+            match_output_ranges: Vec::new(),
+            tail_buffer: VecDeque::with_capacity(config.last + 1),
+            context_buffer: VecDeque::with_capacity(config.before + 1),
+            spare: Vec::with_capacity(SPARE_BUFFERS),
+            after_context_remaining: 0,
+            head_block: if config.block_aware { MAX_EXTENSION } else { 0 },
+            after_block: 0,
+            slack: if config.block_aware { MAX_EXTENSION } else { 0 },
+            lang: config.lang,
+            budget: max_bytes
+                .map(|bytes| Budget::split(bytes, config.head_lines(), match_lines, config.last)),
+            budget_left: None,
+            budget_cut: false,
+            shown_around: None,
+            max_memory: config.max_memory,
+            buffered: 0,
+            memory_cut: false,
+            spill: config.spill.as_deref(),
+            byte_ranges: config.byte_ranges,
+            region_ids: config.region_ids,
+            track_regions: config.track_regions,
+            style: config.marker_style,
+            unit: if config.records {
+                Unit::Records
+            } else {
+                Unit::Lines
+            },
+            byte_offset: 0,
+            nested: config.idempotent.then(Vec::new),
+            timestamp_origin: config.rewrite_timestamps == Some(TimestampRewrite::Relative),
+            arrivals: Arrivals::default(),
+            bytes_seen: 0,
+            shown_through: 0,
+            numbered_through: 0,
+            pending: config.pass_through_lines().map(|_| Vec::new()),
+            pass_through: config.pass_through_lines().unwrap_or(0),
+            read_size: None,
+            skipped: None,
+        }
+    }
+
+    /// Show only the matches in `shortlist`, from the input's [`Survey`].
+    ///
+    /// [`Survey`]: crate::Survey
+    pub fn shortlist(mut self, shortlist: Shortlist) -> Self {
+        self.shortlist = Some(shortlist);
+        self
+    }
+
+    /// Take the matches from `scanned`, the input's [`Truncator::scan`],
+    /// rather than running the pattern on each line.
+    pub fn scanned(mut self, scanned: Scanned) -> Self {
+        self.scanned = Some(scanned);
+        self
+    }
+
+    /// This input starts `offset` bytes into all the inputs read (and the
+    /// file given to [`Truncator::spill`]), for the byte ranges of regions.
+    pub fn byte_offset(mut self, offset: usize) -> Self {
+        self.byte_offset = offset;
+        self
+    }
+
+    /// Lines set aside for matches not (yet) shown, which the tail gets
+    /// under `--rebalance`.
+    fn unused_match_lines(&self) -> usize {
+        self.rebalance.map_or(0, |(room, per_match)| {
+            room.saturating_sub(self.matches_shown) * per_match
+        })
+    }
+
+    /// Record the regions markers hide, as [`Truncator::track_regions`]
+    /// does, for [`TruncatedLines`](crate::TruncatedLines) to say what each
+    /// hid.
+    pub(crate) fn track_regions(mut self) -> Self {
+        self.track_regions = true;
+        self
+    }
+
+    /// Whether markers record the regions they hide.
+    fn records_regions(&self) -> bool {
+        self.region_ids || self.track_regions || self.spill.is_some() || self.byte_ranges
+    }
+
+    /// Whether to keep when and where lines arrived: for `--elapsed`,
+    /// `--timestamps` or the regions markers record.
+    fn keeps_arrivals(&self) -> bool {
+        self.config.elapsed || self.config.timestamps.is_some() || self.records_regions()
+    }
+
+    /// Where input line `original` starts among all inputs' bytes; one past
+    /// the last line is where the input ends so far.
+    fn byte_position(&self, original: usize) -> usize {
+        let offset = match original > self.lines_seen {
+            true => None,
+            false => self.arrivals.get(original).map(|a| a.offset),
+        };
+        self.byte_offset + offset.unwrap_or(self.bytes_seen)
+    }
+
+    /// Spend `cost` bytes from a section's allowance. Always succeeds without
+    /// `--max-bytes`.
+    fn spend(&mut self, section: Section, cost: impl FnOnce() -> usize) -> bool {
+        let Some(budget) = &mut self.budget else {
+            return true;
+        };
+        let allowance = match section {
+            Section::Head => &mut budget.head,
+            Section::Matches => &mut budget.matches,
+            Section::Tail | Section::Checkpoint => &mut budget.tail,
+        };
+        let cost = cost();
+        if cost > *allowance {
+            return false;
+        }
+        *allowance -= cost;
+        true
+    }
+
+    /// `content` as it's buffered: under `--max-memory`, a line longer than
+    /// half the limit is cut down in the middle, as `--width` would.
+    fn fit(&mut self, content: String) -> String {
+        let Some(limit) = self.max_memory else {
+            return content;
+        };
+        let allowance = limit / 2;
+        if content.len() <= allowance {
+            return content;
+        }
+        self.memory_cut = true;
+        // Up to 4 bytes a char, either side of the cut
+        let width = (allowance / 8).max(1);
+        let (mode, cut) = (self.config.width_mode, self.config.line_cut);
+        truncate_line_styled(&content, width, mode, cut, self.style).text
+    }
+
+    /// `content` copied for buffering, into a spare buffer if there is one,
+    /// and [`fit`](Stream::fit) to the memory limit.
+    fn keep(&mut self, content: &str) -> String {
+        let mut kept = self.spare.pop().unwrap_or_default();
+        kept.push_str(content);
+        self.fit(kept)
+    }
+
+    /// Hold on to the buffer of a line dropped from the tail or context
+    /// buffer, for [`Stream::keep`] to reuse.
+    fn recycle(&mut self, mut buffer: String) {
+        if self.spare.len() < SPARE_BUFFERS && buffer.capacity() <= SPARE_CAPACITY {
+            buffer.clear();
+            self.spare.push(buffer);
+        }
+    }
+
+    /// Under `--max-memory`, drop the oldest buffered lines, tail first,
+    /// until the buffers fit.
+    fn trim_buffers<W: Write>(&mut self, out: &mut Output<W>) {
+        let Some(limit) = self.max_memory else {
+            return;
+        };
+        while self.buffered > limit {
+            let Some((line_number, original, dropped)) = self
+                .tail_buffer
+                .pop_front()
+                .or_else(|| self.context_buffer.pop_front())
+            else {
+                break;
+            };
+            self.buffered -= dropped.len();
+            out.explain(|| {
+                format!(
+                    "line {}: dropped, as the lines held came to more than --max-memory's {} bytes",
+                    original, limit
+                )
+            });
+            self.dropped(line_number, original, &dropped);
+            self.recycle(dropped);
+            self.memory_cut = true;
+        }
+    }
+
+    /// A line dropped from the tail buffer, for `--summarize` to count and
+    /// `--sample` to pick from, unless it's been shown or a marker already
+    /// covered it.
+    fn hidden(&mut self, line_number: usize, original: usize, content: &str) {
+        if line_number <= self.last_output_line {
+            return;
+        }
+        if let Some(summary) = &mut self.summary {
+            summary.add(content);
+        }
+        if let Some(reservoir) = &mut self.reservoir {
+            reservoir.offer(line_number, original, content);
+        }
+    }
+
+    /// A line dropped from the tail buffer as lines arrive, for
+    /// [`Stream::hidden`]. The line arriving may yet be shown, so it's left
+    /// to [`Stream::numbered_line`].
+    fn dropped(&mut self, line_number: usize, original: usize, content: &str) {
+        if line_number < self.numbered {
+            self.hidden(line_number, original, content);
+        }
+    }
+
+    /// The marker body for `lines` hidden before line `next`: with
+    /// `--summarize`, what they were, counting those the tail buffer still
+    /// holds.
+    fn gap_body(&mut self, lines: usize, next: usize) -> Body {
+        let Some(summary) = &mut self.summary else {
+            return Body::Lines(lines);
+        };
+        for (line_number, _, content) in &self.tail_buffer {
+            if *line_number > self.last_output_line && *line_number < next {
+                summary.add(content);
+            }
+        }
+        match summary.take() {
+            Some(digest) => Body::Summarized { lines, digest },
+            None => Body::Lines(lines),
+        }
+    }
+
+    /// Emit `[... <body> ...]` for the gap before input line `next`, noting
+    /// if lines were cut to fit `--max-bytes` or `--max-memory`. With
+    /// `--region-ids` or `--save` the gap is recorded as a region: numbered
+    /// in the marker, or located in the spill file.
+    fn marker<W: Write>(&mut self, out: &mut Output<W>, body: &Body, next: usize) {
+        let first = self.shown_through + 1;
+        // Markers in the input hid more lines than they take
+        let nested = self.nested.as_deref().unwrap_or_default();
+        // A line shown out of order (an `--every` checkpoint in the tail)
+        // can leave `next` before `first`, with nothing between
+        let end = nested.partition_point(|&(line, _)| line < next);
+        let start = nested.partition_point(|&(line, _)| line < first).min(end);
+        let unnested;
+        let body = match &nested[start..end] {
+            [] => body,
+            within => {
+                let hidden = within.iter().map(|&(_, n)| n).sum();
+                unnested = body.clone().unnest(within.len(), hidden);
+                &unnested
+            }
+        };
+        let mut note = String::new();
+        if self.budget_cut {
+            note.push_str(BYTE_LIMIT_NOTE);
+        }
+        if self.memory_cut {
+            note.push_str(MEMORY_LIMIT_NOTE);
+        }
+        self.budget_cut = false;
+        self.memory_cut = false;
+        if let Some((head, tail)) = self.shown_around.take() {
+            note.push_str(&marker::shown_note(head, tail));
+        }
+        if self.config.elapsed {
+            // To the line after, or the last line if they run to the end
+            let at = |original: usize| match original > self.lines_seen {
+                true => self.arrivals.last().map(|a| a.at),
+                false => self.arrivals.get(original).map(|a| a.at),
+            };
+            if let (Some(start), Some(end)) = (at(first), at(next)) {
+                note.push_str(&marker::elapsed_note(end.duration_since(start)));
+            }
+        }
+        out.explain(|| match next > first {
+            true => format!("{}: hidden, under a marker", lines(first, next - 1)),
+            false => "a marker, hiding no lines".to_string(),
+        });
+        if !self.records_regions() {
+            out.marker(&marker::format(self.style, self.unit, None, body, &note));
+            return;
+        }
+        let bytes = self.byte_position(first)..self.byte_position(next);
+        let id = out.region(first..=next - 1, bytes.clone());
+        let spilled = match self.spill {
+            Some(path) => marker::spill_note(path, &bytes, id),
+            None if self.byte_ranges => marker::bytes_note(&bytes),
+            None => String::new(),
+        };
+        let notes = format!("{}{}", note, spilled);
+        let id = self.region_ids.then_some(id);
+        out.marker(&marker::format(self.style, self.unit, id, body, &notes));
+    }
+
+    /// Record a line as output in match_output_ranges.
+    fn record_output(ranges: &mut Vec<(usize, usize)>, ln: usize) {
+        if let Some(last) = ranges.last_mut() {
+            if ln == last.1 + 1 {
+                last.1 = ln; // extend current range
+                return;
+            }
+        }
+        ranges.push((ln, ln)); // start new range
+    }
+
+    /// Lines seen so far, excluded ones included.
+    pub fn line_number(&self) -> usize {
+        self.lines_seen
+    }
+
+    /// Matches found so far, shown or not, if there's a pattern. Those in
+    /// the head count too.
+    pub fn matches_found(&self) -> Option<usize> {
+        self.pattern.map(|_| self.head_matches + self.total_matches)
+    }
+
+    /// Count the next `lines` input lines (`bytes` long, with their line
+    /// endings) as read without looking at them. Only for lines that can't
+    /// be shown: past the head and before the tail given by
+    /// [`Truncator::skip_window`].
+    pub fn skip(&mut self, lines: usize, bytes: usize) {
+        // Lines skipped are lines not shown, so the input isn't shown whole
+        if lines > 0 {
+            self.pending = None;
+            let from = self.skipped.map_or(self.lines_seen + 1, |(from, _)| from);
+            self.skipped = Some((from, self.lines_seen + lines));
+        }
+        self.lines_seen += lines;
+        self.numbered += lines;
+        self.bytes_seen += bytes;
+    }
+
+    /// Process the next input line, streaming whatever can be output now.
+    ///
+    /// Only the lines held on to are copied, so the caller can read each
+    /// line into the same buffer.
+    pub fn line<W: Write>(&mut self, content: &str, out: &mut Output<W>) {
+        self.line_at(content, Instant::now(), out);
+    }
+
+    /// The next line was `bytes` of input, not its text and a `\n` (it
+    /// ended in `\r\n` or at the end of the input without one, or had
+    /// invalid UTF-8 replaced): shown with the line if it is.
+    pub fn size(&mut self, bytes: usize) {
+        self.read_size = Some(bytes);
+    }
+
+    /// [`Stream::line`], for a line read `at`.
+    fn line_at<W: Write>(&mut self, content: &str, at: Instant, out: &mut Output<W>) {
+        self.explain_skipped(out);
+        let read = self.read_size.take();
+        let seen = self.lines_seen;
+        let fits = self.pass_through;
+        if let Some(pending) = self.pending.as_mut().filter(|_| seen >= self.first_count) {
+            if pending.is_empty() {
+                out.explain(|| {
+                    format!(
+                        "line {}: held, with the lines after it, in case the input fits in {} lines and is shown whole",
+                        seen + 1,
+                        fits
+                    )
+                });
+            }
+            pending.push((content.to_string(), at, read));
+            let held = seen + pending.len();
+            if held > fits {
+                out.explain(|| {
+                    format!(
+                        "line {}: more than {} lines, so the lines held are truncated as usual",
+                        held, fits
+                    )
+                });
+                self.release_pending(out);
+            }
+            return;
+        }
+        self.lines_seen += 1;
+        if let Some(scanned) = self.scanned.as_ref().filter(|_| self.lines_seen == 1) {
+            out.explain(|| {
+                format!(
+                    "matches looked up in the --parallel scan ({} found), not matched line by line",
+                    scanned.len()
+                )
+            });
+        }
+        if self.keeps_arrivals() {
+            let offset = self.bytes_seen;
+            self.arrivals.push(self.lines_seen, Arrival { at, offset });
+            out.stamp(at);
+        }
+        let size = read.unwrap_or(content.len() + 1);
+        self.bytes_seen += size;
+        if size != content.len() + 1 {
+            out.rewritten(self.lines_seen, size, content.len() + 1);
+        }
+        let (cleaned, redraws) = self.config.clean_counting(content);
+        if let Some(cleaned) = &cleaned {
+            out.rewritten(self.lines_seen, content.len(), cleaned.len());
+        }
+        if redraws > 0 {
+            let body = Body::Progress(redraws);
+            out.note(
+                self.lines_seen,
+                marker::format(self.style, self.unit, None, &body, ""),
+            );
+        }
+        let content = cleaned.as_deref().unwrap_or(content);
+        if self.timestamp_origin && out.timestamp_origin(content) {
+            self.timestamp_origin = false;
+        }
+        if let Some(nested) = &mut self.nested {
+            if let Some(hidden) = marker::hidden_lines(content) {
+                nested.push((self.lines_seen, hidden));
+            }
+        }
+        // Excluded lines don't count as lines at all
+        if self.exclude.is_some_and(|set| set.is_match(content)) {
+            self.excluded.0 += 1;
+            self.excluded.1 += size;
+            let line = self.lines_seen;
+            out.explain(|| format!("line {}: excluded by --exclude, and not counted", line));
+            return;
+        }
+        if self.window.as_mut().is_some_and(|w| !w.admits(content)) {
+            self.outside.0 += 1;
+            self.outside.1 += size;
+            let line = self.lines_seen;
+            out.explain(|| format!("line {}: outside --since/--until, and not counted", line));
+            return;
+        }
+        if self.window.is_some() && !self.window_opened {
+            self.window_opened = true;
+            self.mark_outside(out);
+        }
+        let original = self.lines_seen;
+        match &mut self.fold {
+            Some(fold) => {
+                let released = fold.push(content.to_string(), original);
+                self.release_folded(released, out);
+            }
+            None => self.folded_line(content, original, out),
+        }
+        self.settle(out);
+    }
+
+    /// Mark the lines dropped by `window` since the last marker noting
+    /// them, if any.
+    fn mark_outside<W: Write>(&mut self, out: &mut Output<W>) {
+        if let (lines @ 1.., bytes) = self.outside {
+            out.hiding(lines, bytes);
+            let body = Body::Outside(lines);
+            out.marker(&marker::format(self.style, self.unit, None, &body, ""));
+            self.outside = (0, 0);
+        }
+    }
+
+    /// Forget the rewrites and arrivals of lines that can't be shown any
+    /// more: those before the lines the tail and context buffers hold, and
+    /// before any still held for folding or collapsing. The first line of
+    /// the open gap keeps its arrival, where the marker's time and bytes
+    /// start. `--sample` and `--longest` can show any line, so they keep
+    /// them all.
+    fn settle<W: Write>(&mut self, out: &mut Output<W>) {
+        if self.reservoir.is_some() || self.sample.is_some() || self.longest.is_some() {
+            return;
+        }
+        let oldest = [self.tail_buffer.front(), self.context_buffer.front()]
+            .into_iter()
+            .flatten()
+            .map(|(_, original, _)| *original)
+            .chain([self.numbered_through + 1])
+            .min();
+        out.forget_rewrites(oldest);
+        // The last line times a marker that runs to the end
+        let last = self.lines_seen;
+        self.arrivals
+            .forget_before(oldest.unwrap_or(last).min(last), self.shown_through + 1);
+    }
+
+    /// Process the lines of a trace just through folding, noting the
+    /// frames left out, if any, after the line they follow.
+    fn release_folded<W: Write>(&mut self, released: Vec<(String, usize)>, out: &mut Output<W>) {
+        if let Some((after, frames)) = self.fold.as_mut().and_then(Fold::take_folded) {
+            let body = Body::Frames(frames);
+            out.note(
+                after,
+                marker::format(self.style, self.unit, None, &body, ""),
+            );
+        }
+        for (line, original) in released {
+            self.folded_line(&line, original, out);
+        }
+    }
+
+    /// Process a line that's through traceback folding.
+    fn folded_line<W: Write>(&mut self, content: &str, original: usize, out: &mut Output<W>) {
+        match &mut self.dedup {
+            Some(dedup) => {
+                if let Some((line, original, run)) = dedup.push(content.to_string(), original) {
+                    self.collapsed_line(&line, original, run, out);
+                }
+            }
+            None => self.numbered_line(content, original, out),
+        }
+    }
+
+    /// Process the first line of a run of `run` similar lines, noting the
+    /// run's length after it if there's more than the one.
+    fn collapsed_line<W: Write>(
+        &mut self,
+        content: &str,
+        original: usize,
+        run: usize,
+        out: &mut Output<W>,
+    ) {
+        if run > 1 {
+            let body = Body::Similar(run);
+            out.note(
+                original,
+                marker::format(self.style, self.unit, None, &body, ""),
+            );
+        }
+        self.numbered_line(content, original, out);
+    }
+
+    /// Process a line that counts towards the line numbers: not excluded,
+    /// and with runs already collapsed. `original` is its line number in
+    /// the input, for `--line-numbers`.
+    ///
+    /// Each stage below sees the line in turn: the head, the tail buffer,
+    /// then, with a pattern, context after the last match, the match
+    /// itself, `--always` and the context buffer.
+    fn numbered_line<W: Write>(&mut self, content: &str, original: usize, out: &mut Output<W>) {
+        self.numbered += 1;
+        self.numbered_through = original;
+        let line_number = self.numbered;
+        if let Some(longest) = &mut self.longest {
+            longest.offer(original, content);
+        }
+
+        self.grow_head_block(line_number, original, content, out);
+        if self.head_line(line_number, original, content, out) {
+            return;
+        }
+
+        // Always maintain tail buffer (filled as matches are found, below,
+        // with --tail-matches)
+        if self.tail_matches.is_none() {
+            self.buffer_tail(line_number, original, content, out);
+        }
+
+        // Pattern mode: look for matches and stream them
+        let Some(re) = self.pattern else {
+            self.line_without_pattern(line_number, original, content, out);
+            return;
+        };
+        self.after_context(line_number, original, content, out);
+        let matched = self.is_match(re, original, content);
+        if matched {
+            self.match_line(re, line_number, original, content, out);
+        }
+
+        // After the match, which shows it anyway if it's one
+        self.show_always(line_number, original, content, out);
+
+        if let Some(k) = self.tail_matches {
+            self.keep_for_tail(line_number, original, content, matched, k, out);
+        }
+        self.buffer_context(line_number, original, content, out);
+    }
+
+    /// `--block-aware`: a head that's just ended grows to the end of its
+    /// block.
+    fn grow_head_block<W: Write>(
+        &mut self,
+        line_number: usize,
+        original: usize,
+        content: &str,
+        out: &mut Output<W>,
+    ) {
+        if self.head_block > 0 && self.head_output_count == self.first_count {
+            if self.head_output_count > 0
+                && self.last_output_line + 1 == line_number
+                && block::continues(content, self.lang)
+            {
+                self.first_count += 1;
+                self.head_block -= 1;
+                out.explain(|| {
+                    format!(
+                        "line {}: the head grows by a line to finish its block",
+                        original
+                    )
+                });
+            } else {
+                self.head_block = 0;
+            }
+        }
+    }
+
+    /// Show the line in the head, if the head has room for it. Returns
+    /// whether it was shown.
+    fn head_line<W: Write>(
+        &mut self,
+        line_number: usize,
+        original: usize,
+        content: &str,
+        out: &mut Output<W>,
+    ) -> bool {
+        if self.head_output_count < self.first_count {
+            let cost = out.cost(original, content, Section::Head);
+            let fits = self.head_bytes.is_none_or(|left| cost <= left);
+            if !fits {
+                // A byte-sized head ends at the first line that doesn't fit
+                self.first_count = self.head_output_count;
+                let left = self.head_bytes.unwrap_or(0);
+                out.explain(|| {
+                    format!(
+                        "line {}: {} bytes, more than the head's {} left, so the head ends",
+                        original, cost, left
+                    )
+                });
+            } else if self.spend(Section::Head, || cost) {
+                if let Some(left) = &mut self.head_bytes {
+                    *left -= cost;
+                }
+                // A match in the head is shown anyway, but still counted
+                let matched = self
+                    .pattern
+                    .is_some_and(|re| self.is_match(re, original, content));
+                if matched {
+                    self.head_matches += 1;
+                }
+                let (shown, size, left) = (
+                    self.head_output_count + 1,
+                    self.first_count,
+                    self.head_bytes,
+                );
+                out.explain(|| {
+                    let why = match left {
+                        Some(left) => format!(
+                            "line {}: shown in the head, which has {} bytes left",
+                            original, left
+                        ),
+                        None => format!(
+                            "line {}: shown in the head ({} of {})",
+                            original, shown, size
+                        ),
+                    };
+                    match matched {
+                        true => format!("{}; it's a match", why),
+                        false => why,
+                    }
+                });
+                self.stamp(original, out);
+                out.input_line(original, content, Section::Head);
+                out.flush_shown();
+                self.shown_through = original;
+                self.head_output_count += 1;
+                self.last_output_line = line_number;
+                return true;
+            } else {
+                // Out of bytes: the head ends here
+                self.first_count = self.head_output_count;
+                self.budget_cut = true;
+                out.explain(|| {
+                    format!(
+                        "line {}: the head's share of --max-bytes is spent, so the head ends",
+                        original
+                    )
+                });
+            }
+        }
+        false
+    }
+
+    /// Without a pattern, a line past the head is shown only if sampled or
+    /// kept by `--always`; it's hidden unless it went into the tail buffer.
+    fn line_without_pattern<W: Write>(
+        &mut self,
+        line_number: usize,
+        original: usize,
+        content: &str,
+        out: &mut Output<W>,
+    ) {
+        self.show_sampled(line_number, original, content, out);
+        self.show_always(line_number, original, content, out);
+        // Too big for the tail buffer, and not shown either
+        if self
+            .tail_buffer
+            .back()
+            .is_none_or(|(ln, _, _)| *ln != line_number)
+        {
+            self.hidden(line_number, original, content);
+        }
+    }
+
+    /// Show the line as context after a match, while there's any left.
+    /// With --block-aware, it runs on to the end of its block; with
+    /// --context-until, up to the terminator.
+    fn after_context<W: Write>(
+        &mut self,
+        line_number: usize,
+        original: usize,
+        content: &str,
+        out: &mut Output<W>,
+    ) {
+        if self.context_until.is_some_and(|re| re.is_match(content)) {
+            self.after_context_remaining = 0;
+            self.after_block = 0;
+        }
+        let extends = self.after_context_remaining == 0
+            && self.after_block > 0
+            && line_number == self.last_output_line + 1
+            && block::continues(content, self.lang);
+        if self.after_context_remaining == 0 && !extends {
+            self.after_block = 0;
+        }
+        if self.after_context_remaining > 0 || extends {
+            if extends {
+                self.after_block -= 1;
+            } else {
+                self.after_context_remaining -= 1;
+            }
+            if line_number > self.last_output_line {
+                if self.spend(Section::Matches, || {
+                    out.cost(original, content, Section::Matches)
+                }) {
+                    let more = self.after_context_remaining;
+                    out.explain(|| match extends {
+                        true => format!(
+                            "line {}: shown, as the block after a match goes on",
+                            original
+                        ),
+                        false if more > 0 => format!(
+                            "line {}: shown as context after a match ({} more)",
+                            original, more
+                        ),
+                        false => format!("line {}: shown as context after a match", original),
+                    });
+                    self.stamp(original, out);
+                    out.input_line(original, content, Section::Matches);
+                    out.flush_shown();
+                    Self::record_output(&mut self.match_output_ranges, line_number);
+                    self.last_output_line = line_number;
+                    self.shown_through = original;
+                } else {
+                    // Out of bytes: cut the context short
+                    self.after_context_remaining = 0;
+                    self.after_block = 0;
+                    self.budget_cut = true;
+                    out.explain(|| {
+                        format!(
+                            "line {}: the matches' share of --max-bytes is spent, so the context after the match ends",
+                            original
+                        )
+                    });
+                }
+            }
+        }
+    }
+
+    /// Count a match and show it, with its marker and context, if it's
+    /// chosen, not a repeat, and within its budgets.
+    fn match_line<W: Write>(
+        &mut self,
+        re: &Matcher,
+        line_number: usize,
+        original: usize,
+        content: &str,
+        out: &mut Output<W>,
+    ) {
+        self.total_matches += 1;
+
+        // Only show if we haven't hit the display limit, and it was
+        // chosen if there's a selection
+        let chosen = self
+            .shortlist
+            .as_ref()
+            .is_none_or(|s| s.contains(line_number));
+        // With --unique-by, a repeat of a match shown is passed over
+        let key = self.unique.as_ref().and_then(|u| u.key(content));
+        let repeat = key
+            .as_deref()
+            .is_some_and(|k| self.unique.as_ref().is_some_and(|u| u.is_repeat(k)));
+        if repeat {
+            self.duplicates += 1;
+        }
+        let quota = self.quota(re, content).filter(|_| chosen && !repeat);
+        let found = self.total_matches;
+        if repeat {
+            out.explain(|| {
+                format!(
+                    "line {}: match {}, not shown: it repeats one shown (--unique-by)",
+                    original, found
+                )
+            });
+        } else if !chosen {
+            out.explain(|| {
+                format!(
+                    "line {}: match {}, not shown: not one of the matches selected",
+                    original, found
+                )
+            });
+        } else if quota.is_none() {
+            out.explain(|| {
+                format!(
+                    "line {}: match {}, not shown: no match budget left (-m)",
+                    original, found
+                )
+            });
+        }
+        let Some(quota) = quota else {
+            return;
+        };
+        self.matches_shown += 1;
+
+        // Calculate gap from last output to this match's context start
+        let context_start = self.context_start(line_number, content);
+        let gap_start = self.last_output_line + 1;
+        let gap_end = context_start.max(gap_start);
+        let lines_truncated = gap_end.saturating_sub(gap_start);
+        let marker = self.match_marker(re, quota, lines_truncated, original, content);
+
+        // Under --max-bytes, the match is only shown if it fits along
+        // with its marker and "before" context; once one doesn't, no
+        // more are shown
+        let cost = match self.budget {
+            Some(_) => self.match_cost(
+                marker.as_ref(),
+                context_start,
+                line_number,
+                original,
+                content,
+                out,
+            ),
+            None => 0,
+        };
+        if !self.spend(Section::Matches, || cost) {
+            self.stop_matches(quota);
+            out.explain(|| {
+                format!(
+                    "line {}: match {} needs {} bytes with its marker and context, more than the matches' share of --max-bytes left, so no more matches are shown",
+                    original, found, cost
+                )
+            });
+            return;
+        }
+        if let (Some(unique), Some(key)) = (self.unique.as_mut(), key) {
+            unique.shown(key);
+        }
+        self.show_match(marker, context_start, line_number, original, content, out);
+    }
+
+    /// The marker before a match shown, counting it against its quota: the
+    /// lines hidden since the last line shown, and which match it is. None
+    /// if it follows on from what's shown, bar the first match after the
+    /// head.
+    fn match_marker(
+        &mut self,
+        re: &Matcher,
+        quota: Quota,
+        lines_truncated: usize,
+        original: usize,
+        content: &str,
+    ) -> Option<Body> {
+        let (shown, max, of) = match quota {
+            Quota::Shared => {
+                self.shared_shown += 1;
+                (self.shared_shown, self.max_matches, String::new())
+            }
+            Quota::Pattern(i) => {
+                self.shown_of[i] += 1;
+                let label = &re.labels()[i];
+                (
+                    self.shown_of[i],
+                    self.limits[i].unwrap_or(0),
+                    format!(" of '{}'", label),
+                )
+            }
+        };
+        let mut match_annotation = if shown == max {
+            // This is the last match we'll show AND we hit the limit
+            format!("match {}/{}{}", shown, max, of)
+        } else {
+            format!("match {}{}", shown, of)
+        };
+        if self.duplicates > 0 {
+            match_annotation = format!(
+                "{}, {}",
+                marker::counted(self.duplicates, "duplicate match", "duplicate matches"),
+                match_annotation
+            );
+        }
+        // Which of several patterns matched, e.g. ` ('ERROR', 'WARN')`
+        let label = if self.label_matches {
+            let matched: Vec<String> = re
+                .matching(content)
+                .iter()
+                .map(|p| format!("'{}'", p))
+                .collect();
+            format!(" ({})", matched.join(", "))
+        } else {
+            String::new()
+        };
+        if lines_truncated > 0
+            || (self.matches_shown == 1 && self.last_output_line >= self.first_count)
+        {
+            // The first match right after the head still gets one,
+            // with 0 lines (context overlaps with head end)
+            Some(Body::Match {
+                lines: lines_truncated,
+                shown: match_annotation,
+                at: self.config.match_line_numbers.then_some(original),
+                label,
+            })
+        } else {
+            None
+        }
+    }
+
+    /// The bytes a match takes to show under `--max-bytes`: its marker, the
+    /// "before" context not shown yet, and the match itself.
+    fn match_cost<W: Write>(
+        &self,
+        marker: Option<&Body>,
+        context_start: usize,
+        line_number: usize,
+        original: usize,
+        content: &str,
+        out: &Output<W>,
+    ) -> usize {
+        let unseen = self
+            .context_buffer
+            .iter()
+            .filter(|(ln, _, _)| *ln >= context_start && *ln > self.last_output_line)
+            .map(|(_, orig, c)| (*orig, c.as_str()))
+            .chain((line_number > self.last_output_line).then_some((original, content)));
+        marker.map_or(0, |m| out.marker_cost(&m.longest()))
+            + unseen
+                .map(|(orig, c)| out.cost(orig, c, Section::Matches))
+                .sum::<usize>()
+    }
+
+    /// A match didn't fit in `--max-bytes`: give back its place in its
+    /// `quota`, and show no more matches of any pattern.
+    fn stop_matches(&mut self, quota: Quota) {
+        self.matches_shown -= 1;
+        match quota {
+            Quota::Shared => self.shared_shown -= 1,
+            Quota::Pattern(i) => self.shown_of[i] -= 1,
+        }
+        self.max_matches = self.shared_shown;
+        for (limit, shown) in self.limits.iter_mut().zip(&self.shown_of) {
+            *limit = limit.map(|_| *shown);
+        }
+        self.budget_cut = true;
+    }
+
+    /// Show a match: its marker, if any, the "before" context not shown
+    /// yet, and the match itself, then set up its "after" context.
+    fn show_match<W: Write>(
+        &mut self,
+        marker: Option<Body>,
+        context_start: usize,
+        line_number: usize,
+        original: usize,
+        content: &str,
+        out: &mut Output<W>,
+    ) {
+        let before = self
+            .context_buffer
+            .iter()
+            .filter(|(ln, _, _)| {
+                *ln >= context_start && *ln > self.last_output_line && *ln < line_number
+            })
+            .map(|(_, ctx_original, _)| *ctx_original);
+        let (from, to) = (before.clone().min(), before.max());
+        let (shown, found) = (self.matches_shown, self.total_matches);
+        if let Some(body) = marker {
+            // The first line shown after it: context, or the match
+            let next = self
+                .context_buffer
+                .iter()
+                .find(|(ln, _, _)| *ln >= context_start && *ln > self.last_output_line)
+                .map_or(original, |(_, ctx_original, _)| *ctx_original);
+            self.marker(out, &body, next);
+            out.flush_shown();
+            self.duplicates = 0;
+        }
+        out.explain(|| {
+            let mut why = format!("line {}: match {}, shown", original, found);
+            if shown != found {
+                why.push_str(&format!(" as match {}", shown));
+            }
+            if let Some((from, to)) = from.zip(to) {
+                why.push_str(&format!(", after {} as context", lines(from, to)));
+            }
+            why
+        });
+
+        // Output "before" context (lines we haven't already output)
+        for (ctx_line_num, ctx_original, ctx_content) in &self.context_buffer {
+            if *ctx_line_num >= context_start
+                && *ctx_line_num > self.last_output_line
+                && *ctx_line_num < line_number
+            {
+                self.stamp(*ctx_original, out);
+                out.input_line(*ctx_original, ctx_content, Section::Matches);
+                Self::record_output(&mut self.match_output_ranges, *ctx_line_num);
+                self.last_output_line = *ctx_line_num;
+                self.shown_through = *ctx_original;
+            }
+        }
+
+        // Output the match line itself (if not already output)
+        if line_number > self.last_output_line {
+            self.stamp(original, out);
+            out.input_line(original, content, Section::Matches);
+            out.flush_shown();
+            Self::record_output(&mut self.match_output_ranges, line_number);
+            self.last_output_line = line_number;
+            self.shown_through = original;
+        }
+
+        // Set up "after" context: for the start of a block, the
+        // block is the context
+        self.after_context_remaining = if self.slack > 0 && block::starts(content, self.lang) {
+            0
+        } else {
+            self.after_size
+        };
+        self.after_block = self.slack;
+    }
+
+    /// Keep the line for "before" context, after checking it for a match.
+    fn buffer_context<W: Write>(
+        &mut self,
+        line_number: usize,
+        original: usize,
+        content: &str,
+        out: &mut Output<W>,
+    ) {
+        let content = self.keep(content);
+        self.buffered += content.len();
+        self.context_buffer
+            .push_back((line_number, original, content));
+        if self.context_buffer.len() > self.before_size + self.slack {
+            if let Some((_, _, content)) = self.context_buffer.pop_front() {
+                self.buffered -= content.len();
+                self.recycle(content);
+            }
+        }
+        self.trim_buffers(out);
+    }
+
+    /// Add line `line_number` to the tail buffer, dropping the oldest
+    /// lines once it's past the tail's size.
+    fn buffer_tail<W: Write>(
+        &mut self,
+        line_number: usize,
+        original: usize,
+        content: &str,
+        out: &mut Output<W>,
+    ) {
+        let buffered = self.keep(content);
+        self.buffered += buffered.len();
+        // The first and last lines dropped to make room, for --explain
+        let mut made_room: Option<(usize, usize)> = None;
+        let mut room = |dropped: usize| {
+            made_room = Some((made_room.map_or(dropped, |(first, _)| first), dropped));
+        };
+        let size = match self.tail_bytes {
+            Some(size) => {
+                self.tail_buffered += out.cost(original, &buffered, Section::Tail);
+                self.tail_buffer
+                    .push_back((line_number, original, buffered));
+                while self.tail_buffered > size {
+                    let Some((line_number, original, content)) = self.tail_buffer.pop_front()
+                    else {
+                        break;
+                    };
+                    self.tail_buffered -= out.cost(original, &content, Section::Tail);
+                    self.buffered -= content.len();
+                    self.dropped(line_number, original, &content);
+                    self.recycle(content);
+                    room(original);
+                }
+                (self.tail_buffered, size, "bytes")
+            }
+            None => {
+                self.tail_buffer
+                    .push_back((line_number, original, buffered));
+                let size = self.last_count + self.unused_match_lines() + self.slack;
+                if self.tail_buffer.len() > size {
+                    if let Some((line_number, original, content)) = self.tail_buffer.pop_front() {
+                        self.buffered -= content.len();
+                        self.dropped(line_number, original, &content);
+                        self.recycle(content);
+                        room(original);
+                    }
+                }
+                (self.tail_buffer.len(), size, "lines")
+            }
+        };
+        out.explain(|| {
+            let (held, size, unit) = size;
+            match made_room {
+                None => format!(
+                    "line {}: held for the tail ({} of {} {})",
+                    original, held, size, unit
+                ),
+                Some((first, last)) => format!(
+                    "line {}: held for the tail ({} of {} {}), which drops {}",
+                    original,
+                    held,
+                    size,
+                    unit,
+                    lines(first, last)
+                ),
+            }
+        });
+        self.trim_buffers(out);
+    }
+
+    /// `--tail-matches`: hold line `line_number` in the tail buffer if it's
+    /// one of the last `k` matches or their context, dropping the lines of
+    /// a match that's no longer one of them.
+    fn keep_for_tail<W: Write>(
+        &mut self,
+        line_number: usize,
+        original: usize,
+        content: &str,
+        matched: bool,
+        k: usize,
+        out: &mut Output<W>,
+    ) {
+        let after = self.config.after;
+        if matched {
+            self.last_matches.push_back(line_number);
+            if self.last_matches.len() > k {
+                self.last_matches.pop_front();
+                let from = self
+                    .last_matches
+                    .front()
+                    .map_or(line_number, |m| m.saturating_sub(self.before_size));
+                while self
+                    .tail_buffer
+                    .front()
+                    .is_some_and(|(ln, _, _)| *ln < from)
+                {
+                    if let Some((_, _, dropped)) = self.tail_buffer.pop_front() {
+                        self.buffered -= dropped.len();
+                        self.recycle(dropped);
+                    }
+                }
+            }
+            if k == 0 {
+                return;
+            }
+            let start = line_number.saturating_sub(self.before_size);
+            let held = self.tail_buffer.back().map_or(0, |(ln, _, _)| *ln);
+            let before: Vec<(usize, usize, String)> = self
+                .context_buffer
+                .iter()
+                .filter(|(ln, _, _)| *ln >= start && *ln > held)
+                .cloned()
+                .collect();
+            for line in before {
+                self.buffered += line.2.len();
+                self.tail_buffer.push_back(line);
+            }
+        } else if self
+            .last_matches
+            .back()
+            .is_none_or(|m| line_number > m + after)
+        {
+            return;
+        }
+        let kept = self.keep(content);
+        self.buffered += kept.len();
+        self.tail_buffer.push_back((line_number, original, kept));
+        out.explain(|| {
+            format!(
+                "line {}: held for the tail, as one of the last {} matches or their context",
+                original, k
+            )
+        });
+        self.trim_buffers(out);
+    }
+
+    /// Whether `content`, input line `original`, is a match: looked up in
+    /// the scan if there was one.
+    fn is_match(&self, re: &Matcher, original: usize, content: &str) -> bool {
+        match &self.scanned {
+            Some(scanned) => scanned.contains(original),
+            None => re.is_match(content) && self.config.keeps_match(content),
+        }
+    }
+
+    /// The budget to charge a match on `content` to, if any has room: that
+    /// of the first pattern it matches with a budget of its own left, or
+    /// the shared one.
+    fn quota(&self, re: &Matcher, content: &str) -> Option<Quota> {
+        let shared = (self.shared_shown < self.max_matches).then_some(Quota::Shared);
+        if self.limits.is_empty() {
+            return shared;
+        }
+        re.matching_indices(content).into_iter().find_map(|i| {
+            match self.limits.get(i).copied().flatten() {
+                Some(limit) => (self.shown_of[i] < limit).then_some(Quota::Pattern(i)),
+                None => shared,
+            }
+        })
+    }
+
+    /// The first line of "before" context for a match on `line_number`,
+    /// `content`: `before_size` lines back or, with `--block-aware`, further
+    /// back to the start of the block that line is in. A match that starts
+    /// a block (a Rust diagnostic) gets none.
+    fn context_start(&self, line_number: usize, content: &str) -> usize {
+        if self.slack == 0 {
+            return line_number.saturating_sub(self.before_size);
+        }
+        if block::starts(content, self.lang) {
+            return line_number;
+        }
+        let buffer = &self.context_buffer;
+        let mut start = buffer.len().saturating_sub(self.before_size);
+        while start > 0
+            && block::continues(buffer.get(start).map_or(content, |(_, _, c)| c), self.lang)
+        {
+            start -= 1;
+        }
+        buffer.get(start).map_or(line_number, |(ln, _, _)| *ln)
+    }
+
+    /// Stream a line from the middle that matches `always`, is in one of
+    /// the `ranges` or is an `--every` checkpoint, if it hasn't been output
+    /// already.
+    fn show_always<W: Write>(
+        &mut self,
+        line_number: usize,
+        original: usize,
+        content: &str,
+        out: &mut Output<W>,
+    ) {
+        let checkpoint = self.every.is_some_and(|n| original.is_multiple_of(n));
+        let wanted = checkpoint
+            || self.always.is_some_and(|set| set.is_match(content))
+            || self.ranges.iter().any(|range| range.contains(&original));
+        if line_number <= self.last_output_line || !wanted {
+            return;
+        }
+        let section = match checkpoint {
+            true => Section::Checkpoint,
+            false => Section::Matches,
+        };
+        // Charged to the tail, which gets whatever the rest leave
+        if !self.spend(Section::Tail, || out.cost(original, content, section)) {
+            self.budget_cut = true;
+            out.explain(|| {
+                format!(
+                    "line {}: wanted, but the tail's share of --max-bytes is spent",
+                    original
+                )
+            });
+            return;
+        }
+        out.explain(|| match checkpoint {
+            true => format!("line {}: shown as an --every checkpoint", original),
+            false => format!("line {}: shown, as --always or --lines asks", original),
+        });
+        let lines_truncated = line_number - self.last_output_line - 1;
+        if lines_truncated > 0 {
+            let body = self.gap_body(lines_truncated, line_number);
+            self.marker(out, &body, original);
+        }
+        // --sample picks from the gap before the tail only
+        if let Some(reservoir) = &mut self.reservoir {
+            reservoir.clear();
+        }
+        self.stamp(original, out);
+        out.input_line(original, content, section);
+        out.flush_shown();
+        Self::record_output(&mut self.match_output_ranges, line_number);
+        self.last_output_line = line_number;
+        self.shown_through = original;
+    }
+
+    /// `--sample-per`: stream a line from the middle that's the first in
+    /// its bucket of time. The lines skipped since the last one get no
+    /// marker; the one before the tail covers them all.
+    fn show_sampled<W: Write>(
+        &mut self,
+        line_number: usize,
+        original: usize,
+        content: &str,
+        out: &mut Output<W>,
+    ) {
+        let gap_start = self.last_output_line + 1;
+        let Some(sample) = &mut self.sample else {
+            return;
+        };
+        if line_number < gap_start || !sample.wants(gap_start, content) {
+            return;
+        }
+        // Charged to the tail, as --always lines are
+        if !self.spend(Section::Tail, || {
+            out.cost(original, content, Section::Matches)
+        }) {
+            self.budget_cut = true;
+            if let Some(sample) = &mut self.sample {
+                sample.unshow();
+            }
+            out.explain(|| {
+                format!(
+                    "line {}: first in its --sample-per span, but the tail's share of --max-bytes is spent",
+                    original
+                )
+            });
+            return;
+        }
+        out.explain(|| format!("line {}: shown, first in its --sample-per span", original));
+        self.stamp(original, out);
+        out.input_line(original, content, Section::Matches);
+        out.flush_shown();
+        Self::record_output(&mut self.match_output_ranges, line_number);
+        self.last_output_line = line_number;
+        self.shown_through = original;
+    }
+
+    /// In follow mode, flush the rolling tail if lines arrived since the last
+    /// output.
+    pub fn refresh<W: Write>(&mut self, out: &mut Output<W>) {
+        self.release_pending(out);
+        // A run in progress shows as it stands; the next line starts anew
+        self.release_held(out);
+        if self.numbered > self.last_output_line {
+            self.flush_tail(out);
+            out.flush();
+        }
+    }
+
+    /// `--idle-timeout`: input has stopped for now, so show the tail as it
+    /// stands, under a marker saying so. Lines read later carry on from it.
+    pub fn stalled<W: Write>(&mut self, out: &mut Output<W>) {
+        self.release_pending(out);
+        self.release_held(out);
+        let line = self.lines_seen;
+        out.explain(|| {
+            format!(
+                "after line {}: input stalled, so the tail so far is shown",
+                line
+            )
+        });
+        out.marker(&marker::format(
+            self.style,
+            self.unit,
+            None,
+            &Body::Stalled,
+            "",
+        ));
+        if self.numbered > self.last_output_line {
+            self.flush_tail(out);
+        }
+        out.flush();
+    }
+
+    /// EOF reached - output the end marker and tail.
+    pub fn finish<W: Write>(mut self, out: &mut Output<W>) {
+        self.explain_skipped(out);
+        if let Some(pending) = self.pending.take() {
+            // It fits: every line is shown, as the head
+            self.first_count = usize::MAX;
+            self.head_block = 0;
+            for (line, at, read) in pending {
+                self.read_size = read;
+                self.line_at(&line, at, out);
+            }
+        }
+        self.release_held(out);
+        self.flush_tail(out);
+        if let Some(longest) = self.longest.take() {
+            self.show_longest(longest, out);
+        }
+        // Whatever wasn't shown by now never will be
+        out.forget_rewrites(None);
+        if self.pattern.is_some() {
+            // Matches in the head and tail were shown too
+            let shown = self.head_matches + self.matches_shown + self.tail_shown;
+            out.count_matches(self.head_matches + self.total_matches, shown);
+        }
+    }
+
+    /// `--longest`: list the longest lines under a marker, as many as fit
+    /// in what's left of the budget.
+    fn show_longest<W: Write>(&mut self, longest: Longest, out: &mut Output<W>) {
+        let mut lines = longest.take();
+        if let Some(left) = &mut self.budget_left {
+            let header = out.marker_cost(&Body::Longest(lines.len()).longest());
+            *left = left.saturating_sub(header);
+            lines.retain(|(original, content)| {
+                let cost = out.cost(*original, content, Section::Checkpoint);
+                let fits = cost <= *left;
+                if fits {
+                    *left -= cost;
+                }
+                fits
+            });
+        }
+        if lines.is_empty() {
+            return;
+        }
+        let body = Body::Longest(lines.len());
+        out.marker(&marker::format(self.style, self.unit, None, &body, ""));
+        for (original, content) in lines {
+            self.stamp(original, out);
+            out.repeated_line(original, &content);
+        }
+    }
+
+    /// With `--explain`: say which lines [`Stream::skip`] passed over, once
+    /// the line after them (or the end) comes.
+    fn explain_skipped<W: Write>(&mut self, out: &mut Output<W>) {
+        if let Some((from, to)) = self.skipped.take() {
+            out.explain(|| {
+                format!(
+                    "{}: skipped unread, as only the head and tail can be shown",
+                    lines(from, to)
+                )
+            });
+        }
+    }
+
+    /// With `--timestamps`: have `out` stamp line `original` with when it
+    /// arrived.
+    fn stamp<W: Write>(&self, original: usize, out: &mut Output<W>) {
+        let arrival = self.arrivals.get(original);
+        if let Some(arrival) = arrival.filter(|_| self.config.timestamps.is_some()) {
+            out.stamp(arrival.at);
+        }
+    }
+
+    /// Stop holding lines for [`Truncator::pass_through`]: the input is
+    /// truncated as usual, starting with the lines held.
+    fn release_pending<W: Write>(&mut self, out: &mut Output<W>) {
+        for (line, at, read) in self.pending.take().unwrap_or_default() {
+            self.read_size = read;
+            self.line_at(&line, at, out);
+        }
+    }
+
+    /// Process the lines the traceback fold and `dedup` are holding back,
+    /// if any.
+    fn release_held<W: Write>(&mut self, out: &mut Output<W>) {
+        let released = self.fold.as_mut().map(Fold::flush).unwrap_or_default();
+        self.release_folded(released, out);
+        if let Some((line, original, run)) = self.dedup.as_mut().and_then(Dedup::flush) {
+            self.collapsed_line(&line, original, run, out);
+        }
+    }
+
+    /// Output the marker for the gap before the tail, then the tail lines not
+    /// already output.
+    ///
+    /// Everything up to the current line then counts as output, so a later
+    /// call (follow mode) only covers lines that arrived since.
+    fn flush_tail<W: Write>(&mut self, out: &mut Output<W>) {
+        // --rebalance: matches shown since the last line was buffered leave
+        // the tail less to grow by
+        let size = self.last_count + self.unused_match_lines();
+        let unused = self.unused_match_lines();
+        if unused > 0 {
+            out.explain(|| {
+                format!(
+                    "the tail grows by {} lines, the room left by matches not shown (--rebalance)",
+                    unused
+                )
+            });
+        }
+        // The last lines, not --tail-matches or sized in bytes
+        let raw_tail = self.tail_bytes.is_none() && self.tail_matches.is_none();
+        if self.rebalance.is_some() && raw_tail {
+            let over = self.tail_buffer.len().saturating_sub(size + self.slack);
+            for (line_number, original, content) in
+                self.tail_buffer.drain(..over).collect::<Vec<_>>()
+            {
+                self.hidden(line_number, original, &content);
+            }
+        }
+
+        // --block-aware: the tail starts where the block its first line is
+        // in does
+        if self.slack > 0 && raw_tail {
+            let lang = self.lang;
+            let mut start = self.tail_buffer.len().saturating_sub(size);
+            while start > 0
+                && self
+                    .tail_buffer
+                    .get(start)
+                    .is_some_and(|(_, _, c)| block::continues(c, lang))
+            {
+                start -= 1;
+            }
+            for (line_number, original, content) in
+                self.tail_buffer.drain(..start).collect::<Vec<_>>()
+            {
+                self.hidden(line_number, original, &content);
+            }
+        }
+
+        let total_lines = self.numbered;
+        let first_count = self.first_count;
+        // The tail is whatever the buffer holds: fewer than -l lines if
+        // they're sized in bytes or were dropped for memory
+        let last_count = self.tail_buffer.len();
+
+        // Calculate where tail starts
+        let tail_start = if self.tail_matches.is_some() {
+            self.tail_buffer
+                .front()
+                .map_or(total_lines + 1, |(ln, _, _)| *ln)
+        } else if total_lines > last_count {
+            total_lines - last_count + 1
+        } else {
+            1
+        };
+
+        // Lines between what's been output and the tail
+        let gap_start = self.last_output_line + 1;
+        let mut lines_truncated = tail_start.saturating_sub(gap_start);
+
+        // Tail lines not already output
+        // Use match_output_ranges for precise duplicate detection instead of
+        // last_output_line high-water mark (which incorrectly skips tail lines
+        // that precede match context output).
+        let tail_buffer = std::mem::take(&mut self.tail_buffer);
+        self.tail_buffered = 0;
+        self.buffered = self.context_buffer.iter().map(|(_, _, c)| c.len()).sum();
+        let was_output_in_match = |ln: usize| -> bool {
+            self.match_output_ranges
+                .iter()
+                .any(|(start, end)| ln >= *start && ln <= *end)
+        };
+        let mut tail: Vec<(usize, usize, &str)> = tail_buffer
+            .iter()
+            .filter(|(ln, _, _)| *ln > first_count && !was_output_in_match(*ln))
+            .map(|(ln, original, content)| (*ln, *original, content.as_str()))
+            .collect();
+
+        // Under --max-bytes, keep as many of the last lines as fit in what's
+        // left of this input's budget; --sample and --longest lines get the
+        // rest
+        if let Some(budget) = self.budget.take() {
+            let mut allowance = budget.head + budget.matches + budget.tail;
+            let mut keep_from = tail.len();
+            while keep_from > 0 {
+                let (_, original, content) = tail[keep_from - 1];
+                let cost = out.cost(original, content, Section::Tail);
+                if cost > allowance {
+                    break;
+                }
+                allowance -= cost;
+                keep_from -= 1;
+            }
+            if keep_from > 0 {
+                let (kept, cut) = (tail.len() - keep_from, keep_from);
+                out.explain(|| {
+                    format!(
+                        "only the last {} tail lines fit what's left of --max-bytes, so the {} before them are hidden",
+                        kept, cut
+                    )
+                });
+                for (line_number, original, content) in tail.drain(..keep_from) {
+                    self.hidden(line_number, original, content);
+                }
+                lines_truncated += keep_from;
+                self.budget_cut = true;
+            }
+            self.budget_left = Some(allowance);
+        }
+        // --tail-matches: the tail has gaps, so the first one it keeps is
+        // what ends this one
+        if self.tail_matches.is_some() {
+            let first = tail.first().map_or(total_lines + 1, |(ln, _, _)| *ln);
+            lines_truncated = first.saturating_sub(gap_start);
+        }
+
+        // The gap ends where the tail (or the input) does
+        let next = tail
+            .first()
+            .map_or(self.lines_seen + 1, |(_, original, _)| *original);
+
+        if let (lines @ 1.., bytes) = self.excluded {
+            // Not a cut, so never carries the byte limit note
+            out.hiding(lines, bytes);
+            let body = Body::Excluded(lines);
+            out.marker(&marker::format(self.style, self.unit, None, &body, ""));
+            self.excluded = (0, 0);
+        }
+        self.mark_outside(out);
+
+        // Matches the tail shows, which aren't hidden even though they
+        // weren't shown as matches
+        let tail_matches = self.pattern.map_or(0, |re| {
+            tail.iter()
+                .filter(|(_, original, c)| self.is_match(re, *original, c))
+                .count()
+        });
+        self.tail_shown += tail_matches;
+        // Named by the end marker, whatever the pattern mode. A
+        // --tail-matches tail is nothing but matches, so goes without saying
+        let tail_named = match self.tail_matches {
+            Some(_) => 0,
+            None => tail_matches,
+        };
+        if self.pattern.is_some() && self.head_matches + tail_named > 0 {
+            self.shown_around = Some((self.head_matches, tail_named));
+        }
+
+        if let (Some(re), true) = (self.pattern, self.count_only) {
+            // Count-only mode: one summary of what the gap hides
+            let hidden = self.total_matches - self.matches_counted - tail_matches;
+            self.matches_counted = self.total_matches;
+            if lines_truncated > 0 {
+                let labels: Vec<String> = re.labels().iter().map(|l| format!("'{}'", l)).collect();
+                let body = Body::Count {
+                    lines: lines_truncated,
+                    matches: hidden,
+                    labels: labels.join(", "),
+                };
+                self.marker(out, &body, next);
+            }
+        } else if self.pattern.is_some() {
+            // Pattern mode, with any matches the tail holds still to show
+            if self.head_matches + self.total_matches > 0 {
+                // We found matches — emit end marker with line gap and remaining match info
+                let remaining_matches = self
+                    .total_matches
+                    .saturating_sub(self.matches_shown + self.tail_shown);
+
+                if lines_truncated > 0 || remaining_matches > 0 {
+                    if remaining_matches > 0 {
+                        let body = Body::Remaining {
+                            lines: lines_truncated,
+                            matches: remaining_matches,
+                            duplicates: self.duplicates,
+                            total: self.head_matches + self.total_matches,
+                        };
+                        self.marker(out, &body, next);
+                    } else {
+                        self.marker(out, &Body::Lines(lines_truncated), next);
+                    }
+                }
+            } else if lines_truncated > 0 {
+                // No matches found in middle
+                self.marker(out, &Body::NoMatches(lines_truncated), next);
+            }
+        } else if let Some((start, kept, span)) = self.sample.as_mut().and_then(Sample::take) {
+            // Sampled: the marker covers the whole stretch
+            let body = Body::Sampled {
+                lines: lines_truncated + gap_start - start,
+                span: sample::span(span),
+                kept,
+            };
+            self.marker(out, &body, next);
+        } else {
+            // Default mode (no pattern), with the --sample lines between
+            // markers of their own
+            let mut sampled = self
+                .reservoir
+                .as_mut()
+                .map(Reservoir::take)
+                .unwrap_or_default();
+            if let Some(left) = &mut self.budget_left {
+                // Each with the marker before it
+                let mut from = gap_start;
+                sampled.retain(|(line_number, original, content)| {
+                    let marker = Body::Lines(line_number - from).longest();
+                    let cost = out.cost(*original, content, Section::Checkpoint)
+                        + out.marker_cost(&marker);
+                    let fits = cost <= *left;
+                    if fits {
+                        *left -= cost;
+                        from = line_number + 1;
+                    } else {
+                        self.budget_cut = true;
+                    }
+                    fits
+                });
+            }
+            let mut gap_start = gap_start;
+            for (line_number, original, content) in sampled {
+                let skipped = line_number - gap_start;
+                if skipped > 0 {
+                    self.marker(out, &Body::Lines(skipped), original);
+                }
+                self.stamp(original, out);
+                out.input_line(original, &content, Section::Checkpoint);
+                self.shown_through = original;
+                lines_truncated -= skipped + 1;
+                gap_start = line_number + 1;
+            }
+            if lines_truncated > 0 {
+                let body = self.gap_body(lines_truncated, total_lines + 1);
+                self.marker(out, &body, next);
+            }
+        }
+        // Without an end marker, nothing names them
+        self.shown_around = None;
+
+        let ends = tail.first().zip(tail.last());
+        out.explain(|| match ends {
+            Some(((_, first, _), (_, last, _))) => {
+                format!("{}: shown as the tail", lines(*first, *last))
+            }
+            None => "no lines left to show in the tail".to_string(),
+        });
+        // Only --tail-matches leaves gaps in the tail
+        let mut previous = None;
+        for (line_number, original, content) in tail {
+            if let Some(gap) = previous
+                .filter(|_| self.tail_matches.is_some())
+                .map(|p: usize| line_number - p - 1)
+                .filter(|&g| g > 0)
+            {
+                self.marker(out, &Body::Lines(gap), original);
+            }
+            self.stamp(original, out);
+            out.input_line(original, content, Section::Tail);
+            self.shown_through = original;
+            previous = Some(line_number);
+        }
+        if let Some(last) = previous.filter(|_| self.tail_matches.is_some()) {
+            if total_lines > last {
+                self.marker(out, &Body::Lines(total_lines - last), self.lines_seen + 1);
+            }
+        }
+
+        self.last_output_line = total_lines;
+    }
+}
+
+/// `line 4`, or `lines 4-9`, for `--explain`.
+fn lines(first: usize, last: usize) -> String {
+    match first == last {
+        true => format!("line {}", first),
+        false => format!("lines {}-{}", first, last),
+    }
+}
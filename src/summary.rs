@@ -0,0 +1,149 @@
+//! What a marker hides, in brief (`--summarize`): lines per log level, how
+//! many distinct lines there were and the most frequent ones, counted as
+//! lines leave the tail buffer without keeping them.
+//!
+//! The most frequent lines are counted with the Misra-Gries sketch, so
+//! their counts can fall short by up to the number of lines over
+//! [`CAPACITY`]; distinct lines are estimated from the smallest
+//! [`DISTINCT_SAMPLE`] hashes once there are more than that.
+
+use regex::Regex;
+use std::collections::{BTreeSet, HashMap};
+use std::hash::{DefaultHasher, Hash, Hasher};
+
+use crate::normalize::Normalizer;
+
+/// Most lines counted at a time for the top lines.
+const CAPACITY: usize = 1000;
+
+/// Hashes kept for estimating distinct lines.
+const DISTINCT_SAMPLE: usize = 1024;
+
+/// Lines in the `top:` list.
+const TOP: usize = 5;
+
+/// Longest line counted, and shown in the `top:` list, in bytes.
+const MAX_LINE: usize = 40;
+
+/// The log level a line is at: an upper-case level word, or a logfmt
+/// `level=` value.
+const LEVEL: &str = r"\b(TRACE|DEBUG|INFO|WARN|WARNING|ERROR|FATAL|CRITICAL)\b|\blevel=(trace|debug|info|warn|warning|error|fatal|critical)\b";
+
+/// Running counts over the lines a marker hides.
+#[derive(Debug)]
+pub(crate) struct Summary {
+    normalizer: Normalizer,
+    level: Regex,
+    levels: HashMap<String, usize>,
+    counts: HashMap<String, usize>,
+    hashes: BTreeSet<u64>,
+}
+
+impl Summary {
+    pub(crate) fn new() -> Self {
+        Summary {
+            normalizer: Normalizer::fuzzy(),
+            level: Regex::new(LEVEL).expect("the level pattern is valid"),
+            levels: HashMap::new(),
+            counts: HashMap::new(),
+            hashes: BTreeSet::new(),
+        }
+    }
+
+    /// Count a hidden line.
+    pub(crate) fn add(&mut self, line: &str) {
+        if let Some(caps) = self.level.captures(line) {
+            let level = caps.get(1).or(caps.get(2)).map_or("", |m| m.as_str());
+            let level = match level.to_ascii_uppercase().as_str() {
+                "WARNING" => "WARN".to_string(),
+                other => other.to_string(),
+            };
+            *self.levels.entry(level).or_default() += 1;
+        }
+
+        let normalized = self.normalizer.normalize(line);
+        // A stripped timestamp leaves a `#` in front of every line
+        let key = match normalized.trim().trim_start_matches(['#', ' ']) {
+            "" => normalized.trim(),
+            rest => rest,
+        };
+        let key = shorten(key);
+        let mut hasher = DefaultHasher::new();
+        key.hash(&mut hasher);
+        self.hashes.insert(hasher.finish());
+        if self.hashes.len() > DISTINCT_SAMPLE {
+            self.hashes.pop_last();
+        }
+
+        if let Some(count) = self.counts.get_mut(key) {
+            *count += 1;
+        } else if self.counts.len() < CAPACITY {
+            self.counts.insert(key.to_string(), 1);
+        } else {
+            // Full: every count goes down one, and those at zero make room
+            self.counts.retain(|_, count| {
+                *count -= 1;
+                *count > 0
+            });
+        }
+    }
+
+    /// `3900 INFO, 240 WARN, 60 unique; top: "GET /health #" ×3102`, or
+    /// None if nothing was counted. Starts afresh for the next marker.
+    pub(crate) fn take(&mut self) -> Option<String> {
+        let digest = self.digest();
+        self.levels.clear();
+        self.counts.clear();
+        self.hashes.clear();
+        digest
+    }
+
+    fn digest(&self) -> Option<String> {
+        let mut levels: Vec<(&String, &usize)> = self.levels.iter().collect();
+        levels.sort_by(|a, b| b.1.cmp(a.1).then(a.0.cmp(b.0)));
+        let mut parts: Vec<String> = levels
+            .iter()
+            .map(|(level, count)| format!("{} {}", count, level))
+            .collect();
+        match self.distinct() {
+            0 => return None,
+            n if self.hashes.len() < DISTINCT_SAMPLE => parts.push(format!("{} unique", n)),
+            n => parts.push(format!("~{} unique", n)),
+        }
+        let mut top: Vec<(&String, &usize)> = self
+            .counts
+            .iter()
+            .filter(|(_, count)| **count > 1)
+            .collect();
+        top.sort_by(|a, b| b.1.cmp(a.1).then(a.0.cmp(b.0)));
+        let top: Vec<String> = top
+            .iter()
+            .take(TOP)
+            .map(|(line, count)| format!("{:?} ×{}", line, count))
+            .collect();
+        let mut digest = parts.join(", ");
+        if !top.is_empty() {
+            digest.push_str(&format!("; top: {}", top.join(", ")));
+        }
+        Some(digest)
+    }
+
+    /// Distinct lines counted: exactly while there are few, then estimated
+    /// from how small the smallest hashes are.
+    fn distinct(&self) -> usize {
+        if self.hashes.len() < DISTINCT_SAMPLE {
+            return self.hashes.len();
+        }
+        let largest = *self.hashes.last().expect("the sample is full") as f64;
+        ((DISTINCT_SAMPLE - 1) as f64 * u64::MAX as f64 / largest) as usize
+    }
+}
+
+/// `line` cut to at most [`MAX_LINE`] bytes, at a char boundary.
+fn shorten(line: &str) -> &str {
+    let mut end = line.len().min(MAX_LINE);
+    while !line.is_char_boundary(end) {
+        end -= 1;
+    }
+    &line[..end]
+}
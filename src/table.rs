@@ -0,0 +1,87 @@
+//! Cutting CSV and TSV rows a column at a time (`--table`): an overlong row
+//! loses its middle columns to a `…+8 cols…` cell instead of the middle of
+//! its text, so what's left still lines up with the header.
+
+use crate::width::{self, WidthMode};
+
+/// Which columns of a row to keep: the first `first` and the last `last`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct Columns {
+    first: usize,
+    last: usize,
+}
+
+/// The columns to keep so `line` fits in `width` chars either side of the
+/// dropped ones, as `--width` would keep: as many whole columns from each
+/// end as fit, at least one. None if `line` fits already, or has too few
+/// columns to drop any.
+pub(crate) fn plan(line: &str, width: usize, mode: WidthMode) -> Option<Columns> {
+    if width == 0 {
+        return None;
+    }
+    let (_, cells) = cells(line);
+    let widths: Vec<usize> = cells
+        .iter()
+        .map(|cell| width::measure(cell, mode))
+        .collect();
+    let total = widths.iter().sum::<usize>() + cells.len() - 1;
+    if total <= width * 2 {
+        return None;
+    }
+    let first = fitting(widths.iter(), width);
+    let last = fitting(widths.iter().rev(), width);
+    (first + last < cells.len()).then_some(Columns { first, last })
+}
+
+/// How many of the columns `widths`, in order, fit in `width` with a
+/// delimiter between each, at least one.
+fn fitting<'a>(widths: impl Iterator<Item = &'a usize>, width: usize) -> usize {
+    let mut used = 0;
+    widths
+        .take_while(|w| {
+            used += *w + 1;
+            used <= width + 1
+        })
+        .count()
+        .max(1)
+}
+
+/// `line` with only `columns` kept and a `…+8 cols…` cell for the rest.
+/// None if it has too few columns to drop any.
+pub(crate) fn cut(line: &str, columns: Columns) -> Option<String> {
+    let (delimiter, cells) = cells(line);
+    let hidden = cells.len().checked_sub(columns.first + columns.last)?;
+    if hidden == 0 {
+        return None;
+    }
+    let note = match hidden {
+        1 => "…+1 col…".to_string(),
+        n => format!("…+{} cols…", n),
+    };
+    let kept: Vec<&str> = cells[..columns.first]
+        .iter()
+        .copied()
+        .chain([note.as_str()])
+        .chain(cells[cells.len() - columns.last..].iter().copied())
+        .collect();
+    Some(kept.join(&delimiter.to_string()))
+}
+
+/// The delimiter of `line` (a tab if it has one, else a comma) and its
+/// cells, as written. A delimiter inside double quotes doesn't split.
+fn cells(line: &str) -> (char, Vec<&str>) {
+    let delimiter = if line.contains('\t') { '\t' } else { ',' };
+    let mut cells = Vec::new();
+    let mut start = 0;
+    let mut quoted = false;
+    for (i, c) in line.char_indices() {
+        if c == '"' {
+            quoted = !quoted;
+        } else if c == delimiter && !quoted {
+            cells.push(&line[start..i]);
+            start = i + 1;
+        }
+    }
+    cells.push(&line[start..]);
+    (delimiter, cells)
+}
@@ -0,0 +1,438 @@
+//! Reading the timestamp a log line starts with, for `--since` and
+//! `--until`. Recognised, after any leading `[` or `ts=`/`time=` key:
+//!
+//! - ISO 8601 and its relatives: `2024-05-01T12:00:00.123Z`,
+//!   `2024-05-01 12:00:00,123 +02:00`, `2024/05/01 12:00:00`, `2024-05-01`
+//! - syslog: `May  1 12:00:00`, in the current year
+//! - Common Log Format: `01/May/2024:12:00:00 +0000`
+//! - Unix time: `1714564800`, `1714564800.123` or `1714564800123`
+//!
+//! A timestamp without an offset is taken as UTC.
+
+use std::ops::Range;
+use std::time::Duration;
+
+use crate::clock::{SystemTime, UNIX_EPOCH};
+use crate::TimestampRewrite;
+
+/// Milliseconds since the Unix epoch.
+pub(crate) type Millis = i64;
+
+const DAY: Millis = 86_400_000;
+
+const MONTHS: [&str; 12] = [
+    "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+];
+
+/// Parse `text`, all of it, as a timestamp in one of the formats above.
+pub fn parse_timestamp(text: &str) -> Option<SystemTime> {
+    let text = text.trim();
+    let (at, len) = timestamp(text, this_year())?;
+    (len == text.len()).then(|| time(at))
+}
+
+/// The lines inside `--since`/`--until`. A line without a timestamp goes
+/// with the last one that had one, so a traceback stays with its log line;
+/// lines before the first timestamp are kept.
+#[derive(Debug)]
+pub(crate) struct Window {
+    since: Option<Millis>,
+    until: Option<Millis>,
+    /// For timestamps without one
+    year: i64,
+    inside: bool,
+}
+
+impl Window {
+    pub(crate) fn new(since: Option<SystemTime>, until: Option<SystemTime>) -> Self {
+        Window {
+            since: since.map(millis),
+            until: until.map(millis),
+            year: this_year(),
+            inside: true,
+        }
+    }
+
+    /// Whether the next line, `line`, is inside the window.
+    pub(crate) fn admits(&mut self, line: &str) -> bool {
+        if let Some(at) = leading(line, self.year) {
+            self.inside = self.since.is_none_or(|since| at >= since)
+                && self.until.is_none_or(|until| at <= until);
+        }
+        self.inside
+    }
+}
+
+/// The current year, for timestamps without one.
+pub(crate) fn this_year() -> i64 {
+    year_of(millis(SystemTime::now()))
+}
+
+/// The timestamp `line` starts with, if any.
+pub(crate) fn leading(line: &str, year: i64) -> Option<Millis> {
+    Leading::find(line, year).map(|found| found.at)
+}
+
+/// A timestamp found at the start of a line, by [`Leading::find`].
+struct Leading {
+    at: Millis,
+    /// Where its text is in the line
+    text: Range<usize>,
+    /// Where it is with any `[`...`]`, key and quotes around it
+    whole: Range<usize>,
+}
+
+impl Leading {
+    fn find(line: &str, year: i64) -> Option<Leading> {
+        let start = line.len() - line.trim_start().len();
+        let mut rest = &line[start..];
+        let bracket = rest.starts_with('[');
+        rest = rest.strip_prefix('[').unwrap_or(rest);
+        let mut quote = false;
+        for key in ["ts=", "time=", "timestamp="] {
+            if let Some(value) = rest.strip_prefix(key) {
+                quote = value.starts_with('"');
+                rest = value.strip_prefix('"').unwrap_or(value);
+                break;
+            }
+        }
+        let (at, len) = timestamp(rest, year)?;
+        let from = line.len() - rest.len();
+        let mut end = from + len;
+        for (closes, close) in [(quote, '"'), (bracket, ']')] {
+            if closes && line[end..].starts_with(close) {
+                end += 1;
+            }
+        }
+        Some(Leading {
+            at,
+            text: from..from + len,
+            whole: start..end,
+        })
+    }
+}
+
+/// Rewrites the timestamps lines start with, for `--strip-timestamps` and
+/// `--normalize-timestamps`.
+#[derive(Debug)]
+pub(crate) struct Restamp {
+    rewrite: TimestampRewrite,
+    /// For timestamps without one
+    year: i64,
+    /// The input's first timestamp, that relative ones count from
+    origin: Option<Millis>,
+}
+
+impl Restamp {
+    pub(crate) fn new(rewrite: TimestampRewrite) -> Self {
+        Restamp {
+            rewrite,
+            year: this_year(),
+            origin: None,
+        }
+    }
+
+    /// Count relative timestamps from `line`'s, if it has one: the
+    /// input's first. True if it did.
+    pub(crate) fn set_origin(&mut self, line: &str) -> bool {
+        self.origin = leading(line, self.year);
+        self.origin.is_some()
+    }
+
+    /// `line` with its leading timestamp rewritten, if it has one.
+    pub(crate) fn rewrite(&self, line: &str) -> Option<String> {
+        let found = Leading::find(line, self.year)?;
+        Some(match self.rewrite {
+            // Brackets, key and the space after go too
+            TimestampRewrite::Strip => {
+                let rest = line[found.whole.end..].trim_start();
+                format!("{}{}", &line[..found.whole.start], rest)
+            }
+            TimestampRewrite::Relative => {
+                let since = found.at - self.origin.unwrap_or(found.at);
+                format!(
+                    "{}{}{}",
+                    &line[..found.text.start],
+                    offset_text(since),
+                    &line[found.text.end..]
+                )
+            }
+        })
+    }
+}
+
+/// `since` milliseconds as an offset: `+12.345s`, or `-0.500s` for a time
+/// before the one it counts from.
+pub(crate) fn offset_text(since: Millis) -> String {
+    let sign = if since < 0 { '-' } else { '+' };
+    let since = since.unsigned_abs();
+    format!("{}{}.{:03}s", sign, since / 1000, since % 1000)
+}
+
+/// The timestamp `text` starts with, and its length.
+fn timestamp(text: &str, year: i64) -> Option<(Millis, usize)> {
+    let mut scan = Scan {
+        text: text.as_bytes(),
+        pos: 0,
+    };
+    let at = iso(&mut scan)
+        .or_else(|| syslog(scan.restart(), year))
+        .or_else(|| common_log(scan.restart()))
+        .or_else(|| unix(scan.restart()))?;
+    Some((at, scan.pos))
+}
+
+/// `2024-05-01`, then optionally `T12:00` or ` 12:00`, seconds, a fraction
+/// and an offset.
+fn iso(scan: &mut Scan) -> Option<Millis> {
+    let year = scan.number(4)?;
+    let separator = scan.next().filter(|&b| b == b'-' || b == b'/')?;
+    let month = scan.number(2)?;
+    scan.eat(separator)?;
+    let day = scan.number(2)?;
+    let date = date(year, month, day)?;
+    if scan.digit_next() {
+        return None;
+    }
+    let mark = scan.pos;
+    if !(scan.eat(b'T').is_some() || scan.eat(b' ').is_some()) {
+        return Some(date);
+    }
+    let Some(clock) = clock(scan, false) else {
+        scan.pos = mark;
+        return Some(date);
+    };
+    let mark = scan.pos;
+    let offset = if scan.eat(b'Z').is_some() {
+        0
+    } else {
+        let _ = scan.eat(b' ');
+        offset(scan).unwrap_or_else(|| {
+            scan.pos = mark;
+            0
+        })
+    };
+    Some(date + clock - offset)
+}
+
+/// `May  1 12:00:00`, in `year`.
+fn syslog(scan: &mut Scan, year: i64) -> Option<Millis> {
+    let month = month(scan)?;
+    scan.eat(b' ')?;
+    let _ = scan.eat(b' ');
+    let day = scan.number(2).or_else(|| scan.number(1))?;
+    scan.eat(b' ')?;
+    Some(date(year, month, day)? + clock(scan, true)?)
+}
+
+/// `01/May/2024:12:00:00 +0000`.
+fn common_log(scan: &mut Scan) -> Option<Millis> {
+    let day = scan.number(2)?;
+    scan.eat(b'/')?;
+    let month = month(scan)?;
+    scan.eat(b'/')?;
+    let year = scan.number(4)?;
+    scan.eat(b':')?;
+    let date = date(year, month, day)?;
+    let clock = clock(scan, true)?;
+    let mark = scan.pos;
+    let offset = match scan.eat(b' ').and_then(|_| offset(scan)) {
+        Some(offset) => offset,
+        None => {
+            scan.pos = mark;
+            0
+        }
+    };
+    Some(date + clock - offset)
+}
+
+/// Seconds since the epoch, from 2001 to 2033 so most other numbers aren't
+/// taken for one, optionally with a fraction; or milliseconds.
+fn unix(scan: &mut Scan) -> Option<Millis> {
+    if scan.peek() != Some(b'1') {
+        return None;
+    }
+    if let Some(millis) = scan.number(13).filter(|_| !scan.digit_next()) {
+        return Some(millis);
+    }
+    scan.pos = 0;
+    let seconds = scan.number(10)?;
+    let fraction = match scan.eat(b'.') {
+        Some(()) => scan.fraction()?,
+        None => 0,
+    };
+    (!scan.digit_next()).then_some(seconds * 1000 + fraction)
+}
+
+/// `12:00`, or `12:00:00` with an optional `.123` or `,123`, since
+/// midnight. Seconds are required if `seconds`.
+fn clock(scan: &mut Scan, seconds: bool) -> Option<Millis> {
+    let hour = scan.number(2).filter(|&h| h < 24)?;
+    scan.eat(b':')?;
+    let minute = scan.number(2).filter(|&m| m < 60)?;
+    let mut at = (hour * 60 + minute) * 60_000;
+    if scan.eat(b':').is_none() {
+        return (!seconds).then_some(at);
+    }
+    // 60 for a leap second
+    at += scan.number(2).filter(|&s| s <= 60)? * 1000;
+    let mark = scan.pos;
+    if scan.eat(b'.').or_else(|| scan.eat(b',')).is_some() {
+        match scan.fraction() {
+            Some(fraction) => at += fraction,
+            None => scan.pos = mark,
+        }
+    }
+    Some(at)
+}
+
+/// `+02:00`, `-0700` or `+02`, as milliseconds ahead of UTC.
+fn offset(scan: &mut Scan) -> Option<Millis> {
+    let sign = match scan.next()? {
+        b'+' => 1,
+        b'-' => -1,
+        _ => return None,
+    };
+    let hours = scan.number(2)?;
+    let _ = scan.eat(b':');
+    let minutes = scan.number(2).unwrap_or(0);
+    Some(sign * (hours * 60 + minutes) * 60_000)
+}
+
+/// `May`, as 1 to 12.
+fn month(scan: &mut Scan) -> Option<i64> {
+    let name = scan.text.get(scan.pos..scan.pos + 3)?;
+    let month = MONTHS.iter().position(|m| m.as_bytes() == name)?;
+    scan.pos += 3;
+    Some(month as i64 + 1)
+}
+
+/// Midnight UTC at the start of a day, if it's a real one.
+fn date(year: i64, month: i64, day: i64) -> Option<Millis> {
+    if !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+        return None;
+    }
+    Some(days_from_civil(year, month, day) * DAY)
+}
+
+/// Days from 1970-01-01 to a date in the proleptic Gregorian calendar.
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+/// The year `at` falls in.
+fn year_of(at: Millis) -> i64 {
+    civil_from_days(at.div_euclid(DAY)).0
+}
+
+/// The date (year, month, day) that's `days` after 1970-01-01; the
+/// inverse of [`days_from_civil`].
+fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days - era * 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    // Months and years start in March here
+    let month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month + 2) / 5 + 1;
+    let year = year_of_era + era * 400 + if month >= 10 { 1 } else { 0 };
+    (year, if month < 10 { month + 3 } else { month - 9 }, day)
+}
+
+/// `time` in UTC, to the millisecond: `2024-05-01T12:00:00.123Z`.
+pub(crate) fn format(time: SystemTime) -> String {
+    let at = millis(time);
+    let (year, month, day) = civil_from_days(at.div_euclid(DAY));
+    let ms = at.rem_euclid(DAY);
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}.{:03}Z",
+        year,
+        month,
+        day,
+        ms / 3_600_000,
+        ms / 60_000 % 60,
+        ms / 1000 % 60,
+        ms % 1000
+    )
+}
+
+fn millis(time: SystemTime) -> Millis {
+    match time.duration_since(UNIX_EPOCH) {
+        Ok(after) => after.as_millis() as Millis,
+        Err(before) => -(before.duration().as_millis() as Millis),
+    }
+}
+
+fn time(at: Millis) -> SystemTime {
+    let since_epoch = Duration::from_millis(at.unsigned_abs());
+    match at >= 0 {
+        true => UNIX_EPOCH + since_epoch,
+        false => UNIX_EPOCH - since_epoch,
+    }
+}
+
+/// A position in the text being parsed.
+struct Scan<'a> {
+    text: &'a [u8],
+    pos: usize,
+}
+
+impl Scan<'_> {
+    /// Back to the start, for trying another format.
+    fn restart(&mut self) -> &mut Self {
+        self.pos = 0;
+        self
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.text.get(self.pos).copied()
+    }
+
+    fn next(&mut self) -> Option<u8> {
+        let byte = self.peek()?;
+        self.pos += 1;
+        Some(byte)
+    }
+
+    fn eat(&mut self, byte: u8) -> Option<()> {
+        (self.peek() == Some(byte)).then(|| self.pos += 1)
+    }
+
+    fn digit_next(&self) -> bool {
+        self.peek().is_some_and(|b| b.is_ascii_digit())
+    }
+
+    /// Exactly `len` digits, as a number.
+    fn number(&mut self, len: usize) -> Option<i64> {
+        let digits = self.text.get(self.pos..self.pos + len)?;
+        if !digits.iter().all(u8::is_ascii_digit) {
+            return None;
+        }
+        self.pos += len;
+        Some(digits.iter().fold(0, |n, d| n * 10 + i64::from(d - b'0')))
+    }
+
+    /// The digits of a fraction of a second, as milliseconds.
+    fn fraction(&mut self) -> Option<Millis> {
+        let start = self.pos;
+        while self.digit_next() {
+            self.pos += 1;
+        }
+        let digits = &self.text[start..self.pos];
+        if digits.is_empty() {
+            return None;
+        }
+        Some(
+            (0..3)
+                .map(|i| digits.get(i).map_or(0, |d| i64::from(d - b'0')))
+                .fold(0, |n, d| n * 10 + d),
+        )
+    }
+}
@@ -0,0 +1,159 @@
+//! Folding stack traces (`--fold-tracebacks`): a Python traceback or a run
+//! of JVM `at` frames dozens of frames deep, as Django's and Spring's
+//! usually are, keeps only the frames at either end, and a count of the
+//! ones left out between them for a marker. The exception messages, and
+//! every `Caused by:` in a chain, are kept as they are.
+//!
+//! Like collapsing runs, folding happens before lines are numbered, so both
+//! the [`Stream`] and a [`Survey`] see the folded trace.
+//!
+//! [`Stream`]: crate::Stream
+//! [`Survey`]: crate::Survey
+
+/// A line and its number in the input.
+type Numbered = (String, usize);
+
+/// The kinds of stack trace folded.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Kind {
+    /// `Traceback (most recent call last):`, then frames of a
+    /// `File "...", line N, in f` line and the source below it, most recent
+    /// last
+    Python,
+    /// `\tat com.foo.Bar.run(Bar.java:42)` frames, one line each, most
+    /// recent first
+    Jvm,
+}
+
+impl Kind {
+    /// The kind of trace `line` starts, if any.
+    fn starting(line: &str) -> Option<Kind> {
+        if line.trim() == "Traceback (most recent call last):" {
+            Some(Kind::Python)
+        } else if is_jvm_frame(line) {
+            Some(Kind::Jvm)
+        } else {
+            None
+        }
+    }
+
+    /// Frames kept from the start and the end: the most recent call, where
+    /// the error was raised, gets the most.
+    fn kept(self) -> (usize, usize) {
+        match self {
+            Kind::Python => (1, 3),
+            Kind::Jvm => (3, 1),
+        }
+    }
+}
+
+/// `\tat com.foo.Bar.run(Bar.java:42)`, as a JVM prints a frame.
+fn is_jvm_frame(line: &str) -> bool {
+    line.starts_with([' ', '\t']) && line.trim_start().starts_with("at ") && line.contains('(')
+}
+
+/// A trace being held back.
+#[derive(Debug)]
+struct Trace {
+    kind: Kind,
+    /// The line that started it, for a Python traceback
+    header: Option<Numbered>,
+    /// Each frame's lines
+    frames: Vec<Vec<Numbered>>,
+}
+
+impl Trace {
+    /// Whether `line` carries on the trace.
+    fn takes(&self, line: &str) -> bool {
+        match self.kind {
+            Kind::Python => line.starts_with([' ', '\t']) && !line.trim().is_empty(),
+            Kind::Jvm => is_jvm_frame(line),
+        }
+    }
+
+    fn push(&mut self, line: String, number: usize) {
+        let continues_frame =
+            self.kind == Kind::Python && !line.trim_start().starts_with("File \"");
+        match self.frames.last_mut() {
+            Some(frame) if continues_frame => frame.push((line, number)),
+            _ => self.frames.push(vec![(line, number)]),
+        }
+    }
+
+    /// The trace's lines, folded if it has frames enough, and if it was:
+    /// the number of the line the frames left out follow, and how many.
+    fn fold(self) -> (Vec<Numbered>, Option<(usize, usize)>) {
+        let (first, last) = self.kind.kept();
+        let mut lines: Vec<Numbered> = self.header.into_iter().collect();
+        let hidden = self.frames.len().saturating_sub(first + last);
+        // Leaving out a single frame wouldn't save anything
+        if hidden < 2 {
+            lines.extend(self.frames.into_iter().flatten());
+            return (lines, None);
+        }
+        let mut frames = self.frames.into_iter();
+        lines.extend(frames.by_ref().take(first).flatten());
+        let after = lines.last().map_or(0, |(_, number)| *number);
+        lines.extend(frames.skip(hidden).flatten());
+        (lines, Some((after, hidden)))
+    }
+}
+
+/// Holds back a stack trace's lines until it ends, then releases them
+/// folded.
+#[derive(Debug, Default)]
+pub(crate) struct Fold {
+    trace: Option<Trace>,
+    /// The fold in the trace last released, if it was folded
+    folded: Option<(usize, usize)>,
+}
+
+impl Fold {
+    /// Take line `number`. Returns the lines released, in order: `line`
+    /// itself outside a trace, or the whole trace once `line` ends it
+    /// (the exception after a Python traceback, `Caused by:` or `... 12
+    /// more` after JVM frames), then `line` unless it starts another.
+    pub(crate) fn push(&mut self, line: String, number: usize) -> Vec<Numbered> {
+        if let Some(trace) = &mut self.trace {
+            if trace.takes(&line) {
+                trace.push(line, number);
+                return Vec::new();
+            }
+        }
+        let mut released = self.flush();
+        match Kind::starting(&line) {
+            Some(Kind::Python) => {
+                self.trace = Some(Trace {
+                    kind: Kind::Python,
+                    header: Some((line, number)),
+                    frames: Vec::new(),
+                })
+            }
+            Some(Kind::Jvm) => {
+                self.trace = Some(Trace {
+                    kind: Kind::Jvm,
+                    header: None,
+                    frames: vec![vec![(line, number)]],
+                })
+            }
+            None => released.push((line, number)),
+        }
+        released
+    }
+
+    /// The trace held so far, if any, folded.
+    pub(crate) fn flush(&mut self) -> Vec<Numbered> {
+        let Some(trace) = self.trace.take() else {
+            return Vec::new();
+        };
+        let (lines, folded) = trace.fold();
+        self.folded = folded;
+        lines
+    }
+
+    /// Where frames were left out of the trace just released, if they
+    /// were: the number of the line they follow, and how many.
+    pub(crate) fn take_folded(&mut self) -> Option<(usize, usize)> {
+        self.folded.take()
+    }
+}
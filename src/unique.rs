@@ -0,0 +1,45 @@
+//! Showing matches once per capture value (`--unique-by '$1'`), so `-m`
+//! isn't spent on five copies of the same flaky error.
+
+use std::collections::HashSet;
+use std::sync::Arc;
+
+use crate::matcher::{Matcher, Pattern};
+
+/// The keys of the matches shown so far.
+#[derive(Debug)]
+pub(crate) struct Unique {
+    /// Each pattern on its own, for its captures
+    patterns: Vec<Arc<dyn Pattern>>,
+    /// `$1`, `${code}`: what makes two matches the same
+    template: String,
+    seen: HashSet<String>,
+}
+
+impl Unique {
+    pub(crate) fn new(matcher: &Matcher, template: &str) -> Self {
+        Unique {
+            patterns: matcher.patterns(),
+            template: template.to_string(),
+            seen: HashSet::new(),
+        }
+    }
+
+    /// The key of a match on `line`: the template expanded with the
+    /// captures of the first pattern matching it.
+    pub(crate) fn key(&self, line: &str) -> Option<String> {
+        self.patterns
+            .iter()
+            .find_map(|p| p.expand(line, &self.template))
+    }
+
+    /// Whether a match with `key` has been shown already.
+    pub(crate) fn is_repeat(&self, key: &str) -> bool {
+        self.seen.contains(key)
+    }
+
+    /// Note that a match with `key` was shown.
+    pub(crate) fn shown(&mut self, key: String) {
+        self.seen.insert(key);
+    }
+}
@@ -0,0 +1,122 @@
+//! The JavaScript API, with the `wasm` feature: [`truncate`] a string as
+//! the CLI would its input, for web log viewers and LLM tooling built with
+//! `wasm-bindgen`.
+//!
+//! ```js
+//! import { truncate, Options } from "trunc";
+//!
+//! const options = new Options();
+//! options.pattern = "ERROR";
+//! options.first = 10;
+//! const view = truncate(log, options);
+//! ```
+
+use regex::Regex;
+use wasm_bindgen::prelude::*;
+
+use crate::Truncator;
+
+/// What to keep, as the CLI's flags of the same names say. Unset, each is
+/// the CLI's default.
+#[wasm_bindgen]
+#[derive(Clone, Debug, Default)]
+pub struct Options {
+    first: Option<usize>,
+    last: Option<usize>,
+    pattern: Option<String>,
+    matches: Option<usize>,
+    context: Option<usize>,
+    width: Option<usize>,
+    max_bytes: Option<usize>,
+}
+
+#[wasm_bindgen]
+impl Options {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> Options {
+        Options::default()
+    }
+
+    /// `-f`: lines kept from the start
+    #[wasm_bindgen(setter)]
+    pub fn set_first(&mut self, n: usize) {
+        self.first = Some(n);
+    }
+
+    /// `-l`: lines kept from the end
+    #[wasm_bindgen(setter)]
+    pub fn set_last(&mut self, n: usize) {
+        self.last = Some(n);
+    }
+
+    /// The pattern: a regex whose matches are shown from the middle
+    #[wasm_bindgen(setter)]
+    pub fn set_pattern(&mut self, pattern: String) {
+        self.pattern = Some(pattern);
+    }
+
+    /// `-m`: matches shown at most
+    #[wasm_bindgen(setter)]
+    pub fn set_matches(&mut self, n: usize) {
+        self.matches = Some(n);
+    }
+
+    /// `-C`: lines of context around each match
+    #[wasm_bindgen(setter)]
+    pub fn set_context(&mut self, n: usize) {
+        self.context = Some(n);
+    }
+
+    /// `-w`: characters kept of a long line
+    #[wasm_bindgen(setter)]
+    pub fn set_width(&mut self, n: usize) {
+        self.width = Some(n);
+    }
+
+    /// `--max-bytes`: a limit on the whole output
+    #[wasm_bindgen(setter, js_name = maxBytes)]
+    pub fn set_max_bytes(&mut self, n: usize) {
+        self.max_bytes = Some(n);
+    }
+}
+
+impl Options {
+    /// The [`Truncator`] these options describe, or the pattern's error.
+    pub fn truncator(&self) -> Result<Truncator, regex::Error> {
+        let mut truncator = Truncator::new();
+        if let Some(n) = self.first {
+            truncator = truncator.first(n);
+        }
+        if let Some(n) = self.last {
+            truncator = truncator.last(n);
+        }
+        if let Some(n) = self.matches {
+            truncator = truncator.matches(n);
+        }
+        if let Some(n) = self.context {
+            truncator = truncator.context(n);
+        }
+        if let Some(n) = self.width {
+            truncator = truncator.width(n);
+        }
+        if let Some(n) = self.max_bytes {
+            truncator = truncator.max_bytes(n);
+        }
+        if let Some(pattern) = &self.pattern {
+            truncator = truncator.pattern(Regex::new(pattern)?);
+        }
+        Ok(truncator)
+    }
+}
+
+/// `text` truncated as `options` say: what `trunc` would print for it,
+/// each line ending in a newline. Throws if the pattern isn't a valid
+/// regex.
+#[wasm_bindgen]
+pub fn truncate(text: &str, options: &Options) -> Result<String, JsError> {
+    let truncator = options.truncator()?;
+    let mut out = Vec::new();
+    truncator.process(text.as_bytes(), &mut out)?;
+    // Lines in are UTF-8, and truncation cuts on character boundaries
+    Ok(String::from_utf8_lossy(&out).into_owned())
+}
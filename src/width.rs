@@ -0,0 +1,340 @@
+//! Measuring and cutting long lines for `-w`. Grapheme clusters are never
+//! split, escape sequences take no width, and in columns mode wide chars
+//! count as the two terminal columns they occupy.
+
+use std::ops::Range;
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
+
+use crate::ansi::{self, Piece, RESET};
+use crate::marker::{self, MarkerStyle};
+
+/// What `-w` counts.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum WidthMode {
+    /// User-perceived characters (grapheme clusters)
+    #[default]
+    Chars,
+    /// Terminal columns: wide CJK chars and most emoji count as two
+    Columns,
+}
+
+impl WidthMode {
+    /// The unit named in `[... N <unit> ...]` markers.
+    pub(crate) fn unit(self) -> &'static str {
+        match self {
+            WidthMode::Chars => "chars",
+            WidthMode::Columns => "cols",
+        }
+    }
+
+    /// The unit in compact markers: `…500c…`.
+    pub(crate) fn short_unit(self) -> &'static str {
+        match self {
+            WidthMode::Chars => "c",
+            WidthMode::Columns => "col",
+        }
+    }
+
+    fn measure(self, grapheme: &str) -> usize {
+        match self {
+            WidthMode::Chars => 1,
+            WidthMode::Columns => grapheme.width(),
+        }
+    }
+}
+
+/// Which part of an overlong line `-w` cuts out.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum LineCut {
+    /// The start, keeping the last `2 * width`: a path's file name
+    Start,
+    /// The middle, keeping `width` at each end
+    #[default]
+    Middle,
+    /// The end, keeping the first `2 * width`
+    End,
+}
+
+impl LineCut {
+    /// How much of a line, cut to `width`, is kept before and after the
+    /// marker.
+    pub(crate) fn keep(self, width: usize) -> (usize, usize) {
+        match self {
+            LineCut::Start => (0, width * 2),
+            LineCut::Middle => (width, width),
+            LineCut::End => (width * 2, 0),
+        }
+    }
+}
+
+/// A piece of a line: an escape sequence, or a grapheme cluster and its width.
+enum Unit {
+    Escape(Range<usize>),
+    Visible(Range<usize>, usize),
+}
+
+fn units(line: &str, mode: WidthMode) -> Vec<Unit> {
+    let mut units = Vec::new();
+    for piece in ansi::pieces(line) {
+        match piece {
+            Piece::Escape(r) => units.push(Unit::Escape(r)),
+            Piece::Text(r) => {
+                let start = r.start;
+                units.extend(
+                    line[r].grapheme_indices(true).map(|(i, g)| {
+                        Unit::Visible(start + i..start + i + g.len(), mode.measure(g))
+                    }),
+                );
+            }
+        }
+    }
+    units
+}
+
+/// Chars or columns `text` takes, escape sequences aside.
+pub(crate) fn measure(text: &str, mode: WidthMode) -> usize {
+    units(text, mode)
+        .iter()
+        .map(|u| match u {
+            Unit::Visible(_, w) => *w,
+            Unit::Escape(_) => 0,
+        })
+        .sum()
+}
+
+/// Keep the first and last `width` chars or columns of `line` (or `2 *
+/// width` from one end, as `cut` says), with a `[... N chars ...]` marker
+/// for the rest. None if the line fits, or if cutting it wouldn't make it
+/// shorter.
+///
+/// Escape sequences in the kept parts are preserved. If a style is active
+/// where the head is cut, a reset goes before the marker; the style active
+/// where the tail starts is replayed after it. Non-style sequences in the
+/// part removed are dropped.
+///
+/// Returns the text, the input bytes left out, and the marker's range.
+pub(crate) fn truncate(
+    line: &str,
+    width: usize,
+    mode: WidthMode,
+    cut: LineCut,
+    style: MarkerStyle,
+) -> Option<(String, usize, Range<usize>)> {
+    let (head_keep, tail_keep) = cut.keep(width);
+    let units = units(line, mode);
+    let visible: Vec<(&Range<usize>, usize)> = units
+        .iter()
+        .filter_map(|u| match u {
+            Unit::Visible(r, w) => Some((r, *w)),
+            Unit::Escape(_) => None,
+        })
+        .collect();
+    let total: usize = visible.iter().map(|(_, w)| w).sum();
+    if total <= width * 2 {
+        return None;
+    }
+
+    // Byte offsets where the head ends and the tail starts, and the width
+    // each keeps. A cluster that would straddle the limit is left out.
+    let mut head_end = 0;
+    let mut head_width = 0;
+    for (r, w) in &visible {
+        if head_width + w > head_keep {
+            break;
+        }
+        head_end = r.end;
+        head_width += w;
+    }
+    let mut tail_start = line.len();
+    let mut tail_width = 0;
+    for (r, w) in visible.iter().rev() {
+        if tail_width + w > tail_keep {
+            break;
+        }
+        tail_start = r.start;
+        tail_width += w;
+    }
+
+    let marker = marker::cut(style, total - head_width - tail_width, mode);
+    if head_width + marker.len() + tail_width >= total {
+        return None;
+    }
+
+    // Style in effect at each cut: SGR sequences since the last reset
+    let mut active: Vec<Range<usize>> = Vec::new();
+    let mut styled_at_head_end = None;
+    for unit in &units {
+        let Unit::Escape(r) = unit else {
+            continue;
+        };
+        if r.start >= head_end && styled_at_head_end.is_none() {
+            styled_at_head_end = Some(!active.is_empty());
+        }
+        if r.start >= tail_start {
+            break;
+        }
+        match ansi::sgr(&line[r.clone()]) {
+            Some(true) => active.clear(),
+            Some(false) => active.push(r.clone()),
+            None => {}
+        }
+    }
+    let styled_at_head_end = styled_at_head_end.unwrap_or(!active.is_empty());
+
+    let mut text = String::with_capacity(head_end + marker.len() + line.len() - tail_start);
+    text.push_str(&line[..head_end]);
+    if styled_at_head_end {
+        text.push_str(RESET);
+    }
+    let marker_range = text.len()..text.len() + marker.len();
+    text.push_str(&marker);
+    let mut replayed_from_middle = 0;
+    // With no tail, there's nothing to style
+    let replayed = if tail_start < line.len() {
+        &active[..]
+    } else {
+        &[]
+    };
+    for r in replayed {
+        text.push_str(&line[r.clone()]);
+        if r.start >= head_end {
+            replayed_from_middle += r.len();
+        }
+    }
+    text.push_str(&line[tail_start..]);
+
+    let bytes_removed = tail_start - head_end - replayed_from_middle;
+    Some((text, bytes_removed, marker_range))
+}
+
+/// Keep `2 * width` chars or columns of `line` around `span`, a match that
+/// cutting as `cut` says would hide, with a marker for what's cut either
+/// side: `[... N chars ...]<the match and around it>[... M chars ...]`.
+/// None if the cut shows the match anyway, or the line fits.
+///
+/// Returns the text, the input bytes left out, and the markers' ranges.
+pub(crate) fn truncate_around(
+    line: &str,
+    width: usize,
+    mode: WidthMode,
+    cut: LineCut,
+    span: Range<usize>,
+    style: MarkerStyle,
+) -> Option<(String, usize, Vec<Range<usize>>)> {
+    let units = units(line, mode);
+    let visible: Vec<(&Range<usize>, usize)> = units
+        .iter()
+        .filter_map(|u| match u {
+            Unit::Visible(r, w) => Some((r, *w)),
+            Unit::Escape(_) => None,
+        })
+        .collect();
+    let total: usize = visible.iter().map(|(_, w)| w).sum();
+    let keep = width * 2;
+    if total <= keep {
+        return None;
+    }
+    // Where each cluster starts, in chars or columns
+    let starts: Vec<usize> = visible
+        .iter()
+        .scan(0, |at, (_, w)| {
+            *at += w;
+            Some(*at - w)
+        })
+        .collect();
+    let first = visible.iter().position(|(r, _)| r.end > span.start)?;
+    let last = visible.iter().rposition(|(r, _)| r.start < span.end)?;
+    let (from, to) = (starts[first], starts[last] + visible[last].1);
+    let (head, tail) = cut.keep(width);
+    if to <= head || from >= total - tail {
+        return None;
+    }
+
+    // Near an end, keep that end
+    if to <= keep {
+        return one_end(line, width, mode, LineCut::End, style);
+    }
+    if from >= total - keep {
+        return one_end(line, width, mode, LineCut::Start, style);
+    }
+    // Centre the match in what's kept, or start with it if it's too long
+    let start = match to - from >= keep {
+        true => from,
+        false => ((from + to) / 2).saturating_sub(width).min(total - keep),
+    };
+    let Some(window) = starts.iter().position(|&s| s >= start).filter(|&i| i > 0) else {
+        return one_end(line, width, mode, LineCut::End, style);
+    };
+    let mut kept = 0;
+    let mut end = window;
+    while end < visible.len() && kept + visible[end].1 <= keep {
+        kept += visible[end].1;
+        end += 1;
+    }
+    if end == visible.len() {
+        return one_end(line, width, mode, LineCut::Start, style);
+    }
+    let window_start = visible[window].0.start;
+    let window_end = visible[end - 1].0.end;
+
+    let before = marker::cut(style, starts[window], mode);
+    let after = marker::cut(style, total - starts[window] - kept, mode);
+    if before.len() + kept + after.len() >= total {
+        return None;
+    }
+
+    // Styles in effect where the kept part starts, replayed after the
+    // first marker, and where it ends, reset before the second
+    let replayed = active_before(line, &units, window_start);
+    let styled_at_end = !active_before(line, &units, window_end).is_empty();
+
+    let mut text = String::with_capacity(before.len() + line.len() + after.len());
+    text.push_str(&before);
+    let mut replayed_len = 0;
+    for r in &replayed {
+        text.push_str(&line[r.clone()]);
+        replayed_len += r.len();
+    }
+    text.push_str(&line[window_start..window_end]);
+    if styled_at_end {
+        text.push_str(RESET);
+    }
+    let markers = vec![0..before.len(), text.len()..text.len() + after.len()];
+    text.push_str(&after);
+
+    let bytes_removed = window_start - replayed_len + line.len() - window_end;
+    Some((text, bytes_removed, markers))
+}
+
+/// The SGR sequences in effect at byte `at` of `line`: those since the
+/// last reset.
+fn active_before(line: &str, units: &[Unit], at: usize) -> Vec<Range<usize>> {
+    let mut active = Vec::new();
+    for unit in units {
+        let Unit::Escape(r) = unit else {
+            continue;
+        };
+        if r.start >= at {
+            break;
+        }
+        match ansi::sgr(&line[r.clone()]) {
+            Some(true) => active.clear(),
+            Some(false) => active.push(r.clone()),
+            None => {}
+        }
+    }
+    active
+}
+
+/// [`truncate`], its marker's range in a `Vec`.
+fn one_end(
+    line: &str,
+    width: usize,
+    mode: WidthMode,
+    cut: LineCut,
+    style: MarkerStyle,
+) -> Option<(String, usize, Vec<Range<usize>>)> {
+    truncate(line, width, mode, cut, style)
+        .map(|(text, removed, marker)| (text, removed, vec![marker]))
+}
@@ -0,0 +1,128 @@
+//! [`TruncWriter`]: truncation behind `std::io::Write`, for output that's
+//! written rather than read.
+
+use std::io::{self, Write};
+use std::mem;
+
+use crate::cut::Cut;
+use crate::run::Run;
+use crate::Truncator;
+
+/// An [`io::Write`] that truncates what's written to it into an inner
+/// writer, from [`Truncator::writer`].
+///
+/// Bytes are split into lines as they come, so writes needn't end on a
+/// line boundary. Head and match lines reach the inner writer as they're
+/// found, and [`flush`](Write::flush) flushes them through; the tail
+/// waits for the end of the input. That's [`TruncWriter::finish`], which
+/// hands the inner writer back, or else dropping the writer. Invalid UTF-8
+/// is replaced. With [`Truncator::max_line_bytes`], a line is cut short as
+/// it's written, so one that never ends isn't held whole.
+///
+/// Write errors stop further output and are returned by
+/// [`TruncWriter::finish`]; on drop they're lost.
+///
+/// ```
+/// use std::io::Write;
+/// use trunc::Truncator;
+///
+/// let truncator = Truncator::new().first(2).last(2);
+/// let mut writer = truncator.writer(Vec::new());
+/// for i in 1..=100 {
+///     writeln!(writer, "line {}", i).unwrap();
+/// }
+/// let out = writer.finish().unwrap();
+///
+/// assert_eq!(
+///     String::from_utf8(out).unwrap(),
+///     "line 1\nline 2\n[... 96 lines truncated ...]\nline 99\nline 100\n"
+/// );
+/// ```
+pub struct TruncWriter<'a, W: Write> {
+    /// Taken by [`TruncWriter::finish`]
+    run: Option<Run<'a, W>>,
+    /// The start of a line whose end hasn't been written yet
+    partial: Vec<u8>,
+    /// Bytes cut from the middle of `partial` so far
+    dropped: usize,
+    /// Cutting overlong lines short, with [`Truncator::max_line_bytes`]
+    cut: Option<Cut>,
+}
+
+impl<'a, W: Write> TruncWriter<'a, W> {
+    pub(crate) fn new(config: &'a Truncator, writer: W) -> Self {
+        TruncWriter {
+            run: Some(Run::new(config, config.output(writer))),
+            partial: Vec::new(),
+            dropped: 0,
+            cut: config.long_line_cut(),
+        }
+    }
+
+    /// Truncate the line in `partial`, cut short if it's too long, which
+    /// ended in `newline` bytes (0 at the end of the input).
+    fn line(&mut self, newline: usize) {
+        let mut line = mem::take(&mut self.partial);
+        let mut dropped = mem::take(&mut self.dropped);
+        if let Some(cut) = self.cut {
+            dropped = cut.finish(&mut line, dropped);
+        }
+        let size = line.len() + newline;
+        let content = line.strip_suffix(b"\r").unwrap_or(&line);
+        if let Some(run) = &mut self.run {
+            run.sized(&String::from_utf8_lossy(content), size, dropped);
+        }
+        // The next line is read into the same buffer
+        line.clear();
+        self.partial = line;
+    }
+
+    /// End the input: truncate a last line without a `\n`, write the tail
+    /// and anything else still to come, and hand back the inner writer.
+    pub fn finish(mut self) -> io::Result<W> {
+        self.end()?;
+        let run = self.run.take().expect("finished once");
+        Ok(run.into_output().into_inner())
+    }
+
+    /// What [`TruncWriter::finish`] does, short of giving the writer back.
+    /// Does nothing the second time.
+    fn end(&mut self) -> io::Result<()> {
+        if !self.partial.is_empty() {
+            self.line(0);
+        }
+        match &mut self.run {
+            Some(run) => run.finish(),
+            None => Ok(()),
+        }
+    }
+}
+
+impl<W: Write> Write for TruncWriter<'_, W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let mut rest = buf;
+        while let Some(end) = rest.iter().position(|&b| b == b'\n') {
+            self.partial.extend_from_slice(&rest[..end]);
+            self.line(1);
+            rest = &rest[end + 1..];
+        }
+        self.partial.extend_from_slice(rest);
+        if let Some(cut) = self.cut {
+            self.dropped += cut.trim(&mut self.partial);
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        if let Some(run) = &mut self.run {
+            run.out().flush();
+        }
+        Ok(())
+    }
+}
+
+impl<W: Write> Drop for TruncWriter<'_, W> {
+    fn drop(&mut self) {
+        let _ = self.end();
+    }
+}
@@ -0,0 +1,138 @@
+//! Tests for `trunc::aio`, with the `async` feature: truncation over
+//! tokio's async I/O and streams.
+//!
+//! The async adapters drive the same core as the blocking API, so their
+//! output has to match it exactly.
+#![cfg(feature = "async")]
+
+mod common;
+
+use common::generate_lines_with_errors;
+use futures_core::Stream;
+use regex::Regex;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use trunc::aio::{self, TruncatedStream};
+use trunc::{Selection, TruncatedItem, TruncatedLines, Truncator};
+
+/// Run `truncator` over `input` with the blocking API.
+fn process(truncator: &Truncator, input: &str) -> String {
+    let mut out = Vec::new();
+    truncator.process(input.as_bytes(), &mut out).unwrap();
+    String::from_utf8(out).unwrap()
+}
+
+/// Run `truncator` over `input` with [`aio::process`].
+async fn process_async(truncator: &Truncator, input: &str) -> String {
+    let mut out = Vec::new();
+    aio::process(truncator, input.as_bytes(), &mut out)
+        .await
+        .unwrap();
+    String::from_utf8(out).unwrap()
+}
+
+/// A stream of `lines`, ready at once.
+struct Lines(std::vec::IntoIter<String>);
+
+impl Stream for Lines {
+    type Item = String;
+
+    fn poll_next(mut self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<Option<String>> {
+        Poll::Ready(self.0.next())
+    }
+}
+
+/// Every item from `stream`.
+async fn collect<S: Stream + Unpin>(mut stream: S) -> Vec<S::Item> {
+    let mut items = Vec::new();
+    while let Some(item) = std::future::poll_fn(|cx| Pin::new(&mut stream).poll_next(cx)).await {
+        items.push(item);
+    }
+    items
+}
+
+#[tokio::test]
+async fn process_matches_the_blocking_api() {
+    let input = generate_lines_with_errors(1000, &[200, 600]);
+    let truncator = Truncator::new()
+        .first(10)
+        .last(10)
+        .pattern(Regex::new("ERROR").unwrap());
+    assert_eq!(
+        process_async(&truncator, &input).await,
+        process(&truncator, &input)
+    );
+}
+
+#[tokio::test]
+async fn process_with_a_selection_matches_the_blocking_api() {
+    let input =
+        generate_lines_with_errors(300, &[50, 200]).replace("line 50 ERROR", "line 50 WARN");
+    let truncator = Truncator::new()
+        .first(5)
+        .last(5)
+        .matches(1)
+        .context(0)
+        .pattern(Regex::new("WARN|ERROR").unwrap())
+        .select(Selection::severity());
+    let out = process_async(&truncator, &input).await;
+    assert!(out.contains("line 200 ERROR\n"), "Got: {}", out);
+    assert_eq!(out, process(&truncator, &input));
+}
+
+#[tokio::test]
+async fn process_handles_crlf_and_a_last_line_without_newline() {
+    let input = "one\r\ntwo\r\nthree\r\nfour";
+    let truncator = Truncator::new().first(1).last(1);
+    assert_eq!(
+        process_async(&truncator, input).await,
+        "one\n[... 2 lines truncated ...]\nfour\n"
+    );
+}
+
+#[tokio::test]
+async fn process_writes_the_head_before_the_input_ends() {
+    let (mut input, reader) = tokio::io::duplex(1024);
+    let (writer, output) = tokio::io::duplex(1024);
+    let truncator = std::sync::Arc::new(Truncator::new().first(2).last(1));
+    // Spawned, so the future has to be Send
+    let task = tokio::spawn({
+        let truncator = truncator.clone();
+        async move { aio::process(&truncator, BufReader::new(reader), writer).await }
+    });
+
+    input.write_all(b"a\nb\nc\n").await.unwrap();
+    let mut output = BufReader::new(output);
+    let mut line = String::new();
+    output.read_line(&mut line).await.unwrap();
+    assert_eq!(line, "a\n");
+
+    input.write_all(b"d\n").await.unwrap();
+    drop(input);
+    task.await.unwrap().unwrap();
+    let mut rest = String::new();
+    while output.read_line(&mut rest).await.unwrap() > 0 {}
+    assert_eq!(rest, "b\n[... 1 lines truncated ...]\nd\n");
+}
+
+#[tokio::test]
+async fn truncated_stream_matches_truncated_lines() {
+    let input = generate_lines_with_errors(500, &[100, 300]);
+    let lines: Vec<String> = input.lines().map(String::from).collect();
+    let truncator = Truncator::new()
+        .first(5)
+        .last(5)
+        .pattern(Regex::new("ERROR").unwrap());
+
+    let items = collect(TruncatedStream::new(
+        Lines(lines.clone().into_iter()),
+        &truncator,
+    ))
+    .await;
+    assert!(items.contains(&TruncatedItem::Line("line 300 ERROR".to_string())));
+    assert_eq!(
+        items,
+        TruncatedLines::new(lines, &truncator).collect::<Vec<_>>()
+    );
+}
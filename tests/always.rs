@@ -0,0 +1,147 @@
+//! Tests for `--always`: lines shown wherever they fall in the middle, with
+//! or without a pattern, outside the `-m` budget.
+
+mod common;
+
+use common::{generate_lines_with_matches, trunc};
+use predicates::prelude::*;
+
+#[test]
+fn shown_without_pattern() {
+    let input = generate_lines_with_matches(100, &[50], "FATAL");
+
+    trunc()
+        .args(["-f", "2", "-l", "2", "--always", "FATAL"])
+        .write_stdin(input)
+        .assert()
+        .success()
+        .stdout(
+            "line 1\nline 2\n\
+             [... 47 lines truncated ...]\n\
+             line 50 contains FATAL\n\
+             [... 48 lines truncated ...]\n\
+             line 99\nline 100\n",
+        );
+}
+
+#[test]
+fn no_context() {
+    let input = generate_lines_with_matches(100, &[50], "FATAL");
+
+    trunc()
+        .args(["-f", "2", "-l", "2", "-C", "3", "--always", "FATAL"])
+        .write_stdin(input)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("line 49\n").not())
+        .stdout(predicate::str::contains("line 51\n").not());
+}
+
+#[test]
+fn not_counted_towards_matches() {
+    let input = generate_lines_with_matches(100, &[30, 60], "ERROR")
+        .replace("line 45\n", "line 45 FATAL\n");
+
+    trunc()
+        .args(["-f", "1", "-l", "1", "-C", "0", "-m", "1"])
+        .args(["--always", "FATAL", "ERROR"])
+        .write_stdin(input)
+        .assert()
+        .success()
+        .stdout(
+            "line 1\n\
+             [... 28 lines truncated, match 1/1 shown ...]\n\
+             line 30 contains ERROR\n\
+             [... 14 lines truncated ...]\n\
+             line 45 FATAL\n\
+             [... 54 lines and 1 match truncated (2 total) ...]\n\
+             line 100\n",
+        );
+}
+
+#[test]
+fn match_and_always_shown_once() {
+    let input = generate_lines_with_matches(100, &[50], "ERROR");
+
+    let assert = trunc()
+        .args([
+            "-f", "1", "-l", "1", "-C", "1", "--always", "ERROR", "ERROR",
+        ])
+        .write_stdin(input)
+        .assert()
+        .success();
+
+    let stdout = String::from_utf8_lossy(&assert.get_output().stdout).into_owned();
+    assert_eq!(
+        stdout.matches("contains ERROR").count(),
+        1,
+        "Got: {}",
+        stdout
+    );
+    assert!(
+        stdout.contains("match 1 shown ...]\nline 49\n"),
+        "Got: {}",
+        stdout
+    );
+}
+
+#[test]
+fn within_match_context_shown_once() {
+    let input =
+        generate_lines_with_matches(100, &[50], "ERROR").replace("line 51\n", "line 51 FATAL\n");
+
+    trunc()
+        .args([
+            "-f", "1", "-l", "1", "-C", "2", "--always", "FATAL", "ERROR",
+        ])
+        .write_stdin(input)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "line 48\nline 49\nline 50 contains ERROR\nline 51 FATAL\nline 52\n[...",
+        ));
+}
+
+#[test]
+fn head_and_tail_unchanged() {
+    let input = generate_lines_with_matches(100, &[2, 99], "FATAL");
+
+    trunc()
+        .args(["-f", "2", "-l", "2", "--always", "FATAL"])
+        .write_stdin(input)
+        .assert()
+        .success()
+        .stdout(
+            "line 1\nline 2 contains FATAL\n\
+             [... 96 lines truncated ...]\n\
+             line 99 contains FATAL\nline 100\n",
+        );
+}
+
+#[test]
+fn repeatable() {
+    let input = generate_lines_with_matches(100, &[30], "FATAL")
+        .replace("line 60\n", "line 60 exit status 2\n");
+
+    trunc()
+        .args(["-f", "1", "-l", "1"])
+        .args(["--always", "FATAL", "--always", "exit status"])
+        .write_stdin(input)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("line 30 contains FATAL\n"))
+        .stdout(predicate::str::contains("line 60 exit status 2\n"));
+}
+
+#[test]
+fn fits_under_max_bytes() {
+    let input = generate_lines_with_matches(1000, &[500], "FATAL");
+
+    trunc()
+        .args(["--max-bytes", "400", "--always", "FATAL"])
+        .write_stdin(input)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("line 500 contains FATAL\n"))
+        .stdout(predicate::function(|out: &str| out.len() <= 400));
+}
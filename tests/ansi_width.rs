@@ -0,0 +1,114 @@
+//! Tests for ANSI-escape-aware line truncation.
+//!
+//! Escape sequences in colored input take no columns, so `-w` and the
+//! `[... N chars ...]` count measure visible chars only, sequences are never
+//! split, and colors don't bleed into the marker.
+//!
+//! Escapes are stripped by default when stdout isn't a terminal, as here,
+//! so these keep them with `--keep-ansi`.
+
+mod common;
+
+use common::run;
+
+const RED: &str = "\x1b[31m";
+const GREEN: &str = "\x1b[32m";
+const RESET: &str = "\x1b[0m";
+
+#[test]
+fn escapes_not_counted_as_chars() {
+    // 150 visible chars, but well over 200 bytes with escapes
+    let line = (0..50)
+        .map(|_| format!("{}abc{}", RED, RESET))
+        .collect::<String>();
+
+    let stdout = run(&["--keep-ansi", "-w", "100"], format!("{}\n", line));
+
+    assert_eq!(stdout, format!("{}\n", line), "Should not be truncated");
+}
+
+#[test]
+fn marker_counts_visible_chars() {
+    let line = format!("{}{}{}", RED, "x".repeat(300), RESET);
+
+    let stdout = run(&["--keep-ansi", "-w", "10"], format!("{}\n", line));
+
+    assert!(stdout.contains("[... 280 chars ...]"), "Got: {:?}", stdout);
+}
+
+#[test]
+fn reset_before_marker() {
+    let line = format!("{}{}{}", RED, "x".repeat(300), RESET);
+
+    let stdout = run(&["--keep-ansi", "-w", "10"], format!("{}\n", line));
+
+    assert!(
+        stdout.starts_with(&format!(
+            "{}{}{}[... 280 chars ...]",
+            RED,
+            "x".repeat(10),
+            RESET
+        )),
+        "Got: {:?}",
+        stdout
+    );
+}
+
+#[test]
+fn style_replayed_after_marker() {
+    // Green starts in the removed middle and is still on for the kept end
+    let line = format!("{}{}{}{}", "a".repeat(100), GREEN, "g".repeat(100), RESET);
+
+    let stdout = run(&["--keep-ansi", "-w", "10"], format!("{}\n", line));
+
+    assert_eq!(
+        stdout,
+        format!(
+            "{}[... 180 chars ...]{}{}{}\n",
+            "a".repeat(10),
+            GREEN,
+            "g".repeat(10),
+            RESET
+        )
+    );
+}
+
+#[test]
+fn escape_sequences_not_split() {
+    // Every char is individually colored, so any byte cut would land in one
+    let line = (0..300)
+        .map(|i| format!("\x1b[3{}m{}", i % 8, (b'a' + (i % 26) as u8) as char))
+        .collect::<String>();
+
+    let stdout = run(&["--keep-ansi", "-w", "10"], format!("{}\n", line));
+    let out = stdout.trim_end();
+
+    // Every ESC starts a complete `ESC [ digits m` sequence
+    for (i, _) in out.match_indices('\x1b') {
+        let rest = &out[i + 1..];
+        let end = rest.find('m').expect("unterminated escape");
+        assert!(
+            rest.starts_with('[') && rest[1..end].chars().all(|c| c.is_ascii_digit()),
+            "Split escape in {:?}",
+            out
+        );
+    }
+    assert!(out.contains("[... 280 chars ...]"));
+}
+
+#[test]
+fn trailer_hidden_bytes_account_for_escapes() {
+    let line = format!("{}{}{}", RED, "x".repeat(300), RESET);
+
+    let stdout = run(
+        &["--keep-ansi", "-w", "10", "--trailer", "checksum"],
+        format!("{}\n", line),
+    );
+
+    // Only the 280 removed x's are hidden; both escape sequences are shown
+    assert!(
+        stdout.contains("hidden-lines:0 hidden-bytes:280]"),
+        "Got: {:?}",
+        stdout
+    );
+}
@@ -0,0 +1,76 @@
+//! Tests for `--around LINE[:CONTEXT]`: a window of input lines around a
+//! line number, shown between head and tail.
+
+mod common;
+
+use common::{generate_lines, trunc};
+use predicates::prelude::*;
+
+#[test]
+fn window_between_head_and_tail() {
+    trunc()
+        .args(["-f", "1", "-l", "1", "--around", "50:2"])
+        .write_stdin(generate_lines(100))
+        .assert()
+        .success()
+        .stdout(
+            "line 1\n[... 46 lines truncated ...]\nline 48\nline 49\nline 50\nline 51\nline 52\n[... 47 lines truncated ...]\nline 100\n",
+        );
+}
+
+#[test]
+fn default_context_from_c() {
+    trunc()
+        .args(["-f", "0", "-l", "0", "-C", "1", "--around", "50"])
+        .write_stdin(generate_lines(100))
+        .assert()
+        .success()
+        .stdout("[... 48 lines truncated ...]\nline 49\nline 50\nline 51\n[... 49 lines truncated ...]\n");
+}
+
+#[test]
+fn default_context_from_b_and_a() {
+    trunc()
+        .args(["-f", "0", "-l", "0", "-B", "0", "-A", "2", "--around", "50"])
+        .write_stdin(generate_lines(100))
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "[... 49 lines truncated ...]\nline 50\nline 51\nline 52\n[... 48 lines truncated ...]\n",
+        ));
+}
+
+#[test]
+fn window_clipped_at_start() {
+    trunc()
+        .args(["-f", "0", "-l", "0", "--around", "2:5"])
+        .write_stdin(generate_lines(20))
+        .assert()
+        .success()
+        .stdout(predicate::str::starts_with("line 1\nline 2\n"))
+        .stdout(predicate::str::contains(
+            "line 7\n[... 13 lines truncated ...]\n",
+        ));
+}
+
+#[test]
+fn repeatable() {
+    trunc()
+        .args(["-f", "0", "-l", "0", "--around", "10:0", "--around", "90:0"])
+        .write_stdin(generate_lines(100))
+        .assert()
+        .success()
+        .stdout("[... 9 lines truncated ...]\nline 10\n[... 79 lines truncated ...]\nline 90\n[... 10 lines truncated ...]\n");
+}
+
+#[test]
+fn invalid_windows_rejected() {
+    for around in ["0", "x", "5:x", "5:-1"] {
+        trunc()
+            .args(["--around", around])
+            .write_stdin("x\n")
+            .assert()
+            .failure()
+            .stderr(predicate::str::contains("--around"));
+    }
+}
@@ -0,0 +1,103 @@
+//! Tests for `--auto`: built-in failure detectors for the middle section.
+//!
+//! Agents get the failure without knowing what to grep for, and each match
+//! marker names the detector that fired.
+
+mod common;
+
+use common::{generate_lines, trunc};
+use predicates::prelude::*;
+
+/// 100 lines with `line` in the middle, run through `trunc --auto` with a
+/// one-line head and tail and no context.
+fn detect(line: &str) -> String {
+    let input = format!("{}\n{}\n{}", generate_lines(50), line, generate_lines(50));
+    let assert = trunc()
+        .args(["--auto", "-f", "1", "-l", "1", "-C", "0"])
+        .write_stdin(input)
+        .assert()
+        .success();
+    String::from_utf8_lossy(&assert.get_output().stdout).into_owned()
+}
+
+#[test]
+fn detects_rust_panic() {
+    let stdout = detect("thread 'main' panicked at src/main.rs:2:5:");
+    assert!(stdout.contains("match 1 shown ('panic') ...]\nthread 'main' panicked"));
+}
+
+#[test]
+fn detects_compiler_error() {
+    let stdout = detect("error[E0308]: mismatched types");
+    assert!(stdout.contains("match 1 shown ('error') ...]\nerror[E0308]"));
+}
+
+#[test]
+fn detects_python_traceback() {
+    let stdout = detect("Traceback (most recent call last):");
+    assert!(stdout.contains("('traceback')"), "Got: {}", stdout);
+}
+
+#[test]
+fn detects_java_exception() {
+    let stdout = detect("java.lang.NullPointerException");
+    assert!(stdout.contains("('exception')"), "Got: {}", stdout);
+}
+
+#[test]
+fn detects_failed_test() {
+    let stdout = detect("test tests::parse ... FAILED");
+    assert!(stdout.contains("('failed')"), "Got: {}", stdout);
+}
+
+#[test]
+fn detects_non_zero_exit() {
+    let stdout = detect("Process completed with exit code 2.");
+    assert!(stdout.contains("('exit status')"), "Got: {}", stdout);
+
+    let stdout = detect("make: *** [Makefile:3: all] Error 1");
+    assert!(stdout.contains("('exit status')"), "Got: {}", stdout);
+}
+
+#[test]
+fn names_every_detector_that_fired() {
+    let stdout = detect("FAILED: thread panicked at src/lib.rs:1:1");
+    assert!(stdout.contains("('panic', 'failed')"), "Got: {}", stdout);
+}
+
+#[test]
+fn quiet_input_has_no_matches() {
+    let stdout = detect("everything is fine");
+    assert!(stdout.contains("0 matches found"), "Got: {}", stdout);
+}
+
+#[test]
+fn combines_with_own_pattern() {
+    let input = format!(
+        "{}\nWARN disk almost full\n{}\nerror: out of disk\n{}",
+        generate_lines(50),
+        generate_lines(50),
+        generate_lines(50)
+    );
+
+    trunc()
+        .args(["--auto", "-f", "1", "-l", "1", "-C", "0", "WARN"])
+        .write_stdin(input)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "match 1 shown ('WARN') ...]\nWARN disk",
+        ))
+        .stdout(predicate::str::contains(
+            "match 2 shown ('error') ...]\nerror: out",
+        ));
+}
+
+#[test]
+fn not_with_fixed_string() {
+    trunc()
+        .args(["--auto", "--fixed-string", "x"])
+        .write_stdin("x\n")
+        .assert()
+        .failure();
+}
@@ -0,0 +1,98 @@
+//! Tests for `--binary-safe`: input that isn't valid UTF-8 is read lossily,
+//! each invalid sequence becoming U+FFFD, instead of stopping with an error.
+
+mod common;
+
+use common::{generate_lines, trunc};
+use predicates::prelude::*;
+
+/// 100 lines with line 50 replaced by garbage around "ERROR".
+fn garbled() -> Vec<u8> {
+    generate_lines(100)
+        .lines()
+        .flat_map(|line| match line {
+            "line 50" => b"\x00\xff\xfeERROR\xc3\n".to_vec(),
+            line => format!("{}\n", line).into_bytes(),
+        })
+        .collect()
+}
+
+#[test]
+fn invalid_utf8_fails_by_default() {
+    trunc()
+        .write_stdin(b"a\n\xff\xfe\nb\n".to_vec())
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("valid UTF-8 (see --binary-safe)"));
+}
+
+#[test]
+fn invalid_sequences_replaced() {
+    trunc()
+        .arg("--binary-safe")
+        .write_stdin(b"a\n\xff\xfeb\nc\n".to_vec())
+        .assert()
+        .success()
+        .stdout("a\n\u{FFFD}\u{FFFD}b\nc\n");
+}
+
+#[test]
+fn valid_input_unchanged() {
+    let input = format!("{}\nh\u{e9}llo \u{1F600}\r\n", generate_lines(10));
+    trunc()
+        .arg("--binary-safe")
+        .write_stdin(input)
+        .assert()
+        .success()
+        .stdout(format!("{}\nh\u{e9}llo \u{1F600}\n", generate_lines(10)));
+}
+
+#[test]
+fn truncates_around_garbage() {
+    trunc()
+        .args(["--binary-safe", "-f", "1", "-l", "1"])
+        .write_stdin(garbled())
+        .assert()
+        .success()
+        .stdout("line 1\n[... 98 lines truncated ...]\nline 100\n");
+}
+
+#[test]
+fn pattern_matches_garbled_line() {
+    trunc()
+        .args(["--binary-safe", "-f", "0", "-l", "0", "-C", "0", "ERROR"])
+        .write_stdin(garbled())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "\n\0\u{FFFD}\u{FFFD}ERROR\u{FFFD}\n",
+        ));
+}
+
+#[test]
+fn file_input() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("input.log");
+    std::fs::write(&path, garbled()).unwrap();
+    for args in [&["-f", "1", "-l", "1"][..], &["-f", "50", "-l", "1"]] {
+        trunc()
+            .arg("--binary-safe")
+            .args(args)
+            .arg("--files")
+            .arg(&path)
+            .assert()
+            .success()
+            .stdout(predicate::str::contains("line 1\n"))
+            .stdout(predicate::str::ends_with("line 100\n"));
+    }
+}
+
+#[cfg(unix)]
+#[test]
+fn wrapped_command_output() {
+    trunc()
+        .args(["--binary-safe", "--", "sh", "-c", r"printf 'a\n\377\nb\n'"])
+        .assert()
+        .success()
+        .stdout("a\n\u{FFFD}\nb\n");
+}
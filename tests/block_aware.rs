@@ -0,0 +1,143 @@
+//! Tests for `--block-aware`: cut points move to the edges of indented
+//! blocks such as tracebacks and compiler diagnostics.
+
+mod common;
+
+use common::{generate_lines, run, trunc};
+use predicates::prelude::*;
+
+const TRACEBACK: &str = "Traceback (most recent call last):
+  File \"app.py\", line 10, in <module>
+    main()
+  File \"app.py\", line 6, in main
+    run()
+ValueError: bad input
+";
+
+const DIAGNOSTIC: &str = "error[E0308]: mismatched types
+ --> src/main.rs:2:18
+  |
+2 |     let x: u32 = \"a\";
+  |                  ^^^ expected `u32`
+  |
+  = note: expected type `u32`
+";
+
+#[test]
+fn head_runs_to_end_of_block() {
+    let input = format!("{}{}\n", TRACEBACK, generate_lines(20));
+    let stdout = run(&["--block-aware", "-f", "2", "-l", "1"], input);
+    assert_eq!(
+        stdout,
+        format!("{}[... 19 lines truncated ...]\nline 20\n", TRACEBACK)
+    );
+}
+
+#[test]
+fn without_flag_head_cuts_block() {
+    let input = format!("{}{}\n", TRACEBACK, generate_lines(20));
+    let stdout = run(&["-f", "2", "-l", "1"], input);
+    assert!(
+        stdout.contains("[... 23 lines truncated ...]"),
+        "Got: {}",
+        stdout
+    );
+}
+
+#[test]
+fn tail_starts_at_start_of_block() {
+    let input = format!("{}\n{}", generate_lines(20), DIAGNOSTIC);
+    let stdout = run(&["--block-aware", "-f", "1", "-l", "2"], input);
+    assert_eq!(
+        stdout,
+        format!("line 1\n[... 19 lines truncated ...]\n{}", DIAGNOSTIC)
+    );
+}
+
+#[test]
+fn context_covers_whole_block() {
+    let input = format!(
+        "{}\n{}{}\n",
+        generate_lines(20),
+        TRACEBACK,
+        generate_lines(20)
+    );
+    let stdout = run(
+        &[
+            "--block-aware",
+            "-f",
+            "1",
+            "-l",
+            "1",
+            "-C",
+            "0",
+            "main\\(\\)",
+        ],
+        input,
+    );
+    assert_eq!(
+        stdout,
+        format!(
+            "line 1\n[... 19 lines truncated, match 1 shown ...]\n{}[... 19 lines truncated ...]\nline 20\n",
+            TRACEBACK
+        )
+    );
+}
+
+#[test]
+fn blank_line_ends_block() {
+    let input = format!(
+        "head\n  indented\n\n  after blank\n{}\n",
+        generate_lines(10)
+    );
+    trunc()
+        .args(["--block-aware", "-f", "1", "-l", "1"])
+        .write_stdin(input)
+        .assert()
+        .success()
+        .stdout(predicate::str::starts_with(
+            "head\n  indented\n[... 11 lines truncated ...]\n",
+        ));
+}
+
+#[test]
+fn extension_is_bounded() {
+    let block: String = (1..=100).map(|i| format!("  frame {}\n", i)).collect();
+    let input = format!("start\n{}{}\n", block, generate_lines(10));
+    let stdout = run(&["--block-aware", "-f", "1", "-l", "1"], input);
+    assert!(
+        stdout.contains("  frame 50\n[... 59 lines truncated ...]\n"),
+        "Got: {}",
+        stdout
+    );
+}
+
+#[test]
+fn extra_lines_fit_max_bytes() {
+    let input = format!("{}{}\n", TRACEBACK, generate_lines(100));
+    let stdout = run(
+        &["--block-aware", "-f", "2", "-l", "2", "--max-bytes", "150"],
+        input,
+    );
+    assert!(
+        stdout.len() <= 150,
+        "Got {} bytes: {}",
+        stdout.len(),
+        stdout
+    );
+    assert!(stdout.contains("byte limit"), "Got: {}", stdout);
+}
+
+#[test]
+fn file_input_not_skimmed() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("build.log");
+    std::fs::write(&path, format!("{}{}\n", TRACEBACK, generate_lines(20))).unwrap();
+    trunc()
+        .args(["--block-aware", "-f", "2", "-l", "1"])
+        .arg("--files")
+        .arg(&path)
+        .assert()
+        .success()
+        .stdout(predicate::str::starts_with(TRACEBACK));
+}
@@ -0,0 +1,132 @@
+//! Tests for `--byte-ranges`: every truncation marker gives the byte range
+//! of the input it hides.
+
+mod common;
+
+use common::{generate_terminated_lines, run, trunc};
+use predicates::prelude::*;
+use regex::Regex;
+use std::io::Write;
+use tempfile::NamedTempFile;
+
+/// The byte ranges named by the markers in `stdout`.
+fn ranges(stdout: &str) -> Vec<(usize, usize)> {
+    let re = Regex::new(r"\(bytes (\d+)-(\d+)\)").unwrap();
+    re.captures_iter(stdout)
+        .map(|c| (c[1].parse().unwrap(), c[2].parse().unwrap()))
+        .collect()
+}
+
+#[test]
+fn range_holds_hidden_lines() {
+    let input = generate_terminated_lines(100);
+    let stdout = run(&["-f", "2", "-l", "2", "--byte-ranges"], &input);
+    assert_eq!(
+        stdout,
+        "line 1\nline 2\n[... 96 lines truncated (bytes 14-775) ...]\nline 99\nline 100\n"
+    );
+    let hidden = &input[14..775];
+    assert!(hidden.starts_with("line 3\n"));
+    assert!(hidden.ends_with("line 98\n"));
+}
+
+#[test]
+fn every_marker_in_pattern_mode() {
+    let input = generate_terminated_lines(100);
+    let stdout = run(
+        &["-f", "2", "-l", "2", "-C", "0", "--byte-ranges", "line 50$"],
+        &input,
+    );
+    let found = ranges(&stdout);
+    assert_eq!(found.len(), 2, "Got: {}", stdout);
+    assert!(input[found[0].0..found[0].1].ends_with("line 49\n"));
+    assert!(input[found[1].0..found[1].1].starts_with("line 51\n"));
+}
+
+#[test]
+fn compact_markers() {
+    let stdout = run(
+        &[
+            "-f",
+            "1",
+            "-l",
+            "1",
+            "--byte-ranges",
+            "--markers",
+            "compact",
+        ],
+        generate_terminated_lines(10),
+    );
+    assert!(
+        stdout.contains("\u{2026}8L (bytes 7-63)\u{2026}"),
+        "Got: {}",
+        stdout
+    );
+}
+
+#[test]
+fn counted_across_inputs() {
+    let mut a = NamedTempFile::new().unwrap();
+    a.write_all(generate_terminated_lines(10).as_bytes())
+        .unwrap();
+    let mut b = NamedTempFile::new().unwrap();
+    b.write_all(generate_terminated_lines(10).as_bytes())
+        .unwrap();
+    let output = trunc()
+        .args(["-f", "1", "-l", "1", "--byte-ranges"])
+        .arg("--files")
+        .arg(a.path())
+        .arg(b.path())
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let len = generate_terminated_lines(10).len();
+    assert_eq!(ranges(&stdout), [(7, 63), (len + 7, len + 63)]);
+}
+
+#[test]
+fn not_repeated_with_save() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("full.log");
+    trunc()
+        .args(["-f", "1", "-l", "1", "--byte-ranges", "--save"])
+        .arg(&path)
+        .write_stdin(generate_terminated_lines(10))
+        .assert()
+        .success()
+        .stdout(
+            predicate::str::contains("(full output: ")
+                .and(predicate::str::contains("(bytes ").not()),
+        );
+}
+
+#[test]
+fn short_input_has_no_markers() {
+    trunc()
+        .args(["--byte-ranges"])
+        .write_stdin(generate_terminated_lines(5))
+        .assert()
+        .success()
+        .stdout(generate_terminated_lines(5));
+}
+
+#[test]
+fn range_ends_at_the_end_of_a_file_without_a_final_newline() {
+    let mut file = NamedTempFile::new().unwrap();
+    file.write_all(b"1234\n6789").unwrap();
+    let output = trunc()
+        .args(["-f", "1", "-l", "0", "--byte-ranges", "--files"])
+        .arg(file.path())
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert_eq!(ranges(&stdout), [(5, 9)]);
+
+    let output = trunc()
+        .args(["-f", "0", "-l", "0", "--byte-ranges"])
+        .write_stdin("123456789")
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert_eq!(ranges(&stdout), [(0, 9)]);
+}
@@ -0,0 +1,107 @@
+//! Tests for `--first-bytes` / `--last-bytes`: head and tail sized in bytes
+//! of output rather than lines.
+
+mod common;
+
+use common::{generate_lines, trunc};
+use predicates::prelude::*;
+
+#[test]
+fn whole_lines_that_fit() {
+    // "line N\n" is 7 bytes for N < 10, and "line 999\n" 9
+    trunc()
+        .args(["--first-bytes", "30", "--last-bytes", "20"])
+        .write_stdin(generate_lines(1000))
+        .assert()
+        .success()
+        .stdout(
+            "line 1\nline 2\nline 3\nline 4\n[... 994 lines truncated ...]\nline 999\nline 1000\n",
+        );
+}
+
+#[test]
+fn mixed_with_line_counts() {
+    trunc()
+        .args(["--first-bytes", "14", "-l", "1"])
+        .write_stdin(generate_lines(100))
+        .assert()
+        .success()
+        .stdout("line 1\nline 2\n[... 97 lines truncated ...]\nline 100\n");
+}
+
+#[test]
+fn units() {
+    // 1K of "line N" lines runs past line 100
+    trunc()
+        .args(["--first-bytes", "1K", "-l", "0"])
+        .write_stdin(generate_lines(1000))
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "line 125\n[... 875 lines truncated ...]",
+        ));
+    trunc()
+        .args(["--first-bytes", "1KB", "-l", "0"])
+        .write_stdin(generate_lines(1000))
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "line 123\n[... 877 lines truncated ...]",
+        ));
+}
+
+#[test]
+fn counted_after_width_truncation() {
+    // Each long line shows as 3 + 20 + 3 chars plus a newline
+    let input = "x".repeat(100) + "\n" + &"y".repeat(100) + "\n" + &"z".repeat(100);
+    trunc()
+        .args(["-w", "3", "--first-bytes", "60", "-l", "0"])
+        .write_stdin(input)
+        .assert()
+        .success()
+        .stdout(predicate::str::starts_with(
+            "xxx[... 94 chars ...]xxx\nyyy[... 94 chars ...]yyy\n[... 1 lines truncated ...]",
+        ));
+}
+
+#[test]
+fn short_input_unchanged() {
+    trunc()
+        .args(["--first-bytes", "1K", "--last-bytes", "1K"])
+        .write_stdin(generate_lines(5))
+        .assert()
+        .success()
+        .stdout(generate_lines(5) + "\n");
+}
+
+#[test]
+fn pattern_mode() {
+    trunc()
+        .args(["--first-bytes", "14", "--last-bytes", "20", "-C", "0", "line 500$"])
+        .write_stdin(generate_lines(1000))
+        .assert()
+        .success()
+        .stdout("line 1\nline 2\n[... 497 lines truncated, match 1 shown ...]\nline 500\n[... 498 lines truncated ...]\nline 999\nline 1000\n");
+}
+
+#[test]
+fn conflicts_with_line_counts() {
+    trunc()
+        .args(["-f", "3", "--first-bytes", "100"])
+        .write_stdin("a\n")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("cannot be used with"));
+}
+
+#[test]
+fn invalid_sizes_rejected() {
+    for size in ["x", "4X", "K", "4.5K"] {
+        trunc()
+            .args(["--last-bytes", size])
+            .write_stdin("x\n")
+            .assert()
+            .failure()
+            .stderr(predicate::str::contains("--last-bytes"));
+    }
+}
@@ -0,0 +1,124 @@
+//! Tests for `--color`: matched text is highlighted wherever it's shown,
+//! markers are dimmed, and `auto` stays plain when piped or with NO_COLOR.
+
+mod common;
+
+use common::{generate_lines_with_matches, run, trunc};
+
+const MATCH: &str = "\x1b[1;31m";
+const DIM: &str = "\x1b[2m";
+const RESET: &str = "\x1b[0m";
+
+#[test]
+fn always_highlights_matches() {
+    let input = generate_lines_with_matches(100, &[50], "ERROR");
+    let stdout = run(&["-f", "3", "-l", "3", "--color", "always", "ERROR"], input);
+
+    assert!(
+        stdout.contains(&format!("line 50 contains {}ERROR{}\n", MATCH, RESET)),
+        "Got:\n{}",
+        stdout
+    );
+}
+
+#[test]
+fn every_occurrence_highlighted() {
+    let input = generate_lines_with_matches(100, &[50], "ERROR and ERROR");
+    let stdout = run(&["-f", "3", "-l", "3", "--color", "always", "ERROR"], input);
+
+    assert_eq!(stdout.matches(MATCH).count(), 2, "Got:\n{}", stdout);
+}
+
+#[test]
+fn markers_are_dimmed() {
+    let input = generate_lines_with_matches(100, &[50], "ERROR");
+    let stdout = run(&["-f", "3", "-l", "3", "--color", "always", "ERROR"], input);
+
+    assert!(
+        stdout.contains(&format!(
+            "{}[... 43 lines truncated, match 1 shown ...]{}\n",
+            DIM, RESET
+        )),
+        "Got:\n{}",
+        stdout
+    );
+}
+
+#[test]
+fn within_line_marker_dimmed_not_highlighted() {
+    // The pattern also matches the within-line marker's text
+    let long = format!("ERROR {} tail", "x".repeat(300));
+    let input = format!(
+        "{}\n{}\n{}",
+        generate_lines_with_matches(50, &[], ""),
+        long,
+        "end"
+    );
+    let stdout = run(
+        &["-f", "3", "-l", "1", "--color", "always", "ERROR|chars"],
+        input,
+    );
+
+    assert!(stdout.contains(&format!("{}ERROR{}", MATCH, RESET)));
+    assert!(
+        stdout.contains(&format!("{}[... 111 chars ...]{}", DIM, RESET)),
+        "Got:\n{}",
+        stdout
+    );
+}
+
+#[test]
+fn head_and_tail_highlighted_too() {
+    // Line 100 is past the -m budget, so it's only shown as part of the tail
+    let input = generate_lines_with_matches(100, &[1, 50, 100], "ERROR");
+    let stdout = run(
+        &[
+            "-f", "3", "-l", "3", "-m", "1", "--color", "always", "ERROR",
+        ],
+        input,
+    );
+
+    assert!(stdout.contains(&format!("line 1 contains {}ERROR", MATCH)));
+    assert!(stdout.contains(&format!("line 50 contains {}ERROR", MATCH)));
+    assert!(stdout.contains(&format!("line 100 contains {}ERROR", MATCH)));
+}
+
+#[test]
+fn auto_is_plain_when_piped() {
+    let input = generate_lines_with_matches(100, &[50], "ERROR");
+    let stdout = run(&["-f", "3", "-l", "3", "ERROR"], input);
+
+    assert!(!stdout.contains('\x1b'), "Got:\n{}", stdout);
+}
+
+#[test]
+fn never_is_plain() {
+    let input = generate_lines_with_matches(100, &[50], "ERROR");
+    let stdout = run(&["-f", "3", "-l", "3", "--color", "never", "ERROR"], input);
+
+    assert!(!stdout.contains('\x1b'));
+}
+
+#[test]
+fn always_overrides_no_color() {
+    let input = generate_lines_with_matches(100, &[50], "ERROR");
+    trunc()
+        .env("NO_COLOR", "1")
+        .args(["-f", "3", "-l", "3", "--color", "always", "ERROR"])
+        .write_stdin(input)
+        .assert()
+        .success()
+        .stdout(predicates::str::contains(MATCH));
+}
+
+#[test]
+fn color_counts_against_max_bytes() {
+    let input =
+        generate_lines_with_matches(1000, &(100..900).step_by(50).collect::<Vec<_>>(), "ERROR");
+    let stdout = run(
+        &["--color", "always", "--max-bytes", "1500", "ERROR"],
+        input,
+    );
+
+    assert!(stdout.len() <= 1500, "Got {} bytes", stdout.len());
+}
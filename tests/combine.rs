@@ -0,0 +1,125 @@
+//! Tests for `--all-of`, `--any-of` and `--and-not`: narrowing which lines
+//! count as matches.
+
+mod common;
+
+use common::trunc;
+use predicates::prelude::*;
+
+/// A log with errors of several kinds between lines of noise.
+fn build_log() -> String {
+    [
+        "start",
+        "ERROR timeout talking to db",
+        "ok",
+        "ERROR disk full",
+        "ok",
+        "WARN timeout talking to cache",
+        "ok",
+        "ERROR timeout talking to db, retrying",
+        "ok",
+        "end",
+    ]
+    .iter()
+    .map(|l| format!("{}\n", l))
+    .collect()
+}
+
+#[test]
+fn all_of_requires_every_regex() {
+    trunc()
+        .args([
+            "-f", "0", "-l", "0", "-C", "0", "--all-of", "ERROR", "timeout",
+        ])
+        .write_stdin(build_log())
+        .assert()
+        .success()
+        .stdout(
+            "[... 1 lines truncated, match 1 shown ...]\nERROR timeout talking to db\n\
+             [... 5 lines truncated, match 2 shown ...]\n\
+             ERROR timeout talking to db, retrying\n\
+             [... 2 lines truncated ...]\n",
+        );
+}
+
+#[test]
+fn and_not_rules_out_matches() {
+    trunc()
+        .args([
+            "-f",
+            "0",
+            "-l",
+            "0",
+            "-C",
+            "0",
+            "ERROR",
+            "--and-not",
+            "retrying",
+        ])
+        .write_stdin(build_log())
+        .assert()
+        .success()
+        .stdout(
+            "[... 1 lines truncated, match 1 shown ...]\nERROR timeout talking to db\n\
+             [... 1 lines truncated, match 2 shown ...]\nERROR disk full\n\
+             [... 6 lines truncated ...]\n",
+        );
+}
+
+#[test]
+fn any_of_narrows_pattern() {
+    trunc()
+        .args([
+            "-f", "0", "-l", "0", "-C", "0", "-e", "ERROR", "--any-of", "disk", "cache",
+        ])
+        .write_stdin(build_log())
+        .assert()
+        .success()
+        .stdout(
+            "[... 3 lines truncated, match 1 shown ...]\nERROR disk full\n\
+             [... 6 lines truncated ...]\n",
+        );
+}
+
+#[test]
+fn any_of_alone_is_the_pattern() {
+    trunc()
+        .args(["-f", "0", "-l", "0", "-C", "0", "--any-of", "disk", "cache"])
+        .write_stdin(build_log())
+        .assert()
+        .success()
+        .stdout(
+            "[... 3 lines truncated, match 1 shown ...]\nERROR disk full\n\
+             [... 1 lines truncated, match 2 shown ...]\nWARN timeout talking to cache\n\
+             [... 4 lines truncated ...]\n",
+        );
+}
+
+#[test]
+fn ruled_out_lines_still_shown_as_context() {
+    trunc()
+        .args([
+            "-f",
+            "0",
+            "-l",
+            "0",
+            "-C",
+            "1",
+            "ERROR disk",
+            "--and-not",
+            "x",
+        ])
+        .write_stdin("a\nb\nERROR x\nERROR disk\nc\nd\n")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("ERROR x\nERROR disk\nc\n"));
+}
+
+#[test]
+fn rejected_with_fixed_string() {
+    trunc()
+        .args(["--fixed-string", "--all-of", "a", "--", "b"])
+        .write_stdin("")
+        .assert()
+        .failure();
+}
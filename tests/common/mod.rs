@@ -0,0 +1,95 @@
+//! Helpers shared by the integration tests. Each test file is a crate of
+//! its own and pulls them in with `mod common;`, so not every file uses
+//! every helper.
+#![allow(dead_code)]
+
+use assert_cmd::Command;
+use std::process::Child;
+
+/// Helper to create a Command for the trunc binary.
+pub fn trunc() -> Command {
+    assert_cmd::cargo::cargo_bin_cmd!("trunc")
+}
+
+/// Run trunc with `args` over `input`, asserting it succeeds, and return
+/// its stdout.
+pub fn run(args: &[&str], input: impl AsRef<[u8]>) -> String {
+    let assert = trunc()
+        .args(args)
+        .write_stdin(input.as_ref())
+        .assert()
+        .success();
+    String::from_utf8_lossy(&assert.get_output().stdout).into_owned()
+}
+
+/// Get path to the trunc binary, for tests that spawn it themselves
+pub fn trunc_bin() -> std::path::PathBuf {
+    assert_cmd::cargo::cargo_bin!("trunc").to_path_buf()
+}
+
+/// Generate N lines of input: "line 1\nline 2\n..." (no final newline)
+pub fn generate_lines(n: usize) -> String {
+    (1..=n)
+        .map(|i| format!("line {}", i))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Generate N lines like "line 1\n", "line 2\n", etc., each ending in a
+/// newline.
+pub fn generate_terminated_lines(n: usize) -> String {
+    (1..=n).map(|i| format!("line {}\n", i)).collect()
+}
+
+/// Numbers 1 to `n`, one per line.
+pub fn generate_numbers(n: usize) -> String {
+    (1..=n).map(|i| format!("{}\n", i)).collect()
+}
+
+/// Generate N lines with a specific pattern at certain positions.
+pub fn generate_lines_with_matches(n: usize, match_at: &[usize], pattern: &str) -> String {
+    (1..=n)
+        .map(|i| {
+            if match_at.contains(&i) {
+                format!("line {} contains {}", i, pattern)
+            } else {
+                format!("line {}", i)
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Generate N newline-terminated lines, with `ERROR` on those in `errors`.
+pub fn generate_lines_with_errors(n: usize, errors: &[usize]) -> String {
+    (1..=n)
+        .map(|i| match errors.contains(&i) {
+            true => format!("line {} ERROR\n", i),
+            false => format!("line {}\n", i),
+        })
+        .collect()
+}
+
+/// A child process that's killed when dropped, so a failing assertion can't
+/// leave it running forever.
+pub struct Guard(pub Child);
+
+impl Drop for Guard {
+    fn drop(&mut self) {
+        let _ = self.0.kill();
+        let _ = self.0.wait();
+    }
+}
+
+impl std::ops::Deref for Guard {
+    type Target = Child;
+    fn deref(&self) -> &Child {
+        &self.0
+    }
+}
+
+impl std::ops::DerefMut for Guard {
+    fn deref_mut(&mut self) -> &mut Child {
+        &mut self.0
+    }
+}
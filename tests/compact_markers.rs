@@ -0,0 +1,107 @@
+//! Tests for `--markers compact`: terse `…980L…` and `…500c…` markers in
+//! place of the verbose prose ones.
+
+mod common;
+
+use common::{generate_lines, trunc};
+use predicates::prelude::*;
+
+#[test]
+fn line_gap() {
+    trunc()
+        .args(["-f", "2", "-l", "2", "--markers", "compact"])
+        .write_stdin(generate_lines(100))
+        .assert()
+        .success()
+        .stdout("line 1\nline 2\n\u{2026}96L\u{2026}\nline 99\nline 100\n");
+}
+
+#[test]
+fn verbose_by_default() {
+    trunc()
+        .args(["-f", "2", "-l", "2"])
+        .write_stdin(generate_lines(100))
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("[... 96 lines truncated ...]"));
+}
+
+#[test]
+fn char_cut() {
+    trunc()
+        .args(["-w", "3", "--markers", "compact"])
+        .write_stdin("abcdefghijklmnopqrstuvwxyz\n")
+        .assert()
+        .success()
+        .stdout("abc\u{2026}20c\u{2026}xyz\n");
+}
+
+#[test]
+fn column_cut() {
+    trunc()
+        .args(["-w", "3", "--width-mode", "columns", "--markers", "compact"])
+        .write_stdin("日本語のテキストはとても長いです\n")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("\u{2026}28col\u{2026}"));
+}
+
+#[test]
+fn pattern_mode() {
+    trunc()
+        .args(["-f", "2", "-l", "2", "-m", "1", "-C", "0", "--markers", "compact", "5$"])
+        .write_stdin(generate_lines(100))
+        .assert()
+        .success()
+        .stdout("line 1\nline 2\n\u{2026}2L\u{2026}\nline 5\n\u{2026}93L +9m\u{2026}\nline 99\nline 100\n");
+}
+
+#[test]
+fn no_matches() {
+    trunc()
+        .args(["-f", "1", "-l", "1", "--markers", "compact", "nothing"])
+        .write_stdin(generate_lines(10))
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("\u{2026}8L 0m\u{2026}"));
+}
+
+#[test]
+fn count_only() {
+    trunc()
+        .args(["-f", "1", "-l", "1", "-c", "--markers", "compact", "5$"])
+        .write_stdin(generate_lines(100))
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("\u{2026}98L 10m\u{2026}"));
+}
+
+#[test]
+fn excluded() {
+    trunc()
+        .args(["--exclude", "^skip", "--markers", "compact"])
+        .write_stdin("a\nskip\nb\n")
+        .assert()
+        .success()
+        .stdout("a\nb\n\u{2026}1L excluded\u{2026}\n");
+}
+
+#[test]
+fn with_region_id() {
+    trunc()
+        .args(["-f", "1", "-l", "1", "--markers", "compact", "--region-ids"])
+        .write_stdin("a\nb\nc\n")
+        .assert()
+        .success()
+        .stdout("a\n\u{2026}#1:1L\u{2026}\nc\n[#1: line 2, bytes 2-4]\n");
+}
+
+#[test]
+fn byte_limit_note_kept() {
+    trunc()
+        .args(["--max-bytes", "100", "--markers", "compact"])
+        .write_stdin(generate_lines(1000))
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("L, byte limit\u{2026}"));
+}
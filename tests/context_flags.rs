@@ -0,0 +1,107 @@
+//! Tests for `-B` / `-A`: separate context before and after each match, as
+//! in grep. `-C` sets both, and `-B`/`-A` override either side of it.
+
+mod common;
+
+use common::{generate_lines, trunc};
+use predicates::prelude::*;
+
+/// Lines `from..=to` of `generate_lines`, each with its newline.
+fn lines(from: usize, to: usize) -> String {
+    (from..=to).map(|i| format!("line {}\n", i)).collect()
+}
+
+#[test]
+fn before_only() {
+    trunc()
+        .args(["-f", "1", "-l", "1", "-B", "2", "-A", "0", "line 50$"])
+        .write_stdin(generate_lines(100))
+        .assert()
+        .success()
+        .stdout(format!(
+            "line 1\n[... 46 lines truncated, match 1 shown ...]\n{}[... 49 lines truncated ...]\nline 100\n",
+            lines(48, 50)
+        ));
+}
+
+#[test]
+fn after_only() {
+    trunc()
+        .args(["-f", "1", "-l", "1", "-B", "0", "-A", "2", "line 50$"])
+        .write_stdin(generate_lines(100))
+        .assert()
+        .success()
+        .stdout(format!(
+            "line 1\n[... 48 lines truncated, match 1 shown ...]\n{}[... 47 lines truncated ...]\nline 100\n",
+            lines(50, 52)
+        ));
+}
+
+#[test]
+fn overrides_one_side_of_context() {
+    // -C 5 still applies before the match
+    trunc()
+        .args(["-f", "1", "-l", "1", "-C", "5", "-A", "1", "line 50$"])
+        .write_stdin(generate_lines(100))
+        .assert()
+        .success()
+        .stdout(format!(
+            "line 1\n[... 43 lines truncated, match 1 shown ...]\n{}[... 48 lines truncated ...]\nline 100\n",
+            lines(45, 51)
+        ));
+}
+
+#[test]
+fn default_context_on_other_side() {
+    trunc()
+        .args(["-f", "1", "-l", "1", "-B", "1", "line 50$"])
+        .write_stdin(generate_lines(100))
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(lines(49, 53)))
+        .stdout(predicate::str::contains("line 48\n").not())
+        .stdout(predicate::str::contains("line 54\n").not());
+}
+
+#[test]
+fn long_flags() {
+    trunc()
+        .args(["-f", "1", "-l", "1"])
+        .args(["--before-context", "1", "--after-context", "1", "line 50$"])
+        .write_stdin(generate_lines(100))
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(lines(49, 51)))
+        .stdout(predicate::str::contains("line 52\n").not());
+}
+
+#[test]
+fn after_context_runs_into_next_match() {
+    // 50's after context reaches 53, which merges into 58's group with no
+    // marker once they touch
+    trunc()
+        .args(["-f", "1", "-l", "1", "-B", "1", "-A", "3"])
+        .arg("line (50|53|58)$")
+        .write_stdin(generate_lines(100))
+        .assert()
+        .success()
+        .stdout(format!(
+            "line 1\n[... 47 lines truncated, match 1 shown ...]\n{}[... 38 lines truncated ...]\nline 100\n",
+            lines(49, 61)
+        ));
+}
+
+#[test]
+fn overrides_preset_context() {
+    let input = generate_lines(100).replace("line 50\n", "error: boom\n");
+
+    trunc()
+        .args(["--preset", "cargo", "-f", "1", "-l", "1", "-A", "0"])
+        .write_stdin(input)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(format!(
+            "{}error: boom\n[...",
+            lines(44, 49)
+        )));
+}
@@ -0,0 +1,122 @@
+//! Tests for `--context-until REGEX`: each match's "after" context runs up
+//! to the next line matching the terminator, instead of a fixed count.
+
+mod common;
+
+use common::trunc;
+use predicates::prelude::*;
+
+/// Records separated by `----`, the second an error with a 6-line trace.
+fn build_log() -> String {
+    let mut log = String::from("start\n----\nrequest ok\n----\nERROR request failed\n");
+    for i in 1..=6 {
+        log.push_str(&format!("  at frame {}\n", i));
+    }
+    log.push_str("----\nrequest ok\n----\nend\n");
+    log
+}
+
+#[test]
+fn context_runs_to_terminator() {
+    trunc()
+        .args([
+            "-f",
+            "0",
+            "-l",
+            "0",
+            "-B",
+            "0",
+            "--context-until",
+            "^----",
+            "ERROR",
+        ])
+        .write_stdin(build_log())
+        .assert()
+        .success()
+        .stdout(
+            "[... 4 lines truncated, match 1 shown ...]\n\
+             ERROR request failed\n  at frame 1\n  at frame 2\n  at frame 3\n\
+             \x20 at frame 4\n  at frame 5\n  at frame 6\n\
+             [... 4 lines truncated ...]\n",
+        );
+}
+
+#[test]
+fn terminator_right_after_match() {
+    trunc()
+        .args([
+            "-f",
+            "0",
+            "-l",
+            "0",
+            "-B",
+            "0",
+            "--context-until",
+            "^$",
+            "ERROR",
+        ])
+        .write_stdin("a\nERROR one\n\nb\nc\n")
+        .assert()
+        .success()
+        .stdout(
+            "[... 1 lines truncated, match 1 shown ...]\nERROR one\n[... 3 lines truncated ...]\n",
+        );
+}
+
+#[test]
+fn match_inside_context_starts_its_own() {
+    trunc()
+        .args([
+            "-f",
+            "0",
+            "-l",
+            "0",
+            "-B",
+            "0",
+            "--context-until",
+            "^----",
+            "ERROR",
+        ])
+        .write_stdin("ERROR a\nx\nERROR b\ny\n----\nz\n")
+        .assert()
+        .success()
+        .stdout(
+            "[... 0 lines truncated, match 1 shown ...]\n\
+             ERROR a\nx\nERROR b\ny\n\
+             [... 2 lines truncated ...]\n",
+        );
+}
+
+#[test]
+fn capped_without_terminator() {
+    let input: String = std::iter::once("ERROR\n".to_string())
+        .chain((1..=100).map(|i| format!("line {}\n", i)))
+        .collect();
+    trunc()
+        .args(["-f", "0", "-l", "0", "--context-until", "^----", "ERROR"])
+        .write_stdin(input)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "line 50\n[... 50 lines truncated ...]\n",
+        ));
+}
+
+#[test]
+fn conflicts_with_after_context() {
+    trunc()
+        .args(["-A", "2", "--context-until", "^$", "ERROR"])
+        .write_stdin("")
+        .assert()
+        .failure();
+}
+
+#[test]
+fn invalid_regex_rejected() {
+    trunc()
+        .args(["--context-until", "(", "ERROR"])
+        .write_stdin("")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Invalid regex"));
+}
@@ -0,0 +1,138 @@
+//! Tests for `-c/--count`: head, one summary marker counting the matches in
+//! the middle, and tail, with no match bodies.
+
+mod common;
+
+use common::{generate_lines_with_matches, trunc};
+use predicates::prelude::*;
+use std::io::Write;
+use tempfile::NamedTempFile;
+
+#[test]
+fn summary_instead_of_matches() {
+    let input = generate_lines_with_matches(100, &[20, 40, 60], "ERROR");
+
+    trunc()
+        .args(["-f", "2", "-l", "2", "-c", "ERROR"])
+        .write_stdin(input)
+        .assert()
+        .success()
+        .stdout(
+            "line 1\nline 2\n\
+             [... 96 lines, 3 matches of 'ERROR' ...]\n\
+             line 99\nline 100\n",
+        );
+}
+
+#[test]
+fn counts_past_match_limit() {
+    let at: Vec<usize> = (10..=90).collect();
+    let input = generate_lines_with_matches(100, &at, "ERROR");
+
+    trunc()
+        .args(["-f", "2", "-l", "2", "-m", "1", "--count", "ERROR"])
+        .write_stdin(input)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "[... 96 lines, 81 matches of 'ERROR' ...]\n",
+        ));
+}
+
+#[test]
+fn no_matches() {
+    trunc()
+        .args(["-f", "2", "-l", "2", "-c", "ERROR"])
+        .write_stdin(generate_lines_with_matches(100, &[], ""))
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "[... 96 lines, 0 matches of 'ERROR' ...]\n",
+        ));
+}
+
+#[test]
+fn head_and_tail_matches_not_counted() {
+    let input = generate_lines_with_matches(100, &[1, 50, 100], "ERROR");
+
+    trunc()
+        .args(["-f", "2", "-l", "2", "-c", "ERROR"])
+        .write_stdin(input)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "[... 96 lines, 1 match of 'ERROR', 1 match in head, 1 match in tail ...]\n",
+        ));
+}
+
+#[test]
+fn lists_every_pattern() {
+    let input =
+        generate_lines_with_matches(100, &[30, 60], "ERROR").replace("line 45\n", "line 45 WARN\n");
+
+    trunc()
+        .args(["-f", "2", "-l", "2", "-c", "-e", "ERROR", "-e", "WARN"])
+        .write_stdin(input)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "[... 96 lines, 3 matches of 'ERROR', 'WARN' ...]\n",
+        ));
+}
+
+#[test]
+fn fixed_strings() {
+    let input = generate_lines_with_matches(100, &[50], "a.b");
+
+    trunc()
+        .args(["-f", "2", "-l", "2", "-c", "--fixed-string", "a.b"])
+        .write_stdin(input)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "[... 96 lines, 1 match of 'a.b' ...]\n",
+        ));
+}
+
+#[test]
+fn counts_each_file() {
+    let mut a = NamedTempFile::new().unwrap();
+    write!(a, "{}", generate_lines_with_matches(50, &[25], "ERROR")).unwrap();
+    let mut b = NamedTempFile::new().unwrap();
+    write!(b, "{}", generate_lines_with_matches(50, &[10, 20], "ERROR")).unwrap();
+
+    trunc()
+        .args(["-f", "1", "-l", "1", "-c", "-e", "ERROR"])
+        .arg(a.path())
+        .arg(b.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "[... 48 lines, 1 match of 'ERROR' ...]\n",
+        ))
+        .stdout(predicate::str::contains(
+            "[... 48 lines, 2 matches of 'ERROR' ...]\n",
+        ));
+}
+
+#[test]
+fn short_input_unchanged() {
+    let input = generate_lines_with_matches(10, &[5], "ERROR");
+
+    trunc()
+        .args(["-c", "ERROR"])
+        .write_stdin(input.clone())
+        .assert()
+        .success()
+        .stdout(format!("{}\n", input));
+}
+
+#[test]
+fn not_with_rank_by() {
+    trunc()
+        .args(["-c", "--rank-by", "severity", "ERROR"])
+        .write_stdin("x\n")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("--rank-by"));
+}
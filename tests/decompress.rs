@@ -0,0 +1,146 @@
+//! Tests for compressed inputs: files ending .gz, .zst or .xz are read
+//! through their decoder, and with `--decompress` so is anything whose first
+//! bytes say it's compressed. They need every decoder built in (the
+//! default features).
+
+#![cfg(all(feature = "gzip", feature = "zstd", feature = "xz"))]
+
+mod common;
+
+use common::trunc;
+use predicates::prelude::*;
+use std::io::Write;
+use tempfile::TempDir;
+
+/// Lines `line 1` to `line 100`.
+fn build_input() -> String {
+    (1..=100).map(|i| format!("line {}\n", i)).collect()
+}
+
+/// What `-f 2 -l 2` shows of [`build_input`].
+const EXPECTED: &str = "line 1\nline 2\n[... 96 lines truncated ...]\nline 99\nline 100\n";
+
+fn gzip(data: &[u8]) -> Vec<u8> {
+    let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+    encoder.write_all(data).unwrap();
+    encoder.finish().unwrap()
+}
+
+/// `data` written to `name` in a new directory.
+fn write_file(name: &str, data: &[u8]) -> (TempDir, String) {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join(name);
+    std::fs::File::create(&path)
+        .unwrap()
+        .write_all(data)
+        .unwrap();
+    (dir, path.to_str().unwrap().to_string())
+}
+
+#[test]
+fn gzip_by_suffix() {
+    let (_dir, path) = write_file("app.log.gz", &gzip(build_input().as_bytes()));
+    trunc()
+        .args(["-f", "2", "-l", "2", "--files", &path])
+        .assert()
+        .success()
+        .stdout(EXPECTED);
+}
+
+#[test]
+fn zstd_by_suffix() {
+    let data = zstd::encode_all(build_input().as_bytes(), 0).unwrap();
+    let (_dir, path) = write_file("app.log.zst", &data);
+    trunc()
+        .args(["-f", "2", "-l", "2", "--files", &path])
+        .assert()
+        .success()
+        .stdout(EXPECTED);
+}
+
+#[test]
+fn xz_by_suffix() {
+    let mut encoder = xz2::write::XzEncoder::new(Vec::new(), 6);
+    encoder.write_all(build_input().as_bytes()).unwrap();
+    let (_dir, path) = write_file("app.log.xz", &encoder.finish().unwrap());
+    trunc()
+        .args(["-f", "2", "-l", "2", "--files", &path])
+        .assert()
+        .success()
+        .stdout(EXPECTED);
+}
+
+#[test]
+fn pattern_as_operand() {
+    let (_dir, path) = write_file("app.log.gz", &gzip(build_input().as_bytes()));
+    trunc()
+        .args(["-f", "0", "-l", "0", "-C", "0", "line 50$", &path])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("\nline 50\n"));
+}
+
+#[test]
+fn concatenated_streams() {
+    let input = build_input();
+    let (first, second) = input.split_at(input.find("line 51\n").unwrap());
+    let mut data = gzip(first.as_bytes());
+    data.extend(gzip(second.as_bytes()));
+    let (_dir, path) = write_file("app.log.gz", &data);
+    trunc()
+        .args(["-f", "2", "-l", "2", "--files", &path])
+        .assert()
+        .success()
+        .stdout(EXPECTED);
+}
+
+#[test]
+fn stdin_sniffed_with_flag() {
+    trunc()
+        .args(["--decompress", "-f", "2", "-l", "2"])
+        .write_stdin(gzip(build_input().as_bytes()))
+        .assert()
+        .success()
+        .stdout(EXPECTED);
+}
+
+#[test]
+fn other_names_sniffed_with_flag() {
+    let (_dir, path) = write_file("app.log.1", &gzip(build_input().as_bytes()));
+    trunc()
+        .args(["--decompress", "-f", "2", "-l", "2", "--files", &path])
+        .assert()
+        .success()
+        .stdout(EXPECTED);
+}
+
+#[test]
+fn plain_input_untouched_by_flag() {
+    trunc()
+        .args(["--decompress", "-f", "2", "-l", "2"])
+        .write_stdin(build_input())
+        .assert()
+        .success()
+        .stdout(EXPECTED);
+}
+
+#[test]
+fn percentage_sizes_count_decompressed_lines() {
+    let (_dir, path) = write_file("app.log.gz", &gzip(build_input().as_bytes()));
+    trunc()
+        .args(["-f", "2%", "-l", "2%", "--files", &path])
+        .assert()
+        .success()
+        .stdout(EXPECTED);
+}
+
+#[test]
+fn corrupt_data_error_names_file() {
+    let data = gzip(build_input().as_bytes());
+    let (_dir, path) = write_file("app.log.gz", &data[..data.len() / 2]);
+    trunc()
+        .args(["--files", &path])
+        .assert()
+        .failure()
+        .stderr(predicate::str::starts_with(format!("trunc: {}: ", path)));
+}
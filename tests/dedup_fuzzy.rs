@@ -0,0 +1,174 @@
+//! Tests for `--dedup-fuzzy`: runs of lines that differ only in
+//! timestamps, ids and numbers collapse into their first line with a count.
+
+mod common;
+
+use common::trunc;
+use predicates::prelude::*;
+
+/// `n` health checks with varying timestamps and latencies.
+fn health_checks(n: usize) -> String {
+    (0..n)
+        .map(|i| {
+            format!(
+                "2024-05-01T12:{:02}:{:02}Z GET /health 200 {}ms",
+                i / 60 % 60,
+                i % 60,
+                i % 17
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[test]
+fn collapses_run_with_count() {
+    let input = format!("start\n{}\nend", health_checks(200));
+
+    trunc()
+        .arg("--dedup-fuzzy")
+        .write_stdin(input)
+        .assert()
+        .success()
+        .stdout(
+            "start\n\
+             2024-05-01T12:00:00Z GET /health 200 0ms\n\
+             [... 200 similar lines ...]\n\
+             end\n",
+        );
+}
+
+#[test]
+fn off_by_default() {
+    trunc()
+        .args(["-f", "5", "-l", "5"])
+        .write_stdin(health_checks(20))
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("[... 10 lines truncated ...]"));
+}
+
+#[test]
+fn hex_ids_and_uuids_ignored() {
+    let input = "req 0x1f failed id=550e8400-e29b-41d4-a716-446655440000\n\
+                 req 0xa3b failed id=6ba7b810-9dad-11d1-80b4-00c04fd430c8\n\
+                 commit 3f2a9c1d8e ok\n\
+                 commit 9b8e7d6c5a ok";
+
+    trunc()
+        .arg("--dedup-fuzzy")
+        .write_stdin(input)
+        .assert()
+        .success()
+        .stdout(
+            "req 0x1f failed id=550e8400-e29b-41d4-a716-446655440000\n\
+             [... 2 similar lines ...]\n\
+             commit 3f2a9c1d8e ok\n\
+             [... 2 similar lines ...]\n",
+        );
+}
+
+#[test]
+fn only_consecutive_lines_collapse() {
+    trunc()
+        .arg("--dedup-fuzzy")
+        .write_stdin("tick 1\ntock\ntick 2\n")
+        .assert()
+        .success()
+        .stdout("tick 1\ntock\ntick 2\n");
+}
+
+#[test]
+fn different_text_not_collapsed() {
+    trunc()
+        .arg("--dedup-fuzzy")
+        .write_stdin("GET /health 200\nGET /login 200\n")
+        .assert()
+        .success()
+        .stdout("GET /health 200\nGET /login 200\n");
+}
+
+#[test]
+fn run_counts_as_one_line() {
+    // 3 lines before, a run of 100, 3 lines after: 7 lines fits -f 4 -l 4
+    let input = format!("a\nb\nc\n{}\nx\ny\nz", health_checks(100));
+
+    trunc()
+        .args(["-f", "4", "-l", "4", "--dedup-fuzzy"])
+        .write_stdin(input)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("truncated").not())
+        .stdout(predicate::str::contains("[... 100 similar lines ...]\nx\n"));
+}
+
+#[test]
+fn collapsed_line_can_match() {
+    let input = format!(
+        "{}\n{}\n{}",
+        (1..=50)
+            .map(|i| format!("line {}", i))
+            .collect::<Vec<_>>()
+            .join("\n"),
+        health_checks(30).replace("200", "503"),
+        (51..=100)
+            .map(|i| format!("line {}", i))
+            .collect::<Vec<_>>()
+            .join("\n"),
+    );
+
+    trunc()
+        .args(["-f", "1", "-l", "1", "-C", "0", "--dedup-fuzzy", "503"])
+        .write_stdin(input)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "match 1 shown ...]\n2024-05-01T12:00:00Z GET /health 503 0ms\n[... 30 similar lines ...]\n",
+        ))
+        .stdout(predicate::str::contains("(30 total)").not());
+}
+
+#[test]
+fn extra_strip_rule() {
+    trunc()
+        .args(["--dedup-fuzzy", "--dedup-strip", "user=[a-z]+"])
+        .write_stdin("login user=alice\nlogin user=bob\n")
+        .assert()
+        .success()
+        .stdout("login user=alice\n[... 2 similar lines ...]\n");
+}
+
+#[test]
+fn strip_requires_dedup_fuzzy() {
+    trunc()
+        .args(["--dedup-strip", "x"])
+        .write_stdin("x\n")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("--dedup-fuzzy"));
+}
+
+#[test]
+fn collapsing_counts_as_truncation_for_status() {
+    trunc()
+        .args(["--dedup-fuzzy", "--status"])
+        .write_stdin("a 1\na 2\n")
+        .assert()
+        .code(3);
+}
+
+#[test]
+fn count_goes_to_marker_sink() {
+    trunc()
+        .args([
+            "--dedup-fuzzy",
+            "--markers-to-stderr",
+            "--markers",
+            "compact",
+        ])
+        .write_stdin("start\nretry 1\nretry 2\nretry 3\nend\n")
+        .assert()
+        .success()
+        .stdout("start\nretry 1\nend\n")
+        .stderr("\u{2026}3L similar\u{2026}\n");
+}
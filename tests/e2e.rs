@@ -3,35 +3,10 @@
 //! These tests spawn the actual binary and verify its behavior.
 //! They test observable behavior only - no internal knowledge.
 
-use assert_cmd::Command;
-use predicates::prelude::*;
-
-/// Helper to create a Command for the trunc binary.
-fn trunc() -> Command {
-    Command::cargo_bin("trunc").unwrap()
-}
+mod common;
 
-/// Generate N lines of input: "line 1\nline 2\n..."
-fn generate_lines(n: usize) -> String {
-    (1..=n)
-        .map(|i| format!("line {}", i))
-        .collect::<Vec<_>>()
-        .join("\n")
-}
-
-/// Generate N lines with a specific pattern at certain positions.
-fn generate_lines_with_matches(n: usize, match_at: &[usize], pattern: &str) -> String {
-    (1..=n)
-        .map(|i| {
-            if match_at.contains(&i) {
-                format!("line {} contains {}", i, pattern)
-            } else {
-                format!("line {}", i)
-            }
-        })
-        .collect::<Vec<_>>()
-        .join("\n")
-}
+use common::{generate_lines, generate_lines_with_matches, trunc};
+use predicates::prelude::*;
 
 // =============================================================================
 // BASIC TRUNCATION (NO PATTERN)
@@ -1244,21 +1219,19 @@ mod output_size {
 // =============================================================================
 
 mod streaming {
+    use crate::common::trunc_bin;
     use std::io::{BufRead, BufReader, Write};
     use std::process::{Command, Stdio};
     use std::sync::mpsc;
     use std::time::Duration;
 
-    /// Get path to the trunc binary
-    fn trunc_bin() -> std::path::PathBuf {
-        assert_cmd::cargo::cargo_bin("trunc")
-    }
-
     #[test]
     fn first_lines_stream_immediately() {
         // Spawn trunc and feed it lines slowly
         // The first 30 lines should appear on stdout BEFORE we send more input
+        // (a pipe isn't a terminal, so flushing each line is asked for)
         let mut child = Command::new(trunc_bin())
+            .args(["--flush", "line"])
             .stdin(Stdio::piped())
             .stdout(Stdio::piped())
             .spawn()
@@ -1271,10 +1244,8 @@ mod streaming {
         let (tx, rx) = mpsc::channel();
         std::thread::spawn(move || {
             let reader = BufReader::new(stdout);
-            for line in reader.lines() {
-                if let Ok(l) = line {
-                    let _ = tx.send(l);
-                }
+            for l in reader.lines().map_while(Result::ok) {
+                let _ = tx.send(l);
             }
         });
 
@@ -1316,7 +1287,7 @@ mod streaming {
         // In pattern mode, matches should stream as they're found
         // We verify by checking output arrives BEFORE stdin is closed
         let mut child = Command::new(trunc_bin())
-            .arg("ERROR")
+            .args(["--flush", "line", "ERROR"])
             .stdin(Stdio::piped())
             .stdout(Stdio::piped())
             .spawn()
@@ -1329,10 +1300,8 @@ mod streaming {
         let (tx, rx) = mpsc::channel();
         std::thread::spawn(move || {
             let reader = BufReader::new(stdout);
-            for line in reader.lines() {
-                if let Ok(l) = line {
-                    let _ = tx.send(l);
-                }
+            for l in reader.lines().map_while(Result::ok) {
+                let _ = tx.send(l);
             }
         });
 
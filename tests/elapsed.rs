@@ -0,0 +1,108 @@
+//! Tests for `--elapsed`: every truncation marker notes how long the lines
+//! it hides took to arrive.
+
+mod common;
+
+use common::{generate_terminated_lines, run, trunc};
+use regex::Regex;
+
+/// The spans named by the markers in `stdout`, in seconds.
+fn spans(stdout: &str) -> Vec<f64> {
+    let re = Regex::new(r"\(over (?:(\d+)ms|(\d+\.\d)s|(\d+)m(\d\d)s)\)").unwrap();
+    re.captures_iter(stdout)
+        .map(|c| {
+            if let Some(ms) = c.get(1) {
+                ms.as_str().parse::<f64>().unwrap() / 1000.0
+            } else if let Some(s) = c.get(2) {
+                s.as_str().parse().unwrap()
+            } else {
+                c[3].parse::<f64>().unwrap() * 60.0 + c[4].parse::<f64>().unwrap()
+            }
+        })
+        .collect()
+}
+
+#[test]
+fn marker_notes_time_span() {
+    let stdout = run(
+        &["-f", "2", "-l", "2", "--elapsed"],
+        generate_terminated_lines(100),
+    );
+    let re = Regex::new(
+        r"^line 1\nline 2\n\[\.\.\. 96 lines truncated \(over \d+ms\) \.\.\.\]\nline 99\nline 100\n$",
+    )
+    .unwrap();
+    assert!(re.is_match(&stdout), "{}", stdout);
+}
+
+#[test]
+fn span_covers_pause_in_hidden_lines() {
+    let output = trunc()
+        .args(["-f", "1", "-l", "1", "--elapsed", "--"])
+        .args(["sh", "-c", "seq 1 3; sleep 1.5; seq 4 6"])
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let spans = spans(&stdout);
+    assert_eq!(spans.len(), 1, "{}", stdout);
+    assert!(spans[0] >= 1.4, "{}", stdout);
+}
+
+#[test]
+fn pause_outside_hidden_lines_not_counted() {
+    // The pause is before line 2, the first hidden line
+    let output = trunc()
+        .args(["-f", "1", "-l", "1", "--elapsed", "--"])
+        .args(["sh", "-c", "echo 1; sleep 1.5; seq 2 6"])
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let spans = spans(&stdout);
+    assert_eq!(spans.len(), 1, "{}", stdout);
+    assert!(spans[0] < 1.0, "{}", stdout);
+}
+
+#[test]
+fn every_marker_gets_a_span() {
+    let stdout = run(
+        &[
+            "-f",
+            "2",
+            "-l",
+            "2",
+            "-e",
+            "line 50$",
+            "-C",
+            "1",
+            "--elapsed",
+        ],
+        generate_terminated_lines(100),
+    );
+    assert_eq!(spans(&stdout).len(), 2, "{}", stdout);
+}
+
+#[test]
+fn no_note_without_flag() {
+    let stdout = run(&["-f", "2", "-l", "2"], generate_terminated_lines(100));
+    assert!(!stdout.contains("over"), "{}", stdout);
+}
+
+#[test]
+fn works_with_byte_ranges() {
+    let stdout = run(
+        &["-f", "2", "-l", "2", "--elapsed", "--byte-ranges"],
+        generate_terminated_lines(100),
+    );
+    let re = Regex::new(r"truncated \(over \d+ms\) \(bytes 14-775\) \.\.\.\]").unwrap();
+    assert!(re.is_match(&stdout), "{}", stdout);
+}
+
+#[test]
+fn fits_byte_budget() {
+    let stdout = run(
+        &["--max-bytes", "200", "--elapsed"],
+        generate_terminated_lines(1000),
+    );
+    assert!(stdout.len() <= 200, "{}", stdout);
+    assert_eq!(spans(&stdout).len(), 1, "{}", stdout);
+}
@@ -0,0 +1,115 @@
+//! Tests for `--engine`: the regex engine the patterns are compiled with.
+//!
+//! The default engine (the regex crate) rejects look-around and
+//! backreferences; `--engine pcre2`, in builds with the `pcre2` feature,
+//! accepts them, and its matches are highlighted and grouped like any
+//! other's.
+
+mod common;
+
+#[cfg(feature = "pcre2")]
+use common::run;
+use common::trunc;
+
+/// Filler lines around `middle`, so the middle is truncated.
+#[cfg(feature = "pcre2")]
+fn surround(middle: &[&str]) -> String {
+    let filler = |range: std::ops::RangeInclusive<usize>| -> String {
+        range.map(|i| format!("line {}\n", i)).collect()
+    };
+    let middle: String = middle.iter().map(|l| format!("{}\n", l)).collect();
+    filler(1..=50) + &middle + &filler(51..=100)
+}
+
+#[test]
+fn default_engine_rejects_look_ahead() {
+    trunc()
+        .arg("foo(?!bar)")
+        .write_stdin("foo\n")
+        .assert()
+        .failure()
+        .stderr(predicates::str::contains("look-around"));
+}
+
+#[test]
+fn engine_conflicts_with_fixed_string() {
+    trunc()
+        .args(["--engine", "rust", "--fixed-string", "x"])
+        .write_stdin("x\n")
+        .assert()
+        .failure();
+}
+
+#[cfg(not(feature = "pcre2"))]
+#[test]
+fn pcre2_without_the_feature_is_an_error() {
+    trunc()
+        .args(["--engine", "pcre2", "x"])
+        .write_stdin("x\n")
+        .assert()
+        .failure()
+        .stderr(predicates::str::contains("cargo feature `pcre2`"));
+}
+
+#[cfg(feature = "pcre2")]
+#[test]
+fn pcre2_matches_look_ahead_and_look_behind() {
+    let input = surround(&["foobar", "foo=1", "price $5", "cost 5"]);
+    let out = run(
+        &["--engine", "pcre2", "-C", "0", r"foo(?!bar)|(?<=\$)5"],
+        &input,
+    );
+    assert!(out.contains("\nfoo=1\n"), "Got: {}", out);
+    assert!(out.contains("\nprice $5\n"), "Got: {}", out);
+    assert!(!out.contains("foobar"));
+    assert!(!out.contains("cost 5"));
+}
+
+#[cfg(feature = "pcre2")]
+#[test]
+fn pcre2_matches_backreferences() {
+    let input = surround(&["retry retry", "retry once"]);
+    let out = run(&["--engine", "pcre2", "-C", "0", r"(\w+) \1"], &input);
+    assert!(out.contains("\nretry retry\n"), "Got: {}", out);
+    assert!(!out.contains("retry once"));
+}
+
+#[cfg(feature = "pcre2")]
+#[test]
+fn pcre2_matches_are_highlighted() {
+    let input = surround(&["a foo b", "a foobar b"]);
+    let out = run(
+        &[
+            "--engine",
+            "pcre2",
+            "--color",
+            "always",
+            "-C",
+            "0",
+            "foo(?!bar)",
+        ],
+        &input,
+    );
+    assert!(out.contains("a \x1b[1;31mfoo\x1b[0m b"), "Got: {:?}", out);
+}
+
+#[cfg(feature = "pcre2")]
+#[test]
+fn pcre2_captures_group_unique_matches() {
+    let input = surround(&["E1 code=7", "E2 code=7", "E3 code=9"]);
+    let out = run(
+        &[
+            "--engine",
+            "pcre2",
+            "-C",
+            "0",
+            "--unique-by",
+            "${code}",
+            r"code=(?<code>\d)(?!\d)",
+        ],
+        &input,
+    );
+    assert!(out.contains("E1 code=7"));
+    assert!(!out.contains("E2 code=7"));
+    assert!(out.contains("E3 code=9"));
+}
@@ -0,0 +1,80 @@
+//! Tests for `--every N`: every Nth input line from the middle, numbered,
+//! for a skeleton of a long output.
+
+mod common;
+
+use common::{generate_lines, trunc};
+use predicates::prelude::*;
+
+#[test]
+fn checkpoints_between_head_and_tail() {
+    trunc()
+        .args(["-f", "2", "-l", "2", "--every", "30"])
+        .write_stdin(generate_lines(100))
+        .assert()
+        .success()
+        .stdout(
+            "line 1\nline 2\n\
+             [... 27 lines truncated ...]\n    30\u{2502} line 30\n\
+             [... 29 lines truncated ...]\n    60\u{2502} line 60\n\
+             [... 29 lines truncated ...]\n    90\u{2502} line 90\n\
+             [... 8 lines truncated ...]\nline 99\nline 100\n",
+        );
+}
+
+#[test]
+fn head_lines_not_repeated() {
+    trunc()
+        .args(["-f", "5", "-l", "0", "--every", "2"])
+        .write_stdin(generate_lines(8))
+        .assert()
+        .success()
+        .stdout(
+            "line 1\nline 2\nline 3\nline 4\nline 5\n     6\u{2502} line 6\n\
+             [... 1 lines truncated ...]\n     8\u{2502} line 8\n",
+        );
+}
+
+#[test]
+fn numbers_count_excluded_lines() {
+    trunc()
+        .args([
+            "-f",
+            "0",
+            "-l",
+            "0",
+            "--every",
+            "5",
+            "--exclude",
+            "line [1-3]$",
+        ])
+        .write_stdin(generate_lines(10))
+        .assert()
+        .success()
+        .stdout(
+            "[... 1 lines truncated ...]\n     5\u{2502} line 5\n\
+             [... 4 lines truncated ...]\n    10\u{2502} line 10\n\
+             [... 3 lines excluded ...]\n",
+        );
+}
+
+#[test]
+fn with_a_pattern() {
+    trunc()
+        .args(["-f", "0", "-l", "0", "-C", "0", "--every", "50", "line 75$"])
+        .write_stdin(generate_lines(100))
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("    50\u{2502} line 50\n"))
+        .stdout(predicate::str::contains("match 1 shown ...]\nline 75\n"));
+}
+
+#[test]
+fn zero_rejected() {
+    trunc()
+        .args(["--every", "0"])
+        .write_stdin("")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("at least 1"));
+}
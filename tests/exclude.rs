@@ -0,0 +1,150 @@
+//! Tests for `--exclude`: dropping noise lines before head, tail, matches
+//! and budgets see them, with a marker counting what was dropped.
+
+mod common;
+
+use common::trunc;
+use predicates::prelude::*;
+use std::io::Write;
+use tempfile::NamedTempFile;
+
+/// A build log: 100 "Compiling crate N" lines, each after a "step N" line.
+fn build_log() -> String {
+    (1..=100)
+        .map(|i| format!("step {}\n   Compiling crate{} v0.1.0", i, i))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[test]
+fn excluded_lines_not_shown() {
+    trunc()
+        .args(["-f", "2", "-l", "2", "--exclude", "Compiling"])
+        .write_stdin(build_log())
+        .assert()
+        .success()
+        .stdout(
+            "step 1\nstep 2\n\
+             [... 100 lines excluded ...]\n\
+             [... 96 lines truncated ...]\n\
+             step 99\nstep 100\n",
+        );
+}
+
+#[test]
+fn repeatable() {
+    trunc()
+        .args(["-f", "1", "-l", "1"])
+        .args(["--exclude", "Compiling", "--exclude", r"step \d*[13579]$"])
+        .write_stdin(build_log())
+        .assert()
+        .success()
+        .stdout(
+            "step 2\n\
+             [... 150 lines excluded ...]\n\
+             [... 48 lines truncated ...]\n\
+             step 100\n",
+        );
+}
+
+#[test]
+fn short_after_exclusion() {
+    // 20 lines, 10 left: nothing truncated, only the exclusion marker
+    let input = (1..=10)
+        .map(|i| format!("keep {}\nnoise {}", i, i))
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    trunc()
+        .args(["-f", "5", "-l", "5", "--exclude", "noise"])
+        .write_stdin(input)
+        .assert()
+        .success()
+        .stdout(
+            "keep 1\nkeep 2\nkeep 3\nkeep 4\nkeep 5\n\
+             [... 10 lines excluded ...]\n\
+             keep 6\nkeep 7\nkeep 8\nkeep 9\nkeep 10\n",
+        );
+}
+
+#[test]
+fn excluded_lines_are_not_matches() {
+    trunc()
+        .args(["-f", "1", "-l", "1", "-C", "0", "--exclude", "Compiling"])
+        .arg("crate50|step 50$")
+        .write_stdin(build_log())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("match 1 shown ...]\nstep 50\n"))
+        .stdout(predicate::str::contains("crate50").not())
+        .stdout(predicate::str::contains("(2 total)").not());
+}
+
+#[test]
+fn excluded_lines_not_in_context() {
+    trunc()
+        .args(["-f", "1", "-l", "1", "-C", "1", "--exclude", "Compiling"])
+        .arg("step 50$")
+        .write_stdin(build_log())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("step 49\nstep 50\nstep 51\n"));
+}
+
+#[test]
+fn excluded_lines_cost_no_bytes() {
+    // Without --exclude the Compiling lines would fill the byte limit
+    trunc()
+        .args(["-f", "5", "-l", "0", "--max-bytes", "150"])
+        .args(["--exclude", "Compiling"])
+        .write_stdin(build_log())
+        .assert()
+        .success()
+        .stdout(
+            "step 1\nstep 2\nstep 3\nstep 4\nstep 5\n\
+             [... 100 lines excluded ...]\n\
+             [... 95 lines truncated ...]\n",
+        );
+}
+
+#[test]
+fn counted_per_file() {
+    let mut a = NamedTempFile::new().unwrap();
+    write!(a, "a\nnoise\nb").unwrap();
+    let mut b = NamedTempFile::new().unwrap();
+    write!(b, "c\nnoise\nnoise\nd").unwrap();
+
+    trunc()
+        .args(["--exclude", "noise"])
+        .arg("--files")
+        .arg(a.path())
+        .arg(b.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "a\nb\n[... 1 lines excluded ...]\n",
+        ))
+        .stdout(predicate::str::contains(
+            "c\nd\n[... 2 lines excluded ...]\n",
+        ));
+}
+
+#[test]
+fn invalid_regex_rejected() {
+    trunc()
+        .args(["--exclude", "("])
+        .write_stdin("x\n")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Invalid regex"));
+}
+
+#[test]
+fn gates_report_input_line_numbers() {
+    trunc()
+        .args(["--exclude", "noise", "--forbid", "bad"])
+        .write_stdin("a\nnoise\nnoise\nbad\n")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("line 4: bad"));
+}
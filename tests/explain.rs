@@ -0,0 +1,126 @@
+//! Tests for `--explain`: a trace on stderr of why each line was shown,
+//! held for the tail or hidden.
+//!
+//! When output looks surprising, the trace says which budget decided it:
+//! the head filling up, a match limit, the tail buffer, or `--max-bytes`.
+
+mod common;
+
+use common::{generate_terminated_lines, trunc};
+
+/// Run trunc with `args`, returning (stdout, stderr).
+fn run(args: &[&str], input: &str) -> (String, String) {
+    let output = trunc().args(args).write_stdin(input).output().unwrap();
+    assert!(output.status.success());
+    (
+        String::from_utf8(output.stdout).unwrap(),
+        String::from_utf8(output.stderr).unwrap(),
+    )
+}
+
+#[test]
+fn explain_leaves_stdout_unchanged() {
+    let input = generate_terminated_lines(500);
+    let (plain, quiet) = run(&["-f", "5", "-l", "5", "-e", "line 2"], &input);
+    let (explained, trace) = run(&["-f", "5", "-l", "5", "-e", "line 2", "--explain"], &input);
+    assert_eq!(plain, explained);
+    assert!(quiet.is_empty());
+    assert!(!trace.is_empty());
+    assert!(trace.lines().all(|line| line.starts_with("explain: ")));
+}
+
+#[test]
+fn explain_traces_head_marker_and_tail() {
+    let (_, trace) = run(
+        &["-f", "2", "-l", "2", "--explain"],
+        &generate_terminated_lines(20),
+    );
+    assert!(trace.contains("explain: line 1: shown in the head (1 of 2)\n"));
+    assert!(trace.contains("explain: line 2: shown in the head (2 of 2)\n"));
+    assert!(trace.contains("explain: line 3: held for the tail (1 of 2 lines)\n"));
+    assert!(
+        trace.contains("explain: line 5: held for the tail (2 of 2 lines), which drops line 3\n")
+    );
+    assert!(trace.contains("explain: lines 3-18: hidden, under a marker\n"));
+    assert!(trace.ends_with("explain: lines 19-20: shown as the tail\n"));
+}
+
+#[test]
+fn explain_says_why_matches_are_not_shown() {
+    let input = "ok\nERROR a\nok\nERROR b\nok\nERROR c\nok\nok\nok\nok\nok\nok\n";
+    let (_, trace) = run(
+        &[
+            "-f",
+            "0",
+            "-l",
+            "1",
+            "-m",
+            "2",
+            "-C",
+            "0",
+            "ERROR",
+            "--explain",
+        ],
+        input,
+    );
+    assert!(trace.contains("explain: line 2: match 1, shown\n"));
+    assert!(trace.contains("explain: line 4: match 2, shown\n"));
+    assert!(trace.contains("explain: line 6: match 3, not shown: no match budget left (-m)\n"));
+}
+
+#[test]
+fn explain_names_the_context_shown_with_a_match() {
+    let (_, trace) = run(
+        &[
+            "-f",
+            "1",
+            "-l",
+            "1",
+            "-B",
+            "2",
+            "-A",
+            "1",
+            "line 10$",
+            "--explain",
+        ],
+        &generate_terminated_lines(30),
+    );
+    assert!(trace.contains("explain: lines 2-7: hidden, under a marker\n"));
+    assert!(trace.contains("explain: line 10: match 1, shown, after lines 8-9 as context\n"));
+    assert!(trace.contains("explain: line 11: shown as context after a match\n"));
+}
+
+#[test]
+fn explain_notes_the_byte_limit() {
+    let (_, trace) = run(
+        &["--max-bytes", "200", "--explain"],
+        &generate_terminated_lines(1000),
+    );
+    assert!(trace.contains("the head's share of --max-bytes is spent, so the head ends"));
+    assert!(trace.contains("tail lines fit what's left of --max-bytes"));
+}
+
+#[test]
+fn explain_notes_excluded_lines() {
+    let (_, trace) = run(
+        &["--exclude", "^debug", "--explain"],
+        "start\ndebug noise\nend\n",
+    );
+    assert!(trace.contains("explain: line 2: excluded by --exclude, and not counted\n"));
+}
+
+#[test]
+fn explain_notes_lines_skipped_unread() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("big.log");
+    std::fs::write(&path, generate_terminated_lines(1000)).unwrap();
+    let output = trunc()
+        .args(["-f", "2", "-l", "2", "--explain"])
+        .arg("--files")
+        .arg(&path)
+        .output()
+        .unwrap();
+    let trace = String::from_utf8(output.stderr).unwrap();
+    assert!(trace.contains("skipped unread"));
+    assert!(trace.contains("explain: lines 999-1000: shown as the tail\n"));
+}
@@ -0,0 +1,99 @@
+//! Tests for `--fields`: logfmt lines are cut down to the fields named, with
+//! a count of the rest.
+
+mod common;
+
+use common::trunc;
+use predicates::prelude::*;
+
+/// A logfmt line for request `i`.
+fn log_line(i: usize) -> String {
+    format!(
+        "ts=2024-05-01T12:00:{:02}Z level=info msg=\"request {} done\" path=/api/items/{} status=200 duration_ms={}",
+        i % 60,
+        i,
+        i,
+        i * 3
+    )
+}
+
+fn build_log(n: usize) -> String {
+    (1..=n).map(|i| log_line(i) + "\n").collect()
+}
+
+#[test]
+fn only_named_fields_kept() {
+    trunc()
+        .args(["--fields", "ts,level,msg"])
+        .write_stdin(build_log(1))
+        .assert()
+        .success()
+        .stdout("ts=2024-05-01T12:00:01Z level=info msg=\"request 1 done\" +3 fields\n");
+}
+
+#[test]
+fn fields_kept_in_line_order() {
+    trunc()
+        .args(["--fields", "status", "--fields", "ts"])
+        .write_stdin(build_log(1))
+        .assert()
+        .success()
+        .stdout("ts=2024-05-01T12:00:01Z status=200 +4 fields\n");
+}
+
+#[test]
+fn applies_to_head_matches_and_tail() {
+    trunc()
+        .args([
+            "--fields",
+            "msg",
+            "-f",
+            "1",
+            "-l",
+            "1",
+            "-C",
+            "0",
+            "status=200 duration_ms=150$",
+        ])
+        .write_stdin(build_log(100))
+        .assert()
+        .success()
+        .stdout(
+            "msg=\"request 1 done\" +5 fields\n\
+             [... 48 lines truncated, match 1 shown ...]\n\
+             msg=\"request 50 done\" +5 fields\n\
+             [... 49 lines truncated ...]\n\
+             msg=\"request 100 done\" +5 fields\n",
+        );
+}
+
+#[test]
+fn other_lines_untouched() {
+    let input = "Starting server on :8080\nlevel=warn msg=slow\n";
+    trunc()
+        .args(["--fields", "msg"])
+        .write_stdin(input)
+        .assert()
+        .success()
+        .stdout("Starting server on :8080\nmsg=slow +1 field\n");
+}
+
+#[test]
+fn quoted_values_with_spaces_and_quotes() {
+    let input = "msg=\"said \\\"hi there\\\"\" user=\"a b\" level=debug\n";
+    trunc()
+        .args(["--fields", "level"])
+        .write_stdin(input)
+        .assert()
+        .success()
+        .stdout("level=debug +2 fields\n");
+}
+
+#[test]
+fn off_by_default() {
+    trunc()
+        .write_stdin(build_log(1))
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("duration_ms=3"));
+}
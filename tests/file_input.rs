@@ -0,0 +1,253 @@
+//! Tests for reading input from files named on the command line.
+//!
+//! As with grep, the first operand is the pattern and the rest are files;
+//! `-e` names the pattern explicitly, and `--files` says there's none, so
+//! every operand is a file.
+
+mod common;
+
+use common::{generate_lines_with_matches, trunc};
+use predicates::prelude::*;
+use std::path::PathBuf;
+
+/// Write `contents` to `name` inside `dir`, returning the path.
+fn write_file(dir: &tempfile::TempDir, name: &str, contents: &str) -> PathBuf {
+    let path = dir.path().join(name);
+    std::fs::write(&path, contents).unwrap();
+    path
+}
+
+#[test]
+fn reads_file_instead_of_stdin() {
+    let dir = tempfile::tempdir().unwrap();
+    let log = write_file(&dir, "big.log", &generate_lines_with_matches(100, &[], ""));
+
+    trunc()
+        .args(["-f", "3", "-l", "3", "--files"])
+        .arg(&log)
+        .write_stdin("stdin should be ignored\n")
+        .assert()
+        .success()
+        .stdout(
+            "line 1\nline 2\nline 3\n[... 94 lines truncated ...]\nline 98\nline 99\nline 100\n",
+        );
+}
+
+#[test]
+fn first_operand_is_the_pattern_even_if_a_file() {
+    // A file named ERROR doesn't stop ERROR being the pattern
+    let dir = tempfile::tempdir().unwrap();
+    write_file(&dir, "ERROR", "not read\n");
+
+    trunc()
+        .current_dir(dir.path())
+        .args(["-f", "0", "-l", "0", "-C", "0", "ERROR"])
+        .write_stdin("a\nan ERROR\nb\n")
+        .assert()
+        .success()
+        .stdout(
+            "[... 1 lines truncated, match 1 shown ...]\nan ERROR\n[... 1 lines truncated ...]\n",
+        );
+}
+
+#[test]
+fn pattern_then_file() {
+    let dir = tempfile::tempdir().unwrap();
+    let log = write_file(
+        &dir,
+        "big.log",
+        &generate_lines_with_matches(100, &[50], "ERROR"),
+    );
+
+    trunc()
+        .args(["-f", "3", "-l", "3", "ERROR"])
+        .arg(&log)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("line 50 contains ERROR"));
+}
+
+#[test]
+fn explicit_pattern_flag() {
+    let dir = tempfile::tempdir().unwrap();
+    let log = write_file(
+        &dir,
+        "big.log",
+        &generate_lines_with_matches(100, &[50], "ERROR"),
+    );
+
+    trunc()
+        .args(["-f", "3", "-l", "3", "-e", "ERROR"])
+        .arg(&log)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("line 50 contains ERROR"));
+
+    // -e also works with stdin
+    trunc()
+        .args(["-f", "3", "-l", "3", "--pattern", "ERROR"])
+        .write_stdin(generate_lines_with_matches(100, &[50], "ERROR"))
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("line 50 contains ERROR"));
+}
+
+#[test]
+fn explicit_pattern_that_looks_like_a_file() {
+    // The pattern is the name of an existing file; -e disambiguates
+    let dir = tempfile::tempdir().unwrap();
+    let input = write_file(&dir, "input.log", "mentions notes.txt here\nnothing\n");
+    let notes = write_file(&dir, "notes.txt", "");
+
+    trunc()
+        .current_dir(dir.path())
+        .args(["-f", "0", "-l", "0", "-e", "notes.txt"])
+        .arg(&input)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("mentions notes.txt here"));
+    assert!(notes.exists());
+}
+
+#[test]
+fn dash_means_stdin() {
+    trunc()
+        .args(["ERROR", "-"])
+        .write_stdin("a\nb\n")
+        .assert()
+        .success()
+        .stdout("a\nb\n");
+}
+
+#[test]
+fn missing_file_after_pattern_is_an_error() {
+    trunc()
+        .args(["ERROR", "/nonexistent/big.log"])
+        .write_stdin("ERROR on stdin\n")
+        .assert()
+        .failure()
+        .stdout("")
+        .stderr(predicate::str::contains("/nonexistent/big.log"));
+}
+
+#[test]
+fn unreadable_file_is_an_error() {
+    trunc()
+        .args(["-e", "ERROR", "/nonexistent/big.log"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("/nonexistent/big.log"));
+}
+
+// =============================================================================
+// MULTIPLE FILES
+// =============================================================================
+
+mod multiple_files {
+    use super::*;
+
+    #[test]
+    fn each_file_gets_a_header_and_own_budget() {
+        let dir = tempfile::tempdir().unwrap();
+        let a = write_file(&dir, "a.log", &generate_lines_with_matches(10, &[], ""));
+        let b = write_file(&dir, "b.log", "only line\n");
+
+        let assert = trunc()
+            .args(["-f", "2", "-l", "2", "--files"])
+            .arg(&a)
+            .arg(&b)
+            .assert()
+            .success();
+
+        let stdout = String::from_utf8_lossy(&assert.get_output().stdout);
+        assert_eq!(
+            stdout,
+            format!(
+                "==> {} <==\nline 1\nline 2\n[... 6 lines truncated ...]\nline 9\nline 10\n\
+                 \n==> {} <==\nonly line\n",
+                a.display(),
+                b.display()
+            )
+        );
+    }
+
+    #[test]
+    fn match_budget_is_per_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let a = write_file(
+            &dir,
+            "a.log",
+            &generate_lines_with_matches(100, &[30, 60], "ERROR"),
+        );
+        let b = write_file(
+            &dir,
+            "b.log",
+            &generate_lines_with_matches(100, &[40, 70], "ERROR"),
+        );
+
+        let assert = trunc()
+            .args(["-f", "3", "-l", "3", "-m", "1", "-e", "ERROR"])
+            .arg(&a)
+            .arg(&b)
+            .assert()
+            .success();
+
+        let stdout = String::from_utf8_lossy(&assert.get_output().stdout);
+        assert_eq!(
+            stdout.matches("match 1/1 shown").count(),
+            2,
+            "Got:\n{}",
+            stdout
+        );
+        assert!(stdout.contains("line 30 contains ERROR"));
+        assert!(stdout.contains("line 40 contains ERROR"));
+        assert!(!stdout.contains("line 60 contains ERROR"));
+        assert_eq!(stdout.matches("(2 total)").count(), 2);
+    }
+
+    #[test]
+    fn single_file_has_no_header() {
+        let dir = tempfile::tempdir().unwrap();
+        let a = write_file(&dir, "a.log", "x\n");
+
+        trunc()
+            .arg("--files")
+            .arg(&a)
+            .assert()
+            .success()
+            .stdout("x\n");
+    }
+
+    #[test]
+    fn missing_file_is_reported_and_others_still_processed() {
+        let dir = tempfile::tempdir().unwrap();
+        let a = write_file(&dir, "a.log", "from a\n");
+
+        trunc()
+            .args(["-e", "x"])
+            .arg(&a)
+            .arg(dir.path().join("missing.log"))
+            .assert()
+            .failure()
+            .stdout(predicate::str::contains("from a"))
+            .stderr(predicate::str::contains("missing.log"));
+    }
+
+    #[test]
+    fn forbid_reports_file_and_line() {
+        let dir = tempfile::tempdir().unwrap();
+        let a = write_file(&dir, "a.log", "fine\n");
+        let b = write_file(&dir, "b.log", "fine\nFATAL here\n");
+
+        trunc()
+            .args(["--forbid", "FATAL", "--files"])
+            .arg(&a)
+            .arg(&b)
+            .assert()
+            .failure()
+            .stderr(predicate::str::contains(format!(
+                "{}:2: FATAL here",
+                b.display()
+            )));
+    }
+}
@@ -0,0 +1,74 @@
+//! Tests for `--fixed-string`: patterns are literal substrings, so things
+//! like `[ERROR]` or `foo(bar)` need no escaping.
+
+mod common;
+
+use common::{generate_lines_with_matches, trunc};
+use predicates::prelude::*;
+
+#[test]
+fn brackets_match_literally() {
+    // As a regex, [ERROR] is a character class matching any of E, R, O
+    let mut input = generate_lines_with_matches(100, &[50], "[ERROR]");
+    input.push_str("\nline 101 has an R in it");
+
+    trunc()
+        .args(["-f", "3", "-l", "0", "-C", "0", "--fixed-string", "[ERROR]"])
+        .write_stdin(input)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("line 50 contains [ERROR]"))
+        .stdout(predicate::str::contains("match 2").not())
+        .stdout(predicate::str::contains("has an R").not());
+}
+
+#[test]
+fn parens_need_no_escaping() {
+    let input = generate_lines_with_matches(100, &[40], "foo(bar)");
+
+    trunc()
+        .args(["-f", "3", "-l", "3", "--fixed-string", "foo(bar)"])
+        .write_stdin(input)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("line 40 contains foo(bar)"))
+        .stdout(predicate::str::contains("match 1 shown"));
+}
+
+#[test]
+fn invalid_regex_is_fine_as_literal() {
+    let input = generate_lines_with_matches(100, &[60], "a[b");
+
+    trunc()
+        .args(["-f", "3", "-l", "3", "--fixed-strings", "-e", "a[b"])
+        .write_stdin(input)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("line 60 contains a[b"));
+}
+
+#[test]
+fn several_literals_with_labels() {
+    let mut lines: Vec<String> = (1..=200).map(|i| format!("line {}", i)).collect();
+    lines[49] = "line 50 [WARN] disk".to_string();
+    lines[149] = "line 150 (fatal)".to_string();
+
+    let assert = trunc()
+        .args(["-f", "2", "-l", "2", "-C", "0", "--fixed-string", "--label"])
+        .args(["-e", "[WARN]", "-e", "(fatal)"])
+        .write_stdin(lines.join("\n"))
+        .assert()
+        .success();
+
+    let stdout = String::from_utf8_lossy(&assert.get_output().stdout);
+    assert!(
+        stdout.contains("match 1 shown ('[WARN]')"),
+        "Got:\n{}",
+        stdout
+    );
+    assert!(
+        stdout.contains("match 2 shown ('(fatal)')"),
+        "Got:\n{}",
+        stdout
+    );
+}
@@ -0,0 +1,135 @@
+//! Tests for `--flush line|block|interval=MS`: when shown lines are flushed
+//! to stdout. The output is the same under every policy; only when it
+//! arrives differs.
+
+mod common;
+
+use common::{trunc, Guard};
+use predicates::prelude::*;
+use std::io::{BufRead, BufReader, Write};
+use std::process::{Command, Stdio};
+use std::sync::mpsc::{self, Receiver};
+use std::time::Duration;
+
+/// Spawn trunc with piped stdin/stdout, collecting stdout lines on a channel.
+fn spawn(args: &[&str]) -> (Guard, Receiver<String>) {
+    let mut child = Command::new(assert_cmd::cargo::cargo_bin!("trunc"))
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .expect("Failed to spawn trunc");
+    let stdout = child.stdout.take().unwrap();
+    let (tx, rx) = mpsc::channel();
+    std::thread::spawn(move || {
+        for l in BufReader::new(stdout).lines().map_while(Result::ok) {
+            let _ = tx.send(l);
+        }
+    });
+    (Guard(child), rx)
+}
+
+/// Write `lines` to the child's stdin, leaving it open.
+fn feed(child: &mut Guard, lines: std::ops::RangeInclusive<usize>) {
+    let stdin = child.0.stdin.as_mut().unwrap();
+    for i in lines {
+        writeln!(stdin, "line {}", i).unwrap();
+    }
+    stdin.flush().unwrap();
+}
+
+/// Lines 1 to `n`, every tenth an error.
+fn generate_lines(n: usize) -> String {
+    (1..=n)
+        .map(|i| {
+            if i % 10 == 0 {
+                format!("line {} ERROR\n", i)
+            } else {
+                format!("line {}\n", i)
+            }
+        })
+        .collect()
+}
+
+#[test]
+fn line_flushes_each_head_line() {
+    let (mut child, rx) = spawn(&["--flush", "line"]);
+    feed(&mut child, 1..=1);
+    let first = rx.recv_timeout(Duration::from_secs(5));
+    assert_eq!(first.as_deref(), Ok("line 1"));
+}
+
+#[test]
+fn interval_flushes_while_input_is_open() {
+    // Nothing more arrives to trigger a flush; the interval does it
+    let (mut child, rx) = spawn(&["--flush", "interval=50"]);
+    feed(&mut child, 1..=3);
+    for i in 1..=3 {
+        let line = rx.recv_timeout(Duration::from_secs(5));
+        assert_eq!(line, Ok(format!("line {}", i)));
+    }
+}
+
+#[test]
+fn default_off_a_terminal_still_streams() {
+    let (mut child, rx) = spawn(&[]);
+    feed(&mut child, 1..=30);
+    let received: Vec<String> = (0..30)
+        .map_while(|_| rx.recv_timeout(Duration::from_secs(5)).ok())
+        .collect();
+    assert_eq!(received.len(), 30);
+}
+
+#[test]
+fn block_holds_lines_until_the_end() {
+    let (mut child, rx) = spawn(&["--flush", "block"]);
+    feed(&mut child, 1..=30);
+    assert!(rx.recv_timeout(Duration::from_millis(300)).is_err());
+    drop(child.0.stdin.take());
+    let first = rx.recv_timeout(Duration::from_secs(5));
+    assert_eq!(first.as_deref(), Ok("line 1"));
+}
+
+#[test]
+fn stall_flushes_under_block() {
+    let (mut child, rx) = spawn(&["--flush", "block", "--idle-timeout", "200ms"]);
+    feed(&mut child, 1..=5);
+    let received: Vec<String> = (0..6)
+        .map_while(|_| rx.recv_timeout(Duration::from_secs(5)).ok())
+        .collect();
+    assert_eq!(received[0], "line 1");
+    assert!(received[5].contains("input stalled"), "{:?}", received);
+}
+
+#[test]
+fn same_output_under_every_policy() {
+    let input = generate_lines(1000);
+    let expected = trunc()
+        .args(["--flush", "line", "-m", "50", "ERROR"])
+        .write_stdin(input.clone())
+        .output()
+        .unwrap()
+        .stdout;
+    for policy in ["block", "interval=1"] {
+        trunc()
+            .args(["--flush", policy, "-m", "50", "ERROR"])
+            .write_stdin(input.clone())
+            .assert()
+            .success()
+            .stdout(expected.clone());
+    }
+}
+
+#[test]
+fn bad_policy_rejected() {
+    for policy in ["sometimes", "interval=0", "interval=soon"] {
+        trunc()
+            .args(["--flush", policy])
+            .write_stdin("x\n")
+            .assert()
+            .failure()
+            .stderr(predicate::str::contains(
+                "expected line, block or interval=MS",
+            ));
+    }
+}
@@ -0,0 +1,233 @@
+//! Tests for `--fold-tracebacks`: a deep Python traceback is folded to its
+//! first frame, a count of the frames left out, its last 3 frames and the
+//! exception; a JVM stack trace's frames to the first 3 and last of each
+//! cause.
+
+mod common;
+
+use common::{generate_lines, trunc};
+use predicates::prelude::*;
+
+/// Frame `i` of a traceback: its `File` line and source line.
+fn frame(i: usize) -> String {
+    format!(
+        "  File \"/app/mod{}.py\", line {}, in f{}\n    return f{}()\n",
+        i,
+        i,
+        i,
+        i + 1
+    )
+}
+
+/// A traceback `depth` frames deep, ending in a `ValueError`.
+fn traceback(depth: usize) -> String {
+    let frames: String = (1..=depth).map(frame).collect();
+    format!(
+        "Traceback (most recent call last):\n{}ValueError: bad\n",
+        frames
+    )
+}
+
+/// What `traceback(depth)` folds to.
+fn folded(depth: usize) -> String {
+    format!(
+        "Traceback (most recent call last):\n{}[... {} frames ...]\n{}{}{}ValueError: bad\n",
+        frame(1),
+        depth - 4,
+        frame(depth - 2),
+        frame(depth - 1),
+        frame(depth)
+    )
+}
+
+#[test]
+fn deep_traceback_folded() {
+    trunc()
+        .args(["--fold-tracebacks", "-f", "100"])
+        .write_stdin(traceback(45))
+        .assert()
+        .success()
+        .stdout(folded(45));
+}
+
+#[test]
+fn shallow_traceback_untouched() {
+    for depth in [1, 5] {
+        trunc()
+            .args(["--fold-tracebacks", "-f", "100"])
+            .write_stdin(traceback(depth))
+            .assert()
+            .success()
+            .stdout(traceback(depth));
+    }
+}
+
+#[test]
+fn off_by_default() {
+    trunc()
+        .args(["-f", "100"])
+        .write_stdin(traceback(45))
+        .assert()
+        .success()
+        .stdout(traceback(45));
+}
+
+#[test]
+fn surrounding_lines_kept() {
+    let input = format!("before\n{}after\n", traceback(20));
+    trunc()
+        .args(["--fold-tracebacks", "-f", "100"])
+        .write_stdin(input)
+        .assert()
+        .success()
+        .stdout(format!("before\n{}after\n", folded(20)));
+}
+
+#[test]
+fn folded_lines_are_what_budgets_count() {
+    // The folded traceback is 10 lines, so a head of 10 ends at the exception
+    let input = format!("{}{}\n", traceback(45), generate_lines(50));
+    trunc()
+        .args(["--fold-tracebacks", "-f", "10", "-l", "1"])
+        .write_stdin(input)
+        .assert()
+        .success()
+        .stdout(format!(
+            "{}[... 49 lines truncated ...]\nline 50\n",
+            folded(45)
+        ));
+}
+
+#[test]
+fn auto_folds_and_matches() {
+    let input = format!(
+        "{}\n{}{}\n",
+        generate_lines(50),
+        traceback(45),
+        generate_lines(50)
+    );
+    trunc()
+        .args(["--auto", "-f", "1", "-l", "1", "-C", "0", "-A", "2"])
+        .write_stdin(input)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(format!(
+            "('traceback') ...]\nTraceback (most recent call last):\n{}[... 41 frames ...]\n[...",
+            frame(1)
+        )));
+}
+
+#[test]
+fn consecutive_tracebacks_folded_separately() {
+    let input = format!("{}{}", traceback(10), traceback(12));
+    trunc()
+        .args(["--fold-tracebacks", "-f", "100"])
+        .write_stdin(input)
+        .assert()
+        .success()
+        .stdout(format!("{}{}", folded(10), folded(12)));
+}
+
+/// `depth` JVM frames of class `class`, one line each.
+fn jvm_frames(class: &str, depth: usize) -> String {
+    (1..=depth)
+        .map(|i| format!("\tat com.acme.{}.m{}({}.java:{})\n", class, i, class, i))
+        .collect()
+}
+
+/// A JVM stack trace of two causes, `depth` frames each.
+fn jvm_trace(depth: usize) -> String {
+    format!(
+        "java.lang.IllegalStateException: outer\n{}Caused by: java.sql.SQLException: inner\n{}\t... 12 more\n",
+        jvm_frames("Outer", depth),
+        jvm_frames("Inner", depth)
+    )
+}
+
+#[test]
+fn jvm_causes_folded_separately() {
+    trunc()
+        .args(["--fold-tracebacks", "-f", "100"])
+        .write_stdin(jvm_trace(30))
+        .assert()
+        .success()
+        .stdout(format!(
+            "java.lang.IllegalStateException: outer\n{}[... 26 frames ...]\n\tat com.acme.Outer.m30(Outer.java:30)\n\
+             Caused by: java.sql.SQLException: inner\n{}[... 26 frames ...]\n\tat com.acme.Inner.m30(Inner.java:30)\n\
+             \t... 12 more\n",
+            jvm_frames("Outer", 3),
+            jvm_frames("Inner", 3)
+        ));
+}
+
+#[test]
+fn shallow_jvm_trace_untouched() {
+    trunc()
+        .args(["--fold-tracebacks", "-f", "100"])
+        .write_stdin(jvm_trace(5))
+        .assert()
+        .success()
+        .stdout(jvm_trace(5));
+}
+
+#[test]
+fn auto_folds_jvm_trace() {
+    let input = format!("{}{}\n", jvm_trace(30), generate_lines(50));
+    trunc()
+        .args(["--auto", "-f", "100"])
+        .write_stdin(input)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Outer.java:3)\n[... 26 frames ...]\n\tat com.acme.Outer.m30(",
+        ));
+}
+
+#[test]
+fn frames_marker_goes_to_stderr() {
+    let input = format!("before\n{}after\n", traceback(20));
+    trunc()
+        .args(["--fold-tracebacks", "-f", "100", "--markers-to-stderr"])
+        .write_stdin(input)
+        .assert()
+        .success()
+        .stdout(format!(
+            "before\nTraceback (most recent call last):\n{}{}{}{}ValueError: bad\nafter\n",
+            frame(1),
+            frame(18),
+            frame(19),
+            frame(20)
+        ))
+        .stderr("[... 16 frames ...]\n");
+}
+
+#[test]
+fn frames_marker_compact() {
+    trunc()
+        .args(["--fold-tracebacks", "-f", "100", "--markers", "compact"])
+        .write_stdin(jvm_trace(30))
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Outer.java:3)\n\u{2026}26 frames\u{2026}\n\tat com.acme.Outer.m30(",
+        ))
+        .stdout(predicate::str::contains("[...").not());
+}
+
+#[test]
+fn frames_marker_compact_to_stderr() {
+    trunc()
+        .args([
+            "--fold-tracebacks",
+            "-f",
+            "100",
+            "--markers",
+            "compact",
+            "--markers-to-stderr",
+        ])
+        .write_stdin(jvm_trace(30))
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("frames").not())
+        .stderr("\u{2026}26 frames\u{2026}\n\u{2026}26 frames\u{2026}\n");
+}
@@ -0,0 +1,200 @@
+//! Tests for follow mode (`-F/--follow`).
+//!
+//! Without follow mode the tail only appears at EOF, which never comes for a
+//! live log. In follow mode trunc periodically flushes the rolling tail with a
+//! marker for what it skipped, and keeps reading files past EOF.
+
+mod common;
+
+use common::{trunc_bin, Guard};
+use std::io::{BufRead, BufReader, Write};
+use std::process::{Command, Stdio};
+use std::sync::mpsc::{self, Receiver};
+use std::time::Duration;
+
+/// Spawn trunc with piped stdin/stdout, collecting stdout lines on a channel.
+fn spawn(args: &[&str]) -> (Guard, Receiver<String>) {
+    let mut child = Command::new(trunc_bin())
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .expect("Failed to spawn trunc");
+
+    let stdout = child.stdout.take().expect("Failed to open stdout");
+    let (tx, rx) = mpsc::channel();
+    std::thread::spawn(move || {
+        let reader = BufReader::new(stdout);
+        for l in reader.lines().map_while(Result::ok) {
+            let _ = tx.send(l);
+        }
+    });
+    (Guard(child), rx)
+}
+
+/// Everything received so far.
+fn drain(rx: &Receiver<String>) -> Vec<String> {
+    let mut received = Vec::new();
+    while let Ok(line) = rx.try_recv() {
+        received.push(line);
+    }
+    received
+}
+
+#[test]
+fn tail_appears_before_pipe_closes() {
+    let (mut child, rx) = spawn(&["-F", "--refresh", "100ms", "-f", "3", "-l", "3"]);
+    let mut stdin = child.stdin.take().unwrap();
+
+    for i in 1..=100 {
+        writeln!(stdin, "line {}", i).unwrap();
+    }
+    stdin.flush().unwrap();
+    std::thread::sleep(Duration::from_millis(500));
+
+    // stdin is still open, but the tail has been flushed
+    let received = drain(&rx);
+    assert_eq!(
+        received,
+        vec![
+            "line 1",
+            "line 2",
+            "line 3",
+            "[... 94 lines truncated ...]",
+            "line 98",
+            "line 99",
+            "line 100"
+        ]
+    );
+
+    drop(stdin);
+    let _ = child.wait();
+}
+
+#[test]
+fn later_refresh_covers_only_new_lines() {
+    let (mut child, rx) = spawn(&["-F", "--refresh", "100ms", "-f", "2", "-l", "2"]);
+    let mut stdin = child.stdin.take().unwrap();
+
+    for i in 1..=50 {
+        writeln!(stdin, "line {}", i).unwrap();
+    }
+    stdin.flush().unwrap();
+    std::thread::sleep(Duration::from_millis(400));
+    drain(&rx);
+
+    // One write, so the batch can't straddle a refresh
+    let batch: String = (51..=60).map(|i| format!("line {}\n", i)).collect();
+    stdin.write_all(batch.as_bytes()).unwrap();
+    stdin.flush().unwrap();
+    std::thread::sleep(Duration::from_millis(400));
+
+    assert_eq!(
+        drain(&rx),
+        vec!["[... 8 lines truncated ...]", "line 59", "line 60"]
+    );
+
+    drop(stdin);
+    let _ = child.wait();
+}
+
+#[test]
+fn matches_still_stream_in_follow_mode() {
+    let (mut child, rx) = spawn(&["-F", "--refresh", "10s", "-f", "2", "-l", "2", "ERROR"]);
+    let mut stdin = child.stdin.take().unwrap();
+
+    for i in 1..=20 {
+        writeln!(stdin, "line {}", i).unwrap();
+    }
+    writeln!(stdin, "line 21 contains ERROR").unwrap();
+    stdin.flush().unwrap();
+    std::thread::sleep(Duration::from_millis(300));
+
+    let received = drain(&rx);
+    assert!(
+        received.iter().any(|l| l == "line 21 contains ERROR"),
+        "Got: {:?}",
+        received
+    );
+
+    drop(stdin);
+    let _ = child.wait();
+}
+
+#[test]
+fn follows_file_past_eof() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("live.log");
+    std::fs::write(&path, "line 1\nline 2\nline 3\n").unwrap();
+
+    let (mut child, rx) = spawn(&[
+        "-F",
+        "--refresh",
+        "100ms",
+        "-f",
+        "1",
+        "-l",
+        "1",
+        "--files",
+        path.to_str().unwrap(),
+    ]);
+    std::thread::sleep(Duration::from_millis(400));
+    assert_eq!(
+        drain(&rx),
+        vec!["line 1", "[... 1 lines truncated ...]", "line 3"]
+    );
+
+    let mut file = std::fs::OpenOptions::new()
+        .append(true)
+        .open(&path)
+        .unwrap();
+    writeln!(file, "line 4\nline 5").unwrap();
+    std::thread::sleep(Duration::from_millis(500));
+
+    // The file never "ends": trunc is still running and shows the new tail
+    // (line 4 may or may not land in a refresh of its own)
+    assert!(child.try_wait().unwrap().is_none());
+    let received = drain(&rx);
+    assert_eq!(received.last().map(String::as_str), Some("line 5"));
+}
+
+#[test]
+fn pipe_eof_still_finishes_normally() {
+    let output = Command::new(trunc_bin())
+        .args(["-F", "-f", "1", "-l", "1"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .and_then(|mut child| {
+            let mut stdin = child.stdin.take().unwrap();
+            writeln!(stdin, "a\nb\nc").unwrap();
+            drop(stdin);
+            child.wait_with_output()
+        })
+        .unwrap();
+
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "a\n[... 1 lines truncated ...]\nc\n"
+    );
+}
+
+#[test]
+fn rejects_multiple_inputs() {
+    let dir = tempfile::tempdir().unwrap();
+    let a = dir.path().join("a.log");
+    let b = dir.path().join("b.log");
+    std::fs::write(&a, "a\n").unwrap();
+    std::fs::write(&b, "b\n").unwrap();
+
+    let output = Command::new(trunc_bin())
+        .args(["-F", "--files"])
+        .arg(&a)
+        .arg(&b)
+        .output()
+        .unwrap();
+
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("single input"));
+}
@@ -0,0 +1,157 @@
+//! Tests for output gates: `--require` and `--forbid`.
+//!
+//! Gates turn trunc into a lightweight CI check. The truncated view is always
+//! printed in full; the exit status reports whether the input passed.
+
+mod common;
+
+use common::{generate_lines, trunc};
+use predicates::prelude::*;
+
+// =============================================================================
+// --require
+// =============================================================================
+
+mod require {
+    use super::*;
+
+    #[test]
+    fn passes_when_pattern_present() {
+        let input = format!("{}\nBUILD SUCCESSFUL\n", generate_lines(100));
+
+        trunc()
+            .args(["--require", "BUILD SUCCESSFUL"])
+            .write_stdin(input)
+            .assert()
+            .success();
+    }
+
+    #[test]
+    fn fails_when_pattern_missing_but_still_prints() {
+        let input = generate_lines(100);
+
+        trunc()
+            .args(["-f", "3", "-l", "3", "--require", "BUILD SUCCESSFUL"])
+            .write_stdin(input)
+            .assert()
+            .failure()
+            .stdout(predicate::str::contains("[... 94 lines truncated ...]"))
+            .stdout(predicate::str::ends_with("line 100\n"))
+            .stderr(predicate::str::contains(
+                "required pattern not found: BUILD SUCCESSFUL",
+            ));
+    }
+
+    #[test]
+    fn matches_in_truncated_middle_count() {
+        // The required line is hidden from output but still satisfies the gate
+        let mut lines: Vec<String> = (1..=100).map(|i| format!("line {}", i)).collect();
+        lines[49] = "tests passed".to_string();
+
+        trunc()
+            .args(["-f", "3", "-l", "3", "--require", "tests passed"])
+            .write_stdin(lines.join("\n"))
+            .assert()
+            .success()
+            .stdout(predicate::str::contains("tests passed").not());
+    }
+
+    #[test]
+    fn repeatable_all_must_appear() {
+        trunc()
+            .args(["--require", "alpha", "--require", "gamma"])
+            .write_stdin("alpha\nbeta\n")
+            .assert()
+            .failure()
+            .stderr(predicate::str::contains(
+                "required pattern not found: gamma",
+            ))
+            .stderr(predicate::str::contains("alpha").not());
+    }
+
+    #[test]
+    fn empty_input_fails_requirement() {
+        trunc()
+            .args(["--require", "anything"])
+            .write_stdin("")
+            .assert()
+            .failure()
+            .stdout("");
+    }
+
+    #[test]
+    fn invalid_regex_is_an_error() {
+        trunc()
+            .args(["--require", "[invalid"])
+            .write_stdin("x\n")
+            .assert()
+            .failure()
+            .stderr(predicate::str::contains("Invalid regex"));
+    }
+}
+
+// =============================================================================
+// --forbid
+// =============================================================================
+
+mod forbid {
+    use super::*;
+
+    #[test]
+    fn passes_when_pattern_absent() {
+        trunc()
+            .args(["--forbid", "panicked"])
+            .write_stdin(generate_lines(100))
+            .assert()
+            .success()
+            .stderr("");
+    }
+
+    #[test]
+    fn fails_and_reports_hit_in_truncated_middle() {
+        let mut lines: Vec<String> = (1..=100).map(|i| format!("line {}", i)).collect();
+        lines[49] = "thread 'main' panicked at src/lib.rs:4".to_string();
+
+        trunc()
+            .args(["-f", "3", "-l", "3", "--forbid", "panicked"])
+            .write_stdin(lines.join("\n"))
+            .assert()
+            .failure()
+            .stdout(predicate::str::contains("[... 94 lines truncated ...]"))
+            .stderr(predicate::str::contains(
+                "forbidden pattern found 1 time: panicked",
+            ))
+            .stderr(predicate::str::contains(
+                "line 50: thread 'main' panicked at src/lib.rs:4",
+            ));
+    }
+
+    #[test]
+    fn offending_lines_capped_by_match_limit() {
+        let input = (1..=10)
+            .map(|i| format!("FATAL {}", i))
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        trunc()
+            .args(["-m", "2", "--forbid", "FATAL"])
+            .write_stdin(input)
+            .assert()
+            .failure()
+            .stderr(predicate::str::contains("found 10 times"))
+            .stderr(predicate::str::contains("line 2: FATAL 2"))
+            .stderr(predicate::str::contains("line 3:").not())
+            .stderr(predicate::str::contains("[... 8 more ...]"));
+    }
+
+    #[test]
+    fn combines_with_require() {
+        trunc()
+            .args(["--require", "ok", "--forbid", "bad"])
+            .write_stdin("ok\nbad\n")
+            .assert()
+            .failure()
+            .stderr(predicate::str::contains("required pattern").not())
+            .stderr(predicate::str::contains("forbidden pattern"));
+    }
+}
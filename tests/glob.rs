@@ -0,0 +1,80 @@
+//! Tests for `--glob`: patterns are shell-style wildcards matching a whole
+//! line (`*` any text, `?` one character, `[...]` a set), not regexes.
+
+mod common;
+
+use common::{run, trunc};
+use predicates::prelude::*;
+
+/// Filler lines around `middle`, so the middle is truncated.
+fn surround(middle: &[&str]) -> String {
+    let filler = |range: std::ops::RangeInclusive<usize>| -> String {
+        range.map(|i| format!("line {}\n", i)).collect()
+    };
+    let middle: String = middle.iter().map(|l| format!("{}\n", l)).collect();
+    filler(1..=50) + &middle + &filler(51..=100)
+}
+
+#[test]
+fn star_matches_any_text() {
+    let input = surround(&["conn timeout, will retry", "retry after timeout"]);
+    let out = run(&["--glob", "-C", "0", "*timeout*retry*"], &input);
+    assert!(out.contains("\nconn timeout, will retry\n"), "Got: {}", out);
+    assert!(!out.contains("retry after timeout"));
+}
+
+#[test]
+fn glob_matches_the_whole_line() {
+    let input = surround(&["timeout", "a timeout here"]);
+    let out = run(&["--glob", "-C", "0", "timeout"], &input);
+    assert!(out.contains("\ntimeout\n"), "Got: {}", out);
+    assert!(!out.contains("a timeout here"));
+}
+
+#[test]
+fn question_mark_and_sets_match_one_character() {
+    let input = surround(&["code E1", "code E12", "code W3", "code X4"]);
+    let out = run(&["--glob", "-C", "0", "code [EW]?"], &input);
+    assert!(out.contains("\ncode E1\n"), "Got: {}", out);
+    assert!(out.contains("\ncode W3\n"));
+    assert!(!out.contains("E12"));
+    assert!(!out.contains("X4"));
+
+    let out = run(&["--glob", "-C", "0", "code [!EW]?"], &input);
+    assert!(out.contains("\ncode X4\n"), "Got: {}", out);
+    assert!(!out.contains("code E1"));
+}
+
+#[test]
+fn regex_characters_are_literal() {
+    // As a regex, `(x).+` would match both lines
+    let input = surround(&["f(x).+", "fx.."]);
+    let out = run(&["--glob", "-C", "0", "f(x).+"], &input);
+    assert!(out.contains("\nf(x).+\n"), "Got: {}", out);
+    assert!(!out.contains("fx.."));
+}
+
+#[test]
+fn backslash_escapes_wildcards() {
+    let input = surround(&["a*b", "axb"]);
+    let out = run(&["--glob", "-C", "0", r"a\*b"], &input);
+    assert!(out.contains("\na*b\n"), "Got: {}", out);
+    assert!(!out.contains("axb"));
+}
+
+#[test]
+fn labels_name_the_glob() {
+    let input = surround(&["conn timeout, will retry"]);
+    let out = run(&["--glob", "--label", "-C", "0", "*timeout*"], &input);
+    assert!(out.contains("match 1 shown ('*timeout*')"), "Got: {}", out);
+}
+
+#[test]
+fn glob_conflicts_with_fixed_string() {
+    trunc()
+        .args(["--glob", "--fixed-string", "x"])
+        .write_stdin("x\n")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("cannot be used with"));
+}
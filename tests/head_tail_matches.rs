@@ -0,0 +1,128 @@
+//! Tests for matches that land in the head or tail: they're shown there
+//! rather than as matches, but still counted in `--stats` and the end
+//! marker's total, and never among the matches truncated.
+
+mod common;
+
+use common::{generate_lines_with_matches, trunc};
+use predicates::prelude::*;
+
+#[test]
+fn head_match_counted_in_stats() {
+    let input = generate_lines_with_matches(100, &[2, 50], "ERROR");
+    trunc()
+        .args(["-f", "5", "-l", "5", "-C", "0", "--stats", "ERROR"])
+        .write_stdin(input)
+        .assert()
+        .success()
+        .stderr(predicate::str::contains(
+            "matches:       2 found, 2 shown\n",
+        ));
+}
+
+#[test]
+fn tail_match_not_counted_as_truncated() {
+    // Line 98 is past the -m budget, but the tail shows it
+    let input = generate_lines_with_matches(100, &[50, 60, 98], "ERROR");
+    trunc()
+        .args([
+            "-f", "5", "-l", "5", "-C", "0", "-m", "1", "--stats", "ERROR",
+        ])
+        .write_stdin(input)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "[... 45 lines and 1 match truncated (3 total), 1 match in tail ...]\nline 96\n",
+        ))
+        .stderr(predicate::str::contains(
+            "matches:       3 found, 2 shown\n",
+        ));
+}
+
+#[test]
+fn end_marker_total_counts_head_matches() {
+    let input = generate_lines_with_matches(100, &[2, 50, 60], "ERROR");
+    trunc()
+        .args(["-f", "5", "-l", "5", "-C", "0", "-m", "1", "ERROR"])
+        .write_stdin(input)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "[... 45 lines and 1 match truncated (3 total), 1 match in head ...]",
+        ));
+}
+
+#[test]
+fn short_input_counts_every_match() {
+    let input = generate_lines_with_matches(10, &[3, 7], "ERROR");
+    trunc()
+        .args(["--stats", "ERROR"])
+        .write_stdin(input)
+        .assert()
+        .success()
+        .stderr(predicate::str::contains(
+            "matches:       2 found, 2 shown\n",
+        ));
+}
+
+#[test]
+fn count_only_counts_head_and_tail_as_shown() {
+    let input = generate_lines_with_matches(100, &[2, 50, 98], "ERROR");
+    trunc()
+        .args(["-f", "5", "-l", "5", "-c", "--stats", "ERROR"])
+        .write_stdin(input)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "[... 90 lines, 1 match of 'ERROR', 1 match in head, 1 match in tail ...]",
+        ))
+        .stderr(predicate::str::contains(
+            "matches:       3 found, 2 shown\n",
+        ));
+}
+
+#[test]
+fn explain_names_a_head_match() {
+    let input = generate_lines_with_matches(100, &[2], "ERROR");
+    trunc()
+        .args(["-f", "5", "-l", "5", "--explain", "ERROR"])
+        .write_stdin(input)
+        .assert()
+        .success()
+        .stderr(predicate::str::contains(
+            "line 2: shown in the head (2 of 5); it's a match\n",
+        ));
+}
+
+#[test]
+fn only_match_in_head_is_not_no_matches() {
+    let input = generate_lines_with_matches(100, &[2], "ERROR");
+    trunc()
+        .args(["-f", "5", "-l", "5", "--stats", "ERROR"])
+        .write_stdin(input)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "[... 90 lines truncated, 1 match in head ...]\n",
+        ))
+        .stdout(predicate::str::contains("0 matches found").not())
+        .stderr(predicate::str::contains(
+            "matches:       1 found, 1 shown\n",
+        ));
+}
+
+#[test]
+fn head_and_tail_matches_named_without_color() {
+    // Line 98 is past the -m budget, so it's only shown as part of the tail
+    let input = generate_lines_with_matches(100, &[2, 3, 50, 98], "ERROR");
+    trunc()
+        .args([
+            "-f", "5", "-l", "5", "-C", "0", "-m", "1", "--color", "never", "ERROR",
+        ])
+        .write_stdin(input)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "[... 45 lines truncated, 2 matches in head, 1 match in tail ...]\n",
+        ));
+}
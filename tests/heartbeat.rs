@@ -0,0 +1,107 @@
+//! Tests for `--heartbeat`: a `still reading…` status line on stderr while
+//! a slow producer keeps trunc waiting.
+#![cfg(unix)]
+
+mod common;
+
+use common::trunc;
+use predicates::prelude::*;
+
+/// A shell script printing lines 1-20, pausing, then printing 21 and 22.
+const SLOW: &str = "seq 1 20; sleep 1; echo 21; echo 22";
+
+#[test]
+fn status_on_stderr_while_waiting() {
+    trunc()
+        .args([
+            "-f",
+            "2",
+            "-l",
+            "2",
+            "--heartbeat",
+            "200ms",
+            "--",
+            "sh",
+            "-c",
+            SLOW,
+        ])
+        .assert()
+        .success()
+        .stdout("1\n2\n[... 18 lines truncated ...]\n21\n22\n")
+        .stderr(predicate::str::contains(
+            "[trunc] still reading\u{2026} 20 lines\n",
+        ));
+}
+
+#[test]
+fn counts_matches_with_a_pattern() {
+    trunc()
+        .args([
+            "-f",
+            "2",
+            "-l",
+            "2",
+            "--heartbeat",
+            "200ms",
+            "-e",
+            "^1[05]$",
+        ])
+        .args(["--", "sh", "-c", SLOW])
+        .assert()
+        .success()
+        .stderr(predicate::str::contains(
+            "[trunc] still reading\u{2026} 20 lines, 2 matches so far\n",
+        ));
+}
+
+#[test]
+fn one_match_is_singular() {
+    trunc()
+        .args(["-f", "2", "-l", "2", "--heartbeat", "200ms", "-e", "^15$"])
+        .args(["--", "sh", "-c", SLOW])
+        .assert()
+        .success()
+        .stderr(predicate::str::contains(
+            "[trunc] still reading\u{2026} 20 lines, 1 match so far\n",
+        ));
+}
+
+#[test]
+fn quiet_when_input_is_quick() {
+    trunc()
+        .args(["--heartbeat", "10s", "--", "sh", "-c", "seq 1 100"])
+        .assert()
+        .success()
+        .stderr("");
+}
+
+#[test]
+fn with_idle_timeout() {
+    trunc()
+        .args([
+            "-f",
+            "0",
+            "-l",
+            "2",
+            "--heartbeat",
+            "300ms",
+            "--idle-timeout",
+            "200ms",
+        ])
+        .args(["--", "sh", "-c", SLOW])
+        .assert()
+        .success()
+        .stdout(predicate::str::starts_with(
+            "[... input stalled, partial tail ...]\n[... 18 lines truncated ...]\n19\n20\n",
+        ))
+        .stderr(predicate::str::contains("still reading"));
+}
+
+#[test]
+fn conflicts_with_follow() {
+    trunc()
+        .args(["--heartbeat", "1s", "--follow"])
+        .write_stdin("")
+        .assert()
+        .failure();
+}
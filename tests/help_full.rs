@@ -0,0 +1,75 @@
+//! Tests for `--help-full` and the hidden `manpage` subcommand.
+//!
+//! Both document the markers and other lines trunc adds to its output. The
+//! examples are written by the code that writes real markers, so they should
+//! look exactly like what a run prints.
+
+mod common;
+
+use common::{generate_terminated_lines, run};
+
+#[test]
+fn help_full_prints_the_help_then_the_output_format() {
+    let help = run(&["--help-full"], "");
+    let options = help.find("--help-full").unwrap();
+    let format = help.find("Output format:").unwrap();
+    assert!(options < format, "the options come first");
+    assert!(help.contains("[... 980 lines truncated ...]"));
+    assert!(help.contains("…980L…"));
+}
+
+#[test]
+fn help_full_reads_no_input() {
+    // Exits at once rather than waiting on a terminal or pipe
+    let help = run(&["--help-full"], generate_terminated_lines(100));
+    assert!(!help.contains("line 1\n"));
+}
+
+#[test]
+fn help_full_markers_match_real_markers() {
+    let help = run(&["--help-full"], "");
+    let input = generate_terminated_lines(1040);
+    let verbose = run(&[], &input);
+    let compact = run(&["--markers", "compact"], &input);
+    assert!(verbose.contains("[... 980 lines truncated ...]\n"));
+    assert!(compact.contains("…980L…\n"));
+
+    let with_ids = run(&["--region-ids"], generate_terminated_lines(274));
+    assert!(with_ids.contains("[#1: 214 lines truncated ...]\n"));
+    assert!(help.contains("[#1: 214 lines truncated ...]"));
+}
+
+#[test]
+fn help_full_shows_the_trailer_format() {
+    let help = run(&["--help-full"], "");
+    let trailer = help
+        .lines()
+        .find(|line| line.trim_start().starts_with("[trunc sha256:"))
+        .unwrap();
+    let real = run(&["--trailer", "checksum"], generate_terminated_lines(1040));
+    let real = real.lines().last().unwrap();
+    // Same fields, in the same order, with only the values differing
+    let keys = |line: &str| -> Vec<String> {
+        line.split_whitespace()
+            .map(|field| field.split(':').next().unwrap().to_string())
+            .collect()
+    };
+    assert_eq!(keys(trailer), keys(real));
+    assert!(real.contains(" hidden-lines:980 "));
+}
+
+#[test]
+fn manpage_is_roff_with_options_and_output_format() {
+    let page = run(&["manpage"], "");
+    assert!(page.contains(".TH trunc 1"));
+    assert!(page.contains("\\-\\-help\\-full"));
+    assert!(page.contains(".SH \"OUTPUT FORMAT\""));
+    assert!(page.contains("[... 980 lines truncated ...]"));
+}
+
+#[test]
+fn manpage_is_hidden_from_help() {
+    let help = run(&["--help"], "");
+    assert!(!help.contains("manpage"));
+    assert!(help.contains("--help-full"));
+}
@@ -0,0 +1,109 @@
+//! Tests for truncating trunc's own output again: markers in the input
+//! count as the lines they hid, unless `--no-idempotent`.
+
+mod common;
+
+use common::{generate_numbers, run};
+
+#[test]
+fn retruncating_counts_lines_hidden_before() {
+    let once = run(&["-f", "10", "-l", "10"], generate_numbers(1000));
+    let twice = run(&["-f", "3", "-l", "3"], &once);
+    assert_eq!(twice, run(&["-f", "3", "-l", "3"], generate_numbers(1000)));
+    assert_eq!(
+        twice,
+        "1\n2\n3\n[... 994 lines truncated ...]\n998\n999\n1000\n"
+    );
+}
+
+#[test]
+fn no_idempotent_counts_marker_as_a_line() {
+    let once = run(&["-f", "10", "-l", "10"], generate_numbers(1000));
+    let twice = run(&["-f", "3", "-l", "3", "--no-idempotent"], &once);
+    assert_eq!(
+        twice,
+        "1\n2\n3\n[... 15 lines truncated ...]\n998\n999\n1000\n"
+    );
+}
+
+#[test]
+fn marker_shown_is_left_as_is() {
+    let once = run(&["-f", "3", "-l", "3"], generate_numbers(100));
+    assert_eq!(run(&["-f", "5", "-l", "5"], &once), once);
+}
+
+#[test]
+fn compact_and_numbered_markers() {
+    let once = run(
+        &[
+            "-f",
+            "10",
+            "-l",
+            "10",
+            "--markers",
+            "compact",
+            "--region-ids",
+        ],
+        generate_numbers(1000),
+    );
+    let twice = run(&["-f", "3", "-l", "2", "-w", "0"], &once);
+    // 7 head lines, the 980 the marker hid and 9 tail lines; the region
+    // footer is the last line
+    assert!(
+        twice.contains("\n[... 996 lines truncated ...]\n"),
+        "{}",
+        twice
+    );
+}
+
+#[test]
+fn match_markers_count() {
+    let once = run(
+        &["-f", "2", "-l", "2", "-C", "0", "500"],
+        generate_numbers(1000),
+    );
+    let twice = run(&["-f", "1", "-l", "1"], &once);
+    // Everything but 1 and 1000
+    assert_eq!(twice, "1\n[... 998 lines truncated ...]\n1000\n");
+}
+
+#[test]
+fn excluded_markers_not_counted() {
+    let input = "a\nb\n[... 50 lines excluded ...]\nc\nd\ne\n";
+    assert_eq!(
+        run(&["-f", "1", "-l", "1"], input),
+        "a\n[... 4 lines truncated ...]\ne\n"
+    );
+}
+
+#[test]
+fn lookalike_lines_not_counted() {
+    let input = "a\n[... 50 lines truncated\n[... many lines truncated ...]\nb\nc\n";
+    assert_eq!(
+        run(&["-f", "1", "-l", "1"], input),
+        "a\n[... 3 lines truncated ...]\nc\n"
+    );
+}
+
+#[test]
+fn marker_after_checkpoint_in_tail() {
+    // --every shows line 21 before the tail's 19 and 20, so the marker
+    // before them starts past where it ends
+    let input: String = (1..=21)
+        .map(|i| match i {
+            4 | 7 | 20 | 21 => format!("ERROR {}\n", i),
+            5 | 6 | 19 => "[... 5 lines truncated ...]\n".to_string(),
+            _ => format!("line {}\n", i),
+        })
+        .collect();
+    let output = run(
+        &["-f", "3", "-l", "3", "-m", "2", "--every", "3", "ERROR"],
+        &input,
+    );
+    assert!(output.contains("    21│ ERROR 21\n"), "{}", output);
+    assert!(
+        output.ends_with("[... 5 lines truncated ...]\nERROR 20\n"),
+        "{}",
+        output
+    );
+}
@@ -0,0 +1,99 @@
+//! Tests for `--idle-timeout`: when input stops arriving, the tail so far
+//! is shown under an `input stalled` marker, without waiting for EOF.
+#![cfg(unix)]
+
+mod common;
+
+use common::trunc;
+use predicates::prelude::*;
+use std::time::{Duration, Instant};
+
+/// A shell script printing lines 1-20, pausing for `pause` seconds, then
+/// printing `rest`.
+fn stalling(pause: &str, rest: &str) -> String {
+    format!("seq 1 20; sleep {}; {}", pause, rest)
+}
+
+/// A shell script printing lines 1-20 and hanging (as itself, so killing
+/// it ends the hang).
+fn hanging() -> String {
+    "seq 1 20; exec sleep 30".to_string()
+}
+
+#[test]
+fn partial_tail_shown_then_carries_on() {
+    trunc()
+        .args(["-f", "2", "-l", "3", "--idle-timeout", "200ms", "--"])
+        .args(["sh", "-c", &stalling("1", "echo 21; echo 22")])
+        .assert()
+        .success()
+        .stdout(
+            "1\n2\n[... input stalled, partial tail ...]\n\
+             [... 15 lines truncated ...]\n18\n19\n20\n21\n22\n",
+        );
+}
+
+#[test]
+fn idle_exit_stops_at_stall() {
+    let started = Instant::now();
+    trunc()
+        .args([
+            "-f",
+            "2",
+            "-l",
+            "3",
+            "--idle-timeout",
+            "200ms",
+            "--idle-exit",
+            "--",
+        ])
+        .args(["sh", "-c", &hanging()])
+        .assert()
+        .code(124)
+        .stdout(
+            "1\n2\n[... input stalled, partial tail ...]\n\
+             [... 15 lines truncated ...]\n18\n19\n20\n",
+        );
+    assert!(started.elapsed() < Duration::from_secs(10));
+}
+
+#[test]
+fn no_stall_no_marker() {
+    trunc()
+        .args(["-f", "2", "-l", "2", "--idle-timeout", "5s", "--"])
+        .args(["sh", "-c", "seq 1 10"])
+        .assert()
+        .success()
+        .stdout("1\n2\n[... 6 lines truncated ...]\n9\n10\n");
+}
+
+#[test]
+fn in_pattern_mode() {
+    trunc()
+        .args(["-f", "1", "-l", "1", "-C", "0", "--idle-timeout", "200ms"])
+        .args(["--idle-exit", "-e", "^1[05]$", "--"])
+        .args(["sh", "-c", &hanging()])
+        .assert()
+        .code(124)
+        .stdout(predicate::str::contains(
+            "15\n[... input stalled, partial tail ...]\n[... 4 lines truncated ...]\n20\n",
+        ));
+}
+
+#[test]
+fn idle_exit_needs_timeout() {
+    trunc()
+        .args(["--idle-exit"])
+        .write_stdin("")
+        .assert()
+        .failure();
+}
+
+#[test]
+fn conflicts_with_follow() {
+    trunc()
+        .args(["--idle-timeout", "1s", "--follow"])
+        .write_stdin("")
+        .assert()
+        .failure();
+}
@@ -8,34 +8,9 @@
 //!
 //! These tests verify that every truncation marker communicates what was lost.
 
-use assert_cmd::Command;
+mod common;
 
-/// Helper to create a Command for the trunc binary.
-fn trunc() -> Command {
-    Command::cargo_bin("trunc").unwrap()
-}
-
-/// Generate N lines of input: "line 1\nline 2\n..."
-fn generate_lines(n: usize) -> String {
-    (1..=n)
-        .map(|i| format!("line {}", i))
-        .collect::<Vec<_>>()
-        .join("\n")
-}
-
-/// Generate N lines with a specific pattern at certain positions.
-fn generate_lines_with_matches(n: usize, match_at: &[usize], pattern: &str) -> String {
-    (1..=n)
-        .map(|i| {
-            if match_at.contains(&i) {
-                format!("line {} contains {}", i, pattern)
-            } else {
-                format!("line {}", i)
-            }
-        })
-        .collect::<Vec<_>>()
-        .join("\n")
-}
+use common::{generate_lines, generate_lines_with_matches, trunc};
 
 // =============================================================================
 // WITHIN-LINE TRUNCATION: CHARACTER COUNT
@@ -444,11 +419,8 @@ mod pattern_informative_markers {
     fn last_match_at_limit_shows_n_of_n() {
         // 11 matches, -m 5 → match 5 says "match 5/5 shown"
         // Matches spaced 10 apart so contexts (±3) don't overlap
-        let match_positions: Vec<usize> = (20..=70).step_by(10).collect(); // 20,30,40,50,60,70 = 6 matches
-                                                                           // Plus extras to make > 5: add more beyond
-        let mut positions: Vec<usize> = (15..=85).step_by(10).collect(); // 15,25,35,45,55,65,75,85 = 8 matches
-                                                                         // Actually, let's just use widely-spaced matches in range 20-80
-        positions = vec![20, 30, 40, 50, 60, 70, 75, 80]; // 8 matches, first 5 shown
+        // 8 widely-spaced matches in range 20-80, first 5 shown
+        let positions = vec![20, 30, 40, 50, 60, 70, 75, 80];
         let input = generate_lines_with_matches(100, &positions, "ERROR");
 
         let mut cmd = trunc();
@@ -0,0 +1,62 @@
+//! Tests for `--json-values`: a JSON line has its long string values cut one
+//! at a time, so it stays valid JSON.
+
+mod common;
+
+use common::run;
+
+#[test]
+fn long_values_cut_individually() {
+    let input = format!(
+        "{{\"level\":\"error\",\"body\":\"{}\",\"trace\":[\"{}\"]}}\n",
+        "a".repeat(300),
+        "b".repeat(50)
+    );
+    let stdout = run(&["--json-values", "-w", "20"], input);
+    assert_eq!(
+        stdout,
+        format!(
+            "{{\"level\":\"error\",\"body\":\"{}…[+280 chars]\",\"trace\":[\"{}…[+30 chars]\"]}}\n",
+            "a".repeat(20),
+            "b".repeat(20)
+        )
+    );
+}
+
+#[test]
+fn keys_never_cut() {
+    let key = "k".repeat(100);
+    let input = format!("{{\"{}\":1}}\n", key);
+    let stdout = run(&["--json-values", "-w", "10"], input.clone());
+    assert_eq!(stdout, input);
+}
+
+#[test]
+fn escapes_not_split() {
+    // Each é is one char; cutting at 3 must not leave half of one
+    let input = format!("[\"{}\"]\n", "\\u00e9".repeat(40));
+    let stdout = run(&["--json-values", "-w", "3"], input);
+    assert_eq!(stdout, "[\"\\u00e9\\u00e9\\u00e9…[+37 chars]\"]\n");
+}
+
+#[test]
+fn non_json_lines_truncated_as_usual() {
+    let input = format!("{{not json {}}}\n", "x".repeat(100));
+    let stdout = run(&["--json-values", "-w", "10"], input);
+    assert!(stdout.contains(" chars ...]"), "Got: {}", stdout);
+}
+
+#[test]
+fn off_by_default() {
+    let input = format!("{{\"body\":\"{}\"}}\n", "a".repeat(300));
+    let stdout = run(&["-w", "20"], input);
+    assert!(stdout.contains("[... 271 chars ...]"), "Got: {}", stdout);
+}
+
+#[test]
+fn short_overrun_left_intact() {
+    // Cutting 2 chars would add more than it saves
+    let input = format!("{{\"body\":\"{}\"}}\n", "a".repeat(22));
+    let stdout = run(&["--json-values", "-w", "20"], input.clone());
+    assert_eq!(stdout, input);
+}
@@ -0,0 +1,77 @@
+//! Tests for `--keep-header`: the first K input lines are always shown, on
+//! top of the head.
+
+mod common;
+
+use common::{generate_lines, trunc};
+use predicates::prelude::*;
+
+#[test]
+fn header_shown_with_no_head() {
+    trunc()
+        .args(["--keep-header", "-f", "0", "-l", "1"])
+        .write_stdin(generate_lines(100))
+        .assert()
+        .success()
+        .stdout("line 1\n[... 98 lines truncated ...]\nline 100\n");
+}
+
+#[test]
+fn header_doesnt_count_against_head() {
+    trunc()
+        .args(["--keep-header", "-f", "2", "-l", "1"])
+        .write_stdin(generate_lines(100))
+        .assert()
+        .success()
+        .stdout("line 1\nline 2\nline 3\n[... 96 lines truncated ...]\nline 100\n");
+}
+
+#[test]
+fn several_header_lines() {
+    trunc()
+        .args(["--keep-header=3", "-f", "0", "-l", "0"])
+        .write_stdin(generate_lines(100))
+        .assert()
+        .success()
+        .stdout("line 1\nline 2\nline 3\n[... 97 lines truncated ...]\n");
+}
+
+#[test]
+fn count_after_a_space() {
+    trunc()
+        .args(["--keep-header", "3", "-f", "0", "-l", "0"])
+        .write_stdin(generate_lines(100))
+        .assert()
+        .success()
+        .stdout("line 1\nline 2\nline 3\n[... 97 lines truncated ...]\n");
+}
+
+#[test]
+fn header_with_matches() {
+    trunc()
+        .args(["--keep-header", "-f", "0", "-l", "0", "-C", "0", "^line 50$"])
+        .write_stdin(generate_lines(100))
+        .assert()
+        .success()
+        .stdout(
+            "line 1\n[... 48 lines truncated, match 1 shown ...]\nline 50\n[... 50 lines truncated ...]\n",
+        );
+}
+
+#[test]
+fn header_of_each_file() {
+    let dir = tempfile::tempdir().unwrap();
+    let a = dir.path().join("a.csv");
+    let b = dir.path().join("b.csv");
+    std::fs::write(&a, format!("id,name\n{}\n", generate_lines(50))).unwrap();
+    std::fs::write(&b, format!("id,size\n{}\n", generate_lines(50))).unwrap();
+    trunc()
+        .args(["--keep-header", "-f", "0", "-l", "1"])
+        .arg("--files")
+        .arg(&a)
+        .arg(&b)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("<==\nid,name\n[... 49 lines"))
+        .stdout(predicate::str::contains("<==\nid,size\n[... 49 lines"));
+}
@@ -0,0 +1,118 @@
+//! Tests for `--lang rust`: a match on a compiler diagnostic shows the whole
+//! diagnostic as its context.
+
+mod common;
+
+use common::{generate_lines, trunc};
+use predicates::prelude::*;
+
+const DIAGNOSTIC: &str = "error[E0308]: mismatched types
+ --> src/main.rs:2:18
+  |
+2 |     let x: u32 = \"a\";
+  |            ---   ^^^ expected `u32`, found `&str`
+  |            |
+  |            expected due to this
+  |
+help: try using a conversion method
+  |
+2 |     let x: u32 = \"a\".parse().unwrap();
+  |                     +++++++++++++++++
+note: the type is inferred from here
+";
+
+/// 100 lines of noise with `DIAGNOSTIC` and a blank line after line 50.
+fn build_log() -> String {
+    format!(
+        "{}\n{}\n{}\n",
+        generate_lines(50),
+        DIAGNOSTIC,
+        (51..=100)
+            .map(|i| format!("line {}", i))
+            .collect::<Vec<_>>()
+            .join("\n")
+    )
+}
+
+#[test]
+fn match_shows_whole_diagnostic() {
+    trunc()
+        .args(["--lang", "rust", "-f", "1", "-l", "1", r"^error\["])
+        .write_stdin(build_log())
+        .assert()
+        .success()
+        .stdout(format!(
+            "line 1\n[... 49 lines truncated, match 1 shown ...]\n{}[... 50 lines truncated ...]\nline 100\n",
+            DIAGNOSTIC
+        ));
+}
+
+#[test]
+fn context_flags_still_apply_to_other_matches() {
+    trunc()
+        .args([
+            "--lang",
+            "rust",
+            "-f",
+            "0",
+            "-l",
+            "0",
+            "-C",
+            "1",
+            "^line 70$",
+        ])
+        .write_stdin(build_log())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "match 1 shown ...]\nline 69\nline 70\nline 71\n[...",
+        ));
+}
+
+#[test]
+fn match_inside_diagnostic_shows_all_of_it() {
+    trunc()
+        .args([
+            "--lang",
+            "rust",
+            "-f",
+            "0",
+            "-l",
+            "0",
+            "-C",
+            "0",
+            "expected due",
+        ])
+        .write_stdin(build_log())
+        .assert()
+        .success()
+        .stdout(format!(
+            "[... 50 lines truncated, match 1 shown ...]\n{}[... 51 lines truncated ...]\n",
+            DIAGNOSTIC
+        ));
+}
+
+#[test]
+fn without_lang_context_is_arbitrary() {
+    trunc()
+        .args(["-f", "0", "-l", "0", "-C", "1", r"^error\["])
+        .write_stdin(build_log())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "line 50\nerror[E0308]: mismatched types\n --> src/main.rs:2:18\n[...",
+        ));
+}
+
+#[test]
+fn cargo_preset_groups_diagnostics() {
+    trunc()
+        .args(["--preset", "cargo", "-f", "1", "-l", "1"])
+        .write_stdin(build_log())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(format!(
+            "match 1 shown ...]\n{}[...",
+            DIAGNOSTIC
+        )));
+}
@@ -0,0 +1,109 @@
+//! Tests for `--level-histogram`: one line on stderr at the end counting the
+//! input's lines at each log level.
+
+mod common;
+
+use common::trunc;
+use predicates::prelude::*;
+use std::io::Write;
+
+/// `n` log lines: every 50th an ERROR, every 10th a WARN, the rest INFO.
+fn build_log(n: usize) -> String {
+    (1..=n)
+        .map(|i| {
+            let level = if i % 50 == 0 {
+                "ERROR"
+            } else if i % 10 == 0 {
+                "WARN"
+            } else {
+                "INFO"
+            };
+            format!("2024-05-01T12:00:00Z {} event {}\n", level, i)
+        })
+        .collect()
+}
+
+#[test]
+fn counts_whole_input() {
+    trunc()
+        .args(["--level-histogram", "-f", "2", "-l", "2"])
+        .write_stdin(build_log(1000))
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("[... 996 lines truncated ...]"))
+        .stderr("[trunc levels] ERROR 20 | WARN 80 | INFO 900 | DEBUG 0 | TRACE 0\n");
+}
+
+#[test]
+fn most_severe_level_wins() {
+    let input = "ERROR retry failed after WARN\nWARNING disk\nCRITICAL db\n\
+                 level=debug msg=poll\nno level here\n";
+    trunc()
+        .arg("--level-histogram")
+        .write_stdin(input)
+        .assert()
+        .success()
+        .stderr("[trunc levels] ERROR 2 | WARN 1 | INFO 0 | DEBUG 1 | TRACE 0\n");
+}
+
+#[test]
+fn custom_levels() {
+    let input = "E0001 bad\nW0002 meh\nE0003 worse\nfine\n";
+    trunc()
+        .args([
+            "--level-histogram",
+            "--level",
+            r"E=^E\d{4}",
+            "--level",
+            r"W=^W\d{4}",
+        ])
+        .write_stdin(input)
+        .assert()
+        .success()
+        .stderr("[trunc levels] E 2 | W 1\n");
+}
+
+#[test]
+fn after_stats() {
+    trunc()
+        .args(["--level-histogram", "--stats"])
+        .write_stdin(build_log(10))
+        .assert()
+        .success()
+        .stderr(predicate::str::starts_with("[trunc stats]\n"))
+        .stderr(predicate::str::ends_with(
+            "[trunc levels] ERROR 0 | WARN 1 | INFO 9 | DEBUG 0 | TRACE 0\n",
+        ));
+}
+
+#[test]
+fn file_middle_read() {
+    let mut file = tempfile::NamedTempFile::new().unwrap();
+    file.write_all(build_log(1000).as_bytes()).unwrap();
+    trunc()
+        .args(["--level-histogram", "-f", "1", "-l", "1"])
+        .arg("--files")
+        .arg(file.path())
+        .assert()
+        .success()
+        .stderr("[trunc levels] ERROR 20 | WARN 80 | INFO 900 | DEBUG 0 | TRACE 0\n");
+}
+
+#[test]
+fn level_needs_histogram() {
+    trunc()
+        .args(["--level", "E=^E"])
+        .write_stdin("")
+        .assert()
+        .failure();
+}
+
+#[test]
+fn level_without_name_rejected() {
+    trunc()
+        .args(["--level-histogram", "--level", "^E"])
+        .write_stdin("")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("expected NAME=REGEX"));
+}
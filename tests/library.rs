@@ -0,0 +1,657 @@
+//! Tests for the library API (`trunc::Truncator`).
+//!
+//! The binary is a thin wrapper over the library, so these check that
+//! embedding it gives the same output as piping through `trunc`.
+
+mod common;
+
+use common::{generate_lines_with_matches, run};
+use regex::Regex;
+use std::io::{self, Write};
+use trunc::{
+    truncate_line_cut, LineCut, Predicate, Redactor, Selection, TimestampRewrite, Trailer,
+    TruncatedItem, TruncatedLines, Truncator, WidthMode,
+};
+
+/// Run `truncator` over `input`, returning what it wrote.
+fn process(truncator: &Truncator, input: &str) -> String {
+    let mut out = Vec::new();
+    truncator.process(input.as_bytes(), &mut out).unwrap();
+    String::from_utf8(out).unwrap()
+}
+
+#[test]
+fn defaults_match_cli() {
+    let input = generate_lines_with_matches(500, &[], "");
+
+    assert_eq!(process(&Truncator::new(), &input), run(&[], &input));
+}
+
+#[test]
+fn pattern_mode_matches_cli() {
+    let input = generate_lines_with_matches(500, &[100, 200, 300], "ERROR");
+    let truncator = Truncator::new()
+        .first(5)
+        .last(5)
+        .matches(2)
+        .context(1)
+        .pattern(Regex::new("ERROR").unwrap());
+
+    assert_eq!(
+        process(&truncator, &input),
+        run(
+            &["-f", "5", "-l", "5", "-m", "2", "-C", "1", "ERROR"],
+            &input
+        )
+    );
+}
+
+#[test]
+fn width_max_bytes_and_trailer_match_cli() {
+    let long_line = "x".repeat(1000);
+    let input = vec![long_line.as_str(); 200].join("\n");
+    let truncator = Truncator::new()
+        .width(20)
+        .max_bytes(2000)
+        .trailer(Trailer::Checksum);
+
+    assert_eq!(
+        process(&truncator, &input),
+        run(
+            &["-w", "20", "--max-bytes", "2000", "--trailer", "checksum"],
+            &input
+        )
+    );
+}
+
+#[test]
+fn fixed_strings_match_cli() {
+    let input = generate_lines_with_matches(300, &[100, 200], "[ERROR]");
+    let truncator = Truncator::new().first(5).last(5).fixed_strings(["[ERROR]"]);
+
+    assert_eq!(
+        process(&truncator, &input),
+        run(&["-f", "5", "-l", "5", "--fixed-string", "[ERROR]"], &input)
+    );
+}
+
+#[test]
+fn streams_share_an_output() {
+    let truncator = Truncator::new().first(1).last(1);
+    let mut buf = Vec::new();
+    let mut out = truncator.output(&mut buf);
+
+    for (name, lines) in [("a", ["a1", "a2", "a3"]), ("b", ["b1", "b2", "b3"])] {
+        out.header(name);
+        let mut stream = truncator.stream(None);
+        for line in lines {
+            stream.line(line, &mut out);
+        }
+        stream.finish(&mut out);
+    }
+    out.finish(6, 18).unwrap();
+
+    assert_eq!(
+        String::from_utf8(buf).unwrap(),
+        "==> a <==\na1\n[... 1 lines truncated ...]\na3\n\n\
+         ==> b <==\nb1\n[... 1 lines truncated ...]\nb3\n"
+    );
+}
+
+#[test]
+fn write_errors_are_returned() {
+    struct Closed;
+    impl Write for Closed {
+        fn write(&mut self, _: &[u8]) -> io::Result<usize> {
+            Err(io::Error::from(io::ErrorKind::BrokenPipe))
+        }
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    let err = Truncator::new()
+        .process("a\nb\n".as_bytes(), Closed)
+        .unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::BrokenPipe);
+}
+
+#[test]
+fn named_patterns_label_markers() {
+    let input = generate_lines_with_matches(100, &[50], "boom");
+    let set = regex::RegexSet::new(["bo+m"]).unwrap();
+    let truncator = Truncator::new()
+        .first(1)
+        .last(1)
+        .context(0)
+        .named_patterns(set, ["explosion"])
+        .label_matches(true);
+
+    assert!(process(&truncator, &input)
+        .contains("[... 48 lines truncated, match 1 shown ('explosion') ...]\n"));
+}
+
+#[test]
+fn severity_selection_matches_cli() {
+    let input = (1..=300)
+        .map(|i| match i {
+            50 | 100 => format!("line {} WARN slow", i),
+            200 => format!("line {} ERROR boom", i),
+            _ => format!("line {}", i),
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+    let truncator = Truncator::new()
+        .first(5)
+        .last(5)
+        .matches(1)
+        .context(0)
+        .pattern(Regex::new("WARN|ERROR").unwrap())
+        .select(Selection::severity());
+
+    let out = process(&truncator, &input);
+    assert!(out.contains("line 200 ERROR boom\n"), "Got: {}", out);
+    assert_eq!(
+        out,
+        run(
+            &[
+                "-f",
+                "5",
+                "-l",
+                "5",
+                "-m",
+                "1",
+                "-C",
+                "0",
+                "--rank-by",
+                "severity",
+                "WARN|ERROR"
+            ],
+            &input
+        )
+    );
+}
+
+#[test]
+fn before_and_after_context_match_cli() {
+    let input = generate_lines_with_matches(200, &[100], "ERROR");
+    let truncator = Truncator::new()
+        .first(5)
+        .last(5)
+        .before_context(1)
+        .after_context(4)
+        .pattern(Regex::new("ERROR").unwrap());
+
+    assert_eq!(
+        process(&truncator, &input),
+        run(
+            &["-f", "5", "-l", "5", "-B", "1", "-A", "4", "ERROR"],
+            &input
+        )
+    );
+}
+
+#[test]
+fn redactor_matches_cli() {
+    let input = generate_lines_with_matches(100, &[1, 50, 100], "password=hunter2");
+    let truncator = Truncator::new()
+        .first(5)
+        .last(5)
+        .pattern(Regex::new("password").unwrap())
+        .redact(Redactor::builtin().pattern(Regex::new(r"line \d+").unwrap()));
+
+    let out = process(&truncator, &input);
+    assert!(!out.contains("hunter2"), "Got: {}", out);
+    assert_eq!(
+        out,
+        run(
+            &[
+                "-f",
+                "5",
+                "-l",
+                "5",
+                "--redact",
+                "--redact-pattern",
+                r"line \d+",
+                "password"
+            ],
+            &input
+        )
+    );
+}
+
+#[test]
+fn match_filter_matches_cli() {
+    let input: String = (1..=100)
+        .map(|i| match i % 10 {
+            0 => format!("line {} ERROR timeout\n", i),
+            5 => format!("line {} ERROR timeout, retrying\n", i),
+            _ => format!("line {} ERROR\n", i),
+        })
+        .collect();
+    let truncator = Truncator::new()
+        .first(5)
+        .last(5)
+        .pattern(Regex::new("ERROR").unwrap())
+        .match_filter(Predicate::All(vec![
+            Predicate::all_of([Regex::new("timeout").unwrap()]),
+            Predicate::any_of([Regex::new("retrying").unwrap()]).negate(),
+        ]));
+
+    let out = process(&truncator, &input);
+    assert!(out.contains("match 1 shown ...]\n"), "Got: {}", out);
+    assert_eq!(
+        out,
+        run(
+            &[
+                "-f",
+                "5",
+                "-l",
+                "5",
+                "--all-of",
+                "timeout",
+                "--and-not",
+                "retrying",
+                "-e",
+                "ERROR"
+            ],
+            &input
+        )
+    );
+}
+
+#[test]
+fn line_endings_match_cli() {
+    // CRLF endings, a lone \r mid-line, and a last line with no newline
+    let input: String = (1..=80)
+        .map(|i| format!("line {}\r\n", i))
+        .chain(["half\rway\n".to_string(), "last\r".to_string()])
+        .collect();
+    let truncator = Truncator::new().first(3).last(3);
+    assert_eq!(
+        process(&truncator, &input),
+        run(&["-f", "3", "-l", "3"], &input)
+    );
+}
+
+#[test]
+fn pass_through_matches_cli() {
+    let input = generate_lines_with_matches(20, &[5, 15], "ERROR");
+    let truncator = Truncator::new()
+        .first(10)
+        .last(10)
+        .pattern(Regex::new("ERROR").unwrap())
+        .pass_through(0);
+
+    assert_eq!(
+        process(&truncator, &input),
+        run(
+            &["--only-if-needed", "-f", "10", "-l", "10", "ERROR"],
+            &input
+        )
+    );
+    assert_eq!(process(&truncator, &input), format!("{}\n", input));
+}
+
+#[test]
+fn rebalance_matches_cli() {
+    let input = generate_lines_with_matches(100, &[50], "ERROR");
+    let truncator = Truncator::new()
+        .first(3)
+        .last(3)
+        .matches(3)
+        .context(1)
+        .pattern(Regex::new("ERROR").unwrap())
+        .rebalance(true);
+
+    assert_eq!(
+        process(&truncator, &input),
+        run(
+            &[
+                "--rebalance",
+                "-f",
+                "3",
+                "-l",
+                "3",
+                "-m",
+                "3",
+                "-C",
+                "1",
+                "ERROR"
+            ],
+            &input
+        )
+    );
+}
+
+#[test]
+fn tail_matches_matches_cli() {
+    let input = generate_lines_with_matches(100, &[10, 40, 70, 90], "ERROR");
+    let truncator = Truncator::new()
+        .first(2)
+        .matches(1)
+        .context(1)
+        .pattern(Regex::new("ERROR").unwrap())
+        .tail_matches(2);
+
+    assert_eq!(
+        process(&truncator, &input),
+        run(
+            &[
+                "--tail-matches",
+                "2",
+                "-f",
+                "2",
+                "-m",
+                "1",
+                "-C",
+                "1",
+                "ERROR"
+            ],
+            &input
+        )
+    );
+}
+
+#[test]
+fn normalize_timestamps_matches_cli() {
+    let input: String = (0..100)
+        .map(|i| format!("2024-05-01T12:{:02}:00Z line {}\n", i % 60, i))
+        .collect();
+    let truncator = Truncator::new()
+        .first(3)
+        .last(3)
+        .rewrite_timestamps(TimestampRewrite::Relative);
+
+    assert_eq!(
+        process(&truncator, &input),
+        run(
+            &["--normalize-timestamps", "relative", "-f", "3", "-l", "3"],
+            &input
+        )
+    );
+}
+
+#[test]
+fn crlf_byte_ranges_match_cli() {
+    let input = "a\r\nb\r\nc\r\nd\r\ne\r\n";
+    let truncator = Truncator::new().first(1).last(1).byte_ranges(true);
+
+    let stdout = process(&truncator, input);
+    assert!(stdout.contains("(bytes 3-12)"), "{}", stdout);
+    assert_eq!(stdout, run(&["-f1", "-l1", "--byte-ranges"], input));
+}
+
+#[test]
+fn truncate_line_cut_keeps_one_end() {
+    let line = "a".repeat(50) + &"b".repeat(50);
+    let cut = truncate_line_cut(&line, 10, WidthMode::Chars, LineCut::Start);
+    assert_eq!(cut.text, format!("[... 80 chars ...]{}", "b".repeat(20)));
+    assert_eq!(cut.bytes_removed, 80);
+    assert_eq!(cut.markers.len(), 1);
+    assert_eq!(cut.markers[0], 0..18);
+}
+
+#[test]
+fn section_widths_match_cli() {
+    let input = generate_lines_with_matches(100, &[50], "ERROR") + &"x".repeat(500) + "\n";
+    let truncator = Truncator::new()
+        .first(2)
+        .last(2)
+        .width(20)
+        .match_width(60)
+        .tail_width(5)
+        .pattern(Regex::new("ERROR").unwrap());
+
+    assert_eq!(
+        process(&truncator, &input),
+        run(
+            &[
+                "-f",
+                "2",
+                "-l",
+                "2",
+                "-w",
+                "20",
+                "--width-match",
+                "60",
+                "--width-tail",
+                "5",
+                "ERROR"
+            ],
+            &input
+        )
+    );
+}
+
+#[test]
+fn trunc_writer_matches_process() {
+    let input = generate_lines_with_matches(500, &[100, 200, 300], "ERROR") + "\n";
+    let truncator = Truncator::new()
+        .first(5)
+        .last(5)
+        .pattern(Regex::new("ERROR").unwrap());
+
+    let mut writer = truncator.writer(Vec::new());
+    writer.write_all(input.as_bytes()).unwrap();
+    let out = String::from_utf8(writer.finish().unwrap()).unwrap();
+    assert_eq!(out, process(&truncator, &input));
+}
+
+#[test]
+fn trunc_writer_joins_lines_split_across_writes() {
+    let input = generate_lines_with_matches(200, &[], "") + "\n";
+    let truncator = Truncator::new().first(3).last(3);
+
+    let mut writer = truncator.writer(Vec::new());
+    // Odd-sized pieces, so lines are cut mid-way
+    for piece in input.as_bytes().chunks(7) {
+        writer.write_all(piece).unwrap();
+    }
+    let out = String::from_utf8(writer.finish().unwrap()).unwrap();
+    assert_eq!(out, process(&truncator, &input));
+}
+
+#[test]
+fn trunc_writer_counts_crlf_like_the_cli() {
+    let input = "a\r\nb\r\nc\r\nd\r\ne\r\n";
+    let truncator = Truncator::new().first(1).last(1).byte_ranges(true);
+
+    let mut writer = truncator.writer(Vec::new());
+    writer.write_all(input.as_bytes()).unwrap();
+    let out = String::from_utf8(writer.finish().unwrap()).unwrap();
+    assert_eq!(out, run(&["-f1", "-l1", "--byte-ranges"], input));
+}
+
+#[test]
+fn trunc_writer_cuts_long_lines_like_the_cli() {
+    let long = "x".repeat(300_000);
+    let input = format!("a\n{}\nb\nc\n", long);
+    let truncator = Truncator::new()
+        .first(2)
+        .last(1)
+        .width(0)
+        .max_line_bytes(1000)
+        .trailer(Trailer::Checksum);
+
+    let mut writer = truncator.writer(Vec::new());
+    // The long line arrives a piece at a time
+    for piece in input.as_bytes().chunks(4096) {
+        writer.write_all(piece).unwrap();
+    }
+    let out = String::from_utf8(writer.finish().unwrap()).unwrap();
+    assert!(out.contains("[... 299000 bytes ...]"), "{}", out);
+    assert_eq!(
+        out,
+        run(
+            &[
+                "-f2",
+                "-l1",
+                "-w0",
+                "--max-line-bytes",
+                "1000",
+                "--trailer",
+                "checksum"
+            ],
+            &input
+        )
+    );
+}
+
+#[test]
+fn trunc_writer_truncates_a_last_line_without_newline() {
+    let truncator = Truncator::new().first(1).last(1);
+    let mut writer = truncator.writer(Vec::new());
+    write!(writer, "one\ntwo\nthree\nfour").unwrap();
+    let out = String::from_utf8(writer.finish().unwrap()).unwrap();
+    assert_eq!(out, "one\n[... 2 lines truncated ...]\nfour\n");
+}
+
+#[test]
+fn trunc_writer_streams_the_head_and_finishes_on_drop() {
+    let truncator = Truncator::new().first(2).last(1);
+    let mut out = Vec::new();
+    {
+        let mut writer = truncator.writer(&mut out);
+        writeln!(writer, "a\nb\nc\nd").unwrap();
+        writer.flush().unwrap();
+    }
+    assert_eq!(
+        String::from_utf8(out).unwrap(),
+        "a\nb\n[... 1 lines truncated ...]\nd\n"
+    );
+}
+
+#[test]
+fn trunc_writer_with_a_selection_matches_process() {
+    let input: String = (1..=300)
+        .map(|i| match i {
+            50 => format!("line {} WARN slow\n", i),
+            200 => format!("line {} ERROR boom\n", i),
+            _ => format!("line {}\n", i),
+        })
+        .collect();
+    let truncator = Truncator::new()
+        .first(5)
+        .last(5)
+        .matches(1)
+        .context(0)
+        .pattern(Regex::new("WARN|ERROR").unwrap())
+        .select(Selection::severity());
+
+    let mut writer = truncator.writer(Vec::new());
+    writer.write_all(input.as_bytes()).unwrap();
+    let out = String::from_utf8(writer.finish().unwrap()).unwrap();
+    assert!(out.contains("line 200 ERROR boom\n"), "Got: {}", out);
+    assert_eq!(out, process(&truncator, &input));
+}
+
+/// `input`'s lines, for [`TruncatedLines`].
+fn owned_lines(input: &str) -> Vec<String> {
+    input.lines().map(String::from).collect()
+}
+
+#[test]
+fn truncated_lines_counts_what_each_omission_hides() {
+    let input = generate_lines_with_matches(100, &[], "");
+    let truncator = Truncator::new().first(2).last(2);
+    let items: Vec<_> = TruncatedLines::new(owned_lines(&input), &truncator).collect();
+
+    let hidden: usize = (3..=98).map(|i| format!("line {}", i).len() + 1).sum();
+    assert_eq!(
+        items,
+        [
+            TruncatedItem::Line("line 1".to_string()),
+            TruncatedItem::Line("line 2".to_string()),
+            TruncatedItem::Omission {
+                lines: 96,
+                bytes: hidden
+            },
+            TruncatedItem::Line("line 99".to_string()),
+            TruncatedItem::Line("line 100".to_string()),
+        ]
+    );
+}
+
+#[test]
+fn truncated_lines_renders_like_process() {
+    let input = generate_lines_with_matches(500, &[100, 300], "ERROR");
+    let truncator = Truncator::new()
+        .first(5)
+        .last(5)
+        .pattern(Regex::new("ERROR").unwrap());
+
+    let mut rendered = String::new();
+    let mut hidden = 0;
+    for item in TruncatedLines::new(owned_lines(&input), &truncator) {
+        match item {
+            TruncatedItem::Line(line) => rendered += &format!("{}\n", line),
+            TruncatedItem::Omission { lines, .. } => hidden += lines,
+        }
+    }
+    let processed = process(&truncator, &input);
+    let shown: String = processed
+        .lines()
+        .filter(|line| !line.starts_with("[... "))
+        .map(|line| format!("{}\n", line))
+        .collect();
+    assert_eq!(rendered, shown);
+    assert_eq!(hidden + rendered.lines().count(), 500);
+}
+
+#[test]
+fn truncated_lines_reports_excluded_lines() {
+    // Like the excluded-lines marker, after the lines shown
+    let truncator = Truncator::new().exclude(regex::RegexSet::new(["^debug"]).unwrap());
+    let lines = ["start", "debug one", "debug two", "end"].map(String::from);
+    let items: Vec<_> = TruncatedLines::new(lines, &truncator).collect();
+    assert_eq!(
+        items,
+        [
+            TruncatedItem::Line("start".to_string()),
+            TruncatedItem::Line("end".to_string()),
+            TruncatedItem::Omission {
+                lines: 2,
+                bytes: 20
+            },
+        ]
+    );
+}
+
+#[test]
+fn truncated_lines_with_a_selection_shows_the_chosen_match() {
+    let input: String = (1..=300)
+        .map(|i| match i {
+            50 => format!("line {} WARN slow\n", i),
+            200 => format!("line {} ERROR boom\n", i),
+            _ => format!("line {}\n", i),
+        })
+        .collect();
+    let truncator = Truncator::new()
+        .first(5)
+        .last(5)
+        .matches(1)
+        .context(0)
+        .pattern(Regex::new("WARN|ERROR").unwrap())
+        .select(Selection::severity());
+
+    let items: Vec<_> = TruncatedLines::new(owned_lines(&input), &truncator).collect();
+    assert!(items.contains(&TruncatedItem::Line("line 200 ERROR boom".to_string())));
+    assert!(!items.contains(&TruncatedItem::Line("line 50 WARN slow".to_string())));
+}
+
+#[test]
+fn engine_patterns_match_like_a_regex_set() {
+    let input = generate_lines_with_matches(500, &[100, 250, 400], "ERROR code=42");
+    let sources = ["ERROR", r"code=(\d+)"];
+    let by_set = Truncator::new()
+        .first(5)
+        .last(5)
+        .label_matches(true)
+        .unique_by("$1")
+        .patterns(regex::RegexSet::new(sources).unwrap());
+    let by_engine = by_set
+        .clone()
+        .engine_patterns(sources.map(|s| Regex::new(s).unwrap()));
+    assert_eq!(process(&by_engine, &input), process(&by_set, &input));
+}
@@ -0,0 +1,77 @@
+//! Tests for `--line-cut`: which part of an overlong line is cut out. The
+//! middle by default; `start` keeps the end, such as a path's file name,
+//! and `end` keeps the start.
+
+mod common;
+
+use common::run;
+
+const PATH: &str = "/home/user/projects/app/src/components/widgets/dashboard/panel.rs\n";
+
+#[test]
+fn middle_by_default() {
+    assert_eq!(
+        run(&["-w", "10"], PATH),
+        run(&["-w", "10", "--line-cut", "middle"], PATH)
+    );
+    assert_eq!(
+        run(&["-w", "10"], PATH),
+        "/home/user[... 45 chars ...]d/panel.rs\n"
+    );
+}
+
+#[test]
+fn start_keeps_the_end() {
+    assert_eq!(
+        run(&["-w", "10", "--line-cut", "start"], PATH),
+        "[... 45 chars ...]s/dashboard/panel.rs\n"
+    );
+}
+
+#[test]
+fn end_keeps_the_start() {
+    assert_eq!(
+        run(&["-w", "10", "--line-cut", "end"], PATH),
+        "/home/user/projects/[... 45 chars ...]\n"
+    );
+}
+
+#[test]
+fn short_lines_untouched() {
+    let line = "src/main.rs:42\n";
+    assert_eq!(run(&["-w", "10", "--line-cut", "start"], line), line);
+}
+
+#[test]
+fn graphemes_not_split() {
+    let line = format!("{}\n", "e\u{301}".repeat(300));
+    let kept = "e\u{301}".repeat(10);
+    assert_eq!(
+        run(&["-w", "5", "--line-cut", "start"], &line),
+        format!("[... 290 chars ...]{}\n", kept)
+    );
+    assert_eq!(
+        run(&["-w", "5", "--line-cut", "end"], &line),
+        format!("{}[... 290 chars ...]\n", kept)
+    );
+}
+
+#[test]
+fn end_cut_resets_open_style() {
+    let line = format!("\x1b[31m{}\x1b[0m\n", "x".repeat(100));
+    let stdout = run(&["-w", "5", "--line-cut", "end", "--keep-ansi"], &line);
+    assert_eq!(
+        stdout,
+        format!("\x1b[31m{}\x1b[0m[... 90 chars ...]\n", "x".repeat(10))
+    );
+}
+
+#[test]
+fn start_cut_replays_style() {
+    let line = format!("\x1b[31m{}\x1b[0m\n", "x".repeat(100));
+    let stdout = run(&["-w", "5", "--line-cut", "start", "--keep-ansi"], &line);
+    assert_eq!(
+        stdout,
+        format!("[... 90 chars ...]\x1b[31m{}\x1b[0m\n", "x".repeat(10))
+    );
+}
@@ -0,0 +1,106 @@
+//! Tests for `-n/--line-numbers`: every line shown is prefixed with its line
+//! number in the input, and markers are left alone.
+
+mod common;
+
+use common::{generate_lines, trunc};
+use predicates::prelude::*;
+
+#[test]
+fn head_and_tail_numbered() {
+    trunc()
+        .args(["-n", "-f", "2", "-l", "2"])
+        .write_stdin(generate_lines(100))
+        .assert()
+        .success()
+        .stdout(
+            "     1│ line 1\n     2│ line 2\n[... 96 lines truncated ...]\n    99│ line 99\n   100│ line 100\n",
+        );
+}
+
+#[test]
+fn long_flag() {
+    trunc()
+        .args(["--line-numbers", "-f", "1", "-l", "0"])
+        .write_stdin(generate_lines(5))
+        .assert()
+        .success()
+        .stdout(predicate::str::starts_with("     1│ line 1\n"));
+}
+
+#[test]
+fn off_by_default() {
+    trunc()
+        .write_stdin(generate_lines(3))
+        .assert()
+        .success()
+        .stdout("line 1\nline 2\nline 3\n");
+}
+
+#[test]
+fn matches_and_context_numbered() {
+    trunc()
+        .args(["-n", "-f", "2", "-l", "2", "-C", "1", "line 50$"])
+        .write_stdin(generate_lines(100))
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "[... 46 lines truncated, match 1 shown ...]\n    49│ line 49\n    50│ line 50\n    51│ line 51\n[... 47 lines truncated ...]\n",
+        ));
+}
+
+#[test]
+fn excluded_lines_keep_their_numbers() {
+    trunc()
+        .args(["-n", "--exclude", "^skip"])
+        .write_stdin("a\nskip 1\nskip 2\nb\n")
+        .assert()
+        .success()
+        .stdout(predicate::str::starts_with("     1│ a\n     4│ b\n"));
+}
+
+#[test]
+fn collapsed_run_gets_its_first_number() {
+    trunc()
+        .args(["-n", "--dedup-fuzzy"])
+        .write_stdin("start\nretry 1\nretry 2\nretry 3\nend\n")
+        .assert()
+        .success()
+        .stdout("     1│ start\n     2│ retry 1\n[... 3 similar lines ...]\n     5│ end\n");
+}
+
+#[test]
+fn gutter_not_counted_in_width() {
+    trunc()
+        .args(["-n", "-w", "3"])
+        .write_stdin("abcdefghijklmnopqrstuvwxyz\n")
+        .assert()
+        .success()
+        .stdout("     1│ abc[... 20 chars ...]xyz\n");
+}
+
+#[test]
+fn always_lines_numbered() {
+    trunc()
+        .args(["-n", "-f", "1", "-l", "1", "--always", "exit status"])
+        .write_stdin("a\nb\nexit status 2\nc\nd\n")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("     3│ exit status 2\n"));
+}
+
+#[test]
+fn each_file_numbered_from_one() {
+    let dir = tempfile::tempdir().unwrap();
+    let a = dir.path().join("a.log");
+    let b = dir.path().join("b.log");
+    std::fs::write(&a, "a1\na2\n").unwrap();
+    std::fs::write(&b, "b1\n").unwrap();
+    trunc()
+        .arg("-n")
+        .arg(&a)
+        .arg(&b)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("     1│ b1\n"));
+}
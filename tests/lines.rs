@@ -0,0 +1,107 @@
+//! Tests for `--lines N-M`: input line ranges shown wherever they fall,
+//! alongside head and tail.
+
+mod common;
+
+use common::{generate_lines, trunc};
+use predicates::prelude::*;
+
+#[test]
+fn range_with_head_and_tail() {
+    trunc()
+        .args(["-f", "2", "-l", "2", "--lines", "50-52"])
+        .write_stdin(generate_lines(100))
+        .assert()
+        .success()
+        .stdout(
+            "line 1\nline 2\n[... 47 lines truncated ...]\nline 50\nline 51\nline 52\n[... 46 lines truncated ...]\nline 99\nline 100\n",
+        );
+}
+
+#[test]
+fn range_only() {
+    trunc()
+        .args(["-f", "0", "-l", "0", "--lines", "3-4"])
+        .write_stdin(generate_lines(10))
+        .assert()
+        .success()
+        .stdout("[... 2 lines truncated ...]\nline 3\nline 4\n[... 6 lines truncated ...]\n");
+}
+
+#[test]
+fn single_line() {
+    trunc()
+        .args(["-f", "0", "-l", "0", "--lines", "7"])
+        .write_stdin(generate_lines(10))
+        .assert()
+        .success()
+        .stdout("[... 6 lines truncated ...]\nline 7\n[... 3 lines truncated ...]\n");
+}
+
+#[test]
+fn repeatable() {
+    trunc()
+        .args(["-f", "1", "-l", "1", "--lines", "20-21", "--lines", "80"])
+        .write_stdin(generate_lines(100))
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "line 20\nline 21\n[... 58 lines truncated ...]\nline 80\n",
+        ));
+}
+
+#[test]
+fn overlapping_head_not_repeated() {
+    trunc()
+        .args(["-f", "5", "-l", "0", "--lines", "4-6"])
+        .write_stdin(generate_lines(10))
+        .assert()
+        .success()
+        .stdout("line 1\nline 2\nline 3\nline 4\nline 5\nline 6\n[... 4 lines truncated ...]\n");
+}
+
+#[test]
+fn numbers_count_excluded_lines() {
+    trunc()
+        .args(["-f", "0", "-l", "0", "--exclude", "^skip", "--lines", "3"])
+        .write_stdin("a\nskip\nb\nc\n")
+        .assert()
+        .success()
+        .stdout(predicate::str::starts_with(
+            "[... 1 lines truncated ...]\nb\n",
+        ));
+}
+
+#[test]
+fn with_line_numbers() {
+    trunc()
+        .args(["-n", "-f", "0", "-l", "0", "--lines", "500-501"])
+        .write_stdin(generate_lines(1000))
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "   500│ line 500\n   501│ line 501\n",
+        ));
+}
+
+#[test]
+fn range_past_end_of_input() {
+    trunc()
+        .args(["-f", "0", "-l", "0", "--lines", "9-20"])
+        .write_stdin(generate_lines(10))
+        .assert()
+        .success()
+        .stdout("[... 8 lines truncated ...]\nline 9\nline 10\n");
+}
+
+#[test]
+fn invalid_ranges_rejected() {
+    for range in ["5-3", "0-3", "a-b", ""] {
+        trunc()
+            .args(["--lines", range])
+            .write_stdin("x\n")
+            .assert()
+            .failure()
+            .stderr(predicate::str::contains("--lines"));
+    }
+}
@@ -0,0 +1,117 @@
+//! Tests for `--longest K`: the K longest input lines, listed with their
+//! line numbers after the tail.
+
+mod common;
+
+use common::trunc;
+use predicates::prelude::*;
+
+/// 100 short lines with long ones at lines 30 (500 chars) and 70 (300).
+fn build_input() -> String {
+    (1..=100)
+        .map(|i| match i {
+            30 => format!("{}\n", "x".repeat(500)),
+            70 => format!("SELECT {}\n", "a".repeat(293)),
+            _ => format!("line {}\n", i),
+        })
+        .collect()
+}
+
+#[test]
+fn listed_after_tail() {
+    trunc()
+        .args(["--longest", "2", "-f", "1", "-l", "1", "-w", "10"])
+        .write_stdin(build_input())
+        .assert()
+        .success()
+        .stdout(
+            "line 1\n[... 98 lines truncated ...]\nline 100\n\
+             [... 2 longest lines ...]\n\
+             \x20   30\u{2502} xxxxxxxxxx[... 480 chars ...]xxxxxxxxxx\n\
+             \x20   70\u{2502} SELECT aaa[... 280 chars ...]aaaaaaaaaa\n",
+        );
+}
+
+#[test]
+fn ties_go_to_earlier_line() {
+    let input = "aa\nbbb\nccc\nd\nddd\n";
+    trunc()
+        .args(["--longest", "2"])
+        .write_stdin(input)
+        .assert()
+        .success()
+        .stdout(predicate::str::ends_with(
+            "[... 2 longest lines ...]\n     2\u{2502} bbb\n     3\u{2502} ccc\n",
+        ));
+}
+
+#[test]
+fn counted_in_chars() {
+    let input = format!("{}\n{}\n", "é".repeat(10), "e".repeat(12));
+    trunc()
+        .args(["--longest", "1"])
+        .write_stdin(input)
+        .assert()
+        .success()
+        .stdout(predicate::str::ends_with(
+            "[... 1 longest lines ...]\n     2\u{2502} eeeeeeeeeeee\n",
+        ));
+}
+
+#[test]
+fn with_a_pattern() {
+    trunc()
+        .args([
+            "--longest",
+            "1",
+            "-f",
+            "0",
+            "-l",
+            "0",
+            "-C",
+            "0",
+            "line 50$",
+        ])
+        .write_stdin(build_input())
+        .assert()
+        .success()
+        .stdout(
+            predicate::str::contains(
+                "0 matches found ...]\n[... 1 longest lines ...]\n    30\u{2502} ",
+            )
+            .not(),
+        )
+        .stdout(predicate::str::contains(
+            "[... 1 longest lines ...]\n    30\u{2502} ",
+        ));
+}
+
+#[test]
+fn compact_marker() {
+    trunc()
+        .args([
+            "--longest",
+            "1",
+            "--markers",
+            "compact",
+            "-f",
+            "0",
+            "-l",
+            "0",
+        ])
+        .write_stdin(build_input())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("…1L longest…\n    30\u{2502} "));
+}
+
+#[test]
+fn within_byte_limit() {
+    let output = trunc()
+        .args(["--longest", "2", "-w", "0", "--max-bytes", "400"])
+        .write_stdin(build_input())
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert!(output.stdout.len() <= 400);
+}
@@ -0,0 +1,124 @@
+//! Tests for `--markers-to-stderr`: stdout holds only input lines, and
+//! markers, headers, footer and trailer go to stderr.
+
+mod common;
+
+use common::{generate_lines, trunc};
+use predicates::prelude::*;
+
+#[test]
+fn markers_on_stderr() {
+    trunc()
+        .args(["-f", "2", "-l", "2", "--markers-to-stderr"])
+        .write_stdin(generate_lines(100))
+        .assert()
+        .success()
+        .stdout("line 1\nline 2\nline 99\nline 100\n")
+        .stderr("[... 96 lines truncated ...]\n");
+}
+
+#[test]
+fn pattern_mode() {
+    trunc()
+        .args([
+            "-f",
+            "1",
+            "-l",
+            "1",
+            "-C",
+            "0",
+            "--markers-to-stderr",
+            "line 50$",
+        ])
+        .write_stdin(generate_lines(100))
+        .assert()
+        .success()
+        .stdout("line 1\nline 50\nline 100\n")
+        .stderr("[... 48 lines truncated, match 1 shown ...]\n[... 49 lines truncated ...]\n");
+}
+
+#[test]
+fn headers_on_stderr() {
+    let dir = tempfile::tempdir().unwrap();
+    let a = dir.path().join("a.log");
+    let b = dir.path().join("b.log");
+    std::fs::write(&a, "a1\n").unwrap();
+    std::fs::write(&b, "b1\n").unwrap();
+    trunc()
+        .arg("--markers-to-stderr")
+        .arg("--files")
+        .arg(&a)
+        .arg(&b)
+        .assert()
+        .success()
+        .stdout("a1\nb1\n")
+        .stderr(format!(
+            "==> {} <==\n\n==> {} <==\n",
+            a.display(),
+            b.display()
+        ));
+}
+
+#[test]
+fn footer_and_trailer_on_stderr() {
+    trunc()
+        .args([
+            "-f",
+            "1",
+            "-l",
+            "1",
+            "--region-ids",
+            "--trailer",
+            "checksum",
+            "--markers-to-stderr",
+        ])
+        .write_stdin("a\nb\nc\n")
+        .assert()
+        .success()
+        .stdout("a\nc\n")
+        .stderr(predicate::str::starts_with(
+            "[#1: 1 lines truncated ...]\n[#1: line 2, bytes 2-4]\n[trunc sha256:",
+        ));
+}
+
+#[test]
+fn trailer_digest_covers_stdout() {
+    // sha256 of "a\nc\n"
+    trunc()
+        .args(["-f", "1", "-l", "1", "--trailer", "checksum", "--markers-to-stderr"])
+        .write_stdin("a\nb\nc\n")
+        .assert()
+        .success()
+        .stderr(
+            "[... 1 lines truncated ...]\n[trunc sha256:b72cf6d7918130f75347ff0f8b6e9fde004ee6d7fc26af90a349707207f72750 hidden-lines:1 hidden-bytes:2]\n",
+        );
+}
+
+#[test]
+fn cuts_within_lines_stay_on_stdout() {
+    trunc()
+        .args(["-w", "3", "--markers-to-stderr"])
+        .write_stdin("abcdefghijklmnopqrstuvwxyz\n")
+        .assert()
+        .success()
+        .stdout("abc[... 20 chars ...]xyz\n")
+        .stderr("");
+}
+
+#[test]
+fn byte_limit_leaves_markers_out() {
+    let output = trunc()
+        .args(["--max-bytes", "200", "--markers-to-stderr"])
+        .write_stdin(generate_lines(1000))
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stdout.len() <= 200, "Got {} bytes", stdout.len());
+    assert!(
+        stdout.lines().all(|l| l.starts_with("line ")),
+        "Got: {}",
+        stdout
+    );
+    assert!(stderr.contains("byte limit"), "Got: {}", stderr);
+}
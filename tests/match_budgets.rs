@@ -0,0 +1,154 @@
+//! Tests for per-pattern match budgets: `-e 'ERROR:5' -e 'WARN:2'` shows
+//! up to 5 ERROR matches and 2 WARN ones, each marker naming its pattern.
+
+mod common;
+
+use common::trunc;
+use predicates::prelude::*;
+
+/// A log of 100 lines: WARN every 5th line, ERROR on lines 50 and 90,
+/// INFO otherwise.
+fn build_log() -> String {
+    (1..=100)
+        .map(|i| match i {
+            50 | 90 => format!("ERROR failure {}\n", i),
+            _ if i % 5 == 0 => format!("WARN slow {}\n", i),
+            _ => format!("INFO ok {}\n", i),
+        })
+        .collect()
+}
+
+#[test]
+fn flood_does_not_crowd_out_other_pattern() {
+    trunc()
+        .args([
+            "-f", "0", "-l", "0", "-C", "0", "-e", "WARN:2", "-e", "ERROR:5",
+        ])
+        .write_stdin(build_log())
+        .assert()
+        .success()
+        .stdout(
+            "[... 4 lines truncated, match 1 of 'WARN' shown ...]\nWARN slow 5\n\
+             [... 4 lines truncated, match 2/2 of 'WARN' shown ...]\nWARN slow 10\n\
+             [... 39 lines truncated, match 1 of 'ERROR' shown ...]\nERROR failure 50\n\
+             [... 39 lines truncated, match 2 of 'ERROR' shown ...]\nERROR failure 90\n\
+             [... 10 lines and 16 matches truncated (20 total) ...]\n",
+        );
+}
+
+#[test]
+fn unbudgeted_patterns_share_m() {
+    trunc()
+        .args([
+            "-f", "0", "-l", "0", "-C", "0", "-m", "1", "-e", "ERROR:1", "-e", "WARN",
+        ])
+        .write_stdin(build_log())
+        .assert()
+        .success()
+        .stdout(
+            "[... 4 lines truncated, match 1/1 shown ...]\nWARN slow 5\n\
+             [... 44 lines truncated, match 1/1 of 'ERROR' shown ...]\nERROR failure 50\n\
+             [... 50 lines and 18 matches truncated (20 total) ...]\n",
+        );
+}
+
+#[test]
+fn line_charged_to_first_pattern_with_budget() {
+    trunc()
+        .args([
+            "-f", "0", "-l", "0", "-C", "0", "-m", "0", "-e", "fail:1", "-e", "ERROR:5",
+        ])
+        .write_stdin("a\nERROR fail 1\nb\nERROR fail 2\nc\n")
+        .assert()
+        .success()
+        .stdout(
+            "[... 1 lines truncated, match 1/1 of 'fail' shown ...]\nERROR fail 1\n\
+             [... 1 lines truncated, match 1 of 'ERROR' shown ...]\nERROR fail 2\n\
+             [... 1 lines truncated ...]\n",
+        );
+}
+
+#[test]
+fn escaped_colon_is_part_of_regex() {
+    trunc()
+        .args(["-f", "0", "-l", "0", "-C", "0", "-e", "port\\:80"])
+        .write_stdin("a\nport:80\nport 80\nb\n")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("match 1 shown ...]\nport:80\n"));
+}
+
+#[test]
+fn operand_pattern_keeps_its_colon() {
+    trunc()
+        .args(["-f", "0", "-l", "0", "-C", "0", "port:80"])
+        .write_stdin("a\nport\nport:80\nb\n")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("match 1 shown ...]\nport:80\n"));
+}
+
+#[test]
+fn single_pattern_keeps_its_colon() {
+    trunc()
+        .args(["-f", "0", "-l", "0", "-C", "0", "-e", "localhost:8080"])
+        .write_stdin("a\nlocalhost:8080\nlocalhost:9999\n")
+        .assert()
+        .success()
+        .stdout("[... 1 lines truncated, match 1 shown ...]\nlocalhost:8080\n[... 1 lines truncated ...]\n");
+}
+
+#[test]
+fn escaped_colon_with_fixed_strings() {
+    trunc()
+        .args([
+            "-f",
+            "0",
+            "-l",
+            "0",
+            "-C",
+            "0",
+            "--fixed-string",
+            "-e",
+            "host\\:80",
+            "-e",
+            "none",
+        ])
+        .write_stdin("a\nhost:80\nhost 80\n")
+        .assert()
+        .success()
+        .stdout(
+            "[... 1 lines truncated, match 1 shown ...]\nhost:80\n[... 1 lines truncated ...]\n",
+        );
+}
+
+#[test]
+fn max_per_budgets_a_single_pattern() {
+    trunc()
+        .args([
+            "-f",
+            "0",
+            "-l",
+            "0",
+            "-C",
+            "0",
+            "--max-per",
+            "WARN=2",
+            "WARN",
+        ])
+        .write_stdin(build_log())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("match 2/2 of 'WARN' shown"))
+        .stdout(predicate::str::contains("match 3").not());
+}
+
+#[test]
+fn max_per_unknown_pattern_fails() {
+    trunc()
+        .args(["--max-per", "ERROR=2", "WARN"])
+        .write_stdin(build_log())
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("not one of the patterns given"));
+}
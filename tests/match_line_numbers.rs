@@ -0,0 +1,109 @@
+//! Tests for `--match-line-numbers`: each match marker gives the match's
+//! line number in the input.
+
+mod common;
+
+use common::{generate_lines_with_errors, trunc};
+use predicates::prelude::*;
+
+#[test]
+fn markers_give_line_numbers() {
+    trunc()
+        .args([
+            "-f",
+            "2",
+            "-l",
+            "2",
+            "-C",
+            "1",
+            "--match-line-numbers",
+            "ERROR",
+        ])
+        .write_stdin(generate_lines_with_errors(100, &[40, 80]))
+        .assert()
+        .success()
+        .stdout(
+            "line 1\nline 2\n\
+             [... 36 lines truncated, match 1 @ line 40 shown ...]\n\
+             line 39\nline 40 ERROR\nline 41\n\
+             [... 37 lines truncated, match 2 @ line 80 shown ...]\n\
+             line 79\nline 80 ERROR\nline 81\n\
+             [... 17 lines truncated ...]\nline 99\nline 100\n",
+        );
+}
+
+#[test]
+fn numbers_count_excluded_lines() {
+    trunc()
+        .args([
+            "-f",
+            "0",
+            "-l",
+            "0",
+            "-C",
+            "0",
+            "--exclude",
+            "^line [1-9]$",
+            "--match-line-numbers",
+            "ERROR",
+        ])
+        .write_stdin(generate_lines_with_errors(30, &[20]))
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "match 1 @ line 20 shown ...]\nline 20 ERROR\n",
+        ));
+}
+
+#[test]
+fn compact_marker() {
+    trunc()
+        .args([
+            "-f",
+            "0",
+            "-l",
+            "0",
+            "-C",
+            "0",
+            "--markers",
+            "compact",
+            "--match-line-numbers",
+            "ERROR",
+        ])
+        .write_stdin(generate_lines_with_errors(10, &[5]))
+        .assert()
+        .success()
+        .stdout("\u{2026}4L @5\u{2026}\nline 5 ERROR\n\u{2026}5L\u{2026}\n");
+}
+
+#[test]
+fn with_label() {
+    trunc()
+        .args([
+            "-f",
+            "0",
+            "-l",
+            "0",
+            "-C",
+            "0",
+            "--label",
+            "--match-line-numbers",
+            "ERROR",
+        ])
+        .write_stdin(generate_lines_with_errors(10, &[5]))
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "match 1 @ line 5 shown ('ERROR') ...]",
+        ));
+}
+
+#[test]
+fn off_by_default() {
+    trunc()
+        .args(["-f", "0", "-l", "0", "-C", "0", "ERROR"])
+        .write_stdin(generate_lines_with_errors(10, &[5]))
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("@").not());
+}
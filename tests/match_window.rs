@@ -0,0 +1,106 @@
+//! Tests for keeping the match in view when a long match line is cut: if
+//! the match would fall in the part cut out, the kept part moves to show
+//! it, with a marker either side.
+
+mod common;
+
+use common::run;
+
+#[test]
+fn match_in_middle_is_kept() {
+    let line = format!("{}connection timeout{}\n", "a".repeat(400), "b".repeat(300));
+    assert_eq!(
+        run(&["-w", "10", "timeout"], &line),
+        "[... 404 chars ...]ection timeoutbbbbbb[... 294 chars ...]\n"
+    );
+}
+
+#[test]
+fn touching_matches_dont_move_the_cut() {
+    // Every x matches: the first is already in view, so the end is kept
+    let line = format!("{} END\n", "x".repeat(250));
+    assert_eq!(
+        run(&["-w", "10", "x"], &line),
+        format!("{}[... 234 chars ...]xxxxxx END\n", "x".repeat(10))
+    );
+}
+
+#[test]
+fn match_at_either_end_cut_as_usual() {
+    let line = format!("ERROR {}\n", "x".repeat(300));
+    assert_eq!(
+        run(&["-w", "10", "ERROR"], &line),
+        format!("ERROR xxxx[... 286 chars ...]{}\n", "x".repeat(10))
+    );
+    let line = format!("{} ERROR\n", "x".repeat(300));
+    assert_eq!(
+        run(&["-w", "10", "ERROR"], &line),
+        format!("{}[... 286 chars ...]xxxx ERROR\n", "x".repeat(10))
+    );
+}
+
+#[test]
+fn match_near_start_keeps_start() {
+    // Centred on the match, the kept part would start before the line
+    let line = format!("{}ERROR{}\n", "x".repeat(12), "y".repeat(300));
+    assert_eq!(
+        run(&["-w", "10", "ERROR"], &line),
+        format!("{}ERRORyyy[... 297 chars ...]\n", "x".repeat(12))
+    );
+}
+
+#[test]
+fn long_match_starts_the_kept_part() {
+    let line = format!("{}{}{}\n", "a".repeat(100), "E".repeat(50), "b".repeat(100));
+    assert_eq!(
+        run(&["-w", "10", "E+"], &line),
+        format!("[... 100 chars ...]{}[... 130 chars ...]\n", "E".repeat(20))
+    );
+}
+
+#[test]
+fn context_lines_cut_as_usual() {
+    let long = format!("{}needle{}", "a".repeat(100), "b".repeat(100));
+    let input = format!("{}\nERROR\n", long);
+    let stdout = run(
+        &["-w", "10", "-f", "0", "-l", "0", "-B", "1", "ERROR"],
+        &input,
+    );
+    assert!(
+        stdout.contains("\naaaaaaaaaa[... 186 chars ...]bbbbbbbbbb\n"),
+        "{}",
+        stdout
+    );
+}
+
+#[test]
+fn markers_dimmed_and_match_highlighted() {
+    let line = format!("{}timeout{}\n", "a".repeat(300), "b".repeat(300));
+    let input = format!("{}{}", "filler\n".repeat(50), line);
+    let stdout = run(
+        &[
+            "-w", "10", "-f", "0", "-l", "0", "--color", "always", "timeout",
+        ],
+        &input,
+    );
+    assert!(
+        stdout.contains("\x1b[2m[... 293 chars ...]\x1b[0maaaaaaa"),
+        "{:?}",
+        stdout
+    );
+    assert!(stdout.contains("timeout\x1b[0m"), "{:?}", stdout);
+    assert!(
+        stdout.contains("bbbbbb\x1b[2m[... 294 chars ...]\x1b[0m"),
+        "{:?}",
+        stdout
+    );
+}
+
+#[test]
+fn without_pattern_cut_as_usual() {
+    let line = format!("{}timeout{}\n", "a".repeat(300), "b".repeat(300));
+    assert_eq!(
+        run(&["-w", "10"], &line),
+        format!("{}[... 587 chars ...]{}\n", "a".repeat(10), "b".repeat(10))
+    );
+}
@@ -0,0 +1,168 @@
+//! Tests for the `--max-bytes` hard output budget.
+//!
+//! The line-count defaults bound output size only indirectly (see the
+//! worst-case arithmetic in `e2e.rs` `output_size`). `--max-bytes` makes the
+//! bound explicit: head, matches and tail shrink to fit, and the marker for
+//! a gap widened to save space says so.
+
+mod common;
+
+use common::{generate_lines, generate_lines_with_matches, run, trunc};
+use predicates::prelude::*;
+
+#[test]
+fn long_lines_stay_under_limit() {
+    let long_line = "x".repeat(10_000);
+    let input = vec![long_line.as_str(); 100].join("\n");
+
+    for limit in [500, 2000, 8000] {
+        let stdout = run(&["--max-bytes", &limit.to_string()], input.clone());
+        assert!(
+            stdout.len() <= limit,
+            "Output ({} bytes) exceeds --max-bytes {}",
+            stdout.len(),
+            limit
+        );
+        assert!(stdout.contains("byte limit"), "Got:\n{}", stdout);
+    }
+}
+
+#[test]
+fn pattern_mode_stays_under_limit() {
+    let long_line = "x".repeat(10_000);
+    let match_line = format!("{}ERROR{}", "y".repeat(5000), "z".repeat(5000));
+    let input = (1..=200)
+        .map(|i| {
+            if i % 20 == 0 {
+                match_line.as_str()
+            } else {
+                long_line.as_str()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    for limit in [1000, 4000, 16000] {
+        let stdout = run(&["--max-bytes", &limit.to_string(), "ERROR"], input.clone());
+        assert!(
+            stdout.len() <= limit,
+            "Output ({} bytes) exceeds --max-bytes {}",
+            stdout.len(),
+            limit
+        );
+    }
+}
+
+#[test]
+fn head_and_tail_shrink_together() {
+    // 30/30 lines of "line N" don't fit in 200 bytes; both ends give way
+    let stdout = run(&["--max-bytes", "200"], generate_lines(1000));
+
+    assert!(stdout.len() <= 200);
+    assert!(stdout.starts_with("line 1\n"));
+    assert!(stdout.ends_with("line 1000\n"));
+    let marker = stdout
+        .lines()
+        .find(|l| l.starts_with("[..."))
+        .expect("should have a marker");
+    assert!(
+        marker.ends_with(" lines truncated, byte limit ...]"),
+        "Got: {}",
+        marker
+    );
+
+    // Every input line is either shown or counted in the marker
+    let shown = stdout.lines().filter(|l| l.starts_with("line ")).count();
+    let hidden: usize = marker
+        .trim_start_matches("[... ")
+        .split(' ')
+        .next()
+        .unwrap()
+        .parse()
+        .unwrap();
+    assert_eq!(shown + hidden, 1000);
+}
+
+#[test]
+fn matches_dropped_for_space_are_counted() {
+    let input = generate_lines_with_matches(1000, &[200, 400, 600, 800], "ERROR");
+
+    let stdout = run(
+        &[
+            "-f",
+            "5",
+            "-l",
+            "5",
+            "-C",
+            "1",
+            "--max-bytes",
+            "400",
+            "ERROR",
+        ],
+        input,
+    );
+
+    assert!(stdout.len() <= 400);
+    assert!(stdout.contains("line 200 contains ERROR"));
+    assert!(!stdout.contains("line 800 contains ERROR"));
+    assert!(
+        stdout.contains("matches truncated (4 total), byte limit ...]"),
+        "Got:\n{}",
+        stdout
+    );
+}
+
+#[test]
+fn output_unchanged_when_it_fits() {
+    let input = generate_lines_with_matches(200, &[100], "ERROR");
+
+    let unlimited = run(&["ERROR"], input.clone());
+    let limited = run(&["--max-bytes", "100000", "ERROR"], input);
+
+    assert_eq!(limited, unlimited);
+    assert!(!limited.contains("byte limit"));
+}
+
+#[test]
+fn trailer_counts_against_limit() {
+    let stdout = run(
+        &["--max-bytes", "400", "--trailer", "checksum"],
+        generate_lines(1000),
+    );
+
+    assert!(stdout.len() <= 400);
+    assert!(stdout.lines().last().unwrap().starts_with("[trunc sha256:"));
+}
+
+#[test]
+fn multiple_files_share_limit() {
+    let dir = tempfile::tempdir().unwrap();
+    let a = dir.path().join("a.log");
+    let b = dir.path().join("b.log");
+    std::fs::write(&a, generate_lines(500)).unwrap();
+    std::fs::write(&b, generate_lines(500)).unwrap();
+
+    let assert = trunc()
+        .args(["--max-bytes", "600"])
+        .arg("--files")
+        .arg(&a)
+        .arg(&b)
+        .assert()
+        .success();
+    let stdout = String::from_utf8_lossy(&assert.get_output().stdout);
+
+    assert!(stdout.len() <= 600, "Got {} bytes", stdout.len());
+    assert!(stdout.contains(&format!("==> {} <==", a.display())));
+    assert!(stdout.contains(&format!("==> {} <==", b.display())));
+    assert_eq!(stdout.matches("line 500\n").count(), 2);
+}
+
+#[test]
+fn conflicts_with_follow() {
+    trunc()
+        .args(["-F", "--max-bytes", "1000"])
+        .write_stdin("")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("--max-bytes"));
+}
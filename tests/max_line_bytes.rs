@@ -0,0 +1,99 @@
+//! Tests for `--max-line-bytes SIZE`: lines longer than SIZE are cut as
+//! they're read, keeping their ends around a `[... N bytes ...]` note, so
+//! a giant line is never held whole.
+
+mod common;
+
+use common::trunc;
+use predicates::prelude::*;
+
+/// `start-`, `n` x's and `-end`, on a line of its own between two short ones.
+fn build_input(n: usize) -> String {
+    format!("first\nstart-{}-end\nlast\n", "x".repeat(n))
+}
+
+#[test]
+fn keeps_both_ends_around_a_note() {
+    // 5010 bytes; 512 kept from each end
+    let expected = format!(
+        "first\nstart-{}[... 3986 bytes ...]{}-end\nlast\n",
+        "x".repeat(506),
+        "x".repeat(508)
+    );
+    trunc()
+        .args(["--max-line-bytes", "1K", "-w", "0"])
+        .write_stdin(build_input(5000))
+        .assert()
+        .success()
+        .stdout(expected);
+}
+
+#[test]
+fn within_width_not_truncated_again() {
+    // With -w 100 the ends kept leave room for the note within 200 chars
+    let expected = format!(
+        "first\nstart-{}[... 4846 bytes ...]{}-end\nlast\n",
+        "x".repeat(76),
+        "x".repeat(78)
+    );
+    trunc()
+        .args(["--max-line-bytes", "1K", "-w", "100"])
+        .write_stdin(build_input(5000))
+        .assert()
+        .success()
+        .stdout(expected);
+}
+
+#[test]
+fn shorter_lines_untouched() {
+    let input = build_input(1000);
+    let plain = trunc()
+        .args(["-w", "0"])
+        .write_stdin(input.clone())
+        .output()
+        .unwrap()
+        .stdout;
+    trunc()
+        .args(["--max-line-bytes", "2K", "-w", "0"])
+        .write_stdin(input)
+        .assert()
+        .success()
+        .stdout(plain);
+}
+
+#[test]
+fn never_splits_a_char() {
+    trunc()
+        .args(["--max-line-bytes", "20", "-w", "0"])
+        .write_stdin("héllo wörld ünïcode ëvérywhere\n")
+        .assert()
+        .success()
+        .stdout("héllo wö[... 16 bytes ...]vérywhere\n");
+}
+
+#[test]
+fn cut_bytes_count_as_read_and_hidden() {
+    trunc()
+        .args(["--max-line-bytes", "1K", "-w", "0", "--status", "--stats"])
+        .write_stdin(build_input(5000))
+        .assert()
+        .code(3)
+        .stderr(predicate::str::contains("bytes read:    5022\n"));
+}
+
+#[test]
+fn matches_in_the_ends_still_found() {
+    let input = format!(
+        "{}ERROR {}\n{}",
+        "line\n".repeat(50),
+        "y".repeat(100_000),
+        "line\n".repeat(50)
+    );
+    trunc()
+        .args(["--max-line-bytes", "4K", "-f", "1", "-l", "1", "ERROR"])
+        .write_stdin(input)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("\nERROR yyyy"))
+        .stdout(predicate::str::contains(" bytes ...]yyyy"));
+}
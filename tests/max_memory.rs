@@ -0,0 +1,93 @@
+//! Tests for `--max-memory SIZE`: the tail and context buffers stay under
+//! SIZE bytes, dropping or cutting lines and saying so in a marker.
+
+mod common;
+
+use common::{generate_lines, trunc};
+use predicates::prelude::*;
+
+#[test]
+fn tail_shrinks_to_fit() {
+    // "line 99x" lines are 8 bytes, so 6 fit in 50
+    trunc()
+        .args(["-f", "2", "-l", "100", "--max-memory", "50"])
+        .write_stdin(generate_lines(1000))
+        .assert()
+        .success()
+        .stdout(
+            "line 1\nline 2\n[... 992 lines truncated, memory limit ...]\nline 995\nline 996\nline 997\nline 998\nline 999\nline 1000\n",
+        );
+}
+
+#[test]
+fn unchanged_when_it_fits() {
+    trunc()
+        .args(["-f", "2", "-l", "2", "--max-memory", "1K"])
+        .write_stdin(generate_lines(100))
+        .assert()
+        .success()
+        .stdout("line 1\nline 2\n[... 96 lines truncated ...]\nline 99\nline 100\n");
+}
+
+#[test]
+fn long_line_cut_when_buffered() {
+    let input = format!("{}\nend\n", "a".repeat(100_000));
+    trunc()
+        .args(["-f", "0", "-l", "2", "-w", "0", "--max-memory", "1K"])
+        .write_stdin(input)
+        .assert()
+        .success()
+        .stdout(format!(
+            "{}[... 99872 chars ...]{}\nend\n",
+            "a".repeat(64),
+            "a".repeat(64)
+        ));
+}
+
+#[test]
+fn head_lines_not_buffered() {
+    let input = format!("{}\nb\nc\n", "a".repeat(10_000));
+    trunc()
+        .args(["-f", "1", "-l", "1", "-w", "0", "--max-memory", "100"])
+        .write_stdin(input.clone())
+        .assert()
+        .success()
+        .stdout(predicate::str::starts_with(format!(
+            "{}\n",
+            "a".repeat(10_000)
+        )));
+}
+
+#[test]
+fn context_dropped_in_pattern_mode() {
+    trunc()
+        .args([
+            "-f",
+            "0",
+            "-l",
+            "0",
+            "-B",
+            "50",
+            "-A",
+            "0",
+            "--max-memory",
+            "32",
+            "line 500$",
+        ])
+        .write_stdin(generate_lines(1000))
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "match 1 shown, memory limit ...]\nline 496\nline 497\nline 498\nline 499\nline 500\n",
+        ));
+}
+
+#[test]
+fn invalid_size_rejected() {
+    trunc()
+        .args(["--max-memory", "lots"])
+        .write_stdin("a\n")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("--max-memory"));
+}
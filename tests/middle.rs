@@ -0,0 +1,84 @@
+//! Tests for `--middle N`: the N lines at the centre of each input are
+//! shown, with markers for what's cut either side.
+
+mod common;
+
+use common::{generate_numbers, trunc};
+use predicates::prelude::*;
+use std::io::Write;
+use tempfile::NamedTempFile;
+
+#[test]
+fn centre_only() {
+    trunc()
+        .args(["-f", "0", "-l", "0", "--middle", "4"])
+        .write_stdin(generate_numbers(100))
+        .assert()
+        .success()
+        .stdout("[... 48 lines truncated ...]\n49\n50\n51\n52\n[... 48 lines truncated ...]\n");
+}
+
+#[test]
+fn odd_line_out_goes_after() {
+    trunc()
+        .args(["-f", "0", "-l", "0", "--middle", "4"])
+        .write_stdin(generate_numbers(101))
+        .assert()
+        .success()
+        .stdout("[... 48 lines truncated ...]\n49\n50\n51\n52\n[... 49 lines truncated ...]\n");
+}
+
+#[test]
+fn with_head_and_tail() {
+    trunc()
+        .args(["-f", "1", "-l", "1", "--middle", "1"])
+        .write_stdin(generate_numbers(9))
+        .assert()
+        .success()
+        .stdout("1\n[... 3 lines truncated ...]\n5\n[... 3 lines truncated ...]\n9\n");
+}
+
+#[test]
+fn short_input_shown_whole() {
+    trunc()
+        .args(["-f", "0", "-l", "0", "--middle", "10"])
+        .write_stdin(generate_numbers(5))
+        .assert()
+        .success()
+        .stdout(generate_numbers(5));
+}
+
+#[test]
+fn each_file_by_its_own_length() {
+    let mut a = NamedTempFile::new().unwrap();
+    a.write_all(generate_numbers(10).as_bytes()).unwrap();
+    let mut b = NamedTempFile::new().unwrap();
+    b.write_all(generate_numbers(20).as_bytes()).unwrap();
+    trunc()
+        .args(["-f", "0", "-l", "0", "--middle", "2"])
+        .arg("--files")
+        .arg(a.path())
+        .arg(b.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("\n5\n6\n").and(predicate::str::contains("\n10\n11\n")));
+}
+
+#[test]
+fn not_with_follow() {
+    trunc()
+        .args(["--middle", "5", "-F"])
+        .write_stdin("line\n")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("--middle need the whole input"));
+}
+
+#[test]
+fn zero_rejected() {
+    trunc()
+        .args(["--middle", "0"])
+        .write_stdin("line\n")
+        .assert()
+        .failure();
+}
@@ -0,0 +1,126 @@
+//! Tests for matching several patterns with repeated `-e`.
+//!
+//! A line is a match if it matches any of the patterns; all matches share
+//! the one `-m` budget. `--label` says which pattern matched in each marker.
+
+mod common;
+
+use common::trunc;
+use predicates::prelude::*;
+
+/// Generate N lines, with the given suffixes appended at certain positions.
+fn generate_lines_with(n: usize, extra: &[(usize, &str)]) -> String {
+    (1..=n)
+        .map(|i| match extra.iter().find(|(at, _)| *at == i) {
+            Some((_, suffix)) => format!("line {} {}", i, suffix),
+            None => format!("line {}", i),
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[test]
+fn matches_any_pattern() {
+    let input = generate_lines_with(
+        200,
+        &[
+            (50, "ERROR"),
+            (100, "panicked at src/main.rs"),
+            (150, "WARN"),
+        ],
+    );
+
+    trunc()
+        .args([
+            "-f",
+            "3",
+            "-l",
+            "3",
+            "-e",
+            "ERROR",
+            "-e",
+            "panicked at",
+            "-e",
+            "WARN",
+        ])
+        .write_stdin(input)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("line 50 ERROR"))
+        .stdout(predicate::str::contains("line 100 panicked at src/main.rs"))
+        .stdout(predicate::str::contains("line 150 WARN"))
+        .stdout(predicate::str::contains("match 3 shown"));
+}
+
+#[test]
+fn patterns_share_match_budget() {
+    let input = generate_lines_with(200, &[(50, "ERROR"), (100, "WARN"), (150, "ERROR")]);
+
+    trunc()
+        .args(["-f", "3", "-l", "3", "-m", "2", "-e", "ERROR", "-e", "WARN"])
+        .write_stdin(input)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("match 2/2 shown"))
+        .stdout(predicate::str::contains("line 150 ERROR").not())
+        .stdout(predicate::str::contains("1 match truncated (3 total)"));
+}
+
+#[test]
+fn line_matching_two_patterns_counts_once() {
+    let input = generate_lines_with(200, &[(100, "ERROR WARN")]);
+
+    trunc()
+        .args(["-f", "3", "-l", "3", "-e", "ERROR", "-e", "WARN"])
+        .write_stdin(input)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("match 1 shown"))
+        .stdout(predicate::str::contains("match 2").not());
+}
+
+#[test]
+fn label_names_matching_patterns() {
+    let input = generate_lines_with(200, &[(50, "ERROR"), (150, "WARN ERROR")]);
+
+    let assert = trunc()
+        .args(["-f", "2", "-l", "2", "-C", "0", "--label"])
+        .args(["-e", "ERROR", "-e", "WARN"])
+        .write_stdin(input)
+        .assert()
+        .success();
+
+    let stdout = String::from_utf8_lossy(&assert.get_output().stdout);
+    assert!(
+        stdout.contains("[... 47 lines truncated, match 1 shown ('ERROR') ...]"),
+        "Got:\n{}",
+        stdout
+    );
+    assert!(
+        stdout.contains("[... 99 lines truncated, match 2 shown ('ERROR', 'WARN') ...]"),
+        "Got:\n{}",
+        stdout
+    );
+}
+
+#[test]
+fn no_label_by_default() {
+    let input = generate_lines_with(200, &[(50, "ERROR")]);
+
+    trunc()
+        .args(["-f", "2", "-l", "2", "-e", "ERROR", "-e", "WARN"])
+        .write_stdin(input)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("match 1 shown ...]"));
+}
+
+#[test]
+fn invalid_pattern_among_several_is_an_error() {
+    trunc()
+        .args(["-e", "ERROR", "-e", "[invalid"])
+        .write_stdin("x\n")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Invalid regex"));
+}
@@ -0,0 +1,84 @@
+//! Tests for `--only-if-needed`: input no longer than the head and tail
+//! together passes through whole, like `cat`, with no markers.
+
+mod common;
+
+use common::{run, trunc};
+use predicates::prelude::*;
+
+/// Lines `line 1` to `line n`, with `ERROR` on every fifth.
+fn generate_lines(n: usize) -> String {
+    (1..=n)
+        .map(|i| match i % 5 {
+            0 => format!("line {} ERROR\n", i),
+            _ => format!("line {}\n", i),
+        })
+        .collect()
+}
+
+#[test]
+fn fitting_input_passes_through() {
+    let input = generate_lines(20);
+    trunc()
+        .args(["--only-if-needed", "-f", "10", "-l", "10", "ERROR"])
+        .write_stdin(input.clone())
+        .assert()
+        .success()
+        .stdout(input);
+}
+
+#[test]
+fn without_it_markers_shown() {
+    trunc()
+        .args(["-f", "10", "-l", "10", "ERROR"])
+        .write_stdin(generate_lines(20))
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("[..."));
+}
+
+#[test]
+fn longer_input_truncated_as_usual() {
+    let input = generate_lines(21);
+    let args = ["-f", "10", "-l", "10", "ERROR"];
+    let plain = run(&args, &input);
+    let mut flagged = vec!["--only-if-needed"];
+    flagged.extend(args);
+    assert_eq!(run(&flagged, &input), plain);
+    assert!(plain.contains("[..."));
+}
+
+#[test]
+fn long_file_truncated_as_usual() {
+    let mut file = tempfile::NamedTempFile::new().unwrap();
+    std::io::Write::write_all(&mut file, generate_lines(100).as_bytes()).unwrap();
+    let path = file.path().to_str().unwrap();
+    trunc()
+        .args(["--only-if-needed", "-f", "2", "-l", "2", "--files", path])
+        .assert()
+        .success()
+        .stdout("line 1\nline 2\n[... 96 lines truncated ...]\nline 99\nline 100 ERROR\n");
+}
+
+#[test]
+fn default_from_env() {
+    let input = generate_lines(20);
+    trunc()
+        .env("TRUNC_ONLY_IF_NEEDED", "1")
+        .args(["-f", "10", "-l", "10", "ERROR"])
+        .write_stdin(input.clone())
+        .assert()
+        .success()
+        .stdout(input);
+}
+
+#[test]
+fn always_truncate_overrides_env() {
+    trunc()
+        .env("TRUNC_ONLY_IF_NEEDED", "1")
+        .args(["--always-truncate", "-f", "10", "-l", "10", "ERROR"])
+        .write_stdin(generate_lines(20))
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("[..."));
+}
@@ -0,0 +1,133 @@
+//! Tests for `--out-head`, `--out-matches` and `--out-tail`: each section's
+//! lines are also written to a file of their own, and `-q` leaves stdout
+//! empty.
+
+mod common;
+
+use common::trunc;
+use predicates::prelude::*;
+use std::path::Path;
+use tempfile::TempDir;
+
+/// Lines `line 1` to `line 100`, with `ERROR` on line 50.
+fn build_input() -> String {
+    (1..=100)
+        .map(|i| match i {
+            50 => "line 50 ERROR\n".to_string(),
+            _ => format!("line {}\n", i),
+        })
+        .collect()
+}
+
+fn read(path: &Path) -> String {
+    std::fs::read_to_string(path).unwrap()
+}
+
+#[test]
+fn each_section_to_its_file() {
+    let dir = TempDir::new().unwrap();
+    let (head, matches, tail) = (
+        dir.path().join("head"),
+        dir.path().join("matches"),
+        dir.path().join("tail"),
+    );
+    trunc()
+        .args(["-f", "2", "-l", "2", "-C", "1", "--out-head"])
+        .arg(&head)
+        .arg("--out-matches")
+        .arg(&matches)
+        .arg("--out-tail")
+        .arg(&tail)
+        .arg("ERROR")
+        .write_stdin(build_input())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("line 50 ERROR"));
+    assert_eq!(read(&head), "line 1\nline 2\n");
+    assert_eq!(read(&matches), "line 49\nline 50 ERROR\nline 51\n");
+    assert_eq!(read(&tail), "line 99\nline 100\n");
+}
+
+#[test]
+fn stdout_unchanged() {
+    let dir = TempDir::new().unwrap();
+    let plain = trunc()
+        .args(["-f", "2", "-l", "2", "ERROR"])
+        .write_stdin(build_input())
+        .output()
+        .unwrap();
+    trunc()
+        .args(["-f", "2", "-l", "2", "--out-tail"])
+        .arg(dir.path().join("tail"))
+        .arg("ERROR")
+        .write_stdin(build_input())
+        .assert()
+        .success()
+        .stdout(String::from_utf8(plain.stdout).unwrap());
+}
+
+#[test]
+fn quiet_prints_nothing() {
+    let dir = TempDir::new().unwrap();
+    let tail = dir.path().join("tail");
+    trunc()
+        .args(["-q", "-f", "2", "-l", "2", "--out-tail"])
+        .arg(&tail)
+        .write_stdin(build_input())
+        .assert()
+        .success()
+        .stdout("");
+    assert_eq!(read(&tail), "line 99\nline 100\n");
+}
+
+#[test]
+fn lines_width_truncated_as_shown() {
+    let dir = TempDir::new().unwrap();
+    let head = dir.path().join("head");
+    trunc()
+        .args(["-f", "1", "-l", "0", "-w", "5", "--out-head"])
+        .arg(&head)
+        .write_stdin(format!("{}\nshort\n", "x".repeat(50)))
+        .assert()
+        .success();
+    assert_eq!(read(&head), "xxxxx[... 40 chars ...]xxxxx\n");
+}
+
+#[test]
+fn no_color_in_files() {
+    let dir = TempDir::new().unwrap();
+    let matches = dir.path().join("matches");
+    trunc()
+        .args(["--color", "always", "-C", "0", "--out-matches"])
+        .arg(&matches)
+        .arg("ERROR")
+        .write_stdin(build_input())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("\x1b["));
+    assert_eq!(read(&matches), "line 50 ERROR\n");
+}
+
+#[test]
+fn empty_section_gives_empty_file() {
+    let dir = TempDir::new().unwrap();
+    let matches = dir.path().join("matches");
+    trunc()
+        .args(["--out-matches"])
+        .arg(&matches)
+        .arg("NOTHING")
+        .write_stdin(build_input())
+        .assert()
+        .success();
+    assert_eq!(read(&matches), "");
+}
+
+#[test]
+fn unwritable_file() {
+    trunc()
+        .args(["--out-head", "/nonexistent/dir/head"])
+        .write_stdin(build_input())
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("/nonexistent/dir/head"));
+}
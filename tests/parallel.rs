@@ -0,0 +1,134 @@
+//! Tests for `--parallel`: a file's matches are found a chunk per core
+//! before it's truncated, with the same output as finding them line by
+//! line.
+
+mod common;
+
+use common::trunc;
+use std::io::Write;
+use tempfile::NamedTempFile;
+
+/// A file of `lines` log lines, over 8MB (so scanned in several chunks)
+/// at 200000, with every 997th an ERROR and every 1009th a WARN.
+fn generate_lines(lines: usize, ending: &str) -> NamedTempFile {
+    let mut file = NamedTempFile::new().unwrap();
+    let mut out = std::io::BufWriter::new(file.as_file_mut());
+    for i in 1..=lines {
+        let level = match i {
+            _ if i % 997 == 0 => "ERROR",
+            _ if i % 1009 == 0 => "WARN",
+            _ => "INFO",
+        };
+        write!(
+            out,
+            "2024-01-01 {} request {} handled ok{}",
+            level, i, ending
+        )
+        .unwrap();
+    }
+    out.flush().unwrap();
+    drop(out);
+    file
+}
+
+/// Output with and without `--parallel`, which should be the same.
+fn both(args: &[&str], file: &NamedTempFile) -> (Vec<u8>, Vec<u8>) {
+    let path = file.path().to_str().unwrap();
+    let run = |parallel: bool| {
+        let mut cmd = trunc();
+        if parallel {
+            cmd.arg("--parallel");
+        }
+        let output = cmd.args(args).arg(path).output().unwrap();
+        assert!(output.status.success());
+        output.stdout
+    };
+    (run(false), run(true))
+}
+
+#[test]
+fn same_as_in_order() {
+    let file = generate_lines(200_000, "\n");
+    let (plain, parallel) = both(&["-C", "2", "-m", "50", "ERROR"], &file);
+    assert!(String::from_utf8_lossy(&plain).contains("request 49850 handled"));
+    assert_eq!(plain, parallel);
+}
+
+#[test]
+fn counts_every_match() {
+    let file = generate_lines(200_000, "\n");
+    let (plain, parallel) = both(&["-c", "ERROR"], &file);
+    assert!(String::from_utf8_lossy(&plain).contains("200 matches"));
+    assert_eq!(plain, parallel);
+}
+
+#[test]
+fn several_patterns_and_budgets() {
+    let file = generate_lines(200_000, "\n");
+    let (plain, parallel) = both(
+        &["-e", "ERROR:3", "-e", "WARN", "-m", "10", "--label", "-n"],
+        &file,
+    );
+    assert_eq!(plain, parallel);
+}
+
+#[test]
+fn crlf_line_endings() {
+    let file = generate_lines(200_000, "\r\n");
+    let (plain, parallel) = both(&["-A", "1", "request 150000 "], &file);
+    assert!(String::from_utf8_lossy(&plain).contains("request 150001 handled ok\n"));
+    assert_eq!(plain, parallel);
+}
+
+#[test]
+fn no_final_newline() {
+    let mut file = NamedTempFile::new().unwrap();
+    write!(file, "one\nERROR two\nthree\nERROR four").unwrap();
+    let (plain, parallel) = both(&["-f", "0", "-l", "0", "ERROR"], &file);
+    assert!(String::from_utf8_lossy(&plain).contains("ERROR four"));
+    assert_eq!(plain, parallel);
+}
+
+#[test]
+fn ignored_for_stdin() {
+    trunc()
+        .args(["--parallel", "-C", "0", "-f", "0", "-l", "0", "ERROR"])
+        .write_stdin("one\nERROR two\nthree\n")
+        .assert()
+        .success()
+        .stdout(
+            "[... 1 lines truncated, match 1 shown ...]\nERROR two\n[... 1 lines truncated ...]\n",
+        );
+}
+
+#[test]
+fn cleaned_lines_matched_as_usual() {
+    let mut file = NamedTempFile::new().unwrap();
+    write!(file, "one\n\x1b[31mERR\x1b[0mOR two\nthree\n").unwrap();
+    let (plain, parallel) = both(&["--strip-ansi", "-f", "0", "-l", "0", "ERROR"], &file);
+    assert!(String::from_utf8_lossy(&plain).contains("ERROR two"));
+    assert_eq!(plain, parallel);
+}
+
+#[test]
+fn scanned_with_default_cleaning() {
+    let file = generate_lines(1_000, "\n");
+    trunc()
+        .args(["--parallel", "--explain", "ERROR"])
+        .arg(file.path())
+        .assert()
+        .success()
+        .stderr(predicates::str::starts_with(
+            "explain: matches looked up in the --parallel scan (1 found), not matched line by line\n",
+        ));
+}
+
+#[test]
+fn progress_collapsed_before_scanning() {
+    // Only an overwritten redraw says ERROR, so the line isn't a match
+    let mut file = NamedTempFile::new().unwrap();
+    write!(file, "one\nERROR 10%\rok\nthree\nERROR four\n").unwrap();
+    let (plain, parallel) = both(&["-f", "0", "-l", "0", "-C", "0", "ERROR"], &file);
+    assert!(!String::from_utf8_lossy(&plain).contains("ok"));
+    assert_eq!(plain, parallel);
+}
@@ -0,0 +1,129 @@
+//! Tests for percentage head and tail sizes: `-f 10% -l 20%`.
+//!
+//! Sizes scale with the input, so a 50-line run and a 50k-line run both get
+//! a useful view. Files are counted in a first pass; stdin and wrapped
+//! commands are buffered.
+
+mod common;
+
+use common::{generate_lines, trunc};
+use predicates::prelude::*;
+use std::io::Write;
+use tempfile::NamedTempFile;
+
+/// Expected output for head `f` and tail `l` of `generate_lines(n)`.
+fn expected(n: usize, f: usize, l: usize) -> String {
+    let mut out = String::new();
+    for i in 1..=f {
+        out.push_str(&format!("line {}\n", i));
+    }
+    out.push_str(&format!("[... {} lines truncated ...]\n", n - f - l));
+    for i in n - l + 1..=n {
+        out.push_str(&format!("line {}\n", i));
+    }
+    out
+}
+
+#[test]
+fn percentages_of_stdin() {
+    trunc()
+        .args(["-f", "10%", "-l", "20%"])
+        .write_stdin(generate_lines(100))
+        .assert()
+        .success()
+        .stdout(expected(100, 10, 20));
+}
+
+#[test]
+fn percentages_scale_with_input() {
+    trunc()
+        .args(["-f", "10%", "-l", "20%"])
+        .write_stdin(generate_lines(1000))
+        .assert()
+        .success()
+        .stdout(expected(1000, 100, 200));
+}
+
+#[test]
+fn percentages_of_file() {
+    let mut file = NamedTempFile::new().unwrap();
+    write!(file, "{}", generate_lines(200)).unwrap();
+
+    trunc()
+        .args(["-f", "5%", "-l", "5%"])
+        .arg("--files")
+        .arg(file.path())
+        .assert()
+        .success()
+        .stdout(expected(200, 10, 10));
+}
+
+#[test]
+fn each_file_sized_on_its_own() {
+    let mut small = NamedTempFile::new().unwrap();
+    write!(small, "{}", generate_lines(20)).unwrap();
+    let mut big = NamedTempFile::new().unwrap();
+    write!(big, "{}", generate_lines(200)).unwrap();
+
+    let assert = trunc()
+        .args(["-f", "10%", "-l", "10%"])
+        .arg("--files")
+        .arg(small.path())
+        .arg(big.path())
+        .assert()
+        .success();
+
+    let stdout = String::from_utf8_lossy(&assert.get_output().stdout).into_owned();
+    assert!(stdout.contains(&expected(20, 2, 2)), "Got: {}", stdout);
+    assert!(stdout.contains(&expected(200, 20, 20)), "Got: {}", stdout);
+}
+
+#[test]
+fn percentage_rounds_up() {
+    // 1% of 50 lines is half a line: still show one
+    trunc()
+        .args(["-f", "1%", "-l", "1%"])
+        .write_stdin(generate_lines(50))
+        .assert()
+        .success()
+        .stdout(expected(50, 1, 1));
+}
+
+#[test]
+fn mixed_with_line_count() {
+    trunc()
+        .args(["-f", "3", "-l", "10%"])
+        .write_stdin(generate_lines(100))
+        .assert()
+        .success()
+        .stdout(expected(100, 3, 10));
+}
+
+#[test]
+fn percentages_of_wrapped_command() {
+    trunc()
+        .args(["-f", "10%", "-l", "10%", "--", "seq", "50"])
+        .assert()
+        .success()
+        .stdout("1\n2\n3\n4\n5\n[... 40 lines truncated ...]\n46\n47\n48\n49\n50\n");
+}
+
+#[test]
+fn over_100_percent_rejected() {
+    trunc()
+        .args(["-f", "150%"])
+        .write_stdin("x\n")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("between 0 and 100"));
+}
+
+#[test]
+fn not_with_follow() {
+    trunc()
+        .args(["-F", "-l", "10%"])
+        .write_stdin("x\n")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("--follow"));
+}
@@ -0,0 +1,104 @@
+//! Tests for `--plan`: counting what trunc would print, for the flags given
+//! and a few alternatives, without printing any of the input.
+//!
+//! The counts have to agree with a real run, or the plan is no help in
+//! picking flags.
+
+mod common;
+
+use common::{generate_terminated_lines, run, trunc};
+
+/// The plan's row for `flags`, as numbers: head, matches, tail, markers,
+/// lines, bytes and tokens.
+fn row(plan: &str, flags: &str) -> Vec<usize> {
+    let line = plan
+        .lines()
+        .find(|line| line.starts_with(flags))
+        .unwrap_or_else(|| panic!("no row for {} in:\n{}", flags, plan));
+    line[flags.len()..]
+        .split_whitespace()
+        .map(|n| n.parse().unwrap())
+        .collect()
+}
+
+#[test]
+fn plan_prints_no_input_lines() {
+    let plan = run(&["--plan"], generate_terminated_lines(1000));
+    assert!(plan.starts_with("[trunc plan] read 1000 lines, "));
+    assert!(!plan.contains("line 1\n"));
+    assert!(!plan.contains("truncated"));
+}
+
+#[test]
+fn plan_counts_sections_and_markers() {
+    let plan = run(
+        &["--plan", "-f", "10", "-l", "5"],
+        generate_terminated_lines(1000),
+    );
+    let counts = row(&plan, "as given");
+    assert_eq!(&counts[..5], &[10, 0, 5, 1, 16]);
+}
+
+#[test]
+fn plan_matches_a_real_run() {
+    let input = generate_terminated_lines(5000);
+    let args = ["-f", "20", "-l", "10", "-e", "7$"];
+    let real = run(&args, &input);
+    let plan = run(&[&args[..], &["--plan"]].concat(), &input);
+    let counts = row(&plan, "as given");
+    assert_eq!(counts[4], real.lines().count());
+    assert_eq!(counts[5], real.len());
+    assert_eq!(counts[6], real.len().div_ceil(4));
+}
+
+#[test]
+fn plan_alternatives_match_real_runs() {
+    let input = generate_terminated_lines(5000);
+    let plan = run(&["--plan", "-f", "20", "-l", "10", "-m", "4", "7$"], &input);
+    let halved = run(&["-f", "10", "-l", "5", "-m", "2", "7$"], &input);
+    let doubled = run(&["-f", "40", "-l", "20", "-m", "8", "7$"], &input);
+    let compact = run(
+        &[
+            "-f",
+            "20",
+            "-l",
+            "10",
+            "-m",
+            "4",
+            "--markers",
+            "compact",
+            "7$",
+        ],
+        &input,
+    );
+    assert_eq!(row(&plan, "-f/-l/-m halved")[5], halved.len());
+    assert_eq!(row(&plan, "-f/-l/-m doubled")[5], doubled.len());
+    assert_eq!(row(&plan, "--markers compact")[5], compact.len());
+}
+
+#[test]
+fn plan_leaves_out_compact_when_already_compact() {
+    let plan = run(
+        &["--plan", "--markers", "compact"],
+        generate_terminated_lines(100),
+    );
+    assert!(plan.contains("as given"));
+    assert!(!plan.contains("--markers compact"));
+}
+
+#[test]
+fn plan_with_percentage_sizes_sizes_each_alternative() {
+    let input = generate_terminated_lines(1000);
+    let plan = run(&["--plan", "-f", "10%", "-l", "10%"], &input);
+    assert_eq!(row(&plan, "as given")[..3], [100, 0, 100]);
+    assert_eq!(row(&plan, "-f/-l/-m halved")[..3], [50, 0, 50]);
+}
+
+#[test]
+fn plan_conflicts_with_follow() {
+    trunc()
+        .args(["--plan", "--follow"])
+        .write_stdin("")
+        .assert()
+        .failure();
+}
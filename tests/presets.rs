@@ -0,0 +1,148 @@
+//! Tests for `--preset`: built-in patterns and budgets for common tools.
+//!
+//! A preset fills in whatever the command line leaves alone; explicit flags
+//! and patterns always win.
+
+mod common;
+
+use common::{generate_lines, trunc};
+use predicates::prelude::*;
+
+/// `generate_lines(n)` with `line` inserted after line `at`.
+fn with_line_at(n: usize, at: usize, line: &str) -> String {
+    format!(
+        "{}\n{}\n{}",
+        generate_lines(at),
+        line,
+        (at + 1..=n)
+            .map(|i| format!("line {}", i))
+            .collect::<Vec<_>>()
+            .join("\n")
+    )
+}
+
+#[test]
+fn cargo_finds_compiler_errors() {
+    let input = with_line_at(200, 100, "error[E0308]: mismatched types");
+
+    trunc()
+        .args(["--preset", "cargo"])
+        .write_stdin(input)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("match 1 shown ...]"))
+        .stdout(predicate::str::contains("error[E0308]: mismatched types\n"));
+}
+
+#[test]
+fn cargo_finds_panics() {
+    let input = with_line_at(200, 100, "thread 'main' panicked at src/main.rs:4:5:");
+
+    trunc()
+        .args(["--preset", "cargo"])
+        .write_stdin(input)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("panicked at src/main.rs"));
+}
+
+#[test]
+fn cargo_sets_budgets() {
+    // Head of 10, tail of 30, and 6 lines of context
+    let input = with_line_at(200, 100, "error: could not compile `foo`");
+
+    trunc()
+        .args(["--preset", "cargo"])
+        .write_stdin(input)
+        .assert()
+        .success()
+        .stdout(predicate::str::starts_with(format!(
+            "{}\n[... 84 lines truncated, match 1 shown ...]\n",
+            generate_lines(10)
+        )))
+        .stdout(predicate::str::contains("line 95\n"))
+        .stdout(predicate::str::contains("line 94\n").not())
+        .stdout(predicate::str::contains("line 106\n"))
+        .stdout(predicate::str::contains("line 107\n").not())
+        .stdout(predicate::str::ends_with(format!(
+            "[... 64 lines truncated ...]\n{}\n",
+            (171..=200)
+                .map(|i| format!("line {}", i))
+                .collect::<Vec<_>>()
+                .join("\n")
+        )));
+}
+
+#[test]
+fn pytest_finds_assertion_lines() {
+    let input = with_line_at(200, 100, "E       assert 1 == 2");
+
+    trunc()
+        .args(["--preset", "pytest"])
+        .write_stdin(input)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("E       assert 1 == 2\n"));
+}
+
+#[test]
+fn npm_finds_errors() {
+    let input = with_line_at(200, 100, "npm ERR! code ELIFECYCLE");
+
+    trunc()
+        .args(["--preset", "npm"])
+        .write_stdin(input)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("npm ERR! code ELIFECYCLE\n"));
+}
+
+#[test]
+fn gradle_finds_failures() {
+    let input = with_line_at(200, 100, "FAILURE: Build failed with an exception.");
+
+    trunc()
+        .args(["--preset", "gradle"])
+        .write_stdin(input)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("FAILURE: Build failed"));
+}
+
+#[test]
+fn explicit_flags_override_preset() {
+    let input = with_line_at(200, 100, "error: could not compile `foo`");
+
+    trunc()
+        .args(["--preset", "cargo", "-f", "1", "-l", "1", "-C", "0"])
+        .write_stdin(input)
+        .assert()
+        .success()
+        .stdout(
+            "line 1\n[... 99 lines truncated, match 1 shown ...]\n\
+             error: could not compile `foo`\n[... 99 lines truncated ...]\nline 200\n",
+        );
+}
+
+#[test]
+fn explicit_pattern_replaces_preset_patterns() {
+    let input = with_line_at(200, 100, "error: could not compile `foo`");
+
+    trunc()
+        .args(["--preset", "cargo", "line 150$"])
+        .write_stdin(input)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("could not compile").not())
+        .stdout(predicate::str::contains("line 150\n"));
+}
+
+#[test]
+fn unknown_preset_rejected() {
+    trunc()
+        .args(["--preset", "nope"])
+        .write_stdin("x\n")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("cargo"));
+}
@@ -0,0 +1,136 @@
+//! Tests for collapsing `\r`-redrawn progress lines to their last frame,
+//! and `--keep-progress` to turn it off.
+
+mod common;
+
+use common::trunc;
+use predicates::prelude::*;
+
+/// A progress bar from 0% to 100% in `n` redraws, on one line.
+fn progress_bar(n: usize) -> String {
+    (0..=n)
+        .map(|i| format!("Downloading {:>3}%", i * 100 / n))
+        .collect::<Vec<_>>()
+        .join("\r")
+}
+
+#[test]
+fn keeps_last_frame() {
+    trunc()
+        .write_stdin(format!("start\n{}\nend\n", progress_bar(1423)))
+        .assert()
+        .success()
+        .stdout(
+            "start\n\
+             Downloading 100%\n\
+             [... progress output collapsed (1423 updates) ...]\n\
+             end\n",
+        );
+}
+
+#[test]
+fn trailing_carriage_return_ignored() {
+    // Bars often end by returning to the start of the line, or clearing it
+    trunc()
+        .write_stdin("50%\r100%\r\n70%\r80%\r    \r\n")
+        .assert()
+        .success()
+        .stdout(
+            "100%\n\
+             [... progress output collapsed (1 updates) ...]\n\
+             80%\n\
+             [... progress output collapsed (1 updates) ...]\n",
+        );
+}
+
+#[test]
+fn crlf_lines_unchanged() {
+    trunc()
+        .write_stdin("one\r\ntwo\r\n")
+        .assert()
+        .success()
+        .stdout("one\ntwo\n");
+}
+
+#[test]
+fn keep_progress() {
+    trunc()
+        .arg("--keep-progress")
+        .write_stdin("10%\r100%\n")
+        .assert()
+        .success()
+        .stdout("10%\r100%\n");
+}
+
+#[test]
+fn patterns_see_last_frame() {
+    let input = format!(
+        "{}\nbuilding 10% ERROR\rbuilding 100% ok\n{}",
+        (1..=50)
+            .map(|i| format!("line {}", i))
+            .collect::<Vec<_>>()
+            .join("\n"),
+        (51..=100)
+            .map(|i| format!("line {}", i))
+            .collect::<Vec<_>>()
+            .join("\n"),
+    );
+
+    trunc()
+        .args(["-f", "1", "-l", "1", "ERROR"])
+        .write_stdin(input)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("0 matches found"));
+}
+
+#[test]
+fn gates_see_raw_line() {
+    trunc()
+        .args(["--forbid", "ERROR"])
+        .write_stdin("10% ERROR\r100% ok\n")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("forbidden pattern"));
+}
+
+#[test]
+fn dropped_frames_not_truncation_for_status() {
+    trunc()
+        .arg("--status")
+        .write_stdin(format!("{}\n10%\r100%\n", progress_bar(500)))
+        .assert()
+        .code(0);
+}
+
+#[test]
+fn hidden_progress_line_counted_as_its_input_bytes() {
+    // 17 bytes, of which the 13 between a and h are hidden
+    trunc()
+        .args(["--trailer=checksum", "-f", "1", "-l", "1"])
+        .write_stdin("a\nb\nc\r d\ne\nf\ng\nh\n")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("hidden-lines:5 hidden-bytes:13]"));
+}
+
+#[test]
+fn note_goes_to_marker_sink() {
+    trunc()
+        .args(["--markers-to-stderr", "--markers", "compact"])
+        .write_stdin("start\n10%\r50%\r100%\nend\n")
+        .assert()
+        .success()
+        .stdout("start\n100%\nend\n")
+        .stderr("\u{2026}2 updates\u{2026}\n");
+}
+
+#[test]
+fn note_only_after_a_shown_line() {
+    trunc()
+        .args(["-f", "1", "-l", "1"])
+        .write_stdin("a\n10%\r100%\nc\nd\n")
+        .assert()
+        .success()
+        .stdout("a\n[... 2 lines truncated ...]\nd\n");
+}
@@ -0,0 +1,92 @@
+//! Tests for `--pty`: a wrapped command runs on a pseudo-terminal, so it
+//! writes what it would to a terminal, for trunc to truncate.
+
+#![cfg(unix)]
+
+mod common;
+
+use common::trunc;
+use predicates::prelude::*;
+
+#[test]
+fn child_sees_a_terminal() {
+    trunc()
+        .args([
+            "--pty",
+            "--",
+            "sh",
+            "-c",
+            "test -t 1 && echo tty || echo pipe",
+        ])
+        .assert()
+        .success()
+        .stdout("tty\n");
+}
+
+#[test]
+fn without_it_child_sees_a_pipe() {
+    trunc()
+        .args(["--", "sh", "-c", "test -t 1 && echo tty || echo pipe"])
+        .assert()
+        .success()
+        .stdout("pipe\n");
+}
+
+#[test]
+fn lines_truncated_as_usual() {
+    trunc()
+        .args(["--pty", "-f", "2", "-l", "2", "--", "seq", "1", "100"])
+        .assert()
+        .success()
+        .stdout("1\n2\n[... 96 lines truncated ...]\n99\n100\n");
+}
+
+#[test]
+fn merged_stderr_on_the_terminal_too() {
+    trunc()
+        .args([
+            "--pty",
+            "--stderr",
+            "merge",
+            "--",
+            "sh",
+            "-c",
+            "test -t 2 && echo tty >&2 || echo pipe >&2",
+        ])
+        .assert()
+        .success()
+        .stdout("tty\n");
+}
+
+#[test]
+fn colors_kept_with_keep_ansi() {
+    trunc()
+        .args([
+            "--pty",
+            "--keep-ansi",
+            "--",
+            "printf",
+            "\\033[31mred\\033[0m\\n",
+        ])
+        .assert()
+        .success()
+        .stdout("\x1b[31mred\x1b[0m\n");
+}
+
+#[test]
+fn exit_status_passed_on() {
+    trunc()
+        .args(["--pty", "--", "sh", "-c", "echo done; exit 3"])
+        .assert()
+        .code(3)
+        .stdout("done\n");
+}
+
+#[test]
+fn not_with_separate_stderr() {
+    trunc()
+        .args(["--pty", "--stderr", "separate", "--", "true"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("can't keep stderr separate"));
+}
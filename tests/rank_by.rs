@@ -0,0 +1,165 @@
+//! Tests for `--rank-by severity`: showing the most severe matches rather
+//! than the first ones when there are more than `-m`.
+//!
+//! Choosing needs the whole input, so files are read twice and stdin and
+//! wrapped commands are buffered; the chosen matches still print in order.
+
+mod common;
+
+use common::trunc;
+use predicates::prelude::*;
+use std::io::Write;
+use tempfile::NamedTempFile;
+
+/// 300 lines: INFO every 10th line, WARN at 150, ERROR at 200 and 250.
+fn log() -> String {
+    (1..=300)
+        .map(|i| match i {
+            150 => format!("line {} WARN slow", i),
+            200 | 250 => format!("line {} ERROR boom", i),
+            _ if i % 10 == 0 => format!("line {} INFO tick", i),
+            _ => format!("line {}", i),
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+const ARGS: &[&str] = &["-f", "1", "-l", "1", "-C", "0", "-m", "3"];
+
+#[test]
+fn severe_matches_preferred_on_stdin() {
+    trunc()
+        .args(ARGS)
+        .args(["--rank-by", "severity", "INFO|WARN|ERROR"])
+        .write_stdin(log())
+        .assert()
+        .success()
+        .stdout(
+            "line 1\n\
+             [... 148 lines truncated, match 1 shown ...]\n\
+             line 150 WARN slow\n\
+             [... 49 lines truncated, match 2 shown ...]\n\
+             line 200 ERROR boom\n\
+             [... 49 lines truncated, match 3/3 shown ...]\n\
+             line 250 ERROR boom\n\
+             [... 49 lines and 26 matches truncated (30 total), 1 match in tail ...]\n\
+             line 300 INFO tick\n",
+        );
+}
+
+#[test]
+fn severe_matches_preferred_in_file() {
+    let mut file = NamedTempFile::new().unwrap();
+    write!(file, "{}", log()).unwrap();
+
+    trunc()
+        .args(ARGS)
+        .args(["--rank-by", "severity", "-e", "INFO|WARN|ERROR"])
+        .arg(file.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("line 150 WARN slow\n"))
+        .stdout(predicate::str::contains("line 200 ERROR boom\n"))
+        .stdout(predicate::str::contains("line 250 ERROR boom\n"))
+        .stdout(predicate::str::contains("line 10 INFO").not());
+}
+
+#[test]
+fn first_matches_without_rank_by() {
+    trunc()
+        .args(ARGS)
+        .arg("INFO|WARN|ERROR")
+        .write_stdin(log())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("line 10 INFO tick\n"))
+        .stdout(predicate::str::contains("ERROR").not());
+}
+
+#[test]
+fn ties_go_to_earlier_match() {
+    trunc()
+        .args(["-f", "1", "-l", "1", "-C", "0", "-m", "1"])
+        .args(["--rank-by", "severity", "ERROR"])
+        .write_stdin(log())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("line 200 ERROR boom\n"))
+        .stdout(predicate::str::contains("line 250").not());
+}
+
+#[test]
+fn panicked_outranks_error() {
+    let mut input: String = (1..=20).map(|i| format!("line {}\n", i)).collect();
+    input.push_str("error y\n");
+    input.push_str("thread 'main' panicked at src/main.rs:2:5:\n");
+    input.extend((23..=40).map(|i| format!("line {}\n", i)));
+    trunc()
+        .args(["-f", "1", "-l", "1", "-C", "0", "-m", "1"])
+        .args(["--rank-by", "severity", "panicked|error"])
+        .write_stdin(input)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("thread 'main' panicked at"))
+        .stdout(predicate::str::contains("error y").not())
+        .stdout(predicate::str::contains("1 match truncated (2 total)"));
+}
+
+#[test]
+fn custom_ladder() {
+    // Only INFO is a rung, so INFO outranks WARN and ERROR
+    trunc()
+        .args(["-f", "1", "-l", "1", "-C", "0", "-m", "1"])
+        .args(["--rank-by", "severity", "--severity", "INFO"])
+        .arg("WARN|ERROR|INFO")
+        .write_stdin(log())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("line 10 INFO tick\n"))
+        .stdout(predicate::str::contains("ERROR").not());
+}
+
+#[test]
+fn tail_matches_take_no_slot() {
+    // Both ERRORs are in the tail, so the single slot goes to WARN
+    trunc()
+        .args(["-f", "1", "-l", "110", "-C", "0", "-m", "1"])
+        .args(["--rank-by", "severity", "WARN|ERROR"])
+        .write_stdin(log())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "match 1/1 shown ...]\nline 150 WARN slow\n",
+        ));
+}
+
+#[test]
+fn wrapped_command_is_buffered() {
+    trunc()
+        .args(["-f", "1", "-l", "1", "-C", "0", "-m", "1"])
+        .args(["--rank-by", "severity", "--severity", "^7"])
+        .args(["-e", "^[357]$", "--", "seq", "100"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("match 1/1 shown ...]\n7\n"));
+}
+
+#[test]
+fn severity_requires_rank_by() {
+    trunc()
+        .args(["--severity", "ERROR", "ERROR"])
+        .write_stdin("x\n")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("--rank-by"));
+}
+
+#[test]
+fn not_with_follow() {
+    trunc()
+        .args(["-F", "--rank-by", "severity", "ERROR"])
+        .write_stdin("x\n")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("--follow"));
+}
@@ -0,0 +1,135 @@
+//! Tests for `--no-head`, `--no-tail` and `--rebalance`: a section can be
+//! left out, and the lines one doesn't use can go to the others.
+
+mod common;
+
+use common::{generate_numbers, trunc};
+use predicates::prelude::*;
+
+/// Numbers `from` to `to`, one per line.
+fn lines(from: usize, to: usize) -> String {
+    (from..=to).map(|i| format!("{}\n", i)).collect()
+}
+
+#[test]
+fn no_head() {
+    trunc()
+        .args(["--no-head", "-f", "5", "-l", "2"])
+        .write_stdin(generate_numbers(100))
+        .assert()
+        .success()
+        .stdout("[... 98 lines truncated ...]\n99\n100\n");
+}
+
+#[test]
+fn no_tail() {
+    trunc()
+        .args(["--no-tail", "-f", "2", "-l", "5"])
+        .write_stdin(generate_numbers(100))
+        .assert()
+        .success()
+        .stdout("1\n2\n[... 98 lines truncated ...]\n");
+}
+
+#[test]
+fn head_given_to_tail() {
+    trunc()
+        .args(["--no-head", "--rebalance", "-f", "3", "-l", "2"])
+        .write_stdin(generate_numbers(100))
+        .assert()
+        .success()
+        .stdout(format!("[... 95 lines truncated ...]\n{}", lines(96, 100)));
+}
+
+#[test]
+fn tail_given_to_head() {
+    trunc()
+        .args(["--no-tail", "--rebalance", "-f", "2", "-l", "3"])
+        .write_stdin(generate_numbers(100))
+        .assert()
+        .success()
+        .stdout(format!("{}[... 95 lines truncated ...]\n", lines(1, 5)));
+}
+
+#[test]
+fn unfound_matches_given_to_tail() {
+    // Room for 3 matches of 3 lines; 1 found leaves 6 lines for the tail
+    trunc()
+        .args([
+            "--rebalance",
+            "-f",
+            "2",
+            "-l",
+            "2",
+            "-m",
+            "3",
+            "-C",
+            "1",
+            "^50$",
+        ])
+        .write_stdin(generate_numbers(100))
+        .assert()
+        .success()
+        .stdout(format!(
+            "1\n2\n[... 46 lines truncated, match 1 shown ...]\n49\n50\n51\n\
+             [... 41 lines truncated ...]\n{}",
+            lines(93, 100)
+        ));
+}
+
+#[test]
+fn full_matches_leave_tail_alone() {
+    trunc()
+        .args([
+            "--rebalance",
+            "-f",
+            "0",
+            "-l",
+            "2",
+            "-m",
+            "2",
+            "-C",
+            "0",
+            "0$",
+        ])
+        .write_stdin(generate_numbers(100))
+        .assert()
+        .success()
+        .stdout(predicate::str::ends_with("]\n99\n100\n"));
+}
+
+#[test]
+fn both_given_to_matches() {
+    // 4 lines of head and tail make 4 more matches without context
+    let output = trunc()
+        .args([
+            "--no-head",
+            "--no-tail",
+            "--rebalance",
+            "-f",
+            "2",
+            "-l",
+            "2",
+            "-m",
+            "1",
+            "-C",
+            "0",
+            "0$",
+        ])
+        .write_stdin(generate_numbers(100))
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("match 5/5 shown"), "Got: {}", stdout);
+    assert!(!stdout.contains("\n60\n"), "Got: {}", stdout);
+}
+
+#[test]
+fn no_head_not_with_first_bytes() {
+    trunc()
+        .args(["--no-head", "--first-bytes", "1K"])
+        .write_stdin("line\n")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("cannot be used with"));
+}
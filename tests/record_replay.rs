@@ -0,0 +1,241 @@
+//! Tests for `--record` and `trunc replay`.
+//!
+//! Expensive commands shouldn't need re-running just to see a different slice
+//! of their output. `--record` saves the full input while truncating as usual;
+//! `replay` re-applies truncation with whatever flags the second look needs.
+
+mod common;
+
+use common::{generate_lines_with_matches, trunc};
+use predicates::prelude::*;
+
+#[test]
+fn record_does_not_change_output() {
+    let dir = tempfile::tempdir().unwrap();
+    let rec = dir.path().join("run.trunc");
+    let input = generate_lines_with_matches(100, &[50], "ERROR");
+
+    let plain = trunc()
+        .args(["-f", "5", "-l", "5"])
+        .write_stdin(input.clone())
+        .assert()
+        .success();
+    let recorded = trunc()
+        .args(["-f", "5", "-l", "5", "--record"])
+        .arg(&rec)
+        .write_stdin(input)
+        .assert()
+        .success();
+
+    assert_eq!(plain.get_output().stdout, recorded.get_output().stdout);
+    assert!(rec.exists());
+}
+
+#[test]
+fn replay_with_same_flags_reproduces_output() {
+    let dir = tempfile::tempdir().unwrap();
+    let rec = dir.path().join("run.trunc");
+    let input = generate_lines_with_matches(100, &[50, 70], "ERROR");
+
+    let first = trunc()
+        .args(["-f", "5", "-l", "5", "--record"])
+        .arg(&rec)
+        .arg("ERROR")
+        .write_stdin(input)
+        .assert()
+        .success();
+
+    trunc()
+        .arg("replay")
+        .arg(&rec)
+        .args(["-f", "5", "-l", "5", "ERROR"])
+        .assert()
+        .success()
+        .stdout(String::from_utf8_lossy(&first.get_output().stdout).to_string());
+}
+
+#[test]
+fn replay_with_different_flags() {
+    let dir = tempfile::tempdir().unwrap();
+    let rec = dir.path().join("run.trunc");
+    let input = generate_lines_with_matches(100, &[50], "ERROR");
+
+    // First pass: no pattern, the match is hidden in the middle
+    trunc()
+        .args(["-f", "5", "-l", "5", "--record"])
+        .arg(&rec)
+        .write_stdin(input)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("ERROR").not());
+
+    // Second look: search for it with more context
+    trunc()
+        .arg("replay")
+        .arg(&rec)
+        .args(["-f", "5", "-l", "5", "-C", "10", "ERROR"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("line 50 contains ERROR"))
+        .stdout(predicate::str::contains("line 40\n"))
+        .stdout(predicate::str::contains("line 60\n"));
+}
+
+#[test]
+fn replay_preserves_blank_lines() {
+    let dir = tempfile::tempdir().unwrap();
+    let rec = dir.path().join("run.trunc");
+
+    trunc()
+        .arg("--record")
+        .arg(&rec)
+        .write_stdin("a\n\nb\n")
+        .assert()
+        .success();
+
+    trunc()
+        .arg("replay")
+        .arg(&rec)
+        .assert()
+        .success()
+        .stdout("a\n\nb\n");
+}
+
+#[test]
+fn replay_rejects_non_recording() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("plain.log");
+    std::fs::write(&path, "just some text\n").unwrap();
+
+    trunc()
+        .arg("replay")
+        .arg(&path)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("not a trunc recording"));
+}
+
+#[test]
+fn replay_missing_file_is_an_error() {
+    trunc()
+        .args(["replay", "/nonexistent/run.trunc"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("/nonexistent/run.trunc"));
+}
+
+#[test]
+fn replay_keeps_input_bytes() {
+    let dir = tempfile::tempdir().unwrap();
+    let rec = dir.path().join("run.trunc");
+    let copy = dir.path().join("copy.log");
+    let input = b"a\r\nb \xff\nc".to_vec();
+
+    trunc()
+        .args(["--binary-safe", "--record"])
+        .arg(&rec)
+        .write_stdin(input.clone())
+        .assert()
+        .success();
+
+    trunc()
+        .args(["replay", "--binary-safe", "--tee"])
+        .arg(&copy)
+        .arg(&rec)
+        .assert()
+        .success();
+    assert_eq!(std::fs::read(&copy).unwrap(), input);
+}
+
+/// A recording written by hand: lines, their offsets, and the footer.
+fn recording(data: &[u8], offsets: &[u64], line_count: u64) -> Vec<u8> {
+    let mut file = b"TRUNCREC1\n".to_vec();
+    file.extend_from_slice(data);
+    for offset in offsets {
+        file.extend_from_slice(&offset.to_le_bytes());
+    }
+    file.extend_from_slice(&line_count.to_le_bytes());
+    file.extend_from_slice(&(data.len() as u64).to_le_bytes());
+    file.extend_from_slice(b"TRUNCIDX");
+    file
+}
+
+#[test]
+fn recording_holds_a_line_index() {
+    let dir = tempfile::tempdir().unwrap();
+    let rec = dir.path().join("run.trunc");
+
+    trunc()
+        .arg("--record")
+        .arg(&rec)
+        .write_stdin("a\r\nbb\nc")
+        .assert()
+        .success();
+
+    assert_eq!(
+        std::fs::read(&rec).unwrap(),
+        recording(b"a\r\nbb\nc", &[0, 3, 6], 3)
+    );
+}
+
+#[test]
+fn replay_finds_the_tail_from_the_index() {
+    let dir = tempfile::tempdir().unwrap();
+    let rec = dir.path().join("run.trunc");
+    std::fs::write(
+        &rec,
+        recording(
+            b"1\n2\n[... 5 lines truncated ...]\n4\n5",
+            &[0, 2, 4, 32, 34],
+            5,
+        ),
+    )
+    .unwrap();
+
+    // The marker in the middle still counts the lines it hid
+    trunc()
+        .arg("replay")
+        .arg(&rec)
+        .args(["-f", "1", "-l", "2", "--trailer=checksum"])
+        .assert()
+        .success()
+        .stdout(predicate::str::starts_with(
+            "1\n[... 6 lines truncated ...]\n4\n5\n",
+        ))
+        .stdout(predicate::str::contains("hidden-lines:2 hidden-bytes:30]"));
+}
+
+#[test]
+fn recording_survives_a_read_error() {
+    let dir = tempfile::tempdir().unwrap();
+    let rec = dir.path().join("run.trunc");
+
+    trunc()
+        .arg("--record")
+        .arg(&rec)
+        .write_stdin(b"a\nb\n\xff\nc\n".to_vec())
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Error reading input"));
+
+    trunc()
+        .args(["replay", "--binary-safe"])
+        .arg(&rec)
+        .assert()
+        .success()
+        .stdout("a\nb\n\u{fffd}\n");
+}
+
+#[test]
+fn replay_rejects_overflowing_line_count() {
+    let dir = tempfile::tempdir().unwrap();
+    let rec = dir.path().join("bad.trunc");
+    std::fs::write(&rec, recording(b"a\n", &[0], u64::MAX)).unwrap();
+
+    trunc()
+        .arg("replay")
+        .arg(&rec)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("not a trunc recording"));
+}
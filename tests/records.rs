@@ -0,0 +1,143 @@
+//! Tests for `--separator` and `--paragraph`: multi-line records are
+//! truncated and matched as whole units, and the sizes count records.
+
+mod common;
+
+use common::trunc;
+use predicates::prelude::*;
+
+/// N blank-line separated records like "error 1\n  at a 1\n  at b 1\n".
+fn generate_paragraphs(n: usize) -> String {
+    (1..=n)
+        .map(|i| format!("error {}\n  at a {}\n  at b {}\n", i, i, i))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// N `---` separated records like "doc: 1\nkey: 1".
+fn generate_documents(n: usize) -> String {
+    (1..=n)
+        .map(|i| format!("doc: {}\nkey: {}\n", i, i))
+        .collect::<Vec<_>>()
+        .join("---\n")
+}
+
+#[test]
+fn paragraphs_kept_whole() {
+    trunc()
+        .args(["--paragraph", "-f", "1", "-l", "1"])
+        .write_stdin(generate_paragraphs(10))
+        .assert()
+        .success()
+        .stdout(
+            "error 1\n  at a 1\n  at b 1\n\n[... 8 records truncated ...]\nerror 10\n  at a 10\n  at b 10\n",
+        );
+}
+
+#[test]
+fn separator_kept_whole() {
+    trunc()
+        .args(["--separator", "---", "-f", "2", "-l", "1"])
+        .write_stdin(generate_documents(10))
+        .assert()
+        .success()
+        .stdout("doc: 1\nkey: 1\n---\ndoc: 2\nkey: 2\n---\n[... 7 records truncated ...]\ndoc: 10\nkey: 10\n");
+}
+
+#[test]
+fn untruncated_input_unchanged() {
+    for (flag, input) in [
+        ("--paragraph", generate_paragraphs(5)),
+        ("--separator=---", generate_documents(5)),
+    ] {
+        trunc()
+            .arg(flag)
+            .write_stdin(input.clone())
+            .assert()
+            .success()
+            .stdout(input);
+    }
+}
+
+#[test]
+fn blank_runs_end_one_paragraph() {
+    trunc()
+        .args(["--paragraph", "-f", "1", "-l", "1"])
+        .write_stdin("\na\n\n \n\nb\nc\n\nd\n")
+        .assert()
+        .success()
+        .stdout("\na\n\n \n\n[... 1 records truncated ...]\nd\n");
+}
+
+#[test]
+fn pattern_shows_whole_record() {
+    trunc()
+        .args(["--paragraph", "-f", "0", "-l", "0", "-C", "0", "at b 5$"])
+        .write_stdin(generate_paragraphs(10))
+        .assert()
+        .success()
+        .stdout(
+            "[... 4 records truncated, match 1 shown ...]\nerror 5\n  at a 5\n  at b 5\n\n[... 5 records truncated ...]\n",
+        );
+}
+
+#[test]
+fn context_counts_records() {
+    trunc()
+        .args(["--separator", "---", "-f", "0", "-l", "0", "-C", "1", "doc: 5"])
+        .write_stdin(generate_documents(10))
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "[... 3 records truncated, match 1 shown ...]\ndoc: 4\nkey: 4\n---\ndoc: 5\nkey: 5\n---\ndoc: 6\nkey: 6\n---\n[... 4 records truncated ...]\n",
+        ));
+}
+
+#[test]
+fn compact_markers() {
+    trunc()
+        .args(["--paragraph", "-f", "1", "-l", "0", "--markers", "compact"])
+        .write_stdin(generate_paragraphs(3))
+        .assert()
+        .success()
+        .stdout(predicate::str::ends_with("\n\n\u{2026}2R\u{2026}\n"));
+}
+
+#[test]
+fn file_and_percentages() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("input.log");
+    std::fs::write(&path, generate_paragraphs(10)).unwrap();
+    trunc()
+        .args(["--paragraph", "-f", "20%", "-l", "10%"])
+        .arg("--files")
+        .arg(&path)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "  at b 2\n\n[... 7 records truncated ...]\nerror 10\n",
+        ));
+}
+
+#[test]
+fn separator_and_paragraph_conflict() {
+    trunc()
+        .args(["--separator", "---", "--paragraph"])
+        .write_stdin("a\n")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("cannot be used with"));
+}
+
+#[test]
+fn crlf_records_byte_ranges() {
+    let input = generate_paragraphs(4).replace('\n', "\r\n");
+    trunc()
+        .args(["--paragraph", "-f", "1", "-l", "1", "--byte-ranges"])
+        .write_stdin(input)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "[... 2 records truncated (bytes 31-93) ...]\n",
+        ));
+}
@@ -0,0 +1,110 @@
+//! Tests for `--region-ids`: every truncation marker is numbered, and a
+//! footer gives the input lines and bytes each one hid.
+
+mod common;
+
+use common::{generate_lines, trunc};
+use predicates::prelude::*;
+
+#[test]
+fn markers_numbered_with_footer() {
+    trunc()
+        .args(["-f", "1", "-l", "1", "--region-ids"])
+        .write_stdin("a\nb\nc\n")
+        .assert()
+        .success()
+        .stdout("a\n[#1: 1 lines truncated ...]\nc\n[#1: line 2, bytes 2-4]\n");
+}
+
+#[test]
+fn every_gap_in_pattern_mode() {
+    trunc()
+        .args(["-f", "2", "-l", "2", "-C", "0", "--region-ids", "line 50$"])
+        .write_stdin(generate_lines(100))
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "[#1: 47 lines truncated, match 1 shown ...]\nline 50\n[#2: 48 lines truncated ...]\n",
+        ))
+        .stdout(predicate::str::ends_with(
+            "line 100\n[#1: lines 3-49, bytes 14-383]\n[#2: lines 51-98, bytes 391-775]\n",
+        ));
+}
+
+#[test]
+fn off_by_default() {
+    trunc()
+        .args(["-f", "1", "-l", "1"])
+        .write_stdin("a\nb\nc\n")
+        .assert()
+        .success()
+        .stdout("a\n[... 1 lines truncated ...]\nc\n");
+}
+
+#[test]
+fn no_footer_without_truncation() {
+    trunc()
+        .arg("--region-ids")
+        .write_stdin("a\nb\n")
+        .assert()
+        .success()
+        .stdout("a\nb\n");
+}
+
+#[test]
+fn ids_continue_across_inputs() {
+    let dir = tempfile::tempdir().unwrap();
+    let a = dir.path().join("a.log");
+    let b = dir.path().join("b.log");
+    std::fs::write(&a, "a1\na2\na3\n").unwrap();
+    std::fs::write(&b, "b1\nb2\nb3\n").unwrap();
+    trunc()
+        .args(["-f", "1", "-l", "1", "--region-ids"])
+        .arg("--files")
+        .arg(&a)
+        .arg(&b)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("[#2: 1 lines truncated ...]"))
+        .stdout(predicate::str::contains(format!(
+            "[#1: line 2 of {}, bytes 3-6]\n[#2: line 2 of {}, bytes 12-15]\n",
+            a.display(),
+            b.display()
+        )));
+}
+
+#[test]
+fn footer_before_trailer() {
+    trunc()
+        .args([
+            "-f",
+            "1",
+            "-l",
+            "1",
+            "--region-ids",
+            "--trailer",
+            "checksum",
+        ])
+        .write_stdin("a\nb\nc\n")
+        .assert()
+        .success()
+        .stdout(predicate::str::is_match(r"\[#1: line 2, bytes 2-4\]\n\[trunc sha256:").unwrap());
+}
+
+#[test]
+fn ids_match_save_regions() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("full.log");
+    trunc()
+        .args(["-f", "1", "-l", "1", "-C", "0", "--region-ids", "--save"])
+        .arg(&path)
+        .arg("line 5$")
+        .write_stdin(generate_lines(10))
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "[#2: 4 lines truncated (full output: ",
+        ))
+        .stdout(predicate::str::contains("bytes 35-63, region 2) ...]"))
+        .stdout(predicate::str::contains("[#2: lines 6-9, bytes 35-63]"));
+}
@@ -0,0 +1,113 @@
+//! Tests for `--strip-timestamps` and `--normalize-timestamps`: the
+//! timestamp each line shown starts with is removed, or rewritten relative
+//! to the input's first.
+
+mod common;
+
+use common::{run, trunc};
+
+/// A log of `n` lines a second apart, from 12:00:00.
+fn generate_log(n: usize) -> String {
+    (0..n)
+        .map(|i| {
+            format!(
+                "2024-05-01T12:{:02}:{:02}Z line {}\n",
+                i / 60,
+                i % 60,
+                i + 1
+            )
+        })
+        .collect()
+}
+
+#[test]
+fn strip_removes_timestamp_and_space() {
+    let stdout = run(
+        &["-f", "2", "-l", "2", "--strip-timestamps"],
+        generate_log(100),
+    );
+    assert_eq!(
+        stdout,
+        "line 1\nline 2\n[... 96 lines truncated ...]\nline 99\nline 100\n"
+    );
+}
+
+#[test]
+fn strip_takes_brackets_and_keys() {
+    let input = "[2024-05-01 12:00:01,500] WARN disk\n\
+                 ts=\"2024-05-01T12:00:02Z\" level=info msg=ok\n\
+                 01/May/2024:12:00:03 +0000 GET /\n\
+                 no timestamp here\n";
+    let stdout = run(&["--strip-timestamps"], input);
+    assert_eq!(
+        stdout,
+        "WARN disk\nlevel=info msg=ok\nGET /\nno timestamp here\n"
+    );
+}
+
+#[test]
+fn relative_counts_from_first_line() {
+    let stdout = run(
+        &["-f", "2", "-l", "2", "--normalize-timestamps", "relative"],
+        generate_log(100),
+    );
+    assert_eq!(
+        stdout,
+        "+0.000s line 1\n+1.000s line 2\n[... 96 lines truncated ...]\n+98.000s line 99\n+99.000s line 100\n"
+    );
+}
+
+#[test]
+fn relative_keeps_brackets() {
+    let input = "[2024-05-01 12:00:00] start\n[2024-05-01 12:00:01.250] next\n";
+    let stdout = run(&["--normalize-timestamps", "relative"], input);
+    assert_eq!(stdout, "[+0.000s] start\n[+1.250s] next\n");
+}
+
+#[test]
+fn relative_earlier_time_is_negative() {
+    let input = "2024-05-01T12:00:01Z a\n2024-05-01T12:00:00.500Z b\n";
+    let stdout = run(&["--normalize-timestamps", "relative"], input);
+    assert_eq!(stdout, "+0.000s a\n-0.500s b\n");
+}
+
+#[test]
+fn lines_are_matched_as_read() {
+    // --since and the pattern still see the original timestamps
+    let stdout = run(
+        &[
+            "--strip-timestamps",
+            "--since",
+            "2024-05-01T12:00:50Z",
+            "-f",
+            "0",
+            "-l",
+            "0",
+            "-m",
+            "1",
+            "12:00:55",
+        ],
+        generate_log(60),
+    );
+    assert!(stdout.contains("\nline 56\n"), "{}", stdout);
+    assert!(!stdout.contains("12:00:55"), "{}", stdout);
+}
+
+#[test]
+fn saves_bytes_under_budget() {
+    let input = generate_log(1000);
+    let plain = run(&["--max-bytes", "400"], &input);
+    let stripped = run(&["--max-bytes", "400", "--strip-timestamps"], &input);
+    let shown = |out: &str| out.lines().filter(|l| l.contains("line ")).count();
+    assert!(stripped.len() <= 400, "{}", stripped);
+    assert!(shown(&stripped) > shown(&plain), "{}\n{}", plain, stripped);
+}
+
+#[test]
+fn strip_and_normalize_conflict() {
+    trunc()
+        .args(["--strip-timestamps", "--normalize-timestamps", "relative"])
+        .write_stdin("x\n")
+        .assert()
+        .failure();
+}
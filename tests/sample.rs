@@ -0,0 +1,89 @@
+//! Tests for `--sample K`: K random lines from the middle, numbered, in
+//! input order between the head and tail.
+
+mod common;
+
+use common::{generate_lines, run, trunc};
+use predicates::prelude::*;
+
+/// The input line numbers of the sampled lines in `output`.
+fn sampled(output: &str) -> Vec<usize> {
+    output
+        .lines()
+        .filter_map(|line| line.split_once('\u{2502}'))
+        .map(|(number, _)| number.trim().parse().unwrap())
+        .collect()
+}
+
+#[test]
+fn k_lines_from_middle_in_order() {
+    let output = run(
+        &["--sample", "5", "--seed", "7", "-f", "2", "-l", "2"],
+        generate_lines(10000),
+    );
+    let lines = sampled(&output);
+    assert_eq!(lines.len(), 5);
+    assert!(lines.windows(2).all(|w| w[0] < w[1]));
+    assert!(lines.iter().all(|n| (3..=9998).contains(n)));
+    assert!(output.starts_with("line 1\nline 2\n[... "));
+    assert!(output.ends_with(" lines truncated ...]\nline 9999\nline 10000\n"));
+    // A marker before each sampled line and one before the tail
+    assert_eq!(output.matches("lines truncated").count(), 6);
+    for n in lines {
+        assert!(output.contains(&format!("{:>6}\u{2502} line {}\n", n, n)));
+    }
+}
+
+#[test]
+fn same_seed_same_lines() {
+    let args = ["--sample", "5", "--seed", "42", "-f", "0", "-l", "0"];
+    let first = run(&args, generate_lines(5000));
+    let second = run(&args, generate_lines(5000));
+    assert_eq!(first, second);
+    let other = run(
+        &["--sample", "5", "--seed", "43", "-f", "0", "-l", "0"],
+        generate_lines(5000),
+    );
+    assert_ne!(sampled(&first), sampled(&other));
+}
+
+#[test]
+fn small_middle_shown_whole() {
+    trunc()
+        .args(["--sample", "10", "-f", "2", "-l", "2"])
+        .write_stdin(generate_lines(7))
+        .assert()
+        .success()
+        .stdout(
+            "line 1\nline 2\n     3\u{2502} line 3\n     4\u{2502} line 4\n\
+             \x20    5\u{2502} line 5\nline 6\nline 7\n",
+        );
+}
+
+#[test]
+fn patterns_take_precedence() {
+    trunc()
+        .args(["--sample", "5", "-f", "0", "-l", "0", "line 50$"])
+        .write_stdin(generate_lines(100))
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("\u{2502}").not());
+}
+
+#[test]
+fn seed_needs_sample() {
+    trunc()
+        .args(["--seed", "1"])
+        .write_stdin("")
+        .assert()
+        .failure();
+}
+
+#[test]
+fn conflicts_with_summarize() {
+    trunc()
+        .args(["--sample", "5", "--summarize"])
+        .write_stdin("")
+        .assert()
+        .failure();
+}
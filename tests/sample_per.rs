@@ -0,0 +1,115 @@
+//! Tests for `--sample-per`: the middle of a timestamped log is sampled to
+//! one line per bucket of time, with a marker summing up the stretch.
+
+mod common;
+
+use common::trunc;
+use predicates::prelude::*;
+
+/// Ten lines a second from 12:00 UTC on 2024-05-01, `n` of them.
+fn build_log(n: usize) -> String {
+    (0..n)
+        .map(|i| {
+            format!(
+                "2024-05-01T12:{:02}:{:02}.{}00Z event {}\n",
+                i / 600,
+                i / 10 % 60,
+                i % 10,
+                i
+            )
+        })
+        .collect()
+}
+
+#[test]
+fn one_line_per_bucket() {
+    trunc()
+        .args(["--sample-per", "1m", "-f", "1", "-l", "1"])
+        .write_stdin(build_log(3000))
+        .assert()
+        .success()
+        .stdout(
+            "2024-05-01T12:00:00.000Z event 0\n\
+             2024-05-01T12:00:00.100Z event 1\n\
+             2024-05-01T12:01:00.000Z event 600\n\
+             2024-05-01T12:02:00.000Z event 1200\n\
+             2024-05-01T12:03:00.000Z event 1800\n\
+             2024-05-01T12:04:00.000Z event 2400\n\
+             [... 2998 lines across 00:03:59 sampled to 5 ...]\n\
+             2024-05-01T12:04:59.900Z event 2999\n",
+        );
+}
+
+#[test]
+fn smaller_buckets_keep_more() {
+    trunc()
+        .args(["--sample-per", "1s", "-f", "0", "-l", "0"])
+        .write_stdin(build_log(3000))
+        .assert()
+        .success()
+        .stdout(predicate::str::ends_with(
+            "[... 3000 lines across 00:04:59 sampled to 300 ...]\n",
+        ));
+}
+
+#[test]
+fn untimestamped_lines_never_sampled() {
+    let input: String = (1..=100).map(|i| format!("line {}\n", i)).collect();
+    trunc()
+        .args(["--sample-per", "1s", "-f", "1", "-l", "1"])
+        .write_stdin(input)
+        .assert()
+        .success()
+        .stdout("line 1\n[... 98 lines truncated ...]\nline 100\n");
+}
+
+#[test]
+fn compact_marker() {
+    trunc()
+        .args([
+            "--sample-per",
+            "1m",
+            "-f",
+            "0",
+            "-l",
+            "0",
+            "--markers",
+            "compact",
+        ])
+        .write_stdin(build_log(3000))
+        .assert()
+        .success()
+        .stdout(predicate::str::ends_with("…3000L sampled 5…\n"));
+}
+
+#[test]
+fn patterns_take_precedence() {
+    trunc()
+        .args([
+            "--sample-per",
+            "1m",
+            "-f",
+            "0",
+            "-l",
+            "0",
+            "-C",
+            "0",
+            "event 1500$",
+        ])
+        .write_stdin(build_log(3000))
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("event 600\n").not())
+        .stdout(predicate::str::contains(
+            "match 1 shown ...]\n2024-05-01T12:02:30.000Z event 1500\n",
+        ));
+}
+
+#[test]
+fn conflicts_with_region_ids() {
+    trunc()
+        .args(["--sample-per", "1m", "--region-ids"])
+        .write_stdin(build_log(10))
+        .assert()
+        .failure();
+}
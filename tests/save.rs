@@ -0,0 +1,159 @@
+//! Tests for `--save` / `--save-auto`: the complete input is written to a
+//! file, and each truncation marker names the byte range of it that it hides.
+
+mod common;
+
+use common::{generate_lines, run, trunc};
+use predicates::prelude::*;
+use regex::Regex;
+use std::path::PathBuf;
+
+/// The file and byte range named by each marker in `stdout`.
+fn spilled(stdout: &str) -> Vec<(PathBuf, usize, usize)> {
+    let re = Regex::new(r"\(full output: (.+) bytes (\d+)-(\d+), region \d+\) \.\.\.\]").unwrap();
+    re.captures_iter(stdout)
+        .map(|c| (c[1].into(), c[2].parse().unwrap(), c[3].parse().unwrap()))
+        .collect()
+}
+
+#[test]
+fn file_holds_complete_input() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("full.log");
+    let input = generate_lines(100);
+    run(
+        &["-f", "2", "-l", "2", "--save", path.to_str().unwrap()],
+        &input,
+    );
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), input);
+}
+
+#[test]
+fn marker_names_hidden_bytes() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("full.log");
+    let stdout = run(
+        &["-f", "2", "-l", "2", "--save", path.to_str().unwrap()],
+        generate_lines(100),
+    );
+    let markers = spilled(&stdout);
+    assert_eq!(markers.len(), 1, "Got: {}", stdout);
+    let (file, start, end) = &markers[0];
+    assert_eq!(file, &path);
+    let saved = std::fs::read(&path).unwrap();
+    let hidden = String::from_utf8(saved[*start..*end].to_vec()).unwrap();
+    assert!(hidden.starts_with("line 3\n"), "Got: {}", hidden);
+    assert!(hidden.ends_with("line 98\n"), "Got: {}", hidden);
+    assert_eq!(hidden.lines().count(), 96);
+}
+
+#[test]
+fn marker_format() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("full.log");
+    trunc()
+        .args(["-f", "1", "-l", "1", "--save", path.to_str().unwrap()])
+        .write_stdin("a\nb\nc\n")
+        .assert()
+        .success()
+        .stdout(format!(
+            "a\n[... 1 lines truncated (full output: {} bytes 2-4, region 1) ...]\nc\n",
+            path.display()
+        ));
+}
+
+#[test]
+fn every_gap_in_pattern_mode() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("full.log");
+    let stdout = run(
+        &[
+            "-f",
+            "2",
+            "-l",
+            "2",
+            "-C",
+            "0",
+            "--save",
+            path.to_str().unwrap(),
+            "line 50$",
+        ],
+        generate_lines(100),
+    );
+    let saved = std::fs::read_to_string(&path).unwrap();
+    let hidden: Vec<&str> = spilled(&stdout)
+        .into_iter()
+        .map(|(_, start, end)| &saved[start..end])
+        .collect();
+    assert_eq!(hidden.len(), 2, "Got: {}", stdout);
+    assert!(hidden[0].starts_with("line 3\n") && hidden[0].ends_with("line 49\n"));
+    assert!(hidden[1].starts_with("line 51\n") && hidden[1].ends_with("line 98\n"));
+}
+
+#[test]
+fn no_markers_when_nothing_truncated() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("full.log");
+    trunc()
+        .args(["--save", path.to_str().unwrap()])
+        .write_stdin("a\nb\n")
+        .assert()
+        .success()
+        .stdout("a\nb\n");
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), "a\nb\n");
+}
+
+#[test]
+fn save_auto_uses_temp_file() {
+    let stdout = run(&["-f", "1", "-l", "1", "--save-auto"], generate_lines(10));
+    let markers = spilled(&stdout);
+    assert_eq!(markers.len(), 1, "Got: {}", stdout);
+    let (file, start, end) = &markers[0];
+    assert!(
+        file.starts_with(std::env::temp_dir()),
+        "Got: {}",
+        file.display()
+    );
+    let saved = std::fs::read_to_string(file).unwrap();
+    assert_eq!(
+        &saved[*start..*end],
+        "line 2\nline 3\nline 4\nline 5\nline 6\nline 7\nline 8\nline 9\n"
+    );
+    std::fs::remove_file(file).unwrap();
+    std::fs::remove_file(format!("{}.idx", file.display())).unwrap();
+}
+
+#[test]
+fn several_inputs_saved_in_order() {
+    let dir = tempfile::tempdir().unwrap();
+    let a = dir.path().join("a.log");
+    let b = dir.path().join("b.log");
+    let path = dir.path().join("full.log");
+    std::fs::write(&a, "a1\na2\na3\n").unwrap();
+    std::fs::write(&b, "b1\nb2\nb3\n").unwrap();
+    let output = trunc()
+        .args(["-f", "1", "-l", "1", "--save", path.to_str().unwrap()])
+        .arg("--files")
+        .arg(&a)
+        .arg(&b)
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let saved = std::fs::read_to_string(&path).unwrap();
+    assert_eq!(saved, "a1\na2\na3\nb1\nb2\nb3\n");
+    let hidden: Vec<&str> = spilled(&stdout)
+        .into_iter()
+        .map(|(_, start, end)| &saved[start..end])
+        .collect();
+    assert_eq!(hidden, ["a2\n", "b2\n"]);
+}
+
+#[test]
+fn save_and_save_auto_conflict() {
+    trunc()
+        .args(["--save", "x.log", "--save-auto"])
+        .write_stdin("a\n")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("cannot be used with"));
+}
@@ -0,0 +1,146 @@
+//! Tests for `--width-head`, `--width-match` and `--width-tail`: each sets
+//! the width for its own section's lines, the rest keeping `-w`.
+
+mod common;
+
+use common::run;
+
+/// 100 lines of 200 chars each, the first char giving the line's number
+/// mod 10, with "ERROR" at the start of line 50.
+fn generate_input() -> String {
+    (1..=100)
+        .map(|i| {
+            let digit = char::from(b'0' + (i % 10) as u8);
+            match i {
+                50 => format!("ERROR{}\n", digit.to_string().repeat(195)),
+                _ => format!("{}\n", digit.to_string().repeat(200)),
+            }
+        })
+        .collect()
+}
+
+/// The lengths of the lines in `stdout` that aren't line markers.
+fn lengths(stdout: &str) -> Vec<usize> {
+    stdout
+        .lines()
+        .filter(|l| !l.starts_with("[... ") || l.contains(" chars ...]"))
+        .map(str::len)
+        .collect()
+}
+
+#[test]
+fn each_section_gets_its_width() {
+    let stdout = run(
+        &[
+            "-f",
+            "1",
+            "-l",
+            "1",
+            "-m",
+            "1",
+            "-C",
+            "0",
+            "--width-head",
+            "10",
+            "--width-match",
+            "80",
+            "--width-tail",
+            "20",
+            "ERROR",
+        ],
+        generate_input(),
+    );
+    // Kept chars plus "[... N chars ...]"
+    assert_eq!(
+        lengths(&stdout),
+        vec![20 + 19, 160 + 18, 40 + 19],
+        "{}",
+        stdout
+    );
+}
+
+#[test]
+fn others_keep_width() {
+    let stdout = run(
+        &[
+            "-f",
+            "1",
+            "-l",
+            "1",
+            "-m",
+            "1",
+            "-C",
+            "0",
+            "-w",
+            "30",
+            "--width-match",
+            "0",
+            "ERROR",
+        ],
+        generate_input(),
+    );
+    assert_eq!(lengths(&stdout), vec![60 + 19, 200, 60 + 19], "{}", stdout);
+}
+
+#[test]
+fn match_width_covers_context() {
+    let stdout = run(
+        &[
+            "-f",
+            "0",
+            "-l",
+            "0",
+            "-m",
+            "1",
+            "-C",
+            "1",
+            "-w",
+            "10",
+            "--width-match",
+            "40",
+            "ERROR",
+        ],
+        generate_input(),
+    );
+    assert_eq!(lengths(&stdout), vec![80 + 19; 3], "{}", stdout);
+}
+
+#[test]
+fn without_pattern_head_and_tail() {
+    let stdout = run(
+        &[
+            "-f",
+            "2",
+            "-l",
+            "2",
+            "--width-head",
+            "5",
+            "--width-tail",
+            "0",
+        ],
+        generate_input(),
+    );
+    assert_eq!(
+        lengths(&stdout),
+        vec![10 + 19, 10 + 19, 200, 200],
+        "{}",
+        stdout
+    );
+}
+
+#[test]
+fn max_bytes_counts_section_width() {
+    let input = generate_input();
+    let narrow = run(&["--max-bytes", "2000", "-w", "10"], &input);
+    let wide_tail = run(
+        &["--max-bytes", "2000", "-w", "10", "--width-tail", "0"],
+        &input,
+    );
+    assert!(wide_tail.len() <= 2000, "{}", wide_tail);
+    assert!(
+        wide_tail.lines().count() < narrow.lines().count(),
+        "{}\n{}",
+        narrow,
+        wide_tail
+    );
+}
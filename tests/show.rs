@@ -0,0 +1,112 @@
+//! Tests for `trunc show`: printing what a marker hid, from a file saved
+//! with `--save`.
+
+mod common;
+
+use common::{generate_lines, trunc};
+use predicates::prelude::*;
+use std::path::Path;
+
+/// Truncate 100 lines around a match on line 50, saving them to `path`:
+/// region 1 is lines 3-49, region 2 lines 51-98.
+fn save(path: &Path) {
+    trunc()
+        .args(["-f", "2", "-l", "2", "-C", "0", "--save"])
+        .arg(path)
+        .arg("line 50$")
+        .write_stdin(generate_lines(100))
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("region 2)"));
+}
+
+fn lines(range: std::ops::RangeInclusive<usize>) -> String {
+    range.map(|i| format!("line {}\n", i)).collect()
+}
+
+#[test]
+fn region_prints_hidden_lines() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("full.log");
+    save(&path);
+    trunc()
+        .arg("show")
+        .arg(&path)
+        .args(["--region", "1"])
+        .assert()
+        .success()
+        .stdout(lines(3..=49));
+    trunc()
+        .arg("show")
+        .arg(&path)
+        .args(["--region", "2"])
+        .assert()
+        .success()
+        .stdout(lines(51..=98));
+}
+
+#[test]
+fn bytes_prints_range() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("full.log");
+    save(&path);
+    // "line 1\n" is 7 bytes
+    trunc()
+        .arg("show")
+        .arg(&path)
+        .args(["--bytes", "7-21"])
+        .assert()
+        .success()
+        .stdout("line 2\nline 3\n");
+}
+
+#[test]
+fn index_written_alongside() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("full.log");
+    save(&path);
+    let index = std::fs::read_to_string(dir.path().join("full.log.idx")).unwrap();
+    assert_eq!(index.lines().count(), 2, "Got: {}", index);
+}
+
+#[test]
+fn missing_region_fails() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("full.log");
+    save(&path);
+    trunc()
+        .arg("show")
+        .arg(&path)
+        .args(["--region", "3"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("no region 3 (2 recorded)"));
+}
+
+#[test]
+fn missing_index_fails() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("other.log");
+    std::fs::write(&path, "a\n").unwrap();
+    trunc()
+        .arg("show")
+        .arg(&path)
+        .args(["--region", "1"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("no index"));
+}
+
+#[test]
+fn region_or_bytes_required() {
+    trunc()
+        .args(["show", "full.log"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("--region"));
+    trunc()
+        .args(["show", "full.log", "--bytes", "9-3"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("range ends before it starts"));
+}
@@ -0,0 +1,159 @@
+//! Tests for the fast path for regular files: without a pattern, the middle
+//! of a file is skipped over rather than read line by line, with the same
+//! output as streaming it.
+
+mod common;
+
+use common::{generate_lines, trunc};
+use std::path::Path;
+
+fn run(args: &[&str], file: Option<&Path>, stdin: &[u8]) -> String {
+    let mut cmd = trunc();
+    cmd.args(args);
+    match file {
+        Some(path) => cmd.arg("--files").arg(path),
+        None => cmd.write_stdin(stdin.to_vec()),
+    };
+    let output = cmd.output().unwrap();
+    assert!(output.status.success(), "{:?}", output);
+    String::from_utf8(output.stdout).unwrap()
+}
+
+/// Truncating `content` as a file gives the same as piping it in.
+fn same_as_stdin(args: &[&str], content: &[u8]) {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("input.log");
+    std::fs::write(&path, content).unwrap();
+    assert_eq!(run(args, Some(&path), b""), run(args, None, content));
+}
+
+#[test]
+fn large_file() {
+    same_as_stdin(&["-f", "3", "-l", "3"], generate_lines(100_000).as_bytes());
+}
+
+#[test]
+fn tail_across_read_chunks() {
+    // Lines longer than the 64 KiB chunks the tail is searched in
+    let long = |c: &str| c.repeat(100_000);
+    let content = [long("a"), long("b"), long("c"), long("d"), long("e")].join("\n");
+    same_as_stdin(&["-f", "1", "-l", "2"], content.as_bytes());
+}
+
+#[test]
+fn without_final_newline() {
+    same_as_stdin(&["-f", "1", "-l", "2"], b"a\nb\nc\nd\ne");
+}
+
+#[test]
+fn with_final_newline() {
+    same_as_stdin(&["-f", "1", "-l", "2"], b"a\nb\nc\nd\ne\n");
+}
+
+#[test]
+fn crlf_line_endings() {
+    same_as_stdin(&["-f", "1", "-l", "1"], b"a\r\nb\r\nc\r\nd\r\n");
+}
+
+#[test]
+fn crlf_bytes_counted_alike() {
+    let content: String = (1..=100).map(|i| format!("{}\r\n", i)).collect();
+    let args = ["-f", "2", "-l", "2", "--trailer", "checksum"];
+    same_as_stdin(&args, content.as_bytes());
+    // Lines 3 to 98, each with its \r\n
+    let out = run(&args, None, content.as_bytes());
+    assert!(out.contains(" hidden-bytes:377]"), "Got: {}", out);
+}
+
+#[test]
+fn blank_lines_at_end() {
+    same_as_stdin(&["-f", "1", "-l", "2"], b"a\nb\nc\n\n\n");
+}
+
+#[test]
+fn head_and_tail_overlap() {
+    same_as_stdin(&["-f", "3", "-l", "3"], b"a\nb\nc\nd\ne\n");
+}
+
+#[test]
+fn no_head_or_no_tail() {
+    let content = generate_lines(1000);
+    same_as_stdin(&["-f", "0", "-l", "5"], content.as_bytes());
+    same_as_stdin(&["-f", "5", "-l", "0"], content.as_bytes());
+    same_as_stdin(&["-f", "0", "-l", "0"], content.as_bytes());
+}
+
+#[test]
+fn empty_file() {
+    same_as_stdin(&[], b"");
+}
+
+#[test]
+fn line_numbers_and_trailer() {
+    same_as_stdin(
+        &["-f", "2", "-l", "2", "-n", "--trailer", "checksum"],
+        generate_lines(5000).as_bytes(),
+    );
+}
+
+#[test]
+fn under_byte_limit() {
+    same_as_stdin(&["--max-bytes", "200"], generate_lines(5000).as_bytes());
+}
+
+#[test]
+fn middle_not_decoded() {
+    // Streaming stops at invalid UTF-8; a skipped middle is never decoded
+    let mut content = b"a\nb\n".to_vec();
+    content.extend_from_slice(b"\xff\xfe\n");
+    content.extend_from_slice(b"c\nd\n");
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("input.log");
+    std::fs::write(&path, &content).unwrap();
+    trunc()
+        .args(["-f", "1", "-l", "1", "--files"])
+        .arg(&path)
+        .assert()
+        .success()
+        .stdout("a\n[... 3 lines truncated ...]\nd\n");
+}
+
+#[test]
+fn pattern_reads_every_line() {
+    let mut content = b"a\n\xff\nc\n".to_vec();
+    content.extend_from_slice(b"d\n");
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("input.log");
+    std::fs::write(&path, &content).unwrap();
+    trunc()
+        .args(["-f", "1", "-l", "1", "x"])
+        .arg(&path)
+        .assert()
+        .failure();
+}
+
+#[test]
+fn markers_in_middle_counted() {
+    let mut content = "a\nb\n[... 40 lines truncated ...]\nc\nd\ne\n".to_string();
+    content.push_str(&(1..=10).map(|i| format!("x{}\n", i)).collect::<String>());
+    same_as_stdin(&["-f", "2", "-l", "2"], content.as_bytes());
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("input.log");
+    std::fs::write(&path, &content).unwrap();
+    trunc()
+        .args(["-f", "2", "-l", "2", "--files"])
+        .arg(&path)
+        .assert()
+        .success()
+        .stdout("a\nb\n[... 51 lines truncated ...]\nx9\nx10\n");
+}
+
+#[test]
+fn markers_in_middle_without_tail() {
+    let content = "a\n\u{2026}7L\u{2026}\n[not a marker]\nb\n\u{2026}3L\u{2026}";
+    same_as_stdin(&["-f", "1", "-l", "0"], content.as_bytes());
+    same_as_stdin(
+        &["-f", "1", "-l", "0", "--no-idempotent"],
+        content.as_bytes(),
+    );
+}
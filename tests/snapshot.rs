@@ -0,0 +1,142 @@
+//! Tests for `--snapshot`: in follow mode, each refresh shows the whole
+//! truncated view of the input so far, each under a `==> snapshot N <==`
+//! line when stdout isn't a terminal.
+
+mod common;
+
+use common::trunc;
+use predicates::prelude::*;
+use std::io::Write;
+use std::process::Stdio;
+use std::time::Duration;
+
+/// Generate lines `from` to `to` like "line 1\n", "line 2\n", etc.
+fn generate_lines(from: usize, to: usize) -> String {
+    (from..=to).map(|i| format!("line {}\n", i)).collect()
+}
+
+#[test]
+fn stdin_shown_whole_at_eof() {
+    trunc()
+        .args(["-F", "--snapshot", "-f", "2", "-l", "2"])
+        .write_stdin(generate_lines(1, 30))
+        .assert()
+        .success()
+        .stdout(
+            "==> snapshot 1 <==\nline 1\nline 2\n[... 26 lines truncated ...]\nline 29\nline 30\n",
+        );
+}
+
+#[test]
+fn growing_file_shown_again() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("app.log");
+    std::fs::write(&path, generate_lines(1, 10)).unwrap();
+    let mut child = std::process::Command::new(assert_cmd::cargo::cargo_bin!("trunc"))
+        .args([
+            "-F",
+            "--snapshot",
+            "--refresh",
+            "200ms",
+            "-f",
+            "2",
+            "-l",
+            "2",
+            "--files",
+        ])
+        .arg(&path)
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    std::thread::sleep(Duration::from_millis(700));
+    let mut file = std::fs::OpenOptions::new()
+        .append(true)
+        .open(&path)
+        .unwrap();
+    file.write_all(generate_lines(11, 20).as_bytes()).unwrap();
+    std::thread::sleep(Duration::from_millis(700));
+    let _ = child.kill();
+    let output = child.wait_with_output().unwrap();
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(
+        stdout.starts_with(
+            "==> snapshot 1 <==\nline 1\nline 2\n[... 6 lines truncated ...]\nline 9\nline 10\n"
+        ),
+        "{}",
+        stdout
+    );
+    assert!(
+        stdout.ends_with(
+            "==> snapshot 2 <==\nline 1\nline 2\n[... 16 lines truncated ...]\nline 19\nline 20\n"
+        ),
+        "{}",
+        stdout
+    );
+}
+
+#[test]
+fn slow_stdin_shown_at_each_refresh() {
+    let mut child = std::process::Command::new(assert_cmd::cargo::cargo_bin!("trunc"))
+        .args([
+            "-F",
+            "--snapshot",
+            "--refresh",
+            "200ms",
+            "-f",
+            "1",
+            "-l",
+            "1",
+        ])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    let mut stdin = child.stdin.take().unwrap();
+    stdin.write_all(generate_lines(1, 5).as_bytes()).unwrap();
+    stdin.flush().unwrap();
+    std::thread::sleep(Duration::from_millis(700));
+    stdin.write_all(generate_lines(6, 9).as_bytes()).unwrap();
+    drop(stdin);
+    let output = child.wait_with_output().unwrap();
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(
+        stdout.starts_with("==> snapshot 1 <==\nline 1\n[... 3 lines truncated ...]\nline 5\n"),
+        "{}",
+        stdout
+    );
+    assert!(
+        stdout.ends_with("<==\nline 1\n[... 7 lines truncated ...]\nline 9\n"),
+        "{}",
+        stdout
+    );
+}
+
+#[test]
+fn last_snapshot_gives_status() {
+    trunc()
+        .args(["-F", "--snapshot", "--status", "-f", "2", "-l", "2"])
+        .write_stdin(generate_lines(1, 30))
+        .assert()
+        .code(3);
+}
+
+#[test]
+fn needs_follow() {
+    trunc()
+        .arg("--snapshot")
+        .write_stdin("line\n")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("--follow"));
+}
+
+#[test]
+fn not_with_save() {
+    trunc()
+        .args(["-F", "--snapshot", "--save", "full.log"])
+        .write_stdin("line\n")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("cannot be used with"));
+}
@@ -0,0 +1,192 @@
+//! Tests for `--spread`: a sample of matches across the whole input (first,
+//! last, evenly spaced between) rather than the first `-m`.
+//!
+//! Like `--rank-by`, it needs the whole input before the first match is
+//! shown: files are read twice, stdin and wrapped commands are buffered.
+
+mod common;
+
+use common::trunc;
+use predicates::prelude::*;
+use std::io::Write;
+use tempfile::NamedTempFile;
+
+/// N lines, with "line X ERROR" on every line in `match_at`.
+fn generate_lines_with_matches(n: usize, match_at: &[usize]) -> String {
+    (1..=n)
+        .map(|i| {
+            if match_at.contains(&i) {
+                format!("line {} ERROR", i)
+            } else {
+                format!("line {}", i)
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Matches every 10 lines from 10 to 190, in 200 lines: 19 of them.
+fn every_tenth() -> String {
+    let at: Vec<usize> = (1..20).map(|i| i * 10).collect();
+    generate_lines_with_matches(200, &at)
+}
+
+/// The matching lines in `trunc`'s output.
+fn shown(stdout: &[u8]) -> Vec<String> {
+    String::from_utf8_lossy(stdout)
+        .lines()
+        .filter(|l| l.ends_with("ERROR"))
+        .map(String::from)
+        .collect()
+}
+
+#[test]
+fn first_last_and_evenly_spaced() {
+    let assert = trunc()
+        .args([
+            "-f", "1", "-l", "1", "-C", "0", "-m", "4", "--spread", "ERROR",
+        ])
+        .write_stdin(every_tenth())
+        .assert()
+        .success();
+
+    assert_eq!(
+        shown(&assert.get_output().stdout),
+        [
+            "line 10 ERROR",
+            "line 70 ERROR",
+            "line 130 ERROR",
+            "line 190 ERROR"
+        ]
+    );
+}
+
+#[test]
+fn markers_number_shown_matches() {
+    trunc()
+        .args([
+            "-f", "1", "-l", "1", "-C", "0", "-m", "2", "--spread", "ERROR",
+        ])
+        .write_stdin(every_tenth())
+        .assert()
+        .success()
+        .stdout(
+            "line 1\n\
+             [... 8 lines truncated, match 1 shown ...]\n\
+             line 10 ERROR\n\
+             [... 179 lines truncated, match 2/2 shown ...]\n\
+             line 190 ERROR\n\
+             [... 9 lines and 17 matches truncated (19 total) ...]\n\
+             line 200\n",
+        );
+}
+
+#[test]
+fn single_match_is_the_first() {
+    let assert = trunc()
+        .args([
+            "-f", "1", "-l", "1", "-C", "0", "-m", "1", "--spread", "ERROR",
+        ])
+        .write_stdin(every_tenth())
+        .assert()
+        .success();
+
+    assert_eq!(shown(&assert.get_output().stdout), ["line 10 ERROR"]);
+}
+
+#[test]
+fn all_shown_when_under_limit() {
+    let input = generate_lines_with_matches(200, &[50, 100, 150]);
+
+    let with = trunc()
+        .args(["-f", "1", "-l", "1", "--spread", "ERROR"])
+        .write_stdin(input.clone())
+        .assert()
+        .success();
+    let without = trunc()
+        .args(["-f", "1", "-l", "1", "ERROR"])
+        .write_stdin(input)
+        .assert()
+        .success();
+
+    assert_eq!(with.get_output().stdout, without.get_output().stdout);
+}
+
+#[test]
+fn spread_over_file() {
+    let mut file = NamedTempFile::new().unwrap();
+    write!(file, "{}", every_tenth()).unwrap();
+
+    let assert = trunc()
+        .args([
+            "-f", "1", "-l", "1", "-C", "0", "-m", "3", "--spread", "-e", "ERROR",
+        ])
+        .arg(file.path())
+        .assert()
+        .success();
+
+    assert_eq!(
+        shown(&assert.get_output().stdout),
+        ["line 10 ERROR", "line 100 ERROR", "line 190 ERROR"]
+    );
+}
+
+#[test]
+fn tail_matches_not_sampled() {
+    // Matches from 110 on are in the tail; the sample spans 10..=100
+    let assert = trunc()
+        .args([
+            "-f", "1", "-l", "91", "-C", "0", "-m", "2", "--spread", "ERROR",
+        ])
+        .write_stdin(every_tenth())
+        .assert()
+        .success();
+
+    let stdout = String::from_utf8_lossy(&assert.get_output().stdout).into_owned();
+    assert!(
+        stdout.contains("match 2/2 shown ...]\nline 100 ERROR\n"),
+        "Got: {}",
+        stdout
+    );
+}
+
+#[test]
+fn wrapped_command_is_buffered() {
+    let assert = trunc()
+        .args(["-f", "1", "-l", "1", "-C", "0", "-m", "2", "--spread"])
+        .args(["-e", "0$", "--", "seq", "100"])
+        .assert()
+        .success();
+
+    let stdout = String::from_utf8_lossy(&assert.get_output().stdout).into_owned();
+    assert!(
+        stdout.contains("match 1 shown ...]\n10\n"),
+        "Got: {}",
+        stdout
+    );
+    assert!(
+        stdout.contains("match 2/2 shown ...]\n90\n"),
+        "Got: {}",
+        stdout
+    );
+}
+
+#[test]
+fn not_with_rank_by() {
+    trunc()
+        .args(["--spread", "--rank-by", "severity", "ERROR"])
+        .write_stdin("x\n")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("--rank-by"));
+}
+
+#[test]
+fn not_with_follow() {
+    trunc()
+        .args(["-F", "--spread", "ERROR"])
+        .write_stdin("x\n")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("--follow"));
+}
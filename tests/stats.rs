@@ -0,0 +1,82 @@
+//! Tests for `--stats`: a summary block on stderr of what was read and what
+//! was emitted.
+
+mod common;
+
+use common::{generate_lines, trunc};
+use predicates::prelude::*;
+
+#[test]
+fn block_on_stderr() {
+    trunc()
+        .args(["-f", "2", "-l", "2", "--stats"])
+        .write_stdin(generate_lines(100))
+        .assert()
+        .success()
+        .stdout("line 1\nline 2\n[... 96 lines truncated ...]\nline 99\nline 100\n")
+        .stderr(predicate::str::starts_with(
+            "[trunc stats]\nlines read:    100\nbytes read:    791\nlines emitted: 4\nbytes emitted: 60\nlongest line:  8 chars\nelapsed:       ",
+        ));
+}
+
+#[test]
+fn matches_with_pattern() {
+    trunc()
+        .args(["-f", "0", "-l", "0", "-m", "2", "--stats", "5$"])
+        .write_stdin(generate_lines(100))
+        .assert()
+        .success()
+        .stderr(predicate::str::contains(
+            "matches:       10 found, 2 shown\n",
+        ));
+}
+
+#[test]
+fn no_matches_line_without_pattern() {
+    trunc()
+        .arg("--stats")
+        .write_stdin("a\n")
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("matches:").not());
+}
+
+#[test]
+fn longest_line_in_chars() {
+    trunc()
+        .arg("--stats")
+        .write_stdin("short\nnaïve café\n")
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("longest line:  10 chars\n"));
+}
+
+#[test]
+fn counts_trailer_in_bytes_emitted() {
+    // 4 bytes of input and a 110-byte trailer
+    trunc()
+        .args(["--trailer", "checksum", "--stats"])
+        .write_stdin("a\nb\n")
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("bytes emitted: 114\n"));
+}
+
+#[test]
+fn nothing_without_flag() {
+    trunc()
+        .write_stdin(generate_lines(100))
+        .assert()
+        .success()
+        .stderr("");
+}
+
+#[test]
+fn missing_final_newline_not_counted_as_read() {
+    trunc()
+        .arg("--stats")
+        .write_stdin("a\nb")
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("bytes read:    3\n"));
+}
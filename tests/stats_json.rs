@@ -0,0 +1,154 @@
+//! Tests for `--stats-json`: the `--stats` figures and every marker's region
+//! as one JSON object, on stderr or another file descriptor.
+
+mod common;
+
+use common::{generate_lines, trunc};
+use predicates::prelude::*;
+use regex::Regex;
+
+/// stderr with the elapsed time (which varies) zeroed.
+fn stats(args: &[&str], input: &str) -> String {
+    let output = trunc().args(args).write_stdin(input).output().unwrap();
+    assert!(output.status.success());
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    Regex::new(r#""elapsed_ms":[0-9.]+"#)
+        .unwrap()
+        .replace(&stderr, r#""elapsed_ms":0"#)
+        .into_owned()
+}
+
+#[test]
+fn object_on_stderr() {
+    assert_eq!(
+        stats(
+            &["-f", "2", "-l", "2", "--stats-json"],
+            &generate_lines(100)
+        ),
+        concat!(
+            r#"{"lines_read":100,"bytes_read":791,"lines_emitted":4,"bytes_emitted":60,"#,
+            r#""matches_found":null,"matches_shown":null,"longest_line":8,"elapsed_ms":0,"#,
+            r#""regions":[{"id":1,"input":null,"lines":96,"first_line":3,"last_line":98,"#,
+            r#""byte_start":14,"byte_end":775}]}"#,
+            "\n"
+        )
+    );
+}
+
+#[test]
+fn markers_unchanged() {
+    trunc()
+        .args(["-f", "1", "-l", "1", "--stats-json"])
+        .write_stdin("a\nb\nc\n")
+        .assert()
+        .success()
+        .stdout("a\n[... 1 lines truncated ...]\nc\n");
+}
+
+#[test]
+fn region_per_marker_in_pattern_mode() {
+    let stderr = stats(
+        &["-f", "1", "-l", "1", "-C", "0", "--stats-json", "line 50$"],
+        &generate_lines(100),
+    );
+    assert!(
+        stderr.contains(r#""matches_found":1,"matches_shown":1"#),
+        "Got: {}",
+        stderr
+    );
+    assert!(
+        stderr.contains(concat!(
+            r#""regions":[{"id":1,"input":null,"lines":48,"first_line":2,"last_line":49,"#,
+            r#""byte_start":7,"byte_end":383},{"id":2,"input":null,"lines":49,"#,
+            r#""first_line":51,"last_line":99,"byte_start":391,"byte_end":783}]"#
+        )),
+        "Got: {}",
+        stderr
+    );
+}
+
+#[test]
+fn inputs_named() {
+    let dir = tempfile::tempdir().unwrap();
+    let a = dir.path().join("a\"1.log");
+    std::fs::write(&a, "a1\na2\na3\n").unwrap();
+    let output = trunc()
+        .args(["-f", "1", "-l", "1", "--stats-json", "--files"])
+        .arg(&a)
+        .arg(&a)
+        .output()
+        .unwrap();
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    let name = a.display().to_string().replace('"', "\\\"");
+    assert!(
+        stderr.contains(&format!(r#""input":"{}","lines":1"#, name)),
+        "Got: {}",
+        stderr
+    );
+    assert_eq!(stderr.matches(r#""id":"#).count(), 2, "Got: {}", stderr);
+}
+
+#[test]
+fn to_fd_3() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("stats.json");
+    let output = std::process::Command::new("sh")
+        .arg("-c")
+        .arg(r#"seq 10 | "$0" -f 1 -l 1 --stats-json=3 3>"$1""#)
+        .arg(assert_cmd::cargo::cargo_bin!("trunc"))
+        .arg(&path)
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert!(output.stderr.is_empty());
+    let json = std::fs::read_to_string(&path).unwrap();
+    assert!(json.starts_with(r#"{"lines_read":10,"#), "Got: {}", json);
+}
+
+#[test]
+fn fd_after_a_space() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("stats.json");
+    let output = std::process::Command::new("sh")
+        .arg("-c")
+        .arg(r#"seq 10 | "$0" -f 1 -l 1 --stats-json 3 3>"$1""#)
+        .arg(assert_cmd::cargo::cargo_bin!("trunc"))
+        .arg(&path)
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert!(output.stderr.is_empty());
+    let json = std::fs::read_to_string(&path).unwrap();
+    assert!(json.contains(r#""matches_found":null,"#), "Got: {}", json);
+}
+
+#[test]
+fn appends_to_fd_opened_for_appending() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("stats.json");
+    std::fs::write(&path, "earlier run\n").unwrap();
+    let output = std::process::Command::new("sh")
+        .arg("-c")
+        .arg(r#"seq 10 | "$0" -f 1 -l 1 --stats-json 3 3>>"$1""#)
+        .arg(assert_cmd::cargo::cargo_bin!("trunc"))
+        .arg(&path)
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let json = std::fs::read_to_string(&path).unwrap();
+    assert!(
+        json.starts_with("earlier run\n{\"lines_read\":10,"),
+        "Got: {}",
+        json
+    );
+}
+
+#[test]
+fn unopened_fd_rejected() {
+    trunc()
+        .arg("--stats-json=9")
+        .write_stdin("a\n")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("--stats-json: fd 9"));
+}
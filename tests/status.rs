@@ -0,0 +1,83 @@
+//! Tests for `--status`: exit 3 when anything was truncated.
+//!
+//! Lets scripts and agents tell whether they saw everything, and re-run
+//! with wider limits if not.
+
+mod common;
+
+use common::{generate_lines, trunc};
+
+#[test]
+fn unchanged_output_exits_0() {
+    trunc()
+        .arg("--status")
+        .write_stdin(generate_lines(60))
+        .assert()
+        .code(0);
+}
+
+#[test]
+fn hidden_lines_exit_3() {
+    trunc()
+        .arg("--status")
+        .write_stdin(generate_lines(61))
+        .assert()
+        .code(3);
+}
+
+#[test]
+fn truncated_long_line_exits_3() {
+    trunc()
+        .args(["--status", "-w", "10"])
+        .write_stdin("x".repeat(100))
+        .assert()
+        .code(3);
+}
+
+#[test]
+fn byte_limit_exits_3() {
+    trunc()
+        .args(["--status", "--max-bytes", "200"])
+        .write_stdin(generate_lines(60))
+        .assert()
+        .code(3);
+}
+
+#[test]
+fn without_flag_truncation_exits_0() {
+    trunc().write_stdin(generate_lines(100)).assert().code(0);
+}
+
+#[test]
+fn failed_gate_wins_over_truncation() {
+    trunc()
+        .args(["--status", "--forbid", "line 50"])
+        .write_stdin(generate_lines(100))
+        .assert()
+        .code(1);
+}
+
+#[test]
+fn wrapped_command_status_wins() {
+    trunc()
+        .args(["--status", "--", "sh", "-c", "seq 100; exit 5"])
+        .assert()
+        .code(5);
+}
+
+#[test]
+fn wrapped_command_success_reports_truncation() {
+    trunc()
+        .args(["--status", "--", "seq", "100"])
+        .assert()
+        .code(3);
+}
+
+#[test]
+fn separate_stderr_truncation_exits_3() {
+    trunc()
+        .args(["--status", "--stderr", "separate", "--"])
+        .args(["sh", "-c", "seq 100 >&2"])
+        .assert()
+        .code(3);
+}
@@ -0,0 +1,111 @@
+//! Tests for `--stderr merge|separate|pass` when wrapping a command.
+//!
+//! Build failures usually land on stderr, so trunc can fold a wrapped
+//! command's stderr into its output, truncate it on its own, or leave it be.
+
+mod common;
+
+use common::trunc;
+use predicates::prelude::*;
+
+/// Shell script printing `out1..outN` to stdout and `err1..errN` to stderr.
+fn both_streams(n: usize) -> String {
+    format!("for i in $(seq {}); do echo out$i; echo err$i >&2; done", n)
+}
+
+#[test]
+fn pass_is_the_default() {
+    trunc()
+        .args(["-f", "1", "-l", "1", "--", "sh", "-c", &both_streams(10)])
+        .assert()
+        .success()
+        .stdout("out1\n[... 8 lines truncated ...]\nout10\n")
+        .stderr((1..=10).map(|i| format!("err{}\n", i)).collect::<String>());
+}
+
+#[test]
+fn merge_tags_stderr_lines() {
+    trunc()
+        .args(["--stderr", "merge", "--", "sh", "-c", "echo oops >&2"])
+        .assert()
+        .success()
+        .stdout("[stderr] oops\n")
+        .stderr("");
+}
+
+#[test]
+fn merge_truncates_both_streams_together() {
+    let assert = trunc()
+        .args(["-f", "2", "-l", "2", "--stderr", "merge", "--"])
+        .args(["sh", "-c", &both_streams(50)])
+        .assert()
+        .success()
+        .stderr("");
+
+    let stdout = String::from_utf8_lossy(&assert.get_output().stdout).into_owned();
+    assert!(
+        stdout.contains("[... 96 lines truncated ...]"),
+        "Should count 100 lines from both streams, got: {}",
+        stdout
+    );
+}
+
+#[test]
+fn merge_patterns_match_stderr() {
+    let script = "seq 100; echo 'error: build failed' >&2; seq 100";
+
+    trunc()
+        .args([
+            "-f", "1", "-l", "1", "-C", "0", "--stderr", "merge", "error",
+        ])
+        .args(["--", "sh", "-c", script])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("[stderr] error: build failed\n"));
+}
+
+#[test]
+fn separate_truncates_each_stream_on_its_own() {
+    trunc()
+        .args(["-f", "2", "-l", "2", "--stderr", "separate", "--"])
+        .args(["sh", "-c", &both_streams(20)])
+        .assert()
+        .success()
+        .stdout("out1\nout2\n[... 16 lines truncated ...]\nout19\nout20\n")
+        .stderr("err1\nerr2\n[... 16 lines truncated ...]\nerr19\nerr20\n");
+}
+
+#[test]
+fn separate_gates_see_stderr() {
+    trunc()
+        .args([
+            "-f", "1", "-l", "1", "--stderr", "separate", "--forbid", "err5",
+        ])
+        .args(["--", "sh", "-c", &both_streams(10)])
+        .assert()
+        .code(1)
+        .stderr(predicate::str::contains("stderr line 5: err5"));
+}
+
+#[test]
+fn large_stderr_does_not_stall_command() {
+    // Far more than a pipe buffer on stderr before anything on stdout
+    let script = "seq 200000 >&2; echo done";
+
+    trunc()
+        .args(["--stderr", "separate", "--", "sh", "-c", script])
+        .timeout(std::time::Duration::from_secs(30))
+        .assert()
+        .success()
+        .stdout("done\n");
+}
+
+#[test]
+fn requires_a_command() {
+    trunc()
+        .args(["--stderr", "merge"])
+        .write_stdin("x\n")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("<CMD>"));
+}
@@ -0,0 +1,117 @@
+//! Tests for `--strip-ansi` / `--keep-ansi`: escape sequences in the input
+//! are stripped by default when stdout isn't a terminal (as in these tests).
+
+mod common;
+
+use common::trunc;
+use predicates::prelude::*;
+
+const RED: &str = "\x1b[31m";
+const RESET: &str = "\x1b[0m";
+
+/// Generate N lines with every tenth one colored red.
+fn colored_lines(n: usize) -> String {
+    (1..=n)
+        .map(|i| {
+            if i % 10 == 0 {
+                format!("{}line {} ERROR{}", RED, i, RESET)
+            } else {
+                format!("line {}", i)
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[test]
+fn stripped_when_piped() {
+    trunc()
+        .write_stdin(format!("{}error{}: boom\n", RED, RESET))
+        .assert()
+        .success()
+        .stdout("error: boom\n");
+}
+
+#[test]
+fn strip_ansi_flag() {
+    trunc()
+        .arg("--strip-ansi")
+        .write_stdin("\x1b]8;;https://example.com\x07link\x1b]8;;\x07 \x1b[1;32mok\x1b[0m\n")
+        .assert()
+        .success()
+        .stdout("link ok\n");
+}
+
+#[test]
+fn keep_ansi_flag() {
+    let line = format!("{}error{}: boom\n", RED, RESET);
+
+    trunc()
+        .arg("--keep-ansi")
+        .write_stdin(line.clone())
+        .assert()
+        .success()
+        .stdout(line);
+}
+
+#[test]
+fn flags_conflict() {
+    trunc()
+        .args(["--strip-ansi", "--keep-ansi"])
+        .write_stdin("x\n")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("cannot be used with"));
+}
+
+#[test]
+fn patterns_match_visible_text() {
+    // The escape splits "ERR" from "OR" in the raw bytes
+    let input = colored_lines(100).replace("line 55\n", "line 55 ERR\x1b[1mOR\x1b[0m\n");
+
+    trunc()
+        .args(["-f", "1", "-l", "1", "-C", "0", "-m", "1", "ERROR"])
+        .write_stdin(input)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "match 1/1 shown ...]\nline 10 ERROR\n",
+        ))
+        .stdout(predicate::str::contains("(11 total)"));
+}
+
+#[test]
+fn width_counts_visible_chars() {
+    let line = format!("{}{}{}", RED, "x".repeat(300), RESET);
+
+    trunc()
+        .args(["-w", "10"])
+        .write_stdin(line)
+        .assert()
+        .success()
+        .stdout(format!(
+            "{}[... 280 chars ...]{}\n",
+            "x".repeat(10),
+            "x".repeat(10)
+        ));
+}
+
+#[test]
+fn stripping_is_not_truncation() {
+    trunc()
+        .arg("--status")
+        .write_stdin(format!("{}error{}\n", RED, RESET))
+        .assert()
+        .code(0);
+}
+
+#[test]
+fn own_color_kept() {
+    trunc()
+        .args(["-f", "1", "-l", "1", "--color", "always", "ERROR"])
+        .write_stdin(colored_lines(100))
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("\x1b[2m[..."))
+        .stdout(predicate::str::contains(RED).not());
+}
@@ -0,0 +1,108 @@
+//! Tests for `--summarize`: the marker before the tail says what the lines
+//! it hides were.
+
+mod common;
+
+use common::trunc;
+use predicates::prelude::*;
+
+/// `n` log lines: every 10th a WARN, every 25th an ERROR, the rest health
+/// checks.
+fn build_log(n: usize) -> String {
+    (1..=n)
+        .map(|i| {
+            if i % 25 == 0 {
+                format!("2024-05-01T12:00:00Z ERROR db timeout after {}ms\n", i)
+            } else if i % 10 == 0 {
+                format!("2024-05-01T12:00:00Z WARN slow request id={}\n", i)
+            } else {
+                format!("2024-05-01T12:00:00Z INFO GET /health {}us\n", i)
+            }
+        })
+        .collect()
+}
+
+#[test]
+fn levels_and_top_lines() {
+    trunc()
+        .args(["--summarize", "-f", "0", "-l", "0"])
+        .write_stdin(build_log(1000))
+        .assert()
+        .success()
+        .stdout(
+            "[... 1000 lines truncated: 880 INFO, 80 WARN, 40 ERROR, 3 unique; \
+             top: \"INFO GET /health #us\" ×880, \"WARN slow request id=#\" ×80, \
+             \"ERROR db timeout after #ms\" ×40 ...]\n",
+        );
+}
+
+#[test]
+fn only_hidden_lines_counted() {
+    let input: String = (1..=10).map(|i| format!("INFO step {}\n", i)).collect();
+    trunc()
+        .args(["--summarize", "-f", "3", "-l", "3"])
+        .write_stdin(input)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "[... 4 lines truncated: 4 INFO, 1 unique; top: \"INFO step #\" ×4 ...]",
+        ));
+}
+
+#[test]
+fn each_marker_summarizes_its_gap() {
+    trunc()
+        .args(["--summarize", "-f", "0", "-l", "0", "--always", "ERROR"])
+        .write_stdin(build_log(110))
+        .assert()
+        .success()
+        .stdout(predicate::str::starts_with(
+            "[... 24 lines truncated: 22 INFO, 2 WARN, 2 unique; top: \
+             \"INFO GET /health #us\" ×22, \"WARN slow request id=#\" ×2 ...]\n\
+             2024-05-01T12:00:00Z ERROR db timeout after 25ms\n",
+        ))
+        .stdout(predicate::str::ends_with(
+            "[... 10 lines truncated: 9 INFO, 1 WARN, 2 unique; top: \
+             \"INFO GET /health #us\" ×9 ...]\n",
+        ));
+}
+
+#[test]
+fn logfmt_levels() {
+    let input: String = (1..=20)
+        .map(|i| match i % 4 {
+            0 => format!("level=warning msg=retry n={}\n", i),
+            _ => format!("level=debug msg=poll n={}\n", i),
+        })
+        .collect();
+    trunc()
+        .args(["--summarize", "-f", "0", "-l", "0"])
+        .write_stdin(input)
+        .assert()
+        .success()
+        .stdout(predicate::str::starts_with(
+            "[... 20 lines truncated: 15 DEBUG, 5 WARN, 2 unique;",
+        ));
+}
+
+#[test]
+fn compact_marker() {
+    trunc()
+        .args(["--summarize", "-f", "0", "-l", "0", "--markers", "compact"])
+        .write_stdin(build_log(10))
+        .assert()
+        .success()
+        .stdout(predicate::str::starts_with(
+            "…10L: 9 INFO, 1 WARN, 2 unique;",
+        ));
+}
+
+#[test]
+fn patterns_keep_their_markers() {
+    trunc()
+        .args(["--summarize", "-f", "0", "-l", "0", "-C", "0", "timeout"])
+        .write_stdin(build_log(100))
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("unique").not());
+}
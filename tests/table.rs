@@ -0,0 +1,81 @@
+//! Tests for `--table`: the header row is always shown, and overlong rows
+//! lose middle columns rather than chars, lined up with the header.
+
+mod common;
+
+use common::run;
+
+/// A table of `rows` rows and `cols` columns, cells joined by `delimiter`,
+/// under a `column_N` header.
+fn build_table(rows: usize, cols: usize, delimiter: &str) -> String {
+    let header: Vec<String> = (1..=cols).map(|c| format!("column_{}", c)).collect();
+    let mut table = header.join(delimiter) + "\n";
+    for r in 1..=rows {
+        let row: Vec<String> = (1..=cols).map(|c| format!("r{}c{}", r, c)).collect();
+        table += &(row.join(delimiter) + "\n");
+    }
+    table
+}
+
+#[test]
+fn header_kept_with_no_head() {
+    let stdout = run(&["--table", "-f", "0", "-l", "1"], build_table(50, 3, ","));
+    assert_eq!(
+        stdout,
+        "column_1,column_2,column_3\n[... 49 lines truncated ...]\nr50c1,r50c2,r50c3\n"
+    );
+}
+
+#[test]
+fn without_table_header_can_go() {
+    let stdout = run(&["-f", "0", "-l", "1"], build_table(50, 3, ","));
+    assert_eq!(stdout, "[... 50 lines truncated ...]\nr50c1,r50c2,r50c3\n");
+}
+
+#[test]
+fn wide_rows_drop_middle_columns() {
+    let stdout = run(
+        &["--table", "-w", "40", "-f", "2", "-l", "0"],
+        build_table(10, 30, ","),
+    );
+    assert_eq!(
+        stdout,
+        "column_1,column_2,column_3,column_4,…+22 cols…,column_27,column_28,column_29,column_30\n\
+         r1c1,r1c2,r1c3,r1c4,…+22 cols…,r1c27,r1c28,r1c29,r1c30\n\
+         [... 9 lines truncated ...]\n"
+    );
+}
+
+#[test]
+fn tsv_uses_tabs() {
+    let stdout = run(
+        &["--table", "-w", "40", "-f", "1", "-l", "0"],
+        build_table(3, 30, "\t"),
+    );
+    assert!(
+        stdout.starts_with("column_1\tcolumn_2\tcolumn_3\tcolumn_4\t…+22 cols…\tcolumn_27\t"),
+        "Got: {}",
+        stdout
+    );
+}
+
+#[test]
+fn quoted_delimiters_dont_split() {
+    let input = format!(
+        "name,notes,{},id\n\"Smith, J\",\"a, b, c\",{},7\n",
+        "x".repeat(200),
+        "y".repeat(200)
+    );
+    let stdout = run(&["--table", "-w", "40"], input);
+    assert_eq!(
+        stdout,
+        "name,notes,…+1 col…,id\n\"Smith, J\",\"a, b, c\",…+1 col…,7\n"
+    );
+}
+
+#[test]
+fn narrow_rows_untouched() {
+    let input = build_table(5, 3, ",");
+    let stdout = run(&["--table"], input.clone());
+    assert_eq!(stdout, input);
+}
@@ -0,0 +1,135 @@
+//! Tests for `--tail-matches K`: in pattern mode the tail is the last K
+//! matches with their context, rather than the last lines.
+
+mod common;
+
+use common::trunc;
+use predicates::prelude::*;
+
+/// Lines `1` to `n`, with `ERROR` on every tenth.
+fn build_input(n: usize) -> String {
+    (1..=n)
+        .map(|i| match i % 10 {
+            0 => format!("ERROR {}\n", i),
+            _ => format!("{}\n", i),
+        })
+        .collect()
+}
+
+#[test]
+fn first_and_last_two() {
+    trunc()
+        .args([
+            "-f",
+            "0",
+            "-m",
+            "1",
+            "-C",
+            "1",
+            "--tail-matches",
+            "2",
+            "ERROR",
+        ])
+        .write_stdin(build_input(95))
+        .assert()
+        .success()
+        .stdout(
+            "[... 8 lines truncated, match 1/1 shown ...]\n9\nERROR 10\n11\n\
+             [... 67 lines and 6 matches truncated (9 total) ...]\n\
+             79\nERROR 80\n81\n\
+             [... 7 lines truncated ...]\n\
+             89\nERROR 90\n91\n\
+             [... 4 lines truncated ...]\n",
+        );
+}
+
+#[test]
+fn last_match_at_end() {
+    trunc()
+        .args([
+            "-f",
+            "0",
+            "-m",
+            "0",
+            "-C",
+            "0",
+            "--tail-matches",
+            "1",
+            "ERROR",
+        ])
+        .write_stdin(build_input(100))
+        .assert()
+        .success()
+        .stdout("[... 99 lines and 9 matches truncated (10 total) ...]\nERROR 100\n");
+}
+
+#[test]
+fn overlapping_streamed_matches_shown_once() {
+    trunc()
+        .args([
+            "-f",
+            "0",
+            "-m",
+            "2",
+            "-C",
+            "0",
+            "--tail-matches",
+            "2",
+            "ERROR",
+        ])
+        .write_stdin(build_input(25))
+        .assert()
+        .success()
+        .stdout(
+            "[... 9 lines truncated, match 1 shown ...]\nERROR 10\n\
+             [... 9 lines truncated, match 2/2 shown ...]\nERROR 20\n\
+             [... 5 lines truncated ...]\n",
+        );
+}
+
+#[test]
+fn no_matches() {
+    trunc()
+        .args(["-f", "0", "--tail-matches", "2", "NOPE"])
+        .write_stdin(build_input(50))
+        .assert()
+        .success()
+        .stdout("[... 50 lines truncated, 0 matches found ...]\n");
+}
+
+#[test]
+fn without_pattern_tail_is_lines() {
+    trunc()
+        .args(["-f", "0", "--tail-matches", "2", "-l", "1"])
+        .write_stdin(build_input(50))
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("cannot be used with"));
+    trunc()
+        .args(["-f", "0", "--tail-matches", "2"])
+        .write_stdin(build_input(50))
+        .assert()
+        .success()
+        .stdout(predicate::str::ends_with("\n49\nERROR 50\n"));
+}
+
+#[test]
+fn not_with_count() {
+    trunc()
+        .args(["-c", "--tail-matches", "2", "ERROR"])
+        .write_stdin(build_input(50))
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("cannot be used with"));
+}
+
+#[test]
+fn default_tail_has_no_gap_markers() {
+    // Line 17 is shown as a match, so the tail skips it without a marker
+    trunc()
+        .args(["-f", "2", "-l", "5", "-C", "0", "^17$"])
+        .write_stdin((1..=20).map(|i| format!("{}\n", i)).collect::<String>())
+        .assert()
+        .success()
+        .stdout("1\n2\n[... 14 lines truncated, match 1 shown ...]\n17\n16\n18\n19\n20\n");
+}
@@ -0,0 +1,122 @@
+//! Tests for `--tee`: the complete input is written to a file as it's read,
+//! kept current while trunc runs, and markers name the bytes of it they hide.
+
+mod common;
+
+use common::{generate_terminated_lines, trunc};
+use predicates::prelude::*;
+use regex::Regex;
+use std::time::Duration;
+
+#[test]
+fn file_holds_complete_input() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("full.log");
+    trunc()
+        .args(["-f", "2", "-l", "2", "--tee", path.to_str().unwrap()])
+        .write_stdin(generate_terminated_lines(100))
+        .assert()
+        .success()
+        .stdout(predicate::str::starts_with("line 1\nline 2\n[... 96 lines"));
+    assert_eq!(
+        std::fs::read_to_string(&path).unwrap(),
+        generate_terminated_lines(100)
+    );
+}
+
+#[test]
+fn marker_names_hidden_bytes() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("full.log");
+    let output = trunc()
+        .args(["-f", "2", "-l", "2", "--tee", path.to_str().unwrap()])
+        .write_stdin(generate_terminated_lines(100))
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let re = Regex::new(r"bytes (\d+)-(\d+), region 1\)").unwrap();
+    let c = re.captures(&stdout).expect(&stdout);
+    let (start, end): (usize, usize) = (c[1].parse().unwrap(), c[2].parse().unwrap());
+    let saved = std::fs::read_to_string(&path).unwrap();
+    assert!(saved[start..end].starts_with("line 3\n"));
+    assert!(saved[start..end].ends_with("line 98\n"));
+}
+
+#[test]
+fn region_shown_from_file() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("full.log");
+    let path = path.to_str().unwrap();
+    trunc()
+        .args(["-f", "2", "-l", "2", "--tee", path])
+        .write_stdin(generate_terminated_lines(10))
+        .assert()
+        .success();
+    trunc()
+        .args(["show", path, "--region", "1"])
+        .assert()
+        .success()
+        .stdout("line 3\nline 4\nline 5\nline 6\nline 7\nline 8\n");
+}
+
+#[test]
+fn file_current_while_running() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("full.log");
+    let mut child = std::process::Command::new(assert_cmd::cargo::cargo_bin!("trunc"))
+        .arg("--tee")
+        .arg(&path)
+        .args(["--", "sh", "-c", "seq 1 5; sleep 3"])
+        .stdout(std::process::Stdio::null())
+        .spawn()
+        .unwrap();
+    std::thread::sleep(Duration::from_millis(1000));
+    let saved = std::fs::read_to_string(&path).unwrap_or_default();
+    let _ = child.kill();
+    let _ = child.wait();
+    assert_eq!(saved, "1\n2\n3\n4\n5\n");
+}
+
+#[test]
+fn not_with_save() {
+    trunc()
+        .args(["--tee", "a.log", "--save", "b.log"])
+        .write_stdin("line\n")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("cannot be used with"));
+}
+
+#[test]
+fn file_is_input_byte_for_byte() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("full.log");
+    let input = b"one\r\ntwo \xff\nthree\r\nfour\nfive".to_vec();
+    trunc()
+        .args(["-f", "1", "-l", "1", "--binary-safe", "--tee"])
+        .arg(&path)
+        .write_stdin(input.clone())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("(full output: "));
+    let saved = std::fs::read(&path).unwrap();
+    assert_eq!(saved, input);
+    trunc()
+        .args(["show", path.to_str().unwrap(), "--region", "1"])
+        .assert()
+        .success()
+        .stdout(predicate::eq(&b"two \xff\nthree\r\nfour\n"[..]));
+}
+
+#[test]
+fn file_kept_on_read_error() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("full.log");
+    trunc()
+        .args(["--tee"])
+        .arg(&path)
+        .write_stdin(b"one\ntwo\n\xff\nfour\n".to_vec())
+        .assert()
+        .code(1);
+    assert_eq!(std::fs::read(&path).unwrap(), b"one\ntwo\n\xff\n");
+}
@@ -0,0 +1,116 @@
+//! Tests for `--since` and `--until`: lines timestamped outside the window
+//! are dropped before truncation, and counted in a marker.
+
+mod common;
+
+use common::trunc;
+use predicates::prelude::*;
+
+/// One line a minute from 12:00 UTC on 2024-05-01, `n` of them.
+fn build_log(n: usize) -> String {
+    (0..n)
+        .map(|i| {
+            format!(
+                "2024-05-01T{:02}:{:02}:00Z event {}\n",
+                12 + i / 60,
+                i % 60,
+                i
+            )
+        })
+        .collect()
+}
+
+#[test]
+fn lines_outside_window_dropped() {
+    trunc()
+        .args([
+            "--since",
+            "2024-05-01T12:10:00Z",
+            "--until",
+            "2024-05-01T12:12:00Z",
+        ])
+        .write_stdin(build_log(100))
+        .assert()
+        .success()
+        .stdout(
+            "[... 10 lines outside time window ...]\n\
+             2024-05-01T12:10:00Z event 10\n\
+             2024-05-01T12:11:00Z event 11\n\
+             2024-05-01T12:12:00Z event 12\n\
+             [... 87 lines outside time window ...]\n",
+        );
+}
+
+#[test]
+fn budgets_apply_to_window() {
+    trunc()
+        .args(["--since", "2024-05-01T13:00:00Z", "-f", "1", "-l", "1"])
+        .write_stdin(build_log(100))
+        .assert()
+        .success()
+        .stdout(
+            "[... 60 lines outside time window ...]\n\
+             2024-05-01T13:00:00Z event 60\n\
+             [... 38 lines truncated ...]\n\
+             2024-05-01T13:39:00Z event 99\n",
+        );
+}
+
+#[test]
+fn untimestamped_lines_go_with_line_above() {
+    let input = "2024-05-01T11:00:00Z old error\n  at old frame\n\
+                 2024-05-01T12:00:00Z new error\n  at new frame\n";
+    trunc()
+        .args(["--since", "2024-05-01T12:00:00Z"])
+        .write_stdin(input)
+        .assert()
+        .success()
+        .stdout(
+            "[... 2 lines outside time window ...]\n2024-05-01T12:00:00Z new error\n  at new frame\n",
+        );
+}
+
+#[test]
+fn common_formats_read() {
+    // All 12:00 UTC on 2024-05-01, bar the last
+    let input = "2024-05-01 14:00:00,250 +02:00 iso with offset\n\
+                 [01/May/2024:12:00:00 +0000] common log\n\
+                 ts=2024-05-01T12:00:00Z level=info logfmt\n\
+                 1714564800 unix\n\
+                 2024/05/01 13:00:00 too late\n";
+    trunc()
+        .args([
+            "--since",
+            "2024-05-01T12:00:00Z",
+            "--until",
+            "2024-05-01T12:00:01Z",
+        ])
+        .write_stdin(input)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "unix\n[... 1 lines outside time window ...]\n",
+        ))
+        .stdout(predicate::str::starts_with("2024-05-01 14:00:00,250"));
+}
+
+#[test]
+fn relative_since() {
+    let input = "2020-01-01T00:00:00Z long ago\n2099-01-01T00:00:00Z far future\n";
+    trunc()
+        .args(["--since", "1d"])
+        .write_stdin(input)
+        .assert()
+        .success()
+        .stdout("[... 1 lines outside time window ...]\n2099-01-01T00:00:00Z far future\n");
+}
+
+#[test]
+fn invalid_time_rejected() {
+    trunc()
+        .args(["--since", "yesterday"])
+        .write_stdin("")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("invalid time"));
+}
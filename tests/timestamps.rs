@@ -0,0 +1,122 @@
+//! Tests for `--timestamps`: every line shown is prefixed with when trunc
+//! read it, as a UTC time or seconds since trunc started.
+
+mod common;
+
+use common::{generate_terminated_lines, run, trunc};
+use regex::Regex;
+
+/// The relative stamps on the lines of `stdout`, in seconds.
+fn offsets(stdout: &str) -> Vec<f64> {
+    stdout
+        .lines()
+        .filter_map(|line| line.strip_prefix('+')?.split_once("s ")?.0.parse().ok())
+        .collect()
+}
+
+#[test]
+fn wall_clock_by_default() {
+    let stdout = run(
+        &["-f", "2", "-l", "2", "--timestamps"],
+        generate_terminated_lines(100),
+    );
+    let stamp = r"\d{4}-\d\d-\d\dT\d\d:\d\d:\d\d\.\d{3}Z";
+    let re = Regex::new(&format!(
+        r"^{0} line 1\n{0} line 2\n\[\.\.\. 96 lines truncated \.\.\.\]\n{0} line 99\n{0} line 100\n$",
+        stamp
+    ))
+    .unwrap();
+    assert!(re.is_match(&stdout), "{}", stdout);
+}
+
+#[test]
+fn relative_offsets() {
+    let stdout = run(&["--timestamps=relative"], "a\nb\n");
+    let re = Regex::new(r"^\+\d+\.\d{3}s a\n\+\d+\.\d{3}s b\n$").unwrap();
+    assert!(re.is_match(&stdout), "{}", stdout);
+}
+
+#[test]
+fn value_after_a_space() {
+    let stdout = run(&["--timestamps", "relative"], "a\nb\n");
+    let re = Regex::new(r"^\+\d+\.\d{3}s a\n\+\d+\.\d{3}s b\n$").unwrap();
+    assert!(re.is_match(&stdout), "{}", stdout);
+}
+
+#[test]
+fn other_word_after_is_the_pattern() {
+    let input = "x\n".repeat(50) + "ERROR\n" + &"x\n".repeat(50);
+    let stdout = run(
+        &["-f", "1", "-l", "1", "-C", "0", "--timestamps", "ERROR"],
+        &input,
+    );
+    assert!(stdout.contains("Z ERROR\n"), "{}", stdout);
+}
+
+#[test]
+fn tail_keeps_time_it_was_read() {
+    let output = trunc()
+        .args(["-f", "1", "-l", "2", "--timestamps=relative", "--"])
+        .args(["sh", "-c", "seq 1 3; sleep 1.5; seq 4 6"])
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let offsets = offsets(&stdout);
+    assert_eq!(offsets.len(), 3, "{}", stdout);
+    assert!(offsets[0] < 1.0, "{}", stdout);
+    assert!(offsets[1] >= 1.4 && offsets[2] >= 1.4, "{}", stdout);
+}
+
+#[test]
+fn context_keeps_time_it_was_read() {
+    let output = trunc()
+        .args(["-f", "1", "-l", "1", "-B", "1", "-A", "0"])
+        .args(["--timestamps=relative", "ERROR", "--"])
+        .args(["sh", "-c", "echo a; echo b; sleep 1.5; echo ERROR; seq 1 5"])
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("s b\n"), "{}", stdout);
+    let offsets = offsets(&stdout);
+    assert_eq!(offsets.len(), 4, "{}", stdout);
+    assert!(offsets[1] < 1.0, "{}", stdout);
+    assert!(offsets[2] >= 1.4, "{}", stdout);
+}
+
+#[test]
+fn held_lines_keep_time_they_were_read() {
+    let output = trunc()
+        .args([
+            "-f",
+            "1",
+            "-l",
+            "1",
+            "--only-if-needed",
+            "--timestamps=relative",
+            "--",
+        ])
+        .args(["sh", "-c", "echo a; sleep 1.5; echo b"])
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let offsets = offsets(&stdout);
+    assert_eq!(offsets.len(), 2, "{}", stdout);
+    assert!(offsets[0] < 1.0 && offsets[1] >= 1.4, "{}", stdout);
+}
+
+#[test]
+fn goes_before_line_numbers() {
+    let stdout = run(&["-n", "--timestamps=relative"], "a\n");
+    let re = Regex::new(r"^\+\d+\.\d{3}s +1\u{2502} a\n$").unwrap();
+    assert!(re.is_match(&stdout), "{}", stdout);
+}
+
+#[test]
+fn counts_towards_max_bytes() {
+    let stdout = run(
+        &["--max-bytes", "300", "--timestamps"],
+        generate_terminated_lines(1000),
+    );
+    assert!(stdout.len() <= 300, "{}", stdout);
+    assert!(stdout.contains("Z line 1\n"), "{}", stdout);
+}
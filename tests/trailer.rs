@@ -0,0 +1,142 @@
+//! Tests for the `--trailer checksum` integrity line.
+//!
+//! Harnesses that forward trunc output need to tell "trunc hid this" apart
+//! from "something between trunc and me cut or edited this". The trailer is a
+//! final line carrying a digest of everything emitted before it, plus how many
+//! input lines and bytes trunc itself chose not to show.
+
+mod common;
+
+use common::{generate_lines, trunc};
+use sha2::{Digest, Sha256};
+
+/// Split stdout into (body, trailer line).
+fn split_trailer(stdout: &str) -> (&str, &str) {
+    let body_end = stdout
+        .trim_end_matches('\n')
+        .rfind('\n')
+        .map_or(0, |i| i + 1);
+    (&stdout[..body_end], stdout[body_end..].trim_end())
+}
+
+fn sha256_hex(data: &str) -> String {
+    Sha256::digest(data.as_bytes())
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+#[test]
+fn no_trailer_by_default() {
+    trunc()
+        .write_stdin("a\nb\n")
+        .assert()
+        .success()
+        .stdout("a\nb\n");
+}
+
+#[test]
+fn digest_covers_everything_before_trailer() {
+    let input = generate_lines(100);
+
+    let assert = trunc()
+        .args(["-f", "5", "-l", "5", "--trailer", "checksum"])
+        .write_stdin(input)
+        .assert()
+        .success();
+
+    let stdout = String::from_utf8_lossy(&assert.get_output().stdout).to_string();
+    let (body, trailer) = split_trailer(&stdout);
+
+    assert!(body.contains("[... 90 lines truncated ...]"));
+    assert!(
+        trailer.contains(&format!("sha256:{}", sha256_hex(body))),
+        "Trailer should carry the digest of the body. Got: {}",
+        trailer
+    );
+}
+
+#[test]
+fn reports_hidden_lines_and_bytes() {
+    // Lines 6..=95 are hidden: 90 lines. "line 6".."line 9" are 6 bytes,
+    // "line 10".."line 95" are 7 bytes, each plus a newline.
+    let input = generate_lines(100);
+    let hidden_bytes = 4 * 7 + 86 * 8;
+
+    let assert = trunc()
+        .args(["-f", "5", "-l", "5", "--trailer", "checksum"])
+        .write_stdin(input)
+        .assert()
+        .success();
+
+    let stdout = String::from_utf8_lossy(&assert.get_output().stdout).to_string();
+    let (_, trailer) = split_trailer(&stdout);
+
+    assert!(
+        trailer.ends_with(&format!("hidden-lines:90 hidden-bytes:{}]", hidden_bytes)),
+        "Got: {}",
+        trailer
+    );
+}
+
+#[test]
+fn width_truncation_counts_as_hidden_bytes() {
+    // 700-char line with default width keeps 200 chars, hides 500 bytes
+    let line = "x".repeat(700);
+
+    let assert = trunc()
+        .args(["--trailer", "checksum"])
+        .write_stdin(format!("{}\n", line))
+        .assert()
+        .success();
+
+    let stdout = String::from_utf8_lossy(&assert.get_output().stdout).to_string();
+    let (_, trailer) = split_trailer(&stdout);
+
+    assert!(
+        trailer.ends_with("hidden-lines:0 hidden-bytes:500]"),
+        "Got: {}",
+        trailer
+    );
+}
+
+#[test]
+fn untruncated_output_reports_nothing_hidden() {
+    let assert = trunc()
+        .args(["--trailer", "checksum"])
+        .write_stdin("a\nb\n")
+        .assert()
+        .success();
+
+    let stdout = String::from_utf8_lossy(&assert.get_output().stdout).to_string();
+    let (body, trailer) = split_trailer(&stdout);
+
+    assert_eq!(body, "a\nb\n");
+    assert!(trailer.ends_with("hidden-lines:0 hidden-bytes:0]"));
+}
+
+#[test]
+fn stdin_and_skimmed_file_agree_without_final_newline() {
+    let input = "a\nb\nc\nd\ne\nf";
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("input.log");
+    std::fs::write(&path, input).unwrap();
+    let args = ["-f", "1", "-l", "1", "--trailer=checksum"];
+
+    let piped = trunc().args(args).write_stdin(input).output().unwrap();
+    let skimmed = trunc()
+        .args(args)
+        .arg("--files")
+        .arg(&path)
+        .output()
+        .unwrap();
+
+    let piped = String::from_utf8(piped.stdout).unwrap();
+    let (_, trailer) = split_trailer(&piped);
+    assert!(
+        trailer.ends_with("hidden-lines:4 hidden-bytes:8]"),
+        "Got: {}",
+        trailer
+    );
+    assert_eq!(piped, String::from_utf8(skimmed.stdout).unwrap());
+}
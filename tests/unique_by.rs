@@ -0,0 +1,147 @@
+//! Tests for `--unique-by TEMPLATE`: one match per capture value, with the
+//! repeats passed over counted in markers.
+
+mod common;
+
+use common::trunc;
+use predicates::prelude::*;
+
+/// A test run where `flaky` fails four times between other failures.
+fn build_log() -> String {
+    [
+        "start",
+        "test flaky FAILED",
+        "ok",
+        "test flaky FAILED",
+        "ok",
+        "test real FAILED",
+        "ok",
+        "test flaky FAILED",
+        "ok",
+        "test other FAILED",
+        "ok",
+        "test flaky FAILED",
+        "end",
+    ]
+    .iter()
+    .map(|l| format!("{}\n", l))
+    .collect()
+}
+
+#[test]
+fn repeats_passed_over_and_counted() {
+    trunc()
+        .args([
+            "-f",
+            "0",
+            "-l",
+            "0",
+            "-C",
+            "0",
+            "--unique-by",
+            "$1",
+            r"test (\S+) FAILED",
+        ])
+        .write_stdin(build_log())
+        .assert()
+        .success()
+        .stdout(
+            "[... 1 lines truncated, match 1 shown ...]\ntest flaky FAILED\n\
+             [... 3 lines truncated, 1 duplicate match, match 2 shown ...]\n\
+             test real FAILED\n\
+             [... 3 lines truncated, 1 duplicate match, match 3 shown ...]\n\
+             test other FAILED\n\
+             [... 3 lines and 3 matches truncated (1 duplicate, 6 total) ...]\n",
+        );
+}
+
+#[test]
+fn repeats_do_not_use_up_m() {
+    trunc()
+        .args([
+            "-f",
+            "0",
+            "-l",
+            "0",
+            "-C",
+            "0",
+            "-m",
+            "3",
+            "--unique-by",
+            "$1",
+            r"test (\S+) FAILED",
+        ])
+        .write_stdin(build_log())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "match 3/3 shown ...]\ntest other FAILED\n",
+        ));
+}
+
+#[test]
+fn named_group() {
+    trunc()
+        .args([
+            "-f",
+            "0",
+            "-l",
+            "0",
+            "-C",
+            "0",
+            "--unique-by",
+            "${code}",
+            r"error\[(?P<code>E\d+)\]",
+        ])
+        .write_stdin("error[E0308] a\nerror[E0308] b\nerror[E0599] c\n")
+        .assert()
+        .success()
+        .stdout(
+            "[... 0 lines truncated, match 1 shown ...]\nerror[E0308] a\n\
+             [... 1 lines truncated, 1 duplicate match, match 2 shown ...]\n\
+             error[E0599] c\n\
+             [... 0 lines and 1 match truncated (3 total) ...]\n",
+        );
+}
+
+#[test]
+fn without_unique_by_every_match_counts() {
+    trunc()
+        .args([
+            "-f",
+            "0",
+            "-l",
+            "0",
+            "-C",
+            "0",
+            "-m",
+            "2",
+            r"test (\S+) FAILED",
+        ])
+        .write_stdin(build_log())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("duplicate").not())
+        .stdout(predicate::str::contains(
+            "match 2/2 shown ...]\ntest flaky FAILED\n",
+        ));
+}
+
+#[test]
+fn fixed_strings_key_on_whole_match() {
+    trunc()
+        .args(["-f", "0", "-l", "0", "-C", "0"])
+        .args([
+            "--fixed-string",
+            "--unique-by",
+            "$0",
+            "-e",
+            "flaky",
+            "-e",
+            "real",
+        ])
+        .write_stdin(build_log())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("(2 duplicates, 5 total)"));
+}
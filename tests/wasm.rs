@@ -0,0 +1,68 @@
+//! Tests for `trunc::wasm`, with the `wasm` feature: the JavaScript API,
+//! called natively here.
+//!
+//! `truncate` is meant to give exactly what the CLI prints for the same
+//! input and flags.
+#![cfg(feature = "wasm")]
+
+mod common;
+
+use common::{generate_lines_with_errors, run};
+use trunc::wasm::{truncate, Options};
+
+/// [`truncate`], which only fails on a bad pattern.
+fn truncated(text: &str, options: &Options) -> String {
+    truncate(text, options).unwrap_or_else(|_| panic!("truncate failed"))
+}
+
+#[test]
+fn defaults_match_the_cli() {
+    let input = generate_lines_with_errors(500, &[]);
+    assert_eq!(truncated(&input, &Options::new()), run(&[], &input));
+}
+
+#[test]
+fn pattern_mode_matches_the_cli() {
+    let input = generate_lines_with_errors(1000, &[200, 400, 600]);
+    let mut options = Options::new();
+    options.set_pattern("ERROR".to_string());
+    options.set_first(5);
+    options.set_last(5);
+    options.set_matches(2);
+    options.set_context(1);
+    assert_eq!(
+        truncated(&input, &options),
+        run(
+            &["-f", "5", "-l", "5", "-m", "2", "-C", "1", "ERROR"],
+            &input
+        )
+    );
+}
+
+#[test]
+fn width_and_max_bytes_match_the_cli() {
+    let input: String = (1..=300)
+        .map(|i| format!("line {} {}\n", i, "x".repeat(i % 150)))
+        .collect();
+    let mut options = Options::new();
+    options.set_width(40);
+    options.set_max_bytes(2000);
+    assert_eq!(
+        truncated(&input, &options),
+        run(&["-w", "40", "--max-bytes", "2000"], &input)
+    );
+}
+
+#[test]
+fn short_input_is_unchanged() {
+    assert_eq!(truncated("a\nb\nc", &Options::new()), "a\nb\nc\n");
+}
+
+#[test]
+fn options_describe_a_truncator() {
+    let mut options = Options::new();
+    options.set_pattern("(unclosed".to_string());
+    assert!(options.truncator().is_err());
+    options.set_pattern("ERROR".to_string());
+    assert!(options.truncator().is_ok());
+}
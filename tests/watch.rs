@@ -0,0 +1,107 @@
+//! Tests for `--watch`: a file is truncated again each time it changes.
+
+mod common;
+
+use common::{trunc, Guard};
+use predicates::prelude::*;
+use std::io::{BufRead, BufReader};
+use std::process::Stdio;
+use std::sync::mpsc::{self, Receiver};
+use std::time::Duration;
+use tempfile::TempDir;
+
+/// Lines `prefix 1` to `prefix n`.
+fn generate_lines(prefix: &str, n: usize) -> String {
+    (1..=n).map(|i| format!("{} {}\n", prefix, i)).collect()
+}
+
+/// Spawn `trunc --watch` on `path`, collecting stdout lines on a channel.
+fn spawn(path: &std::path::Path) -> (Guard, Receiver<String>) {
+    let mut child = std::process::Command::new(assert_cmd::cargo::cargo_bin!("trunc"))
+        .args(["--watch", "-f", "1", "-l", "1", "--files"])
+        .arg(path)
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    let stdout = child.stdout.take().unwrap();
+    let (tx, rx) = mpsc::channel();
+    std::thread::spawn(move || {
+        for line in BufReader::new(stdout).lines().map_while(Result::ok) {
+            let _ = tx.send(line);
+        }
+    });
+    (Guard(child), rx)
+}
+
+/// The next `n` lines, waiting a while for each.
+fn next_lines(rx: &Receiver<String>, n: usize) -> Vec<String> {
+    (0..n)
+        .map(|_| rx.recv_timeout(Duration::from_secs(5)).unwrap())
+        .collect()
+}
+
+#[test]
+fn truncated_again_on_change() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("build.log");
+    std::fs::write(&path, generate_lines("first", 10)).unwrap();
+    let (_child, rx) = spawn(&path);
+    assert_eq!(
+        next_lines(&rx, 3),
+        ["first 1", "[... 8 lines truncated ...]", "first 10"]
+    );
+
+    std::fs::write(&path, generate_lines("second", 20)).unwrap();
+    assert_eq!(
+        next_lines(&rx, 3),
+        ["second 1", "[... 18 lines truncated ...]", "second 20"]
+    );
+}
+
+#[test]
+fn file_renamed_over() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("build.log");
+    std::fs::write(&path, generate_lines("first", 10)).unwrap();
+    let (_child, rx) = spawn(&path);
+    next_lines(&rx, 3);
+
+    let new = dir.path().join("build.log.new");
+    std::fs::write(&new, generate_lines("second", 5)).unwrap();
+    std::fs::rename(&new, &path).unwrap();
+    assert_eq!(
+        next_lines(&rx, 3),
+        ["second 1", "[... 3 lines truncated ...]", "second 5"]
+    );
+}
+
+#[test]
+fn other_files_ignored() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("build.log");
+    std::fs::write(&path, generate_lines("first", 10)).unwrap();
+    let (_child, rx) = spawn(&path);
+    next_lines(&rx, 3);
+
+    std::fs::write(dir.path().join("other.log"), "other\n").unwrap();
+    assert!(rx.recv_timeout(Duration::from_millis(500)).is_err());
+}
+
+#[test]
+fn needs_a_file() {
+    trunc()
+        .arg("--watch")
+        .write_stdin("line\n")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("--watch takes a single file"));
+}
+
+#[test]
+fn not_with_follow() {
+    trunc()
+        .args(["--watch", "-F", "Cargo.toml"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("cannot be used with"));
+}
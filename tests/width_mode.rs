@@ -0,0 +1,100 @@
+//! Tests for grapheme-cluster and display-width aware line truncation.
+//!
+//! By default `-w` counts user-perceived chars (grapheme clusters), which are
+//! never split. `--width-mode columns` counts terminal columns instead, so
+//! wide chars take two.
+
+mod common;
+
+use common::run;
+
+#[test]
+fn combining_marks_not_split() {
+    // "e" + combining acute accent: one char, two codepoints
+    let line = "e\u{301}".repeat(300);
+
+    let stdout = run(&["-w", "5"], format!("{}\n", line));
+
+    let kept = "e\u{301}".repeat(5);
+    assert_eq!(stdout, format!("{}[... 290 chars ...]{}\n", kept, kept));
+}
+
+#[test]
+fn emoji_sequences_not_split() {
+    // Thumbs up with a skin tone modifier, and a ZWJ family
+    let line = "👍🏽👨‍👩‍👧".repeat(100);
+
+    let stdout = run(&["-w", "4"], format!("{}\n", line));
+
+    let kept = "👍🏽👨‍👩‍👧".repeat(2);
+    assert_eq!(stdout, format!("{}[... 192 chars ...]{}\n", kept, kept));
+}
+
+#[test]
+fn chars_mode_counts_wide_chars_once() {
+    let line = "日".repeat(300);
+
+    let stdout = run(&["-w", "10"], format!("{}\n", line));
+
+    let kept = "日".repeat(10);
+    assert_eq!(stdout, format!("{}[... 280 chars ...]{}\n", kept, kept));
+}
+
+#[test]
+fn columns_mode_counts_wide_chars_twice() {
+    let line = "日".repeat(300);
+
+    let stdout = run(
+        &["-w", "10", "--width-mode", "columns"],
+        format!("{}\n", line),
+    );
+
+    let kept = "日".repeat(5);
+    assert_eq!(stdout, format!("{}[... 580 cols ...]{}\n", kept, kept));
+}
+
+#[test]
+fn columns_mode_leaves_out_straddling_char() {
+    // "a" then wide chars: 5 columns ends in the middle of the third one
+    let line = format!("a{}b", "日".repeat(200));
+
+    let stdout = run(
+        &["-w", "5", "--width-mode", "columns"],
+        format!("{}\n", line),
+    );
+
+    assert_eq!(
+        stdout, "a日日[... 392 cols ...]日日b\n",
+        "Should keep 5 columns at each end without splitting a char"
+    );
+}
+
+#[test]
+fn columns_mode_short_wide_line_fits() {
+    // 150 chars fit in -w 100 as chars, but take 300 columns
+    let line = "日".repeat(150);
+
+    let chars = run(&["-w", "100"], format!("{}\n", line.clone()));
+    let columns = run(
+        &["-w", "100", "--width-mode", "columns"],
+        format!("{}\n", line),
+    );
+
+    assert_eq!(chars, format!("{}\n", line));
+    assert!(columns.contains("[... 100 cols ...]"), "Got: {:?}", columns);
+}
+
+#[test]
+fn columns_mode_ascii_same_as_chars() {
+    let line = "x".repeat(300);
+
+    let stdout = run(
+        &["-w", "10", "--width-mode", "columns"],
+        format!("{}\n", line),
+    );
+
+    assert_eq!(
+        stdout,
+        format!("{}[... 280 cols ...]{}\n", "x".repeat(10), "x".repeat(10))
+    );
+}
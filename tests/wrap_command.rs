@@ -0,0 +1,93 @@
+//! Tests for wrapping a command: `trunc -- cmd args`.
+//!
+//! trunc runs the command itself and truncates its stdout, so the command's
+//! exit status comes back as trunc's own instead of being lost in a pipe.
+
+mod common;
+
+use common::trunc;
+use predicates::prelude::*;
+
+#[test]
+fn truncates_command_stdout() {
+    trunc()
+        .args(["-f", "2", "-l", "2", "--", "seq", "100"])
+        .assert()
+        .success()
+        .stdout("1\n2\n[... 96 lines truncated ...]\n99\n100\n");
+}
+
+#[test]
+fn propagates_exit_code() {
+    trunc()
+        .args(["-f", "1", "-l", "1", "--", "sh", "-c", "seq 10; exit 7"])
+        .assert()
+        .code(7)
+        .stdout("1\n[... 8 lines truncated ...]\n10\n");
+}
+
+#[test]
+fn killed_by_signal_exits_128_plus_signal() {
+    trunc()
+        .args(["--", "sh", "-c", "kill -9 $$"])
+        .assert()
+        .code(137);
+}
+
+#[test]
+fn command_not_found_exits_127() {
+    trunc()
+        .args(["--", "trunc-test-no-such-command"])
+        .assert()
+        .code(127)
+        .stderr(predicate::str::contains("trunc-test-no-such-command"));
+}
+
+#[test]
+fn command_arguments_are_not_trunc_options() {
+    trunc()
+        .args(["--", "echo", "-f", "--width"])
+        .assert()
+        .success()
+        .stdout("-f --width\n");
+}
+
+#[test]
+fn operand_is_pattern_even_if_file_exists() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(dir.path().join("50"), "not an input").unwrap();
+
+    trunc()
+        .current_dir(dir.path())
+        .args(["-f", "1", "-l", "1", "-m", "1", "-C", "0", "50", "--", "seq", "100"])
+        .assert()
+        .success()
+        .stdout("1\n[... 48 lines truncated, match 1/1 shown ...]\n50\n[... 49 lines truncated ...]\n100\n");
+}
+
+#[test]
+fn stdin_goes_to_command() {
+    trunc()
+        .args(["--", "cat"])
+        .write_stdin("hello\n")
+        .assert()
+        .success()
+        .stdout("hello\n");
+}
+
+#[test]
+fn gate_failure_exits_1_when_command_succeeds() {
+    trunc()
+        .args(["--forbid", "^50$", "--", "seq", "100"])
+        .assert()
+        .code(1)
+        .stderr(predicate::str::contains("forbidden pattern found"));
+}
+
+#[test]
+fn command_status_wins_over_gate_failure() {
+    trunc()
+        .args(["--forbid", "^5$", "--", "sh", "-c", "seq 10; exit 3"])
+        .assert()
+        .code(3);
+}